@@ -0,0 +1,13 @@
+-- Versions that upstream has yanked, deprecated or unpublished
+
+CREATE TABLE upstream_yanks (
+    ecosystem TEXT NOT NULL,
+    name TEXT NOT NULL,
+    version TEXT NOT NULL,
+    platform TEXT NOT NULL DEFAULT '',
+    reason TEXT NOT NULL,
+    detected_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (ecosystem, name, version, platform)
+);
+
+CREATE INDEX idx_cached_assets_last_accessed ON cached_assets(last_accessed);
@@ -0,0 +1,25 @@
+-- Append-only audit trail of quarantine decisions
+
+CREATE TABLE quarantine_events (
+    id BIGSERIAL PRIMARY KEY,
+    name TEXT NOT NULL,
+    version TEXT NOT NULL,
+    platform TEXT,
+    action TEXT NOT NULL,
+    actor TEXT NOT NULL,
+    reason TEXT,
+    previous_status TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_quarantine_events_gem ON quarantine_events(name, version);
+
+CREATE FUNCTION quarantine_events_append_only() RETURNS trigger AS $$
+BEGIN
+    RAISE EXCEPTION 'quarantine_events is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER quarantine_events_no_modify
+BEFORE UPDATE OR DELETE ON quarantine_events
+FOR EACH ROW EXECUTE FUNCTION quarantine_events_append_only();
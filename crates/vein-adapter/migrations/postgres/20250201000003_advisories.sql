@@ -0,0 +1,25 @@
+-- Vulnerability advisories imported from offline OSV dumps
+
+CREATE TABLE advisories (
+    id TEXT PRIMARY KEY,
+    summary TEXT,
+    details TEXT,
+    severity TEXT NOT NULL DEFAULT 'unknown',
+    aliases_json TEXT NOT NULL DEFAULT '[]',
+    url TEXT,
+    published TIMESTAMPTZ,
+    modified TIMESTAMPTZ NOT NULL,
+    withdrawn TIMESTAMPTZ,
+    imported_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- One row per affected package; ranges are kept as JSON and matched in Rust
+CREATE TABLE advisory_packages (
+    advisory_id TEXT NOT NULL REFERENCES advisories(id) ON DELETE CASCADE,
+    ecosystem TEXT NOT NULL,
+    name TEXT NOT NULL,
+    affected_json TEXT NOT NULL,
+    PRIMARY KEY (advisory_id, ecosystem, name)
+);
+
+CREATE INDEX idx_advisory_packages_package ON advisory_packages(ecosystem, name);
@@ -0,0 +1,20 @@
+-- Manifest metadata for non-gem packages (crates, npm); gems keep gem_metadata
+
+CREATE TABLE package_metadata (
+    ecosystem TEXT NOT NULL,
+    name TEXT NOT NULL,
+    version TEXT NOT NULL,
+    description TEXT,
+    license TEXT,
+    authors_json TEXT NOT NULL DEFAULT '[]',
+    homepage TEXT,
+    repository TEXT,
+    dependencies_json TEXT NOT NULL DEFAULT '[]',
+    features_json TEXT NOT NULL DEFAULT '{}',
+    attributes_json TEXT NOT NULL DEFAULT '{}',
+    size_bytes BIGINT NOT NULL,
+    sha256 TEXT NOT NULL,
+    sbom_json TEXT,
+    analyzed_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (ecosystem, name, version)
+);
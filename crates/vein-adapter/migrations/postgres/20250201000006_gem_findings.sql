@@ -0,0 +1,19 @@
+-- Suspicious patterns found in the sources of cached gem versions
+
+CREATE TABLE gem_findings (
+    id BIGSERIAL PRIMARY KEY,
+    gem_name TEXT NOT NULL,
+    gem_version TEXT NOT NULL,
+    gem_platform TEXT NOT NULL DEFAULT 'ruby',
+    rule TEXT NOT NULL,
+    file_path TEXT NOT NULL,
+    line_number INTEGER,
+    excerpt TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT fk_gem_findings_metadata
+        FOREIGN KEY (gem_name, gem_version, gem_platform)
+        REFERENCES gem_metadata(name, version, platform)
+        ON DELETE CASCADE
+);
+
+CREATE INDEX idx_gem_findings_gem ON gem_findings(gem_name, gem_version);
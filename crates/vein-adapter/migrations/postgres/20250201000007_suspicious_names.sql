@@ -0,0 +1,18 @@
+-- Download counts for popularity, and package names flagged as typosquats
+-- or dependency confusion
+
+ALTER TABLE cached_assets ADD COLUMN download_count BIGINT NOT NULL DEFAULT 0;
+
+CREATE TABLE suspicious_names (
+    ecosystem TEXT NOT NULL,
+    name TEXT NOT NULL,
+    technique TEXT NOT NULL,
+    similar_to TEXT,
+    action TEXT NOT NULL,
+    hits BIGINT NOT NULL DEFAULT 1,
+    first_seen TIMESTAMPTZ NOT NULL,
+    last_seen TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (ecosystem, name)
+);
+
+CREATE INDEX idx_suspicious_names_last_seen ON suspicious_names(last_seen);
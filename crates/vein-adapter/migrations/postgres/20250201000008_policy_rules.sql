@@ -0,0 +1,15 @@
+-- Package policy rules created in the admin UI; config rules are not stored
+
+CREATE TABLE policy_rules (
+    id TEXT PRIMARY KEY,
+    ecosystem TEXT,
+    package TEXT NOT NULL DEFAULT '*',
+    versions TEXT,
+    platform TEXT,
+    attributes_json TEXT NOT NULL DEFAULT '{}',
+    action TEXT NOT NULL,
+    reason TEXT,
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX idx_policy_rules_created_at ON policy_rules(created_at);
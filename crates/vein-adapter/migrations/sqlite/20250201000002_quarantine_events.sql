@@ -0,0 +1,27 @@
+-- Append-only audit trail of quarantine decisions
+
+CREATE TABLE quarantine_events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    version TEXT NOT NULL,
+    platform TEXT,
+    action TEXT NOT NULL,
+    actor TEXT NOT NULL,
+    reason TEXT,
+    previous_status TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ','now'))
+);
+
+CREATE INDEX idx_quarantine_events_gem ON quarantine_events(name, version);
+
+CREATE TRIGGER quarantine_events_no_update
+BEFORE UPDATE ON quarantine_events
+BEGIN
+    SELECT RAISE(ABORT, 'quarantine_events is append-only');
+END;
+
+CREATE TRIGGER quarantine_events_no_delete
+BEFORE DELETE ON quarantine_events
+BEGIN
+    SELECT RAISE(ABORT, 'quarantine_events is append-only');
+END;
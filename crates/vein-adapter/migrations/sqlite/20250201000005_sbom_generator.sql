@@ -0,0 +1,4 @@
+-- Vein version that generated each stored SBOM, so stale ones can be backfilled
+
+ALTER TABLE gem_metadata ADD COLUMN sbom_generator TEXT;
+ALTER TABLE package_metadata ADD COLUMN sbom_generator TEXT;
@@ -0,0 +1,15 @@
+-- Suspicious patterns found in the sources of cached gem versions
+
+CREATE TABLE gem_findings (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    gem_name TEXT NOT NULL,
+    gem_version TEXT NOT NULL,
+    gem_platform TEXT,
+    rule TEXT NOT NULL,
+    file_path TEXT NOT NULL,
+    line_number INTEGER,
+    excerpt TEXT NOT NULL,
+    created_at TIMESTAMP DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ','now'))
+);
+
+CREATE INDEX idx_gem_findings_gem ON gem_findings(gem_name, gem_version);
@@ -1,3 +1,4 @@
+pub mod advisory;
 mod backend_common;
 pub mod models;
 #[cfg(feature = "postgres")]
@@ -27,16 +28,28 @@ pub struct GemSymbolRecord<'a> {
     pub line_number: Option<i32>,
 }
 
+#[derive(Clone, Copy, Debug)]
+pub struct GemFindingRecord<'a> {
+    pub rule: &'a str,
+    pub file_path: &'a str,
+    pub line_number: Option<i32>,
+    pub excerpt: &'a str,
+}
+
 // Re-export commonly used types
 pub use types::{
-    AssetKey, AssetKind, CachedAsset, DependencyKind, GemDependency, GemMetadata, IndexStats,
-    SbomCoverage,
+    AssetKey, AssetKind, CachedAsset, DependencyKind, Ecosystem, GemDependency, GemFinding,
+    GemMetadata, GemSymbol, IndexStats, PackageDependency, PackageLicense, PackageMetadata,
+    SbomCoverage, StoredPolicyRule, SuspiciousName, UpstreamYank, YankedDownload,
 };
 
+// Re-export advisory types
+pub use advisory::{Advisory, AdvisorySeverity, AffectedPackage, AffectedRange, RangeEvent};
+
 // Re-export quarantine types
 pub use quarantine::{
-    DelayPolicy, GemVersion, QuarantineInfo, QuarantineStats, VersionStatus,
-    calculate_availability, is_version_available, is_version_downloadable,
+    DelayPolicy, GemVersion, QuarantineAction, QuarantineEvent, QuarantineInfo, QuarantineStats,
+    VersionStatus, calculate_availability, is_version_available, is_version_downloadable,
 };
 
 // Re-export backend implementations (conditional)
@@ -49,6 +62,9 @@ pub use sqlite::SqliteCacheBackend;
 // Uses explicit `impl Future + Send` to satisfy auto-trait bounds without #[allow]
 pub trait CacheBackendTrait: Send + Sync {
     fn get(&self, key: &AssetKey<'_>) -> impl Future<Output = Result<Option<CachedAsset>>> + Send;
+    /// Like [`get`](Self::get) but leaves `last_accessed` untouched, for
+    /// audits and reports that should not count as downloads.
+    fn peek(&self, key: &AssetKey<'_>) -> impl Future<Output = Result<Option<CachedAsset>>> + Send;
     fn insert_or_replace(
         &self,
         key: &AssetKey<'_>,
@@ -58,6 +74,8 @@ pub trait CacheBackendTrait: Send + Sync {
     ) -> impl Future<Output = Result<()>> + Send;
     fn get_all_gems(&self) -> impl Future<Output = Result<Vec<(String, String)>>> + Send;
     fn stats(&self) -> impl Future<Output = Result<IndexStats>> + Send;
+    /// Cheap round trip used by readiness probes.
+    fn ping(&self) -> impl Future<Output = Result<()>> + Send;
     fn catalog_upsert_names(&self, names: &[String]) -> impl Future<Output = Result<()>> + Send;
     fn catalog_total(&self) -> impl Future<Output = Result<u64>> + Send;
     fn catalog_page(
@@ -80,6 +98,17 @@ pub trait CacheBackendTrait: Send + Sync {
         platform: Option<&str>,
     ) -> impl Future<Output = Result<Option<GemMetadata>>> + Send;
     fn sbom_coverage(&self) -> impl Future<Output = Result<SbomCoverage>> + Send;
+    /// Stores manifest metadata for a crate or npm package.
+    fn upsert_package_metadata(
+        &self,
+        metadata: &PackageMetadata,
+    ) -> impl Future<Output = Result<()>> + Send;
+    fn package_metadata(
+        &self,
+        ecosystem: Ecosystem,
+        name: &str,
+        version: &str,
+    ) -> impl Future<Output = Result<Option<PackageMetadata>>> + Send;
     fn catalog_languages(&self) -> impl Future<Output = Result<Vec<String>>> + Send;
     fn catalog_page_by_language(
         &self,
@@ -146,11 +175,81 @@ pub trait CacheBackendTrait: Send + Sync {
         name: &str,
     ) -> impl Future<Output = Result<Vec<GemVersion>>> + Send;
 
+    /// Appends an entry to the quarantine audit trail.
+    fn record_quarantine_event(
+        &self,
+        event: &QuarantineEvent,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    /// Audit trail for a gem (optionally one version), newest first.
+    fn quarantine_events(
+        &self,
+        name: &str,
+        version: Option<&str>,
+        limit: u32,
+    ) -> impl Future<Output = Result<Vec<QuarantineEvent>>> + Send;
+
+    /// Most recent audit trail entries across all gems.
+    fn recent_quarantine_events(
+        &self,
+        limit: u32,
+    ) -> impl Future<Output = Result<Vec<QuarantineEvent>>> + Send;
+
+    // ==================== Upstream Yank Methods ====================
+
+    /// Lists every cached `(name, version, platform)` of the given kind.
+    fn cached_versions(
+        &self,
+        kind: AssetKind,
+    ) -> impl Future<Output = Result<Vec<(String, String, Option<String>)>>> + Send;
+
+    /// Distinct cached versions of one package.
+    fn cached_package_versions(
+        &self,
+        kind: AssetKind,
+        name: &str,
+    ) -> impl Future<Output = Result<Vec<String>>> + Send;
+
+    /// Records that upstream no longer offers a version. Returns `true` when
+    /// the yank was not known before.
+    fn record_upstream_yank(
+        &self,
+        yank: &UpstreamYank,
+    ) -> impl Future<Output = Result<bool>> + Send;
+
+    fn get_upstream_yank(
+        &self,
+        key: &AssetKey<'_>,
+    ) -> impl Future<Output = Result<Option<UpstreamYank>>> + Send;
+
+    /// Yanked versions that are cached and were served at or after `since`.
+    fn recently_downloaded_yanks(
+        &self,
+        since: DateTime<Utc>,
+        limit: u32,
+    ) -> impl Future<Output = Result<Vec<YankedDownload>>> + Send;
+
+    // ==================== Advisory Methods ====================
+
+    /// Inserts or replaces an advisory together with its affected packages.
+    fn upsert_advisory(&self, advisory: &Advisory) -> impl Future<Output = Result<()>> + Send;
+
+    /// Advisories affecting a package; each carries only that package's ranges.
+    fn advisories_for_package(
+        &self,
+        ecosystem: Ecosystem,
+        name: &str,
+    ) -> impl Future<Output = Result<Vec<Advisory>>> + Send;
+
+    /// Number of stored advisories.
+    fn advisory_count(&self) -> impl Future<Output = Result<u64>> + Send;
+
     // ==================== Symbol Indexing Methods ====================
 
+    /// Stores the symbols of one gem in a single transaction.
     fn insert_symbols(
         &self,
-        symbol: GemSymbolRecord<'_>,
+        symbols: &[GemSymbolRecord<'_>],
     ) -> impl Future<Output = Result<()>> + Send;
 
     fn clear_symbols(
@@ -159,4 +258,75 @@ pub trait CacheBackendTrait: Send + Sync {
         gem_version: &str,
         gem_platform: Option<&str>,
     ) -> impl Future<Output = Result<()>> + Send;
+
+    /// Symbols whose fully qualified name matches `query`, where `*` is a
+    /// wildcard. `symbol_type` narrows the search to one kind.
+    fn search_symbols(
+        &self,
+        query: &str,
+        symbol_type: Option<&str>,
+        limit: u32,
+    ) -> impl Future<Output = Result<Vec<GemSymbol>>> + Send;
+
+    // ==================== Content Finding Methods ====================
+
+    /// Replaces the suspicious-content findings of one gem version.
+    fn replace_findings(
+        &self,
+        gem_name: &str,
+        gem_version: &str,
+        gem_platform: Option<&str>,
+        findings: &[GemFindingRecord<'_>],
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    /// Findings of one gem version, ordered by file and line.
+    fn gem_findings(
+        &self,
+        gem_name: &str,
+        gem_version: &str,
+        gem_platform: Option<&str>,
+    ) -> impl Future<Output = Result<Vec<GemFinding>>> + Send;
+
+    /// Most recently recorded findings across all gems.
+    fn recent_findings(&self, limit: u32) -> impl Future<Output = Result<Vec<GemFinding>>> + Send;
+
+    // ==================== Typosquat Methods ====================
+
+    /// Names of the most downloaded cached packages of one kind.
+    fn popular_packages(
+        &self,
+        kind: AssetKind,
+        limit: u32,
+    ) -> impl Future<Output = Result<Vec<String>>> + Send;
+
+    /// Records a flagged package name, or counts another request for it.
+    fn record_suspicious_name(
+        &self,
+        flagged: &SuspiciousName,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    /// Flagged package names, most recently requested first.
+    fn suspicious_names(
+        &self,
+        limit: u32,
+    ) -> impl Future<Output = Result<Vec<SuspiciousName>>> + Send;
+
+    /// Number of flagged package names.
+    fn suspicious_name_count(&self) -> impl Future<Output = Result<u64>> + Send;
+
+    // ==================== License Methods ====================
+
+    /// Declared licenses of every analyzed package version.
+    fn package_licenses(&self) -> impl Future<Output = Result<Vec<PackageLicense>>> + Send;
+
+    // ==================== Policy Rule Methods ====================
+
+    /// Policy rules created in the admin UI, oldest first.
+    fn policy_rules(&self) -> impl Future<Output = Result<Vec<StoredPolicyRule>>> + Send;
+
+    /// Creates a policy rule, or replaces the one with the same id.
+    fn save_policy_rule(&self, rule: &StoredPolicyRule) -> impl Future<Output = Result<()>> + Send;
+
+    /// Deletes a policy rule; returns whether it existed.
+    fn delete_policy_rule(&self, id: &str) -> impl Future<Output = Result<bool>> + Send;
 }
@@ -0,0 +1,132 @@
+//! Vulnerability advisory types.
+//!
+//! Advisories are imported from offline OSV dumps and stored per affected
+//! package. Version matching needs ecosystem-specific ordering, so it lives
+//! in the main crate; the adapter only stores and loads the ranges.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::types::Ecosystem;
+
+/// Advisory severity, ordered from least to most severe.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum AdvisorySeverity {
+    #[default]
+    Unknown,
+    Low,
+    Moderate,
+    High,
+    Critical,
+}
+
+impl AdvisorySeverity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Unknown => "unknown",
+            Self::Low => "low",
+            Self::Moderate => "moderate",
+            Self::High => "high",
+            Self::Critical => "critical",
+        }
+    }
+}
+
+impl std::fmt::Display for AdvisorySeverity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for AdvisorySeverity {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "unknown" | "none" => Ok(Self::Unknown),
+            "low" => Ok(Self::Low),
+            "moderate" | "medium" => Ok(Self::Moderate),
+            "high" => Ok(Self::High),
+            "critical" => Ok(Self::Critical),
+            _ => Err(()),
+        }
+    }
+}
+
+/// One event of an OSV affected range.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RangeEvent {
+    Introduced(String),
+    Fixed(String),
+    LastAffected(String),
+    Limit(String),
+}
+
+/// An ordered list of range events (`introduced`, then `fixed`, ...).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AffectedRange {
+    pub events: Vec<RangeEvent>,
+}
+
+/// Versions of one package affected by an advisory.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AffectedPackage {
+    pub ecosystem: Ecosystem,
+    pub name: String,
+    #[serde(default)]
+    pub ranges: Vec<AffectedRange>,
+    /// Explicitly listed affected versions.
+    #[serde(default)]
+    pub versions: Vec<String>,
+}
+
+impl AffectedPackage {
+    /// Versions named by `fixed` events, in range order.
+    pub fn fixed_versions(&self) -> Vec<&str> {
+        self.ranges
+            .iter()
+            .flat_map(|range| &range.events)
+            .filter_map(|event| match event {
+                RangeEvent::Fixed(version) => Some(version.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// A vulnerability advisory.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Advisory {
+    /// OSV identifier (GHSA-..., RUSTSEC-..., ...).
+    pub id: String,
+    pub summary: Option<String>,
+    pub details: Option<String>,
+    pub severity: AdvisorySeverity,
+    /// Other identifiers, e.g. CVE numbers.
+    pub aliases: Vec<String>,
+    /// Primary reference URL.
+    pub url: Option<String>,
+    pub published: Option<DateTime<Utc>>,
+    pub modified: DateTime<Utc>,
+    pub withdrawn: Option<DateTime<Utc>>,
+    /// Affected packages; lookups by package return only that package.
+    pub affected: Vec<AffectedPackage>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn severity_orders_and_parses() {
+        assert!(AdvisorySeverity::Critical > AdvisorySeverity::High);
+        assert!(AdvisorySeverity::Low > AdvisorySeverity::Unknown);
+        assert_eq!("MEDIUM".parse(), Ok(AdvisorySeverity::Moderate));
+        assert_eq!(AdvisorySeverity::High.to_string(), "high");
+        assert!("severe".parse::<AdvisorySeverity>().is_err());
+    }
+}
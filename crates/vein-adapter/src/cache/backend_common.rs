@@ -70,6 +70,23 @@ pub fn search_like_pattern(query: &str) -> String {
     format!("%{}%", query)
 }
 
+/// LIKE pattern for a symbol search: `*` matches anything, everything else is
+/// literal (`_` is common in Ruby names).
+pub fn symbol_like_pattern(query: &str) -> String {
+    let mut pattern = String::with_capacity(query.len());
+    for ch in query.chars() {
+        match ch {
+            '*' => pattern.push('%'),
+            '%' | '_' | '\\' => {
+                pattern.push('\\');
+                pattern.push(ch);
+            }
+            _ => pattern.push(ch),
+        }
+    }
+    pattern
+}
+
 pub fn json_array_like_pattern(value: &str) -> String {
     format!("%\"{}\"%", value)
 }
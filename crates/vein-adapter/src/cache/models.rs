@@ -1,7 +1,12 @@
 use chrono::{DateTime, SecondsFormat, Utc};
 use sqlx::FromRow;
 
-use super::types::CachedAsset;
+use serde_json::Value as JsonValue;
+
+use super::types::{
+    CachedAsset, Ecosystem, GemFinding, GemSymbol, PackageLicense, StoredPolicyRule,
+    SuspiciousName, UpstreamYank, YankedDownload,
+};
 
 /// Parses an RFC 3339 timestamp string, falling back to the current time when
 /// the stored value cannot be parsed.
@@ -81,6 +86,26 @@ pub struct DbGemMetadataRow {
     pub size_bytes: i64,
     pub sha256: String,
     pub sbom_json: Option<String>,
+    pub sbom_generator: Option<String>,
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct PackageMetadataRow {
+    pub ecosystem: String,
+    pub name: String,
+    pub version: String,
+    pub description: Option<String>,
+    pub license: Option<String>,
+    pub authors_json: String,
+    pub homepage: Option<String>,
+    pub repository: Option<String>,
+    pub dependencies_json: String,
+    pub features_json: String,
+    pub attributes_json: String,
+    pub size_bytes: i64,
+    pub sha256: String,
+    pub sbom_json: Option<String>,
+    pub sbom_generator: Option<String>,
 }
 
 pub fn format_timestamp(ts: DateTime<Utc>) -> String {
@@ -89,7 +114,7 @@ pub fn format_timestamp(ts: DateTime<Utc>) -> String {
 
 // ==================== Quarantine Row Types ====================
 
-use super::quarantine::GemVersion;
+use super::quarantine::{GemVersion, QuarantineAction, QuarantineEvent};
 
 /// SQLite row type for gem_versions table (stores DateTime as TEXT)
 #[derive(Debug, FromRow)]
@@ -162,3 +187,435 @@ impl From<PostgresGemVersionRow> for GemVersion {
         }
     }
 }
+
+/// SQLite row type for quarantine_events table
+#[derive(Debug, FromRow)]
+pub struct QuarantineEventRow {
+    pub id: i64,
+    pub name: String,
+    pub version: String,
+    pub platform: Option<String>,
+    pub action: String,
+    pub actor: String,
+    pub reason: Option<String>,
+    pub previous_status: Option<String>,
+    pub created_at: String,
+}
+
+/// PostgreSQL row type for quarantine_events table
+#[derive(Debug, FromRow)]
+pub struct PostgresQuarantineEventRow {
+    pub id: i64,
+    pub name: String,
+    pub version: String,
+    pub platform: Option<String>,
+    pub action: String,
+    pub actor: String,
+    pub reason: Option<String>,
+    pub previous_status: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<QuarantineEventRow> for QuarantineEvent {
+    fn from(row: QuarantineEventRow) -> Self {
+        QuarantineEvent {
+            id: row.id,
+            name: row.name,
+            version: row.version,
+            platform: row.platform,
+            action: row.action.parse().unwrap_or(QuarantineAction::Approve),
+            actor: row.actor,
+            reason: row.reason,
+            previous_status: row.previous_status.and_then(|s| s.parse().ok()),
+            created_at: parse_timestamp(&row.created_at),
+        }
+    }
+}
+
+impl From<PostgresQuarantineEventRow> for QuarantineEvent {
+    fn from(row: PostgresQuarantineEventRow) -> Self {
+        QuarantineEvent {
+            id: row.id,
+            name: row.name,
+            version: row.version,
+            platform: row.platform,
+            action: row.action.parse().unwrap_or(QuarantineAction::Approve),
+            actor: row.actor,
+            reason: row.reason,
+            previous_status: row.previous_status.and_then(|s| s.parse().ok()),
+            created_at: row.created_at,
+        }
+    }
+}
+
+// ==================== Upstream Yank Row Types ====================
+
+/// SQLite row type for upstream_yanks joined with cached_assets
+#[derive(Debug, FromRow)]
+pub struct UpstreamYankRow {
+    pub ecosystem: String,
+    pub name: String,
+    pub version: String,
+    pub platform: String,
+    pub reason: String,
+    pub detected_at: String,
+    pub last_accessed: Option<String>,
+}
+
+#[derive(Debug, FromRow)]
+pub struct SuspiciousNameRow {
+    pub ecosystem: String,
+    pub name: String,
+    pub technique: String,
+    pub similar_to: Option<String>,
+    pub action: String,
+    pub hits: i64,
+    pub first_seen: String,
+    pub last_seen: String,
+}
+
+#[derive(Debug, FromRow)]
+pub struct PostgresSuspiciousNameRow {
+    pub ecosystem: String,
+    pub name: String,
+    pub technique: String,
+    pub similar_to: Option<String>,
+    pub action: String,
+    pub hits: i64,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+}
+
+impl From<SuspiciousNameRow> for SuspiciousName {
+    fn from(row: SuspiciousNameRow) -> Self {
+        SuspiciousName {
+            ecosystem: row.ecosystem.parse().unwrap_or(Ecosystem::RubyGems),
+            name: row.name,
+            technique: row.technique,
+            similar_to: row.similar_to,
+            action: row.action,
+            hits: row.hits.max(0) as u64,
+            first_seen: parse_timestamp(&row.first_seen),
+            last_seen: parse_timestamp(&row.last_seen),
+        }
+    }
+}
+
+impl From<PostgresSuspiciousNameRow> for SuspiciousName {
+    fn from(row: PostgresSuspiciousNameRow) -> Self {
+        SuspiciousName {
+            ecosystem: row.ecosystem.parse().unwrap_or(Ecosystem::RubyGems),
+            name: row.name,
+            technique: row.technique,
+            similar_to: row.similar_to,
+            action: row.action,
+            hits: row.hits.max(0) as u64,
+            first_seen: row.first_seen,
+            last_seen: row.last_seen,
+        }
+    }
+}
+
+#[derive(Debug, FromRow)]
+pub struct PolicyRuleRow {
+    pub id: String,
+    pub ecosystem: Option<String>,
+    pub package: String,
+    pub versions: Option<String>,
+    pub platform: Option<String>,
+    pub attributes_json: String,
+    pub action: String,
+    pub reason: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Debug, FromRow)]
+pub struct PostgresPolicyRuleRow {
+    pub id: String,
+    pub ecosystem: Option<String>,
+    pub package: String,
+    pub versions: Option<String>,
+    pub platform: Option<String>,
+    pub attributes_json: String,
+    pub action: String,
+    pub reason: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+fn parse_attributes(json: &str) -> JsonValue {
+    serde_json::from_str(json).unwrap_or_else(|_| JsonValue::Object(Default::default()))
+}
+
+impl From<PolicyRuleRow> for StoredPolicyRule {
+    fn from(row: PolicyRuleRow) -> Self {
+        StoredPolicyRule {
+            id: row.id,
+            ecosystem: row.ecosystem.and_then(|ecosystem| ecosystem.parse().ok()),
+            package: row.package,
+            versions: row.versions,
+            platform: row.platform,
+            attributes: parse_attributes(&row.attributes_json),
+            action: row.action,
+            reason: row.reason,
+            created_at: parse_timestamp(&row.created_at),
+        }
+    }
+}
+
+impl From<PostgresPolicyRuleRow> for StoredPolicyRule {
+    fn from(row: PostgresPolicyRuleRow) -> Self {
+        StoredPolicyRule {
+            id: row.id,
+            ecosystem: row.ecosystem.and_then(|ecosystem| ecosystem.parse().ok()),
+            package: row.package,
+            versions: row.versions,
+            platform: row.platform,
+            attributes: parse_attributes(&row.attributes_json),
+            action: row.action,
+            reason: row.reason,
+            created_at: row.created_at,
+        }
+    }
+}
+
+/// PostgreSQL row type for upstream_yanks joined with cached_assets
+#[derive(Debug, FromRow)]
+pub struct PostgresUpstreamYankRow {
+    pub ecosystem: String,
+    pub name: String,
+    pub version: String,
+    pub platform: String,
+    pub reason: String,
+    pub detected_at: DateTime<Utc>,
+    pub last_accessed: Option<DateTime<Utc>>,
+}
+
+fn build_upstream_yank(
+    ecosystem: &str,
+    name: String,
+    version: String,
+    platform: String,
+    reason: String,
+    detected_at: DateTime<Utc>,
+) -> UpstreamYank {
+    UpstreamYank {
+        ecosystem: ecosystem.parse().unwrap_or(Ecosystem::RubyGems),
+        name,
+        version,
+        // Platform is stored as '' so it can take part in the primary key.
+        platform: (!platform.is_empty()).then_some(platform),
+        reason,
+        detected_at,
+    }
+}
+
+impl From<UpstreamYankRow> for UpstreamYank {
+    fn from(row: UpstreamYankRow) -> Self {
+        build_upstream_yank(
+            &row.ecosystem,
+            row.name,
+            row.version,
+            row.platform,
+            row.reason,
+            parse_timestamp(&row.detected_at),
+        )
+    }
+}
+
+impl From<PostgresUpstreamYankRow> for UpstreamYank {
+    fn from(row: PostgresUpstreamYankRow) -> Self {
+        build_upstream_yank(
+            &row.ecosystem,
+            row.name,
+            row.version,
+            row.platform,
+            row.reason,
+            row.detected_at,
+        )
+    }
+}
+
+impl From<UpstreamYankRow> for YankedDownload {
+    fn from(mut row: UpstreamYankRow) -> Self {
+        let last_accessed = row.last_accessed.take().unwrap_or_default();
+        YankedDownload {
+            yank: row.into(),
+            last_accessed,
+        }
+    }
+}
+
+impl From<PostgresUpstreamYankRow> for YankedDownload {
+    fn from(mut row: PostgresUpstreamYankRow) -> Self {
+        let last_accessed = row
+            .last_accessed
+            .take()
+            .map(format_timestamp)
+            .unwrap_or_default();
+        YankedDownload {
+            yank: row.into(),
+            last_accessed,
+        }
+    }
+}
+
+// ==================== Advisory Row Types ====================
+
+use super::advisory::{Advisory, AffectedPackage};
+
+/// SQLite row type for advisories joined with one affected package
+#[derive(Debug, FromRow)]
+pub struct AdvisoryRow {
+    pub id: String,
+    pub summary: Option<String>,
+    pub details: Option<String>,
+    pub severity: String,
+    pub aliases_json: String,
+    pub url: Option<String>,
+    pub published: Option<String>,
+    pub modified: String,
+    pub withdrawn: Option<String>,
+    pub affected_json: String,
+}
+
+/// PostgreSQL row type for advisories joined with one affected package
+#[derive(Debug, FromRow)]
+pub struct PostgresAdvisoryRow {
+    pub id: String,
+    pub summary: Option<String>,
+    pub details: Option<String>,
+    pub severity: String,
+    pub aliases_json: String,
+    pub url: Option<String>,
+    pub published: Option<DateTime<Utc>>,
+    pub modified: DateTime<Utc>,
+    pub withdrawn: Option<DateTime<Utc>>,
+    pub affected_json: String,
+}
+
+fn parse_affected(affected_json: &str) -> Vec<AffectedPackage> {
+    serde_json::from_str::<AffectedPackage>(affected_json)
+        .map(|package| vec![package])
+        .unwrap_or_default()
+}
+
+impl From<AdvisoryRow> for Advisory {
+    fn from(row: AdvisoryRow) -> Self {
+        Advisory {
+            id: row.id,
+            summary: row.summary,
+            details: row.details,
+            severity: row.severity.parse().unwrap_or_default(),
+            aliases: serde_json::from_str(&row.aliases_json).unwrap_or_default(),
+            url: row.url,
+            published: row.published.as_deref().map(parse_timestamp),
+            modified: parse_timestamp(&row.modified),
+            withdrawn: row.withdrawn.as_deref().map(parse_timestamp),
+            affected: parse_affected(&row.affected_json),
+        }
+    }
+}
+
+impl From<PostgresAdvisoryRow> for Advisory {
+    fn from(row: PostgresAdvisoryRow) -> Self {
+        Advisory {
+            id: row.id,
+            summary: row.summary,
+            details: row.details,
+            severity: row.severity.parse().unwrap_or_default(),
+            aliases: serde_json::from_str(&row.aliases_json).unwrap_or_default(),
+            url: row.url,
+            published: row.published,
+            modified: row.modified,
+            withdrawn: row.withdrawn,
+            affected: parse_affected(&row.affected_json),
+        }
+    }
+}
+
+// ==================== Symbol Row Types ====================
+
+#[derive(Debug, FromRow)]
+pub struct GemSymbolRow {
+    pub gem_name: String,
+    pub gem_version: String,
+    pub gem_platform: Option<String>,
+    pub file_path: String,
+    pub symbol_type: String,
+    pub symbol_name: String,
+    pub parent_name: Option<String>,
+    pub line_number: Option<i32>,
+}
+
+#[derive(Debug, FromRow)]
+pub struct GemFindingRow {
+    pub gem_name: String,
+    pub gem_version: String,
+    pub gem_platform: Option<String>,
+    pub rule: String,
+    pub file_path: String,
+    pub line_number: Option<i32>,
+    pub excerpt: String,
+}
+
+impl From<GemFindingRow> for GemFinding {
+    fn from(row: GemFindingRow) -> Self {
+        GemFinding {
+            gem_name: row.gem_name,
+            gem_version: row.gem_version,
+            gem_platform: row.gem_platform,
+            rule: row.rule,
+            file_path: row.file_path,
+            line_number: row.line_number,
+            excerpt: row.excerpt,
+        }
+    }
+}
+
+impl From<GemSymbolRow> for GemSymbol {
+    fn from(row: GemSymbolRow) -> Self {
+        GemSymbol {
+            gem_name: row.gem_name,
+            gem_version: row.gem_version,
+            gem_platform: row.gem_platform,
+            file_path: row.file_path,
+            symbol_type: row.symbol_type,
+            symbol_name: row.symbol_name,
+            parent_name: row.parent_name,
+            line_number: row.line_number,
+        }
+    }
+}
+
+/// Row of the license inventory; `license` holds the JSON `licenses` list
+/// for gems and the SPDX expression for other packages.
+#[derive(Debug, FromRow)]
+pub struct PackageLicenseRow {
+    pub ecosystem: String,
+    pub name: String,
+    pub version: String,
+    pub platform: Option<String>,
+    pub license: Option<String>,
+}
+
+impl From<PackageLicenseRow> for PackageLicense {
+    fn from(row: PackageLicenseRow) -> Self {
+        let ecosystem = row.ecosystem.parse().unwrap_or(Ecosystem::RubyGems);
+        let license = match ecosystem {
+            Ecosystem::RubyGems => row
+                .license
+                .and_then(|json| serde_json::from_str::<Vec<String>>(&json).ok())
+                .and_then(|licenses| PackageLicense::gem_expression(&licenses)),
+            _ => row.license,
+        }
+        .filter(|license| !license.trim().is_empty());
+
+        PackageLicense {
+            ecosystem,
+            name: row.name,
+            version: row.version,
+            platform: row.platform,
+            license,
+        }
+    }
+}
@@ -6,14 +6,27 @@ use sqlx::{
 };
 
 use super::{
-    CacheBackendTrait, GemVersion, QuarantineStats, VersionStatus,
+    CacheBackendTrait, GemVersion, QuarantineEvent, QuarantineStats, VersionStatus,
+    advisory::Advisory,
     backend_common::{
         build_index_stats, build_quarantine_stats, build_sbom_coverage, into_gem_versions,
-        json_array_like_pattern, latest_gem_version, search_like_pattern,
+        json_array_like_pattern, latest_gem_version, search_like_pattern, symbol_like_pattern,
+    },
+    models::{
+        DbGemMetadataRow, GemFindingRow, GemSymbolRow, PackageLicenseRow, PackageMetadataRow,
+        PostgresAdvisoryRow, PostgresCachedAssetRow, PostgresGemVersionRow, PostgresPolicyRuleRow,
+        PostgresQuarantineEventRow, PostgresSuspiciousNameRow, PostgresUpstreamYankRow,
+        format_timestamp,
+    },
+    serialization::{
+        hydrate_metadata_row, hydrate_package_row, parse_language_rows, prepare_metadata_strings,
+        prepare_package_strings,
+    },
+    types::{
+        AssetKey, AssetKind, CachedAsset, Ecosystem, GemFinding, GemMetadata, GemSymbol,
+        IndexStats, PackageLicense, PackageMetadata, SbomCoverage, StoredPolicyRule,
+        SuspiciousName, UpstreamYank, YankedDownload,
     },
-    models::{DbGemMetadataRow, PostgresCachedAssetRow, PostgresGemVersionRow, format_timestamp},
-    serialization::{hydrate_metadata_row, parse_language_rows, prepare_metadata_strings},
-    types::{AssetKey, CachedAsset, GemMetadata, IndexStats, SbomCoverage},
 };
 
 #[derive(Debug, Clone)]
@@ -45,7 +58,8 @@ impl PostgresCacheBackend {
         sqlx::query(
             r#"
             UPDATE cached_assets
-            SET last_accessed = NOW()
+            SET last_accessed = NOW(),
+                download_count = download_count + 1
             WHERE kind = $1 AND name = $2 AND version = $3 AND
                   ((platform IS NULL AND $4 IS NULL) OR platform = $4)
             "#,
@@ -98,7 +112,8 @@ impl PostgresCacheBackend {
                 built_at,
                 size_bytes,
                 sha256,
-                sbom_json
+                sbom_json,
+                sbom_generator
             FROM gem_metadata
             WHERE name = $1
               AND version = $2
@@ -195,11 +210,13 @@ impl PostgresCacheBackend {
                 built_at,
                 size_bytes,
                 sha256,
-                sbom_json
+                sbom_json,
+                sbom_generator
             ) VALUES (
                 $1, $2, $3, $4, $5, $6, $7, $8, $9, $10,
                 $11, $12, $13, $14, $15, $16, $17, $18, $19, $20,
-                $21, $22, $23, $24, $25, $26, $27, $28, $29, $30
+                $21, $22, $23, $24, $25, $26, $27, $28, $29, $30,
+                $31
             )
             ON CONFLICT ON CONSTRAINT gem_metadata_pkey
             DO UPDATE SET
@@ -229,7 +246,8 @@ impl PostgresCacheBackend {
                 built_at = EXCLUDED.built_at,
                 size_bytes = EXCLUDED.size_bytes,
                 sha256 = EXCLUDED.sha256,
-                sbom_json = EXCLUDED.sbom_json
+                sbom_json = EXCLUDED.sbom_json,
+                sbom_generator = EXCLUDED.sbom_generator
             "#,
         )
         .bind(&metadata.name)
@@ -262,6 +280,7 @@ impl PostgresCacheBackend {
         .bind(prepared.size_bytes)
         .bind(&metadata.sha256)
         .bind(prepared.sbom_json)
+        .bind(metadata.sbom_generator.as_deref())
         .execute(&self.pool)
         .await
         .context("upserting gem metadata (postgres)")?;
@@ -272,6 +291,14 @@ impl PostgresCacheBackend {
 
 impl CacheBackendTrait for PostgresCacheBackend {
     async fn get(&self, key: &AssetKey<'_>) -> Result<Option<CachedAsset>> {
+        let record = self.peek(key).await?;
+        if record.is_some() {
+            self.touch(key).await?;
+        }
+        Ok(record)
+    }
+
+    async fn peek(&self, key: &AssetKey<'_>) -> Result<Option<CachedAsset>> {
         let record = sqlx::query_as::<_, PostgresCachedAssetRow>(
             r#"
             SELECT path, sha256, size_bytes, last_accessed
@@ -288,12 +315,7 @@ impl CacheBackendTrait for PostgresCacheBackend {
         .await
         .context("fetching cached asset (postgres)")?;
 
-        if let Some(row) = record {
-            self.touch(key).await?;
-            Ok(Some(row.into()))
-        } else {
-            Ok(None)
-        }
+        Ok(record.map(Into::into))
     }
 
     async fn insert_or_replace(
@@ -306,15 +328,17 @@ impl CacheBackendTrait for PostgresCacheBackend {
         sqlx::query(
             r#"
             INSERT INTO cached_assets(
-                kind, name, version, platform, path, sha256, size_bytes, last_accessed
+                kind, name, version, platform, path, sha256, size_bytes, last_accessed,
+                download_count
             )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, NOW())
+            VALUES ($1, $2, $3, $4, $5, $6, $7, NOW(), 1)
             ON CONFLICT ON CONSTRAINT cached_assets_unique
             DO UPDATE SET
                 path = EXCLUDED.path,
                 sha256 = EXCLUDED.sha256,
                 size_bytes = EXCLUDED.size_bytes,
-                last_accessed = EXCLUDED.last_accessed
+                last_accessed = EXCLUDED.last_accessed,
+                download_count = cached_assets.download_count + 1
             "#,
         )
         .bind(key.kind.as_str())
@@ -346,6 +370,14 @@ impl CacheBackendTrait for PostgresCacheBackend {
         Ok(rows)
     }
 
+    async fn ping(&self) -> Result<()> {
+        sqlx::query("SELECT 1")
+            .execute(&self.pool)
+            .await
+            .context("pinging database (postgres)")?;
+        Ok(())
+    }
+
     async fn stats(&self) -> Result<IndexStats> {
         let total_assets: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM cached_assets")
             .fetch_one(&self.pool)
@@ -522,6 +554,82 @@ impl CacheBackendTrait for PostgresCacheBackend {
         self.sbom_coverage_stats().await
     }
 
+    async fn upsert_package_metadata(&self, metadata: &PackageMetadata) -> Result<()> {
+        let prepared = prepare_package_strings(metadata)?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO package_metadata (
+                ecosystem, name, version, description, license, authors_json,
+                homepage, repository, dependencies_json, features_json,
+                attributes_json, size_bytes, sha256, sbom_json,
+                   sbom_generator
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10,
+                      $11, $12, $13, $14, $15)
+            ON CONFLICT (ecosystem, name, version) DO UPDATE SET
+                description = EXCLUDED.description,
+                license = EXCLUDED.license,
+                authors_json = EXCLUDED.authors_json,
+                homepage = EXCLUDED.homepage,
+                repository = EXCLUDED.repository,
+                dependencies_json = EXCLUDED.dependencies_json,
+                features_json = EXCLUDED.features_json,
+                attributes_json = EXCLUDED.attributes_json,
+                size_bytes = EXCLUDED.size_bytes,
+                sha256 = EXCLUDED.sha256,
+                sbom_json = EXCLUDED.sbom_json,
+                sbom_generator = EXCLUDED.sbom_generator,
+                analyzed_at = NOW()
+            "#,
+        )
+        .bind(metadata.ecosystem.as_str())
+        .bind(&metadata.name)
+        .bind(&metadata.version)
+        .bind(metadata.description.as_deref())
+        .bind(metadata.license.as_deref())
+        .bind(prepared.authors_json)
+        .bind(metadata.homepage.as_deref())
+        .bind(metadata.repository.as_deref())
+        .bind(prepared.dependencies_json)
+        .bind(prepared.features_json)
+        .bind(prepared.attributes_json)
+        .bind(prepared.size_bytes)
+        .bind(&metadata.sha256)
+        .bind(prepared.sbom_json)
+        .bind(metadata.sbom_generator.as_deref())
+        .execute(&self.pool)
+        .await
+        .context("upserting package metadata (postgres)")?;
+
+        Ok(())
+    }
+
+    async fn package_metadata(
+        &self,
+        ecosystem: Ecosystem,
+        name: &str,
+        version: &str,
+    ) -> Result<Option<PackageMetadata>> {
+        let row = sqlx::query_as::<_, PackageMetadataRow>(
+            r#"
+            SELECT ecosystem, name, version, description, license, authors_json,
+                   homepage, repository, dependencies_json, features_json,
+                   attributes_json, size_bytes, sha256, sbom_json,
+                   sbom_generator
+            FROM package_metadata
+            WHERE ecosystem = $1 AND name = $2 AND version = $3
+            "#,
+        )
+        .bind(ecosystem.as_str())
+        .bind(name)
+        .bind(version)
+        .fetch_optional(&self.pool)
+        .await
+        .context("fetching package metadata (postgres)")?;
+
+        row.map(hydrate_package_row).transpose()
+    }
+
     async fn catalog_languages(&self) -> Result<Vec<String>> {
         self.catalog_languages_list().await
     }
@@ -844,37 +952,325 @@ impl CacheBackendTrait for PostgresCacheBackend {
         Ok(into_gem_versions(rows))
     }
 
-    async fn insert_symbols(&self, symbol: super::GemSymbolRecord<'_>) -> Result<()> {
+    async fn record_quarantine_event(&self, event: &QuarantineEvent) -> Result<()> {
         sqlx::query(
             r#"
-            INSERT INTO gem_symbols (
-                gem_name,
-                gem_version,
-                gem_platform,
-                file_path,
-                symbol_type,
-                symbol_name,
-                parent_name,
-                line_number
-            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
-            ON CONFLICT (gem_name, gem_version, gem_platform, file_path, symbol_name)
-            DO UPDATE SET
-                symbol_type = EXCLUDED.symbol_type,
-                parent_name = EXCLUDED.parent_name,
-                line_number = EXCLUDED.line_number
-            "#,
-        )
-        .bind(symbol.gem_name)
-        .bind(symbol.gem_version)
-        .bind(symbol.gem_platform)
-        .bind(symbol.file_path)
-        .bind(symbol.symbol_type)
-        .bind(symbol.symbol_name)
-        .bind(symbol.parent_name)
-        .bind(symbol.line_number)
+            INSERT INTO quarantine_events
+                (name, version, platform, action, actor, reason, previous_status, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            "#,
+        )
+        .bind(&event.name)
+        .bind(&event.version)
+        .bind(event.platform.as_deref())
+        .bind(event.action.to_string())
+        .bind(&event.actor)
+        .bind(event.reason.as_deref())
+        .bind(event.previous_status.map(|status| status.to_string()))
+        .bind(event.created_at)
         .execute(&self.pool)
         .await
-        .context("inserting gem symbol (postgres)")?;
+        .context("recording quarantine event (postgres)")?;
+
+        Ok(())
+    }
+
+    async fn quarantine_events(
+        &self,
+        name: &str,
+        version: Option<&str>,
+        limit: u32,
+    ) -> Result<Vec<QuarantineEvent>> {
+        let rows = sqlx::query_as::<_, PostgresQuarantineEventRow>(
+            r#"
+            SELECT id, name, version, platform, action, actor, reason, previous_status,
+                   created_at
+            FROM quarantine_events
+            WHERE name = $1
+              AND ($2::TEXT IS NULL OR version = $2)
+            ORDER BY created_at DESC, id DESC
+            LIMIT $3
+            "#,
+        )
+        .bind(name)
+        .bind(version)
+        .bind(i64::from(limit))
+        .fetch_all(&self.pool)
+        .await
+        .context("fetching quarantine events (postgres)")?;
+
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+
+    async fn recent_quarantine_events(&self, limit: u32) -> Result<Vec<QuarantineEvent>> {
+        let rows = sqlx::query_as::<_, PostgresQuarantineEventRow>(
+            r#"
+            SELECT id, name, version, platform, action, actor, reason, previous_status,
+                   created_at
+            FROM quarantine_events
+            ORDER BY created_at DESC, id DESC
+            LIMIT $1
+            "#,
+        )
+        .bind(i64::from(limit))
+        .fetch_all(&self.pool)
+        .await
+        .context("fetching recent quarantine events (postgres)")?;
+
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+
+    // ==================== Upstream Yank Methods ====================
+
+    async fn cached_versions(
+        &self,
+        kind: AssetKind,
+    ) -> Result<Vec<(String, String, Option<String>)>> {
+        let rows = sqlx::query_as::<_, (String, String, Option<String>)>(
+            r#"
+            SELECT DISTINCT name, version, platform
+            FROM cached_assets
+            WHERE kind = $1
+            ORDER BY name, version
+            "#,
+        )
+        .bind(kind.as_str())
+        .fetch_all(&self.pool)
+        .await
+        .context("fetching cached versions (postgres)")?;
+
+        Ok(rows)
+    }
+
+    async fn cached_package_versions(&self, kind: AssetKind, name: &str) -> Result<Vec<String>> {
+        let rows = sqlx::query_scalar::<_, String>(
+            r#"
+            SELECT DISTINCT version
+            FROM cached_assets
+            WHERE kind = $1 AND name = $2
+            ORDER BY version
+            "#,
+        )
+        .bind(kind.as_str())
+        .bind(name)
+        .fetch_all(&self.pool)
+        .await
+        .context("fetching cached package versions (postgres)")?;
+
+        Ok(rows)
+    }
+
+    async fn record_upstream_yank(&self, yank: &UpstreamYank) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO upstream_yanks (ecosystem, name, version, platform, reason, detected_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (ecosystem, name, version, platform) DO NOTHING
+            "#,
+        )
+        .bind(yank.ecosystem.as_str())
+        .bind(&yank.name)
+        .bind(&yank.version)
+        .bind(yank.platform.as_deref().unwrap_or_default())
+        .bind(&yank.reason)
+        .bind(yank.detected_at)
+        .execute(&self.pool)
+        .await
+        .context("recording upstream yank (postgres)")?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn get_upstream_yank(&self, key: &AssetKey<'_>) -> Result<Option<UpstreamYank>> {
+        let row = sqlx::query_as::<_, PostgresUpstreamYankRow>(
+            r#"
+            SELECT ecosystem, name, version, platform, reason, detected_at,
+                   NULL::TIMESTAMPTZ AS last_accessed
+            FROM upstream_yanks
+            WHERE ecosystem = $1 AND name = $2 AND version = $3 AND platform = $4
+            "#,
+        )
+        .bind(key.kind.ecosystem().as_str())
+        .bind(key.name)
+        .bind(key.version)
+        .bind(key.platform.unwrap_or_default())
+        .fetch_optional(&self.pool)
+        .await
+        .context("fetching upstream yank (postgres)")?;
+
+        Ok(row.map(Into::into))
+    }
+
+    async fn recently_downloaded_yanks(
+        &self,
+        since: DateTime<Utc>,
+        limit: u32,
+    ) -> Result<Vec<YankedDownload>> {
+        let rows = sqlx::query_as::<_, PostgresUpstreamYankRow>(
+            r#"
+            SELECT y.ecosystem, y.name, y.version, y.platform, y.reason, y.detected_at,
+                   MAX(a.last_accessed) AS last_accessed
+            FROM upstream_yanks y
+            JOIN cached_assets a
+              ON a.name = y.name
+             AND a.version = y.version
+             AND COALESCE(a.platform, '') = y.platform
+             AND CASE a.kind
+                     WHEN 'crate' THEN 'crates'
+                     WHEN 'npm' THEN 'npm'
+                     ELSE 'rubygems'
+                 END = y.ecosystem
+            WHERE a.last_accessed >= $1
+            GROUP BY y.ecosystem, y.name, y.version, y.platform, y.reason, y.detected_at
+            ORDER BY last_accessed DESC
+            LIMIT $2
+            "#,
+        )
+        .bind(since)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await
+        .context("fetching recently downloaded yanks (postgres)")?;
+
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+
+    async fn upsert_advisory(&self, advisory: &Advisory) -> Result<()> {
+        let aliases_json =
+            serde_json::to_string(&advisory.aliases).context("serializing advisory aliases")?;
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO advisories (
+                id, summary, details, severity, aliases_json, url,
+                published, modified, withdrawn, imported_at
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, NOW())
+            ON CONFLICT (id) DO UPDATE SET
+                summary = EXCLUDED.summary,
+                details = EXCLUDED.details,
+                severity = EXCLUDED.severity,
+                aliases_json = EXCLUDED.aliases_json,
+                url = EXCLUDED.url,
+                published = EXCLUDED.published,
+                modified = EXCLUDED.modified,
+                withdrawn = EXCLUDED.withdrawn,
+                imported_at = EXCLUDED.imported_at
+            "#,
+        )
+        .bind(&advisory.id)
+        .bind(&advisory.summary)
+        .bind(&advisory.details)
+        .bind(advisory.severity.as_str())
+        .bind(aliases_json)
+        .bind(&advisory.url)
+        .bind(advisory.published)
+        .bind(advisory.modified)
+        .bind(advisory.withdrawn)
+        .execute(&mut *tx)
+        .await
+        .context("upserting advisory (postgres)")?;
+
+        sqlx::query("DELETE FROM advisory_packages WHERE advisory_id = $1")
+            .bind(&advisory.id)
+            .execute(&mut *tx)
+            .await
+            .context("clearing advisory packages (postgres)")?;
+
+        for package in &advisory.affected {
+            let affected_json =
+                serde_json::to_string(package).context("serializing affected package")?;
+            sqlx::query(
+                r#"
+                INSERT INTO advisory_packages (advisory_id, ecosystem, name, affected_json)
+                VALUES ($1, $2, $3, $4)
+                ON CONFLICT (advisory_id, ecosystem, name)
+                DO UPDATE SET affected_json = EXCLUDED.affected_json
+                "#,
+            )
+            .bind(&advisory.id)
+            .bind(package.ecosystem.as_str())
+            .bind(&package.name)
+            .bind(affected_json)
+            .execute(&mut *tx)
+            .await
+            .context("inserting advisory package (postgres)")?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn advisories_for_package(
+        &self,
+        ecosystem: Ecosystem,
+        name: &str,
+    ) -> Result<Vec<Advisory>> {
+        let rows = sqlx::query_as::<_, PostgresAdvisoryRow>(
+            r#"
+            SELECT a.id, a.summary, a.details, a.severity, a.aliases_json, a.url,
+                   a.published, a.modified, a.withdrawn, p.affected_json
+            FROM advisory_packages p
+            JOIN advisories a ON a.id = p.advisory_id
+            WHERE p.ecosystem = $1 AND p.name = $2
+            ORDER BY a.modified DESC, a.id
+            "#,
+        )
+        .bind(ecosystem.as_str())
+        .bind(name)
+        .fetch_all(&self.pool)
+        .await
+        .context("fetching advisories (postgres)")?;
+
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+
+    async fn advisory_count(&self) -> Result<u64> {
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM advisories")
+            .fetch_one(&self.pool)
+            .await
+            .context("counting advisories (postgres)")?;
+
+        Ok(count as u64)
+    }
+
+    async fn insert_symbols(&self, symbols: &[super::GemSymbolRecord<'_>]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for symbol in symbols {
+            sqlx::query(
+                r#"
+                INSERT INTO gem_symbols (
+                    gem_name,
+                    gem_version,
+                    gem_platform,
+                    file_path,
+                    symbol_type,
+                    symbol_name,
+                    parent_name,
+                    line_number
+                ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                ON CONFLICT (gem_name, gem_version, gem_platform, file_path, symbol_name)
+                DO UPDATE SET
+                    symbol_type = EXCLUDED.symbol_type,
+                    parent_name = EXCLUDED.parent_name,
+                    line_number = EXCLUDED.line_number
+                "#,
+            )
+            .bind(symbol.gem_name)
+            .bind(symbol.gem_version)
+            .bind(symbol.gem_platform)
+            .bind(symbol.file_path)
+            .bind(symbol.symbol_type)
+            .bind(symbol.symbol_name)
+            .bind(symbol.parent_name)
+            .bind(symbol.line_number)
+            .execute(&mut *tx)
+            .await
+            .context("inserting gem symbol (postgres)")?;
+        }
+        tx.commit()
+            .await
+            .context("committing gem symbols (postgres)")?;
 
         Ok(())
     }
@@ -902,4 +1298,281 @@ impl CacheBackendTrait for PostgresCacheBackend {
 
         Ok(())
     }
+
+    async fn search_symbols(
+        &self,
+        query: &str,
+        symbol_type: Option<&str>,
+        limit: u32,
+    ) -> Result<Vec<GemSymbol>> {
+        let rows = sqlx::query_as::<_, GemSymbolRow>(
+            r#"
+            SELECT gem_name, gem_version, gem_platform, file_path, symbol_type,
+                   symbol_name, parent_name, line_number
+            FROM gem_symbols
+            WHERE symbol_name LIKE $1 ESCAPE '\'
+              AND ($2::text IS NULL OR symbol_type = $2)
+            ORDER BY symbol_name, gem_name, gem_version, file_path
+            LIMIT $3
+            "#,
+        )
+        .bind(symbol_like_pattern(query))
+        .bind(symbol_type)
+        .bind(i64::from(limit))
+        .fetch_all(&self.pool)
+        .await
+        .context("searching gem symbols (postgres)")?;
+
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+
+    async fn replace_findings(
+        &self,
+        gem_name: &str,
+        gem_version: &str,
+        gem_platform: Option<&str>,
+        findings: &[super::GemFindingRecord<'_>],
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            r#"
+            DELETE FROM gem_findings
+            WHERE gem_name = $1
+              AND gem_version = $2
+              AND gem_platform IS NOT DISTINCT FROM $3
+            "#,
+        )
+        .bind(gem_name)
+        .bind(gem_version)
+        .bind(gem_platform)
+        .execute(&mut *tx)
+        .await
+        .context("clearing gem findings (postgres)")?;
+
+        for finding in findings {
+            sqlx::query(
+                r#"
+                INSERT INTO gem_findings (
+                    gem_name,
+                    gem_version,
+                    gem_platform,
+                    rule,
+                    file_path,
+                    line_number,
+                    excerpt
+                ) VALUES ($1, $2, $3, $4, $5, $6, $7)
+                "#,
+            )
+            .bind(gem_name)
+            .bind(gem_version)
+            .bind(gem_platform)
+            .bind(finding.rule)
+            .bind(finding.file_path)
+            .bind(finding.line_number)
+            .bind(finding.excerpt)
+            .execute(&mut *tx)
+            .await
+            .context("inserting gem finding (postgres)")?;
+        }
+        tx.commit()
+            .await
+            .context("committing gem findings (postgres)")?;
+
+        Ok(())
+    }
+
+    async fn gem_findings(
+        &self,
+        gem_name: &str,
+        gem_version: &str,
+        gem_platform: Option<&str>,
+    ) -> Result<Vec<GemFinding>> {
+        let rows = sqlx::query_as::<_, GemFindingRow>(
+            r#"
+            SELECT gem_name, gem_version, gem_platform, rule, file_path, line_number, excerpt
+            FROM gem_findings
+            WHERE gem_name = $1
+              AND gem_version = $2
+              AND gem_platform IS NOT DISTINCT FROM $3
+            ORDER BY file_path, line_number, rule
+            "#,
+        )
+        .bind(gem_name)
+        .bind(gem_version)
+        .bind(gem_platform)
+        .fetch_all(&self.pool)
+        .await
+        .context("loading gem findings (postgres)")?;
+
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+
+    async fn recent_findings(&self, limit: u32) -> Result<Vec<GemFinding>> {
+        let rows = sqlx::query_as::<_, GemFindingRow>(
+            r#"
+            SELECT gem_name, gem_version, gem_platform, rule, file_path, line_number, excerpt
+            FROM gem_findings
+            ORDER BY id DESC
+            LIMIT $1
+            "#,
+        )
+        .bind(i64::from(limit))
+        .fetch_all(&self.pool)
+        .await
+        .context("loading recent gem findings (postgres)")?;
+
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+
+    async fn popular_packages(&self, kind: AssetKind, limit: u32) -> Result<Vec<String>> {
+        sqlx::query_scalar::<_, String>(
+            r#"
+            SELECT name
+            FROM cached_assets
+            WHERE kind = $1
+            GROUP BY name
+            ORDER BY SUM(download_count) DESC, name
+            LIMIT $2
+            "#,
+        )
+        .bind(kind.as_str())
+        .bind(i64::from(limit))
+        .fetch_all(&self.pool)
+        .await
+        .context("listing popular packages (postgres)")
+    }
+
+    async fn record_suspicious_name(&self, flagged: &SuspiciousName) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO suspicious_names (
+                ecosystem, name, technique, similar_to, action, hits, first_seen, last_seen
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            ON CONFLICT (ecosystem, name) DO UPDATE SET
+                technique = excluded.technique,
+                similar_to = excluded.similar_to,
+                action = excluded.action,
+                hits = suspicious_names.hits + excluded.hits,
+                last_seen = excluded.last_seen
+            "#,
+        )
+        .bind(flagged.ecosystem.as_str())
+        .bind(&flagged.name)
+        .bind(&flagged.technique)
+        .bind(&flagged.similar_to)
+        .bind(&flagged.action)
+        .bind(flagged.hits.max(1) as i64)
+        .bind(flagged.first_seen)
+        .bind(flagged.last_seen)
+        .execute(&self.pool)
+        .await
+        .context("recording suspicious name (postgres)")?;
+
+        Ok(())
+    }
+
+    async fn suspicious_names(&self, limit: u32) -> Result<Vec<SuspiciousName>> {
+        let rows = sqlx::query_as::<_, PostgresSuspiciousNameRow>(
+            r#"
+            SELECT ecosystem, name, technique, similar_to, action, hits, first_seen, last_seen
+            FROM suspicious_names
+            ORDER BY last_seen DESC, name
+            LIMIT $1
+            "#,
+        )
+        .bind(i64::from(limit))
+        .fetch_all(&self.pool)
+        .await
+        .context("listing suspicious names (postgres)")?;
+
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+
+    async fn suspicious_name_count(&self) -> Result<u64> {
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM suspicious_names")
+            .fetch_one(&self.pool)
+            .await
+            .context("counting suspicious names (postgres)")?;
+
+        Ok(count.max(0) as u64)
+    }
+
+    async fn package_licenses(&self) -> Result<Vec<PackageLicense>> {
+        let rows = sqlx::query_as::<_, PackageLicenseRow>(
+            r#"
+            SELECT 'rubygems' AS ecosystem, name, version, platform, licenses AS license
+            FROM gem_metadata
+            UNION ALL
+            SELECT ecosystem, name, version, NULL AS platform, license
+            FROM package_metadata
+            ORDER BY ecosystem, name, version, platform
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("listing package licenses (postgres)")?;
+
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+
+    async fn policy_rules(&self) -> Result<Vec<StoredPolicyRule>> {
+        let rows = sqlx::query_as::<_, PostgresPolicyRuleRow>(
+            r#"
+            SELECT id, ecosystem, package, versions, platform, attributes_json, action, reason,
+                   created_at
+            FROM policy_rules
+            ORDER BY created_at, id
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("listing policy rules (postgres)")?;
+
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+
+    async fn save_policy_rule(&self, rule: &StoredPolicyRule) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO policy_rules (
+                id, ecosystem, package, versions, platform, attributes_json, action, reason,
+                created_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            ON CONFLICT (id) DO UPDATE SET
+                ecosystem = excluded.ecosystem,
+                package = excluded.package,
+                versions = excluded.versions,
+                platform = excluded.platform,
+                attributes_json = excluded.attributes_json,
+                action = excluded.action,
+                reason = excluded.reason
+            "#,
+        )
+        .bind(&rule.id)
+        .bind(rule.ecosystem.map(|ecosystem| ecosystem.as_str()))
+        .bind(&rule.package)
+        .bind(&rule.versions)
+        .bind(&rule.platform)
+        .bind(rule.attributes.to_string())
+        .bind(&rule.action)
+        .bind(&rule.reason)
+        .bind(rule.created_at)
+        .execute(&self.pool)
+        .await
+        .context("saving policy rule (postgres)")?;
+
+        Ok(())
+    }
+
+    async fn delete_policy_rule(&self, id: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM policy_rules WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .context("deleting policy rule (postgres)")?;
+
+        Ok(result.rows_affected() > 0)
+    }
 }
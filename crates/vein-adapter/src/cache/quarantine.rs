@@ -89,6 +89,58 @@ pub struct QuarantineStats {
     pub versions_releasing_this_week: u64,
 }
 
+/// Kind of decision recorded in the quarantine audit trail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuarantineAction {
+    /// An operator approved early release (may still await more approvers)
+    Approve,
+    /// Enough distinct approvers signed off and the version was pinned
+    Release,
+    /// An operator blocked the version
+    Block,
+}
+
+impl std::fmt::Display for QuarantineAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Approve => write!(f, "approve"),
+            Self::Release => write!(f, "release"),
+            Self::Block => write!(f, "block"),
+        }
+    }
+}
+
+impl std::str::FromStr for QuarantineAction {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "approve" => Ok(Self::Approve),
+            "release" => Ok(Self::Release),
+            "block" => Ok(Self::Block),
+            _ => Err(()),
+        }
+    }
+}
+
+/// One entry of the append-only quarantine audit trail.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuarantineEvent {
+    /// Assigned by the backend; ignored on insert
+    pub id: i64,
+    pub name: String,
+    pub version: String,
+    pub platform: Option<String>,
+    pub action: QuarantineAction,
+    /// Who took the action (CLI user, admin approver, ...)
+    pub actor: String,
+    pub reason: Option<String>,
+    /// Status of the version before this event, if it was tracked
+    pub previous_status: Option<VersionStatus>,
+    pub created_at: DateTime<Utc>,
+}
+
 /// Policy configuration for delay calculation.
 /// This is a simplified version for the adapter crate - full config lives in main crate.
 #[derive(Debug, Clone)]
@@ -4,8 +4,8 @@ use anyhow::{Context, Result};
 use serde::de::DeserializeOwned;
 use serde_json::Value as JsonValue;
 
-use super::models::DbGemMetadataRow;
-use super::types::{GemDependency, GemMetadata};
+use super::models::{DbGemMetadataRow, PackageMetadataRow};
+use super::types::{Ecosystem, GemDependency, GemMetadata, PackageMetadata};
 
 pub struct PreparedMetadataStrings {
     pub licenses_json: String,
@@ -58,6 +58,72 @@ pub fn prepare_metadata_strings(metadata: &GemMetadata) -> Result<PreparedMetada
     })
 }
 
+pub struct PreparedPackageStrings {
+    pub authors_json: String,
+    pub dependencies_json: String,
+    pub features_json: String,
+    pub attributes_json: String,
+    pub size_bytes: i64,
+    pub sbom_json: Option<String>,
+}
+
+pub fn prepare_package_strings(metadata: &PackageMetadata) -> Result<PreparedPackageStrings> {
+    let authors_json = serde_json::to_string(&metadata.authors).context("serializing authors")?;
+    let dependencies_json =
+        serde_json::to_string(&metadata.dependencies).context("serializing dependencies")?;
+    let features_json =
+        serde_json::to_string(&metadata.features).context("serializing features")?;
+    let attributes_json = if metadata.attributes.is_null() {
+        "{}".to_string()
+    } else {
+        serde_json::to_string(&metadata.attributes).context("serializing attributes")?
+    };
+    let sbom_json = match &metadata.sbom {
+        Some(value) => Some(serde_json::to_string(value).context("serializing sbom json")?),
+        None => None,
+    };
+
+    Ok(PreparedPackageStrings {
+        authors_json,
+        dependencies_json,
+        features_json,
+        attributes_json,
+        size_bytes: i64::try_from(metadata.size_bytes).unwrap_or(i64::MAX),
+        sbom_json,
+    })
+}
+
+pub fn hydrate_package_row(row: PackageMetadataRow) -> Result<PackageMetadata> {
+    let ecosystem: Ecosystem = row.ecosystem.parse().map_err(|_| {
+        anyhow::anyhow!("unknown ecosystem {:?} in package_metadata", row.ecosystem)
+    })?;
+    let sbom = match row.sbom_json {
+        Some(raw) => {
+            let value: JsonValue = serde_json::from_str(&raw).context("parsing sbom json value")?;
+            (!value.is_null()).then_some(value)
+        }
+        None => None,
+    };
+
+    Ok(PackageMetadata {
+        ecosystem,
+        name: row.name,
+        version: row.version,
+        description: row.description,
+        license: row.license,
+        authors: parse_required_json_array("authors", row.authors_json)?,
+        homepage: row.homepage,
+        repository: row.repository,
+        dependencies: parse_required_json_array("dependencies", row.dependencies_json)?,
+        features: serde_json::from_str(&row.features_json).context("parsing features json")?,
+        attributes: parse_json_value("attributes", Some(row.attributes_json))?,
+        size_bytes: row.size_bytes.max(0) as u64,
+        sha256: row.sha256,
+        sbom,
+        sbom_generator: row.sbom_generator,
+    })
+}
+
 pub fn parse_language_rows(rows: Vec<Option<String>>) -> Result<Vec<String>> {
     let mut languages = BTreeSet::new();
     for row in rows.into_iter().flatten() {
@@ -146,5 +212,6 @@ pub fn hydrate_metadata_row(row: DbGemMetadataRow) -> Result<GemMetadata> {
         size_bytes: row.size_bytes.max(0) as u64,
         sha256: row.sha256,
         sbom,
+        sbom_generator: row.sbom_generator,
     })
 }
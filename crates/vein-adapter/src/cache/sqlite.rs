@@ -5,14 +5,26 @@ use chrono::{DateTime, Duration, Utc};
 use sqlx::{SqlitePool, sqlite::SqlitePoolOptions};
 
 use super::{
-    CacheBackendTrait, GemVersion, QuarantineStats, VersionStatus,
+    CacheBackendTrait, GemVersion, QuarantineEvent, QuarantineStats, VersionStatus,
+    advisory::Advisory,
     backend_common::{
         build_index_stats, build_quarantine_stats, build_sbom_coverage, into_gem_versions,
-        json_array_like_pattern, latest_gem_version, search_like_pattern,
+        json_array_like_pattern, latest_gem_version, search_like_pattern, symbol_like_pattern,
+    },
+    models::{
+        AdvisoryRow, CachedAssetRow, DbGemMetadataRow, GemFindingRow, GemSymbolRow, GemVersionRow,
+        PackageLicenseRow, PackageMetadataRow, PolicyRuleRow, QuarantineEventRow,
+        SuspiciousNameRow, UpstreamYankRow, format_timestamp,
+    },
+    serialization::{
+        hydrate_metadata_row, hydrate_package_row, parse_language_rows, prepare_metadata_strings,
+        prepare_package_strings,
+    },
+    types::{
+        AssetKey, AssetKind, CachedAsset, Ecosystem, GemFinding, GemMetadata, GemSymbol,
+        IndexStats, PackageLicense, PackageMetadata, SbomCoverage, StoredPolicyRule,
+        SuspiciousName, UpstreamYank, YankedDownload,
     },
-    models::{CachedAssetRow, DbGemMetadataRow, GemVersionRow},
-    serialization::{hydrate_metadata_row, parse_language_rows, prepare_metadata_strings},
-    types::{AssetKey, CachedAsset, GemMetadata, IndexStats, SbomCoverage},
 };
 
 #[derive(Debug, Clone)]
@@ -66,7 +78,8 @@ impl SqliteCacheBackend {
         sqlx::query(
             r#"
             UPDATE cached_assets
-            SET last_accessed = strftime('%Y-%m-%dT%H:%M:%fZ','now')
+            SET last_accessed = strftime('%Y-%m-%dT%H:%M:%fZ','now'),
+                download_count = download_count + 1
             WHERE kind = ?1 AND name = ?2 AND version = ?3 AND
                   ((platform IS NULL AND ?4 IS NULL) OR platform = ?4)
             "#,
@@ -116,11 +129,13 @@ impl SqliteCacheBackend {
                 built_at,
                 size_bytes,
                 sha256,
-                sbom_json
+                sbom_json,
+                sbom_generator
             ) VALUES (
                 ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10,
                 ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20,
-                ?21, ?22, ?23, ?24, ?25, ?26, ?27, ?28, ?29, ?30
+                ?21, ?22, ?23, ?24, ?25, ?26, ?27, ?28, ?29, ?30,
+                ?31
             )
             ON CONFLICT(name, version, platform)
             DO UPDATE SET
@@ -150,7 +165,8 @@ impl SqliteCacheBackend {
                 built_at = excluded.built_at,
                 size_bytes = excluded.size_bytes,
                 sha256 = excluded.sha256,
-                sbom_json = excluded.sbom_json
+                sbom_json = excluded.sbom_json,
+                sbom_generator = excluded.sbom_generator
             "#,
         )
         .bind(&metadata.name)
@@ -183,6 +199,7 @@ impl SqliteCacheBackend {
         .bind(prepared.size_bytes)
         .bind(&metadata.sha256)
         .bind(prepared.sbom_json)
+        .bind(metadata.sbom_generator.as_deref())
         .execute(&self.pool)
         .await
         .context("upserting gem metadata")?;
@@ -228,7 +245,8 @@ impl SqliteCacheBackend {
                 built_at,
                 size_bytes,
                 sha256,
-                sbom_json
+                sbom_json,
+                sbom_generator
             FROM gem_metadata
             WHERE name = ?1
               AND version = ?2
@@ -271,6 +289,14 @@ impl SqliteCacheBackend {
 
 impl CacheBackendTrait for SqliteCacheBackend {
     async fn get(&self, key: &AssetKey<'_>) -> Result<Option<CachedAsset>> {
+        let record = self.peek(key).await?;
+        if record.is_some() {
+            self.touch(key).await?;
+        }
+        Ok(record)
+    }
+
+    async fn peek(&self, key: &AssetKey<'_>) -> Result<Option<CachedAsset>> {
         let record = sqlx::query_as::<_, CachedAssetRow>(
             r#"
             SELECT path, sha256, size_bytes, last_accessed
@@ -287,12 +313,7 @@ impl CacheBackendTrait for SqliteCacheBackend {
         .await
         .context("fetching cached asset")?;
 
-        if let Some(row) = record {
-            self.touch(key).await?;
-            Ok(Some(row.into()))
-        } else {
-            Ok(None)
-        }
+        Ok(record.map(Into::into))
     }
 
     async fn insert_or_replace(
@@ -305,15 +326,17 @@ impl CacheBackendTrait for SqliteCacheBackend {
         sqlx::query(
             r#"
             INSERT INTO cached_assets(
-                kind, name, version, platform, path, sha256, size_bytes, last_accessed
+                kind, name, version, platform, path, sha256, size_bytes, last_accessed,
+                download_count
             )
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, strftime('%Y-%m-%dT%H:%M:%fZ','now'))
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, strftime('%Y-%m-%dT%H:%M:%fZ','now'), 1)
             ON CONFLICT(kind, name, version, platform)
             DO UPDATE SET
                 path = excluded.path,
                 sha256 = excluded.sha256,
                 size_bytes = excluded.size_bytes,
-                last_accessed = excluded.last_accessed
+                last_accessed = excluded.last_accessed,
+                download_count = cached_assets.download_count + 1
             "#,
         )
         .bind(key.kind.as_str())
@@ -345,6 +368,14 @@ impl CacheBackendTrait for SqliteCacheBackend {
         Ok(rows)
     }
 
+    async fn ping(&self) -> Result<()> {
+        sqlx::query("SELECT 1")
+            .execute(&self.pool)
+            .await
+            .context("pinging database")?;
+        Ok(())
+    }
+
     async fn stats(&self) -> Result<IndexStats> {
         let total_assets: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM cached_assets")
             .fetch_one(&self.pool)
@@ -519,6 +550,82 @@ impl CacheBackendTrait for SqliteCacheBackend {
         self.sbom_coverage_stats().await
     }
 
+    async fn upsert_package_metadata(&self, metadata: &PackageMetadata) -> Result<()> {
+        let prepared = prepare_package_strings(metadata)?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO package_metadata (
+                ecosystem, name, version, description, license, authors_json,
+                homepage, repository, dependencies_json, features_json,
+                attributes_json, size_bytes, sha256, sbom_json,
+                   sbom_generator
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10,
+                      ?11, ?12, ?13, ?14, ?15)
+            ON CONFLICT (ecosystem, name, version) DO UPDATE SET
+                description = excluded.description,
+                license = excluded.license,
+                authors_json = excluded.authors_json,
+                homepage = excluded.homepage,
+                repository = excluded.repository,
+                dependencies_json = excluded.dependencies_json,
+                features_json = excluded.features_json,
+                attributes_json = excluded.attributes_json,
+                size_bytes = excluded.size_bytes,
+                sha256 = excluded.sha256,
+                sbom_json = excluded.sbom_json,
+                sbom_generator = excluded.sbom_generator,
+                analyzed_at = strftime('%Y-%m-%dT%H:%M:%fZ','now')
+            "#,
+        )
+        .bind(metadata.ecosystem.as_str())
+        .bind(&metadata.name)
+        .bind(&metadata.version)
+        .bind(metadata.description.as_deref())
+        .bind(metadata.license.as_deref())
+        .bind(prepared.authors_json)
+        .bind(metadata.homepage.as_deref())
+        .bind(metadata.repository.as_deref())
+        .bind(prepared.dependencies_json)
+        .bind(prepared.features_json)
+        .bind(prepared.attributes_json)
+        .bind(prepared.size_bytes)
+        .bind(&metadata.sha256)
+        .bind(prepared.sbom_json)
+        .bind(metadata.sbom_generator.as_deref())
+        .execute(&self.pool)
+        .await
+        .context("upserting package metadata (sqlite)")?;
+
+        Ok(())
+    }
+
+    async fn package_metadata(
+        &self,
+        ecosystem: Ecosystem,
+        name: &str,
+        version: &str,
+    ) -> Result<Option<PackageMetadata>> {
+        let row = sqlx::query_as::<_, PackageMetadataRow>(
+            r#"
+            SELECT ecosystem, name, version, description, license, authors_json,
+                   homepage, repository, dependencies_json, features_json,
+                   attributes_json, size_bytes, sha256, sbom_json,
+                   sbom_generator
+            FROM package_metadata
+            WHERE ecosystem = ?1 AND name = ?2 AND version = ?3
+            "#,
+        )
+        .bind(ecosystem.as_str())
+        .bind(name)
+        .bind(version)
+        .fetch_optional(&self.pool)
+        .await
+        .context("fetching package metadata (sqlite)")?;
+
+        row.map(hydrate_package_row).transpose()
+    }
+
     async fn catalog_languages(&self) -> Result<Vec<String>> {
         let rows = sqlx::query_scalar::<_, Option<String>>(
             r#"
@@ -869,32 +976,319 @@ impl CacheBackendTrait for SqliteCacheBackend {
         Ok(into_gem_versions(rows))
     }
 
-    async fn insert_symbols(&self, symbol: super::GemSymbolRecord<'_>) -> Result<()> {
+    async fn record_quarantine_event(&self, event: &QuarantineEvent) -> Result<()> {
         sqlx::query(
             r#"
-            INSERT OR REPLACE INTO gem_symbols (
-                gem_name,
-                gem_version,
-                gem_platform,
-                file_path,
-                symbol_type,
-                symbol_name,
-                parent_name,
-                line_number
-            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
-            "#,
-        )
-        .bind(symbol.gem_name)
-        .bind(symbol.gem_version)
-        .bind(symbol.gem_platform)
-        .bind(symbol.file_path)
-        .bind(symbol.symbol_type)
-        .bind(symbol.symbol_name)
-        .bind(symbol.parent_name)
-        .bind(symbol.line_number)
+            INSERT INTO quarantine_events
+                (name, version, platform, action, actor, reason, previous_status, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+            "#,
+        )
+        .bind(&event.name)
+        .bind(&event.version)
+        .bind(event.platform.as_deref())
+        .bind(event.action.to_string())
+        .bind(&event.actor)
+        .bind(event.reason.as_deref())
+        .bind(event.previous_status.map(|status| status.to_string()))
+        .bind(format_timestamp(event.created_at))
+        .execute(&self.pool)
+        .await
+        .context("recording quarantine event (sqlite)")?;
+
+        Ok(())
+    }
+
+    async fn quarantine_events(
+        &self,
+        name: &str,
+        version: Option<&str>,
+        limit: u32,
+    ) -> Result<Vec<QuarantineEvent>> {
+        let rows = sqlx::query_as::<_, QuarantineEventRow>(
+            r#"
+            SELECT id, name, version, platform, action, actor, reason, previous_status,
+                   created_at
+            FROM quarantine_events
+            WHERE name = ?1
+              AND (?2 IS NULL OR version = ?2)
+            ORDER BY created_at DESC, id DESC
+            LIMIT ?3
+            "#,
+        )
+        .bind(name)
+        .bind(version)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .context("fetching quarantine events (sqlite)")?;
+
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+
+    async fn recent_quarantine_events(&self, limit: u32) -> Result<Vec<QuarantineEvent>> {
+        let rows = sqlx::query_as::<_, QuarantineEventRow>(
+            r#"
+            SELECT id, name, version, platform, action, actor, reason, previous_status,
+                   created_at
+            FROM quarantine_events
+            ORDER BY created_at DESC, id DESC
+            LIMIT ?1
+            "#,
+        )
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .context("fetching recent quarantine events (sqlite)")?;
+
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+
+    // ==================== Upstream Yank Methods ====================
+
+    async fn cached_versions(
+        &self,
+        kind: AssetKind,
+    ) -> Result<Vec<(String, String, Option<String>)>> {
+        let rows = sqlx::query_as::<_, (String, String, Option<String>)>(
+            r#"
+            SELECT DISTINCT name, version, platform
+            FROM cached_assets
+            WHERE kind = ?1
+            ORDER BY name, version
+            "#,
+        )
+        .bind(kind.as_str())
+        .fetch_all(&self.pool)
+        .await
+        .context("fetching cached versions (sqlite)")?;
+
+        Ok(rows)
+    }
+
+    async fn cached_package_versions(&self, kind: AssetKind, name: &str) -> Result<Vec<String>> {
+        let rows = sqlx::query_scalar::<_, String>(
+            r#"
+            SELECT DISTINCT version
+            FROM cached_assets
+            WHERE kind = ?1 AND name = ?2
+            ORDER BY version
+            "#,
+        )
+        .bind(kind.as_str())
+        .bind(name)
+        .fetch_all(&self.pool)
+        .await
+        .context("fetching cached package versions (sqlite)")?;
+
+        Ok(rows)
+    }
+
+    async fn record_upstream_yank(&self, yank: &UpstreamYank) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO upstream_yanks (ecosystem, name, version, platform, reason, detected_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            ON CONFLICT (ecosystem, name, version, platform) DO NOTHING
+            "#,
+        )
+        .bind(yank.ecosystem.as_str())
+        .bind(&yank.name)
+        .bind(&yank.version)
+        .bind(yank.platform.as_deref().unwrap_or_default())
+        .bind(&yank.reason)
+        .bind(format_timestamp(yank.detected_at))
         .execute(&self.pool)
         .await
-        .context("inserting gem symbol (sqlite)")?;
+        .context("recording upstream yank (sqlite)")?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn get_upstream_yank(&self, key: &AssetKey<'_>) -> Result<Option<UpstreamYank>> {
+        let row = sqlx::query_as::<_, UpstreamYankRow>(
+            r#"
+            SELECT ecosystem, name, version, platform, reason, detected_at,
+                   NULL AS last_accessed
+            FROM upstream_yanks
+            WHERE ecosystem = ?1 AND name = ?2 AND version = ?3 AND platform = ?4
+            "#,
+        )
+        .bind(key.kind.ecosystem().as_str())
+        .bind(key.name)
+        .bind(key.version)
+        .bind(key.platform.unwrap_or_default())
+        .fetch_optional(&self.pool)
+        .await
+        .context("fetching upstream yank (sqlite)")?;
+
+        Ok(row.map(Into::into))
+    }
+
+    async fn recently_downloaded_yanks(
+        &self,
+        since: DateTime<Utc>,
+        limit: u32,
+    ) -> Result<Vec<YankedDownload>> {
+        let rows = sqlx::query_as::<_, UpstreamYankRow>(
+            r#"
+            SELECT y.ecosystem, y.name, y.version, y.platform, y.reason, y.detected_at,
+                   MAX(a.last_accessed) AS last_accessed
+            FROM upstream_yanks y
+            JOIN cached_assets a
+              ON a.name = y.name
+             AND a.version = y.version
+             AND COALESCE(a.platform, '') = y.platform
+             AND CASE a.kind
+                     WHEN 'crate' THEN 'crates'
+                     WHEN 'npm' THEN 'npm'
+                     ELSE 'rubygems'
+                 END = y.ecosystem
+            WHERE a.last_accessed >= ?1
+            GROUP BY y.ecosystem, y.name, y.version, y.platform, y.reason, y.detected_at
+            ORDER BY last_accessed DESC
+            LIMIT ?2
+            "#,
+        )
+        .bind(format_timestamp(since))
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .context("fetching recently downloaded yanks (sqlite)")?;
+
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+
+    async fn upsert_advisory(&self, advisory: &Advisory) -> Result<()> {
+        let aliases_json =
+            serde_json::to_string(&advisory.aliases).context("serializing advisory aliases")?;
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO advisories (
+                id, summary, details, severity, aliases_json, url,
+                published, modified, withdrawn, imported_at
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+            ON CONFLICT(id) DO UPDATE SET
+                summary = excluded.summary,
+                details = excluded.details,
+                severity = excluded.severity,
+                aliases_json = excluded.aliases_json,
+                url = excluded.url,
+                published = excluded.published,
+                modified = excluded.modified,
+                withdrawn = excluded.withdrawn,
+                imported_at = excluded.imported_at
+            "#,
+        )
+        .bind(&advisory.id)
+        .bind(&advisory.summary)
+        .bind(&advisory.details)
+        .bind(advisory.severity.as_str())
+        .bind(aliases_json)
+        .bind(&advisory.url)
+        .bind(advisory.published.map(format_timestamp))
+        .bind(format_timestamp(advisory.modified))
+        .bind(advisory.withdrawn.map(format_timestamp))
+        .bind(format_timestamp(Utc::now()))
+        .execute(&mut *tx)
+        .await
+        .context("upserting advisory (sqlite)")?;
+
+        sqlx::query("DELETE FROM advisory_packages WHERE advisory_id = ?1")
+            .bind(&advisory.id)
+            .execute(&mut *tx)
+            .await
+            .context("clearing advisory packages (sqlite)")?;
+
+        for package in &advisory.affected {
+            let affected_json =
+                serde_json::to_string(package).context("serializing affected package")?;
+            sqlx::query(
+                r#"
+                INSERT OR REPLACE INTO advisory_packages (advisory_id, ecosystem, name, affected_json)
+                VALUES (?1, ?2, ?3, ?4)
+                "#,
+            )
+            .bind(&advisory.id)
+            .bind(package.ecosystem.as_str())
+            .bind(&package.name)
+            .bind(affected_json)
+            .execute(&mut *tx)
+            .await
+            .context("inserting advisory package (sqlite)")?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn advisories_for_package(
+        &self,
+        ecosystem: Ecosystem,
+        name: &str,
+    ) -> Result<Vec<Advisory>> {
+        let rows = sqlx::query_as::<_, AdvisoryRow>(
+            r#"
+            SELECT a.id, a.summary, a.details, a.severity, a.aliases_json, a.url,
+                   a.published, a.modified, a.withdrawn, p.affected_json
+            FROM advisory_packages p
+            JOIN advisories a ON a.id = p.advisory_id
+            WHERE p.ecosystem = ?1 AND p.name = ?2
+            ORDER BY a.modified DESC, a.id
+            "#,
+        )
+        .bind(ecosystem.as_str())
+        .bind(name)
+        .fetch_all(&self.pool)
+        .await
+        .context("fetching advisories (sqlite)")?;
+
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+
+    async fn advisory_count(&self) -> Result<u64> {
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM advisories")
+            .fetch_one(&self.pool)
+            .await
+            .context("counting advisories (sqlite)")?;
+
+        Ok(count as u64)
+    }
+
+    async fn insert_symbols(&self, symbols: &[super::GemSymbolRecord<'_>]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for symbol in symbols {
+            sqlx::query(
+                r#"
+                INSERT OR REPLACE INTO gem_symbols (
+                    gem_name,
+                    gem_version,
+                    gem_platform,
+                    file_path,
+                    symbol_type,
+                    symbol_name,
+                    parent_name,
+                    line_number
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+                "#,
+            )
+            .bind(symbol.gem_name)
+            .bind(symbol.gem_version)
+            .bind(symbol.gem_platform)
+            .bind(symbol.file_path)
+            .bind(symbol.symbol_type)
+            .bind(symbol.symbol_name)
+            .bind(symbol.parent_name)
+            .bind(symbol.line_number)
+            .execute(&mut *tx)
+            .await
+            .context("inserting gem symbol (sqlite)")?;
+        }
+        tx.commit()
+            .await
+            .context("committing gem symbols (sqlite)")?;
 
         Ok(())
     }
@@ -926,4 +1320,289 @@ impl CacheBackendTrait for SqliteCacheBackend {
 
         Ok(())
     }
+
+    async fn search_symbols(
+        &self,
+        query: &str,
+        symbol_type: Option<&str>,
+        limit: u32,
+    ) -> Result<Vec<GemSymbol>> {
+        let rows = sqlx::query_as::<_, GemSymbolRow>(
+            r#"
+            SELECT gem_name, gem_version, gem_platform, file_path, symbol_type,
+                   symbol_name, parent_name, line_number
+            FROM gem_symbols
+            WHERE symbol_name LIKE ?1 ESCAPE '\'
+              AND (?2 IS NULL OR symbol_type = ?2)
+            ORDER BY symbol_name, gem_name, gem_version, file_path
+            LIMIT ?3
+            "#,
+        )
+        .bind(symbol_like_pattern(query))
+        .bind(symbol_type)
+        .bind(i64::from(limit))
+        .fetch_all(&self.pool)
+        .await
+        .context("searching gem symbols (sqlite)")?;
+
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+
+    async fn replace_findings(
+        &self,
+        gem_name: &str,
+        gem_version: &str,
+        gem_platform: Option<&str>,
+        findings: &[super::GemFindingRecord<'_>],
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            r#"
+            DELETE FROM gem_findings
+            WHERE gem_name = ?1
+              AND gem_version = ?2
+              AND (
+                    (?3 IS NULL AND gem_platform IS NULL)
+                    OR
+                    (?3 IS NOT NULL AND gem_platform = ?3)
+                  )
+            "#,
+        )
+        .bind(gem_name)
+        .bind(gem_version)
+        .bind(gem_platform)
+        .execute(&mut *tx)
+        .await
+        .context("clearing gem findings (sqlite)")?;
+
+        for finding in findings {
+            sqlx::query(
+                r#"
+                INSERT INTO gem_findings (
+                    gem_name,
+                    gem_version,
+                    gem_platform,
+                    rule,
+                    file_path,
+                    line_number,
+                    excerpt
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+                "#,
+            )
+            .bind(gem_name)
+            .bind(gem_version)
+            .bind(gem_platform)
+            .bind(finding.rule)
+            .bind(finding.file_path)
+            .bind(finding.line_number)
+            .bind(finding.excerpt)
+            .execute(&mut *tx)
+            .await
+            .context("inserting gem finding (sqlite)")?;
+        }
+        tx.commit()
+            .await
+            .context("committing gem findings (sqlite)")?;
+
+        Ok(())
+    }
+
+    async fn gem_findings(
+        &self,
+        gem_name: &str,
+        gem_version: &str,
+        gem_platform: Option<&str>,
+    ) -> Result<Vec<GemFinding>> {
+        let rows = sqlx::query_as::<_, GemFindingRow>(
+            r#"
+            SELECT gem_name, gem_version, gem_platform, rule, file_path, line_number, excerpt
+            FROM gem_findings
+            WHERE gem_name = ?1
+              AND gem_version = ?2
+              AND (
+                    (?3 IS NULL AND gem_platform IS NULL)
+                    OR
+                    (?3 IS NOT NULL AND gem_platform = ?3)
+                  )
+            ORDER BY file_path, line_number, rule
+            "#,
+        )
+        .bind(gem_name)
+        .bind(gem_version)
+        .bind(gem_platform)
+        .fetch_all(&self.pool)
+        .await
+        .context("loading gem findings (sqlite)")?;
+
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+
+    async fn recent_findings(&self, limit: u32) -> Result<Vec<GemFinding>> {
+        let rows = sqlx::query_as::<_, GemFindingRow>(
+            r#"
+            SELECT gem_name, gem_version, gem_platform, rule, file_path, line_number, excerpt
+            FROM gem_findings
+            ORDER BY id DESC
+            LIMIT ?1
+            "#,
+        )
+        .bind(i64::from(limit))
+        .fetch_all(&self.pool)
+        .await
+        .context("loading recent gem findings (sqlite)")?;
+
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+
+    async fn popular_packages(&self, kind: AssetKind, limit: u32) -> Result<Vec<String>> {
+        sqlx::query_scalar::<_, String>(
+            r#"
+            SELECT name
+            FROM cached_assets
+            WHERE kind = ?1
+            GROUP BY name
+            ORDER BY SUM(download_count) DESC, name
+            LIMIT ?2
+            "#,
+        )
+        .bind(kind.as_str())
+        .bind(i64::from(limit))
+        .fetch_all(&self.pool)
+        .await
+        .context("listing popular packages (sqlite)")
+    }
+
+    async fn record_suspicious_name(&self, flagged: &SuspiciousName) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO suspicious_names (
+                ecosystem, name, technique, similar_to, action, hits, first_seen, last_seen
+            )
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+            ON CONFLICT (ecosystem, name) DO UPDATE SET
+                technique = excluded.technique,
+                similar_to = excluded.similar_to,
+                action = excluded.action,
+                hits = suspicious_names.hits + excluded.hits,
+                last_seen = excluded.last_seen
+            "#,
+        )
+        .bind(flagged.ecosystem.as_str())
+        .bind(&flagged.name)
+        .bind(&flagged.technique)
+        .bind(&flagged.similar_to)
+        .bind(&flagged.action)
+        .bind(flagged.hits.max(1) as i64)
+        .bind(format_timestamp(flagged.first_seen))
+        .bind(format_timestamp(flagged.last_seen))
+        .execute(&self.pool)
+        .await
+        .context("recording suspicious name (sqlite)")?;
+
+        Ok(())
+    }
+
+    async fn suspicious_names(&self, limit: u32) -> Result<Vec<SuspiciousName>> {
+        let rows = sqlx::query_as::<_, SuspiciousNameRow>(
+            r#"
+            SELECT ecosystem, name, technique, similar_to, action, hits, first_seen, last_seen
+            FROM suspicious_names
+            ORDER BY last_seen DESC, name
+            LIMIT ?1
+            "#,
+        )
+        .bind(i64::from(limit))
+        .fetch_all(&self.pool)
+        .await
+        .context("listing suspicious names (sqlite)")?;
+
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+
+    async fn suspicious_name_count(&self) -> Result<u64> {
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM suspicious_names")
+            .fetch_one(&self.pool)
+            .await
+            .context("counting suspicious names (sqlite)")?;
+
+        Ok(count.max(0) as u64)
+    }
+
+    async fn package_licenses(&self) -> Result<Vec<PackageLicense>> {
+        let rows = sqlx::query_as::<_, PackageLicenseRow>(
+            r#"
+            SELECT 'rubygems' AS ecosystem, name, version, platform, licenses AS license
+            FROM gem_metadata
+            UNION ALL
+            SELECT ecosystem, name, version, NULL AS platform, license
+            FROM package_metadata
+            ORDER BY ecosystem, name, version, platform
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("listing package licenses (sqlite)")?;
+
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+
+    async fn policy_rules(&self) -> Result<Vec<StoredPolicyRule>> {
+        let rows = sqlx::query_as::<_, PolicyRuleRow>(
+            r#"
+            SELECT id, ecosystem, package, versions, platform, attributes_json, action, reason,
+                   created_at
+            FROM policy_rules
+            ORDER BY created_at, id
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("listing policy rules (sqlite)")?;
+
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+
+    async fn save_policy_rule(&self, rule: &StoredPolicyRule) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO policy_rules (
+                id, ecosystem, package, versions, platform, attributes_json, action, reason,
+                created_at
+            )
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+            ON CONFLICT (id) DO UPDATE SET
+                ecosystem = excluded.ecosystem,
+                package = excluded.package,
+                versions = excluded.versions,
+                platform = excluded.platform,
+                attributes_json = excluded.attributes_json,
+                action = excluded.action,
+                reason = excluded.reason
+            "#,
+        )
+        .bind(&rule.id)
+        .bind(rule.ecosystem.map(|ecosystem| ecosystem.as_str()))
+        .bind(&rule.package)
+        .bind(&rule.versions)
+        .bind(&rule.platform)
+        .bind(rule.attributes.to_string())
+        .bind(&rule.action)
+        .bind(&rule.reason)
+        .bind(format_timestamp(rule.created_at))
+        .execute(&self.pool)
+        .await
+        .context("saving policy rule (sqlite)")?;
+
+        Ok(())
+    }
+
+    async fn delete_policy_rule(&self, id: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM policy_rules WHERE id = ?1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .context("deleting policy rule (sqlite)")?;
+
+        Ok(result.rows_affected() > 0)
+    }
 }
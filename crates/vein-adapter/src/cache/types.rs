@@ -1,10 +1,16 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum Ecosystem {
+    #[serde(rename = "rubygems")]
     RubyGems,
+    #[serde(rename = "crates")]
     CratesIo,
+    #[serde(rename = "npm")]
     Npm,
 }
 
@@ -18,6 +24,25 @@ impl Ecosystem {
     }
 }
 
+impl std::fmt::Display for Ecosystem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for Ecosystem {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "rubygems" | "gem" | "gems" => Ok(Self::RubyGems),
+            "crates" | "crates.io" | "cargo" => Ok(Self::CratesIo),
+            "npm" => Ok(Self::Npm),
+            _ => Err(()),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum AssetKind {
     Gem,
@@ -90,7 +115,7 @@ pub struct GemDependency {
     pub kind: DependencyKind,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
 pub struct GemMetadata {
     pub name: String,
     pub version: String,
@@ -125,6 +150,52 @@ pub struct GemMetadata {
     pub sha256: String,
     #[serde(default)]
     pub sbom: Option<JsonValue>,
+    /// Generator that wrote `sbom`, e.g. `vein/0.3.0`
+    #[serde(default)]
+    pub sbom_generator: Option<String>,
+}
+
+/// A dependency declared in a crate or npm manifest.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PackageDependency {
+    pub name: String,
+    pub requirement: String,
+    /// Ecosystem-specific kind: `normal`, `dev` or `build` for crates;
+    /// `runtime`, `dev`, `peer` or `optional` for npm
+    pub kind: String,
+    #[serde(default)]
+    pub optional: bool,
+    /// Platform condition, e.g. `cfg(windows)`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+}
+
+/// Manifest metadata for crates and npm packages. Gems use [`GemMetadata`].
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PackageMetadata {
+    pub ecosystem: Ecosystem,
+    pub name: String,
+    pub version: String,
+    pub description: Option<String>,
+    /// SPDX license expression as declared by the package
+    pub license: Option<String>,
+    pub authors: Vec<String>,
+    pub homepage: Option<String>,
+    pub repository: Option<String>,
+    pub dependencies: Vec<PackageDependency>,
+    /// Feature name to the features and dependencies it enables
+    #[serde(default)]
+    pub features: BTreeMap<String, Vec<String>>,
+    /// Ecosystem-specific facts, e.g. `build_script` or `proc_macro` for crates
+    #[serde(default)]
+    pub attributes: JsonValue,
+    pub size_bytes: u64,
+    pub sha256: String,
+    #[serde(default)]
+    pub sbom: Option<JsonValue>,
+    /// Generator that wrote `sbom`, e.g. `vein/0.3.0`
+    #[serde(default)]
+    pub sbom_generator: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -143,3 +214,125 @@ pub struct SbomCoverage {
     pub metadata_rows: u64,
     pub with_sbom: u64,
 }
+
+/// A class, module, method or constant defined by a cached gem.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct GemSymbol {
+    pub gem_name: String,
+    pub gem_version: String,
+    pub gem_platform: Option<String>,
+    /// Path inside the gem's `data.tar.gz`
+    pub file_path: String,
+    /// `class`, `module`, `method`, `singleton_method` or `constant`
+    pub symbol_type: String,
+    /// Fully qualified name, e.g. `ActiveSupport::Concern` or `String#blank?`
+    pub symbol_name: String,
+    /// Enclosing class or module
+    pub parent_name: Option<String>,
+    pub line_number: Option<i32>,
+}
+
+/// A suspicious pattern found in a cached gem's sources.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct GemFinding {
+    pub gem_name: String,
+    pub gem_version: String,
+    pub gem_platform: Option<String>,
+    /// `install_network`, `obfuscated_eval`, `credential_access` or `pipe_to_shell`
+    pub rule: String,
+    /// Path inside the gem's `data.tar.gz`
+    pub file_path: String,
+    pub line_number: Option<i32>,
+    /// The offending line, shortened
+    pub excerpt: String,
+}
+
+/// A cached version that upstream has yanked, deprecated or unpublished.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpstreamYank {
+    pub ecosystem: Ecosystem,
+    pub name: String,
+    pub version: String,
+    pub platform: Option<String>,
+    /// Upstream signal, e.g. "yanked", "unpublished", "deprecated: use v2"
+    pub reason: String,
+    pub detected_at: DateTime<Utc>,
+}
+
+/// A requested package name that looks like a typosquat or dependency
+/// confusion attempt.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SuspiciousName {
+    pub ecosystem: Ecosystem,
+    pub name: String,
+    /// `internal_prefix`, `homoglyph`, `separator` or `edit_distance`
+    pub technique: String,
+    /// The established package the name imitates
+    pub similar_to: Option<String>,
+    /// `warn`, `block` or `quarantine`
+    pub action: String,
+    /// Requests seen for the name
+    pub hits: u64,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+}
+
+/// A package policy rule created in the admin UI.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StoredPolicyRule {
+    pub id: String,
+    /// `None` applies the rule to every ecosystem
+    pub ecosystem: Option<Ecosystem>,
+    /// Package name glob
+    pub package: String,
+    /// Version requirement in the ecosystem's syntax
+    pub versions: Option<String>,
+    /// Gem platform glob
+    pub platform: Option<String>,
+    /// Metadata attributes a version must have, e.g. `{"has_native_extensions": true}`
+    pub attributes: JsonValue,
+    /// `allow`, `deny`, `quarantine` or `require_approval`
+    pub action: String,
+    pub reason: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A yanked version that is still cached and was served recently.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct YankedDownload {
+    pub yank: UpstreamYank,
+    pub last_accessed: String,
+}
+
+/// The declared license of a cached package version.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PackageLicense {
+    pub ecosystem: Ecosystem,
+    pub name: String,
+    pub version: String,
+    /// Gem platform; `None` for crates and npm packages
+    pub platform: Option<String>,
+    /// SPDX expression; a gem's `licenses` list becomes an `OR` expression
+    pub license: Option<String>,
+}
+
+impl PackageLicense {
+    /// A gem may be used under any license it lists.
+    pub fn gem_expression(licenses: &[String]) -> Option<String> {
+        let licenses: Vec<&str> = licenses
+            .iter()
+            .map(|license| license.trim())
+            .filter(|license| !license.is_empty())
+            .collect();
+        match licenses.as_slice() {
+            [] => None,
+            [single] => Some(single.to_string()),
+            many => Some(
+                many.iter()
+                    .map(|license| format!("({license})"))
+                    .collect::<Vec<_>>()
+                    .join(" OR "),
+            ),
+        }
+    }
+}
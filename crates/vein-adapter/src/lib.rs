@@ -10,8 +10,10 @@ mod storage;
 
 // Core types (always available)
 pub use cache::{
-    AssetKey, AssetKind, CacheBackendTrait, CachedAsset, DependencyKind, GemDependency,
-    GemMetadata, GemSymbolRecord, IndexStats, SbomCoverage,
+    AssetKey, AssetKind, CacheBackendTrait, CachedAsset, DependencyKind, Ecosystem, GemDependency,
+    GemFinding, GemFindingRecord, GemMetadata, GemSymbol, GemSymbolRecord, IndexStats,
+    PackageDependency, PackageLicense, PackageMetadata, SbomCoverage, StoredPolicyRule,
+    SuspiciousName, UpstreamYank, YankedDownload,
 };
 
 // Backend type alias - compile-time selection
@@ -23,8 +25,11 @@ pub use cache::PostgresCacheBackend as CacheBackend;
 
 pub use storage::{FileHandle, FilesystemStorage, TempFile};
 
+// Advisory types
+pub use cache::{Advisory, AdvisorySeverity, AffectedPackage, AffectedRange, RangeEvent};
+
 // Quarantine types
 pub use cache::{
-    DelayPolicy, GemVersion, QuarantineInfo, QuarantineStats, VersionStatus,
-    calculate_availability, is_version_available, is_version_downloadable,
+    DelayPolicy, GemVersion, QuarantineAction, QuarantineEvent, QuarantineInfo, QuarantineStats,
+    VersionStatus, calculate_availability, is_version_available, is_version_downloadable,
 };
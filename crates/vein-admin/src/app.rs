@@ -12,7 +12,7 @@ use crate::{
     ruby,
     state::{AdminResources, AdminState},
 };
-use vein::{catalog, config::Config as VeinConfig, db::connect_cache_backend};
+use vein::{catalog, config::Config as VeinConfig, db::connect_cache_backend, notifications};
 use vein_adapter::{CacheBackend, FilesystemStorage};
 
 const TEMPLATE_GLOB: &str = "crates/vein-admin/assets/views/**/*.html";
@@ -39,6 +39,7 @@ async fn bootstrap_vein_runtime(config: &AdminConfig) -> Result<VeinRuntime> {
     let vein_config = load_vein_config(config)?;
     prepare_storage(vein_config.as_ref()).await?;
     let cache = connect_cache(&vein_config).await?;
+    notifications::install(&vein_config.notifications);
 
     Ok(VeinRuntime {
         config: vein_config,
@@ -6,7 +6,10 @@ use tera::{Context, Tera};
 pub mod catalog;
 pub mod dashboard;
 pub mod health;
+pub mod licenses;
+pub mod policy;
 pub mod quarantine;
+pub mod symbols;
 
 /// Render a Tera template with error handling.
 pub fn render(tera: &Tera, template: &str, context: &Context) -> Html<String> {
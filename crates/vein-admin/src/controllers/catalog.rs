@@ -6,8 +6,8 @@ use rama::http::service::web::extract::{Path, Query, State};
 use rama::http::service::web::response::{Html, IntoResponse};
 use rama::http::sse::Event;
 use serde::Deserialize;
-use serde_json::to_string_pretty;
 use tokio::sync::mpsc;
+use vein::sbom::{self, SbomFormat};
 use vein::util::sanitize_filename;
 use vein_adapter::GemMetadata;
 
@@ -17,6 +17,7 @@ use crate::views;
 
 const DEFAULT_PLATFORM: &str = "ruby";
 const PAGE_SIZE: i64 = 100;
+const QUARANTINE_HISTORY_LIMIT: u32 = 25;
 
 #[derive(Debug, Deserialize, Default)]
 pub struct CatalogQuery {
@@ -43,6 +44,8 @@ pub struct GemDetailQuery {
     version: Option<String>,
     #[serde(default)]
     platform: Option<String>,
+    #[serde(default)]
+    format: Option<String>,
 }
 
 struct CatalogDetailSelection {
@@ -160,30 +163,37 @@ pub async fn sbom(
         Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Error").into_response(),
     };
 
-    let Some(sbom) = metadata.sbom.as_ref() else {
-        return (StatusCode::NOT_FOUND, "SBOM not found").into_response();
+    let format = match query.format.as_deref().map(str::parse::<SbomFormat>) {
+        None => SbomFormat::default(),
+        Some(Ok(format)) => format,
+        Some(Err(())) => return (StatusCode::BAD_REQUEST, "Unknown SBOM format").into_response(),
     };
 
-    let json_body = to_string_pretty(sbom).unwrap_or_else(|_| sbom.to_string());
+    let body = match sbom::render_gem(format, &metadata) {
+        Ok(Some(body)) => body,
+        Ok(None) => return (StatusCode::NOT_FOUND, "SBOM not found").into_response(),
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Error").into_response(),
+    };
 
     let platform_slug = &metadata.platform;
     let filename = format!(
-        "{}-{}-{}.sbom.json",
+        "{}-{}-{}{}",
         sanitize_filename(&metadata.name),
         sanitize_filename(&metadata.version),
-        sanitize_filename(platform_slug)
+        sanitize_filename(platform_slug),
+        format.file_suffix()
     );
 
     (
         StatusCode::OK,
         [
-            ("content-type", "application/json; charset=utf-8"),
+            ("content-type", format.content_type()),
             (
                 "content-disposition",
                 &format!("attachment; filename=\"{}\"", filename),
             ),
         ],
-        json_body,
+        body,
     )
         .into_response()
 }
@@ -246,6 +256,28 @@ async fn load_catalog_detail(
         .as_ref()
         .map(|meta| meta.platform.clone())
         .unwrap_or_else(|| selection.requested_platform.clone());
+    let quarantine_history = match selection.selected_version.as_deref() {
+        Some(version) => resources
+            .quarantine_history(name, Some(version), QUARANTINE_HISTORY_LIMIT)
+            .await?
+            .into_iter()
+            .map(views::quarantine::QuarantineEventView::from)
+            .collect(),
+        None => Vec::new(),
+    };
+    let advisories = match selection.selected_version.as_deref() {
+        Some(version) => resources
+            .gem_advisories(name, version)
+            .await?
+            .iter()
+            .map(views::catalog::AdvisoryView::from)
+            .collect(),
+        None => Vec::new(),
+    };
+    let findings = match selection.selected_version.as_deref() {
+        Some(version) => resources.gem_findings(name, version, &platform).await?,
+        None => Vec::new(),
+    };
 
     Ok(views::catalog::GemDetailData {
         name: name.to_string(),
@@ -256,6 +288,9 @@ async fn load_catalog_detail(
         platform,
         platform_query: query.platform.clone(),
         metadata: metadata.as_ref().map(views::catalog::GemMetadataView::from),
+        quarantine_history,
+        advisories,
+        findings,
     })
 }
 
@@ -24,10 +24,19 @@ pub async fn index(
 ) -> impl IntoResponse {
     tracing::info!("Dashboard index requested");
 
-    let data = match load_dashboard_data(&state.resources, query.upstream.is_some()).await {
+    let show_upstream = query.upstream.is_some();
+    let mut data = match load_dashboard_data(&state.resources, show_upstream).await {
         Ok(data) => data,
         Err(err) => return error_html(err),
     };
+    if state.resources.has_upstream() {
+        match state.resources.mirror_health().await {
+            Ok(mirrors) => {
+                data.mirrors = views::dashboard::MirrorRow::from_snapshots(&mirrors, show_upstream);
+            }
+            Err(err) => data.mirrors_error = Some(format!("proxy unreachable: {err}")),
+        }
+    }
 
     match views::dashboard::index(&state.tera, data) {
         Ok(html) => Html(html),
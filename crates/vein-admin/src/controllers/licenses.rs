@@ -0,0 +1,85 @@
+//! License report of cached packages.
+//!
+//! Lists cached packages grouped by declared license and flags the ones the
+//! `[license_policy]` would warn about, block or hold.
+
+use rama::http::service::web::extract::{Query, State};
+use rama::http::service::web::response::IntoResponse;
+use serde::Deserialize;
+use tera::Context;
+use vein::license::{self, NO_LICENSE};
+
+use crate::controllers::render;
+use crate::state::AdminState;
+use crate::utils::error_html;
+use crate::views::licenses::LicensePackageView;
+
+const PACKAGE_LIMIT: usize = 500;
+const STATUSES: [&str; 3] = ["violation", "unknown", "allowed"];
+
+#[derive(Debug, Deserialize, Default)]
+pub struct LicenseQuery {
+    #[serde(default)]
+    license: Option<String>,
+    #[serde(default)]
+    status: Option<String>,
+}
+
+impl LicenseQuery {
+    fn license(&self) -> Option<&str> {
+        self.license
+            .as_deref()
+            .map(str::trim)
+            .filter(|license| !license.is_empty())
+    }
+
+    fn status(&self) -> Option<&str> {
+        self.status
+            .as_deref()
+            .filter(|status| STATUSES.contains(status))
+    }
+}
+
+pub async fn index(
+    State(state): State<AdminState>,
+    Query(query): Query<LicenseQuery>,
+) -> impl IntoResponse {
+    let entries = match state.resources.license_report().await {
+        Ok(entries) => entries,
+        Err(err) => return error_html(err),
+    };
+    let summary = license::summarize(&entries);
+    let violations = entries
+        .iter()
+        .filter(|entry| entry.status == "violation")
+        .count();
+    let total = entries.len();
+
+    let packages: Vec<LicensePackageView> = entries
+        .into_iter()
+        .filter(|entry| {
+            query
+                .license()
+                .is_none_or(|license| entry.license.as_deref().unwrap_or(NO_LICENSE) == license)
+        })
+        .filter(|entry| query.status().is_none_or(|status| entry.status == status))
+        .take(PACKAGE_LIMIT + 1)
+        .map(LicensePackageView::from)
+        .collect();
+    let truncated = packages.len() > PACKAGE_LIMIT;
+    let packages = &packages[..packages.len().min(PACKAGE_LIMIT)];
+
+    let mut context = Context::new();
+    context.insert("current_page", "licenses");
+    context.insert("policy_enabled", &state.resources.license_policy_enabled());
+    context.insert("total", &total);
+    context.insert("violations", &violations);
+    context.insert("summary", &summary);
+    context.insert("selected_license", &query.license().unwrap_or_default());
+    context.insert("selected_status", &query.status());
+    context.insert("statuses", &STATUSES);
+    context.insert("packages", packages);
+    context.insert("truncated", &truncated);
+    context.insert("limit", &PACKAGE_LIMIT);
+    render(&state.tera, "licenses/index.html", &context)
+}
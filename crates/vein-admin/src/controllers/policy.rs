@@ -0,0 +1,143 @@
+//! Package policy rules.
+//!
+//! Lists the rules from `[policy]` in vein.toml and manages the ones stored
+//! in the database. The proxy picks up changes on its next rule refresh.
+
+use chrono::Utc;
+use rama::http::service::web::extract::{Form, Path, State};
+use rama::http::service::web::response::{Html, IntoResponse, Redirect};
+use serde::Deserialize;
+use serde_json::{Map, Value as JsonValue};
+use tera::Context;
+use vein_adapter::{Ecosystem, StoredPolicyRule};
+
+use crate::controllers::render;
+use crate::state::AdminState;
+use crate::utils::error_html;
+use crate::views::policy::PolicyRuleView;
+
+const ECOSYSTEMS: [Ecosystem; 3] = [Ecosystem::RubyGems, Ecosystem::CratesIo, Ecosystem::Npm];
+const ACTIONS: [&str; 4] = ["allow", "deny", "quarantine", "require_approval"];
+
+#[derive(Debug, Deserialize)]
+pub struct RuleForm {
+    id: String,
+    ecosystem: Option<String>,
+    package: Option<String>,
+    versions: Option<String>,
+    platform: Option<String>,
+    attributes: Option<String>,
+    action: String,
+    reason: Option<String>,
+}
+
+fn non_empty(value: Option<&str>) -> Option<String> {
+    value
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+}
+
+impl RuleForm {
+    fn ecosystem(&self) -> Option<Ecosystem> {
+        ECOSYSTEMS
+            .into_iter()
+            .find(|ecosystem| Some(ecosystem.as_str()) == self.ecosystem.as_deref())
+    }
+
+    /// Parses `key=value` pairs separated by commas; values are read as JSON
+    /// when they parse (`true`, `3`) and as strings otherwise.
+    fn attributes(&self) -> Result<JsonValue, String> {
+        let mut attributes = Map::new();
+        for pair in self.attributes.as_deref().unwrap_or_default().split(',') {
+            let pair = pair.trim();
+            if pair.is_empty() {
+                continue;
+            }
+            let Some((key, value)) = pair.split_once('=') else {
+                return Err(format!("attribute '{pair}' is not a key=value pair"));
+            };
+            let value = value.trim();
+            let value = serde_json::from_str(value)
+                .unwrap_or_else(|_| JsonValue::String(value.to_string()));
+            attributes.insert(key.trim().to_string(), value);
+        }
+        Ok(JsonValue::Object(attributes))
+    }
+
+    fn into_rule(self) -> Result<StoredPolicyRule, String> {
+        Ok(StoredPolicyRule {
+            id: self.id.trim().to_string(),
+            ecosystem: self.ecosystem(),
+            package: non_empty(self.package.as_deref()).unwrap_or_else(|| "*".to_string()),
+            versions: non_empty(self.versions.as_deref()),
+            platform: non_empty(self.platform.as_deref()),
+            attributes: self.attributes()?,
+            action: self.action.clone(),
+            reason: non_empty(self.reason.as_deref()),
+            created_at: Utc::now(),
+        })
+    }
+}
+
+pub async fn index(State(state): State<AdminState>) -> impl IntoResponse {
+    render_index(&state, None).await
+}
+
+pub async fn create(
+    State(state): State<AdminState>,
+    Form(form): Form<RuleForm>,
+) -> impl IntoResponse {
+    let rule = match form.into_rule() {
+        Ok(rule) => rule,
+        Err(err) => return render_index(&state, Some(err)).await.into_response(),
+    };
+    match state.resources.save_policy_rule(&rule).await {
+        Ok(()) => {
+            tracing::info!(
+                rule = %rule.id,
+                action = %rule.action,
+                package = %rule.package,
+                "Policy rule saved"
+            );
+            Redirect::to("/policy").into_response()
+        }
+        Err(err) => render_index(&state, Some(format!("{err:#}")))
+            .await
+            .into_response(),
+    }
+}
+
+pub async fn delete(State(state): State<AdminState>, Path(id): Path<String>) -> impl IntoResponse {
+    match state.resources.delete_policy_rule(&id).await {
+        Ok(true) => tracing::info!(rule = %id, "Policy rule deleted"),
+        Ok(false) => {}
+        Err(e) => tracing::error!(error = %e, rule = %id, "Failed to delete policy rule"),
+    }
+    Redirect::to("/policy")
+}
+
+async fn render_index(state: &AdminState, error: Option<String>) -> Html<String> {
+    let stored = match state.resources.stored_policy_rules().await {
+        Ok(rules) => rules,
+        Err(err) => return error_html(err),
+    };
+    let configured: Vec<PolicyRuleView> = state
+        .resources
+        .configured_policy_rules()
+        .iter()
+        .map(PolicyRuleView::from)
+        .collect();
+    let stored: Vec<PolicyRuleView> = stored.into_iter().map(PolicyRuleView::from).collect();
+    let ecosystems: Vec<&str> = ECOSYSTEMS.iter().map(Ecosystem::as_str).collect();
+
+    let mut context = Context::new();
+    context.insert("current_page", "policy");
+    context.insert("policy_enabled", &state.resources.policy_enabled());
+    context.insert("configured", &configured);
+    context.insert("stored", &stored);
+    context.insert("ecosystems", &ecosystems);
+    context.insert("actions", &ACTIONS);
+    context.insert("error", &error);
+    render(&state.tera, "policy/index.html", &context)
+}
@@ -2,30 +2,42 @@
 //!
 //! Provides admin UI and API for managing quarantined gem versions:
 //! - View quarantine statistics and pending versions
-//! - Approve versions for early release
+//! - Approve versions for early release (optionally by several approvers)
 //! - Block malicious versions
+//! - Review the audit trail of past decisions
+//! - See which quarantined dependencies hold a version back
+
+use std::convert::Infallible;
 
 use chrono::Utc;
-use rama::http::service::web::extract::{Form, Path, Query, State};
-use rama::http::service::web::response::{Html, IntoResponse, Json, Redirect};
+use rama::http::request::Parts;
+use rama::http::service::web::extract::{Form, FromPartsStateRefPair, Path, Query, State};
+use rama::http::service::web::response::{IntoResponse, Json, Redirect};
 use serde::{Deserialize, Serialize};
 use tera::Context;
+use vein::quarantine::{ApprovalOutcome, Approver, dependencies::Installability, risk};
 use vein_adapter::{GemVersion, QuarantineStats as AdapterQuarantineStats, VersionStatus};
 
 use crate::controllers::render;
 use crate::state::{AdminResources, AdminState};
 use crate::utils::error_html;
+use crate::views::quarantine::{BlockedByView, QuarantineEventView, SuspiciousNameView};
 
 const DEFAULT_PENDING_LIMIT: u32 = 50;
 const MAX_PENDING_LIMIT: u32 = 100;
 const QUARANTINE_DISABLED: &str = "Quarantine feature is disabled";
 const DEFAULT_APPROVAL_REASON: &str = "admin approval";
 const DEFAULT_BLOCK_REASON: &str = "admin blocked";
+const DEFAULT_ACTOR: &str = "admin";
+const HISTORY_LIMIT: u32 = 25;
+const FINDINGS_LIMIT: u32 = 25;
+const SUSPICIOUS_NAMES_LIMIT: u32 = 25;
 
 #[derive(Debug, Deserialize)]
 pub struct ActionForm {
     reason: Option<String>,
     platform: Option<String>,
+    actor: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -50,6 +62,43 @@ struct PendingGem {
     platform_raw: String,
     status: String,
     hours_remaining: i64,
+    installable_hours: i64,
+    awaiting_approval: bool,
+    reason: String,
+    blocked_by: Vec<BlockedByView>,
+}
+
+/// User named by the configured `approver_header`, if the request has one.
+#[derive(Debug)]
+pub struct SignedInUser(Option<String>);
+
+impl FromPartsStateRefPair<AdminState> for SignedInUser {
+    type Rejection = Infallible;
+
+    async fn from_parts_state_ref_pair(
+        parts: &Parts,
+        state: &AdminState,
+    ) -> Result<Self, Self::Rejection> {
+        let user = state
+            .resources
+            .approver_header()
+            .and_then(|header| parts.headers.get(header))
+            .and_then(|value| value.to_str().ok())
+            .map(str::trim)
+            .filter(|user| !user.is_empty())
+            .map(str::to_string);
+        Ok(Self(user))
+    }
+}
+
+impl SignedInUser {
+    /// The signed-in user, or the name typed into the form.
+    fn approver<'a>(&'a self, form: &'a ActionForm) -> Approver<'a> {
+        match &self.0 {
+            Some(user) => Approver::Verified(user),
+            None => Approver::Claimed(form.actor()),
+        }
+    }
 }
 
 impl ActionForm {
@@ -60,6 +109,14 @@ impl ActionForm {
     fn reason_or<'a>(&'a self, fallback: &'a str) -> &'a str {
         self.reason.as_deref().unwrap_or(fallback)
     }
+
+    fn actor(&self) -> &str {
+        self.actor
+            .as_deref()
+            .map(str::trim)
+            .filter(|actor| !actor.is_empty())
+            .unwrap_or(DEFAULT_ACTOR)
+    }
 }
 
 impl PendingQuery {
@@ -85,9 +142,12 @@ impl From<AdapterQuarantineStats> for QuarantineStats {
     }
 }
 
-impl From<GemVersion> for PendingGem {
-    fn from(gem: GemVersion) -> Self {
-        let time_remaining = gem.available_after.signed_duration_since(Utc::now());
+impl PendingGem {
+    fn new(gem: GemVersion, installability: Installability) -> Self {
+        let now = Utc::now();
+        let time_remaining = gem.available_after.signed_duration_since(now);
+        let until_installable = installability.installable_at.signed_duration_since(now);
+        let awaiting_approval = risk::awaits_manual_approval(&gem);
         Self {
             name: gem.name,
             version: gem.version,
@@ -95,13 +155,29 @@ impl From<GemVersion> for PendingGem {
             platform_raw: gem.platform.unwrap_or_default(),
             status: status_label(gem.status).to_string(),
             hours_remaining: time_remaining.num_hours().max(0),
+            installable_hours: until_installable.num_hours().max(0),
+            awaiting_approval,
+            reason: gem.status_reason.unwrap_or_default(),
+            blocked_by: installability
+                .blocked_by
+                .into_iter()
+                .map(BlockedByView::from)
+                .collect(),
         }
     }
 }
 
 pub async fn index(State(state): State<AdminState>) -> impl IntoResponse {
+    let suspicious_names = match load_suspicious_names(&state.resources).await {
+        Ok(names) => names,
+        Err(err) => return error_html(err),
+    };
     if !state.resources.quarantine_enabled() {
-        return render_disabled(&state.tera);
+        // Typosquat detection works without delays, so keep its report visible
+        let mut context = quarantine_context();
+        context.insert("typosquat_enabled", &state.resources.typosquat_enabled());
+        context.insert("suspicious_names", &suspicious_names);
+        return render(&state.tera, "quarantine/disabled.html", &context);
     }
 
     let stats = match load_quarantine_stats(&state.resources).await {
@@ -112,10 +188,25 @@ pub async fn index(State(state): State<AdminState>) -> impl IntoResponse {
         Ok(pending) => pending,
         Err(err) => return error_html(err),
     };
+    let history = match load_recent_history(&state.resources).await {
+        Ok(history) => history,
+        Err(err) => return error_html(err),
+    };
+    let findings = match state.resources.recent_findings(FINDINGS_LIMIT).await {
+        Ok(findings) => findings,
+        Err(err) => return error_html(err),
+    };
 
     let mut context = quarantine_context();
     context.insert("stats", &stats);
     context.insert("pending", &pending);
+    context.insert("history", &history);
+    context.insert("findings", &findings);
+    context.insert("typosquat_enabled", &state.resources.typosquat_enabled());
+    context.insert("suspicious_names", &suspicious_names);
+    context.insert("required_approvers", &state.resources.required_approvers());
+    // Behind an authenticating proxy the signed-in user is the actor
+    context.insert("ask_name", &state.resources.approver_header().is_none());
     render(&state.tera, "quarantine/index.html", &context)
 }
 
@@ -146,14 +237,19 @@ pub async fn api_pending(
         return Json(disabled_payload());
     }
 
-    match state
+    let pending = match state
         .resources
         .quarantine_pending(query.limit(), query.offset())
         .await
     {
-        Ok(pending) => Json(serde_json::json!({
+        Ok(pending) => pending,
+        Err(e) => return Json(error_payload(e)),
+    };
+
+    match state.resources.quarantine_installability(&pending).await {
+        Ok(installability) => Json(serde_json::json!({
             "enabled": true,
-            "versions": pending_payload(pending),
+            "versions": pending_payload(pending, installability),
         })),
         Err(e) => Json(error_payload(e)),
     }
@@ -161,6 +257,7 @@ pub async fn api_pending(
 
 pub async fn approve(
     State(state): State<AdminState>,
+    user: SignedInUser,
     Path((gem, version)): Path<(String, String)>,
     Form(form): Form<ActionForm>,
 ) -> impl IntoResponse {
@@ -170,14 +267,35 @@ pub async fn approve(
 
     let platform = form.platform();
     let reason = form.reason_or(DEFAULT_APPROVAL_REASON);
+    let approver = user.approver(&form);
+    let actor = approver.name();
 
     match state
         .resources
-        .approve_version(&gem, &version, platform, reason)
+        .approve_version(&gem, &version, platform, approver, reason)
         .await
     {
-        Ok(()) => {
-            tracing::info!(gem = %gem, version = %version, reason = %reason, "Version approved");
+        Ok(ApprovalOutcome::Released { approvers }) => {
+            tracing::info!(
+                gem = %gem,
+                version = %version,
+                reason = %reason,
+                approvers = %approvers.join(", "),
+                "Version approved"
+            );
+        }
+        Ok(ApprovalOutcome::Pending {
+            approvers,
+            required,
+        }) => {
+            tracing::info!(
+                gem = %gem,
+                version = %version,
+                actor = %actor,
+                approvals = approvers.len(),
+                required,
+                "Approval recorded, awaiting more approvers"
+            );
         }
         Err(e) => {
             tracing::error!(error = %e, gem = %gem, version = %version, "Failed to approve version");
@@ -189,6 +307,7 @@ pub async fn approve(
 
 pub async fn block(
     State(state): State<AdminState>,
+    user: SignedInUser,
     Path((gem, version)): Path<(String, String)>,
     Form(form): Form<ActionForm>,
 ) -> impl IntoResponse {
@@ -198,14 +317,21 @@ pub async fn block(
 
     let platform = form.platform();
     let reason = form.reason_or(DEFAULT_BLOCK_REASON);
+    let actor = user.approver(&form).name();
 
     match state
         .resources
-        .block_version(&gem, &version, platform, reason)
+        .block_version(&gem, &version, platform, actor, reason)
         .await
     {
         Ok(()) => {
-            tracing::warn!(gem = %gem, version = %version, reason = %reason, "Version blocked");
+            tracing::warn!(
+                gem = %gem,
+                version = %version,
+                actor = %actor,
+                reason = %reason,
+                "Version blocked"
+            );
         }
         Err(e) => {
             tracing::error!(error = %e, gem = %gem, version = %version, "Failed to block version");
@@ -224,11 +350,23 @@ async fn load_pending_versions(
     limit: u32,
     offset: u32,
 ) -> anyhow::Result<Vec<PendingGem>> {
+    let pending = resources.quarantine_pending(limit, offset).await?;
+    let installability = resources.quarantine_installability(&pending).await?;
+    Ok(pending
+        .into_iter()
+        .zip(installability)
+        .map(|(gem, installability)| PendingGem::new(gem, installability))
+        .collect())
+}
+
+async fn load_recent_history(
+    resources: &AdminResources,
+) -> anyhow::Result<Vec<QuarantineEventView>> {
     Ok(resources
-        .quarantine_pending(limit, offset)
+        .recent_quarantine_events(HISTORY_LIMIT)
         .await?
         .into_iter()
-        .map(PendingGem::from)
+        .map(QuarantineEventView::from)
         .collect())
 }
 
@@ -238,8 +376,15 @@ fn quarantine_context() -> Context {
     context
 }
 
-fn render_disabled(tera: &tera::Tera) -> Html<String> {
-    render(tera, "quarantine/disabled.html", &quarantine_context())
+/// Suspicious names for the quarantine page; empty while detection is off.
+async fn load_suspicious_names(
+    resources: &AdminResources,
+) -> anyhow::Result<Vec<SuspiciousNameView>> {
+    if !resources.typosquat_enabled() {
+        return Ok(Vec::new());
+    }
+    let names = resources.suspicious_names(SUSPICIOUS_NAMES_LIMIT).await?;
+    Ok(names.into_iter().map(SuspiciousNameView::from).collect())
 }
 
 fn disabled_payload() -> serde_json::Value {
@@ -256,10 +401,15 @@ fn error_payload(err: impl std::fmt::Display) -> serde_json::Value {
     })
 }
 
-fn pending_payload(pending: Vec<GemVersion>) -> Vec<serde_json::Value> {
+fn pending_payload(
+    pending: Vec<GemVersion>,
+    installability: Vec<Installability>,
+) -> Vec<serde_json::Value> {
     pending
         .into_iter()
-        .map(|version| {
+        .zip(installability)
+        .map(|(version, installability)| {
+            let awaiting_approval = risk::awaits_manual_approval(&version);
             serde_json::json!({
                 "name": version.name,
                 "version": version.version,
@@ -267,6 +417,10 @@ fn pending_payload(pending: Vec<GemVersion>) -> Vec<serde_json::Value> {
                 "status": format!("{:?}", version.status),
                 "published_at": version.published_at.to_rfc3339(),
                 "available_after": version.available_after.to_rfc3339(),
+                "awaiting_approval": awaiting_approval,
+                "reason": version.status_reason,
+                "installable_at": installability.installable_at.to_rfc3339(),
+                "blocked_by": installability.blocked_by,
             })
         })
         .collect()
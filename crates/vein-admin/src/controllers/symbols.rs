@@ -0,0 +1,63 @@
+//! Ruby symbol search across cached gems.
+//!
+//! Answers "which cached gems define `ActiveSupport::Concern` or reopen
+//! `String#blank?`" during incident response and license audits.
+
+use rama::http::service::web::extract::{Query, State};
+use rama::http::service::web::response::IntoResponse;
+use serde::Deserialize;
+use tera::Context;
+
+use crate::controllers::render;
+use crate::state::AdminState;
+use crate::utils::error_html;
+
+const SYMBOL_LIMIT: u32 = 200;
+const SYMBOL_TYPES: [&str; 5] = ["class", "module", "method", "singleton_method", "constant"];
+
+#[derive(Debug, Deserialize, Default)]
+pub struct SymbolQuery {
+    #[serde(default)]
+    q: Option<String>,
+    #[serde(default)]
+    r#type: Option<String>,
+}
+
+impl SymbolQuery {
+    fn term(&self) -> Option<&str> {
+        self.q.as_deref().map(str::trim).filter(|q| !q.is_empty())
+    }
+
+    fn symbol_type(&self) -> Option<&str> {
+        self.r#type
+            .as_deref()
+            .filter(|kind| SYMBOL_TYPES.contains(kind))
+    }
+}
+
+pub async fn index(
+    State(state): State<AdminState>,
+    Query(query): Query<SymbolQuery>,
+) -> impl IntoResponse {
+    let symbols = match query.term() {
+        Some(term) => match state
+            .resources
+            .search_symbols(term, query.symbol_type(), SYMBOL_LIMIT)
+            .await
+        {
+            Ok(symbols) => symbols,
+            Err(err) => return error_html(err),
+        },
+        None => Vec::new(),
+    };
+
+    let mut context = Context::new();
+    context.insert("current_page", "symbols");
+    context.insert("query", &query.term().unwrap_or_default());
+    context.insert("selected_type", &query.symbol_type());
+    context.insert("symbol_types", &SYMBOL_TYPES);
+    context.insert("truncated", &(symbols.len() as u32 >= SYMBOL_LIMIT));
+    context.insert("limit", &SYMBOL_LIMIT);
+    context.insert("symbols", &symbols);
+    render(&state.tera, "symbols/index.html", &context)
+}
@@ -42,6 +42,14 @@ pub fn build(state: AdminState) -> Router<AdminState> {
             "/quarantine/{gem}/{version}/block",
             controllers::quarantine::block,
         )
+        // Licenses
+        .with_get("/licenses", controllers::licenses::index)
+        // Policy rules
+        .with_get("/policy", controllers::policy::index)
+        .with_post("/policy/rules", controllers::policy::create)
+        .with_post("/policy/rules/{id}/delete", controllers::policy::delete)
+        // Symbol index
+        .with_get("/symbols", controllers::symbols::index)
         // Health
         .with_get("/up", controllers::health::up)
         .with_get("/debug", controllers::health::debug)
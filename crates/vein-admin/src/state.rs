@@ -2,13 +2,26 @@
 
 use std::{path::PathBuf, sync::Arc};
 
-use anyhow::Result;
-use chrono::{DateTime, Utc};
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Duration, Utc};
+use rama::http::{HeaderMap, body::util::BodyExt};
+use serde::Deserialize;
 use tera::Tera;
-use vein::config::Config as VeinConfig;
+use vein::{
+    advisories,
+    config::{Config as VeinConfig, PolicyRule},
+    license::{self, LicenseReportEntry},
+    policy,
+    quarantine::{
+        self, ApprovalOutcome, Approver,
+        dependencies::{DependencyResolver, Installability},
+    },
+    upstream::{MirrorSnapshot, simple_get},
+};
 use vein_adapter::{
-    CacheBackend, CacheBackendTrait, GemMetadata, GemVersion, IndexStats, QuarantineStats,
-    SbomCoverage, VersionStatus,
+    Advisory, CacheBackend, CacheBackendTrait, Ecosystem, GemFinding, GemMetadata, GemSymbol,
+    GemVersion, IndexStats, QuarantineEvent, QuarantineStats, SbomCoverage, StoredPolicyRule,
+    SuspiciousName, YankedDownload,
 };
 
 use crate::ruby::RubyStatus;
@@ -51,6 +64,12 @@ impl AdminResources {
         let catalog_total = self.cache.catalog_total().await?;
 
         let upstream = self.config.upstream.as_ref().map(|up| up.url.to_string());
+        let recent_yanks = if self.config.yank_sync.enabled {
+            let since = Utc::now() - Duration::days(i64::from(self.config.yank_sync.recent_days));
+            self.cache.recently_downloaded_yanks(since, 10).await?
+        } else {
+            Vec::new()
+        };
 
         Ok(DashboardSnapshot {
             generated_at: Utc::now(),
@@ -64,9 +83,38 @@ impl AdminResources {
             catalog_total,
             ruby_status: self.ruby_status.clone(),
             sbom: self.cache.sbom_coverage().await?,
+            recent_yanks,
         })
     }
 
+    pub fn has_upstream(&self) -> bool {
+        self.config.upstream.is_some()
+    }
+
+    /// Per-mirror upstream health as reported by the running proxy's `/readyz`.
+    pub async fn mirror_health(&self) -> Result<Vec<MirrorSnapshot>> {
+        #[derive(Deserialize)]
+        struct Health {
+            #[serde(default)]
+            mirrors: Vec<MirrorSnapshot>,
+        }
+
+        let host = match self.config.server.host.as_str() {
+            "0.0.0.0" | "::" | "[::]" => "127.0.0.1",
+            host => host,
+        };
+        let url = format!("http://{host}:{}/readyz", self.config.server.port);
+        let response = tokio::time::timeout(
+            std::time::Duration::from_secs(2),
+            simple_get(&url, &HeaderMap::new(), Some("application/json")),
+        )
+        .await
+        .context("proxy health check timed out")??;
+        let body = response.into_body().collect().await?.to_bytes();
+        let health: Health = serde_json::from_slice(&body).context("parsing proxy health")?;
+        Ok(health.mirrors)
+    }
+
     pub async fn catalog_total(&self) -> Result<u64> {
         self.cache.catalog_total().await
     }
@@ -115,7 +163,88 @@ impl AdminResources {
         version: &str,
         platform: Option<&str>,
     ) -> Result<Option<GemMetadata>> {
-        self.cache.gem_metadata(name, version, platform).await
+        vein::sbom::current_gem_metadata(&self.cache, name, version, platform).await
+    }
+
+    /// Ruby symbols of cached gems matching `query` (`*` is a wildcard).
+    pub async fn search_symbols(
+        &self,
+        query: &str,
+        symbol_type: Option<&str>,
+        limit: u32,
+    ) -> Result<Vec<GemSymbol>> {
+        self.cache.search_symbols(query, symbol_type, limit).await
+    }
+
+    /// Active advisories affecting a cached gem version.
+    pub async fn gem_advisories(&self, name: &str, version: &str) -> Result<Vec<Advisory>> {
+        advisories::advisories_for(&self.cache, Ecosystem::RubyGems, name, Some(version)).await
+    }
+
+    /// Suspicious-content findings of a cached gem version.
+    pub async fn gem_findings(
+        &self,
+        name: &str,
+        version: &str,
+        platform: &str,
+    ) -> Result<Vec<GemFinding>> {
+        self.cache.gem_findings(name, version, Some(platform)).await
+    }
+
+    /// Most recent suspicious-content findings across cached gems.
+    pub async fn recent_findings(&self, limit: u32) -> Result<Vec<GemFinding>> {
+        self.cache.recent_findings(limit).await
+    }
+
+    /// Whether typosquat detection is enabled.
+    pub fn typosquat_enabled(&self) -> bool {
+        self.config.typosquat.enabled
+    }
+
+    /// Most recently requested names that look like typosquats.
+    pub async fn suspicious_names(&self, limit: u32) -> Result<Vec<SuspiciousName>> {
+        self.cache.suspicious_names(limit).await
+    }
+
+    /// Whether the license policy is enabled.
+    pub fn license_policy_enabled(&self) -> bool {
+        self.config.license_policy.enabled
+    }
+
+    /// Every cached package version with its license and policy verdict.
+    pub async fn license_report(&self) -> Result<Vec<LicenseReportEntry>> {
+        license::license_report(&self.config.license_policy, &self.cache).await
+    }
+
+    // Policy rule methods
+    pub fn policy_enabled(&self) -> bool {
+        self.config.policy.enabled
+    }
+
+    /// Rules from `[policy]` in vein.toml, which the admin UI cannot edit.
+    pub fn configured_policy_rules(&self) -> &[PolicyRule] {
+        &self.config.policy.rules
+    }
+
+    pub async fn stored_policy_rules(&self) -> Result<Vec<StoredPolicyRule>> {
+        self.cache.policy_rules().await
+    }
+
+    /// Validates and stores a rule; ids of configured rules are reserved.
+    pub async fn save_policy_rule(&self, rule: &StoredPolicyRule) -> Result<()> {
+        if self
+            .configured_policy_rules()
+            .iter()
+            .any(|configured| configured.id == rule.id)
+        {
+            bail!("rule id '{}' is already used in vein.toml", rule.id);
+        }
+        policy::rule_from_stored(rule)?;
+        self.cache.save_policy_rule(rule).await
+    }
+
+    pub async fn delete_policy_rule(&self, id: &str) -> Result<bool> {
+        self.cache.delete_policy_rule(id).await
     }
 
     // Quarantine methods
@@ -131,22 +260,37 @@ impl AdminResources {
         self.cache.get_all_quarantined(limit, offset).await
     }
 
+    /// Computes when each version becomes installable, given its dependencies.
+    pub async fn quarantine_installability(
+        &self,
+        versions: &[GemVersion],
+    ) -> Result<Vec<Installability>> {
+        let mut resolver = DependencyResolver::new(&self.cache);
+        let mut installability = Vec::with_capacity(versions.len());
+        for version in versions {
+            installability.push(resolver.installability(version).await?);
+        }
+        Ok(installability)
+    }
+
     pub async fn approve_version(
         &self,
         name: &str,
         version: &str,
         platform: Option<&str>,
+        approver: Approver<'_>,
         reason: &str,
-    ) -> Result<()> {
-        self.cache
-            .update_version_status(
-                name,
-                version,
-                platform,
-                VersionStatus::Pinned,
-                Some(format!("approved: {}", reason)),
-            )
-            .await
+    ) -> Result<ApprovalOutcome> {
+        quarantine::approve_version(
+            &self.cache,
+            &self.config.delay_policy,
+            name,
+            version,
+            platform,
+            approver,
+            reason,
+        )
+        .await
     }
 
     pub async fn block_version(
@@ -154,17 +298,32 @@ impl AdminResources {
         name: &str,
         version: &str,
         platform: Option<&str>,
+        actor: &str,
         reason: &str,
     ) -> Result<()> {
-        self.cache
-            .update_version_status(
-                name,
-                version,
-                platform,
-                VersionStatus::Yanked,
-                Some(format!("blocked: {}", reason)),
-            )
-            .await
+        quarantine::block_version(&self.cache, name, version, platform, actor, reason).await
+    }
+
+    pub fn required_approvers(&self) -> u32 {
+        self.config.delay_policy.required_approvers.max(1)
+    }
+
+    /// Header naming the signed-in user, set by an authenticating proxy.
+    pub fn approver_header(&self) -> Option<&str> {
+        self.config.delay_policy.approver_header.as_deref()
+    }
+
+    pub async fn quarantine_history(
+        &self,
+        name: &str,
+        version: Option<&str>,
+        limit: u32,
+    ) -> Result<Vec<QuarantineEvent>> {
+        self.cache.quarantine_events(name, version, limit).await
+    }
+
+    pub async fn recent_quarantine_events(&self, limit: u32) -> Result<Vec<QuarantineEvent>> {
+        self.cache.recent_quarantine_events(limit).await
     }
 }
 
@@ -180,4 +339,5 @@ pub struct DashboardSnapshot {
     pub catalog_total: u64,
     pub ruby_status: Arc<RubyStatus>,
     pub sbom: SbomCoverage,
+    pub recent_yanks: Vec<YankedDownload>,
 }
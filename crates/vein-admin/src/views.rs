@@ -1,2 +1,5 @@
 pub mod catalog;
 pub mod dashboard;
+pub mod licenses;
+pub mod policy;
+pub mod quarantine;
@@ -3,7 +3,9 @@
 use serde::Serialize;
 use tera::{Context, Tera};
 use vein::util::format_bytes;
-use vein_adapter::{DependencyKind, GemMetadata};
+use vein_adapter::{Advisory, AdvisorySeverity, DependencyKind, GemFinding, GemMetadata};
+
+use crate::views::quarantine::QuarantineEventView;
 
 #[derive(Debug, Serialize)]
 pub struct CatalogListData {
@@ -28,6 +30,9 @@ pub struct GemDetailData {
     pub platform: String,
     pub platform_query: Option<String>,
     pub metadata: Option<GemMetadataView>,
+    pub quarantine_history: Vec<QuarantineEventView>,
+    pub advisories: Vec<AdvisoryView>,
+    pub findings: Vec<GemFinding>,
 }
 
 #[derive(Debug, Serialize)]
@@ -64,6 +69,42 @@ pub struct GemMetadataView {
     pub purl: String,
 }
 
+/// Advisory badge shown on the catalog detail page.
+#[derive(Debug, Serialize)]
+pub struct AdvisoryView {
+    pub id: String,
+    pub summary: Option<String>,
+    pub severity: String,
+    /// Pill modifier: `danger`, `warning` or empty.
+    pub tone: &'static str,
+    pub url: Option<String>,
+    pub aliases: Vec<String>,
+    pub patched_versions: Vec<String>,
+}
+
+impl From<&Advisory> for AdvisoryView {
+    fn from(advisory: &Advisory) -> Self {
+        Self {
+            id: advisory.id.clone(),
+            summary: advisory.summary.clone(),
+            severity: advisory.severity.to_string(),
+            tone: match advisory.severity {
+                AdvisorySeverity::Critical | AdvisorySeverity::High => "danger",
+                AdvisorySeverity::Moderate => "warning",
+                AdvisorySeverity::Low | AdvisorySeverity::Unknown => "",
+            },
+            url: advisory.url.clone(),
+            aliases: advisory.aliases.clone(),
+            patched_versions: advisory
+                .affected
+                .iter()
+                .flat_map(|package| package.fixed_versions())
+                .map(str::to_string)
+                .collect(),
+        }
+    }
+}
+
 #[derive(Debug, Serialize)]
 pub struct DependencyView {
     pub name: String,
@@ -95,6 +136,9 @@ impl GemDetailData {
         context.insert("platform", &self.platform);
         context.insert("platform_query", &self.platform_query);
         context.insert("metadata", &self.metadata);
+        context.insert("quarantine_history", &self.quarantine_history);
+        context.insert("advisories", &self.advisories);
+        context.insert("findings", &self.findings);
         context
     }
 }
@@ -3,7 +3,7 @@
 use chrono::Local;
 use serde::Serialize;
 use tera::{Context, Tera};
-use vein::util::format_bytes;
+use vein::{upstream::MirrorSnapshot, util::format_bytes};
 
 use crate::state::DashboardSnapshot;
 
@@ -29,6 +29,63 @@ pub struct DashboardData {
     pub last_accessed: String,
     pub endpoint: String,
     pub workers: u64,
+    pub recent_yanks: Vec<YankedRow>,
+    pub mirrors: Vec<MirrorRow>,
+    /// Set when the proxy's `/readyz` could not be read.
+    pub mirrors_error: Option<String>,
+}
+
+/// Health of one upstream mirror, as reported by the proxy.
+#[derive(Debug, Serialize)]
+pub struct MirrorRow {
+    pub label: String,
+    pub state: String,
+    pub healthy: bool,
+    pub latency: String,
+    pub error_rate: String,
+    pub requests: u64,
+    pub failures: u64,
+    pub hedged: u64,
+    pub last_error: String,
+}
+
+impl MirrorRow {
+    /// Mirror URLs follow the upstream visibility toggle.
+    pub fn from_snapshots(mirrors: &[MirrorSnapshot], show_upstream: bool) -> Vec<Self> {
+        mirrors
+            .iter()
+            .enumerate()
+            .map(|(position, mirror)| Self {
+                label: if show_upstream {
+                    mirror.url.clone()
+                } else {
+                    format!("mirror {}", position + 1)
+                },
+                state: mirror.state.clone(),
+                healthy: mirror.healthy,
+                latency: mirror
+                    .latency_ms
+                    .map(|ms| format!("{ms:.0} ms"))
+                    .unwrap_or_else(|| "—".to_string()),
+                error_rate: format!("{:.1}%", mirror.error_rate * 100.0),
+                requests: mirror.requests,
+                failures: mirror.failures,
+                hedged: mirror.hedged,
+                last_error: mirror.last_error.clone().unwrap_or_default(),
+            })
+            .collect()
+    }
+}
+
+/// A cached version that upstream has yanked but clients still download.
+#[derive(Debug, Serialize)]
+pub struct YankedRow {
+    pub ecosystem: String,
+    pub name: String,
+    pub version: String,
+    pub platform: Option<String>,
+    pub reason: String,
+    pub last_accessed: String,
 }
 
 impl DashboardData {
@@ -61,6 +118,20 @@ impl DashboardData {
                 .to_string(),
             endpoint: format!("{}:{}", snapshot.server_host, snapshot.server_port),
             workers: snapshot.worker_count,
+            recent_yanks: snapshot
+                .recent_yanks
+                .iter()
+                .map(|entry| YankedRow {
+                    ecosystem: entry.yank.ecosystem.to_string(),
+                    name: entry.yank.name.clone(),
+                    version: entry.yank.version.clone(),
+                    platform: entry.yank.platform.clone(),
+                    reason: entry.yank.reason.clone(),
+                    last_accessed: entry.last_accessed.clone(),
+                })
+                .collect(),
+            mirrors: Vec::new(),
+            mirrors_error: None,
         }
     }
 
@@ -74,6 +145,9 @@ impl DashboardData {
         context.insert("last_accessed", &self.last_accessed);
         context.insert("endpoint", &self.endpoint);
         context.insert("workers", &self.workers);
+        context.insert("recent_yanks", &self.recent_yanks);
+        context.insert("mirrors", &self.mirrors);
+        context.insert("mirrors_error", &self.mirrors_error);
         context
     }
 }
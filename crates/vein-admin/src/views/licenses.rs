@@ -0,0 +1,30 @@
+//! License report view helpers.
+
+use serde::Serialize;
+use vein::license::{LicenseReportEntry, NO_LICENSE};
+
+/// One cached package version as rendered in the license table.
+#[derive(Debug, Serialize)]
+pub struct LicensePackageView {
+    pub ecosystem: String,
+    pub name: String,
+    pub version: String,
+    pub platform: String,
+    pub license: String,
+    pub status: String,
+    pub reason: String,
+}
+
+impl From<LicenseReportEntry> for LicensePackageView {
+    fn from(entry: LicenseReportEntry) -> Self {
+        Self {
+            ecosystem: entry.ecosystem.as_str().to_string(),
+            name: entry.name,
+            version: entry.version,
+            platform: entry.platform.unwrap_or_default(),
+            license: entry.license.unwrap_or_else(|| NO_LICENSE.to_string()),
+            status: entry.status.to_string(),
+            reason: entry.reason.unwrap_or_default(),
+        }
+    }
+}
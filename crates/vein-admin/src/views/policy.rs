@@ -0,0 +1,82 @@
+//! Policy rule view helpers.
+
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+use vein::config::PolicyRule;
+use vein_adapter::StoredPolicyRule;
+
+/// One policy rule as rendered in the rules table.
+#[derive(Debug, Serialize)]
+pub struct PolicyRuleView {
+    pub id: String,
+    pub ecosystems: String,
+    pub packages: String,
+    pub versions: String,
+    pub platforms: String,
+    pub attributes: String,
+    pub action: String,
+    pub reason: String,
+    pub created_at: String,
+}
+
+/// Renders attributes as the `key=value` pairs the form accepts.
+fn attribute_pairs<'a>(attributes: impl Iterator<Item = (&'a String, &'a JsonValue)>) -> String {
+    attributes
+        .map(|(key, value)| match value {
+            JsonValue::String(text) => format!("{key}={text}"),
+            other => format!("{key}={other}"),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn or_any(values: Vec<String>) -> String {
+    if values.is_empty() {
+        "*".to_string()
+    } else {
+        values.join(", ")
+    }
+}
+
+impl From<&PolicyRule> for PolicyRuleView {
+    fn from(rule: &PolicyRule) -> Self {
+        Self {
+            id: rule.id.clone(),
+            ecosystems: or_any(
+                rule.ecosystems
+                    .iter()
+                    .map(|ecosystem| ecosystem.as_str().to_string())
+                    .collect(),
+            ),
+            packages: or_any(rule.packages.clone()),
+            versions: rule.versions.clone().unwrap_or_default(),
+            platforms: or_any(rule.platforms.clone()),
+            attributes: attribute_pairs(rule.attributes.iter()),
+            action: rule.action.as_str().to_string(),
+            reason: rule.reason.clone().unwrap_or_default(),
+            created_at: String::new(),
+        }
+    }
+}
+
+impl From<StoredPolicyRule> for PolicyRuleView {
+    fn from(rule: StoredPolicyRule) -> Self {
+        let attributes = match &rule.attributes {
+            JsonValue::Object(map) => attribute_pairs(map.iter()),
+            _ => String::new(),
+        };
+        Self {
+            id: rule.id,
+            ecosystems: rule
+                .ecosystem
+                .map_or_else(|| "*".to_string(), |ecosystem| ecosystem.to_string()),
+            packages: rule.package,
+            versions: rule.versions.unwrap_or_default(),
+            platforms: rule.platform.unwrap_or_else(|| "*".to_string()),
+            attributes,
+            action: rule.action,
+            reason: rule.reason.unwrap_or_default(),
+            created_at: rule.created_at.format("%Y-%m-%d %H:%M UTC").to_string(),
+        }
+    }
+}
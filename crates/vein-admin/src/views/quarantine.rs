@@ -0,0 +1,85 @@
+//! Quarantine view helpers.
+
+use serde::Serialize;
+use vein::quarantine::dependencies::BlockedBy;
+use vein_adapter::{QuarantineEvent, SuspiciousName};
+
+/// One row of the quarantine audit trail as rendered in tables.
+#[derive(Debug, Serialize)]
+pub struct QuarantineEventView {
+    pub when: String,
+    pub name: String,
+    pub version: String,
+    pub platform: String,
+    pub action: String,
+    pub actor: String,
+    pub reason: String,
+    pub previous_status: String,
+}
+
+impl From<QuarantineEvent> for QuarantineEventView {
+    fn from(event: QuarantineEvent) -> Self {
+        Self {
+            when: event.created_at.format("%Y-%m-%d %H:%M UTC").to_string(),
+            name: event.name,
+            version: event.version,
+            platform: event.platform.unwrap_or_else(|| "ruby".to_string()),
+            action: event.action.to_string(),
+            actor: event.actor,
+            reason: event.reason.unwrap_or_default(),
+            previous_status: event
+                .previous_status
+                .map(|status| status.to_string())
+                .unwrap_or_else(|| "—".to_string()),
+        }
+    }
+}
+
+/// A dependency still holding a quarantined version back.
+#[derive(Debug, Serialize)]
+pub struct BlockedByView {
+    /// Declared requirement, e.g. `bar >= 3.1`.
+    pub requirement: String,
+    /// Chain of quarantined versions, e.g. `bar 3.1.0 → baz 1.0.0`.
+    pub chain: String,
+    pub installable_at: String,
+}
+
+impl From<BlockedBy> for BlockedByView {
+    fn from(blocker: BlockedBy) -> Self {
+        Self {
+            requirement: format!("{} {}", blocker.name, blocker.requirement),
+            chain: blocker.chain.join(" → "),
+            installable_at: blocker
+                .installable_at
+                .format("%Y-%m-%d %H:%M UTC")
+                .to_string(),
+        }
+    }
+}
+
+/// A requested name that looks like a typosquat or dependency confusion.
+#[derive(Debug, Serialize)]
+pub struct SuspiciousNameView {
+    pub ecosystem: String,
+    pub name: String,
+    pub similar_to: String,
+    pub technique: String,
+    pub action: String,
+    pub hits: u64,
+    pub last_seen: String,
+}
+
+impl From<SuspiciousName> for SuspiciousNameView {
+    fn from(entry: SuspiciousName) -> Self {
+        Self {
+            ecosystem: entry.ecosystem.to_string(),
+            name: entry.name,
+            similar_to: entry.similar_to.unwrap_or_else(|| "—".to_string()),
+            technique: entry.technique,
+            action: entry.action,
+            hits: entry.hits,
+            last_seen: entry.last_seen.format("%Y-%m-%d %H:%M UTC").to_string(),
+        }
+    }
+}
@@ -0,0 +1,535 @@
+//! Offline vulnerability advisories.
+//!
+//! OSV dumps (a directory of `.json` files or a `.zip` as published by
+//! osv.dev) are imported into the cache index without touching the network.
+//! Stored ranges are matched against versions here because ordering is
+//! ecosystem specific: RubyGems uses `Gem::Version` rules, crates.io and npm
+//! use semver.
+
+pub mod osv;
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use rama::telemetry::tracing;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use vein_adapter::{
+    Advisory, AdvisorySeverity, AffectedPackage, AssetKind, CacheBackend, CacheBackendTrait,
+    Ecosystem, RangeEvent,
+};
+
+use crate::config::AdvisoriesConfig;
+use crate::gem_requirement::compare_versions;
+
+/// Compares two versions using the ecosystem's ordering rules.
+pub fn compare(ecosystem: Ecosystem, a: &str, b: &str) -> Ordering {
+    match ecosystem {
+        Ecosystem::RubyGems => compare_versions(a, b),
+        Ecosystem::CratesIo | Ecosystem::Npm => {
+            match (semver::Version::parse(a), semver::Version::parse(b)) {
+                (Ok(a), Ok(b)) => a.cmp_precedence(&b),
+                _ => compare_versions(a, b),
+            }
+        }
+    }
+}
+
+/// Whether `version` falls inside any range (or the explicit version list)
+/// of an affected package, following the OSV range evaluation rules.
+pub fn affects(package: &AffectedPackage, version: &str) -> bool {
+    if package.versions.iter().any(|listed| listed == version) {
+        return true;
+    }
+
+    let ecosystem = package.ecosystem;
+    package.ranges.iter().any(|range| {
+        let mut events: Vec<&RangeEvent> = range.events.iter().collect();
+        events.sort_by(|a, b| compare_event(ecosystem, a, b));
+
+        let mut affected = false;
+        for event in events {
+            match event {
+                RangeEvent::Introduced(start) => {
+                    if start == "0" || compare(ecosystem, version, start) != Ordering::Less {
+                        affected = true;
+                    }
+                }
+                RangeEvent::Fixed(end) | RangeEvent::Limit(end) => {
+                    if compare(ecosystem, version, end) != Ordering::Less {
+                        affected = false;
+                    }
+                }
+                RangeEvent::LastAffected(end) => {
+                    if compare(ecosystem, version, end) == Ordering::Greater {
+                        affected = false;
+                    }
+                }
+            }
+        }
+        affected
+    })
+}
+
+fn event_version(event: &RangeEvent) -> &str {
+    match event {
+        RangeEvent::Introduced(version)
+        | RangeEvent::Fixed(version)
+        | RangeEvent::LastAffected(version)
+        | RangeEvent::Limit(version) => version,
+    }
+}
+
+fn compare_event(ecosystem: Ecosystem, a: &RangeEvent, b: &RangeEvent) -> Ordering {
+    let (a, b) = (event_version(a), event_version(b));
+    match (a == "0", b == "0") {
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Less,
+        (false, true) => Ordering::Greater,
+        (false, false) => compare(ecosystem, a, b),
+    }
+}
+
+/// An advisory as reported for one package version.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdvisoryMatch {
+    pub id: String,
+    pub summary: Option<String>,
+    pub severity: AdvisorySeverity,
+    pub aliases: Vec<String>,
+    pub url: Option<String>,
+    pub published: Option<DateTime<Utc>>,
+    pub modified: DateTime<Utc>,
+    /// Versions that fix the advisory.
+    pub patched_versions: Vec<String>,
+}
+
+impl From<&Advisory> for AdvisoryMatch {
+    fn from(advisory: &Advisory) -> Self {
+        Self {
+            id: advisory.id.clone(),
+            summary: advisory.summary.clone(),
+            severity: advisory.severity,
+            aliases: advisory.aliases.clone(),
+            url: advisory.url.clone(),
+            published: advisory.published,
+            modified: advisory.modified,
+            patched_versions: advisory
+                .affected
+                .iter()
+                .flat_map(|package| package.fixed_versions())
+                .map(str::to_string)
+                .collect(),
+        }
+    }
+}
+
+/// Active (non-withdrawn) advisories for a package, optionally limited to
+/// those affecting `version`. Sorted by severity, most severe first.
+pub async fn advisories_for(
+    index: &CacheBackend,
+    ecosystem: Ecosystem,
+    name: &str,
+    version: Option<&str>,
+) -> Result<Vec<Advisory>> {
+    let advisories = index.advisories_for_package(ecosystem, name).await?;
+    Ok(filter_active(advisories, version))
+}
+
+fn filter_active(advisories: Vec<Advisory>, version: Option<&str>) -> Vec<Advisory> {
+    let mut active: Vec<Advisory> = advisories
+        .into_iter()
+        .filter(|advisory| advisory.withdrawn.is_none())
+        .filter(|advisory| {
+            version.is_none_or(|version| {
+                advisory
+                    .affected
+                    .iter()
+                    .any(|package| affects(package, version))
+            })
+        })
+        .collect();
+    active.sort_by(|a, b| b.severity.cmp(&a.severity).then_with(|| a.id.cmp(&b.id)));
+    active
+}
+
+/// The most severe advisory at or above `advisories.block_severity` that
+/// affects a version, if blocking is enabled.
+pub async fn blocking_advisory(
+    config: &AdvisoriesConfig,
+    index: &CacheBackend,
+    ecosystem: Ecosystem,
+    name: &str,
+    version: &str,
+) -> Result<Option<Advisory>> {
+    let Some(threshold) = config.block_severity else {
+        return Ok(None);
+    };
+    Ok(advisories_for(index, ecosystem, name, Some(version))
+        .await?
+        .into_iter()
+        .find(|advisory| advisory.severity >= threshold))
+}
+
+/// Outcome of importing an OSV dump.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ImportReport {
+    /// JSON documents read from the dump.
+    pub files: usize,
+    /// Advisories stored in the index.
+    pub imported: usize,
+    /// Documents that only concern unsupported ecosystems.
+    pub skipped: usize,
+    /// Documents that could not be parsed or stored.
+    pub failed: usize,
+}
+
+type ParsedDocument = (String, Result<Option<Advisory>>);
+
+/// Imports an OSV dump from a directory (searched recursively for `.json`
+/// files) or a `.zip` archive. Works entirely offline.
+pub async fn import_path(index: &CacheBackend, path: &Path) -> Result<ImportReport> {
+    let metadata = tokio::fs::metadata(path)
+        .await
+        .with_context(|| format!("reading advisory source {}", path.display()))?;
+
+    let (tx, mut rx) = mpsc::channel::<ParsedDocument>(64);
+    let source = path.to_path_buf();
+    let reader = tokio::task::spawn_blocking(move || {
+        if metadata.is_dir() {
+            read_directory(&source, &tx)
+        } else {
+            read_zip(&source, &tx)
+        }
+    });
+
+    let mut report = ImportReport::default();
+    while let Some((name, parsed)) = rx.recv().await {
+        report.files += 1;
+        match parsed {
+            Ok(Some(advisory)) => match index.upsert_advisory(&advisory).await {
+                Ok(()) => report.imported += 1,
+                Err(err) => {
+                    report.failed += 1;
+                    tracing::warn!(file = %name, error = %err, "failed to store advisory");
+                }
+            },
+            Ok(None) => report.skipped += 1,
+            Err(err) => {
+                report.failed += 1;
+                tracing::warn!(file = %name, error = %err, "failed to parse advisory");
+            }
+        }
+    }
+
+    reader.await.context("advisory reader task panicked")??;
+    Ok(report)
+}
+
+fn read_directory(root: &Path, tx: &mpsc::Sender<ParsedDocument>) -> Result<()> {
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let entries =
+            std::fs::read_dir(&dir).with_context(|| format!("listing {}", dir.display()))?;
+        let mut files: Vec<PathBuf> = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            if path.is_dir() {
+                pending.push(path);
+            } else if path.extension().is_some_and(|ext| ext == "json") {
+                files.push(path);
+            }
+        }
+        files.sort();
+        for file in files {
+            let parsed = std::fs::read(&file)
+                .with_context(|| format!("reading {}", file.display()))
+                .and_then(|bytes| osv::parse(&bytes));
+            if tx
+                .blocking_send((file.display().to_string(), parsed))
+                .is_err()
+            {
+                return Ok(());
+            }
+        }
+    }
+    Ok(())
+}
+
+fn read_zip(path: &Path, tx: &mpsc::Sender<ParsedDocument>) -> Result<()> {
+    let file = File::open(path).with_context(|| format!("opening {}", path.display()))?;
+    let mut archive = zip::ZipArchive::new(file)
+        .with_context(|| format!("reading zip archive {}", path.display()))?;
+
+    for i in 0..archive.len() {
+        let mut entry = archive
+            .by_index(i)
+            .with_context(|| format!("reading entry {i} of {}", path.display()))?;
+        if !entry.is_file() || !entry.name().ends_with(".json") {
+            continue;
+        }
+        let name = entry.name().to_string();
+        let mut bytes = Vec::with_capacity(entry.size() as usize);
+        let parsed = entry
+            .read_to_end(&mut bytes)
+            .with_context(|| format!("extracting {name}"))
+            .and_then(|_| osv::parse(&bytes));
+        if tx.blocking_send((name, parsed)).is_err() {
+            return Ok(());
+        }
+    }
+    Ok(())
+}
+
+/// Imports `advisories.path` in the background when the server starts.
+pub fn spawn_startup_import(config: &AdvisoriesConfig, index: Arc<CacheBackend>) {
+    let Some(path) = config.path.clone() else {
+        return;
+    };
+
+    std::thread::spawn(move || {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("Failed to create advisory import runtime");
+
+        rt.block_on(async {
+            match import_path(&index, &path).await {
+                Ok(report) => tracing::info!(
+                    path = %path.display(),
+                    imported = report.imported,
+                    skipped = report.skipped,
+                    failed = report.failed,
+                    "Imported vulnerability advisories"
+                ),
+                Err(err) => tracing::error!(
+                    path = %path.display(),
+                    error = %err,
+                    "Advisory import failed"
+                ),
+            }
+        });
+    });
+}
+
+/// A cached version affected by an advisory.
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditFinding {
+    pub ecosystem: Ecosystem,
+    pub name: String,
+    pub version: String,
+    pub platform: Option<String>,
+    pub advisory: AdvisoryMatch,
+}
+
+/// Matches every cached version of the given ecosystems against the stored
+/// advisories, keeping findings at or above `min_severity`.
+pub async fn audit_cached(
+    index: &CacheBackend,
+    ecosystems: &[Ecosystem],
+    min_severity: AdvisorySeverity,
+) -> Result<Vec<AuditFinding>> {
+    let mut findings = Vec::new();
+    for &ecosystem in ecosystems {
+        let kind = match ecosystem {
+            Ecosystem::RubyGems => AssetKind::Gem,
+            Ecosystem::CratesIo => AssetKind::Crate,
+            Ecosystem::Npm => AssetKind::NpmPackage,
+        };
+        let mut known: HashMap<String, Vec<Advisory>> = HashMap::new();
+        for (name, version, platform) in index.cached_versions(kind).await? {
+            if !known.contains_key(&name) {
+                let advisories = index.advisories_for_package(ecosystem, &name).await?;
+                known.insert(name.clone(), advisories);
+            }
+            let matching = filter_active(known[&name].clone(), Some(&version));
+            findings.extend(
+                matching
+                    .iter()
+                    .filter(|advisory| advisory.severity >= min_severity)
+                    .map(|advisory| AuditFinding {
+                        ecosystem,
+                        name: name.clone(),
+                        version: version.clone(),
+                        platform: platform.clone(),
+                        advisory: advisory.into(),
+                    }),
+            );
+        }
+    }
+    Ok(findings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vein_adapter::AffectedRange;
+
+    fn package(ecosystem: Ecosystem, events: Vec<RangeEvent>) -> AffectedPackage {
+        AffectedPackage {
+            ecosystem,
+            name: "pkg".to_string(),
+            ranges: vec![AffectedRange { events }],
+            versions: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn evaluates_introduced_fixed_ranges() {
+        let rack = package(
+            Ecosystem::RubyGems,
+            vec![
+                RangeEvent::Fixed("2.2.8".to_string()),
+                RangeEvent::Introduced("0".to_string()),
+                RangeEvent::Introduced("3.0.0".to_string()),
+                RangeEvent::Fixed("3.0.9".to_string()),
+            ],
+        );
+        assert!(affects(&rack, "2.2.7"));
+        assert!(!affects(&rack, "2.2.8"));
+        assert!(!affects(&rack, "2.10.0"));
+        assert!(!affects(&rack, "3.0.0.beta1"));
+        assert!(affects(&rack, "3.0.8"));
+        assert!(!affects(&rack, "3.0.10"));
+    }
+
+    #[test]
+    fn honours_last_affected_and_semver_prereleases() {
+        let hyper = package(
+            Ecosystem::CratesIo,
+            vec![
+                RangeEvent::Introduced("0.14.0".to_string()),
+                RangeEvent::LastAffected("0.14.9".to_string()),
+            ],
+        );
+        assert!(affects(&hyper, "0.14.9"));
+        assert!(!affects(&hyper, "0.14.10"));
+        assert!(!affects(&hyper, "0.14.0-rc.1"));
+
+        let mut listed = package(Ecosystem::Npm, Vec::new());
+        listed.versions.push("1.2.3".to_string());
+        assert!(affects(&listed, "1.2.3"));
+        assert!(!affects(&listed, "1.2.4"));
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn imports_zip_archive() {
+        use std::io::Write;
+
+        let index = CacheBackend::connect_memory().await.unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("all.zip");
+        let mut writer = zip::ZipWriter::new(File::create(&archive).unwrap());
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated);
+        writer
+            .start_file("RUSTSEC-2024-0001.json", options)
+            .unwrap();
+        writer
+            .write_all(
+                br#"{
+                    "id": "RUSTSEC-2024-0001",
+                    "modified": "2024-03-01T00:00:00Z",
+                    "affected": [{
+                        "package": {"ecosystem": "crates.io", "name": "hyper"},
+                        "ranges": [{"type": "SEMVER", "events": [{"introduced": "0"}, {"fixed": "0.14.10"}]}]
+                    }]
+                }"#,
+            )
+            .unwrap();
+        writer.start_file("README.txt", options).unwrap();
+        writer.write_all(b"not an advisory").unwrap();
+        writer.finish().unwrap();
+
+        let report = import_path(&index, &archive).await.unwrap();
+        assert_eq!(report.files, 1);
+        assert_eq!(report.imported, 1);
+
+        let found = advisories_for(&index, Ecosystem::CratesIo, "hyper", Some("0.14.9"))
+            .await
+            .unwrap();
+        assert_eq!(found[0].id, "RUSTSEC-2024-0001");
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn imports_directory_and_audits_cached_versions() {
+        let index = CacheBackend::connect_memory().await.unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("RubyGems");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::write(
+            nested.join("GHSA-1.json"),
+            r#"{
+                "id": "GHSA-1",
+                "modified": "2024-03-01T00:00:00Z",
+                "summary": "Remote code execution",
+                "affected": [{
+                    "package": {"ecosystem": "RubyGems", "name": "rack"},
+                    "ranges": [{"type": "ECOSYSTEM", "events": [{"introduced": "0"}, {"fixed": "3.0.1"}]}]
+                }],
+                "database_specific": {"severity": "CRITICAL"}
+            }"#,
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("PYSEC-1.json"),
+            r#"{"id": "PYSEC-1", "modified": "2024-03-01T00:00:00Z", "affected": []}"#,
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("broken.json"), "{").unwrap();
+
+        let report = import_path(&index, dir.path()).await.unwrap();
+        assert_eq!(
+            report,
+            ImportReport {
+                files: 3,
+                imported: 1,
+                skipped: 1,
+                failed: 1,
+            }
+        );
+
+        for version in ["3.0.0", "3.0.1"] {
+            let key = vein_adapter::AssetKey {
+                kind: AssetKind::Gem,
+                name: "rack",
+                version,
+                platform: None,
+            };
+            index
+                .insert_or_replace(&key, "gems/rack.gem", "hash", 1)
+                .await
+                .unwrap();
+        }
+
+        let findings = audit_cached(&index, &[Ecosystem::RubyGems], AdvisorySeverity::Low)
+            .await
+            .unwrap();
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].version, "3.0.0");
+        assert_eq!(findings[0].advisory.patched_versions, ["3.0.1"]);
+
+        let config = AdvisoriesConfig {
+            path: None,
+            block_severity: Some(AdvisorySeverity::Critical),
+        };
+        let blocked = blocking_advisory(&config, &index, Ecosystem::RubyGems, "rack", "3.0.0")
+            .await
+            .unwrap();
+        assert_eq!(blocked.map(|a| a.id).as_deref(), Some("GHSA-1"));
+        assert!(
+            blocking_advisory(&config, &index, Ecosystem::RubyGems, "rack", "3.0.1")
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,366 @@
+//! OSV record parsing.
+//!
+//! Converts one OSV JSON document into an [`Advisory`], keeping only the
+//! RubyGems, crates.io and npm packages. Severity comes from the GHSA
+//! `database_specific.severity` field when present, then from
+//! `ecosystem_specific.severity`, and finally from a CVSS v3 vector.
+
+use std::collections::BTreeMap;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+use vein_adapter::{
+    Advisory, AdvisorySeverity, AffectedPackage, AffectedRange, Ecosystem, RangeEvent,
+};
+
+#[derive(Debug, Deserialize)]
+struct OsvRecord {
+    id: String,
+    #[serde(default)]
+    summary: Option<String>,
+    #[serde(default)]
+    details: Option<String>,
+    #[serde(default)]
+    aliases: Vec<String>,
+    modified: DateTime<Utc>,
+    #[serde(default)]
+    published: Option<DateTime<Utc>>,
+    #[serde(default)]
+    withdrawn: Option<DateTime<Utc>>,
+    #[serde(default)]
+    severity: Vec<OsvSeverity>,
+    #[serde(default)]
+    affected: Vec<OsvAffected>,
+    #[serde(default)]
+    references: Vec<OsvReference>,
+    #[serde(default)]
+    database_specific: Option<JsonValue>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OsvSeverity {
+    #[serde(rename = "type")]
+    kind: String,
+    score: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct OsvAffected {
+    #[serde(default)]
+    package: Option<OsvPackage>,
+    #[serde(default)]
+    ranges: Vec<OsvRange>,
+    #[serde(default)]
+    versions: Vec<String>,
+    #[serde(default)]
+    severity: Vec<OsvSeverity>,
+    #[serde(default)]
+    ecosystem_specific: Option<JsonValue>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OsvPackage {
+    ecosystem: String,
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct OsvRange {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    events: Vec<BTreeMap<String, String>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OsvReference {
+    #[serde(rename = "type", default)]
+    kind: Option<String>,
+    url: String,
+}
+
+/// Maps an OSV ecosystem name to a Vein ecosystem.
+fn ecosystem(name: &str) -> Option<Ecosystem> {
+    match name {
+        "RubyGems" => Some(Ecosystem::RubyGems),
+        "crates.io" => Some(Ecosystem::CratesIo),
+        "npm" => Some(Ecosystem::Npm),
+        _ => None,
+    }
+}
+
+/// Parses an OSV document. Returns `None` when it affects no supported
+/// ecosystem.
+pub fn parse(bytes: &[u8]) -> Result<Option<Advisory>> {
+    let record: OsvRecord = serde_json::from_slice(bytes).context("parsing OSV record")?;
+
+    let mut packages: BTreeMap<(&'static str, String), AffectedPackage> = BTreeMap::new();
+    for affected in &record.affected {
+        let Some(package) = &affected.package else {
+            continue;
+        };
+        let Some(ecosystem) = ecosystem(&package.ecosystem) else {
+            continue;
+        };
+        let entry = packages
+            .entry((ecosystem.as_str(), package.name.clone()))
+            .or_insert_with(|| AffectedPackage {
+                ecosystem,
+                name: package.name.clone(),
+                ranges: Vec::new(),
+                versions: Vec::new(),
+            });
+        entry.ranges.extend(
+            affected
+                .ranges
+                .iter()
+                .filter(|range| range.kind != "GIT")
+                .map(|range| AffectedRange {
+                    events: range.events.iter().filter_map(range_event).collect(),
+                })
+                .filter(|range| !range.events.is_empty()),
+        );
+        entry.versions.extend(affected.versions.iter().cloned());
+    }
+    if packages.is_empty() {
+        return Ok(None);
+    }
+
+    let url = record
+        .references
+        .iter()
+        .find(|reference| reference.kind.as_deref() == Some("ADVISORY"))
+        .or_else(|| record.references.first())
+        .map(|reference| reference.url.clone());
+
+    Ok(Some(Advisory {
+        severity: severity(&record),
+        id: record.id,
+        summary: record.summary.filter(|summary| !summary.trim().is_empty()),
+        details: record.details,
+        aliases: record.aliases,
+        url,
+        published: record.published,
+        modified: record.modified,
+        withdrawn: record.withdrawn,
+        affected: packages.into_values().collect(),
+    }))
+}
+
+fn range_event(event: &BTreeMap<String, String>) -> Option<RangeEvent> {
+    let (kind, version) = event.iter().next()?;
+    let version = version.clone();
+    match kind.as_str() {
+        "introduced" => Some(RangeEvent::Introduced(version)),
+        "fixed" => Some(RangeEvent::Fixed(version)),
+        "last_affected" => Some(RangeEvent::LastAffected(version)),
+        "limit" => Some(RangeEvent::Limit(version)),
+        _ => None,
+    }
+}
+
+fn severity(record: &OsvRecord) -> AdvisorySeverity {
+    let labelled = |value: Option<&JsonValue>| {
+        value
+            .and_then(|value| value.get("severity"))
+            .and_then(JsonValue::as_str)
+            .and_then(|label| label.parse().ok())
+    };
+
+    labelled(record.database_specific.as_ref())
+        .or_else(|| {
+            record
+                .affected
+                .iter()
+                .find_map(|affected| labelled(affected.ecosystem_specific.as_ref()))
+        })
+        .or_else(|| {
+            record
+                .severity
+                .iter()
+                .chain(record.affected.iter().flat_map(|a| &a.severity))
+                .filter(|severity| severity.kind == "CVSS_V3")
+                .find_map(|severity| cvss3_base_score(&severity.score))
+                .map(severity_from_score)
+        })
+        .unwrap_or_default()
+}
+
+/// Qualitative rating for a CVSS base score.
+fn severity_from_score(score: f64) -> AdvisorySeverity {
+    match score {
+        s if s >= 9.0 => AdvisorySeverity::Critical,
+        s if s >= 7.0 => AdvisorySeverity::High,
+        s if s >= 4.0 => AdvisorySeverity::Moderate,
+        s if s > 0.0 => AdvisorySeverity::Low,
+        _ => AdvisorySeverity::Unknown,
+    }
+}
+
+/// Computes the CVSS v3.x base score of a vector such as
+/// `CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H`.
+fn cvss3_base_score(vector: &str) -> Option<f64> {
+    let metrics: BTreeMap<&str, &str> = vector
+        .strip_prefix("CVSS:3.")?
+        .split('/')
+        .skip(1)
+        .filter_map(|metric| metric.split_once(':'))
+        .collect();
+    let metric = |name: &str| metrics.get(name).copied();
+
+    let changed = match metric("S")? {
+        "U" => false,
+        "C" => true,
+        _ => return None,
+    };
+    let attack_vector = match metric("AV")? {
+        "N" => 0.85,
+        "A" => 0.62,
+        "L" => 0.55,
+        "P" => 0.2,
+        _ => return None,
+    };
+    let attack_complexity = match metric("AC")? {
+        "L" => 0.77,
+        "H" => 0.44,
+        _ => return None,
+    };
+    let privileges = match (metric("PR")?, changed) {
+        ("N", _) => 0.85,
+        ("L", false) => 0.62,
+        ("L", true) => 0.68,
+        ("H", false) => 0.27,
+        ("H", true) => 0.5,
+        _ => return None,
+    };
+    let interaction = match metric("UI")? {
+        "N" => 0.85,
+        "R" => 0.62,
+        _ => return None,
+    };
+    let impact_of = |name: &str| match metric(name)? {
+        "H" => Some(0.56),
+        "L" => Some(0.22),
+        "N" => Some(0.0),
+        _ => None,
+    };
+    let (c, i, a) = (impact_of("C")?, impact_of("I")?, impact_of("A")?);
+
+    let iss = 1.0 - (1.0 - c) * (1.0 - i) * (1.0 - a);
+    let impact = if changed {
+        7.52 * (iss - 0.029) - 3.25 * (iss - 0.02_f64).powi(15)
+    } else {
+        6.42 * iss
+    };
+    if impact <= 0.0 {
+        return Some(0.0);
+    }
+    let exploitability = 8.22 * attack_vector * attack_complexity * privileges * interaction;
+    let base = if changed {
+        1.08 * (impact + exploitability)
+    } else {
+        impact + exploitability
+    };
+    Some(round_up(base.min(10.0)))
+}
+
+/// CVSS "round up" to one decimal, avoiding floating point artefacts.
+fn round_up(value: f64) -> f64 {
+    let scaled = (value * 100_000.0).round() as i64;
+    if scaled % 10_000 == 0 {
+        scaled as f64 / 100_000.0
+    } else {
+        (scaled / 10_000 + 1) as f64 / 10.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_ghsa_record_for_supported_ecosystems() {
+        let json = br#"{
+            "id": "GHSA-aaaa-bbbb-cccc",
+            "modified": "2024-03-01T00:00:00Z",
+            "published": "2024-02-01T00:00:00Z",
+            "aliases": ["CVE-2024-0001"],
+            "summary": "Header injection in rack",
+            "affected": [
+                {
+                    "package": {"ecosystem": "RubyGems", "name": "rack"},
+                    "ranges": [{"type": "ECOSYSTEM", "events": [{"introduced": "0"}, {"fixed": "2.2.8"}]}]
+                },
+                {
+                    "package": {"ecosystem": "RubyGems", "name": "rack"},
+                    "ranges": [{"type": "ECOSYSTEM", "events": [{"introduced": "3.0.0"}, {"fixed": "3.0.9"}]}]
+                },
+                {
+                    "package": {"ecosystem": "PyPI", "name": "rack"},
+                    "ranges": [{"type": "ECOSYSTEM", "events": [{"introduced": "0"}]}]
+                }
+            ],
+            "references": [
+                {"type": "WEB", "url": "https://example.com/post"},
+                {"type": "ADVISORY", "url": "https://github.com/advisories/GHSA-aaaa-bbbb-cccc"}
+            ],
+            "database_specific": {"severity": "MODERATE"}
+        }"#;
+
+        let advisory = parse(json).unwrap().unwrap();
+        assert_eq!(advisory.severity, AdvisorySeverity::Moderate);
+        assert_eq!(
+            advisory.url.as_deref(),
+            Some("https://github.com/advisories/GHSA-aaaa-bbbb-cccc")
+        );
+        assert_eq!(advisory.affected.len(), 1);
+        assert_eq!(advisory.affected[0].ranges.len(), 2);
+        assert_eq!(advisory.affected[0].fixed_versions(), ["2.2.8", "3.0.9"]);
+    }
+
+    #[test]
+    fn skips_records_without_supported_packages() {
+        let json = br#"{
+            "id": "PYSEC-1",
+            "modified": "2024-03-01T00:00:00Z",
+            "affected": [{"package": {"ecosystem": "PyPI", "name": "requests"}}]
+        }"#;
+        assert!(parse(json).unwrap().is_none());
+        assert!(parse(b"not json").is_err());
+    }
+
+    #[test]
+    fn falls_back_to_cvss_vector() {
+        let json = br#"{
+            "id": "RUSTSEC-2024-0001",
+            "modified": "2024-03-01T00:00:00Z",
+            "severity": [{"type": "CVSS_V3", "score": "CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H"}],
+            "affected": [{
+                "package": {"ecosystem": "crates.io", "name": "hyper"},
+                "ranges": [{"type": "SEMVER", "events": [{"introduced": "0.0.0-0"}, {"fixed": "0.14.10"}]}]
+            }]
+        }"#;
+        let advisory = parse(json).unwrap().unwrap();
+        assert_eq!(advisory.severity, AdvisorySeverity::Critical);
+        assert_eq!(advisory.affected[0].ecosystem, Ecosystem::CratesIo);
+    }
+
+    #[test]
+    fn computes_cvss3_base_scores() {
+        assert_eq!(
+            cvss3_base_score("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H"),
+            Some(9.8)
+        );
+        assert_eq!(
+            cvss3_base_score("CVSS:3.0/AV:N/AC:L/PR:N/UI:R/S:C/C:L/I:L/A:N"),
+            Some(6.1)
+        );
+        assert_eq!(
+            cvss3_base_score("CVSS:3.1/AV:L/AC:L/PR:L/UI:N/S:U/C:N/I:N/A:N"),
+            Some(0.0)
+        );
+        assert_eq!(cvss3_base_score("CVSS:4.0/AV:N"), None);
+    }
+}
@@ -1,15 +1,24 @@
+mod advisories;
+mod audit;
+mod bundle;
 mod catalog;
 mod cli;
 mod health;
 mod init;
+mod license;
+mod prefetch;
 mod quarantine;
+mod reload;
+mod sbom;
 mod server;
 mod setup;
 mod stats;
 
-use anyhow::Result;
+use anyhow::{Result, anyhow};
 
-use self::cli::{CatalogCommand, Command, QuarantineCommand};
+use self::cli::{
+    AdvisoriesCommand, CatalogCommand, Command, LicenseCommand, QuarantineCommand, SbomCommand,
+};
 
 pub(crate) use self::cli::Cli;
 
@@ -33,15 +42,104 @@ pub(crate) fn run(cli: Cli) -> Result<()> {
                 version,
                 platform,
                 reason,
-            } => quarantine::run_quarantine_approve(config, gem, version, platform, reason),
+                actor,
+            } => quarantine::run_quarantine_approve(config, gem, version, platform, reason, actor),
             QuarantineCommand::Block {
                 config,
                 gem,
                 version,
                 platform,
                 reason,
-            } => quarantine::run_quarantine_block(config, gem, version, platform, reason),
+                actor,
+            } => quarantine::run_quarantine_block(config, gem, version, platform, reason, actor),
+            QuarantineCommand::History {
+                config,
+                gem,
+                version,
+                limit,
+            } => quarantine::run_quarantine_history(config, gem, version, limit),
+        },
+        Command::Advisories { action } => match action {
+            AdvisoriesCommand::Import { config, path } => {
+                advisories::run_advisories_import(config, path)
+            }
+        },
+        Command::License { action } => match action {
+            LicenseCommand::Report {
+                config,
+                format,
+                output,
+            } => license::run_license_report(config, format, output),
+        },
+        Command::Audit {
+            config,
+            ecosystem,
+            min_severity,
+            json,
+            lockfile,
+            server,
+        } => match lockfile {
+            Some(lockfile) => {
+                let target = match server {
+                    Some(url) => audit::LockfileTarget::Server(url),
+                    None => audit::LockfileTarget::Local(config),
+                };
+                audit::run_lockfile_audit(target, lockfile, min_severity, json)
+            }
+            None => advisories::run_audit(config, ecosystem, min_severity, json),
         },
+        Command::Prefetch {
+            config,
+            lockfile,
+            server,
+            concurrency,
+            json,
+        } => {
+            let target = match server {
+                Some(url) => audit::LockfileTarget::Server(url),
+                None => audit::LockfileTarget::Local(config),
+            };
+            prefetch::run_prefetch(target, lockfile, concurrency, json)
+        }
+        Command::Sbom {
+            action:
+                Some(SbomCommand::Backfill {
+                    config,
+                    force,
+                    ecosystem,
+                    concurrency,
+                }),
+            ..
+        } => sbom::run_backfill(config, force, ecosystem, concurrency),
+        Command::Sbom {
+            action: None,
+            config,
+            lockfile,
+            format,
+            name,
+            output,
+            server,
+        } => {
+            let lockfile = lockfile.ok_or_else(|| anyhow!("--lockfile is required"))?;
+            let target = match server {
+                Some(url) => audit::LockfileTarget::Server(url),
+                None => audit::LockfileTarget::Local(config),
+            };
+            sbom::run_lockfile_sbom(target, lockfile, format, name, output)
+        }
+        Command::Export {
+            config,
+            output,
+            ecosystem,
+            name,
+            lockfile,
+            since,
+        } => bundle::run_export(config, output, ecosystem, name, lockfile, since),
+        Command::Import {
+            config,
+            bundle: path,
+            verify_only,
+        } => bundle::run_import(config, path, verify_only),
         Command::Init { output, force } => init::run_init(output, force),
     }
 }
@@ -0,0 +1,103 @@
+use std::path::PathBuf;
+
+use anyhow::{Context, Result, anyhow, bail};
+use vein::advisories;
+use vein_adapter::{AdvisorySeverity, CacheBackendTrait, Ecosystem};
+
+use super::setup::{build_current_thread_runtime, connect_cache_index, init_tracing, load_config};
+
+pub(crate) fn run_advisories_import(config_path: PathBuf, path: Option<PathBuf>) -> Result<()> {
+    let config = load_config(config_path)?;
+    init_tracing(&config)?;
+
+    let Some(path) = path.or_else(|| config.advisories.path.clone()) else {
+        bail!("no advisory source given; pass a path or set advisories.path in vein.toml");
+    };
+
+    let rt = build_current_thread_runtime("advisories")?;
+    let (index, _) = connect_cache_index(&rt, &config)?;
+    let report = rt
+        .block_on(advisories::import_path(&index, &path))
+        .with_context(|| format!("importing advisories from {}", path.display()))?;
+    let total = rt
+        .block_on(index.advisory_count())
+        .context("counting advisories")?;
+
+    println!("Imported advisories from {}", path.display());
+    println!("  documents read: {}", report.files);
+    println!("  imported:       {}", report.imported);
+    println!("  other ecosystems skipped: {}", report.skipped);
+    println!("  failed:         {}", report.failed);
+    println!("  advisories in index: {total}");
+
+    Ok(())
+}
+
+pub(crate) fn run_audit(
+    config_path: PathBuf,
+    ecosystem: Option<String>,
+    min_severity: String,
+    json: bool,
+) -> Result<()> {
+    let config = load_config(config_path)?;
+    init_tracing(&config)?;
+
+    let ecosystems = match ecosystem {
+        Some(name) => vec![
+            name.parse::<Ecosystem>()
+                .map_err(|_| anyhow!("unknown ecosystem '{name}'"))?,
+        ],
+        None => vec![Ecosystem::RubyGems, Ecosystem::CratesIo, Ecosystem::Npm],
+    };
+    let min_severity = min_severity
+        .parse::<AdvisorySeverity>()
+        .map_err(|_| anyhow!("unknown severity '{min_severity}'"))?;
+
+    let rt = build_current_thread_runtime("audit")?;
+    let (index, _) = connect_cache_index(&rt, &config)?;
+    if rt.block_on(index.advisory_count())? == 0 {
+        eprintln!("No advisories imported yet; run `vein advisories import` first.");
+    }
+    let findings = rt
+        .block_on(advisories::audit_cached(&index, &ecosystems, min_severity))
+        .context("auditing cached versions")?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&findings)?);
+    } else if findings.is_empty() {
+        println!("No cached versions are affected by known advisories.");
+    } else {
+        println!(
+            "{:<9} {:<8} {:<30} {:<15} {:<22} PATCHED",
+            "SEVERITY", "ECO", "PACKAGE", "VERSION", "ADVISORY"
+        );
+        for finding in &findings {
+            let version = match &finding.platform {
+                Some(platform) => format!("{}-{platform}", finding.version),
+                None => finding.version.clone(),
+            };
+            let patched = if finding.advisory.patched_versions.is_empty() {
+                "-".to_string()
+            } else {
+                finding.advisory.patched_versions.join(", ")
+            };
+            println!(
+                "{:<9} {:<8} {:<30} {:<15} {:<22} {}",
+                finding.advisory.severity,
+                finding.ecosystem,
+                finding.name,
+                version,
+                finding.advisory.id,
+                patched
+            );
+            if let Some(summary) = &finding.advisory.summary {
+                println!("          {summary}");
+            }
+        }
+    }
+
+    if !findings.is_empty() {
+        bail!("{} cached versions affected by advisories", findings.len());
+    }
+    Ok(())
+}
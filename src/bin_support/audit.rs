@@ -0,0 +1,202 @@
+use std::path::PathBuf;
+
+use anyhow::{Context, Result, anyhow, bail};
+use rama::{
+    Service,
+    http::{Body, Method, Request, body::util::BodyExt, client::EasyHttpWebClient, header},
+    tls::rustls::dep::rustls,
+};
+use serde::de::DeserializeOwned;
+use vein::lockfile::{
+    Lockfile,
+    audit::{self, ChecksumCheck, LockfileAudit},
+};
+use vein_adapter::{AdvisorySeverity, FilesystemStorage};
+
+use super::setup::{build_current_thread_runtime, connect_cache_index, init_tracing, load_config};
+
+/// Where a lockfile command runs.
+pub(crate) enum LockfileTarget {
+    /// Against the local cache index and storage from the config file.
+    Local(PathBuf),
+    /// Against a running Vein instance (base URL).
+    Server(String),
+}
+
+pub(crate) fn run_lockfile_audit(
+    target: LockfileTarget,
+    lockfile_path: PathBuf,
+    min_severity: String,
+    json: bool,
+) -> Result<()> {
+    let content = std::fs::read_to_string(&lockfile_path)
+        .with_context(|| format!("reading lockfile {}", lockfile_path.display()))?;
+    let file_name = lockfile_path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .map(str::to_string);
+    let severity = min_severity
+        .parse::<AdvisorySeverity>()
+        .map_err(|_| anyhow!("unknown severity '{min_severity}'"))?;
+
+    let report = match target {
+        LockfileTarget::Local(config_path) => {
+            let config = load_config(config_path)?;
+            init_tracing(&config)?;
+            let lockfile = Lockfile::parse(file_name.as_deref(), &content)
+                .with_context(|| format!("parsing {}", lockfile_path.display()))?;
+
+            let rt = build_current_thread_runtime("audit")?;
+            let (index, _) = connect_cache_index(&rt, &config)?;
+            let storage = FilesystemStorage::new(config.storage.path.clone());
+            rt.block_on(audit::audit_lockfile(&index, &storage, &lockfile, severity))?
+        }
+        LockfileTarget::Server(base) => {
+            let _ = rustls::crypto::aws_lc_rs::default_provider().install_default();
+            let rt = build_current_thread_runtime("audit")?;
+            let url = lockfile_url(
+                &base,
+                "/.well-known/vein/audit",
+                file_name.as_deref(),
+                &format!("min_severity={min_severity}"),
+            );
+            rt.block_on(post_lockfile::<LockfileAudit>(&url, content))?
+        }
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print_report(&report);
+    }
+
+    if !report.passed {
+        bail!("lockfile audit failed");
+    }
+    Ok(())
+}
+
+/// Builds `{base}{endpoint}?lockfile=<name>&<query>` for the lockfile endpoints.
+pub(super) fn lockfile_url(
+    base: &str,
+    endpoint: &str,
+    file_name: Option<&str>,
+    query: &str,
+) -> String {
+    let mut params = Vec::new();
+    if let Some(name) = file_name {
+        params.push(format!(
+            "lockfile={}",
+            percent_encoding::utf8_percent_encode(name, percent_encoding::NON_ALPHANUMERIC)
+        ));
+    }
+    if !query.is_empty() {
+        params.push(query.to_string());
+    }
+    let mut url = format!("{}{endpoint}", base.trim_end_matches('/'));
+    if !params.is_empty() {
+        url.push('?');
+        url.push_str(&params.join("&"));
+    }
+    url
+}
+
+/// POSTs a lockfile to a running Vein instance and decodes the JSON report.
+pub(super) async fn post_lockfile<T: DeserializeOwned>(url: &str, content: String) -> Result<T> {
+    let body = post_lockfile_raw(url, content).await?;
+    serde_json::from_slice(&body).with_context(|| format!("parsing response from {url}"))
+}
+
+/// POSTs a lockfile to a running Vein instance and returns the response body.
+pub(super) async fn post_lockfile_raw(url: &str, content: String) -> Result<Vec<u8>> {
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri(url)
+        .header(header::CONTENT_TYPE, "text/plain; charset=utf-8")
+        .body(Body::from(content))
+        .map_err(|e| anyhow!("building request to {url}: {e}"))?;
+    let response = EasyHttpWebClient::default()
+        .serve(request)
+        .await
+        .map_err(|e| anyhow!("sending lockfile to {url}: {e}"))?;
+    let status = response.status();
+    let body = response
+        .into_body()
+        .collect()
+        .await
+        .map_err(|e| anyhow!("reading response from {url}: {e}"))?
+        .to_bytes();
+    if !status.is_success() {
+        bail!(
+            "{url} returned {status}: {}",
+            String::from_utf8_lossy(&body).trim()
+        );
+    }
+    Ok(body.to_vec())
+}
+
+fn print_report(report: &LockfileAudit) {
+    println!(
+        "{:<30} {:<18} {:<7} {:<12} {:<10} {:<16} ADVISORIES",
+        "PACKAGE", "VERSION", "CACHED", "STATUS", "CHECKSUM", "LICENSE"
+    );
+    for dep in &report.dependencies {
+        let version = match &dep.platform {
+            Some(platform) => format!("{}-{platform}", dep.version),
+            None => dep.version.clone(),
+        };
+        let status = if dep.yanked {
+            "yanked".to_string()
+        } else if dep.quarantined {
+            "quarantined".to_string()
+        } else {
+            dep.status
+                .map(|status| status.to_string())
+                .unwrap_or_else(|| "-".to_string())
+        };
+        let checksum = match dep.checksum {
+            ChecksumCheck::Match => "ok",
+            ChecksumCheck::Mismatch => "MISMATCH",
+            ChecksumCheck::Missing => "none",
+            ChecksumCheck::NotCached => "-",
+            ChecksumCheck::Unsupported => "unverified",
+        };
+        let licenses = if dep.licenses.is_empty() {
+            "-".to_string()
+        } else {
+            dep.licenses.join(",")
+        };
+        let advisories = if dep.advisories.is_empty() {
+            "-".to_string()
+        } else {
+            dep.advisories
+                .iter()
+                .map(|advisory| format!("{} ({})", advisory.id, advisory.severity))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        println!(
+            "{:<30} {:<18} {:<7} {:<12} {:<10} {:<16} {}",
+            dep.name,
+            version,
+            if dep.cached { "yes" } else { "no" },
+            status,
+            checksum,
+            licenses,
+            advisories
+        );
+    }
+
+    let summary = &report.summary;
+    println!();
+    println!(
+        "{} packages: {} cached, {} quarantined, {} yanked, {} checksum mismatches, {} vulnerable",
+        summary.total,
+        summary.cached,
+        summary.quarantined,
+        summary.yanked,
+        summary.checksum_mismatches,
+        summary.vulnerable
+    );
+    println!("Result: {}", if report.passed { "PASS" } else { "FAIL" });
+}
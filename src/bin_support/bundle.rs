@@ -0,0 +1,110 @@
+use std::path::PathBuf;
+
+use anyhow::{Context, Result, anyhow};
+use chrono::{DateTime, NaiveDate, Utc};
+use vein::{
+    bundle::{self, ExportFilter},
+    lockfile::Lockfile,
+    util::format_bytes,
+};
+use vein_adapter::{Ecosystem, FilesystemStorage};
+
+use super::setup::{build_current_thread_runtime, connect_cache_index, init_tracing, load_config};
+
+fn parse_since(value: &str) -> Result<DateTime<Utc>> {
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(value) {
+        return Ok(timestamp.with_timezone(&Utc));
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map(|date| date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc())
+        .map_err(|_| anyhow!("invalid date '{value}' (expected YYYY-MM-DD or RFC 3339)"))
+}
+
+pub(crate) fn run_export(
+    config_path: PathBuf,
+    output: PathBuf,
+    ecosystems: Vec<String>,
+    names: Vec<String>,
+    lockfile: Option<PathBuf>,
+    since: Option<String>,
+) -> Result<()> {
+    let config = load_config(config_path)?;
+    init_tracing(&config)?;
+
+    let ecosystems = ecosystems
+        .iter()
+        .map(|name| {
+            name.parse::<Ecosystem>()
+                .map_err(|_| anyhow!("unknown ecosystem '{name}'"))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let lockfile = lockfile
+        .map(|path| {
+            let content = std::fs::read_to_string(&path)
+                .with_context(|| format!("reading lockfile {}", path.display()))?;
+            let file_name = path.file_name().and_then(|name| name.to_str());
+            Lockfile::parse(file_name, &content)
+                .with_context(|| format!("parsing {}", path.display()))
+        })
+        .transpose()?;
+    let filter = ExportFilter {
+        ecosystems,
+        names,
+        lockfile,
+        since: since.as_deref().map(parse_since).transpose()?,
+    };
+
+    let rt = build_current_thread_runtime("export")?;
+    let (index, _) = connect_cache_index(&rt, &config)?;
+    let storage = FilesystemStorage::new(config.storage.path.clone());
+    let report = rt
+        .block_on(bundle::export_bundle(&index, &storage, &filter, &output))
+        .with_context(|| format!("exporting bundle to {}", output.display()))?;
+
+    println!(
+        "Wrote {} ({})",
+        output.display(),
+        format_bytes(report.bytes)
+    );
+    println!("  artifacts:     {}", report.artifacts);
+    println!("  index files:   {}", report.index_files);
+    println!("  gem metadata:  {}", report.gem_metadata);
+    println!("  gem versions:  {}", report.gem_versions);
+    if report.skipped > 0 {
+        println!(
+            "  skipped:       {} (missing or failed checksum)",
+            report.skipped
+        );
+    }
+    Ok(())
+}
+
+pub(crate) fn run_import(config_path: PathBuf, path: PathBuf, verify_only: bool) -> Result<()> {
+    let config = load_config(config_path)?;
+    init_tracing(&config)?;
+    config
+        .storage
+        .ensure_directories()
+        .context("creating storage directories")?;
+
+    let rt = build_current_thread_runtime("import")?;
+    let (index, _) = connect_cache_index(&rt, &config)?;
+    let storage = FilesystemStorage::new(config.storage.path.clone());
+    rt.block_on(storage.prepare())
+        .context("preparing storage directory")?;
+    let report = rt
+        .block_on(bundle::import_bundle(&index, &storage, &path, verify_only))
+        .with_context(|| format!("importing bundle {}", path.display()))?;
+
+    if verify_only {
+        println!("Bundle {} verified", path.display());
+    } else {
+        println!("Imported {}", path.display());
+    }
+    println!("  artifacts:     {}", report.artifacts);
+    println!("  index files:   {}", report.index_files);
+    println!("  gem metadata:  {}", report.gem_metadata);
+    println!("  gem versions:  {}", report.gem_versions);
+    println!("  size:          {}", format_bytes(report.bytes));
+    Ok(())
+}
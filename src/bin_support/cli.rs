@@ -31,7 +31,7 @@ pub(crate) enum Command {
     /// Perform a health check against a Vein instance
     Health {
         /// URL of the health endpoint (defaults to local proxy)
-        #[arg(long, default_value = "http://127.0.0.1:8346/up")]
+        #[arg(long, default_value = "http://127.0.0.1:8346/readyz")]
         url: String,
         /// Timeout in seconds for the request
         #[arg(long, default_value_t = 5)]
@@ -42,6 +42,113 @@ pub(crate) enum Command {
         #[command(subcommand)]
         action: QuarantineCommand,
     },
+    /// Vulnerability advisory operations
+    Advisories {
+        #[command(subcommand)]
+        action: AdvisoriesCommand,
+    },
+    /// Report cached packages by license and license policy status
+    License {
+        #[command(subcommand)]
+        action: LicenseCommand,
+    },
+    /// Report cached versions affected by imported advisories, or audit a lockfile
+    Audit {
+        /// Path to the configuration file
+        #[arg(long, default_value = "vein.toml")]
+        config: PathBuf,
+        /// Only audit one ecosystem (rubygems, crates, npm)
+        #[arg(long)]
+        ecosystem: Option<String>,
+        /// Minimum severity to report (low, moderate, high, critical)
+        #[arg(long, default_value = "unknown")]
+        min_severity: String,
+        /// Print findings as JSON
+        #[arg(long)]
+        json: bool,
+        /// Audit the dependencies pinned by a lockfile instead of the whole cache
+        /// (Gemfile.lock, Cargo.lock, package-lock.json, pnpm-lock.yaml)
+        #[arg(long)]
+        lockfile: Option<PathBuf>,
+        /// Send the lockfile to a running Vein instance instead of reading the
+        /// local cache (e.g. http://vein.internal:8346)
+        #[arg(long, requires = "lockfile")]
+        server: Option<String>,
+    },
+    /// Pull every artifact pinned by a lockfile into the cache
+    Prefetch {
+        /// Path to the configuration file
+        #[arg(long, default_value = "vein.toml")]
+        config: PathBuf,
+        /// Lockfile to warm (Gemfile.lock, Cargo.lock, package-lock.json, pnpm-lock.yaml)
+        #[arg(long)]
+        lockfile: PathBuf,
+        /// Ask a running Vein instance to prefetch instead of fetching locally
+        #[arg(long)]
+        server: Option<String>,
+        /// Maximum number of artifacts fetched in parallel
+        #[arg(long, default_value_t = vein::proxy::prefetch::DEFAULT_PREFETCH_CONCURRENCY)]
+        concurrency: usize,
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Write one SBOM covering every package a lockfile pins
+    #[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+    Sbom {
+        #[command(subcommand)]
+        action: Option<SbomCommand>,
+        /// Path to the configuration file
+        #[arg(long, default_value = "vein.toml")]
+        config: PathBuf,
+        /// Lockfile to describe (Gemfile.lock, Cargo.lock, package-lock.json, pnpm-lock.yaml)
+        #[arg(long, required = true)]
+        lockfile: Option<PathBuf>,
+        /// Output format (cyclonedx, spdx, spdx-tag-value, spdx3)
+        #[arg(long, default_value = "cyclonedx")]
+        format: String,
+        /// Application name recorded in the SBOM (defaults to the lockfile's directory)
+        #[arg(long)]
+        name: Option<String>,
+        /// File to write instead of stdout
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+        /// Ask a running Vein instance instead of reading the local cache
+        #[arg(long)]
+        server: Option<String>,
+    },
+    /// Write cached artifacts, index rows and metadata into a bundle for an air-gapped instance
+    Export {
+        /// Path to the configuration file
+        #[arg(long, default_value = "vein.toml")]
+        config: PathBuf,
+        /// Bundle to write (gzip-compressed when ending in .gz or .tgz)
+        #[arg(long, short)]
+        output: PathBuf,
+        /// Only export these ecosystems (rubygems, crates, npm); repeatable
+        #[arg(long)]
+        ecosystem: Vec<String>,
+        /// Only export packages matching these name globs; repeatable
+        #[arg(long)]
+        name: Vec<String>,
+        /// Only export the versions pinned by this lockfile
+        #[arg(long)]
+        lockfile: Option<PathBuf>,
+        /// Only export artifacts cached or served since this date (YYYY-MM-DD or RFC 3339)
+        #[arg(long)]
+        since: Option<String>,
+    },
+    /// Verify a bundle written by `vein export` and load it into this instance
+    Import {
+        /// Path to the configuration file
+        #[arg(long, default_value = "vein.toml")]
+        config: PathBuf,
+        /// Bundle to import
+        bundle: PathBuf,
+        /// Only verify checksums, do not write anything
+        #[arg(long)]
+        verify_only: bool,
+    },
     /// Initialize a new vein configuration file
     Init {
         /// Output path for config file
@@ -63,6 +170,53 @@ pub(crate) enum CatalogCommand {
     },
 }
 
+#[derive(Debug, Subcommand)]
+pub(crate) enum SbomCommand {
+    /// Re-parse cached artifacts and regenerate missing or outdated SBOMs
+    Backfill {
+        /// Path to the configuration file
+        #[arg(long, default_value = "vein.toml")]
+        config: PathBuf,
+        /// Also regenerate SBOMs the running version already wrote
+        #[arg(long)]
+        force: bool,
+        /// Only backfill these ecosystems (rubygems, crates, npm); repeatable
+        #[arg(long)]
+        ecosystem: Vec<String>,
+        /// Maximum number of artifacts parsed in parallel
+        #[arg(long, default_value_t = vein::sbom::backfill::DEFAULT_BACKFILL_CONCURRENCY)]
+        concurrency: usize,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub(crate) enum AdvisoriesCommand {
+    /// Import an OSV dump (directory or .zip) into the cache index
+    Import {
+        /// Path to the configuration file
+        #[arg(long, default_value = "vein.toml")]
+        config: PathBuf,
+        /// OSV dump to import (defaults to advisories.path)
+        path: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub(crate) enum LicenseCommand {
+    /// Export every cached package with its license and policy verdict
+    Report {
+        /// Path to the configuration file
+        #[arg(long, default_value = "vein.toml")]
+        config: PathBuf,
+        /// Output format (csv, json)
+        #[arg(long, default_value = "csv")]
+        format: String,
+        /// File to write instead of stdout
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+}
+
 #[derive(Debug, Subcommand)]
 pub(crate) enum QuarantineCommand {
     /// Show quarantine statistics
@@ -101,6 +255,9 @@ pub(crate) enum QuarantineCommand {
         /// Reason for approval
         #[arg(long, default_value = "cli approval")]
         reason: String,
+        /// Name recorded in the audit trail (defaults to $USER)
+        #[arg(long)]
+        actor: Option<String>,
     },
     /// Block a specific gem version (mark as yanked)
     Block {
@@ -117,5 +274,22 @@ pub(crate) enum QuarantineCommand {
         /// Reason for blocking
         #[arg(long, default_value = "cli block")]
         reason: String,
+        /// Name recorded in the audit trail (defaults to $USER)
+        #[arg(long)]
+        actor: Option<String>,
+    },
+    /// Show the approval/block audit trail for a gem
+    History {
+        /// Path to the configuration file
+        #[arg(long, default_value = "vein.toml")]
+        config: PathBuf,
+        /// Gem name
+        gem: String,
+        /// Only show events for this version
+        #[arg(long)]
+        version: Option<String>,
+        /// Maximum number of entries to show
+        #[arg(long, default_value_t = 50)]
+        limit: u32,
     },
 }
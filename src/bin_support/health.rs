@@ -2,9 +2,11 @@ use std::time::Duration;
 
 use anyhow::{Result, bail};
 use rama::Layer;
+use rama::http::BodyExtractExt;
 use rama::http::client::EasyHttpWebClient;
 use rama::http::layer::timeout::TimeoutLayer;
 use rama::http::service::client::HttpClientExt;
+use vein::health::{ComponentCheck, ReadinessReport};
 
 use super::setup::build_current_thread_runtime;
 
@@ -21,11 +23,52 @@ pub(crate) fn run_health(url: String, timeout: u64) -> Result<()> {
             .await
             .map_err(|e| anyhow::anyhow!("sending health check request: {e}"))?;
 
-        if response.status().is_success() {
-            println!("Vein healthy: {}", response.status());
+        let status = response.status();
+        let body = response
+            .try_into_string()
+            .await
+            .map_err(|e| anyhow::anyhow!("reading health check response: {e}"))?;
+
+        // `/livez` and older servers answer without a component breakdown.
+        if let Ok(report) = serde_json::from_str::<ReadinessReport>(&body) {
+            print_report(&report);
+        }
+
+        if status.is_success() {
+            println!("Vein healthy: {status}");
             Ok(())
         } else {
-            bail!("health endpoint returned status {}", response.status());
+            bail!("health endpoint returned status {status}");
         }
     })
 }
+
+fn print_report(report: &ReadinessReport) {
+    println!(
+        "{:<18} {:<8} {:<9} {:>10}  DETAIL",
+        "COMPONENT", "STATUS", "LEVEL", "MEASURE"
+    );
+    for check in &report.checks {
+        println!(
+            "{:<18} {:<8} {:<9} {:>10}  {}",
+            check.component,
+            check.status.as_str(),
+            check.criticality.as_str(),
+            measure(check),
+            check.detail.as_deref().unwrap_or("")
+        );
+    }
+    println!("Readiness: {}", report.status.as_str());
+}
+
+fn measure(check: &ComponentCheck) -> String {
+    if let Some(latency) = check.latency_ms {
+        format!("{latency:.1}ms")
+    } else if let Some(age) = check.age_secs {
+        format!("{age}s ago")
+    } else if let Some(free) = check.free_bytes {
+        format!("{}MiB", free / (1024 * 1024))
+    } else {
+        String::new()
+    }
+}
@@ -0,0 +1,45 @@
+use std::path::PathBuf;
+
+use anyhow::{Context, Result, bail};
+use vein::license;
+
+use super::setup::{build_current_thread_runtime, connect_cache_index, init_tracing, load_config};
+
+pub(crate) fn run_license_report(
+    config_path: PathBuf,
+    format: String,
+    output: Option<PathBuf>,
+) -> Result<()> {
+    let config = load_config(config_path)?;
+    init_tracing(&config)?;
+
+    let rt = build_current_thread_runtime("license")?;
+    let (index, _) = connect_cache_index(&rt, &config)?;
+    let entries = rt
+        .block_on(license::license_report(&config.license_policy, &index))
+        .context("building license report")?;
+
+    let document = match format.as_str() {
+        "csv" => license::report_csv(&entries),
+        "json" => format!("{}\n", serde_json::to_string_pretty(&entries)?),
+        other => bail!("unknown report format '{other}' (expected csv or json)"),
+    };
+
+    match output {
+        Some(path) => {
+            std::fs::write(&path, document)
+                .with_context(|| format!("writing license report to {}", path.display()))?;
+            let violations = entries
+                .iter()
+                .filter(|entry| entry.status == "violation")
+                .count();
+            eprintln!(
+                "Wrote {} packages ({violations} violations) to {}",
+                entries.len(),
+                path.display()
+            );
+        }
+        None => print!("{document}"),
+    }
+    Ok(())
+}
@@ -0,0 +1,114 @@
+use std::{path::PathBuf, sync::Arc};
+
+use anyhow::{Context, Result, bail};
+use rama::tls::rustls::dep::rustls;
+use vein::{
+    lockfile::Lockfile,
+    proxy::{
+        VeinProxy,
+        prefetch::{PrefetchOutcome, PrefetchReport},
+    },
+};
+use vein_adapter::FilesystemStorage;
+
+use super::{
+    audit::{LockfileTarget, lockfile_url, post_lockfile},
+    setup::{build_current_thread_runtime, connect_cache_index, init_tracing, load_config},
+};
+
+pub(crate) fn run_prefetch(
+    target: LockfileTarget,
+    lockfile_path: PathBuf,
+    concurrency: usize,
+    json: bool,
+) -> Result<()> {
+    let _ = rustls::crypto::aws_lc_rs::default_provider().install_default();
+
+    let content = std::fs::read_to_string(&lockfile_path)
+        .with_context(|| format!("reading lockfile {}", lockfile_path.display()))?;
+    let file_name = lockfile_path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .map(str::to_string);
+
+    let report = match target {
+        LockfileTarget::Local(config_path) => {
+            let config = load_config(config_path)?;
+            config
+                .storage
+                .ensure_directories()
+                .context("creating storage directories")?;
+            init_tracing(&config)?;
+            let lockfile = Lockfile::parse(file_name.as_deref(), &content)
+                .with_context(|| format!("parsing {}", lockfile_path.display()))?;
+
+            let rt = build_current_thread_runtime("prefetch")?;
+            let storage = Arc::new(FilesystemStorage::new(config.storage.path.clone()));
+            rt.block_on(storage.prepare())
+                .context("preparing storage directory")?;
+            let (index, _) = connect_cache_index(&rt, &config)?;
+            let proxy = VeinProxy::new(config, storage, index).context("creating proxy service")?;
+            rt.block_on(proxy.prefetch(&lockfile, concurrency))
+        }
+        LockfileTarget::Server(base) => {
+            let rt = build_current_thread_runtime("prefetch")?;
+            let url = lockfile_url(
+                &base,
+                "/.well-known/vein/prefetch",
+                file_name.as_deref(),
+                &format!("concurrency={concurrency}"),
+            );
+            rt.block_on(post_lockfile::<PrefetchReport>(&url, content))?
+        }
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print_report(&report);
+    }
+
+    if report.summary.failed > 0 {
+        bail!(
+            "{} artifacts could not be prefetched",
+            report.summary.failed
+        );
+    }
+    Ok(())
+}
+
+fn print_report(report: &PrefetchReport) {
+    for item in &report.items {
+        if item.outcome == PrefetchOutcome::Hit && !item.quarantined {
+            continue;
+        }
+        let version = match &item.platform {
+            Some(platform) => format!("{}-{platform}", item.version),
+            None => item.version.clone(),
+        };
+        let outcome = match item.outcome {
+            PrefetchOutcome::Hit => "hit",
+            PrefetchOutcome::Cached => "cached",
+            PrefetchOutcome::Blocked => "blocked",
+            PrefetchOutcome::Failed => "failed",
+        };
+        let note = match (&item.error, item.quarantined) {
+            (Some(error), _) => error.clone(),
+            (None, true) => "quarantined".to_string(),
+            (None, false) => String::new(),
+        };
+        println!("{:<8} {:<30} {:<18} {}", outcome, item.name, version, note);
+    }
+
+    let summary = &report.summary;
+    println!();
+    println!(
+        "{} artifacts: {} already cached, {} newly cached, {} quarantined, {} blocked, {} failed",
+        summary.total,
+        summary.hits,
+        summary.cached,
+        summary.quarantined,
+        summary.blocked,
+        summary.failed
+    );
+}
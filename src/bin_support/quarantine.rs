@@ -1,9 +1,14 @@
 use std::{path::PathBuf, sync::Arc};
 
 use anyhow::{Context, Result};
+use chrono::{Duration, Utc};
 use tokio::runtime::Runtime;
-use vein::{config::Config, quarantine};
-use vein_adapter::{CacheBackend, CacheBackendTrait, VersionStatus};
+use vein::{
+    config::Config,
+    notifications,
+    quarantine::{self, ApprovalOutcome, Approver, risk},
+};
+use vein_adapter::{CacheBackend, CacheBackendTrait};
 
 use super::setup::{build_current_thread_runtime, connect_cache_index, load_config};
 
@@ -14,11 +19,17 @@ struct QuarantineContext {
 }
 
 pub(crate) fn run_quarantine_status(config_path: PathBuf) -> Result<()> {
-    let Some(ctx) = load_quarantine_context(config_path)? else {
+    let config = load_config(config_path)?;
+    if !config.delay_policy.enabled {
         println!("Quarantine feature is disabled in configuration.");
         println!("Enable it by setting delay_policy.enabled = true in vein.toml");
+        // Typosquat detection reports here even without delays
+        if config.typosquat.enabled {
+            print_suspicious_names(&open_quarantine_context(config)?)?;
+        }
         return Ok(());
-    };
+    }
+    let ctx = open_quarantine_context(config)?;
 
     let stats = ctx
         .rt
@@ -44,6 +55,78 @@ pub(crate) fn run_quarantine_status(config_path: PathBuf) -> Result<()> {
     );
     println!("Skip weekends: {}", ctx.config.delay_policy.skip_weekends);
 
+    print_recent_yanks(&ctx)?;
+    print_suspicious_names(&ctx)?;
+
+    Ok(())
+}
+
+/// Lists cached versions that upstream has since yanked but clients still fetch.
+fn print_recent_yanks(ctx: &QuarantineContext) -> Result<()> {
+    if !ctx.config.yank_sync.enabled {
+        return Ok(());
+    }
+
+    let since = Utc::now() - Duration::days(i64::from(ctx.config.yank_sync.recent_days));
+    let yanks = ctx
+        .rt
+        .block_on(ctx.index.recently_downloaded_yanks(since, 20))
+        .context("fetching recently downloaded yanked versions")?;
+
+    println!();
+    println!(
+        "Yanked upstream, downloaded in the last {} days: {}",
+        ctx.config.yank_sync.recent_days,
+        yanks.len()
+    );
+    for entry in yanks {
+        let platform = entry.yank.platform.as_deref().unwrap_or("-");
+        println!(
+            "  {:<8} {:<30} {:<15} {:<15} last {} ({})",
+            entry.yank.ecosystem,
+            entry.yank.name,
+            entry.yank.version,
+            platform,
+            entry.last_accessed,
+            entry.yank.reason
+        );
+    }
+
+    Ok(())
+}
+
+/// Lists the latest requests for names that look like typosquats or
+/// dependency confusion.
+fn print_suspicious_names(ctx: &QuarantineContext) -> Result<()> {
+    if !ctx.config.typosquat.enabled {
+        return Ok(());
+    }
+
+    let (total, names) = ctx
+        .rt
+        .block_on(async {
+            Ok::<_, anyhow::Error>((
+                ctx.index.suspicious_name_count().await?,
+                ctx.index.suspicious_names(20).await?,
+            ))
+        })
+        .context("fetching suspicious names")?;
+
+    println!();
+    println!("Suspicious names requested: {total}");
+    for entry in names {
+        println!(
+            "  {:<8} {:<30} {:<15} {:<30} {:<10} {} hit(s), last {}",
+            entry.ecosystem,
+            entry.name,
+            entry.technique,
+            entry.similar_to.as_deref().unwrap_or("-"),
+            entry.action,
+            entry.hits,
+            entry.last_seen
+        );
+    }
+
     Ok(())
 }
 
@@ -71,14 +154,29 @@ pub(crate) fn run_quarantine_list(config_path: PathBuf, limit: u32) -> Result<()
 
     for version in versions {
         let platform = version.platform.as_deref().unwrap_or("ruby");
+        let available_after = if risk::awaits_manual_approval(&version) {
+            "manual approval".to_string()
+        } else {
+            version
+                .available_after
+                .format("%Y-%m-%d %H:%M UTC")
+                .to_string()
+        };
         println!(
             "{:<30} {:<15} {:<15} {:<10} {}",
             version.name,
             version.version,
             platform,
             format!("{:?}", version.status),
-            version.available_after.format("%Y-%m-%d %H:%M UTC")
+            available_after
         );
+        if let Some(reason) = version
+            .status_reason
+            .as_deref()
+            .filter(|reason| reason.starts_with("risk:") || reason.starts_with("suspicious name:"))
+        {
+            println!("  {reason}");
+        }
     }
 
     Ok(())
@@ -94,6 +192,7 @@ pub(crate) fn run_quarantine_promote(config_path: PathBuf) -> Result<()> {
         .rt
         .block_on(quarantine::promote_now(ctx.index.as_ref()))
         .context("promoting expired quarantines")?;
+    ctx.rt.block_on(notifications::flush());
 
     if count > 0 {
         println!(
@@ -113,24 +212,45 @@ pub(crate) fn run_quarantine_approve(
     version: String,
     platform: Option<String>,
     reason: String,
+    actor: Option<String>,
 ) -> Result<()> {
     let Some(ctx) = load_quarantine_context(config_path)? else {
         println!("Quarantine feature is disabled in configuration.");
         return Ok(());
     };
 
-    set_version_status(
-        &ctx,
-        &gem,
-        &version,
-        platform.as_deref(),
-        VersionStatus::Pinned,
-        format!("approved: {reason}"),
-        "approving version",
-    )?;
+    let actor = resolve_actor(actor);
+    let outcome = ctx
+        .rt
+        .block_on(quarantine::approve_version(
+            ctx.index.as_ref(),
+            &ctx.config.delay_policy,
+            &gem,
+            &version,
+            platform.as_deref(),
+            Approver::Claimed(&actor),
+            &reason,
+        ))
+        .context("approving version")?;
+    ctx.rt.block_on(notifications::flush());
 
     let platform_str = platform.as_deref().unwrap_or("ruby");
-    println!("Approved {gem}-{version} ({platform_str}) for immediate availability.");
+    match outcome {
+        ApprovalOutcome::Released { approvers } => {
+            println!("Approved {gem}-{version} ({platform_str}) for immediate availability.");
+            println!("Approvers: {}", approvers.join(", "));
+        }
+        ApprovalOutcome::Pending {
+            approvers,
+            required,
+        } => {
+            println!(
+                "Recorded approval for {gem}-{version} ({platform_str}): {} of {required} approvers.",
+                approvers.len()
+            );
+            println!("Approvers so far: {}", approvers.join(", "));
+        }
+    }
     println!("Reason: {reason}");
 
     Ok(())
@@ -142,21 +262,25 @@ pub(crate) fn run_quarantine_block(
     version: String,
     platform: Option<String>,
     reason: String,
+    actor: Option<String>,
 ) -> Result<()> {
     let Some(ctx) = load_quarantine_context(config_path)? else {
         println!("Quarantine feature is disabled in configuration.");
         return Ok(());
     };
 
-    set_version_status(
-        &ctx,
-        &gem,
-        &version,
-        platform.as_deref(),
-        VersionStatus::Yanked,
-        format!("blocked: {reason}"),
-        "blocking version",
-    )?;
+    let actor = resolve_actor(actor);
+    ctx.rt
+        .block_on(quarantine::block_version(
+            ctx.index.as_ref(),
+            &gem,
+            &version,
+            platform.as_deref(),
+            &actor,
+            &reason,
+        ))
+        .context("blocking version")?;
+    ctx.rt.block_on(notifications::flush());
 
     let platform_str = platform.as_deref().unwrap_or("ruby");
     println!("Blocked {gem}-{version} ({platform_str}).");
@@ -165,33 +289,74 @@ pub(crate) fn run_quarantine_block(
     Ok(())
 }
 
-/// Applies a quarantine decision (status + audit note) to a gem version.
-fn set_version_status(
-    ctx: &QuarantineContext,
-    gem: &str,
-    version: &str,
-    platform: Option<&str>,
-    status: VersionStatus,
-    note: String,
-    context_msg: &'static str,
+pub(crate) fn run_quarantine_history(
+    config_path: PathBuf,
+    gem: String,
+    version: Option<String>,
+    limit: u32,
 ) -> Result<()> {
-    ctx.rt
-        .block_on(
-            ctx.index
-                .update_version_status(gem, version, platform, status, Some(note)),
-        )
-        .context(context_msg)?;
+    let Some(ctx) = load_quarantine_context(config_path)? else {
+        println!("Quarantine feature is disabled in configuration.");
+        return Ok(());
+    };
+
+    let events = ctx
+        .rt
+        .block_on(ctx.index.quarantine_events(&gem, version.as_deref(), limit))
+        .context("fetching quarantine history")?;
+
+    if events.is_empty() {
+        println!("No quarantine decisions recorded for {gem}.");
+        return Ok(());
+    }
+
+    println!(
+        "{:<20} {:<15} {:<15} {:<8} {:<16} {:<11} REASON",
+        "WHEN", "VERSION", "PLATFORM", "ACTION", "ACTOR", "PREVIOUS"
+    );
+    println!("{}", "-".repeat(110));
+
+    for event in events {
+        let previous = event
+            .previous_status
+            .map(|status| status.to_string())
+            .unwrap_or_else(|| "-".to_string());
+        println!(
+            "{:<20} {:<15} {:<15} {:<8} {:<16} {:<11} {}",
+            event.created_at.format("%Y-%m-%d %H:%M UTC"),
+            event.version,
+            event.platform.as_deref().unwrap_or("ruby"),
+            event.action,
+            event.actor,
+            previous,
+            event.reason.as_deref().unwrap_or("")
+        );
+    }
+
     Ok(())
 }
 
+/// Name recorded as the actor for CLI decisions.
+fn resolve_actor(actor: Option<String>) -> String {
+    actor
+        .or_else(|| std::env::var("USER").ok())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "cli".to_string())
+}
+
 fn load_quarantine_context(config_path: PathBuf) -> Result<Option<QuarantineContext>> {
     let config = load_config(config_path)?;
     if !config.delay_policy.enabled {
         return Ok(None);
     }
 
+    open_quarantine_context(config).map(Some)
+}
+
+fn open_quarantine_context(config: Arc<Config>) -> Result<QuarantineContext> {
     let rt = build_current_thread_runtime("quarantine")?;
     let (index, _) = connect_cache_index(&rt, &config)?;
+    notifications::install(&config.notifications);
 
-    Ok(Some(QuarantineContext { config, rt, index }))
+    Ok(QuarantineContext { config, rt, index })
 }
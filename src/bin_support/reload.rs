@@ -0,0 +1,118 @@
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use anyhow::{Context, Result, bail};
+use rama::telemetry::tracing::{self, subscriber::EnvFilter};
+use vein::{config::Config, proxy::VeinProxy};
+
+use super::setup::LogFilterHandle;
+
+/// How often the config file's modification time is checked.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Reloads `config_path` into `proxy` on SIGHUP or when the file changes.
+pub(crate) async fn watch_config(
+    config_path: PathBuf,
+    proxy: VeinProxy,
+    log_filter: LogFilterHandle,
+) {
+    let mut hangup = hangup_signal();
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+    let mut last_modified = modified(&config_path);
+
+    loop {
+        let trigger = tokio::select! {
+            _ = recv_hangup(&mut hangup) => "SIGHUP",
+            _ = interval.tick() => {
+                let current = modified(&config_path);
+                if current == last_modified {
+                    continue;
+                }
+                last_modified = current;
+                "file change"
+            }
+        };
+
+        match reload(&config_path, &proxy, &log_filter) {
+            Ok(()) => tracing::info!(
+                path = %config_path.display(),
+                trigger,
+                "configuration reloaded"
+            ),
+            Err(err) => tracing::error!(
+                path = %config_path.display(),
+                trigger,
+                error = %format!("{err:#}"),
+                "configuration reload failed, keeping current configuration"
+            ),
+        }
+    }
+}
+
+fn reload(config_path: &Path, proxy: &VeinProxy, log_filter: &LogFilterHandle) -> Result<()> {
+    // `Config::load` falls back to defaults for a missing file.
+    if !config_path.exists() {
+        bail!("{} not found", config_path.display());
+    }
+    let next = Config::load(Some(config_path.to_path_buf())).context("loading configuration")?;
+    // RUST_LOG takes precedence over the configured level, as at startup.
+    let filter = if std::env::var_os("RUST_LOG").is_none() {
+        Some(EnvFilter::try_new(&next.logging.level).context("building log filter")?)
+    } else {
+        None
+    };
+
+    for setting in proxy.reload(next)? {
+        tracing::warn!(
+            setting,
+            "{setting} changed but only takes effect on restart; keeping the running value"
+        );
+    }
+
+    if let Some(filter) = filter {
+        log_filter.reload(filter).context("swapping log filter")?;
+    }
+    Ok(())
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+}
+
+#[cfg(unix)]
+type Hangup = Option<tokio::signal::unix::Signal>;
+
+#[cfg(unix)]
+fn hangup_signal() -> Hangup {
+    use tokio::signal::unix::{SignalKind, signal};
+
+    signal(SignalKind::hangup())
+        .inspect_err(|err| tracing::warn!(error = %err, "cannot listen for SIGHUP"))
+        .ok()
+}
+
+#[cfg(unix)]
+async fn recv_hangup(hangup: &mut Hangup) {
+    let closed = match hangup {
+        Some(signal) => signal.recv().await.is_none(),
+        None => std::future::pending().await,
+    };
+    if closed {
+        *hangup = None;
+    }
+}
+
+#[cfg(not(unix))]
+type Hangup = ();
+
+#[cfg(not(unix))]
+fn hangup_signal() -> Hangup {}
+
+#[cfg(not(unix))]
+async fn recv_hangup(_hangup: &mut Hangup) {
+    std::future::pending().await
+}
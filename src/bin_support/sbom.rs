@@ -0,0 +1,155 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, anyhow, bail};
+use rama::tls::rustls::dep::rustls;
+use vein::{
+    lockfile::Lockfile,
+    sbom::{
+        self, SBOM_GENERATOR, SbomFormat,
+        backfill::{self, BackfillOptions, BackfillOutcome},
+    },
+};
+use vein_adapter::{CacheBackendTrait, Ecosystem, FilesystemStorage};
+
+use super::{
+    audit::{LockfileTarget, lockfile_url, post_lockfile_raw},
+    setup::{build_current_thread_runtime, connect_cache_index, init_tracing, load_config},
+};
+
+pub(crate) fn run_lockfile_sbom(
+    target: LockfileTarget,
+    lockfile_path: PathBuf,
+    format: String,
+    name: Option<String>,
+    output: Option<PathBuf>,
+) -> Result<()> {
+    let sbom_format = format
+        .parse::<SbomFormat>()
+        .map_err(|_| anyhow!("unknown SBOM format '{format}'"))?;
+    let content = std::fs::read_to_string(&lockfile_path)
+        .with_context(|| format!("reading lockfile {}", lockfile_path.display()))?;
+    let file_name = lockfile_path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .map(str::to_string);
+    let name = name.unwrap_or_else(|| application_name(&lockfile_path));
+
+    let document = match target {
+        LockfileTarget::Local(config_path) => {
+            let config = load_config(config_path)?;
+            init_tracing(&config)?;
+            let lockfile = Lockfile::parse(file_name.as_deref(), &content)
+                .with_context(|| format!("parsing {}", lockfile_path.display()))?;
+
+            let rt = build_current_thread_runtime("sbom")?;
+            let (index, _) = connect_cache_index(&rt, &config)?;
+            rt.block_on(sbom::lockfile_sbom(&index, &lockfile, &name, sbom_format))?
+        }
+        LockfileTarget::Server(base) => {
+            let _ = rustls::crypto::aws_lc_rs::default_provider().install_default();
+            let rt = build_current_thread_runtime("sbom")?;
+            let query = format!(
+                "format={format}&name={}",
+                percent_encoding::utf8_percent_encode(&name, percent_encoding::NON_ALPHANUMERIC)
+            );
+            let url = lockfile_url(
+                &base,
+                "/.well-known/vein/sbom",
+                file_name.as_deref(),
+                &query,
+            );
+            let body = rt.block_on(post_lockfile_raw(&url, content))?;
+            String::from_utf8(body).context("SBOM response is not UTF-8")?
+        }
+    };
+
+    match output {
+        Some(path) => {
+            std::fs::write(&path, document)
+                .with_context(|| format!("writing SBOM to {}", path.display()))?;
+            eprintln!("Wrote {} SBOM to {}", format, path.display());
+        }
+        None => println!("{document}"),
+    }
+    Ok(())
+}
+
+pub(crate) fn run_backfill(
+    config_path: PathBuf,
+    force: bool,
+    ecosystems: Vec<String>,
+    concurrency: usize,
+) -> Result<()> {
+    let config = load_config(config_path)?;
+    init_tracing(&config)?;
+
+    let ecosystems = ecosystems
+        .iter()
+        .map(|name| {
+            name.parse::<Ecosystem>()
+                .map_err(|_| anyhow!("unknown ecosystem '{name}'"))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let options = BackfillOptions {
+        force,
+        ecosystems,
+        concurrency,
+    };
+
+    let rt = build_current_thread_runtime("sbom backfill")?;
+    let (index, _) = connect_cache_index(&rt, &config)?;
+    let storage = FilesystemStorage::new(config.storage.path.clone());
+    let summary = rt.block_on(backfill::backfill_sboms(
+        index.clone(),
+        storage,
+        &options,
+        |done, total, item| {
+            if item.outcome == BackfillOutcome::Failed {
+                eprintln!(
+                    "\r  failed {} {} {}: {}",
+                    item.ecosystem,
+                    item.name,
+                    item.version,
+                    item.error.as_deref().unwrap_or("unknown error")
+                );
+            }
+            eprint!("\rBackfilling SBOMs: {done}/{total}");
+            if done == total {
+                eprintln!();
+            }
+        },
+    ))?;
+
+    println!("SBOM backfill ({SBOM_GENERATOR})");
+    println!("  artifacts:    {}", summary.total);
+    println!("  regenerated:  {}", summary.regenerated);
+    println!("  up to date:   {}", summary.up_to_date);
+    println!("  no metadata:  {}", summary.no_metadata);
+    println!("  missing:      {}", summary.missing);
+    println!("  failed:       {}", summary.failed);
+
+    let coverage = rt
+        .block_on(index.sbom_coverage())
+        .context("loading SBOM coverage")?;
+    println!(
+        "Gem SBOM coverage: {}/{}",
+        coverage.with_sbom, coverage.metadata_rows
+    );
+
+    if summary.failed > 0 {
+        bail!("{} artifact(s) failed to backfill", summary.failed);
+    }
+    Ok(())
+}
+
+/// The directory holding the lockfile, which is usually the project root.
+fn application_name(lockfile_path: &Path) -> String {
+    std::path::absolute(lockfile_path)
+        .ok()
+        .as_deref()
+        .and_then(Path::parent)
+        .and_then(Path::file_name)
+        .and_then(|name| name.to_str())
+        .map(str::to_string)
+        .unwrap_or_else(|| "application".to_string())
+}
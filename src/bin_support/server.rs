@@ -17,9 +17,12 @@ use rama::{
     telemetry::tracing,
     tls::rustls::dep::rustls,
 };
-use vein::{proxy::VeinProxy, quarantine};
+use vein::{
+    advisories, notifications, proxy::VeinProxy, quarantine, rate_limit::RateLimitLayer, yank_sync,
+};
 use vein_adapter::FilesystemStorage;
 
+use super::reload::watch_config;
 use super::setup::{
     build_current_thread_runtime, connect_cache_index, init_tracing, load_validated_config,
 };
@@ -27,7 +30,7 @@ use super::setup::{
 pub(crate) fn run_server(config_path: PathBuf) -> Result<()> {
     let _ = rustls::crypto::aws_lc_rs::default_provider().install_default();
 
-    let config = load_validated_config(config_path)?;
+    let config = load_validated_config(config_path.clone())?;
 
     config
         .storage
@@ -38,7 +41,7 @@ pub(crate) fn run_server(config_path: PathBuf) -> Result<()> {
         .ensure_directories()
         .context("creating database directories")?;
 
-    init_tracing(&config)?;
+    let log_filter = init_tracing(&config)?;
 
     let setup_rt = build_current_thread_runtime("setup")?;
 
@@ -49,7 +52,10 @@ pub(crate) fn run_server(config_path: PathBuf) -> Result<()> {
 
     let (index, _) = connect_cache_index(&setup_rt, &config)?;
 
+    notifications::install(&config.notifications);
     quarantine::spawn_promotion_scheduler(&config.delay_policy, index.clone(), None);
+    yank_sync::spawn_yank_sync_scheduler(config.clone(), index.clone());
+    advisories::spawn_startup_import(&config.advisories, index.clone());
 
     drop(setup_rt);
 
@@ -67,6 +73,8 @@ pub(crate) fn run_server(config_path: PathBuf) -> Result<()> {
 
         tracing::info!(%addr, "starting Rama HTTP server");
 
+        tokio::spawn(watch_config(config_path, proxy.clone(), log_filter));
+
         graceful.spawn_task_fn(move |guard| {
             let proxy = proxy.clone();
             let addr = addr.clone();
@@ -76,6 +84,7 @@ pub(crate) fn run_server(config_path: PathBuf) -> Result<()> {
                     .await
                     .expect("bind tcp proxy");
 
+                let rate_limit = RateLimitLayer::new(&config.rate_limit);
                 let exec = Executor::graceful(guard.clone());
                 let http_service = HttpServer::auto(exec).service(
                     (
@@ -83,6 +92,7 @@ pub(crate) fn run_server(config_path: PathBuf) -> Result<()> {
                         PropagateRequestIdLayer::x_request_id(),
                         TraceLayer::new_for_http(),
                         ConsumeErrLayer::default(),
+                        rate_limit,
                     )
                         .into_layer(proxy),
                 );
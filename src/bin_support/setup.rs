@@ -11,7 +11,8 @@ use rama::telemetry::{
     tracing::{
         self,
         subscriber::{
-            self, EnvFilter, Layer as _, layer::SubscriberExt as _, util::SubscriberInitExt as _,
+            self, EnvFilter, Layer as _, Registry, layer::SubscriberExt as _, reload,
+            util::SubscriberInitExt as _,
         },
     },
 };
@@ -49,10 +50,14 @@ pub(crate) fn connect_cache_index(
         .context("connecting to cache index")
 }
 
-pub(crate) fn init_tracing(config: &Config) -> Result<()> {
+/// Handle for swapping the log filter when the config is reloaded.
+pub(crate) type LogFilterHandle = reload::Handle<EnvFilter, Registry>;
+
+pub(crate) fn init_tracing(config: &Config) -> Result<LogFilterHandle> {
     let filter = EnvFilter::try_from_default_env()
         .or_else(|_| EnvFilter::try_new(&config.logging.level))
         .context("building log filter")?;
+    let (filter, filter_handle) = reload::Layer::new(filter);
 
     let fmt_layer = if config.logging.json {
         subscriber::fmt::layer()
@@ -94,5 +99,5 @@ pub(crate) fn init_tracing(config: &Config) -> Result<()> {
         registry.try_init()?;
     }
 
-    Ok(())
+    Ok(filter_handle)
 }
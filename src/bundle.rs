@@ -0,0 +1,342 @@
+//! Air-gapped export/import bundles.
+//!
+//! A bundle is a tar archive (optionally gzip-compressed) whose first entry is
+//! `manifest.json`, followed by every file under `files/<storage path>`. The
+//! manifest records the SHA-256 of each file together with the index rows,
+//! gem metadata (including SBOMs) and quarantine state needed to serve the
+//! artifacts from another instance without touching the network.
+
+mod export;
+mod import;
+
+use std::collections::HashSet;
+use std::io::Read;
+
+use anyhow::{Result, bail};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use vein_adapter::{AssetKey, AssetKind, Ecosystem, GemMetadata, GemVersion};
+
+use crate::lockfile::Lockfile;
+use crate::util::glob_match;
+
+pub use export::{ExportReport, export_bundle};
+pub use import::{ImportReport, import_bundle};
+
+/// Bundle layout version written by this build.
+pub const BUNDLE_FORMAT_VERSION: u32 = 1;
+
+const MANIFEST_ENTRY: &str = "manifest.json";
+const FILES_PREFIX: &str = "files/";
+
+/// Describes the contents of a bundle.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleManifest {
+    pub format_version: u32,
+    pub created_at: DateTime<Utc>,
+    /// Vein version that wrote the bundle.
+    pub generator: String,
+    pub artifacts: Vec<BundleArtifact>,
+    pub index_files: Vec<BundleIndexFile>,
+    pub gem_metadata: Vec<GemMetadata>,
+    pub gem_versions: Vec<GemVersion>,
+}
+
+/// A cached artifact and its `cached_assets` row.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleArtifact {
+    pub kind: String,
+    pub name: String,
+    pub version: String,
+    pub platform: Option<String>,
+    pub path: String,
+    pub sha256: String,
+    pub size_bytes: u64,
+}
+
+impl BundleArtifact {
+    fn asset_kind(&self) -> Option<AssetKind> {
+        match self.kind.as_str() {
+            "gem" => Some(AssetKind::Gem),
+            "gemspec" => Some(AssetKind::Spec),
+            "crate" => Some(AssetKind::Crate),
+            "npm" => Some(AssetKind::NpmPackage),
+            _ => None,
+        }
+    }
+
+    fn asset_key(&self, kind: AssetKind) -> AssetKey<'_> {
+        AssetKey {
+            kind,
+            name: &self.name,
+            version: &self.version,
+            platform: self.platform.as_deref(),
+        }
+    }
+}
+
+/// Cached index text (compact index, sparse index, packument) and the
+/// revalidation metadata stored for it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleIndexFile {
+    pub path: String,
+    pub sha256: String,
+    pub size_bytes: u64,
+    pub meta_key: String,
+    /// Raw `catalog_meta` value (ETag / Last-Modified JSON).
+    pub meta: Option<String>,
+}
+
+/// `(ecosystem, name, version, platform)` of a pinned package.
+type PinnedKey<'a> = (Ecosystem, &'a str, &'a str, Option<&'a str>);
+
+/// Selects what goes into an export. Empty lists match everything.
+#[derive(Debug, Default, Clone)]
+pub struct ExportFilter {
+    pub ecosystems: Vec<Ecosystem>,
+    /// Package name globs (`rails-*`, `@acme/*`).
+    pub names: Vec<String>,
+    /// Only the exact versions pinned by this lockfile.
+    pub lockfile: Option<Lockfile>,
+    /// Only artifacts served or cached at or after this instant.
+    pub since: Option<DateTime<Utc>>,
+}
+
+impl ExportFilter {
+    fn includes_ecosystem(&self, ecosystem: Ecosystem) -> bool {
+        self.ecosystems.is_empty() || self.ecosystems.contains(&ecosystem)
+    }
+
+    fn pinned(&self) -> Option<HashSet<PinnedKey<'_>>> {
+        self.lockfile.as_ref().map(|lockfile| {
+            lockfile
+                .packages
+                .iter()
+                .map(|package| {
+                    (
+                        package.ecosystem,
+                        package.name.as_str(),
+                        package.version.as_str(),
+                        package.platform.as_deref(),
+                    )
+                })
+                .collect()
+        })
+    }
+
+    fn includes_name(&self, name: &str) -> bool {
+        self.names.is_empty() || self.names.iter().any(|pattern| glob_match(pattern, name))
+    }
+
+    fn includes_access(&self, last_accessed: &str) -> bool {
+        let Some(since) = self.since else {
+            return true;
+        };
+        DateTime::parse_from_rfc3339(last_accessed)
+            .map(|accessed| accessed.with_timezone(&Utc) >= since)
+            .unwrap_or(true)
+    }
+}
+
+/// Storage paths come from untrusted manifests on import; only plain relative
+/// paths inside the cache directory are accepted.
+fn is_safe_path(path: &str) -> bool {
+    !path.is_empty()
+        && !path.starts_with('/')
+        && !path.contains('\\')
+        && path
+            .split('/')
+            .all(|segment| !segment.is_empty() && segment != "." && segment != "..")
+}
+
+fn hash_reader(mut reader: impl Read) -> Result<(String, u64)> {
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut size = 0u64;
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        size += read as u64;
+    }
+    Ok((hex::encode(hasher.finalize()), size))
+}
+
+fn check_manifest(manifest: &BundleManifest) -> Result<()> {
+    if manifest.format_version > BUNDLE_FORMAT_VERSION {
+        bail!(
+            "bundle format {} is newer than supported format {}",
+            manifest.format_version,
+            BUNDLE_FORMAT_VERSION
+        );
+    }
+    for artifact in &manifest.artifacts {
+        if artifact.asset_kind().is_none() {
+            bail!("unknown artifact kind '{}'", artifact.kind);
+        }
+        if !is_safe_path(&artifact.path) {
+            bail!("unsafe artifact path '{}'", artifact.path);
+        }
+    }
+    for file in &manifest.index_files {
+        if !is_safe_path(&file.path) {
+            bail!("unsafe index file path '{}'", file.path);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_paths_outside_the_cache() {
+        assert!(is_safe_path("gems/rack/rack-3.0.8.gem"));
+        assert!(is_safe_path("npm/@types__node/node-20.0.0.tgz"));
+        assert!(!is_safe_path("/etc/passwd"));
+        assert!(!is_safe_path("gems/../../etc/passwd"));
+        assert!(!is_safe_path("gems//rack"));
+        assert!(!is_safe_path("gems\\rack"));
+        assert!(!is_safe_path(""));
+    }
+
+    #[test]
+    fn filter_matches_globs_and_access_time() {
+        let filter = ExportFilter {
+            names: vec!["rails-*".to_string()],
+            since: Some("2025-01-01T00:00:00Z".parse().unwrap()),
+            ..ExportFilter::default()
+        };
+        assert!(filter.includes_name("rails-html-sanitizer"));
+        assert!(!filter.includes_name("rack"));
+        assert!(filter.includes_access("2025-02-01T00:00:00.000Z"));
+        assert!(!filter.includes_access("2024-12-31T23:59:59.000Z"));
+    }
+
+    #[cfg(feature = "sqlite")]
+    async fn seed_gem(
+        index: &vein_adapter::CacheBackend,
+        storage: &vein_adapter::FilesystemStorage,
+        name: &str,
+        version: &str,
+        body: &[u8],
+    ) {
+        use vein_adapter::CacheBackendTrait;
+
+        let path = format!("gems/{name}/{name}-{version}.gem");
+        let target = storage.resolve(&path);
+        tokio::fs::create_dir_all(target.parent().unwrap())
+            .await
+            .unwrap();
+        tokio::fs::write(&target, body).await.unwrap();
+        index
+            .insert_or_replace(
+                &AssetKey {
+                    kind: AssetKind::Gem,
+                    name,
+                    version,
+                    platform: None,
+                },
+                &path,
+                &hex::encode(Sha256::digest(body)),
+                body.len() as u64,
+            )
+            .await
+            .unwrap();
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn bundles_round_trip_and_reject_tampering() {
+        use vein_adapter::{CacheBackend, CacheBackendTrait, FilesystemStorage};
+
+        let source_dir = tempfile::tempdir().unwrap();
+        let source_storage = FilesystemStorage::new(source_dir.path().join("cache"));
+        source_storage.prepare().await.unwrap();
+        let source = CacheBackend::connect_memory().await.unwrap();
+
+        seed_gem(&source, &source_storage, "rack", "3.0.8", b"rack-gem-bytes").await;
+        seed_gem(
+            &source,
+            &source_storage,
+            "rails",
+            "8.0.0",
+            b"rails-gem-bytes",
+        )
+        .await;
+        let info = source_storage.resolve("compact_index/info/rack");
+        tokio::fs::create_dir_all(info.parent().unwrap())
+            .await
+            .unwrap();
+        tokio::fs::write(&info, "---\n3.0.8 |checksum:00\n")
+            .await
+            .unwrap();
+        source
+            .catalog_meta_set("compact:info:rack", r#"{"etag":"\"rack-v1\""}"#)
+            .await
+            .unwrap();
+
+        let filter = ExportFilter {
+            names: vec!["rack".to_string()],
+            ..ExportFilter::default()
+        };
+        let bundle = source_dir.path().join("bundle.tar");
+        let exported = export_bundle(&source, &source_storage, &filter, &bundle)
+            .await
+            .unwrap();
+        assert_eq!(exported.artifacts, 1);
+        assert_eq!(exported.index_files, 1);
+
+        let target_dir = tempfile::tempdir().unwrap();
+        let target_storage = FilesystemStorage::new(target_dir.path().join("cache"));
+        target_storage.prepare().await.unwrap();
+        let target = CacheBackend::connect_memory().await.unwrap();
+
+        let imported = import_bundle(&target, &target_storage, &bundle, false)
+            .await
+            .unwrap();
+        assert_eq!(imported.artifacts, 1);
+        let key = AssetKey {
+            kind: AssetKind::Gem,
+            name: "rack",
+            version: "3.0.8",
+            platform: None,
+        };
+        let asset = target.peek(&key).await.unwrap().unwrap();
+        assert_eq!(
+            tokio::fs::read(target_storage.resolve(&asset.path))
+                .await
+                .unwrap(),
+            b"rack-gem-bytes"
+        );
+        assert!(target_storage.resolve("compact_index/info/rack").exists());
+        assert_eq!(
+            target
+                .catalog_meta_get("compact:info:rack")
+                .await
+                .unwrap()
+                .as_deref(),
+            Some(r#"{"etag":"\"rack-v1\""}"#)
+        );
+
+        let mut raw = std::fs::read(&bundle).unwrap();
+        let offset = raw
+            .windows(b"rack-gem-bytes".len())
+            .position(|window| window == b"rack-gem-bytes")
+            .unwrap();
+        raw[offset] = b'R';
+        let tampered = source_dir.path().join("tampered.tar");
+        std::fs::write(&tampered, raw).unwrap();
+
+        let fresh = CacheBackend::connect_memory().await.unwrap();
+        let err = import_bundle(&fresh, &target_storage, &tampered, false)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("checksum mismatch"));
+        assert!(fresh.peek(&key).await.unwrap().is_none());
+    }
+}
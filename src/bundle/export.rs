@@ -0,0 +1,331 @@
+use std::collections::BTreeSet;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use flate2::{Compression, write::GzEncoder};
+use rama::telemetry::tracing;
+use serde::Serialize;
+use vein_adapter::{
+    AssetKey, AssetKind, CacheBackend, CacheBackendTrait, Ecosystem, FilesystemStorage,
+};
+
+use crate::util::{sanitize_filename, sanitize_npm_segment};
+
+use super::{
+    BUNDLE_FORMAT_VERSION, BundleArtifact, BundleIndexFile, BundleManifest, ExportFilter,
+    FILES_PREFIX, MANIFEST_ENTRY, hash_reader,
+};
+
+/// Outcome of writing a bundle.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ExportReport {
+    pub artifacts: usize,
+    pub index_files: usize,
+    pub gem_metadata: usize,
+    pub gem_versions: usize,
+    /// Indexed artifacts whose file is missing or fails its checksum.
+    pub skipped: usize,
+    pub bytes: u64,
+}
+
+/// Cached index text for a package, as `(storage path, catalog_meta key)`.
+/// Mirrors the layout used by the compact index, sparse index and npm
+/// metadata handlers.
+fn index_files_for(ecosystem: Ecosystem, name: &str) -> Option<(String, String)> {
+    match ecosystem {
+        Ecosystem::RubyGems => Some((
+            format!("compact_index/info/{}", sanitize_filename(name)),
+            format!("compact:info:{name}"),
+        )),
+        Ecosystem::CratesIo => crate::crates::index_path(name).map(|path| {
+            (
+                format!("crates_index/{path}"),
+                format!("crates:index:{name}"),
+            )
+        }),
+        Ecosystem::Npm => Some((
+            format!("npm_index/{}/metadata.json", sanitize_npm_segment(name)),
+            format!("npm:metadata:{name}"),
+        )),
+    }
+}
+
+/// Writes the artifacts selected by `filter` into a bundle at `output`.
+/// Files ending in `.gz` or `.tgz` are gzip-compressed.
+pub async fn export_bundle(
+    index: &CacheBackend,
+    storage: &FilesystemStorage,
+    filter: &ExportFilter,
+    output: &Path,
+) -> Result<ExportReport> {
+    let pinned = filter.pinned();
+    let mut artifacts = Vec::new();
+    let mut packages: BTreeSet<(Ecosystem, String)> = BTreeSet::new();
+
+    for kind in [
+        AssetKind::Gem,
+        AssetKind::Spec,
+        AssetKind::Crate,
+        AssetKind::NpmPackage,
+    ] {
+        let ecosystem = kind.ecosystem();
+        if !filter.includes_ecosystem(ecosystem) {
+            continue;
+        }
+        let versions = index
+            .cached_versions(kind)
+            .await
+            .with_context(|| format!("listing cached {} artifacts", kind.as_str()))?;
+        for (name, version, platform) in versions {
+            if !filter.includes_name(&name) {
+                continue;
+            }
+            if let Some(pinned) = &pinned
+                && !pinned.contains(&(
+                    ecosystem,
+                    name.as_str(),
+                    version.as_str(),
+                    platform.as_deref(),
+                ))
+            {
+                continue;
+            }
+            let key = AssetKey {
+                kind,
+                name: &name,
+                version: &version,
+                platform: platform.as_deref(),
+            };
+            let Some(asset) = index.peek(&key).await? else {
+                continue;
+            };
+            if !filter.includes_access(&asset.last_accessed) {
+                continue;
+            }
+            packages.insert((ecosystem, name.clone()));
+            artifacts.push(BundleArtifact {
+                kind: kind.as_str().to_string(),
+                name,
+                version,
+                platform,
+                path: asset.path,
+                sha256: asset.sha256,
+                size_bytes: asset.size_bytes,
+            });
+        }
+    }
+
+    let mut gem_metadata = Vec::new();
+    let mut gem_versions = Vec::new();
+    for artifact in artifacts
+        .iter()
+        .filter(|a| a.kind == AssetKind::Gem.as_str())
+    {
+        let platform = artifact.platform.as_deref();
+        if let Some(meta) = index
+            .gem_metadata(
+                &artifact.name,
+                &artifact.version,
+                Some(platform.unwrap_or("ruby")),
+            )
+            .await
+            .context("loading gem metadata")?
+        {
+            gem_metadata.push(meta);
+        }
+        if let Some(row) = index
+            .get_gem_version(&artifact.name, &artifact.version, platform)
+            .await
+            .context("loading gem version state")?
+        {
+            gem_versions.push(row);
+        }
+    }
+
+    let mut candidates = Vec::new();
+    if packages
+        .iter()
+        .any(|(ecosystem, _)| *ecosystem == Ecosystem::RubyGems)
+    {
+        candidates.push((
+            "compact_index/versions".to_string(),
+            "compact:versions".to_string(),
+        ));
+        candidates.push((
+            "compact_index/names".to_string(),
+            "compact:names".to_string(),
+        ));
+    }
+    candidates.extend(
+        packages
+            .iter()
+            .filter_map(|(ecosystem, name)| index_files_for(*ecosystem, name)),
+    );
+    let mut index_files = Vec::new();
+    for (path, meta_key) in candidates {
+        let meta = index
+            .catalog_meta_get(&meta_key)
+            .await
+            .context("loading cached index metadata")?;
+        index_files.push(BundleIndexFile {
+            path,
+            sha256: String::new(),
+            size_bytes: 0,
+            meta_key,
+            meta,
+        });
+    }
+
+    let manifest = BundleManifest {
+        format_version: BUNDLE_FORMAT_VERSION,
+        created_at: Utc::now(),
+        generator: format!("vein {}", env!("CARGO_PKG_VERSION")),
+        artifacts,
+        index_files,
+        gem_metadata,
+        gem_versions,
+    };
+
+    let root = storage.resolve("");
+    let output = output.to_path_buf();
+    tokio::task::spawn_blocking(move || write_bundle(&root, &output, manifest))
+        .await
+        .context("bundle writer task panicked")?
+}
+
+fn write_bundle(root: &Path, output: &Path, mut manifest: BundleManifest) -> Result<ExportReport> {
+    let mut report = ExportReport::default();
+
+    // Artifacts are verified against their index row so a corrupt cache entry
+    // never makes it into a bundle.
+    manifest.artifacts.retain(|artifact| {
+        let path = root.join(&artifact.path);
+        match File::open(&path).map_err(anyhow::Error::from).and_then(hash_reader) {
+            Ok((sha256, _)) if sha256 == artifact.sha256 => true,
+            Ok(_) => {
+                tracing::warn!(path = %path.display(), "skipping artifact with checksum mismatch");
+                report.skipped += 1;
+                false
+            }
+            Err(err) => {
+                tracing::warn!(path = %path.display(), error = %err, "skipping unreadable artifact");
+                report.skipped += 1;
+                false
+            }
+        }
+    });
+    let exported = |name: &str, version: &str, platform: Option<&str>| {
+        manifest.artifacts.iter().any(|artifact| {
+            artifact.kind == AssetKind::Gem.as_str()
+                && artifact.name == name
+                && artifact.version == version
+                && artifact.platform.as_deref() == platform
+        })
+    };
+    let gem_metadata = std::mem::take(&mut manifest.gem_metadata)
+        .into_iter()
+        .filter(|meta| {
+            let platform = (meta.platform != "ruby").then_some(meta.platform.as_str());
+            exported(&meta.name, &meta.version, platform)
+        })
+        .collect();
+    let gem_versions = std::mem::take(&mut manifest.gem_versions)
+        .into_iter()
+        .filter(|row| exported(&row.name, &row.version, row.platform.as_deref()))
+        .collect();
+    manifest.gem_metadata = gem_metadata;
+    manifest.gem_versions = gem_versions;
+
+    // Index text can be rewritten by a running server at any time, so it is
+    // captured once and written from memory.
+    let mut index_bodies = Vec::new();
+    manifest
+        .index_files
+        .retain_mut(|file| match std::fs::read(root.join(&file.path)) {
+            Ok(body) => {
+                let (sha256, size) = hash_reader(body.as_slice()).unwrap_or_default();
+                file.sha256 = sha256;
+                file.size_bytes = size;
+                index_bodies.push(body);
+                true
+            }
+            Err(_) => false,
+        });
+
+    let manifest_json = serde_json::to_vec_pretty(&manifest).context("serializing manifest")?;
+    let file = File::create(output).with_context(|| format!("creating {}", output.display()))?;
+    let writer = BufWriter::new(file);
+    let compressed = output
+        .extension()
+        .is_some_and(|ext| ext == "gz" || ext == "tgz");
+
+    if compressed {
+        let encoder = write_entries(
+            GzEncoder::new(writer, Compression::default()),
+            root,
+            &manifest,
+            &manifest_json,
+            &index_bodies,
+        )?;
+        encoder.finish().context("finishing gzip stream")?.flush()?;
+    } else {
+        write_entries(writer, root, &manifest, &manifest_json, &index_bodies)?.flush()?;
+    }
+
+    report.artifacts = manifest.artifacts.len();
+    report.index_files = manifest.index_files.len();
+    report.gem_metadata = manifest.gem_metadata.len();
+    report.gem_versions = manifest.gem_versions.len();
+    report.bytes = std::fs::metadata(output)?.len();
+    Ok(report)
+}
+
+fn write_entries<W: Write>(
+    writer: W,
+    root: &Path,
+    manifest: &BundleManifest,
+    manifest_json: &[u8],
+    index_bodies: &[Vec<u8>],
+) -> Result<W> {
+    let mut builder = tar::Builder::new(writer);
+    let mtime = manifest.created_at.timestamp().max(0) as u64;
+
+    append_bytes(&mut builder, MANIFEST_ENTRY, manifest_json, mtime)?;
+    for artifact in &manifest.artifacts {
+        let source: PathBuf = root.join(&artifact.path);
+        let mut file =
+            File::open(&source).with_context(|| format!("opening {}", source.display()))?;
+        builder
+            .append_file(format!("{FILES_PREFIX}{}", artifact.path), &mut file)
+            .with_context(|| format!("archiving {}", artifact.path))?;
+    }
+    for (file, body) in manifest.index_files.iter().zip(index_bodies) {
+        append_bytes(
+            &mut builder,
+            &format!("{FILES_PREFIX}{}", file.path),
+            body,
+            mtime,
+        )?;
+    }
+
+    builder.into_inner().context("finishing tar archive")
+}
+
+fn append_bytes<W: Write>(
+    builder: &mut tar::Builder<W>,
+    path: &str,
+    body: &[u8],
+    mtime: u64,
+) -> Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(body.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(mtime);
+    header.set_cksum();
+    builder
+        .append_data(&mut header, path, body)
+        .with_context(|| format!("archiving {path}"))
+}
@@ -0,0 +1,223 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use anyhow::{Context, Result, anyhow, bail};
+use flate2::read::GzDecoder;
+use serde::Serialize;
+use tokio::{io::AsyncWriteExt, sync::mpsc};
+use vein_adapter::{CacheBackend, CacheBackendTrait, FilesystemStorage};
+
+use super::{BundleManifest, FILES_PREFIX, MANIFEST_ENTRY, check_manifest, hash_reader};
+
+/// Outcome of loading a bundle.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ImportReport {
+    pub artifacts: usize,
+    pub index_files: usize,
+    pub gem_metadata: usize,
+    pub gem_versions: usize,
+    pub bytes: u64,
+}
+
+/// Verifies every file of the bundle at `path` against its manifest and, unless
+/// `verify_only` is set, loads it into `storage` and `index`. Nothing is
+/// written until the whole bundle has verified.
+pub async fn import_bundle(
+    index: &CacheBackend,
+    storage: &FilesystemStorage,
+    path: &Path,
+    verify_only: bool,
+) -> Result<ImportReport> {
+    let source = path.to_path_buf();
+    let (manifest, bytes) = tokio::task::spawn_blocking(move || verify_bundle(&source))
+        .await
+        .context("bundle verifier task panicked")??;
+
+    let mut report = ImportReport {
+        artifacts: manifest.artifacts.len(),
+        index_files: manifest.index_files.len(),
+        gem_metadata: manifest.gem_metadata.len(),
+        gem_versions: manifest.gem_versions.len(),
+        bytes,
+    };
+    if verify_only {
+        return Ok(report);
+    }
+
+    let expected = expected_files(&manifest);
+    let (tx, mut rx) = mpsc::channel::<(String, Vec<u8>)>(8);
+    let source = path.to_path_buf();
+    let reader = tokio::task::spawn_blocking(move || read_files(&source, &expected, &tx));
+
+    report.bytes = 0;
+    while let Some((relative, body)) = rx.recv().await {
+        let mut temp = storage
+            .create_temp_writer(&relative)
+            .await
+            .with_context(|| format!("creating {relative}"))?;
+        temp.file_mut()
+            .write_all(&body)
+            .await
+            .with_context(|| format!("writing {relative}"))?;
+        temp.commit()
+            .await
+            .with_context(|| format!("committing {relative}"))?;
+        report.bytes += body.len() as u64;
+    }
+    reader.await.context("bundle reader task panicked")??;
+
+    for artifact in &manifest.artifacts {
+        let kind = artifact
+            .asset_kind()
+            .ok_or_else(|| anyhow!("unknown artifact kind '{}'", artifact.kind))?;
+        index
+            .insert_or_replace(
+                &artifact.asset_key(kind),
+                &artifact.path,
+                &artifact.sha256,
+                artifact.size_bytes,
+            )
+            .await
+            .with_context(|| format!("indexing {}", artifact.path))?;
+    }
+    for meta in &manifest.gem_metadata {
+        index
+            .upsert_metadata(meta)
+            .await
+            .with_context(|| format!("storing metadata for {} {}", meta.name, meta.version))?;
+    }
+    for row in &manifest.gem_versions {
+        index
+            .upsert_gem_version(row)
+            .await
+            .with_context(|| format!("storing version state for {} {}", row.name, row.version))?;
+    }
+    for file in &manifest.index_files {
+        if let Some(meta) = &file.meta {
+            index
+                .catalog_meta_set(&file.meta_key, meta)
+                .await
+                .with_context(|| format!("storing cache metadata for {}", file.path))?;
+        }
+    }
+
+    Ok(report)
+}
+
+/// Storage path to `(sha256, size)` for every file listed in the manifest.
+fn expected_files(manifest: &BundleManifest) -> HashMap<String, (String, u64)> {
+    manifest
+        .artifacts
+        .iter()
+        .map(|artifact| {
+            (
+                artifact.path.clone(),
+                (artifact.sha256.clone(), artifact.size_bytes),
+            )
+        })
+        .chain(
+            manifest
+                .index_files
+                .iter()
+                .map(|file| (file.path.clone(), (file.sha256.clone(), file.size_bytes))),
+        )
+        .collect()
+}
+
+fn open_archive(path: &Path) -> Result<tar::Archive<Box<dyn Read>>> {
+    let mut file =
+        BufReader::new(File::open(path).with_context(|| format!("opening {}", path.display()))?);
+    let mut magic = [0u8; 2];
+    let compressed = file.read_exact(&mut magic).is_ok() && magic == [0x1f, 0x8b];
+    drop(file);
+
+    let file = BufReader::new(File::open(path)?);
+    let reader: Box<dyn Read> = if compressed {
+        Box::new(GzDecoder::new(file))
+    } else {
+        Box::new(file)
+    };
+    Ok(tar::Archive::new(reader))
+}
+
+/// Maps a tar entry to the storage path it carries, rejecting anything that
+/// is not listed in the manifest.
+fn entry_target<'a>(
+    entry_path: &str,
+    expected: &'a HashMap<String, (String, u64)>,
+) -> Result<(&'a str, &'a (String, u64))> {
+    let relative = entry_path
+        .strip_prefix(FILES_PREFIX)
+        .ok_or_else(|| anyhow!("unexpected bundle entry '{entry_path}'"))?;
+    expected
+        .get_key_value(relative)
+        .map(|(path, digest)| (path.as_str(), digest))
+        .ok_or_else(|| anyhow!("bundle entry '{entry_path}' is not listed in the manifest"))
+}
+
+fn verify_bundle(path: &Path) -> Result<(BundleManifest, u64)> {
+    let mut archive = open_archive(path)?;
+    let mut entries = archive.entries().context("reading bundle")?;
+
+    let mut first = entries
+        .next()
+        .ok_or_else(|| anyhow!("bundle is empty"))?
+        .context("reading manifest entry")?;
+    if first.path()?.to_string_lossy() != MANIFEST_ENTRY {
+        bail!("bundle does not start with {MANIFEST_ENTRY}");
+    }
+    let mut raw = Vec::new();
+    first.read_to_end(&mut raw).context("reading manifest")?;
+    let manifest: BundleManifest = serde_json::from_slice(&raw).context("parsing manifest")?;
+    check_manifest(&manifest)?;
+
+    let expected = expected_files(&manifest);
+    let mut seen = HashSet::new();
+    let mut bytes = 0u64;
+    for entry in entries {
+        let entry = entry.context("reading bundle entry")?;
+        let entry_path = entry.path()?.to_string_lossy().into_owned();
+        let (relative, (sha256, size)) = entry_target(&entry_path, &expected)?;
+        if !seen.insert(relative) {
+            bail!("bundle contains '{relative}' twice");
+        }
+        let (actual, actual_size) = hash_reader(entry)?;
+        if actual != *sha256 || actual_size != *size {
+            bail!("checksum mismatch for {relative}: expected {sha256}, found {actual}");
+        }
+        bytes += actual_size;
+    }
+
+    if let Some(missing) = expected.keys().find(|path| !seen.contains(path.as_str())) {
+        bail!("bundle is missing {missing}");
+    }
+    Ok((manifest, bytes))
+}
+
+fn read_files(
+    path: &Path,
+    expected: &HashMap<String, (String, u64)>,
+    tx: &mpsc::Sender<(String, Vec<u8>)>,
+) -> Result<()> {
+    let mut archive = open_archive(path)?;
+    for entry in archive.entries().context("reading bundle")?.skip(1) {
+        let mut entry = entry.context("reading bundle entry")?;
+        let entry_path = entry.path()?.to_string_lossy().into_owned();
+        let (relative, (sha256, _)) = entry_target(&entry_path, expected)?;
+        let mut body = Vec::new();
+        entry
+            .read_to_end(&mut body)
+            .with_context(|| format!("reading {relative}"))?;
+        // The file may have been swapped since the verification pass.
+        let (actual, _) = hash_reader(body.as_slice())?;
+        if actual != *sha256 {
+            bail!("{relative} changed while importing");
+        }
+        if tx.blocking_send((relative.to_string(), body)).is_err() {
+            return Ok(());
+        }
+    }
+    Ok(())
+}
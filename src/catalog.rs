@@ -2,6 +2,7 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use anyhow::Result;
+use chrono::Utc;
 use rama::{
     Layer, Service,
     error::{ErrorExt as _, extra::OpaqueError},
@@ -18,6 +19,8 @@ use vein_adapter::{CacheBackend, CacheBackendTrait};
 const NAMES_URL: &str = "https://rubygems.org/names.gz";
 const META_ETAG: &str = "catalog_names_etag";
 const META_LAST_MODIFIED: &str = "catalog_names_last_modified";
+/// `catalog_meta` key holding the RFC 3339 time of the last successful sync.
+pub const META_SYNCED_AT: &str = "catalog_names_synced_at";
 
 pub async fn sync_names_once(index: &CacheBackend) -> Result<Option<usize>> {
     let client = build_client()?;
@@ -61,6 +64,7 @@ async fn sync_names_with_client(
 
     if response.status() == StatusCode::NOT_MODIFIED {
         info!("catalog names list is up to date");
+        mark_synced(index).await?;
         return Ok(None);
     }
 
@@ -105,11 +109,18 @@ async fn sync_names_with_client(
             .await?;
     }
 
+    mark_synced(index).await?;
     info!(total, "catalog names synced");
 
     Ok(Some(total))
 }
 
+async fn mark_synced(index: &CacheBackend) -> Result<()> {
+    index
+        .catalog_meta_set(META_SYNCED_AT, &Utc::now().to_rfc3339())
+        .await
+}
+
 /// Spawns a background task that periodically syncs the catalog names.
 /// Syncs every 6 hours.
 pub fn spawn_background_sync(backend: Arc<CacheBackend>) -> Result<()> {
@@ -8,13 +8,23 @@ use rama::net::Protocol;
 use serde::Deserialize;
 
 // Re-export all submodules
+pub mod advisories;
 pub mod database;
 pub mod delay_policy;
+pub mod health;
+pub mod index_cache;
+pub mod license_policy;
 pub mod logging;
+pub mod notifications;
+pub mod offline;
+pub mod policy;
+pub mod rate_limit;
 pub mod reliability;
 pub mod server;
 pub mod storage;
+pub mod typosquat;
 pub mod upstream;
+pub mod yank_sync;
 
 #[cfg(test)]
 mod tests;
@@ -27,13 +37,23 @@ pub(crate) fn resolve_relative(path: &mut PathBuf, base_dir: &Path) {
 }
 
 // Re-export types from submodules for convenience
+pub use advisories::AdvisoriesConfig;
 pub use database::{DatabaseBackend, DatabaseConfig};
-pub use delay_policy::DelayPolicyConfig;
+pub use delay_policy::{DelayPolicyConfig, RiskPolicyConfig, RiskRule};
+pub use health::{Criticality, HealthConfig, HealthCriticality};
+pub use index_cache::IndexCacheConfig;
+pub use license_policy::{LicenseAction, LicensePolicyConfig, LicenseRule, LicenseTerms};
 pub use logging::LoggingConfig;
+pub use notifications::{NotificationKind, NotificationsConfig, WebhookConfig, WebhookFormat};
+pub use offline::OfflineConfig;
+pub use policy::{PolicyAction, PolicyConfig, PolicyRule};
+pub use rate_limit::{RateLimitConfig, RateLimitKey};
 pub use reliability::{BackoffStrategy, RetryConfig};
 pub use server::ServerConfig;
 pub use storage::StorageConfig;
+pub use typosquat::{TyposquatAction, TyposquatConfig};
 pub use upstream::UpstreamConfig;
+pub use yank_sync::YankSyncConfig;
 
 #[derive(Debug, Clone, Deserialize, Default)]
 pub struct Config {
@@ -49,6 +69,26 @@ pub struct Config {
     pub logging: LoggingConfig,
     #[serde(default)]
     pub delay_policy: DelayPolicyConfig,
+    #[serde(default)]
+    pub yank_sync: YankSyncConfig,
+    #[serde(default)]
+    pub notifications: NotificationsConfig,
+    #[serde(default)]
+    pub advisories: AdvisoriesConfig,
+    #[serde(default)]
+    pub offline: OfflineConfig,
+    #[serde(default)]
+    pub index_cache: IndexCacheConfig,
+    #[serde(default)]
+    pub health: HealthConfig,
+    #[serde(default)]
+    pub rate_limit: RateLimitConfig,
+    #[serde(default)]
+    pub typosquat: TyposquatConfig,
+    #[serde(default)]
+    pub license_policy: LicensePolicyConfig,
+    #[serde(default)]
+    pub policy: PolicyConfig,
 }
 
 impl Config {
@@ -65,6 +105,9 @@ impl Config {
             config
                 .database
                 .normalize_paths(candidate.parent().unwrap_or(Path::new(".")));
+            config
+                .advisories
+                .normalize_paths(candidate.parent().unwrap_or(Path::new(".")));
             Ok(config)
         } else {
             if let Some(path) = candidate.to_str() {
@@ -82,6 +125,38 @@ impl Config {
         }
     }
 
+    /// Restores settings that only take effect on restart from `current`,
+    /// returning the names of those that differed.
+    pub fn keep_restart_settings(&mut self, current: &Config) -> Vec<&'static str> {
+        let mut kept = Vec::new();
+        if self.storage.path != current.storage.path {
+            kept.push("storage.path");
+            self.storage.path = current.storage.path.clone();
+        }
+        if !self.database.same_target(&current.database) {
+            kept.push("database");
+            self.database = current.database.clone();
+        }
+        if self.server.host != current.server.host || self.server.port != current.server.port {
+            kept.push("server listen address");
+            self.server.host = current.server.host.clone();
+            self.server.port = current.server.port;
+        }
+        if self.server.workers != current.server.workers {
+            kept.push("server.workers");
+            self.server.workers = current.server.workers;
+        }
+        if self.logging.json != current.logging.json {
+            kept.push("logging.json");
+            self.logging.json = current.logging.json;
+        }
+        if self.rate_limit != current.rate_limit {
+            kept.push("rate_limit");
+            self.rate_limit = current.rate_limit.clone();
+        }
+        kept
+    }
+
     pub fn validate(&self) -> Result<()> {
         if let Some(upstream) = self.upstream.as_ref().filter(|upstream| {
             upstream.url.scheme() != Some(&Protocol::HTTPS)
@@ -90,6 +165,7 @@ impl Config {
             bail!("unsupported upstream scheme {}", upstream.url);
         }
         self.database.backend()?;
+        crate::policy::validate_rules(&self.policy.rules)?;
         Ok(())
     }
 }
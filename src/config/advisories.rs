@@ -0,0 +1,29 @@
+//! Vulnerability advisory configuration.
+//!
+//! Points Vein at an offline OSV dump and decides whether downloads of
+//! affected versions are refused.
+
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use vein_adapter::AdvisorySeverity;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AdvisoriesConfig {
+    /// OSV dump (directory of `.json` files or a `.zip`) imported on startup
+    /// and by `vein advisories import`.
+    #[serde(default)]
+    pub path: Option<PathBuf>,
+    /// Refuse downloads of versions with an advisory at or above this
+    /// severity (e.g. `"critical"`). Unset serves everything.
+    #[serde(default)]
+    pub block_severity: Option<AdvisorySeverity>,
+}
+
+impl AdvisoriesConfig {
+    pub fn normalize_paths(&mut self, base_dir: &Path) {
+        if let Some(path) = self.path.as_mut() {
+            crate::config::resolve_relative(path, base_dir);
+        }
+    }
+}
@@ -45,6 +45,15 @@ impl DatabaseConfig {
     #[cfg(not(feature = "sqlite"))]
     pub fn normalize_paths(&mut self, _base_dir: &Path) {}
 
+    /// Whether `other` points at the same database.
+    pub fn same_target(&self, other: &Self) -> bool {
+        #[cfg(feature = "sqlite")]
+        if self.path != other.path {
+            return false;
+        }
+        self.url == other.url
+    }
+
     #[cfg(feature = "sqlite")]
     pub fn ensure_directories(&self) -> std::io::Result<()> {
         if let Some(parent) = self.path.parent() {
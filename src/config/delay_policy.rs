@@ -6,6 +6,8 @@
 use serde::Deserialize;
 use vein_adapter::DelayPolicy as AdapterDelayPolicy;
 
+use crate::util::glob_match;
+
 /// Main delay policy configuration.
 #[derive(Debug, Clone, Deserialize)]
 #[serde(default)]
@@ -31,6 +33,21 @@ pub struct DelayPolicyConfig {
     /// Pinned versions (bypass quarantine immediately).
     #[serde(default)]
     pub pinned: Vec<PinnedVersion>,
+    /// Distinct approvers needed before a quarantined version is released early.
+    /// Above 1, only approvers identified by `approver_header` count.
+    #[serde(default = "DelayPolicyConfig::default_required_approvers")]
+    pub required_approvers: u32,
+    /// Request header carrying the signed-in user, set by an authenticating
+    /// proxy in front of vein-admin.
+    #[serde(default)]
+    pub approver_header: Option<String>,
+    /// Hide versions from the index until their runtime dependencies have
+    /// left quarantine, so resolvers never see an unsatisfiable release.
+    #[serde(default)]
+    pub hold_dependents: bool,
+    /// Extra delay or manual approval driven by release risk signals.
+    #[serde(default)]
+    pub risk: RiskPolicyConfig,
 }
 
 impl DelayPolicyConfig {
@@ -50,10 +67,19 @@ impl DelayPolicyConfig {
         9
     }
 
+    fn default_required_approvers() -> u32 {
+        1
+    }
+
     /// Convert to the adapter's DelayPolicy type.
     pub fn to_adapter_policy(&self) -> AdapterDelayPolicy {
+        self.adapter_policy_with_delay(self.default_delay_days)
+    }
+
+    /// Adapter policy using `delay_days` instead of the default delay.
+    pub fn adapter_policy_with_delay(&self, delay_days: u32) -> AdapterDelayPolicy {
         AdapterDelayPolicy {
-            default_delay_days: self.default_delay_days,
+            default_delay_days: delay_days,
             skip_weekends: self.skip_weekends,
             business_hours_only: self.business_hours_only,
             release_hour_utc: self.release_hour_utc,
@@ -100,6 +126,10 @@ impl Default for DelayPolicyConfig {
             release_hour_utc: Self::default_release_hour_utc(),
             gems: Vec::new(),
             pinned: Vec::new(),
+            required_approvers: Self::default_required_approvers(),
+            approver_header: None,
+            hold_dependents: false,
+            risk: RiskPolicyConfig::default(),
         }
     }
 }
@@ -127,29 +157,68 @@ pub struct PinnedVersion {
     pub reason: String,
 }
 
-/// Simple glob matching for patterns like "*-internal" or "rails-*".
-fn glob_match(pattern: &str, name: &str) -> bool {
-    if pattern == "*" {
-        return true;
-    }
+/// Risk signals that lengthen quarantine for a new version.
+///
+/// Each rule is off unless configured. Signals compare the new version's
+/// metadata against the closest earlier cached version.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct RiskPolicyConfig {
+    /// The package has no other published versions.
+    pub first_version: Option<RiskRule>,
+    /// Authors or emails that the previous version did not list.
+    pub author_change: Option<RiskRule>,
+    /// Native extensions or embedded binaries the previous version did not ship.
+    pub new_native_code: Option<RiskRule>,
+    /// Executables the previous version did not install.
+    pub new_executables: Option<RiskRule>,
+    /// Package grew by at least `size_jump_ratio` times.
+    pub size_jump: Option<RiskRule>,
+    /// The content scan flagged suspicious patterns in the sources.
+    pub suspicious_content: Option<RiskRule>,
+    #[serde(default = "RiskPolicyConfig::default_size_jump_ratio")]
+    pub size_jump_ratio: f64,
+}
 
-    if let Some(suffix) = pattern.strip_prefix('*') {
-        return name.ends_with(suffix);
+impl RiskPolicyConfig {
+    fn default_size_jump_ratio() -> f64 {
+        3.0
     }
 
-    if let Some(prefix) = pattern.strip_suffix('*') {
-        return name.starts_with(prefix);
+    /// Whether any risk rule is configured.
+    pub fn is_enabled(&self) -> bool {
+        self.first_version.is_some()
+            || self.author_change.is_some()
+            || self.new_native_code.is_some()
+            || self.new_executables.is_some()
+            || self.size_jump.is_some()
+            || self.suspicious_content.is_some()
     }
+}
 
-    // If pattern contains * in the middle, do a simple split match
-    if let Some(pos) = pattern.find('*') {
-        let prefix = &pattern[..pos];
-        let suffix = &pattern[pos + 1..];
-        return name.starts_with(prefix) && name.ends_with(suffix);
+impl Default for RiskPolicyConfig {
+    fn default() -> Self {
+        Self {
+            first_version: None,
+            author_change: None,
+            new_native_code: None,
+            new_executables: None,
+            size_jump: None,
+            suspicious_content: None,
+            size_jump_ratio: Self::default_size_jump_ratio(),
+        }
     }
+}
 
-    // No wildcards, exact match
-    pattern == name
+/// What happens when a risk signal fires.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub struct RiskRule {
+    /// Days added on top of the gem's delay.
+    #[serde(default)]
+    pub add_days: u32,
+    /// Keep the version quarantined until someone approves it.
+    #[serde(default)]
+    pub require_approval: bool,
 }
 
 #[cfg(test)]
@@ -164,6 +233,7 @@ mod tests {
         assert!(config.skip_weekends);
         assert!(config.business_hours_only);
         assert_eq!(config.release_hour_utc, 9);
+        assert_eq!(config.required_approvers, 1);
     }
 
     #[test]
@@ -215,28 +285,6 @@ mod tests {
         assert!(!config.is_pinned("rails", "7.0.0"));
     }
 
-    #[test]
-    fn test_glob_match() {
-        // Suffix match
-        assert!(glob_match("*-internal", "my-gem-internal"));
-        assert!(!glob_match("*-internal", "internal-gem"));
-
-        // Prefix match
-        assert!(glob_match("rails-*", "rails-api"));
-        assert!(!glob_match("rails-*", "my-rails"));
-
-        // Middle wildcard
-        assert!(glob_match("my-*-gem", "my-awesome-gem"));
-        assert!(!glob_match("my-*-gem", "your-awesome-gem"));
-
-        // Exact match
-        assert!(glob_match("rails", "rails"));
-        assert!(!glob_match("rails", "rack"));
-
-        // Wildcard all
-        assert!(glob_match("*", "anything"));
-    }
-
     #[test]
     fn test_toml_parsing() {
         let toml = r#"
@@ -268,5 +316,32 @@ mod tests {
         assert_eq!(config.release_hour_utc, 14);
         assert_eq!(config.gems.len(), 2);
         assert_eq!(config.pinned.len(), 1);
+        assert!(!config.risk.is_enabled());
+    }
+
+    #[test]
+    fn test_risk_rules_parsing() {
+        let toml = r#"
+            enabled = true
+
+            [risk]
+            first_version = { add_days = 7 }
+            author_change = { require_approval = true }
+            size_jump = { add_days = 2 }
+            size_jump_ratio = 5.0
+        "#;
+
+        let config: DelayPolicyConfig = toml::from_str(toml).unwrap();
+        assert!(config.risk.is_enabled());
+        assert_eq!(
+            config.risk.first_version,
+            Some(RiskRule {
+                add_days: 7,
+                require_approval: false
+            })
+        );
+        assert!(config.risk.author_change.unwrap().require_approval);
+        assert!(config.risk.new_native_code.is_none());
+        assert_eq!(config.risk.size_jump_ratio, 5.0);
     }
 }
@@ -0,0 +1,122 @@
+//! Readiness probe thresholds and per-component criticality.
+//!
+//! A failing `critical` check makes `/readyz` answer 503, a failing `warn`
+//! check only marks the report degraded, and `ignore` skips the check.
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Criticality {
+    Critical,
+    Warn,
+    Ignore,
+}
+
+impl Criticality {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Criticality::Critical => "critical",
+            Criticality::Warn => "warn",
+            Criticality::Ignore => "ignore",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct HealthConfig {
+    /// Slowest acceptable database round trip (milliseconds)
+    #[serde(default = "HealthConfig::default_max_db_latency_ms")]
+    pub max_db_latency_ms: u64,
+    /// Free space required on the storage volume (bytes)
+    #[serde(default = "HealthConfig::default_min_free_bytes")]
+    pub min_free_bytes: u64,
+    /// Oldest acceptable quarantine promotion run (seconds)
+    #[serde(default = "HealthConfig::default_max_scheduler_age_secs")]
+    pub max_scheduler_age_secs: u64,
+    /// Oldest acceptable RubyGems catalog sync (seconds)
+    #[serde(default = "HealthConfig::default_max_catalog_sync_age_secs")]
+    pub max_catalog_sync_age_secs: u64,
+    #[serde(default)]
+    pub criticality: HealthCriticality,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct HealthCriticality {
+    #[serde(default = "HealthCriticality::critical")]
+    pub database: Criticality,
+    #[serde(default = "HealthCriticality::critical")]
+    pub storage: Criticality,
+    #[serde(default = "HealthCriticality::warn")]
+    pub upstream: Criticality,
+    #[serde(default = "HealthCriticality::warn")]
+    pub scheduler: Criticality,
+    #[serde(default = "HealthCriticality::warn")]
+    pub catalog: Criticality,
+}
+
+impl HealthConfig {
+    fn default_max_db_latency_ms() -> u64 {
+        500
+    }
+
+    fn default_min_free_bytes() -> u64 {
+        1024 * 1024 * 1024
+    }
+
+    fn default_max_scheduler_age_secs() -> u64 {
+        2 * 60 * 60
+    }
+
+    fn default_max_catalog_sync_age_secs() -> u64 {
+        24 * 60 * 60
+    }
+
+    pub fn max_db_latency(&self) -> Duration {
+        Duration::from_millis(self.max_db_latency_ms)
+    }
+
+    pub fn max_scheduler_age(&self) -> Duration {
+        Duration::from_secs(self.max_scheduler_age_secs)
+    }
+
+    pub fn max_catalog_sync_age(&self) -> Duration {
+        Duration::from_secs(self.max_catalog_sync_age_secs)
+    }
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        Self {
+            max_db_latency_ms: Self::default_max_db_latency_ms(),
+            min_free_bytes: Self::default_min_free_bytes(),
+            max_scheduler_age_secs: Self::default_max_scheduler_age_secs(),
+            max_catalog_sync_age_secs: Self::default_max_catalog_sync_age_secs(),
+            criticality: HealthCriticality::default(),
+        }
+    }
+}
+
+impl HealthCriticality {
+    fn critical() -> Criticality {
+        Criticality::Critical
+    }
+
+    fn warn() -> Criticality {
+        Criticality::Warn
+    }
+}
+
+impl Default for HealthCriticality {
+    fn default() -> Self {
+        Self {
+            database: Self::critical(),
+            storage: Self::critical(),
+            upstream: Self::warn(),
+            scheduler: Self::warn(),
+            catalog: Self::warn(),
+        }
+    }
+}
@@ -0,0 +1,64 @@
+//! Server-side freshness of cached index metadata.
+//!
+//! Within its TTL a compact index file, sparse index entry or npm packument is
+//! served straight from disk. Past the TTL it is still served for the
+//! stale-while-revalidate window while a background request refreshes it;
+//! after that the request waits for a conditional upstream fetch.
+
+use std::time::Duration;
+
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct IndexCacheConfig {
+    /// Freshness of `/versions`, `/names` and `/info/*` (seconds)
+    #[serde(default = "IndexCacheConfig::default_ttl_secs")]
+    pub compact_index_ttl_secs: u64,
+    /// Freshness of crates.io sparse index entries (seconds)
+    #[serde(default = "IndexCacheConfig::default_ttl_secs")]
+    pub sparse_index_ttl_secs: u64,
+    /// Freshness of npm package metadata (seconds)
+    #[serde(default = "IndexCacheConfig::default_ttl_secs")]
+    pub npm_metadata_ttl_secs: u64,
+    /// How long past its TTL an entry may be served while it is refreshed
+    /// in the background (seconds, 0 disables)
+    #[serde(default = "IndexCacheConfig::default_stale_while_revalidate_secs")]
+    pub stale_while_revalidate_secs: u64,
+}
+
+impl IndexCacheConfig {
+    fn default_ttl_secs() -> u64 {
+        60
+    }
+
+    fn default_stale_while_revalidate_secs() -> u64 {
+        300
+    }
+
+    pub fn compact_index_ttl(&self) -> Duration {
+        Duration::from_secs(self.compact_index_ttl_secs)
+    }
+
+    pub fn sparse_index_ttl(&self) -> Duration {
+        Duration::from_secs(self.sparse_index_ttl_secs)
+    }
+
+    pub fn npm_metadata_ttl(&self) -> Duration {
+        Duration::from_secs(self.npm_metadata_ttl_secs)
+    }
+
+    pub fn stale_while_revalidate(&self) -> Duration {
+        Duration::from_secs(self.stale_while_revalidate_secs)
+    }
+}
+
+impl Default for IndexCacheConfig {
+    fn default() -> Self {
+        Self {
+            compact_index_ttl_secs: Self::default_ttl_secs(),
+            sparse_index_ttl_secs: Self::default_ttl_secs(),
+            npm_metadata_ttl_secs: Self::default_ttl_secs(),
+            stale_while_revalidate_secs: Self::default_stale_while_revalidate_secs(),
+        }
+    }
+}
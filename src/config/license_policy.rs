@@ -0,0 +1,127 @@
+//! License policy configuration.
+//!
+//! Allow and deny lists of SPDX license identifiers, optionally narrowed to
+//! ecosystems and package globs, and what happens to packages that break them.
+
+use serde::Deserialize;
+use vein_adapter::Ecosystem;
+
+use crate::util::glob_match;
+
+/// What happens to a download whose license breaks the policy.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LicenseAction {
+    /// Log and add an `X-Vein-License-Warning` header, then serve it.
+    #[default]
+    Warn,
+    /// Refuse the download.
+    Block,
+    /// Hold newly cached gem versions for manual approval. Crates, npm
+    /// packages and gems without an enabled `[delay_policy]` are blocked.
+    Quarantine,
+}
+
+impl LicenseAction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Warn => "warn",
+            Self::Block => "block",
+            Self::Quarantine => "quarantine",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LicensePolicyConfig {
+    /// Enforce the policy on downloads.
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub action: LicenseAction,
+    /// Accept packages that declare no license.
+    #[serde(default = "LicensePolicyConfig::default_allow_unknown")]
+    pub allow_unknown: bool,
+    /// License identifiers or globs (e.g. `BSD-*`) that are accepted; empty
+    /// accepts everything not denied.
+    #[serde(default)]
+    pub allow: Vec<String>,
+    /// License identifiers or globs that are never accepted.
+    #[serde(default)]
+    pub deny: Vec<String>,
+    /// Lists for specific ecosystems or packages; the first matching rule
+    /// replaces the lists above.
+    #[serde(default)]
+    pub rules: Vec<LicenseRule>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LicenseRule {
+    /// Ecosystems the rule applies to (empty = all).
+    #[serde(default)]
+    pub ecosystems: Vec<Ecosystem>,
+    /// Package name globs the rule applies to (empty = all).
+    #[serde(default)]
+    pub packages: Vec<String>,
+    #[serde(default)]
+    pub allow: Vec<String>,
+    #[serde(default)]
+    pub deny: Vec<String>,
+    /// Overrides the policy action for matching packages.
+    #[serde(default)]
+    pub action: Option<LicenseAction>,
+}
+
+impl LicenseRule {
+    fn matches(&self, ecosystem: Ecosystem, name: &str) -> bool {
+        (self.ecosystems.is_empty() || self.ecosystems.contains(&ecosystem))
+            && (self.packages.is_empty()
+                || self
+                    .packages
+                    .iter()
+                    .any(|pattern| glob_match(pattern, name)))
+    }
+}
+
+/// The lists and action that apply to one package.
+#[derive(Debug, Clone, Copy)]
+pub struct LicenseTerms<'a> {
+    pub allow: &'a [String],
+    pub deny: &'a [String],
+    pub action: LicenseAction,
+}
+
+impl LicensePolicyConfig {
+    fn default_allow_unknown() -> bool {
+        true
+    }
+
+    /// Terms of the first rule matching the package, or the top-level lists.
+    pub fn terms_for(&self, ecosystem: Ecosystem, name: &str) -> LicenseTerms<'_> {
+        match self.rules.iter().find(|rule| rule.matches(ecosystem, name)) {
+            Some(rule) => LicenseTerms {
+                allow: &rule.allow,
+                deny: &rule.deny,
+                action: rule.action.unwrap_or(self.action),
+            },
+            None => LicenseTerms {
+                allow: &self.allow,
+                deny: &self.deny,
+                action: self.action,
+            },
+        }
+    }
+}
+
+impl Default for LicensePolicyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            action: LicenseAction::default(),
+            allow_unknown: Self::default_allow_unknown(),
+            allow: Vec::new(),
+            deny: Vec::new(),
+            rules: Vec::new(),
+        }
+    }
+}
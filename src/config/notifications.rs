@@ -0,0 +1,160 @@
+//! Outgoing notification configuration.
+//!
+//! Webhooks fire on quarantine and security events; each hook can narrow
+//! which events, ecosystems and packages it cares about.
+
+use serde::{Deserialize, Serialize};
+use vein_adapter::Ecosystem;
+
+use crate::util::glob_match;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct NotificationsConfig {
+    /// Webhook endpoints to notify.
+    #[serde(default)]
+    pub webhooks: Vec<WebhookConfig>,
+}
+
+/// Events a webhook can subscribe to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    /// A new version entered quarantine.
+    Quarantined,
+    /// A quarantined version became available after its delay.
+    Promoted,
+    /// A version was released early by approvers.
+    Released,
+    /// A version was blocked by an operator.
+    Blocked,
+    /// A cached artifact no longer matched its recorded checksum.
+    ChecksumFailed,
+    /// A download was refused by policy (e.g. an upstream yank).
+    PolicyFailed,
+}
+
+impl NotificationKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Quarantined => "quarantined",
+            Self::Promoted => "promoted",
+            Self::Released => "released",
+            Self::Blocked => "blocked",
+            Self::ChecksumFailed => "checksum_failed",
+            Self::PolicyFailed => "policy_failed",
+        }
+    }
+}
+
+/// Payload layout sent to a webhook.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookFormat {
+    /// Vein's own JSON document.
+    #[default]
+    Json,
+    /// Slack incoming-webhook message.
+    Slack,
+    /// Microsoft Teams MessageCard.
+    Teams,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhookConfig {
+    /// Target URL.
+    pub url: String,
+    /// Label used in logs.
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub format: WebhookFormat,
+    /// Shared secret; when set, requests carry an HMAC-SHA256 signature.
+    #[serde(default)]
+    pub secret: Option<String>,
+    /// Events to send (empty = all).
+    #[serde(default)]
+    pub events: Vec<NotificationKind>,
+    /// Ecosystems to send (empty = all).
+    #[serde(default)]
+    pub ecosystems: Vec<Ecosystem>,
+    /// Package name globs to send (empty = all).
+    #[serde(default)]
+    pub packages: Vec<String>,
+    /// Delivery attempts before giving up.
+    #[serde(default = "WebhookConfig::default_max_attempts")]
+    pub max_attempts: u8,
+    /// Delay before the first retry; doubles on each attempt.
+    #[serde(default = "WebhookConfig::default_initial_backoff_ms")]
+    pub initial_backoff_ms: u64,
+    /// Per-attempt request timeout.
+    #[serde(default = "WebhookConfig::default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+impl WebhookConfig {
+    fn default_max_attempts() -> u8 {
+        4
+    }
+
+    fn default_initial_backoff_ms() -> u64 {
+        500
+    }
+
+    fn default_timeout_secs() -> u64 {
+        10
+    }
+
+    /// Label used in logs: the configured name, or the URL host.
+    pub fn label(&self) -> &str {
+        self.name.as_deref().unwrap_or_else(|| {
+            self.url
+                .split("://")
+                .nth(1)
+                .and_then(|rest| rest.split('/').next())
+                .unwrap_or(&self.url)
+        })
+    }
+
+    /// Whether this hook wants an event for `name` in `ecosystem`.
+    pub fn matches(&self, kind: NotificationKind, ecosystem: Ecosystem, name: &str) -> bool {
+        (self.events.is_empty() || self.events.contains(&kind))
+            && (self.ecosystems.is_empty() || self.ecosystems.contains(&ecosystem))
+            && (self.packages.is_empty()
+                || self
+                    .packages
+                    .iter()
+                    .any(|pattern| glob_match(pattern, name)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn webhook_filters_by_event_ecosystem_and_glob() {
+        let toml = r#"
+            [[webhooks]]
+            url = "https://hooks.example.test/vein"
+            format = "slack"
+            events = ["blocked", "checksum_failed"]
+            ecosystems = ["rubygems"]
+            packages = ["rails*"]
+        "#;
+
+        let config: NotificationsConfig = toml::from_str(toml).unwrap();
+        let hook = &config.webhooks[0];
+        assert_eq!(hook.format, WebhookFormat::Slack);
+        assert_eq!(hook.max_attempts, 4);
+        assert_eq!(hook.label(), "hooks.example.test");
+
+        assert!(hook.matches(
+            NotificationKind::Blocked,
+            Ecosystem::RubyGems,
+            "rails-html-sanitizer"
+        ));
+        assert!(!hook.matches(NotificationKind::Promoted, Ecosystem::RubyGems, "rails"));
+        assert!(!hook.matches(NotificationKind::Blocked, Ecosystem::Npm, "rails"));
+        assert!(!hook.matches(NotificationKind::Blocked, Ecosystem::RubyGems, "rack"));
+    }
+}
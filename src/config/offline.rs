@@ -0,0 +1,42 @@
+//! Offline and stale-if-error configuration.
+//!
+//! Controls whether cached index metadata (compact index, sparse index, npm
+//! packuments) is served when upstream cannot be reached, and whether Vein
+//! contacts upstream at all.
+
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct OfflineConfig {
+    /// Never contact upstream; serve only what is cached.
+    #[serde(default)]
+    pub enabled: bool,
+    /// Serve the last cached index text when upstream fails or returns 5xx.
+    #[serde(default = "OfflineConfig::default_stale_if_error")]
+    pub stale_if_error: bool,
+    /// Limit npm metadata and crates index responses to cached versions even
+    /// while online (always applied when `enabled`).
+    #[serde(default)]
+    pub cached_versions_only: bool,
+}
+
+impl OfflineConfig {
+    fn default_stale_if_error() -> bool {
+        true
+    }
+
+    /// Whether index responses should only list versions Vein has cached.
+    pub fn filters_uncached(&self) -> bool {
+        self.enabled || self.cached_versions_only
+    }
+}
+
+impl Default for OfflineConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            stale_if_error: Self::default_stale_if_error(),
+            cached_versions_only: false,
+        }
+    }
+}
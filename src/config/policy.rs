@@ -0,0 +1,106 @@
+//! Package policy rule configuration.
+//!
+//! Ordered allow/deny rules matching packages by ecosystem, name, version,
+//! platform and metadata attributes. Rules created in the admin UI are added
+//! after these.
+
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+use vein_adapter::Ecosystem;
+
+/// What happens to a package version matched by a rule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyAction {
+    /// Serve it; later rules are not consulted.
+    Allow,
+    /// Refuse the download and hide the version from indexes.
+    Deny,
+    /// Hold new gem versions for the regular `[delay_policy]` delay.
+    Quarantine,
+    /// Hold new gem versions until an admin approves them.
+    RequireApproval,
+}
+
+impl PolicyAction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Allow => "allow",
+            Self::Deny => "deny",
+            Self::Quarantine => "quarantine",
+            Self::RequireApproval => "require_approval",
+        }
+    }
+}
+
+impl FromStr for PolicyAction {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "allow" => Ok(Self::Allow),
+            "deny" => Ok(Self::Deny),
+            "quarantine" => Ok(Self::Quarantine),
+            "require_approval" => Ok(Self::RequireApproval),
+            other => Err(format!("unknown policy action '{other}'")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PolicyConfig {
+    /// Evaluate rules on downloads and index responses.
+    #[serde(default)]
+    pub enabled: bool,
+    /// Seconds between reloads of the rules stored in the database.
+    #[serde(default = "PolicyConfig::default_refresh_seconds")]
+    pub refresh_seconds: u64,
+    /// Evaluated in order; the first matching rule decides.
+    #[serde(default)]
+    pub rules: Vec<PolicyRule>,
+}
+
+impl Default for PolicyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            refresh_seconds: Self::default_refresh_seconds(),
+            rules: Vec::new(),
+        }
+    }
+}
+
+impl PolicyConfig {
+    fn default_refresh_seconds() -> u64 {
+        30
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct PolicyRule {
+    /// Logged with every decision the rule makes.
+    pub id: String,
+    /// Ecosystems the rule applies to (empty = all).
+    #[serde(default)]
+    pub ecosystems: Vec<Ecosystem>,
+    /// Package name globs (empty = all).
+    #[serde(default)]
+    pub packages: Vec<String>,
+    /// Version requirement: `Gem::Requirement` syntax for gems (`~> 7.0`),
+    /// semver for crates and npm (`>=1.2, <2 || ^3`).
+    #[serde(default)]
+    pub versions: Option<String>,
+    /// Gem platform globs; plain gems are `ruby` (empty = all).
+    #[serde(default)]
+    pub platforms: Vec<String>,
+    /// Metadata values a version must have, e.g. `has_native_extensions = true`.
+    /// Only versions whose metadata has been extracted can match.
+    #[serde(default)]
+    pub attributes: BTreeMap<String, JsonValue>,
+    pub action: PolicyAction,
+    #[serde(default)]
+    pub reason: Option<String>,
+}
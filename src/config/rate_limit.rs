@@ -0,0 +1,86 @@
+//! Per-client request budgets and the global cap on upstream fetches.
+//!
+//! Budgets are token buckets refilled continuously at the per-minute rate, so
+//! a client can burst up to a minute's worth of requests. A budget of 0 is
+//! unlimited.
+
+use std::time::Duration;
+
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum RateLimitKey {
+    /// Client IP address.
+    #[default]
+    Ip,
+    /// `Authorization` header, falling back to the client IP.
+    Token,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct RateLimitConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub key: RateLimitKey,
+    /// Take the client IP from the first `X-Forwarded-For` entry
+    #[serde(default)]
+    pub trust_forwarded_for: bool,
+    /// Artifacts served from cache, per client
+    #[serde(default = "RateLimitConfig::default_hits_per_minute")]
+    pub hits_per_minute: u32,
+    /// Artifacts fetched from upstream on a cache miss, per client
+    #[serde(default = "RateLimitConfig::default_misses_per_minute")]
+    pub misses_per_minute: u32,
+    /// Index, metadata and API requests, per client
+    #[serde(default = "RateLimitConfig::default_metadata_per_minute")]
+    pub metadata_per_minute: u32,
+    /// Upstream artifact and index fetches in flight across all clients (0 = unlimited)
+    #[serde(default = "RateLimitConfig::default_max_concurrent_upstream")]
+    pub max_concurrent_upstream: usize,
+    /// How long an upstream fetch waits for a slot before a 429 (milliseconds)
+    #[serde(default = "RateLimitConfig::default_upstream_queue_timeout_ms")]
+    pub upstream_queue_timeout_ms: u64,
+}
+
+impl RateLimitConfig {
+    fn default_hits_per_minute() -> u32 {
+        6000
+    }
+
+    fn default_misses_per_minute() -> u32 {
+        300
+    }
+
+    fn default_metadata_per_minute() -> u32 {
+        1200
+    }
+
+    fn default_max_concurrent_upstream() -> usize {
+        32
+    }
+
+    fn default_upstream_queue_timeout_ms() -> u64 {
+        10_000
+    }
+
+    pub fn upstream_queue_timeout(&self) -> Duration {
+        Duration::from_millis(self.upstream_queue_timeout_ms)
+    }
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            key: RateLimitKey::default(),
+            trust_forwarded_for: false,
+            hits_per_minute: Self::default_hits_per_minute(),
+            misses_per_minute: Self::default_misses_per_minute(),
+            metadata_per_minute: Self::default_metadata_per_minute(),
+            max_concurrent_upstream: Self::default_max_concurrent_upstream(),
+            upstream_queue_timeout_ms: Self::default_upstream_queue_timeout_ms(),
+        }
+    }
+}
@@ -623,3 +623,22 @@ fn test_parent_directory_resolution() {
     // Should be relative to the config file's parent directory
     assert_eq!(config.storage.path, subdir.join("gems"));
 }
+
+#[test]
+fn test_keep_restart_settings() {
+    let current = Config::default();
+    let mut next = Config::default();
+    next.storage.path = PathBuf::from("/elsewhere");
+    next.server.port = 9000;
+    next.logging.level = "debug".to_string();
+    next.delay_policy.default_delay_days = 7;
+
+    let kept = next.keep_restart_settings(&current);
+    assert_eq!(kept, ["storage.path", "server listen address"]);
+    assert_eq!(next.storage.path, current.storage.path);
+    assert_eq!(next.server.port, 8346);
+    assert_eq!(next.logging.level, "debug");
+    assert_eq!(next.delay_policy.default_delay_days, 7);
+
+    assert!(next.keep_restart_settings(&current).is_empty());
+}
@@ -0,0 +1,92 @@
+//! Typosquatting and dependency confusion detection configuration.
+//!
+//! Decides which new package names are compared against established ones
+//! and what happens to requests for names that look like imitations.
+
+use serde::Deserialize;
+
+/// What happens to a download of a suspicious package name.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TyposquatAction {
+    /// Log and record the name, then serve it.
+    #[default]
+    Warn,
+    /// Refuse the download.
+    Block,
+    /// Hold gem versions for manual approval. Ecosystems without quarantine,
+    /// or a disabled `[delay_policy]`, block instead.
+    Quarantine,
+}
+
+impl TyposquatAction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Warn => "warn",
+            Self::Block => "block",
+            Self::Quarantine => "quarantine",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TyposquatConfig {
+    /// Check names that have never been cached before.
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub action: TyposquatAction,
+    /// Prefixes of internal package names (e.g. `acme-` or `@acme/`). Such
+    /// names reaching the public upstream are dependency confusion.
+    #[serde(default)]
+    pub internal_prefixes: Vec<String>,
+    /// Names that are never flagged.
+    #[serde(default)]
+    pub allow: Vec<String>,
+    /// Most downloaded cached packages per ecosystem that new names are
+    /// compared against.
+    #[serde(default = "TyposquatConfig::default_popular_limit")]
+    pub popular_limit: u32,
+    /// Minutes between refreshes of the known-name snapshot.
+    #[serde(default = "TyposquatConfig::default_refresh_minutes")]
+    pub refresh_minutes: u64,
+}
+
+impl TyposquatConfig {
+    fn default_popular_limit() -> u32 {
+        500
+    }
+
+    fn default_refresh_minutes() -> u64 {
+        15
+    }
+
+    /// Whether `name` is on the allow list, ignoring case.
+    pub fn is_allowed(&self, name: &str) -> bool {
+        self.allow
+            .iter()
+            .any(|allowed| allowed.eq_ignore_ascii_case(name))
+    }
+
+    /// The configured internal prefix `name` starts with, ignoring case.
+    pub fn internal_prefix(&self, name: &str) -> Option<&str> {
+        let name = name.to_ascii_lowercase();
+        self.internal_prefixes
+            .iter()
+            .map(String::as_str)
+            .find(|prefix| !prefix.is_empty() && name.starts_with(&prefix.to_ascii_lowercase()))
+    }
+}
+
+impl Default for TyposquatConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            action: TyposquatAction::default(),
+            internal_prefixes: Vec::new(),
+            allow: Vec::new(),
+            popular_limit: Self::default_popular_limit(),
+            refresh_minutes: Self::default_refresh_minutes(),
+        }
+    }
+}
@@ -10,6 +10,10 @@ pub struct UpstreamConfig {
     pub fallback_urls: Vec<Uri>,
     #[serde(default)]
     pub reliability: ReliabilityConfig,
+    /// Send a duplicate request to the next-healthiest mirror when the first
+    /// has not answered within this many milliseconds (unset disables)
+    #[serde(default)]
+    pub hedge_after_ms: Option<u64>,
 }
 
 impl Default for UpstreamConfig {
@@ -18,6 +22,7 @@ impl Default for UpstreamConfig {
             url: default_upstream_url(),
             fallback_urls: Vec::new(),
             reliability: ReliabilityConfig::default(),
+            hedge_after_ms: None,
         }
     }
 }
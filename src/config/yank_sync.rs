@@ -0,0 +1,51 @@
+//! Upstream yank synchronisation configuration.
+//!
+//! Controls the background job that checks cached versions against
+//! upstream yank, deprecation and unpublish signals.
+
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct YankSyncConfig {
+    /// Run the scheduled yank check.
+    #[serde(default = "YankSyncConfig::default_enabled")]
+    pub enabled: bool,
+    /// Cron schedule (sec min hour day month weekday).
+    #[serde(default = "YankSyncConfig::default_schedule")]
+    pub schedule: String,
+    /// Refuse to serve cached artifacts that upstream has yanked.
+    #[serde(default)]
+    pub block_downloads: bool,
+    /// Treat npm deprecations as yanks (unpublishes always count).
+    #[serde(default)]
+    pub npm_deprecations: bool,
+    /// Window used to flag yanked versions that were downloaded recently.
+    #[serde(default = "YankSyncConfig::default_recent_days")]
+    pub recent_days: u32,
+}
+
+impl YankSyncConfig {
+    fn default_enabled() -> bool {
+        true
+    }
+
+    fn default_schedule() -> String {
+        "0 17 */6 * * *".to_string()
+    }
+
+    fn default_recent_days() -> u32 {
+        7
+    }
+}
+
+impl Default for YankSyncConfig {
+    fn default() -> Self {
+        Self {
+            enabled: Self::default_enabled(),
+            schedule: Self::default_schedule(),
+            block_downloads: false,
+            npm_deprecations: false,
+            recent_days: Self::default_recent_days(),
+        }
+    }
+}
@@ -5,7 +5,9 @@
 mod handlers;
 mod types;
 
+pub(crate) use handlers::crates_index_base;
 pub use handlers::handle_sparse_index;
+pub(crate) use types::index_path;
 
 #[cfg(test)]
 pub(crate) use handlers::test_override::override_crates_index_base;
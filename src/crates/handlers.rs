@@ -1,6 +1,7 @@
 //! Request handlers for crates.io registry protocol
 
 use std::borrow::Cow;
+use std::collections::HashSet;
 use std::sync::Arc;
 
 use anyhow::{Context, Result};
@@ -8,15 +9,19 @@ use rama::http::{
     Body, Response, StatusCode,
     header::{self, HeaderValue},
 };
-use vein_adapter::{CacheBackend, FilesystemStorage};
+use serde::Deserialize;
+use vein_adapter::{AssetKind, CacheBackend, CacheBackendTrait, FilesystemStorage};
 
 use super::types::{IndexConfig, index_path};
-use crate::http_cache::{CacheOutcome, CachedTextOptions, MetaStoreMode, fetch_cached_text};
+use crate::http_cache::{
+    CacheOutcome, CachedTextOptions, IndexOptions, MetaStoreMode, fetch_cached_text,
+};
+use crate::rate_limit;
 use crate::upstream::simple_get;
 
 const CRATES_INDEX_BASE: &str = "https://index.crates.io";
 
-fn crates_index_base() -> Cow<'static, str> {
+pub(crate) fn crates_index_base() -> Cow<'static, str> {
     #[cfg(test)]
     if let Some(base) = test_override::current_crates_index_base() {
         return Cow::Owned(base);
@@ -36,9 +41,10 @@ pub async fn handle_sparse_index(
     our_base: &str,
     storage: Arc<FilesystemStorage>,
     index: Arc<CacheBackend>,
+    options: IndexOptions<'_>,
 ) -> Result<(Response<Body>, CacheOutcome)> {
     let index_base = crates_index_base();
-    handle_sparse_index_from(path, our_base, storage, index, index_base.as_ref()).await
+    handle_sparse_index_from(path, our_base, storage, index, options, index_base.as_ref()).await
 }
 
 async fn handle_sparse_index_from(
@@ -46,6 +52,7 @@ async fn handle_sparse_index_from(
     our_base: &str,
     storage: Arc<FilesystemStorage>,
     index: Arc<CacheBackend>,
+    options: IndexOptions<'_>,
     index_base: &str,
 ) -> Result<(Response<Body>, CacheOutcome)> {
     // Handle config.json specially - serve our own
@@ -80,9 +87,27 @@ async fn handle_sparse_index_from(
     let meta_key = format!("crates:index:{}", crate_name);
     let upstream_url = format!("{}/{}", index_base.trim_end_matches('/'), expected_path);
 
+    let IndexOptions {
+        offline,
+        index_cache,
+        policy,
+        client,
+    } = options;
+    // The refresh may outlive this request, so it owns its client handle
+    let client = client.cloned();
+    let cached_versions = if offline.filters_uncached() {
+        let versions = index
+            .cached_package_versions(AssetKind::Crate, crate_name)
+            .await
+            .context("listing cached crate versions")?;
+        Some(versions.into_iter().collect::<HashSet<_>>())
+    } else {
+        None
+    };
+
     let result = fetch_cached_text(
-        storage.as_ref(),
-        index.as_ref(),
+        &storage,
+        &index,
         CachedTextOptions {
             storage_path: &storage_path,
             meta_key: &meta_key,
@@ -91,15 +116,52 @@ async fn handle_sparse_index_from(
             include_content_length: false,
             meta_mode: MetaStoreMode::BestEffort,
             strip_transfer_encoding: false,
+            offline: offline.enabled,
+            stale_if_error: offline.stale_if_error,
+            fresh_for: index_cache.sparse_index_ttl(),
+            stale_while_revalidate: index_cache.stale_while_revalidate(),
+        },
+        move |headers| {
+            rate_limit::with_upstream_slot(client, async move {
+                simple_get(&upstream_url, &headers, Some("text/plain")).await
+            })
+        },
+        |body| async move {
+            let body = match &cached_versions {
+                Some(cached) => retain_cached_lines(&body, cached),
+                None => body,
+            };
+            Ok(match policy {
+                Some(filter) => filter.filter_sparse_index(crate_name, &body),
+                None => body,
+            })
         },
-        |headers| async move { simple_get(&upstream_url, &headers, Some("text/plain")).await },
-        |body| async move { Ok(body) },
     )
     .await?;
 
     Ok((result.response, result.outcome))
 }
 
+#[derive(Deserialize)]
+struct IndexLine {
+    vers: String,
+}
+
+/// Keeps only the index lines for versions whose `.crate` is cached, so
+/// resolvers never pick a version Vein cannot serve.
+fn retain_cached_lines(body: &[u8], cached: &HashSet<String>) -> Vec<u8> {
+    let mut filtered = Vec::with_capacity(body.len());
+    for line in body.split(|byte| *byte == b'\n') {
+        let keep = serde_json::from_slice::<IndexLine>(line)
+            .is_ok_and(|entry| cached.contains(&entry.vers));
+        if keep {
+            filtered.extend_from_slice(line);
+            filtered.push(b'\n');
+        }
+    }
+    filtered
+}
+
 /// Serve the sparse index config.json
 fn serve_index_config(our_base: &str) -> Result<Response<Body>> {
     let config = IndexConfig {
@@ -167,6 +229,7 @@ pub(crate) mod test_override {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::{IndexCacheConfig, OfflineConfig};
     use rama::http::body::util::BodyExt;
     use rama::tls::rustls::dep::rustls;
     use std::sync::{Arc, Once};
@@ -213,6 +276,12 @@ mod tests {
             "http://localhost:8346",
             storage.clone(),
             index.clone(),
+            IndexOptions {
+                offline: &OfflineConfig::default(),
+                index_cache: &revalidate_always(),
+                policy: None,
+                client: None,
+            },
             &upstream_base,
         )
         .await
@@ -225,6 +294,12 @@ mod tests {
             "http://localhost:8346",
             storage.clone(),
             index.clone(),
+            IndexOptions {
+                offline: &OfflineConfig::default(),
+                index_cache: &revalidate_always(),
+                policy: None,
+                client: None,
+            },
             &upstream_base,
         )
         .await
@@ -249,6 +324,195 @@ mod tests {
         );
     }
 
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_handle_sparse_index_serves_stale_and_filters_offline() {
+        install_rustls_provider();
+
+        let temp_dir = tempdir().unwrap();
+        let storage = Arc::new(FilesystemStorage::new(temp_dir.path().join("cache")));
+        storage.prepare().await.unwrap();
+        let index = Arc::new(CacheBackend::connect_memory().await.unwrap());
+
+        let body =
+            b"{\"name\":\"serde\",\"vers\":\"1.0.0\"}\n{\"name\":\"serde\",\"vers\":\"1.0.1\"}\n";
+        let (upstream_base, server) = spawn_sequence_server(vec![
+            raw_response("200 OK", &[("ETag", "\"serde-v1\"")], body),
+            raw_response("503 Service Unavailable", &[], b"down"),
+        ])
+        .await;
+
+        let online = OfflineConfig::default();
+        for expected in [CacheOutcome::Miss, CacheOutcome::Stale] {
+            let (response, outcome) = handle_sparse_index_from(
+                "/index/se/rd/serde",
+                "http://localhost:8346",
+                storage.clone(),
+                index.clone(),
+                IndexOptions {
+                    offline: &online,
+                    index_cache: &revalidate_always(),
+                    policy: None,
+                    client: None,
+                },
+                &upstream_base,
+            )
+            .await
+            .unwrap();
+            assert_eq!(outcome, expected);
+            if expected == CacheOutcome::Stale {
+                assert_eq!(
+                    response.headers().get(header::WARNING).unwrap(),
+                    "111 - \"Revalidation Failed\""
+                );
+                assert!(response.headers().contains_key(header::AGE));
+            }
+            assert_eq!(body_bytes(response).await, body);
+        }
+        assert_eq!(server.await.unwrap().len(), 2);
+
+        index
+            .insert_or_replace(
+                &vein_adapter::AssetKey {
+                    kind: AssetKind::Crate,
+                    name: "serde",
+                    version: "1.0.0",
+                    platform: None,
+                },
+                "crates/serde/serde-1.0.0.crate",
+                "00",
+                1,
+            )
+            .await
+            .unwrap();
+        let offline = OfflineConfig {
+            enabled: true,
+            ..OfflineConfig::default()
+        };
+        let (response, outcome) = handle_sparse_index_from(
+            "/index/se/rd/serde",
+            "http://localhost:8346",
+            storage.clone(),
+            index.clone(),
+            IndexOptions {
+                offline: &offline,
+                index_cache: &revalidate_always(),
+                policy: None,
+                client: None,
+            },
+            "http://127.0.0.1:9",
+        )
+        .await
+        .unwrap();
+        assert_eq!(outcome, CacheOutcome::Stale);
+        assert_eq!(
+            response.headers().get(header::WARNING).unwrap(),
+            "112 - \"Disconnected Operation\""
+        );
+        assert_eq!(
+            body_bytes(response).await,
+            b"{\"name\":\"serde\",\"vers\":\"1.0.0\"}\n"
+        );
+
+        let (response, _) = handle_sparse_index_from(
+            "/index/to/ki/tokio",
+            "http://localhost:8346",
+            storage,
+            index,
+            IndexOptions {
+                offline: &offline,
+                index_cache: &revalidate_always(),
+                policy: None,
+                client: None,
+            },
+            "http://127.0.0.1:9",
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::GATEWAY_TIMEOUT);
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_handle_sparse_index_serves_fresh_then_refreshes_in_background() {
+        install_rustls_provider();
+
+        let temp_dir = tempdir().unwrap();
+        let storage = Arc::new(FilesystemStorage::new(temp_dir.path().join("cache")));
+        storage.prepare().await.unwrap();
+        let index = Arc::new(CacheBackend::connect_memory().await.unwrap());
+
+        let body = b"{\"name\":\"serde\",\"vers\":\"1.0.0\"}\n";
+        let (upstream_base, server) = spawn_sequence_server(vec![
+            raw_response("200 OK", &[("ETag", "\"serde-v1\"")], body),
+            raw_response("304 Not Modified", &[], &[]),
+        ])
+        .await;
+
+        let fresh = IndexCacheConfig::default();
+        let mut outcomes = Vec::new();
+        for _ in 0..2 {
+            let (response, outcome) = handle_sparse_index_from(
+                "/index/se/rd/serde",
+                "http://localhost:8346",
+                storage.clone(),
+                index.clone(),
+                IndexOptions {
+                    offline: &OfflineConfig::default(),
+                    index_cache: &fresh,
+                    policy: None,
+                    client: None,
+                },
+                &upstream_base,
+            )
+            .await
+            .unwrap();
+            assert_eq!(body_bytes(response).await, body);
+            outcomes.push(outcome);
+        }
+        assert_eq!(outcomes, [CacheOutcome::Miss, CacheOutcome::Hit]);
+
+        let expired = IndexCacheConfig {
+            sparse_index_ttl_secs: 0,
+            ..IndexCacheConfig::default()
+        };
+        let (response, outcome) = handle_sparse_index_from(
+            "/index/se/rd/serde",
+            "http://localhost:8346",
+            storage.clone(),
+            index.clone(),
+            IndexOptions {
+                offline: &OfflineConfig::default(),
+                index_cache: &expired,
+                policy: None,
+                client: None,
+            },
+            &upstream_base,
+        )
+        .await
+        .unwrap();
+        assert_eq!(outcome, CacheOutcome::Stale);
+        assert_eq!(
+            response.headers().get(header::WARNING).unwrap(),
+            "110 - \"Response is Stale\""
+        );
+        assert_eq!(body_bytes(response).await, body);
+
+        // The background refresh is the second and last upstream request.
+        let requests = server.await.unwrap();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[1].contains("if-none-match: \"serde-v1\""));
+    }
+
+    #[cfg(feature = "sqlite")]
+    fn revalidate_always() -> IndexCacheConfig {
+        IndexCacheConfig {
+            sparse_index_ttl_secs: 0,
+            stale_while_revalidate_secs: 0,
+            ..IndexCacheConfig::default()
+        }
+    }
+
     #[cfg(feature = "sqlite")]
     fn install_rustls_provider() {
         static INIT: Once = Once::new();
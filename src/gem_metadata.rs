@@ -1,16 +1,29 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use std::path::Path;
 use tokio::task;
-use vein_adapter::GemMetadata;
+use vein_adapter::{CacheBackend, CacheBackendTrait, GemMetadata};
 
 mod analyzer;
+pub mod inspection;
 mod parser;
 mod sbom;
+pub mod symbols;
 
 #[cfg(test)]
 mod tests;
 
-pub use parser::parse_gem_metadata;
+pub use inspection::{ContentFinding, store_gem_findings};
+pub use parser::{parse_gem_archive, parse_gem_metadata};
+pub use sbom::generate_cyclonedx_sbom;
+pub use symbols::{RubySymbol, store_gem_symbols};
+
+/// Metadata of a gem plus what its sources define and do.
+#[derive(Debug, Clone)]
+pub struct GemArchive {
+    pub metadata: GemMetadata,
+    pub symbols: Vec<RubySymbol>,
+    pub findings: Vec<ContentFinding>,
+}
 
 /// Extract structured metadata from a cached gem archive.
 ///
@@ -24,6 +37,30 @@ pub async fn extract_gem_metadata(
     sha256: &str,
     existing_sbom: Option<serde_json::Value>,
 ) -> Result<Option<GemMetadata>> {
+    extract_gem_archive(
+        path,
+        name,
+        version,
+        platform,
+        size_bytes,
+        sha256,
+        existing_sbom,
+    )
+    .await
+    .map(|archive| archive.map(|archive| archive.metadata))
+}
+
+/// Like [`extract_gem_metadata`], also returning the Ruby symbols for the
+/// symbol index and suspicious-content findings.
+pub async fn extract_gem_archive(
+    path: &Path,
+    name: &str,
+    version: &str,
+    platform: Option<&str>,
+    size_bytes: u64,
+    sha256: &str,
+    existing_sbom: Option<serde_json::Value>,
+) -> Result<Option<GemArchive>> {
     let path = path.to_owned();
     let name = name.to_owned();
     let version = version.to_owned();
@@ -31,7 +68,7 @@ pub async fn extract_gem_metadata(
     let sha256 = sha256.to_owned();
 
     task::spawn_blocking(move || {
-        parse_gem_metadata(
+        parse_gem_archive(
             &path,
             &name,
             &version,
@@ -43,3 +80,13 @@ pub async fn extract_gem_metadata(
     })
     .await?
 }
+
+/// Stores an extracted gem's metadata, symbols and content findings.
+pub async fn store_gem_archive(index: &CacheBackend, archive: &GemArchive) -> Result<()> {
+    index
+        .upsert_metadata(&archive.metadata)
+        .await
+        .context("storing gem metadata")?;
+    store_gem_symbols(index, &archive.metadata, &archive.symbols).await?;
+    store_gem_findings(index, &archive.metadata, &archive.findings).await
+}
@@ -3,24 +3,59 @@ use std::{collections::BTreeSet, io::Read};
 use anyhow::{Context, Result};
 use tar::Archive;
 
+use super::inspection::{ContentFinding, MAX_FINDINGS_PER_GEM, is_inspected_path, scan_source};
+use super::symbols::{MAX_SOURCE_BYTES, MAX_SYMBOLS_PER_GEM, RubySymbol, scan_ruby_source};
+
 const EMBEDDED_BINARY_DIR_PREFIXES: &[&str] = &["vendor/", "libexec/", "resources/"];
 
-pub fn analyze_data_tar<R: Read>(reader: R) -> Result<(bool, bool, BTreeSet<String>)> {
+/// What a gem's `data.tar.gz` contains.
+#[derive(Debug, Default)]
+pub struct DataAnalysis {
+    pub has_native_extensions: bool,
+    pub has_embedded_binaries: bool,
+    pub languages: BTreeSet<String>,
+    pub symbols: Vec<RubySymbol>,
+    pub findings: Vec<ContentFinding>,
+}
+
+pub fn analyze_data_tar<R: Read>(reader: R) -> Result<DataAnalysis> {
     let mut archive = Archive::new(reader);
     let mut has_native_extensions = false;
     let mut has_embedded_binaries = false;
     let mut languages = BTreeSet::new();
+    let mut symbols = Vec::new();
+    let mut findings = Vec::new();
 
     for entry in archive.entries().context("reading gem data archive")? {
-        let entry = entry.context("reading file in data archive")?;
+        let mut entry = entry.context("reading file in data archive")?;
         let header = entry.header();
         if !header.entry_type().is_file() {
             continue;
         }
-        let path = entry.path().context("reading data archive path")?;
+        let size = header.size().unwrap_or(u64::MAX);
+        let path = entry
+            .path()
+            .context("reading data archive path")?
+            .into_owned();
         let path_str = path.to_string_lossy();
         let path_lower = path_str.to_ascii_lowercase();
 
+        if size <= MAX_SOURCE_BYTES && is_inspected_path(&path_str) {
+            let mut source = Vec::new();
+            entry
+                .read_to_end(&mut source)
+                .context("reading source from data archive")?;
+            let source = String::from_utf8_lossy(&source);
+            if path_lower.ends_with(".rb") && symbols.len() < MAX_SYMBOLS_PER_GEM {
+                symbols.extend(scan_ruby_source(&path_str, &source));
+                symbols.truncate(MAX_SYMBOLS_PER_GEM);
+            }
+            if findings.len() < MAX_FINDINGS_PER_GEM {
+                findings.extend(scan_source(&path_str, &source));
+                findings.truncate(MAX_FINDINGS_PER_GEM);
+            }
+        }
+
         if let Some(language) = detect_language_from_path(&path_str) {
             languages.insert(language.to_string());
         }
@@ -62,13 +97,15 @@ pub fn analyze_data_tar<R: Read>(reader: R) -> Result<(bool, bool, BTreeSet<Stri
                 has_embedded_binaries = true;
             }
         }
-
-        if has_native_extensions && has_embedded_binaries {
-            break;
-        }
     }
 
-    Ok((has_native_extensions, has_embedded_binaries, languages))
+    Ok(DataAnalysis {
+        has_native_extensions,
+        has_embedded_binaries,
+        languages,
+        symbols,
+        findings,
+    })
 }
 
 pub fn detect_language_from_path(path: &str) -> Option<&'static str> {
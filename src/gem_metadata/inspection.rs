@@ -0,0 +1,269 @@
+//! Suspicious-content scan of gem sources.
+//!
+//! Looks for the patterns behind past RubyGems and npm compromises: install
+//! hooks that reach the network, `eval` of decoded payloads, credential
+//! harvesting and `curl | sh`. Matching is per line and deliberately simple,
+//! so findings feed the `suspicious_content` risk rule and the admin UI
+//! instead of blocking anything on their own.
+
+use anyhow::{Context, Result};
+use vein_adapter::{CacheBackend, CacheBackendTrait, GemFindingRecord, GemMetadata};
+
+/// Findings kept per gem; one bad pattern repeated in generated code adds nothing.
+pub const MAX_FINDINGS_PER_GEM: usize = 200;
+const MAX_EXCERPT_CHARS: usize = 160;
+/// Unbroken base64 runs this long are payloads, not identifiers.
+const MIN_ENCODED_BLOB_CHARS: usize = 120;
+
+const NETWORK_CALLS: &[&str] = &[
+    "Net::HTTP",
+    "open-uri",
+    "URI.open",
+    "TCPSocket",
+    "UDPSocket",
+    "Socket.tcp",
+    "Faraday",
+    "HTTParty",
+    "RestClient",
+    "Excon",
+    "curl ",
+    "wget ",
+];
+const DECODERS: &[&str] = &[
+    "Base64.decode64",
+    "Base64.strict_decode64",
+    "Base64.urlsafe_decode64",
+    "unpack(\"m",
+    "unpack('m",
+    "unpack1(\"m",
+    "unpack1('m",
+    "pack(\"H*\")",
+    "pack('H*')",
+    "pack(\"c*\")",
+    "pack('c*')",
+    "Zlib::Inflate",
+    "Zlib.inflate",
+    "atob(",
+    "'base64')",
+    "\"base64\")",
+];
+const CREDENTIAL_FILES: &[&str] = &[
+    ".ssh/",
+    "id_rsa",
+    "id_ed25519",
+    ".aws/credentials",
+    ".gem/credentials",
+    ".git-credentials",
+    ".netrc",
+    ".npmrc",
+    ".docker/config.json",
+    ".kube/config",
+];
+const ENVIRONMENT_DUMPS: &[&str] = &[
+    "ENV.to_h",
+    "ENV.to_a",
+    "ENV.inspect",
+    "ENV.each",
+    "ENV.map",
+    "ENV.select",
+    "JSON.stringify(process.env",
+    "printenv",
+];
+const ENV_ACCESSORS: &[&str] = &["ENV[", "ENV.fetch(", "process.env"];
+const SECRET_NAMES: &[&str] = &[
+    "TOKEN",
+    "SECRET",
+    "PASSWORD",
+    "API_KEY",
+    "ACCESS_KEY",
+    "PRIVATE_KEY",
+];
+const SHELLS: &[&str] = &["sh", "bash", "zsh", "ruby", "python", "python3", "node"];
+
+/// A suspicious pattern family.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FindingRule {
+    /// `extconf.rb` or another install hook makes network calls.
+    InstallNetwork,
+    /// `eval` of a base64-decoded, packed or long encoded string.
+    ObfuscatedEval,
+    /// Reads SSH keys, credential files or secret environment variables.
+    CredentialAccess,
+    /// Pipes a download straight into a shell or interpreter.
+    PipeToShell,
+}
+
+impl FindingRule {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FindingRule::InstallNetwork => "install_network",
+            FindingRule::ObfuscatedEval => "obfuscated_eval",
+            FindingRule::CredentialAccess => "credential_access",
+            FindingRule::PipeToShell => "pipe_to_shell",
+        }
+    }
+}
+
+/// A suspicious line in a gem's sources.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentFinding {
+    pub file_path: String,
+    pub rule: FindingRule,
+    pub line: u32,
+    pub excerpt: String,
+}
+
+/// Whether `path` is a Ruby, JavaScript or shell source worth scanning.
+pub fn is_inspected_path(path: &str) -> bool {
+    let lower = path.to_ascii_lowercase();
+    let file_name = lower.rsplit('/').next().unwrap_or(&lower);
+    let extension = file_name.rsplit_once('.').map(|(_, ext)| ext);
+    matches!(
+        extension,
+        Some("rb" | "rake" | "gemspec" | "js" | "mjs" | "cjs" | "sh" | "bash")
+    ) || file_name == "rakefile"
+        // Extensionless executables are usually scripts
+        || (extension.is_none() && (lower.starts_with("bin/") || lower.starts_with("exe/")))
+}
+
+/// Code that runs when the gem is installed rather than when it is required.
+fn is_install_hook(path: &str) -> bool {
+    let lower = path.to_ascii_lowercase();
+    let file_name = lower.rsplit('/').next().unwrap_or(&lower);
+    matches!(file_name, "extconf.rb" | "mkrf_conf.rb")
+        || (lower.starts_with("ext/") && (file_name == "rakefile" || file_name.ends_with(".rb")))
+}
+
+/// Lists the suspicious lines of one source file.
+pub fn scan_source(file_path: &str, source: &str) -> Vec<ContentFinding> {
+    let install_hook = is_install_hook(file_path);
+    let mut findings = Vec::new();
+
+    for (index, raw) in source.lines().enumerate() {
+        let code = raw.trim();
+        if code.is_empty() || is_comment(code) {
+            continue;
+        }
+        let line = u32::try_from(index + 1).unwrap_or(u32::MAX);
+        let mut record = |rule| {
+            findings.push(ContentFinding {
+                file_path: file_path.to_string(),
+                rule,
+                line,
+                excerpt: excerpt(code),
+            });
+        };
+
+        if install_hook && contains_any(code, NETWORK_CALLS) {
+            record(FindingRule::InstallNetwork);
+        }
+        if calls_eval(code) && (contains_any(code, DECODERS) || has_encoded_blob(code)) {
+            record(FindingRule::ObfuscatedEval);
+        }
+        // Plenty of API clients read `ENV["SERVICE_TOKEN"]` at runtime; only
+        // install hooks reading named secrets are out of the ordinary.
+        if contains_any(code, CREDENTIAL_FILES)
+            || contains_any(code, ENVIRONMENT_DUMPS)
+            || (install_hook && reads_secret_env(code))
+        {
+            record(FindingRule::CredentialAccess);
+        }
+        if pipes_download_to_shell(code) {
+            record(FindingRule::PipeToShell);
+        }
+    }
+
+    findings
+}
+
+/// Replaces the stored findings of a gem with `findings`.
+pub async fn store_gem_findings(
+    index: &CacheBackend,
+    metadata: &GemMetadata,
+    findings: &[ContentFinding],
+) -> Result<()> {
+    let records: Vec<GemFindingRecord<'_>> = findings
+        .iter()
+        .map(|finding| GemFindingRecord {
+            rule: finding.rule.as_str(),
+            file_path: &finding.file_path,
+            line_number: i32::try_from(finding.line).ok(),
+            excerpt: &finding.excerpt,
+        })
+        .collect();
+    index
+        .replace_findings(
+            &metadata.name,
+            &metadata.version,
+            Some(metadata.platform.as_str()),
+            &records,
+        )
+        .await
+        .context("storing gem content findings")
+}
+
+fn is_comment(code: &str) -> bool {
+    (code.starts_with('#') && !code.starts_with("#!")) || code.starts_with("//")
+}
+
+fn contains_any(code: &str, needles: &[&str]) -> bool {
+    needles.iter().any(|needle| code.contains(needle))
+}
+
+/// `eval`, `instance_eval`, `class_eval`, ... or JavaScript's `Function(...)`.
+fn calls_eval(code: &str) -> bool {
+    code.contains("Function(")
+        || code.match_indices("eval").any(|(start, _)| {
+            let before = code[..start].chars().next_back();
+            let after = code[start + 4..].chars().next();
+            !before.is_some_and(|ch| ch.is_ascii_alphanumeric()) && matches!(after, Some('(' | ' '))
+        })
+}
+
+fn has_encoded_blob(code: &str) -> bool {
+    let mut run = 0;
+    for ch in code.chars() {
+        if ch.is_ascii_alphanumeric() || matches!(ch, '+' | '/' | '=') {
+            run += 1;
+            if run >= MIN_ENCODED_BLOB_CHARS {
+                return true;
+            }
+        } else {
+            run = 0;
+        }
+    }
+    false
+}
+
+fn reads_secret_env(code: &str) -> bool {
+    contains_any(code, ENV_ACCESSORS) && contains_any(code, SECRET_NAMES)
+}
+
+/// `curl ... | sh`, `wget -qO- ... | sudo bash` and the like.
+fn pipes_download_to_shell(code: &str) -> bool {
+    let Some(download) = ["curl", "wget"]
+        .iter()
+        .filter_map(|tool| code.find(tool))
+        .min()
+    else {
+        return false;
+    };
+    code[download..].split('|').skip(1).any(|stage| {
+        let mut words = stage.split_whitespace();
+        let command = match words.next() {
+            Some("sudo") => words.next(),
+            command => command,
+        };
+        command
+            .map(|command| command.trim_end_matches(['"', '\'', '`', ')']))
+            .map(|command| command.rsplit('/').next().unwrap_or(command))
+            .is_some_and(|command| SHELLS.contains(&command))
+    })
+}
+
+fn excerpt(code: &str) -> String {
+    match code.char_indices().nth(MAX_EXCERPT_CHARS) {
+        Some((cut, _)) => format!("{}…", &code[..cut]),
+        None => code.to_string(),
+    }
+}
@@ -8,9 +8,11 @@ use tar::Archive;
 use vein_adapter::{DependencyKind, GemDependency, GemMetadata};
 
 use crate::gem_metadata::{
+    GemArchive,
     analyzer::{analyze_data_tar, detect_language_from_path},
     sbom::generate_cyclonedx_sbom,
 };
+use crate::sbom::SBOM_GENERATOR;
 
 pub fn parse_gem_metadata(
     path: &Path,
@@ -21,6 +23,29 @@ pub fn parse_gem_metadata(
     sha256: &str,
     existing_sbom: Option<serde_json::Value>,
 ) -> Result<Option<GemMetadata>> {
+    parse_gem_archive(
+        path,
+        name,
+        version,
+        platform,
+        size_bytes,
+        sha256,
+        existing_sbom,
+    )
+    .map(|archive| archive.map(|archive| archive.metadata))
+}
+
+/// Like [`parse_gem_metadata`], also returning the Ruby symbols the gem defines
+/// and suspicious content found in its sources.
+pub fn parse_gem_archive(
+    path: &Path,
+    name: &str,
+    version: &str,
+    platform: Option<String>,
+    size_bytes: u64,
+    sha256: &str,
+    existing_sbom: Option<serde_json::Value>,
+) -> Result<Option<GemArchive>> {
     let file = File::open(path).with_context(|| format!("opening gem at {}", path.display()))?;
     let mut archive = Archive::new(file);
 
@@ -28,6 +53,8 @@ pub fn parse_gem_metadata(
     let mut has_native_extensions = false;
     let mut has_embedded_binaries = false;
     let mut detected_languages = BTreeSet::new();
+    let mut symbols = Vec::new();
+    let mut findings = Vec::new();
 
     for entry in archive.entries().context("reading gem archive entries")? {
         let entry = entry.context("accessing gem archive entry")?;
@@ -43,10 +70,12 @@ pub fn parse_gem_metadata(
             }
             "data.tar.gz" => {
                 let mut decoder = GzDecoder::new(entry);
-                let (native, vendor, languages) = analyze_data_tar(&mut decoder)?;
-                has_native_extensions |= native;
-                has_embedded_binaries |= vendor;
-                detected_languages.extend(languages);
+                let analysis = analyze_data_tar(&mut decoder)?;
+                has_native_extensions |= analysis.has_native_extensions;
+                has_embedded_binaries |= analysis.has_embedded_binaries;
+                detected_languages.extend(analysis.languages);
+                symbols = analysis.symbols;
+                findings = analysis.findings;
             }
             _ => {}
         }
@@ -152,10 +181,12 @@ pub fn parse_gem_metadata(
         size_bytes,
         sha256: sha256.to_string(),
         sbom: None,
+        sbom_generator: None,
     };
 
     match generate_cyclonedx_sbom(&metadata, existing_sbom) {
         Ok(sbom) => {
+            metadata.sbom_generator = sbom.as_ref().map(|_| SBOM_GENERATOR.to_string());
             metadata.sbom = sbom;
         }
         Err(err) => {
@@ -163,7 +194,11 @@ pub fn parse_gem_metadata(
         }
     }
 
-    Ok(Some(metadata))
+    Ok(Some(GemArchive {
+        metadata,
+        symbols,
+        findings,
+    }))
 }
 
 pub fn parse_dependencies(value: Option<&YamlValue>) -> Vec<GemDependency> {
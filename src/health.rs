@@ -0,0 +1,453 @@
+//! Liveness and readiness probes.
+//!
+//! `/livez` only proves the process is serving requests. `/readyz` runs the
+//! component checks below; each check's [`Criticality`] decides whether a
+//! failure takes the node out of rotation or only marks it degraded.
+
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use vein_adapter::{CacheBackend, CacheBackendTrait};
+
+use crate::config::{Config, Criticality};
+use crate::upstream::MirrorSnapshot;
+use crate::upstream::health::{observed_snapshot, origin_of};
+use crate::{catalog, crates, npm, quarantine};
+
+/// Prefix of the file written and removed in the storage root to prove it
+/// is writable. Each probe adds the pid and a counter so overlapping probes
+/// never remove each other's file.
+const WRITE_PROBE: &str = ".vein-readyz";
+
+static PROBE_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Ok,
+    Fail,
+    Skipped,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Readiness {
+    /// Every check passed.
+    Ok,
+    /// Only `warn` checks failed; the node still takes traffic.
+    Degraded,
+    /// A `critical` check failed.
+    Unavailable,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComponentCheck {
+    pub component: String,
+    pub status: CheckStatus,
+    pub criticality: Criticality,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub age_secs: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub free_bytes: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadinessReport {
+    pub status: Readiness,
+    pub checks: Vec<ComponentCheck>,
+    #[serde(default)]
+    pub mirrors: Vec<MirrorSnapshot>,
+}
+
+impl CheckStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CheckStatus::Ok => "ok",
+            CheckStatus::Fail => "fail",
+            CheckStatus::Skipped => "skipped",
+        }
+    }
+}
+
+impl Readiness {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Readiness::Ok => "ok",
+            Readiness::Degraded => "degraded",
+            Readiness::Unavailable => "unavailable",
+        }
+    }
+}
+
+impl ComponentCheck {
+    fn new(component: &str, criticality: Criticality) -> Self {
+        Self {
+            component: component.to_string(),
+            status: CheckStatus::Ok,
+            criticality,
+            detail: None,
+            latency_ms: None,
+            age_secs: None,
+            free_bytes: None,
+        }
+    }
+
+    fn skipped(component: &str, criticality: Criticality, detail: &str) -> Self {
+        Self {
+            status: CheckStatus::Skipped,
+            detail: Some(detail.to_string()),
+            ..Self::new(component, criticality)
+        }
+    }
+
+    fn fail(mut self, detail: impl Into<String>) -> Self {
+        self.status = CheckStatus::Fail;
+        self.detail = Some(detail.into());
+        self
+    }
+
+    fn detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+}
+
+impl ReadinessReport {
+    /// Whether the node should receive traffic.
+    pub fn is_ready(&self) -> bool {
+        self.status != Readiness::Unavailable
+    }
+}
+
+/// Runs every readiness check that is not set to `ignore`.
+pub async fn check_readiness(
+    config: &Config,
+    index: &CacheBackend,
+    mirrors: &[MirrorSnapshot],
+) -> ReadinessReport {
+    let criticality = &config.health.criticality;
+    let mut checks = vec![
+        gated(
+            "database",
+            criticality.database,
+            check_database(config, index),
+        )
+        .await,
+        gated("storage", criticality.storage, check_storage(config)).await,
+    ];
+    checks.extend(check_upstreams(config, mirrors));
+    checks.push(
+        gated(
+            "scheduler",
+            criticality.scheduler,
+            check_scheduler(config, index),
+        )
+        .await,
+    );
+    checks.push(gated("catalog", criticality.catalog, check_catalog(config, index)).await);
+
+    let failed = |level| {
+        checks
+            .iter()
+            .any(|check| check.status == CheckStatus::Fail && check.criticality == level)
+    };
+    let status = if failed(Criticality::Critical) {
+        Readiness::Unavailable
+    } else if failed(Criticality::Warn) {
+        Readiness::Degraded
+    } else {
+        Readiness::Ok
+    };
+
+    ReadinessReport {
+        status,
+        checks,
+        mirrors: mirrors.to_vec(),
+    }
+}
+
+async fn gated(
+    component: &str,
+    criticality: Criticality,
+    check: impl Future<Output = ComponentCheck>,
+) -> ComponentCheck {
+    if criticality == Criticality::Ignore {
+        return ComponentCheck::skipped(component, criticality, "ignored");
+    }
+    check.await
+}
+
+async fn check_database(config: &Config, index: &CacheBackend) -> ComponentCheck {
+    let check = ComponentCheck::new("database", config.health.criticality.database);
+    let started = Instant::now();
+    let result = index.ping().await;
+    let elapsed = started.elapsed();
+    let check = ComponentCheck {
+        latency_ms: Some((elapsed.as_secs_f64() * 10_000.0).round() / 10.0),
+        ..check
+    };
+    match result {
+        Err(err) => check.fail(format!("{err:#}")),
+        Ok(()) if elapsed > config.health.max_db_latency() => check.fail(format!(
+            "ping slower than {} ms",
+            config.health.max_db_latency_ms
+        )),
+        Ok(()) => check,
+    }
+}
+
+async fn check_storage(config: &Config) -> ComponentCheck {
+    let check = ComponentCheck::new("storage", config.health.criticality.storage);
+    let root = &config.storage.path;
+    if let Err(err) = probe_writable(root).await {
+        return check.fail(format!("{err:#}"));
+    }
+    match free_bytes(root) {
+        Some(free) => {
+            let check = ComponentCheck {
+                free_bytes: Some(free),
+                ..check
+            };
+            if free < config.health.min_free_bytes {
+                check.fail(format!(
+                    "{free} bytes free, below {}",
+                    config.health.min_free_bytes
+                ))
+            } else {
+                check
+            }
+        }
+        None => check.detail("free space unavailable on this platform"),
+    }
+}
+
+async fn probe_writable(root: &Path) -> Result<()> {
+    let probe = root.join(format!(
+        "{WRITE_PROBE}-{}-{}",
+        std::process::id(),
+        PROBE_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    tokio::fs::write(&probe, b"ok")
+        .await
+        .with_context(|| format!("writing {}", probe.display()))?;
+    tokio::fs::remove_file(&probe)
+        .await
+        .with_context(|| format!("removing {}", probe.display()))
+}
+
+#[cfg(unix)]
+fn free_bytes(root: &Path) -> Option<u64> {
+    let stat = rustix::fs::statvfs(root).ok()?;
+    Some(stat.f_bavail.saturating_mul(stat.f_frsize))
+}
+
+#[cfg(not(unix))]
+fn free_bytes(_root: &Path) -> Option<u64> {
+    None
+}
+
+/// One check per ecosystem: RubyGems from the configured mirrors' breakers,
+/// crates.io and npm from the passive health of their registry origins.
+fn check_upstreams(config: &Config, mirrors: &[MirrorSnapshot]) -> Vec<ComponentCheck> {
+    let criticality = config.health.criticality.upstream;
+    let ecosystems = ["upstream:rubygems", "upstream:crates", "upstream:npm"];
+    if criticality == Criticality::Ignore || config.offline.enabled {
+        let reason = if config.offline.enabled {
+            "offline mode"
+        } else {
+            "ignored"
+        };
+        return ecosystems
+            .iter()
+            .map(|component| ComponentCheck::skipped(component, criticality, reason))
+            .collect();
+    }
+
+    let rubygems = if config.upstream.is_none() {
+        ComponentCheck::skipped(ecosystems[0], criticality, "no upstream configured")
+    } else {
+        let check = ComponentCheck::new(ecosystems[0], criticality);
+        let healthy = mirrors.iter().filter(|mirror| mirror.healthy).count();
+        let detail = format!("{healthy}/{} mirrors healthy", mirrors.len());
+        if healthy == 0 {
+            check.fail(detail)
+        } else {
+            check.detail(detail)
+        }
+    };
+
+    vec![
+        rubygems,
+        observed_check(ecosystems[1], criticality, &crates::crates_index_base()),
+        observed_check(ecosystems[2], criticality, &npm::npm_registry_base()),
+    ]
+}
+
+fn observed_check(component: &str, criticality: Criticality, base: &str) -> ComponentCheck {
+    let origin = origin_of(base);
+    let check = ComponentCheck::new(component, criticality);
+    let Some(snapshot) = observed_snapshot(&origin) else {
+        return check.detail(format!("no requests to {origin} yet"));
+    };
+    let check = ComponentCheck {
+        latency_ms: snapshot.latency_ms,
+        ..check
+    };
+    if snapshot.healthy {
+        check.detail(origin)
+    } else {
+        check.fail(format!(
+            "circuit {} for {origin}: {}",
+            snapshot.state,
+            snapshot.last_error.as_deref().unwrap_or("unknown error")
+        ))
+    }
+}
+
+async fn check_scheduler(config: &Config, index: &CacheBackend) -> ComponentCheck {
+    let criticality = config.health.criticality.scheduler;
+    if !config.delay_policy.enabled {
+        return ComponentCheck::skipped("scheduler", criticality, "quarantine disabled");
+    }
+    let check = ComponentCheck::new("scheduler", criticality);
+    age_check(
+        check,
+        index.catalog_meta_get(quarantine::META_LAST_RUN).await,
+        config.health.max_scheduler_age(),
+        "promotion scheduler has not run",
+    )
+}
+
+async fn check_catalog(config: &Config, index: &CacheBackend) -> ComponentCheck {
+    let check = ComponentCheck::new("catalog", config.health.criticality.catalog);
+    age_check(
+        check,
+        index.catalog_meta_get(catalog::META_SYNCED_AT).await,
+        config.health.max_catalog_sync_age(),
+        "catalog has never been synced",
+    )
+}
+
+/// Fails `check` when the RFC 3339 timestamp is missing or older than `max`.
+fn age_check(
+    check: ComponentCheck,
+    stamp: Result<Option<String>>,
+    max: Duration,
+    missing: &str,
+) -> ComponentCheck {
+    let stamp = match stamp {
+        Ok(Some(stamp)) => stamp,
+        Ok(None) => return check.fail(missing),
+        Err(err) => return check.fail(format!("{err:#}")),
+    };
+    let Ok(at) = DateTime::parse_from_rfc3339(&stamp) else {
+        return check.fail(format!("unreadable timestamp {stamp:?}"));
+    };
+    let age = (Utc::now() - at.with_timezone(&Utc)).num_seconds().max(0);
+    let check = ComponentCheck {
+        age_secs: Some(age),
+        ..check
+    };
+    if age as u64 > max.as_secs() {
+        check.fail(format!("last run {age}s ago, limit {}s", max.as_secs()))
+    } else {
+        check
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "sqlite")]
+    use tempfile::tempdir;
+
+    #[cfg(feature = "sqlite")]
+    fn check<'a>(report: &'a ReadinessReport, component: &str) -> &'a ComponentCheck {
+        report
+            .checks
+            .iter()
+            .find(|check| check.component == component)
+            .unwrap()
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn readiness_reports_components_by_criticality() {
+        let temp_dir = tempdir().unwrap();
+        let index = CacheBackend::connect_memory().await.unwrap();
+        let mut config = Config::default();
+        config.storage.path = temp_dir.path().to_path_buf();
+        config.health.min_free_bytes = 0;
+
+        // The catalog has never synced, which only degrades the node by default.
+        let report = check_readiness(&config, &index, &[]).await;
+        assert_eq!(report.status, Readiness::Degraded);
+        assert!(report.is_ready());
+        assert_eq!(check(&report, "database").status, CheckStatus::Ok);
+        assert!(check(&report, "database").latency_ms.is_some());
+        assert_eq!(check(&report, "storage").status, CheckStatus::Ok);
+        assert_eq!(std::fs::read_dir(temp_dir.path()).unwrap().count(), 0);
+        assert_eq!(check(&report, "scheduler").status, CheckStatus::Skipped);
+        assert_eq!(
+            check(&report, "upstream:rubygems").status,
+            CheckStatus::Skipped
+        );
+        assert_eq!(check(&report, "catalog").status, CheckStatus::Fail);
+
+        let stale = (Utc::now() - chrono::Duration::days(3)).to_rfc3339();
+        index
+            .catalog_meta_set(catalog::META_SYNCED_AT, &stale)
+            .await
+            .unwrap();
+        config.health.criticality.catalog = Criticality::Critical;
+        let report = check_readiness(&config, &index, &[]).await;
+        assert_eq!(report.status, Readiness::Unavailable);
+        assert!(check(&report, "catalog").age_secs.unwrap() >= 3 * 24 * 60 * 60);
+
+        index
+            .catalog_meta_set(catalog::META_SYNCED_AT, &Utc::now().to_rfc3339())
+            .await
+            .unwrap();
+        config.health.min_free_bytes = u64::MAX;
+        config.health.criticality.storage = Criticality::Ignore;
+        let report = check_readiness(&config, &index, &[]).await;
+        assert_eq!(report.status, Readiness::Ok);
+        assert_eq!(check(&report, "storage").status, CheckStatus::Skipped);
+    }
+
+    #[tokio::test]
+    async fn overlapping_write_probes_succeed() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut probes = tokio::task::JoinSet::new();
+        for _ in 0..8 {
+            let root = temp_dir.path().to_path_buf();
+            probes.spawn(async move { probe_writable(&root).await });
+        }
+        while let Some(result) = probes.join_next().await {
+            result.unwrap().unwrap();
+        }
+        assert_eq!(std::fs::read_dir(temp_dir.path()).unwrap().count(), 0);
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn unwritable_storage_is_not_ready() {
+        let temp_dir = tempdir().unwrap();
+        let index = CacheBackend::connect_memory().await.unwrap();
+        let mut config = Config::default();
+        config.storage.path = temp_dir.path().join("missing");
+        config.health.criticality.catalog = Criticality::Ignore;
+
+        let report = check_readiness(&config, &index, &[]).await;
+        assert_eq!(report.status, Readiness::Unavailable);
+        assert_eq!(check(&report, "storage").status, CheckStatus::Fail);
+    }
+}
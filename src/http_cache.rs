@@ -1,17 +1,30 @@
+use std::collections::HashSet;
 use std::future::Future;
+use std::sync::{Arc, LazyLock, Mutex, PoisonError};
+use std::time::Duration;
 
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Utc};
 use rama::http::{Body, HeaderMap, HeaderValue, Response, StatusCode, header};
+use rama::telemetry::tracing::warn;
 use tokio::io::AsyncWriteExt;
 use vein_adapter::{CacheBackend, CacheBackendTrait, FilesystemStorage};
 
+use crate::config::{IndexCacheConfig, OfflineConfig};
+use crate::policy::IndexFilter;
+use crate::rate_limit::ClientBudget;
+
 #[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
 pub struct CacheEntryMeta {
     pub etag: Option<String>,
     pub last_modified: Option<String>,
+    /// When upstream last returned or confirmed this body.
+    #[serde(default)]
+    pub fetched_at: Option<DateTime<Utc>>,
 }
 
 impl CacheEntryMeta {
+    /// Validators from an upstream response received just now.
     pub fn from_headers(headers: &HeaderMap) -> Self {
         Self {
             etag: headers
@@ -22,6 +35,21 @@ impl CacheEntryMeta {
                 .get(header::LAST_MODIFIED)
                 .and_then(|v| v.to_str().ok())
                 .map(|s| s.to_string()),
+            fetched_at: Some(Utc::now()),
+        }
+    }
+
+    /// Time since upstream last confirmed the body; `None` for entries stored
+    /// before freshness was tracked.
+    pub fn age(&self) -> Option<Duration> {
+        self.fetched_at
+            .map(|at| (Utc::now() - at).to_std().unwrap_or_default())
+    }
+
+    fn touched(self) -> Self {
+        Self {
+            fetched_at: Some(Utc::now()),
+            ..self
         }
     }
 }
@@ -37,6 +65,9 @@ pub enum CacheOutcome {
     Hit,
     Miss,
     Revalidated,
+    /// Served from cache past its TTL (background refresh, upstream failure
+    /// or offline mode).
+    Stale,
     Pass,
 }
 
@@ -54,35 +85,201 @@ pub struct CachedTextOptions<'a> {
     pub include_content_length: bool,
     pub meta_mode: MetaStoreMode,
     pub strip_transfer_encoding: bool,
+    /// Serve only from cache, never calling `fetch`.
+    pub offline: bool,
+    /// Serve the cached body when `fetch` fails or upstream returns 5xx.
+    pub stale_if_error: bool,
+    /// Serve the cached body without contacting upstream for this long after
+    /// it was fetched.
+    pub fresh_for: Duration,
+    /// Past `fresh_for`, keep serving the cached body for this long while it
+    /// is refreshed in the background.
+    pub stale_while_revalidate: Duration,
+}
+
+/// Settings shared by the registry index and metadata handlers.
+#[derive(Debug, Clone, Copy)]
+pub struct IndexOptions<'a> {
+    pub offline: &'a OfflineConfig,
+    pub index_cache: &'a IndexCacheConfig,
+    /// Hides versions refused by policy rules.
+    pub policy: Option<&'a IndexFilter>,
+    /// Client whose request this is; upstream refreshes wait for its global
+    /// upstream slot.
+    pub client: Option<&'a ClientBudget>,
+}
+
+/// Why a cached body is served without revalidation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StaleReason {
+    Revalidating,
+    Offline,
+    UpstreamFailed,
+}
+
+impl StaleReason {
+    /// RFC 7234 `Warning` header value.
+    fn warning(self) -> &'static str {
+        match self {
+            Self::Revalidating => "110 - \"Response is Stale\"",
+            Self::Offline => "112 - \"Disconnected Operation\"",
+            Self::UpstreamFailed => "111 - \"Revalidation Failed\"",
+        }
+    }
+}
+
+/// Storage paths with a background refresh in flight, so a burst of stale
+/// hits triggers a single upstream request.
+static REFRESHING: LazyLock<Mutex<HashSet<String>>> = LazyLock::new(Default::default);
+
+/// Where a background refresh stores what it fetched.
+struct RefreshTarget {
+    storage_path: String,
+    meta_key: String,
+    meta_mode: MetaStoreMode,
 }
 
 pub async fn fetch_cached_text<F, Fut, T, TFut>(
-    storage: &FilesystemStorage,
-    index: &CacheBackend,
+    storage: &Arc<FilesystemStorage>,
+    index: &Arc<CacheBackend>,
     options: CachedTextOptions<'_>,
     fetch: F,
     transform: T,
 ) -> Result<CachedFetchResult>
 where
-    F: FnOnce(HeaderMap) -> Fut,
-    Fut: Future<Output = Result<Response<Body>>>,
+    F: FnOnce(HeaderMap) -> Fut + Send + 'static,
+    Fut: Future<Output = Result<Response<Body>>> + Send + 'static,
     T: FnOnce(Vec<u8>) -> TFut,
     TFut: Future<Output = Result<Vec<u8>>>,
 {
-    let cached_bytes = tokio::fs::read(storage.resolve(options.storage_path))
-        .await
-        .ok();
+    let cached_path = storage.resolve(options.storage_path);
+    let cached_bytes = tokio::fs::read(&cached_path).await.ok();
     let had_cache = cached_bytes.is_some();
 
     let cached_meta = load_cached_meta(index, options.meta_key, options.meta_mode).await?;
+
+    if options.offline {
+        let Some(body) = cached_bytes else {
+            return Ok(CachedFetchResult {
+                response: offline_miss()?,
+                outcome: CacheOutcome::Pass,
+            });
+        };
+        let response = serve_stale(
+            &cached_path,
+            body,
+            cached_meta.unwrap_or_default(),
+            &options,
+            StaleReason::Offline,
+            transform,
+        )
+        .await?;
+        return Ok(CachedFetchResult {
+            response,
+            outcome: CacheOutcome::Stale,
+        });
+    }
+
+    let age = cached_meta
+        .as_ref()
+        .filter(|_| had_cache)
+        .and_then(CacheEntryMeta::age);
+    if let Some(age) = age
+        && age < options.fresh_for
+    {
+        let meta = cached_meta.unwrap_or_default();
+        let transformed = transform(cached_bytes.unwrap_or_default()).await?;
+        let mut response = build_cached_response(
+            &transformed,
+            &meta,
+            options.content_type,
+            options.cache_control,
+            options.include_content_length,
+        )?;
+        response
+            .headers_mut()
+            .insert(header::AGE, HeaderValue::from(age.as_secs()));
+        return Ok(CachedFetchResult {
+            response,
+            outcome: CacheOutcome::Hit,
+        });
+    }
+    if let Some(age) = age
+        && age < options.fresh_for + options.stale_while_revalidate
+    {
+        let meta = cached_meta.unwrap_or_default();
+        spawn_refresh(storage, index, &options, meta.clone(), fetch);
+        let response = serve_stale(
+            &cached_path,
+            cached_bytes.unwrap_or_default(),
+            meta,
+            &options,
+            StaleReason::Revalidating,
+            transform,
+        )
+        .await?;
+        return Ok(CachedFetchResult {
+            response,
+            outcome: CacheOutcome::Stale,
+        });
+    }
+
     let request_headers = build_conditional_headers(&cached_meta);
 
-    let response = fetch(request_headers).await?;
+    let response = match fetch(request_headers).await {
+        Ok(response) => response,
+        Err(err) => {
+            let Some(body) = cached_bytes.filter(|_| options.stale_if_error) else {
+                return Err(err);
+            };
+            warn!(
+                error = %err,
+                path = options.storage_path,
+                "upstream unreachable, serving stale cached copy"
+            );
+            let response = serve_stale(
+                &cached_path,
+                body,
+                cached_meta.unwrap_or_default(),
+                &options,
+                StaleReason::UpstreamFailed,
+                transform,
+            )
+            .await?;
+            return Ok(CachedFetchResult {
+                response,
+                outcome: CacheOutcome::Stale,
+            });
+        }
+    };
     let status = response.status();
 
+    if status.is_server_error() && options.stale_if_error && had_cache {
+        let body = cached_bytes.unwrap_or_default();
+        warn!(
+            status = %status,
+            path = options.storage_path,
+            "upstream failed, serving stale cached copy"
+        );
+        let response = serve_stale(
+            &cached_path,
+            body,
+            cached_meta.unwrap_or_default(),
+            &options,
+            StaleReason::UpstreamFailed,
+            transform,
+        )
+        .await?;
+        return Ok(CachedFetchResult {
+            response,
+            outcome: CacheOutcome::Stale,
+        });
+    }
+
     if status == StatusCode::NOT_MODIFIED && cached_bytes.is_some() {
         let body = cached_bytes.unwrap_or_default();
-        let meta = cached_meta.unwrap_or_default();
+        let meta = cached_meta.unwrap_or_default().touched();
+        store_cached_meta(index, options.meta_key, &meta, options.meta_mode).await?;
         let transformed = transform(body).await?;
         let response = build_cached_response(
             &transformed,
@@ -98,20 +295,17 @@ where
     }
 
     if status.is_success() {
-        use rama::http::body::util::BodyExt;
-        let headers = response.headers().clone();
-        let body = response
-            .into_body()
-            .collect()
-            .await
-            .context("reading cached response body")?
-            .to_bytes();
-        let meta = CacheEntryMeta::from_headers(&headers);
-
-        persist_body(storage, options.storage_path, &body).await?;
-        store_cached_meta(index, options.meta_key, &meta, options.meta_mode).await?;
+        let (body, meta) = persist_response(
+            storage,
+            index,
+            options.storage_path,
+            options.meta_key,
+            options.meta_mode,
+            response,
+        )
+        .await?;
 
-        let transformed = transform(body.to_vec()).await?;
+        let transformed = transform(body).await?;
         let response = build_cached_response(
             &transformed,
             &meta,
@@ -134,6 +328,149 @@ where
     })
 }
 
+/// Serves a cached body without revalidating it, flagged with `Warning` and
+/// an `Age` taken from the file's modification time.
+async fn serve_stale<T, TFut>(
+    cached_path: &std::path::Path,
+    body: Vec<u8>,
+    meta: CacheEntryMeta,
+    options: &CachedTextOptions<'_>,
+    reason: StaleReason,
+    transform: T,
+) -> Result<Response<Body>>
+where
+    T: FnOnce(Vec<u8>) -> TFut,
+    TFut: Future<Output = Result<Vec<u8>>>,
+{
+    let age = match meta.age() {
+        Some(age) => age.as_secs(),
+        None => tokio::fs::metadata(cached_path)
+            .await
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or(0),
+    };
+
+    let transformed = transform(body).await?;
+    let mut response = build_cached_response(
+        &transformed,
+        &meta,
+        options.content_type,
+        options.cache_control,
+        options.include_content_length,
+    )?;
+    let headers = response.headers_mut();
+    headers.insert(header::AGE, HeaderValue::from(age));
+    headers.insert(header::WARNING, HeaderValue::from_static(reason.warning()));
+    Ok(response)
+}
+
+/// Revalidates an entry in the background unless a refresh for the same path
+/// is already running.
+fn spawn_refresh<F, Fut>(
+    storage: &Arc<FilesystemStorage>,
+    index: &Arc<CacheBackend>,
+    options: &CachedTextOptions<'_>,
+    meta: CacheEntryMeta,
+    fetch: F,
+) where
+    F: FnOnce(HeaderMap) -> Fut + Send + 'static,
+    Fut: Future<Output = Result<Response<Body>>> + Send + 'static,
+{
+    let target = RefreshTarget {
+        storage_path: options.storage_path.to_string(),
+        meta_key: options.meta_key.to_string(),
+        meta_mode: options.meta_mode,
+    };
+    let started = REFRESHING
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .insert(target.storage_path.clone());
+    if !started {
+        return;
+    }
+
+    let storage = Arc::clone(storage);
+    let index = Arc::clone(index);
+    tokio::spawn(async move {
+        if let Err(err) = refresh(&storage, &index, &target, meta, fetch).await {
+            warn!(
+                error = %err,
+                path = %target.storage_path,
+                "background revalidation failed"
+            );
+        }
+        REFRESHING
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&target.storage_path);
+    });
+}
+
+async fn refresh<F, Fut>(
+    storage: &FilesystemStorage,
+    index: &CacheBackend,
+    target: &RefreshTarget,
+    meta: CacheEntryMeta,
+    fetch: F,
+) -> Result<()>
+where
+    F: FnOnce(HeaderMap) -> Fut,
+    Fut: Future<Output = Result<Response<Body>>>,
+{
+    let response = fetch(build_conditional_headers(&Some(meta.clone()))).await?;
+    let status = response.status();
+    if status == StatusCode::NOT_MODIFIED {
+        return store_cached_meta(index, &target.meta_key, &meta.touched(), target.meta_mode).await;
+    }
+    if !status.is_success() {
+        bail!("upstream returned {status}");
+    }
+    persist_response(
+        storage,
+        index,
+        &target.storage_path,
+        &target.meta_key,
+        target.meta_mode,
+        response,
+    )
+    .await?;
+    Ok(())
+}
+
+/// Stores a successful upstream body and its validators, returning both.
+async fn persist_response(
+    storage: &FilesystemStorage,
+    index: &CacheBackend,
+    storage_path: &str,
+    meta_key: &str,
+    meta_mode: MetaStoreMode,
+    response: Response<Body>,
+) -> Result<(Vec<u8>, CacheEntryMeta)> {
+    use rama::http::body::util::BodyExt;
+    let meta = CacheEntryMeta::from_headers(response.headers());
+    let body = response
+        .into_body()
+        .collect()
+        .await
+        .context("reading cached response body")?
+        .to_bytes();
+
+    persist_body(storage, storage_path, &body).await?;
+    store_cached_meta(index, meta_key, &meta, meta_mode).await?;
+    Ok((body.to_vec(), meta))
+}
+
+fn offline_miss() -> Result<Response<Body>> {
+    Response::builder()
+        .status(StatusCode::GATEWAY_TIMEOUT)
+        .header(header::CONTENT_TYPE, "text/plain")
+        .body(Body::from("not cached (offline mode)"))
+        .context("building offline response")
+}
+
 /// Inserts `name: value` into `headers` when `value` is present and parses into
 /// a valid header value; otherwise leaves the map unchanged.
 fn insert_opt_header(headers: &mut HeaderMap, name: header::HeaderName, value: &Option<String>) {
@@ -276,6 +613,7 @@ mod tests {
         let meta = CacheEntryMeta {
             etag: Some("\"abc\"".to_string()),
             last_modified: Some("Wed, 01 Jan 2025 00:00:00 GMT".to_string()),
+            fetched_at: None,
         };
         let headers = build_conditional_headers(&Some(meta));
         assert_eq!(headers.get(header::IF_NONE_MATCH).unwrap(), "\"abc\"");
@@ -7,14 +7,27 @@
     clippy::unused_async
 )]
 
+pub mod advisories;
+pub mod bundle;
 pub mod catalog;
 pub mod config;
 pub mod crates;
 pub mod db;
 pub mod gem_metadata;
+pub mod gem_requirement;
+pub mod health;
 pub mod http_cache;
+pub mod license;
+pub mod lockfile;
+pub mod notifications;
 pub mod npm;
+pub mod package_metadata;
+pub mod policy;
 pub mod proxy;
 pub mod quarantine;
+pub mod rate_limit;
+pub mod sbom;
+pub mod typosquat;
 pub mod upstream;
 pub mod util;
+pub mod yank_sync;
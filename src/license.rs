@@ -0,0 +1,547 @@
+//! License policy evaluation and reporting.
+//!
+//! Declared licenses come from the metadata extracted when an artifact is
+//! cached: a gem's `licenses` list, a crate's `license` field or an npm
+//! package's `license`. They are parsed as SPDX expressions (`OR` needs one
+//! acceptable side, `AND` needs all) and checked against `[license_policy]`.
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use vein_adapter::{CacheBackend, CacheBackendTrait, Ecosystem, PackageLicense};
+
+use crate::config::{LicenseAction, LicensePolicyConfig, LicenseTerms};
+use crate::util::glob_match;
+
+/// Label used in reports for packages without a declared license.
+pub const NO_LICENSE: &str = "(none)";
+
+/// Outcome of checking a declared license against the policy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LicenseVerdict {
+    Allowed,
+    /// No license declared, which `allow_unknown` accepts.
+    Unknown,
+    /// Breaks the policy; `reason` names the offending licenses.
+    Violation {
+        reason: String,
+        action: LicenseAction,
+    },
+}
+
+impl LicenseVerdict {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Allowed => "allowed",
+            Self::Unknown => "unknown",
+            Self::Violation { .. } => "violation",
+        }
+    }
+}
+
+/// Checks the declared `license` of a package against the policy.
+pub fn evaluate(
+    config: &LicensePolicyConfig,
+    ecosystem: Ecosystem,
+    name: &str,
+    license: Option<&str>,
+) -> LicenseVerdict {
+    let terms = config.terms_for(ecosystem, name);
+    let Some(license) = license.map(str::trim).filter(|license| !license.is_empty()) else {
+        return if config.allow_unknown {
+            LicenseVerdict::Unknown
+        } else {
+            LicenseVerdict::Violation {
+                reason: "no license declared".to_string(),
+                action: terms.action,
+            }
+        };
+    };
+
+    match rejected(&parse_expression(license), &terms) {
+        None => LicenseVerdict::Allowed,
+        Some(mut offenders) => {
+            offenders.dedup();
+            let reason = offenders
+                .iter()
+                .map(|id| {
+                    if is_denied(id, &terms) {
+                        format!("{id} is denied")
+                    } else {
+                        format!("{id} is not allowed")
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("; ");
+            LicenseVerdict::Violation {
+                reason,
+                action: terms.action,
+            }
+        }
+    }
+}
+
+/// Declared license of a cached package version; `None` when its metadata
+/// has not been extracted.
+pub async fn cached_license(
+    index: &CacheBackend,
+    ecosystem: Ecosystem,
+    name: &str,
+    version: &str,
+    platform: Option<&str>,
+) -> Result<Option<PackageLicense>> {
+    let license = if ecosystem == Ecosystem::RubyGems {
+        index
+            .gem_metadata(name, version, Some(platform.unwrap_or("ruby")))
+            .await
+            .context("loading gem metadata for license policy")?
+            .map(|metadata| PackageLicense::gem_expression(&metadata.licenses))
+    } else {
+        index
+            .package_metadata(ecosystem, name, version)
+            .await
+            .context("loading package metadata for license policy")?
+            .map(|metadata| metadata.license)
+    };
+
+    Ok(license.map(|license| PackageLicense {
+        ecosystem,
+        name: name.to_string(),
+        version: version.to_string(),
+        platform: platform.map(String::from),
+        license,
+    }))
+}
+
+/// One cached package version in the license report.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct LicenseReportEntry {
+    pub ecosystem: Ecosystem,
+    pub name: String,
+    pub version: String,
+    pub platform: Option<String>,
+    pub license: Option<String>,
+    /// `allowed`, `unknown` or `violation`
+    pub status: &'static str,
+    pub reason: Option<String>,
+}
+
+/// Package count of one declared license.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct LicenseSummary {
+    pub license: String,
+    pub packages: usize,
+    pub violations: usize,
+}
+
+/// Every analyzed package version with its license and policy verdict.
+pub async fn license_report(
+    config: &LicensePolicyConfig,
+    index: &CacheBackend,
+) -> Result<Vec<LicenseReportEntry>> {
+    let licenses = index
+        .package_licenses()
+        .await
+        .context("listing package licenses")?;
+
+    Ok(licenses
+        .into_iter()
+        .map(|package| {
+            let verdict = evaluate(
+                config,
+                package.ecosystem,
+                &package.name,
+                package.license.as_deref(),
+            );
+            let status = verdict.as_str();
+            let reason = match verdict {
+                LicenseVerdict::Violation { reason, .. } => Some(reason),
+                _ => None,
+            };
+            LicenseReportEntry {
+                ecosystem: package.ecosystem,
+                name: package.name,
+                version: package.version,
+                platform: package.platform,
+                license: package.license,
+                status,
+                reason,
+            }
+        })
+        .collect())
+}
+
+/// Groups report entries by license, most common first.
+pub fn summarize(entries: &[LicenseReportEntry]) -> Vec<LicenseSummary> {
+    let mut summary: Vec<LicenseSummary> = Vec::new();
+    for entry in entries {
+        let license = entry.license.as_deref().unwrap_or(NO_LICENSE);
+        let position = match summary.iter().position(|row| row.license == license) {
+            Some(position) => position,
+            None => {
+                summary.push(LicenseSummary {
+                    license: license.to_string(),
+                    packages: 0,
+                    violations: 0,
+                });
+                summary.len() - 1
+            }
+        };
+        summary[position].packages += 1;
+        if entry.status == "violation" {
+            summary[position].violations += 1;
+        }
+    }
+    summary.sort_by(|a, b| {
+        b.packages
+            .cmp(&a.packages)
+            .then_with(|| a.license.cmp(&b.license))
+    });
+    summary
+}
+
+/// Renders the report as CSV with a header row.
+pub fn report_csv(entries: &[LicenseReportEntry]) -> String {
+    let mut csv = String::from("ecosystem,name,version,platform,license,status,reason\n");
+    for entry in entries {
+        let fields = [
+            entry.ecosystem.as_str(),
+            &entry.name,
+            &entry.version,
+            entry.platform.as_deref().unwrap_or(""),
+            entry.license.as_deref().unwrap_or(""),
+            entry.status,
+            entry.reason.as_deref().unwrap_or(""),
+        ];
+        let row: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+        csv.push_str(&row.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// A parsed SPDX license expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Expression {
+    /// A license identifier, with its `WITH` exception if any.
+    License(String),
+    And(Vec<Expression>),
+    Or(Vec<Expression>),
+}
+
+/// Parses an SPDX expression. Old `MIT/Apache-2.0` slashes mean `OR`; text
+/// that is not an expression (e.g. `MIT License`) is one identifier.
+fn parse_expression(text: &str) -> Expression {
+    let spaced = text
+        .replace('(', " ( ")
+        .replace(')', " ) ")
+        .replace('/', " OR ");
+    let tokens: Vec<&str> = spaced.split_whitespace().collect();
+    let mut parser = Parser {
+        tokens: &tokens,
+        position: 0,
+    };
+    match parser.or_expression() {
+        Some(expression) if parser.position == tokens.len() => expression,
+        _ => Expression::License(text.trim().to_string()),
+    }
+}
+
+struct Parser<'a> {
+    tokens: &'a [&'a str],
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&'a str> {
+        self.tokens.get(self.position).copied()
+    }
+
+    fn next(&mut self) -> Option<&'a str> {
+        let token = self.peek()?;
+        self.position += 1;
+        Some(token)
+    }
+
+    fn keyword(&mut self, keyword: &str) -> bool {
+        let matched = self
+            .peek()
+            .is_some_and(|token| token.eq_ignore_ascii_case(keyword));
+        if matched {
+            self.position += 1;
+        }
+        matched
+    }
+
+    fn or_expression(&mut self) -> Option<Expression> {
+        let mut parts = vec![self.and_expression()?];
+        while self.keyword("OR") {
+            parts.push(self.and_expression()?);
+        }
+        Some(if parts.len() == 1 {
+            parts.remove(0)
+        } else {
+            Expression::Or(parts)
+        })
+    }
+
+    fn and_expression(&mut self) -> Option<Expression> {
+        let mut parts = vec![self.term()?];
+        while self.keyword("AND") {
+            parts.push(self.term()?);
+        }
+        Some(if parts.len() == 1 {
+            parts.remove(0)
+        } else {
+            Expression::And(parts)
+        })
+    }
+
+    fn term(&mut self) -> Option<Expression> {
+        if self.keyword("(") {
+            let inner = self.or_expression()?;
+            return self.keyword(")").then_some(inner);
+        }
+        let id = self.next()?;
+        if [")", "AND", "OR", "WITH"]
+            .iter()
+            .any(|reserved| id.eq_ignore_ascii_case(reserved))
+        {
+            return None;
+        }
+        let id = id.to_string();
+        if self.keyword("WITH") {
+            let exception = self.next()?;
+            return Some(Expression::License(format!("{id} WITH {exception}")));
+        }
+        Some(Expression::License(id))
+    }
+}
+
+/// Licenses that make `expression` unacceptable, or `None` when it passes.
+fn rejected(expression: &Expression, terms: &LicenseTerms<'_>) -> Option<Vec<String>> {
+    match expression {
+        Expression::License(id) => (!is_acceptable(id, terms)).then(|| vec![id.clone()]),
+        Expression::And(parts) => {
+            let offenders: Vec<String> = parts
+                .iter()
+                .filter_map(|part| rejected(part, terms))
+                .flatten()
+                .collect();
+            (!offenders.is_empty()).then_some(offenders)
+        }
+        Expression::Or(parts) => {
+            let mut offenders = Vec::new();
+            for part in parts {
+                offenders.extend(rejected(part, terms)?);
+            }
+            Some(offenders)
+        }
+    }
+}
+
+fn is_acceptable(id: &str, terms: &LicenseTerms<'_>) -> bool {
+    !is_denied(id, terms) && (terms.allow.is_empty() || matches_any(terms.allow, id))
+}
+
+fn is_denied(id: &str, terms: &LicenseTerms<'_>) -> bool {
+    matches_any(terms.deny, id)
+}
+
+/// Matches the identifier, ignoring case, with and without its exception
+/// and trailing `+`.
+fn matches_any(patterns: &[String], id: &str) -> bool {
+    let id = id.to_ascii_lowercase();
+    let base = id.split(" with ").next().unwrap_or(&id);
+    let candidates = [id.as_str(), base, base.trim_end_matches('+')];
+    patterns.iter().any(|pattern| {
+        let pattern = pattern.to_ascii_lowercase();
+        candidates
+            .iter()
+            .any(|candidate| glob_match(&pattern, candidate))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::LicenseRule;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    fn policy() -> LicensePolicyConfig {
+        LicensePolicyConfig {
+            enabled: true,
+            allow: strings(&["MIT", "Apache-2.0", "BSD-*", "ISC"]),
+            deny: strings(&["GPL-*", "AGPL-*"]),
+            ..LicensePolicyConfig::default()
+        }
+    }
+
+    #[test]
+    fn parses_spdx_expressions() {
+        assert_eq!(
+            parse_expression("MIT OR (Apache-2.0 AND BSD-3-Clause)"),
+            Expression::Or(vec![
+                Expression::License("MIT".into()),
+                Expression::And(vec![
+                    Expression::License("Apache-2.0".into()),
+                    Expression::License("BSD-3-Clause".into()),
+                ]),
+            ])
+        );
+        assert_eq!(
+            parse_expression("MIT/Apache-2.0"),
+            Expression::Or(vec![
+                Expression::License("MIT".into()),
+                Expression::License("Apache-2.0".into()),
+            ])
+        );
+        assert_eq!(
+            parse_expression("GPL-2.0-only WITH Classpath-exception-2.0"),
+            Expression::License("GPL-2.0-only WITH Classpath-exception-2.0".into())
+        );
+        assert_eq!(
+            parse_expression("MIT License"),
+            Expression::License("MIT License".into())
+        );
+    }
+
+    #[test]
+    fn evaluates_expressions_against_allow_and_deny_lists() {
+        let config = policy();
+        let verdict =
+            |license| evaluate(&config, Ecosystem::CratesIo, "serde", Some(license)).as_str();
+
+        assert_eq!(verdict("MIT OR Apache-2.0"), "allowed");
+        assert_eq!(verdict("MIT OR GPL-3.0-only"), "allowed");
+        assert_eq!(verdict("BSD-3-Clause"), "allowed");
+        assert_eq!(verdict("mit"), "allowed");
+        assert_eq!(verdict("MIT AND GPL-3.0-only"), "violation");
+        assert_eq!(verdict("MPL-2.0"), "violation");
+        assert_eq!(
+            evaluate(&config, Ecosystem::Npm, "x", Some("GPL-2.0+ OR MPL-2.0")),
+            LicenseVerdict::Violation {
+                reason: "GPL-2.0+ is denied; MPL-2.0 is not allowed".to_string(),
+                action: LicenseAction::Warn,
+            }
+        );
+        assert_eq!(
+            evaluate(&config, Ecosystem::Npm, "x", None),
+            LicenseVerdict::Unknown
+        );
+        let strict = LicensePolicyConfig {
+            allow_unknown: false,
+            ..policy()
+        };
+        assert_eq!(
+            evaluate(&strict, Ecosystem::Npm, "x", Some(" ")).as_str(),
+            "violation"
+        );
+    }
+
+    #[test]
+    fn first_matching_rule_replaces_the_lists() {
+        let config = LicensePolicyConfig {
+            rules: vec![
+                LicenseRule {
+                    ecosystems: vec![Ecosystem::RubyGems],
+                    packages: strings(&["acme-*"]),
+                    allow: Vec::new(),
+                    deny: Vec::new(),
+                    action: None,
+                },
+                LicenseRule {
+                    ecosystems: vec![Ecosystem::Npm],
+                    packages: Vec::new(),
+                    allow: Vec::new(),
+                    deny: strings(&["WTFPL"]),
+                    action: Some(LicenseAction::Block),
+                },
+            ],
+            ..policy()
+        };
+
+        let verdict = |ecosystem, name, license| evaluate(&config, ecosystem, name, Some(license));
+        assert_eq!(
+            verdict(Ecosystem::RubyGems, "acme-billing", "Proprietary"),
+            LicenseVerdict::Allowed
+        );
+        assert_eq!(
+            verdict(Ecosystem::RubyGems, "rails", "Proprietary").as_str(),
+            "violation"
+        );
+        assert_eq!(
+            verdict(Ecosystem::Npm, "left-pad", "WTFPL"),
+            LicenseVerdict::Violation {
+                reason: "WTFPL is denied".to_string(),
+                action: LicenseAction::Block,
+            }
+        );
+        assert_eq!(
+            verdict(Ecosystem::Npm, "left-pad", "Unlicense"),
+            LicenseVerdict::Allowed
+        );
+    }
+
+    #[test]
+    fn renders_csv_and_summary() {
+        let entry = |name: &str, license: Option<&str>, status| LicenseReportEntry {
+            ecosystem: Ecosystem::RubyGems,
+            name: name.to_string(),
+            version: "1.0.0".to_string(),
+            platform: Some("ruby".to_string()),
+            license: license.map(String::from),
+            status,
+            reason: (status == "violation").then(|| "GPL-3.0, really is denied".to_string()),
+        };
+        let entries = [
+            entry("a", Some("MIT"), "allowed"),
+            entry("b", Some("GPL-3.0"), "violation"),
+            entry("c", Some("MIT"), "allowed"),
+            entry("d", None, "unknown"),
+        ];
+
+        let csv = report_csv(&entries);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            lines[0],
+            "ecosystem,name,version,platform,license,status,reason"
+        );
+        assert_eq!(
+            lines[2],
+            "rubygems,b,1.0.0,ruby,GPL-3.0,violation,\"GPL-3.0, really is denied\""
+        );
+        assert_eq!(lines[4], "rubygems,d,1.0.0,ruby,,unknown,");
+
+        assert_eq!(
+            summarize(&entries),
+            [
+                LicenseSummary {
+                    license: "MIT".to_string(),
+                    packages: 2,
+                    violations: 0,
+                },
+                LicenseSummary {
+                    license: NO_LICENSE.to_string(),
+                    packages: 1,
+                    violations: 0,
+                },
+                LicenseSummary {
+                    license: "GPL-3.0".to_string(),
+                    packages: 1,
+                    violations: 1,
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,174 @@
+//! Lockfile parsing.
+//!
+//! Extracts the registry packages pinned by `Gemfile.lock`, `Cargo.lock`,
+//! `package-lock.json` and `pnpm-lock.yaml`. Path, git and workspace
+//! dependencies are skipped since Vein never serves them.
+
+pub mod audit;
+mod cargo;
+mod gemfile;
+mod npm;
+mod pnpm;
+
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+use vein_adapter::{AssetKey, AssetKind, Ecosystem};
+
+/// Supported lockfile formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LockfileKind {
+    GemfileLock,
+    CargoLock,
+    PackageLock,
+    PnpmLock,
+}
+
+impl LockfileKind {
+    /// Detects the format from a file name, falling back to the content.
+    pub fn detect(file_name: Option<&str>, content: &str) -> Option<Self> {
+        let base = file_name
+            .map(|name| name.rsplit(['/', '\\']).next().unwrap_or(name))
+            .unwrap_or_default();
+        match base {
+            "Gemfile.lock" | "gems.locked" => return Some(Self::GemfileLock),
+            "Cargo.lock" => return Some(Self::CargoLock),
+            "package-lock.json" | "npm-shrinkwrap.json" => return Some(Self::PackageLock),
+            "pnpm-lock.yaml" => return Some(Self::PnpmLock),
+            _ => {}
+        }
+
+        let trimmed = content.trim_start();
+        if trimmed.starts_with('{') {
+            Some(Self::PackageLock)
+        } else if trimmed.starts_with("lockfileVersion") {
+            Some(Self::PnpmLock)
+        } else if content.contains("[[package]]") {
+            Some(Self::CargoLock)
+        } else if content
+            .lines()
+            .any(|line| line == "GEM" || line == "  specs:")
+        {
+            Some(Self::GemfileLock)
+        } else {
+            None
+        }
+    }
+
+    pub fn ecosystem(self) -> Ecosystem {
+        match self {
+            Self::GemfileLock => Ecosystem::RubyGems,
+            Self::CargoLock => Ecosystem::CratesIo,
+            Self::PackageLock | Self::PnpmLock => Ecosystem::Npm,
+        }
+    }
+}
+
+/// Checksum recorded for a package in the lockfile.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LockedChecksum {
+    /// Hex SHA-256 of the artifact (Bundler `CHECKSUMS`, Cargo).
+    Sha256(String),
+    /// Subresource-integrity string (`sha512-...`) as written by npm/pnpm.
+    Integrity(String),
+}
+
+/// A dependency edge recorded for a locked package.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LockedDependency {
+    pub name: String,
+    /// Resolved version, when the lockfile records which one this edge uses.
+    pub version: Option<String>,
+}
+
+/// A registry package pinned by a lockfile.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LockedPackage {
+    pub ecosystem: Ecosystem,
+    pub name: String,
+    pub version: String,
+    pub platform: Option<String>,
+    pub checksum: Option<LockedChecksum>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dependencies: Vec<LockedDependency>,
+}
+
+impl LockedPackage {
+    /// Cache key of the artifact this entry resolves to.
+    pub fn asset_key(&self) -> AssetKey<'_> {
+        AssetKey {
+            kind: match self.ecosystem {
+                Ecosystem::RubyGems => AssetKind::Gem,
+                Ecosystem::CratesIo => AssetKind::Crate,
+                Ecosystem::Npm => AssetKind::NpmPackage,
+            },
+            name: &self.name,
+            version: &self.version,
+            platform: self.platform.as_deref(),
+        }
+    }
+}
+
+/// A parsed lockfile.
+#[derive(Debug, Clone, Serialize)]
+pub struct Lockfile {
+    pub kind: LockfileKind,
+    pub packages: Vec<LockedPackage>,
+}
+
+impl Lockfile {
+    /// Parses a lockfile, detecting the format from `file_name` or content.
+    pub fn parse(file_name: Option<&str>, content: &str) -> Result<Self> {
+        let Some(kind) = LockfileKind::detect(file_name, content) else {
+            bail!("unrecognised lockfile format");
+        };
+        Self::parse_as(kind, content)
+    }
+
+    pub fn parse_as(kind: LockfileKind, content: &str) -> Result<Self> {
+        let mut packages = match kind {
+            LockfileKind::GemfileLock => gemfile::parse(content),
+            LockfileKind::CargoLock => cargo::parse(content)?,
+            LockfileKind::PackageLock => npm::parse(content)?,
+            LockfileKind::PnpmLock => pnpm::parse(content)?,
+        };
+        packages.sort_by(|a, b| {
+            (&a.name, &a.version, &a.platform).cmp(&(&b.name, &b.version, &b.platform))
+        });
+        packages.dedup_by(|a, b| {
+            a.name == b.name && a.version == b.version && a.platform == b.platform
+        });
+        Ok(Self { kind, packages })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_formats_by_name_and_content() {
+        assert_eq!(
+            LockfileKind::detect(Some("app/Gemfile.lock"), ""),
+            Some(LockfileKind::GemfileLock)
+        );
+        assert_eq!(
+            LockfileKind::detect(None, "{\"lockfileVersion\": 3}"),
+            Some(LockfileKind::PackageLock)
+        );
+        assert_eq!(
+            LockfileKind::detect(None, "lockfileVersion: '9.0'\n"),
+            Some(LockfileKind::PnpmLock)
+        );
+        assert_eq!(
+            LockfileKind::detect(None, "version = 3\n\n[[package]]\nname = \"a\"\n"),
+            Some(LockfileKind::CargoLock)
+        );
+        assert_eq!(
+            LockfileKind::detect(None, "GEM\n  remote: https://rubygems.org/\n"),
+            Some(LockfileKind::GemfileLock)
+        );
+        assert_eq!(LockfileKind::detect(None, "hello"), None);
+    }
+}
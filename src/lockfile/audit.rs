@@ -0,0 +1,458 @@
+//! Lockfile auditing against the cache index.
+//!
+//! Reports, for every pinned package, whether Vein has it cached, its
+//! quarantine and yank state, whether the lockfile checksum matches the
+//! cached artifact, its licenses and known advisories. Everything is read
+//! from the local index and storage, so CI can gate on it offline.
+
+use anyhow::Result;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256, Sha512};
+use vein_adapter::{
+    AdvisorySeverity, CacheBackend, CacheBackendTrait, CachedAsset, Ecosystem, FilesystemStorage,
+    VersionStatus, is_version_available,
+};
+
+use super::{LockedChecksum, LockedPackage, Lockfile, LockfileKind};
+use crate::advisories::{self, AdvisoryMatch};
+
+/// Outcome of comparing the lockfile checksum with the cached artifact.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChecksumCheck {
+    Match,
+    Mismatch,
+    /// The lockfile records no checksum for this package.
+    Missing,
+    /// Vein has not cached the artifact.
+    NotCached,
+    /// The lockfile uses an algorithm Vein cannot verify (e.g. `sha1-`).
+    Unsupported,
+}
+
+/// Audit result for one locked package.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DependencyAudit {
+    pub ecosystem: Ecosystem,
+    pub name: String,
+    pub version: String,
+    pub platform: Option<String>,
+    pub cached: bool,
+    /// Status from `gem_versions`, when Vein tracks the version.
+    pub status: Option<VersionStatus>,
+    pub available_after: Option<DateTime<Utc>>,
+    pub quarantined: bool,
+    pub yanked: bool,
+    pub checksum: ChecksumCheck,
+    /// Gem license list, or the SPDX expression a crate or npm package declares.
+    pub licenses: Vec<String>,
+    pub advisories: Vec<AdvisoryMatch>,
+}
+
+/// Counts across the audited packages.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditSummary {
+    pub total: usize,
+    pub cached: usize,
+    pub quarantined: usize,
+    pub yanked: usize,
+    pub checksum_mismatches: usize,
+    /// Packages with an advisory at or above the requested severity.
+    pub vulnerable: usize,
+}
+
+impl AuditSummary {
+    /// Whether nothing should block a merge. Packages missing from the cache
+    /// are reported but do not fail the audit.
+    pub fn passed(&self) -> bool {
+        self.quarantined == 0
+            && self.yanked == 0
+            && self.checksum_mismatches == 0
+            && self.vulnerable == 0
+    }
+}
+
+/// Full lockfile audit report.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LockfileAudit {
+    pub lockfile: LockfileKind,
+    pub passed: bool,
+    pub summary: AuditSummary,
+    pub dependencies: Vec<DependencyAudit>,
+}
+
+/// Audits every package of a lockfile. Advisories below `min_severity` are
+/// still listed but do not count as vulnerable.
+pub async fn audit_lockfile(
+    index: &CacheBackend,
+    storage: &FilesystemStorage,
+    lockfile: &Lockfile,
+    min_severity: AdvisorySeverity,
+) -> Result<LockfileAudit> {
+    let now = Utc::now();
+    let mut summary = AuditSummary::default();
+    let mut dependencies = Vec::with_capacity(lockfile.packages.len());
+
+    for package in &lockfile.packages {
+        let entry = audit_package(index, storage, package, now).await?;
+        summary.total += 1;
+        summary.cached += usize::from(entry.cached);
+        summary.quarantined += usize::from(entry.quarantined);
+        summary.yanked += usize::from(entry.yanked);
+        summary.checksum_mismatches += usize::from(entry.checksum == ChecksumCheck::Mismatch);
+        summary.vulnerable += usize::from(
+            entry
+                .advisories
+                .iter()
+                .any(|advisory| advisory.severity >= min_severity),
+        );
+        dependencies.push(entry);
+    }
+
+    Ok(LockfileAudit {
+        lockfile: lockfile.kind,
+        passed: summary.passed(),
+        summary,
+        dependencies,
+    })
+}
+
+async fn audit_package(
+    index: &CacheBackend,
+    storage: &FilesystemStorage,
+    package: &LockedPackage,
+    now: DateTime<Utc>,
+) -> Result<DependencyAudit> {
+    let key = package.asset_key();
+    let cached = index.peek(&key).await?;
+
+    let tracked = match package.ecosystem {
+        Ecosystem::RubyGems => {
+            index
+                .get_gem_version(&package.name, &package.version, package.platform.as_deref())
+                .await?
+        }
+        Ecosystem::CratesIo | Ecosystem::Npm => None,
+    };
+    let quarantined = tracked.as_ref().is_some_and(|version| {
+        version.status == VersionStatus::Quarantine && !is_version_available(version, now)
+    });
+    let yanked = tracked
+        .as_ref()
+        .is_some_and(|version| version.status == VersionStatus::Yanked)
+        || index.get_upstream_yank(&key).await?.is_some();
+
+    let licenses = match package.ecosystem {
+        Ecosystem::RubyGems => index
+            .gem_metadata(
+                &package.name,
+                &package.version,
+                Some(package.platform.as_deref().unwrap_or("ruby")),
+            )
+            .await?
+            .map(|metadata| metadata.licenses)
+            .unwrap_or_default(),
+        Ecosystem::CratesIo | Ecosystem::Npm => index
+            .package_metadata(package.ecosystem, &package.name, &package.version)
+            .await?
+            .and_then(|metadata| metadata.license)
+            .into_iter()
+            .collect(),
+    };
+
+    let advisories = advisories::advisories_for(
+        index,
+        package.ecosystem,
+        &package.name,
+        Some(&package.version),
+    )
+    .await?
+    .iter()
+    .map(AdvisoryMatch::from)
+    .collect();
+
+    let checksum = check_checksum(storage, package.checksum.as_ref(), cached.as_ref()).await;
+
+    Ok(DependencyAudit {
+        ecosystem: package.ecosystem,
+        name: package.name.clone(),
+        version: package.version.clone(),
+        platform: package.platform.clone(),
+        cached: cached.is_some(),
+        status: tracked.as_ref().map(|version| version.status),
+        available_after: tracked.map(|version| version.available_after),
+        quarantined,
+        yanked,
+        checksum,
+        licenses,
+        advisories,
+    })
+}
+
+async fn check_checksum(
+    storage: &FilesystemStorage,
+    locked: Option<&LockedChecksum>,
+    cached: Option<&CachedAsset>,
+) -> ChecksumCheck {
+    let Some(cached) = cached else {
+        return ChecksumCheck::NotCached;
+    };
+    let Some(locked) = locked else {
+        return ChecksumCheck::Missing;
+    };
+
+    let matches = match locked {
+        LockedChecksum::Sha256(expected) => cached.sha256.eq_ignore_ascii_case(expected),
+        LockedChecksum::Integrity(integrity) => {
+            match verify_integrity(storage, integrity, cached).await {
+                Some(matches) => matches,
+                None => return ChecksumCheck::Unsupported,
+            }
+        }
+    };
+    if matches {
+        ChecksumCheck::Match
+    } else {
+        ChecksumCheck::Mismatch
+    }
+}
+
+/// Checks an SRI string against the cached artifact. `sha256` compares with
+/// the recorded digest; `sha512` hashes the stored file. Returns `None` when
+/// no supported algorithm is present or the file cannot be read.
+async fn verify_integrity(
+    storage: &FilesystemStorage,
+    integrity: &str,
+    cached: &CachedAsset,
+) -> Option<bool> {
+    let hashes: Vec<(&str, Vec<u8>)> = integrity
+        .split_whitespace()
+        .filter_map(|hash| {
+            let (algorithm, encoded) = hash.split_once('-')?;
+            Some((algorithm, BASE64.decode(encoded).ok()?))
+        })
+        .collect();
+
+    if let Some((_, digest)) = hashes.iter().find(|(algorithm, _)| *algorithm == "sha512") {
+        let data = tokio::fs::read(storage.resolve(&cached.path)).await.ok()?;
+        return Some(Sha512::digest(&data).as_slice() == digest.as_slice());
+    }
+    if let Some((_, digest)) = hashes.iter().find(|(algorithm, _)| *algorithm == "sha256") {
+        if !cached.sha256.is_empty() {
+            return Some(cached.sha256.eq_ignore_ascii_case(&hex::encode(digest)));
+        }
+        let data = tokio::fs::read(storage.resolve(&cached.path)).await.ok()?;
+        return Some(Sha256::digest(&data).as_slice() == digest.as_slice());
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use vein_adapter::{
+        Advisory, AffectedPackage, AffectedRange, GemVersion, PackageMetadata, RangeEvent,
+    };
+
+    #[cfg(feature = "sqlite")]
+    fn package(ecosystem: Ecosystem, name: &str, version: &str, license: &str) -> PackageMetadata {
+        PackageMetadata {
+            ecosystem,
+            name: name.to_string(),
+            version: version.to_string(),
+            description: None,
+            license: Some(license.to_string()),
+            authors: Vec::new(),
+            homepage: None,
+            repository: None,
+            dependencies: Vec::new(),
+            features: Default::default(),
+            attributes: serde_json::Value::Null,
+            size_bytes: 0,
+            sha256: String::new(),
+            sbom: None,
+            sbom_generator: None,
+        }
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn reports_cache_quarantine_checksum_and_advisories() {
+        let index = CacheBackend::connect_memory().await.unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let storage = FilesystemStorage::new(dir.path().to_path_buf());
+        storage.prepare().await.unwrap();
+
+        let tarball = b"left-pad tarball";
+        std::fs::create_dir_all(dir.path().join("npm")).unwrap();
+        std::fs::write(dir.path().join("npm/left-pad-1.3.0.tgz"), tarball).unwrap();
+        let cached = [
+            (
+                vein_adapter::AssetKind::Gem,
+                "rack",
+                "3.0.8",
+                "gems/rack.gem",
+                "aa11",
+            ),
+            (
+                vein_adapter::AssetKind::Gem,
+                "rails",
+                "8.0.0",
+                "gems/rails.gem",
+                "bb22",
+            ),
+            (
+                vein_adapter::AssetKind::NpmPackage,
+                "left-pad",
+                "1.3.0",
+                "npm/left-pad-1.3.0.tgz",
+                "",
+            ),
+        ];
+        for (kind, name, version, path, sha256) in cached {
+            let key = vein_adapter::AssetKey {
+                kind,
+                name,
+                version,
+                platform: None,
+            };
+            index
+                .insert_or_replace(&key, path, sha256, 1)
+                .await
+                .unwrap();
+        }
+        let now = Utc::now();
+        index
+            .upsert_gem_version(&GemVersion {
+                id: 0,
+                name: "rails".to_string(),
+                version: "8.0.0".to_string(),
+                platform: None,
+                sha256: None,
+                published_at: now,
+                available_after: now + Duration::days(3),
+                status: VersionStatus::Quarantine,
+                status_reason: None,
+                upstream_yanked: false,
+                created_at: now,
+                updated_at: now,
+            })
+            .await
+            .unwrap();
+        index
+            .upsert_advisory(&Advisory {
+                id: "GHSA-rack".to_string(),
+                summary: None,
+                details: None,
+                severity: AdvisorySeverity::Moderate,
+                aliases: Vec::new(),
+                url: None,
+                published: None,
+                modified: now,
+                withdrawn: None,
+                affected: vec![AffectedPackage {
+                    ecosystem: Ecosystem::RubyGems,
+                    name: "rack".to_string(),
+                    ranges: vec![AffectedRange {
+                        events: vec![
+                            RangeEvent::Introduced("0".to_string()),
+                            RangeEvent::Fixed("3.0.9".to_string()),
+                        ],
+                    }],
+                    versions: Vec::new(),
+                }],
+            })
+            .await
+            .unwrap();
+
+        let gems = Lockfile::parse(
+            Some("Gemfile.lock"),
+            "GEM\n  remote: https://rubygems.org/\n  specs:\n    rack (3.0.8)\n    rails (8.0.0)\n    sinatra (4.0.0)\n\nCHECKSUMS\n  rack (3.0.8) sha256=AA11\n  rails (8.0.0) sha256=ffff\n",
+        )
+        .unwrap();
+        let report = audit_lockfile(&index, &storage, &gems, AdvisorySeverity::High)
+            .await
+            .unwrap();
+
+        let by_name = |name: &str| {
+            report
+                .dependencies
+                .iter()
+                .find(|dep| dep.name == name)
+                .unwrap()
+        };
+        assert_eq!(by_name("rack").checksum, ChecksumCheck::Match);
+        assert_eq!(by_name("rack").advisories[0].id, "GHSA-rack");
+        assert_eq!(by_name("rails").checksum, ChecksumCheck::Mismatch);
+        assert!(by_name("rails").quarantined);
+        assert!(!by_name("sinatra").cached);
+        assert_eq!(by_name("sinatra").checksum, ChecksumCheck::NotCached);
+        assert_eq!(
+            report.summary,
+            AuditSummary {
+                total: 3,
+                cached: 2,
+                quarantined: 1,
+                yanked: 0,
+                checksum_mismatches: 1,
+                vulnerable: 0,
+            }
+        );
+        assert!(!report.passed);
+
+        index
+            .upsert_package_metadata(&package(Ecosystem::Npm, "left-pad", "1.3.0", "WTFPL"))
+            .await
+            .unwrap();
+        let integrity = format!("sha512-{}", BASE64.encode(Sha512::digest(tarball)));
+        let npm = Lockfile::parse(
+            None,
+            &serde_json::json!({
+                "lockfileVersion": 3,
+                "packages": {"node_modules/left-pad": {"version": "1.3.0", "integrity": integrity}}
+            })
+            .to_string(),
+        )
+        .unwrap();
+        let report = audit_lockfile(&index, &storage, &npm, AdvisorySeverity::Low)
+            .await
+            .unwrap();
+        assert_eq!(report.dependencies[0].checksum, ChecksumCheck::Match);
+        assert_eq!(report.dependencies[0].licenses, ["WTFPL"]);
+        assert!(report.passed);
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn reports_crate_licenses_from_stored_metadata() {
+        let index = CacheBackend::connect_memory().await.unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let storage = FilesystemStorage::new(dir.path().to_path_buf());
+        storage.prepare().await.unwrap();
+        index
+            .upsert_package_metadata(&package(
+                Ecosystem::CratesIo,
+                "serde",
+                "1.0.200",
+                "MIT OR Apache-2.0",
+            ))
+            .await
+            .unwrap();
+
+        let lockfile = Lockfile::parse(
+            Some("Cargo.lock"),
+            "version = 3\n\n[[package]]\nname = \"serde\"\nversion = \"1.0.200\"\nsource = \"registry+https://github.com/rust-lang/crates.io-index\"\n\n[[package]]\nname = \"syn\"\nversion = \"2.0.60\"\nsource = \"registry+https://github.com/rust-lang/crates.io-index\"\n",
+        )
+        .unwrap();
+        let report = audit_lockfile(&index, &storage, &lockfile, AdvisorySeverity::High)
+            .await
+            .unwrap();
+        assert_eq!(report.dependencies[0].name, "serde");
+        assert_eq!(report.dependencies[0].licenses, ["MIT OR Apache-2.0"]);
+        assert!(report.dependencies[1].licenses.is_empty());
+    }
+}
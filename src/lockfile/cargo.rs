@@ -0,0 +1,116 @@
+//! `Cargo.lock` parsing.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use vein_adapter::Ecosystem;
+
+use super::{LockedChecksum, LockedDependency, LockedPackage};
+
+#[derive(Debug, Deserialize)]
+struct CargoLock {
+    #[serde(default)]
+    package: Vec<CargoPackage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CargoPackage {
+    name: String,
+    version: String,
+    #[serde(default)]
+    source: Option<String>,
+    #[serde(default)]
+    checksum: Option<String>,
+    #[serde(default)]
+    dependencies: Vec<String>,
+}
+
+/// Entries are `name`, `name version` or `name version (source)`; the
+/// version is only written when several versions of `name` are locked.
+fn parse_dependency(entry: &str) -> Option<LockedDependency> {
+    let mut parts = entry.split_whitespace();
+    Some(LockedDependency {
+        name: parts.next()?.to_string(),
+        version: parts.next().map(str::to_string),
+    })
+}
+
+/// Returns every package resolved from a registry (workspace members, path
+/// and git dependencies have no `registry+`/`sparse+` source).
+pub(super) fn parse(content: &str) -> Result<Vec<LockedPackage>> {
+    let lock: CargoLock = toml::from_str(content).context("parsing Cargo.lock")?;
+    Ok(lock
+        .package
+        .into_iter()
+        .filter(|package| {
+            package.source.as_deref().is_some_and(|source| {
+                source.starts_with("registry+") || source.starts_with("sparse+")
+            })
+        })
+        .map(|package| LockedPackage {
+            ecosystem: Ecosystem::CratesIo,
+            name: package.name,
+            version: package.version,
+            platform: None,
+            checksum: package
+                .checksum
+                .map(|checksum| LockedChecksum::Sha256(checksum.to_ascii_lowercase())),
+            dependencies: package
+                .dependencies
+                .iter()
+                .filter_map(|entry| parse_dependency(entry))
+                .collect(),
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_registry_packages_only() {
+        let lock = r#"
+version = 3
+
+[[package]]
+name = "app"
+version = "0.1.0"
+dependencies = ["serde"]
+
+[[package]]
+name = "serde"
+version = "1.0.200"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "ABC123"
+dependencies = [
+ "serde_derive",
+ "syn 2.0.60 (registry+https://github.com/rust-lang/crates.io-index)",
+]
+
+[[package]]
+name = "forked"
+version = "0.2.0"
+source = "git+https://example.com/forked#deadbeef"
+"#;
+        let packages = parse(lock).unwrap();
+        assert_eq!(packages.len(), 1);
+        assert_eq!(packages[0].name, "serde");
+        assert_eq!(
+            packages[0].checksum,
+            Some(LockedChecksum::Sha256("abc123".to_string()))
+        );
+        assert_eq!(
+            packages[0].dependencies,
+            [
+                LockedDependency {
+                    name: "serde_derive".to_string(),
+                    version: None,
+                },
+                LockedDependency {
+                    name: "syn".to_string(),
+                    version: Some("2.0.60".to_string()),
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,156 @@
+//! `Gemfile.lock` parsing.
+//!
+//! Reads the `specs:` of every `GEM` source with their dependencies and, when
+//! Bundler recorded them, the SHA-256 values of the `CHECKSUMS` section.
+
+use std::collections::HashMap;
+
+use vein_adapter::Ecosystem;
+
+use super::{LockedChecksum, LockedDependency, LockedPackage};
+
+/// Splits `1.15.5-x86_64-linux` into version and platform.
+fn split_version(token: &str) -> (String, Option<String>) {
+    match token.split_once('-') {
+        Some((version, platform)) => (version.to_string(), Some(platform.to_string())),
+        None => (token.to_string(), None),
+    }
+}
+
+/// Parses `name (version)` into name, version and platform.
+fn parse_spec(line: &str) -> Option<(String, String, Option<String>)> {
+    let (name, rest) = line.trim().split_once(" (")?;
+    let (token, _) = rest.split_once(')')?;
+    let (version, platform) = split_version(token.trim());
+    Some((name.to_string(), version, platform))
+}
+
+pub(super) fn parse(content: &str) -> Vec<LockedPackage> {
+    let mut section = "";
+    let mut in_specs = false;
+    let mut specs: Vec<(_, Vec<LockedDependency>)> = Vec::new();
+    let mut checksums: HashMap<(String, String, Option<String>), String> = HashMap::new();
+
+    for line in content.lines() {
+        if !line.starts_with(' ') {
+            section = line.trim();
+            in_specs = false;
+            continue;
+        }
+        match section {
+            "GEM" => {
+                if line == "  specs:" {
+                    in_specs = true;
+                } else if in_specs
+                    && line.starts_with("    ")
+                    && !line.starts_with("     ")
+                    && let Some(spec) = parse_spec(line)
+                {
+                    specs.push((spec, Vec::new()));
+                } else if in_specs
+                    && line.starts_with("      ")
+                    && !line.starts_with("       ")
+                    && let Some((_, dependencies)) = specs.last_mut()
+                {
+                    // Bundler lists requirements, not the resolved version.
+                    let name = line.trim().split(" (").next().unwrap_or_default();
+                    dependencies.push(LockedDependency {
+                        name: name.to_string(),
+                        version: None,
+                    });
+                }
+            }
+            "CHECKSUMS" => {
+                let Some(spec) = parse_spec(line) else {
+                    continue;
+                };
+                let digest = line
+                    .split_whitespace()
+                    .flat_map(|part| part.split(','))
+                    .find_map(|part| part.strip_prefix("sha256="));
+                if let Some(digest) = digest {
+                    checksums.insert(spec, digest.to_ascii_lowercase());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    specs
+        .into_iter()
+        .map(|(spec, dependencies)| {
+            let checksum = checksums.get(&spec).cloned().map(LockedChecksum::Sha256);
+            let (name, version, platform) = spec;
+            LockedPackage {
+                ecosystem: Ecosystem::RubyGems,
+                name,
+                version,
+                platform,
+                checksum,
+                dependencies,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOCKFILE: &str = "\
+GIT
+  remote: https://github.com/rails/rails.git
+  revision: abc
+  specs:
+    rails (8.0.0.alpha)
+
+GEM
+  remote: https://rubygems.org/
+  specs:
+    nokogiri (1.16.0-x86_64-linux)
+      racc (~> 1.4)
+    racc (1.7.3)
+    rack (3.0.8)
+
+PLATFORMS
+  x86_64-linux
+
+DEPENDENCIES
+  nokogiri
+  rack
+
+CHECKSUMS
+  nokogiri (1.16.0-x86_64-linux) sha256=ABCDEF
+  racc (1.7.3)
+  rack (3.0.8) sha256=0123
+
+BUNDLED WITH
+   2.5.4
+";
+
+    #[test]
+    fn parses_gem_specs_and_checksums() {
+        let packages = parse(LOCKFILE);
+        assert_eq!(packages.len(), 3);
+
+        assert_eq!(packages[0].name, "nokogiri");
+        assert_eq!(packages[0].version, "1.16.0");
+        assert_eq!(packages[0].platform.as_deref(), Some("x86_64-linux"));
+        assert_eq!(
+            packages[0].checksum,
+            Some(LockedChecksum::Sha256("abcdef".to_string()))
+        );
+        assert_eq!(
+            packages[0].dependencies,
+            [LockedDependency {
+                name: "racc".to_string(),
+                version: None,
+            }]
+        );
+        assert_eq!(packages[1].name, "racc");
+        assert_eq!(packages[1].checksum, None);
+        assert!(packages[1].dependencies.is_empty());
+        assert_eq!(packages[2].name, "rack");
+        assert!(packages.iter().all(|p| p.name != "rails"));
+    }
+}
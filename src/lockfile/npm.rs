@@ -0,0 +1,211 @@
+//! `package-lock.json` / `npm-shrinkwrap.json` parsing.
+//!
+//! Lockfile v2/v3 lists packages under `packages` keyed by install path;
+//! v1 nests them under `dependencies`.
+
+use anyhow::{Context, Result};
+use serde_json::{Map, Value as JsonValue};
+use vein_adapter::Ecosystem;
+
+use super::{LockedChecksum, LockedDependency, LockedPackage};
+
+/// Manifest fields whose entries npm installs alongside a package.
+const DEPENDENCY_FIELDS: [&str; 3] = ["dependencies", "optionalDependencies", "peerDependencies"];
+
+pub(super) fn parse(content: &str) -> Result<Vec<LockedPackage>> {
+    let lock: JsonValue = serde_json::from_str(content).context("parsing package-lock.json")?;
+    let mut packages = Vec::new();
+
+    if let Some(entries) = lock.get("packages").and_then(JsonValue::as_object) {
+        for (path, entry) in entries {
+            // The root project and workspace members live outside node_modules
+            let Some(installed_as) = installed_name(path) else {
+                continue;
+            };
+            if entry.get("link").and_then(JsonValue::as_bool) == Some(true) {
+                continue;
+            }
+            let name = entry
+                .get("name")
+                .and_then(JsonValue::as_str)
+                .unwrap_or(installed_as);
+            let dependencies = resolve_dependencies(entries, path, entry);
+            push_entry(&mut packages, name, entry, dependencies);
+        }
+    } else if let Some(dependencies) = lock.get("dependencies") {
+        collect_v1(&mut packages, dependencies);
+    }
+
+    Ok(packages)
+}
+
+/// Name a `packages` key installs under, `None` unless the path has a
+/// `node_modules/` segment.
+fn installed_name(path: &str) -> Option<&str> {
+    if let Some(name) = path.strip_prefix("node_modules/") {
+        return Some(
+            name.rsplit_once("/node_modules/")
+                .map_or(name, |(_, name)| name),
+        );
+    }
+    path.rsplit_once("/node_modules/").map(|(_, name)| name)
+}
+
+/// Resolves each declared dependency the way Node does: the closest
+/// `node_modules` directory walking up from the package's install path.
+fn resolve_dependencies(
+    entries: &Map<String, JsonValue>,
+    path: &str,
+    entry: &JsonValue,
+) -> Vec<LockedDependency> {
+    DEPENDENCY_FIELDS
+        .iter()
+        .filter_map(|field| entry.get(*field).and_then(JsonValue::as_object))
+        .flat_map(|declared| declared.keys())
+        .map(|name| {
+            let mut base = path;
+            let version = loop {
+                let candidate = format!("{base}/node_modules/{name}");
+                let candidate = candidate.trim_start_matches('/');
+                if let Some(found) = entries.get(candidate) {
+                    break found.get("version").and_then(JsonValue::as_str);
+                }
+                if base.is_empty() {
+                    break None;
+                }
+                base = base
+                    .rsplit_once("/node_modules/")
+                    .map_or("", |(head, _)| head);
+            };
+            LockedDependency {
+                name: name.clone(),
+                version: version.map(str::to_string),
+            }
+        })
+        .collect()
+}
+
+/// v1 only records `requires` ranges, so edges carry names alone.
+fn collect_v1(packages: &mut Vec<LockedPackage>, dependencies: &JsonValue) {
+    let Some(dependencies) = dependencies.as_object() else {
+        return;
+    };
+    for (name, entry) in dependencies {
+        let requires = entry
+            .get("requires")
+            .and_then(JsonValue::as_object)
+            .map(|requires| {
+                requires
+                    .keys()
+                    .map(|name| LockedDependency {
+                        name: name.clone(),
+                        version: None,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        push_entry(packages, name, entry, requires);
+        if let Some(nested) = entry.get("dependencies") {
+            collect_v1(packages, nested);
+        }
+    }
+}
+
+fn push_entry(
+    packages: &mut Vec<LockedPackage>,
+    name: &str,
+    entry: &JsonValue,
+    dependencies: Vec<LockedDependency>,
+) {
+    let Some(version) = entry.get("version").and_then(JsonValue::as_str) else {
+        return;
+    };
+    // git, file and tarball-URL dependencies carry a spec instead of a version
+    let resolved = entry
+        .get("resolved")
+        .and_then(JsonValue::as_str)
+        .unwrap_or_default();
+    if version.contains(':') || resolved.starts_with("file:") || resolved.starts_with("git") {
+        return;
+    }
+    packages.push(LockedPackage {
+        ecosystem: Ecosystem::Npm,
+        name: name.to_string(),
+        version: version.to_string(),
+        platform: None,
+        checksum: entry
+            .get("integrity")
+            .and_then(JsonValue::as_str)
+            .map(|integrity| LockedChecksum::Integrity(integrity.to_string())),
+        dependencies,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_v3_packages() {
+        let lock = r#"{
+            "name": "app",
+            "lockfileVersion": 3,
+            "packages": {
+                "": {"name": "app", "version": "1.0.0", "workspaces": ["packages/*"]},
+                "packages/foo": {"name": "foo", "version": "1.0.0", "dependencies": {"left-pad": "^1.3.0"}},
+                "node_modules/foo": {"resolved": "packages/foo", "link": true},
+                "node_modules/left-pad": {"version": "1.3.0", "integrity": "sha512-abc"},
+                "node_modules/@types/node": {"version": "20.1.0"},
+                "node_modules/a": {"version": "2.0.0", "dependencies": {"left-pad": "^1.1.0", "@types/node": "*"}},
+                "node_modules/a/node_modules/left-pad": {"version": "1.1.0"},
+                "node_modules/local": {"resolved": "packages/local", "link": true},
+                "node_modules/forked": {"version": "1.0.0", "resolved": "git+ssh://git@example.com/forked.git"}
+            }
+        }"#;
+        let mut packages = parse(lock).unwrap();
+        packages.sort_by(|a, b| (&a.name, &a.version).cmp(&(&b.name, &b.version)));
+        let names: Vec<_> = packages
+            .iter()
+            .map(|p| format!("{}@{}", p.name, p.version))
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "@types/node@20.1.0",
+                "a@2.0.0",
+                "left-pad@1.1.0",
+                "left-pad@1.3.0"
+            ]
+        );
+        let mut edges: Vec<_> = packages[1]
+            .dependencies
+            .iter()
+            .map(|dep| format!("{}@{}", dep.name, dep.version.as_deref().unwrap_or("?")))
+            .collect();
+        edges.sort();
+        assert_eq!(edges, ["@types/node@20.1.0", "left-pad@1.1.0"]);
+        assert_eq!(
+            packages[3].checksum,
+            Some(LockedChecksum::Integrity("sha512-abc".to_string()))
+        );
+    }
+
+    #[test]
+    fn parses_v1_dependencies() {
+        let lock = r#"{
+            "lockfileVersion": 1,
+            "dependencies": {
+                "express": {
+                    "version": "4.18.2",
+                    "requires": {"debug": "2.6.9"},
+                    "dependencies": {"debug": {"version": "2.6.9"}}
+                },
+                "mine": {"version": "file:../mine"}
+            }
+        }"#;
+        let packages = parse(lock).unwrap();
+        assert_eq!(packages.len(), 2);
+        assert!(packages.iter().any(|p| p.name == "debug"));
+        assert_eq!(packages[0].dependencies[0].name, "debug");
+    }
+}
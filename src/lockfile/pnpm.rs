@@ -0,0 +1,165 @@
+//! `pnpm-lock.yaml` parsing.
+//!
+//! Package keys differ by lockfile version: `/name/1.0.0` (v5),
+//! `/name@1.0.0(peer@2.0.0)` (v6) and `name@1.0.0` (v9). v9 moves each
+//! package's dependencies from `packages` into `snapshots`.
+
+use anyhow::{Context, Result};
+use serde_yaml::Value as YamlValue;
+use vein_adapter::Ecosystem;
+
+use super::{LockedChecksum, LockedDependency, LockedPackage};
+
+/// Splits a `packages` key into name and version.
+fn parse_key(key: &str) -> Option<(String, String)> {
+    let key = key.strip_prefix('/').unwrap_or(key);
+    let key = key.split_once('(').map_or(key, |(head, _)| head);
+
+    // v5: name/version, with peer suffixes after `_`
+    if let Some((name, version)) = key.rsplit_once('/')
+        && version.starts_with(|c: char| c.is_ascii_digit())
+    {
+        let version = version.split_once('_').map_or(version, |(head, _)| head);
+        return Some((name.to_string(), version.to_string()));
+    }
+
+    // v6/v9: the version follows the last `@` that is not the scope marker
+    let at = key.rfind('@').filter(|&at| at > 0)?;
+    Some((key[..at].to_string(), key[at + 1..].to_string()))
+}
+
+/// Reads the resolved `dependencies`/`optionalDependencies` of an entry.
+/// Values are a version with peer suffixes, or a `/name/version` key for
+/// aliased packages.
+fn dependencies(entry: Option<&YamlValue>) -> Vec<LockedDependency> {
+    let Some(entry) = entry else {
+        return Vec::new();
+    };
+    ["dependencies", "optionalDependencies"]
+        .iter()
+        .filter_map(|field| entry.get(*field).and_then(YamlValue::as_mapping))
+        .flatten()
+        .filter_map(|(name, value)| {
+            let name = name.as_str()?;
+            let value = value.as_str()?;
+            if value.starts_with("link:") {
+                return None;
+            }
+            if value.starts_with('/') {
+                let (name, version) = parse_key(value)?;
+                return Some(LockedDependency {
+                    name,
+                    version: Some(version),
+                });
+            }
+            let version = value.split(['(', '_']).next().unwrap_or(value);
+            Some(LockedDependency {
+                name: name.to_string(),
+                version: Some(version.to_string()),
+            })
+        })
+        .collect()
+}
+
+pub(super) fn parse(content: &str) -> Result<Vec<LockedPackage>> {
+    let lock: YamlValue = serde_yaml::from_str(content).context("parsing pnpm-lock.yaml")?;
+    let Some(entries) = lock.get("packages").and_then(YamlValue::as_mapping) else {
+        return Ok(Vec::new());
+    };
+    let snapshots = lock.get("snapshots");
+
+    let mut packages = Vec::new();
+    for (key, entry) in entries {
+        let Some(raw_key) = key.as_str() else {
+            continue;
+        };
+        let Some((name, version)) = parse_key(raw_key) else {
+            continue;
+        };
+        let resolution = entry.get("resolution");
+        let is_remote = resolution
+            .and_then(|resolution| resolution.get("tarball").or(resolution.get("repo")))
+            .is_some();
+        if is_remote || version.contains(':') || version.is_empty() {
+            continue;
+        }
+        packages.push(LockedPackage {
+            ecosystem: Ecosystem::Npm,
+            name,
+            version,
+            platform: None,
+            checksum: resolution
+                .and_then(|resolution| resolution.get("integrity"))
+                .and_then(YamlValue::as_str)
+                .map(|integrity| LockedChecksum::Integrity(integrity.to_string())),
+            dependencies: dependencies(
+                snapshots
+                    .and_then(|snapshots| snapshots.get(raw_key))
+                    .or(Some(entry)),
+            ),
+        });
+    }
+    Ok(packages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_keys_for_each_lockfile_version() {
+        let expected = Some(("@babel/core".to_string(), "7.24.0".to_string()));
+        assert_eq!(parse_key("/@babel/core/7.24.0"), expected);
+        assert_eq!(
+            parse_key("/@babel/core@7.24.0(supports-color@8.1.1)"),
+            expected
+        );
+        assert_eq!(parse_key("@babel/core@7.24.0"), expected);
+        assert_eq!(
+            parse_key("/debug/4.3.4_supports-color@8.1.1"),
+            Some(("debug".to_string(), "4.3.4".to_string()))
+        );
+    }
+
+    #[test]
+    fn parses_v9_packages() {
+        let lock = r#"
+lockfileVersion: '9.0'
+
+packages:
+  debug@4.3.4:
+    resolution: {integrity: sha512-xyz}
+  '@scope/pkg@1.0.0':
+    resolution: {integrity: sha512-abc}
+  remote@1.0.0:
+    resolution: {tarball: https://example.com/remote.tgz}
+
+snapshots:
+  debug@4.3.4:
+    dependencies:
+      ms: 2.1.2
+      supports-color: 8.1.1(patch_hash=abc)
+  '@scope/pkg@1.0.0':
+    dependencies:
+      local: link:../local
+"#;
+        let packages = parse(lock).unwrap();
+        assert_eq!(packages.len(), 2);
+        assert_eq!(packages[0].name, "debug");
+        assert_eq!(
+            packages[0].checksum,
+            Some(LockedChecksum::Integrity("sha512-xyz".to_string()))
+        );
+        let edges: Vec<_> = packages[0]
+            .dependencies
+            .iter()
+            .map(|dep| (dep.name.as_str(), dep.version.as_deref()))
+            .collect();
+        assert_eq!(
+            edges,
+            [("ms", Some("2.1.2")), ("supports-color", Some("8.1.1"))]
+        );
+        assert_eq!(packages[1].name, "@scope/pkg");
+        assert!(packages[1].dependencies.is_empty());
+    }
+}
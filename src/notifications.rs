@@ -0,0 +1,378 @@
+//! Outgoing webhook notifications for quarantine and security events.
+//!
+//! Events are raised from the proxy, schedulers and CLI via [`emit`], matched
+//! against each `[[notifications.webhooks]]` filter, and delivered in the
+//! background with retry. Short-lived processes call [`flush`] before exiting.
+
+mod payload;
+
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::{DateTime, Utc};
+use chrono_machines::{BackoffPolicy, BackoffStrategy, ExponentialBackoff};
+use parking_lot::Mutex;
+use rama::{
+    Service,
+    http::{
+        Body, Method, Request, StatusCode,
+        client::EasyHttpWebClient,
+        header::{self, HeaderValue},
+    },
+    telemetry::tracing::{debug, warn},
+};
+use rand::SeedableRng;
+use rand::rngs::SmallRng;
+use tokio::task::JoinHandle;
+use vein_adapter::Ecosystem;
+
+use crate::config::{NotificationKind, NotificationsConfig, WebhookConfig};
+use crate::upstream::UA;
+
+/// Process-wide notifier, installed once at startup.
+static NOTIFIER: OnceLock<Notifier> = OnceLock::new();
+
+/// A single quarantine or security event.
+#[derive(Debug, Clone)]
+pub struct Notification {
+    pub kind: NotificationKind,
+    pub ecosystem: Ecosystem,
+    pub name: String,
+    pub version: String,
+    pub platform: Option<String>,
+    pub reason: Option<String>,
+    pub occurred_at: DateTime<Utc>,
+}
+
+impl Notification {
+    pub fn new(
+        kind: NotificationKind,
+        ecosystem: Ecosystem,
+        name: &str,
+        version: &str,
+        platform: Option<&str>,
+        reason: Option<&str>,
+    ) -> Self {
+        Self {
+            kind,
+            ecosystem,
+            name: name.to_string(),
+            version: version.to_string(),
+            platform: platform.map(str::to_string),
+            reason: reason.map(str::to_string),
+            occurred_at: Utc::now(),
+        }
+    }
+}
+
+/// Fans notifications out to matching webhooks.
+pub struct Notifier {
+    webhooks: Vec<WebhookConfig>,
+    pending: Mutex<Vec<JoinHandle<()>>>,
+}
+
+impl Notifier {
+    pub fn new(config: &NotificationsConfig) -> Self {
+        Self {
+            webhooks: config.webhooks.clone(),
+            pending: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Queues delivery to every matching webhook on the current runtime.
+    pub fn emit(&self, notification: Notification) {
+        let targets: Vec<WebhookConfig> = self
+            .webhooks
+            .iter()
+            .filter(|hook| {
+                hook.matches(
+                    notification.kind,
+                    notification.ecosystem,
+                    &notification.name,
+                )
+            })
+            .cloned()
+            .collect();
+        if targets.is_empty() {
+            return;
+        }
+
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            warn!(
+                event = notification.kind.as_str(),
+                "no async runtime available, dropping notification"
+            );
+            return;
+        };
+
+        let mut pending = self.pending.lock();
+        pending.retain(|handle| !handle.is_finished());
+        for hook in targets {
+            let notification = notification.clone();
+            pending.push(runtime.spawn(async move {
+                if let Err(err) = deliver(&hook, &notification).await {
+                    warn!(
+                        webhook = %hook.label(),
+                        event = notification.kind.as_str(),
+                        error = %err,
+                        "webhook delivery failed"
+                    );
+                }
+            }));
+        }
+    }
+
+    /// Waits for queued deliveries to finish.
+    pub async fn flush(&self) {
+        let handles: Vec<_> = self.pending.lock().drain(..).collect();
+        for handle in handles {
+            let _ = handle.await;
+        }
+    }
+}
+
+/// Installs the process-wide notifier. Later calls are ignored.
+pub fn install(config: &NotificationsConfig) {
+    let _ = NOTIFIER.set(Notifier::new(config));
+}
+
+/// Emits a notification through the installed notifier, if any.
+pub fn emit(notification: Notification) {
+    if let Some(notifier) = NOTIFIER.get() {
+        notifier.emit(notification);
+    }
+}
+
+/// Waits for deliveries queued through [`emit`].
+pub async fn flush() {
+    if let Some(notifier) = NOTIFIER.get() {
+        notifier.flush().await;
+    }
+}
+
+/// Delivers one notification to one webhook, retrying on transport errors,
+/// `429` and `5xx` responses with exponential backoff.
+pub async fn deliver(hook: &WebhookConfig, notification: &Notification) -> Result<()> {
+    let body = serde_json::to_vec(&payload::render(hook.format, notification))
+        .context("serializing webhook payload")?;
+    let signature = hook
+        .secret
+        .as_deref()
+        .map(|secret| format!("sha256={}", payload::sign(secret, &body)));
+
+    let max_attempts = hook.max_attempts.max(1);
+    let backoff: BackoffPolicy = ExponentialBackoff::new()
+        .base_delay_ms(hook.initial_backoff_ms)
+        .max_delay_ms(hook.initial_backoff_ms.saturating_mul(16))
+        .max_attempts(max_attempts)
+        .jitter_factor(0.1)
+        .into();
+    let mut rng = {
+        let mut seed_rng = rand::rng();
+        SmallRng::from_rng(&mut seed_rng)
+    };
+    let client = EasyHttpWebClient::default();
+    let mut attempt: u8 = 0;
+
+    loop {
+        attempt += 1;
+
+        let mut builder = Request::builder()
+            .method(Method::POST)
+            .uri(hook.url.as_str())
+            .header(header::USER_AGENT, HeaderValue::from_static(UA))
+            .header(
+                header::CONTENT_TYPE,
+                HeaderValue::from_static("application/json"),
+            )
+            .header("x-vein-event", notification.kind.as_str());
+        if let Some(signature) = &signature {
+            builder = builder.header("x-vein-signature-256", signature.as_str());
+        }
+        let request = builder
+            .body(Body::from(body.clone()))
+            .map_err(|e| anyhow!("building webhook request: {e}"))?;
+
+        let timeout = Duration::from_secs(hook.timeout_secs);
+        let error = match tokio::time::timeout(timeout, client.serve(request)).await {
+            Ok(Ok(response)) if response.status().is_success() => {
+                debug!(
+                    webhook = %hook.label(),
+                    event = notification.kind.as_str(),
+                    attempt,
+                    "webhook delivered"
+                );
+                return Ok(());
+            }
+            Ok(Ok(response)) => {
+                let status = response.status();
+                if status != StatusCode::TOO_MANY_REQUESTS && !status.is_server_error() {
+                    bail!("{} rejected the webhook with {status}", hook.label());
+                }
+                anyhow!("{} returned {status}", hook.label())
+            }
+            Ok(Err(err)) => anyhow!("webhook request failed: {err}"),
+            Err(_) => anyhow!("webhook request timed out after {}s", hook.timeout_secs),
+        };
+
+        if attempt >= max_attempts {
+            return Err(error.context(format!("giving up after {attempt} attempts")));
+        }
+        if let Some(delay_ms) = backoff.delay(attempt, &mut rng) {
+            tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::WebhookFormat;
+    use rama::tls::rustls::dep::rustls;
+    use std::sync::Once;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    fn install_rustls_provider() {
+        static INIT: Once = Once::new();
+        INIT.call_once(|| {
+            let _ = rustls::crypto::aws_lc_rs::default_provider().install_default();
+        });
+    }
+
+    fn hook(url: String, format: WebhookFormat) -> WebhookConfig {
+        WebhookConfig {
+            url,
+            name: Some("test".to_string()),
+            format,
+            secret: Some("s3cret".to_string()),
+            events: Vec::new(),
+            ecosystems: Vec::new(),
+            packages: Vec::new(),
+            max_attempts: 3,
+            initial_backoff_ms: 1,
+            timeout_secs: 5,
+        }
+    }
+
+    fn blocked() -> Notification {
+        Notification::new(
+            NotificationKind::Blocked,
+            Ecosystem::RubyGems,
+            "rack",
+            "3.0.0",
+            None,
+            Some("suspicious release"),
+        )
+    }
+
+    /// Answers each connection with the next status, returning the raw requests.
+    async fn spawn_status_server(statuses: Vec<&'static str>) -> (String, JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let handle = tokio::spawn(async move {
+            let mut requests = Vec::new();
+            for status in statuses {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                requests.push(read_http_request(&mut socket).await);
+                let response =
+                    format!("HTTP/1.1 {status}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n");
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+            requests
+        });
+
+        (format!("http://{addr}/hook"), handle)
+    }
+
+    async fn read_http_request(socket: &mut tokio::net::TcpStream) -> String {
+        let mut buffer = vec![0_u8; 4096];
+        let mut request = Vec::new();
+
+        loop {
+            let read = socket.read(&mut buffer).await.unwrap();
+            if read == 0 {
+                break;
+            }
+            request.extend_from_slice(&buffer[..read]);
+            let text = String::from_utf8_lossy(&request);
+            if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                let length = head
+                    .lines()
+                    .find_map(|line| {
+                        let (name, value) = line.split_once(':')?;
+                        name.eq_ignore_ascii_case("content-length")
+                            .then(|| value.trim().parse::<usize>().ok())?
+                    })
+                    .unwrap_or(0);
+                if body.len() >= length {
+                    break;
+                }
+            }
+        }
+
+        String::from_utf8_lossy(&request).into_owned()
+    }
+
+    #[tokio::test]
+    async fn deliver_retries_server_errors_and_signs_payload() {
+        install_rustls_provider();
+        let (url, server) = spawn_status_server(vec!["500 Internal Server Error", "200 OK"]).await;
+
+        deliver(&hook(url, WebhookFormat::Json), &blocked())
+            .await
+            .unwrap();
+
+        let requests = server.await.unwrap();
+        assert_eq!(requests.len(), 2);
+        let request = &requests[1];
+        let (head, body) = request.split_once("\r\n\r\n").unwrap();
+        let head = head.to_ascii_lowercase();
+        assert!(head.contains("x-vein-event: blocked"));
+        let expected = format!(
+            "x-vein-signature-256: sha256={}",
+            payload::sign("s3cret", body.as_bytes())
+        );
+        assert!(head.contains(&expected), "missing signature in {head}");
+
+        let json: serde_json::Value = serde_json::from_str(body).unwrap();
+        assert_eq!(json["event"], "blocked");
+        assert_eq!(json["name"], "rack");
+        assert_eq!(json["reason"], "suspicious release");
+    }
+
+    #[tokio::test]
+    async fn deliver_gives_up_on_client_errors() {
+        install_rustls_provider();
+        let (url, server) = spawn_status_server(vec!["404 Not Found"]).await;
+
+        let err = deliver(&hook(url, WebhookFormat::Slack), &blocked())
+            .await
+            .unwrap_err();
+
+        assert!(err.to_string().contains("404"));
+        assert_eq!(server.await.unwrap().len(), 1);
+    }
+
+    #[test]
+    fn chat_formats_carry_summary() {
+        let notification = blocked();
+
+        let slack = payload::render(WebhookFormat::Slack, &notification);
+        assert_eq!(slack["text"], "rack 3.0.0 (rubygems) was blocked");
+
+        let teams = payload::render(WebhookFormat::Teams, &notification);
+        assert_eq!(teams["@type"], "MessageCard");
+        assert_eq!(teams["themeColor"], "A30200");
+        assert!(
+            teams["text"]
+                .as_str()
+                .unwrap()
+                .contains("reason: suspicious release")
+        );
+    }
+}
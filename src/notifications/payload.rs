@@ -0,0 +1,90 @@
+//! Webhook payload templates and request signing.
+
+use hmac::{Hmac, KeyInit, Mac};
+use serde_json::{Value, json};
+use sha2::Sha256;
+
+use super::Notification;
+use crate::config::{NotificationKind, WebhookFormat};
+
+/// Renders the body for a webhook in its configured format.
+pub(super) fn render(format: WebhookFormat, notification: &Notification) -> Value {
+    match format {
+        WebhookFormat::Json => json!({
+            "event": notification.kind.as_str(),
+            "ecosystem": notification.ecosystem.as_str(),
+            "name": notification.name,
+            "version": notification.version,
+            "platform": notification.platform,
+            "reason": notification.reason,
+            "occurred_at": notification.occurred_at.to_rfc3339(),
+        }),
+        WebhookFormat::Slack => json!({
+            "text": summary(notification),
+            "blocks": [
+                {
+                    "type": "section",
+                    "text": { "type": "mrkdwn", "text": format!("*{}*", summary(notification)) }
+                },
+                {
+                    "type": "context",
+                    "elements": [
+                        { "type": "mrkdwn", "text": details(notification) }
+                    ]
+                }
+            ]
+        }),
+        WebhookFormat::Teams => json!({
+            "@type": "MessageCard",
+            "@context": "https://schema.org/extensions",
+            "summary": summary(notification),
+            "themeColor": theme_color(notification.kind),
+            "title": summary(notification),
+            "text": details(notification),
+        }),
+    }
+}
+
+/// Hex HMAC-SHA256 of `body`, sent as `X-Vein-Signature-256: sha256=<hex>`.
+pub(super) fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(body);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+fn summary(notification: &Notification) -> String {
+    let action = match notification.kind {
+        NotificationKind::Quarantined => "entered quarantine",
+        NotificationKind::Promoted => "left quarantine",
+        NotificationKind::Released => "was released early",
+        NotificationKind::Blocked => "was blocked",
+        NotificationKind::ChecksumFailed => "failed checksum verification",
+        NotificationKind::PolicyFailed => "was refused by policy",
+    };
+    format!(
+        "{} {} ({}) {action}",
+        notification.name, notification.version, notification.ecosystem
+    )
+}
+
+fn details(notification: &Notification) -> String {
+    let mut text = format!("event: {}", notification.kind.as_str());
+    if let Some(platform) = &notification.platform {
+        text.push_str(&format!(" | platform: {platform}"));
+    }
+    if let Some(reason) = &notification.reason {
+        text.push_str(&format!(" | reason: {reason}"));
+    }
+    text
+}
+
+fn theme_color(kind: NotificationKind) -> &'static str {
+    match kind {
+        NotificationKind::Promoted | NotificationKind::Released => "2EB886",
+        NotificationKind::Quarantined => "DAA038",
+        NotificationKind::Blocked
+        | NotificationKind::ChecksumFailed
+        | NotificationKind::PolicyFailed => "A30200",
+    }
+}
@@ -7,6 +7,7 @@ mod handlers;
 mod types;
 
 pub use handlers::{handle_npm_request, is_npm_request};
+pub(crate) use handlers::{npm_registry_base, tarball_request};
 
 #[cfg(test)]
 pub(crate) use handlers::test_override::override_npm_registry_base;
@@ -3,10 +3,12 @@
 //! Handles package metadata and tarball requests with caching.
 
 use std::borrow::Cow;
+use std::collections::HashSet;
 use std::sync::Arc;
 
 use anyhow::{Context, Result};
 use rama::Service;
+use rama::extensions::ExtensionsRef as _;
 use rama::http::{
     Body, Method, Request, Response, StatusCode,
     body::util::BodyExt,
@@ -15,16 +17,22 @@ use rama::http::{
 };
 use rama::telemetry::tracing::warn;
 use serde_json::Value as JsonValue;
-use vein_adapter::{AssetKind, CacheBackend, CacheBackendTrait, FilesystemStorage};
+use vein_adapter::{AssetKind, CacheBackend, CacheBackendTrait, Ecosystem, FilesystemStorage};
 
 use super::types::NpmPackageRequest;
-use crate::http_cache::{CacheOutcome, CachedTextOptions, MetaStoreMode, fetch_cached_text};
+use crate::advisories;
+use crate::config::OfflineConfig;
+use crate::http_cache::{
+    CacheOutcome, CachedTextOptions, IndexOptions, MetaStoreMode, fetch_cached_text,
+};
+use crate::policy::IndexFilter;
 use crate::proxy::{cache as proxy_cache, types::CacheableRequest};
+use crate::rate_limit::{self, Budget, ClientBudget};
 use crate::upstream::{UA, simple_get};
 
 const NPM_REGISTRY_BASE: &str = "https://registry.npmjs.org";
 
-fn npm_registry_base() -> Cow<'static, str> {
+pub(crate) fn npm_registry_base() -> Cow<'static, str> {
     #[cfg(test)]
     if let Some(base) = test_override::current_npm_registry_base() {
         return Cow::Owned(base);
@@ -67,9 +75,18 @@ pub async fn handle_npm_request(
     our_base: &str,
     storage: Arc<FilesystemStorage>,
     index: Arc<CacheBackend>,
+    options: IndexOptions<'_>,
 ) -> Result<(Response<Body>, CacheOutcome)> {
     let registry_base = npm_registry_base();
-    handle_npm_request_from(req, our_base, storage, index, registry_base.as_ref()).await
+    handle_npm_request_from(
+        req,
+        our_base,
+        storage,
+        index,
+        options,
+        registry_base.as_ref(),
+    )
+    .await
 }
 
 async fn handle_npm_request_from(
@@ -77,12 +94,19 @@ async fn handle_npm_request_from(
     our_base: &str,
     storage: Arc<FilesystemStorage>,
     index: Arc<CacheBackend>,
+    options: IndexOptions<'_>,
     registry_base: &str,
 ) -> Result<(Response<Body>, CacheOutcome)> {
     let method = req.method().clone();
     let path = req.uri().path_or_root().into_owned();
 
     if path.starts_with("/-/") {
+        if options.offline.enabled {
+            return respond_error(
+                StatusCode::GATEWAY_TIMEOUT,
+                "Registry API unavailable offline",
+            );
+        }
         return handle_npm_api(req, registry_base).await;
     }
 
@@ -99,9 +123,18 @@ async fn handle_npm_request_from(
     };
 
     if npm_req.is_tarball {
-        handle_tarball_download(&npm_req, storage, index, registry_base).await
+        let client = ClientBudget::of(req.extensions());
+        handle_tarball_download(
+            &npm_req,
+            storage,
+            index,
+            options.offline,
+            registry_base,
+            client,
+        )
+        .await
     } else {
-        handle_package_metadata(&npm_req, our_base, storage, index, registry_base).await
+        handle_package_metadata(&npm_req, our_base, storage, index, options, registry_base).await
     }
 }
 
@@ -114,8 +147,17 @@ async fn handle_package_metadata(
     our_base: &str,
     storage: Arc<FilesystemStorage>,
     index: Arc<CacheBackend>,
+    options: IndexOptions<'_>,
     registry_base: &str,
 ) -> Result<(Response<Body>, CacheOutcome)> {
+    let IndexOptions {
+        offline,
+        index_cache,
+        policy,
+        client,
+    } = options;
+    // The refresh may outlive this request, so it owns its client handle
+    let client = client.cloned();
     let storage_path = npm_req.storage_path();
     let meta_key = npm_req.meta_key();
     let registry_base = registry_base.trim_end_matches('/').to_string();
@@ -130,9 +172,23 @@ async fn handle_package_metadata(
 
     let our_base = our_base.to_string();
 
+    // Only full packuments list versions; single-version documents pass through.
+    let cached_versions = if offline.filters_uncached() && npm_req.version.is_none() {
+        let versions = index
+            .cached_package_versions(AssetKind::NpmPackage, &npm_req.name)
+            .await
+            .context("listing cached npm versions")?;
+        Some(versions.into_iter().collect::<HashSet<_>>())
+    } else {
+        None
+    };
+    let policy = policy
+        .filter(|_| npm_req.version.is_none())
+        .map(|filter| (filter, npm_req.name.as_str()));
+
     let result = fetch_cached_text(
-        storage.as_ref(),
-        index.as_ref(),
+        &storage,
+        &index,
         CachedTextOptions {
             storage_path: &storage_path,
             meta_key: &meta_key,
@@ -141,13 +197,19 @@ async fn handle_package_metadata(
             include_content_length: true,
             meta_mode: MetaStoreMode::BestEffort,
             strip_transfer_encoding: false,
+            offline: offline.enabled,
+            stale_if_error: offline.stale_if_error,
+            fresh_for: index_cache.npm_metadata_ttl(),
+            stale_while_revalidate: index_cache.stale_while_revalidate(),
         },
-        |headers| async move {
-            simple_get(&upstream_url, &headers, Some("application/json")).await
+        move |headers| {
+            rate_limit::with_upstream_slot(client, async move {
+                simple_get(&upstream_url, &headers, Some("application/json")).await
+            })
         },
         move |body| async move {
             // Transform tarball URLs to point to our proxy
-            transform_metadata(&body, &our_base)
+            transform_metadata(&body, &our_base, cached_versions.as_ref(), policy)
         },
     )
     .await?;
@@ -155,6 +217,31 @@ async fn handle_package_metadata(
     Ok((result.response, result.outcome))
 }
 
+/// Maps a tarball download path to its cache entry; `None` for metadata paths.
+pub(crate) fn tarball_request(path: &str) -> Option<CacheableRequest> {
+    NpmPackageRequest::from_path(path)
+        .filter(|npm_req| npm_req.is_tarball)
+        .map(|npm_req| tarball_cacheable(&npm_req))
+}
+
+fn tarball_cacheable(npm_req: &NpmPackageRequest) -> CacheableRequest {
+    CacheableRequest {
+        kind: AssetKind::NpmPackage,
+        name: npm_req.name.clone(),
+        version: npm_req
+            .version
+            .clone()
+            .unwrap_or_else(|| "unknown".to_string()),
+        platform: None,
+        file_name: npm_req
+            .tarball_name
+            .as_deref()
+            .unwrap_or("package.tgz")
+            .to_string(),
+        relative_path: npm_req.storage_path(),
+    }
+}
+
 /// Handle tarball download request
 ///
 /// Fetches from registry.npmjs.org with permanent caching.
@@ -162,9 +249,10 @@ async fn handle_tarball_download(
     npm_req: &NpmPackageRequest,
     storage: Arc<FilesystemStorage>,
     index: Arc<CacheBackend>,
+    offline: &OfflineConfig,
     registry_base: &str,
+    client: Option<&ClientBudget>,
 ) -> Result<(Response<Body>, CacheOutcome)> {
-    let storage_path = npm_req.storage_path();
     let registry_base = registry_base.trim_end_matches('/').to_string();
 
     // URL-encode the package name for scoped packages
@@ -172,20 +260,15 @@ async fn handle_tarball_download(
     let tarball_name = npm_req.tarball_name.as_deref().unwrap_or("package.tgz");
     let upstream_url = format!("{}/{}/-/{}", registry_base, encoded_name, tarball_name);
 
-    let cacheable = CacheableRequest {
-        kind: AssetKind::NpmPackage,
-        name: npm_req.name.clone(),
-        version: npm_req
-            .version
-            .clone()
-            .unwrap_or_else(|| "unknown".to_string()),
-        platform: None,
-        file_name: tarball_name.to_string(),
-        relative_path: storage_path,
-    };
+    let cacheable = tarball_cacheable(npm_req);
 
     let mut had_cache = false;
     if let Some(entry) = index.get(&cacheable.asset_key()).await? {
+        if let Some(client) = client
+            && let Err(limited) = client.charge(Budget::Hits)
+        {
+            return Ok((limited.into_response()?, CacheOutcome::Pass));
+        }
         match proxy_cache::serve_cached(&cacheable, entry, storage.as_ref()).await {
             Ok(resp) => return Ok((resp, CacheOutcome::Hit)),
             Err(err) => {
@@ -198,6 +281,17 @@ async fn handle_tarball_download(
         }
     }
 
+    if offline.enabled {
+        return respond_error(StatusCode::GATEWAY_TIMEOUT, "not cached (offline mode)");
+    }
+
+    let _permit = match client {
+        Some(client) => match client.admit_upstream().await {
+            Ok(permit) => permit,
+            Err(limited) => return Ok((limited.into_response()?, CacheOutcome::Pass)),
+        },
+        None => None,
+    };
     let response = simple_get(&upstream_url, &HeaderMap::new(), None).await?;
     if !response.status().is_success() {
         let forwarded = forward_response(response).await?;
@@ -290,10 +384,76 @@ fn rewrite_dist_tarball(dist: &mut serde_json::Map<String, JsonValue>, our_base:
     }
 }
 
-/// Transform package metadata to point tarball URLs to our proxy
-fn transform_metadata(body: &[u8], our_base: &str) -> Result<Vec<u8>> {
+/// Drops versions not accepted by `keep`, along with their `time` entries and
+/// any dist-tags pointing at them. A dropped `latest` is moved to the newest
+/// remaining version.
+fn retain_versions(metadata: &mut JsonValue, keep: impl Fn(&str) -> bool) {
+    if let Some(versions) = metadata.get_mut("versions").and_then(|v| v.as_object_mut()) {
+        versions.retain(|version, _| keep(version));
+    }
+    if let Some(times) = metadata.get_mut("time").and_then(|t| t.as_object_mut()) {
+        times.retain(|key, _| key == "created" || key == "modified" || keep(key));
+    }
+
+    let newest = metadata
+        .get("versions")
+        .and_then(|v| v.as_object())
+        .and_then(|versions| {
+            versions
+                .keys()
+                .max_by(|a, b| advisories::compare(Ecosystem::Npm, a, b))
+                .cloned()
+        });
+    if let Some(tags) = metadata
+        .get_mut("dist-tags")
+        .and_then(|t| t.as_object_mut())
+    {
+        tags.retain(|_, version| version.as_str().is_some_and(&keep));
+        if !tags.contains_key("latest")
+            && let Some(newest) = newest
+        {
+            tags.insert("latest".to_string(), JsonValue::String(newest));
+        }
+    }
+}
+
+/// Versions of a packument hidden by `filter`, logged with the hiding rule.
+fn policy_hidden_versions(
+    metadata: &JsonValue,
+    name: &str,
+    filter: &IndexFilter,
+) -> HashSet<String> {
+    let Some(versions) = metadata.get("versions").and_then(|v| v.as_object()) else {
+        return HashSet::new();
+    };
+    let hidden = filter.hidden_versions(name, versions.keys().map(String::as_str));
+    filter.log_hidden(name, &hidden);
+    hidden
+        .into_iter()
+        .map(|(version, _)| version.to_string())
+        .collect()
+}
+
+/// Transform package metadata to point tarball URLs to our proxy, optionally
+/// limited to the `cached` versions and without versions hidden by `policy`.
+fn transform_metadata(
+    body: &[u8],
+    our_base: &str,
+    cached: Option<&HashSet<String>>,
+    policy: Option<(&IndexFilter, &str)>,
+) -> Result<Vec<u8>> {
     let mut metadata: JsonValue = serde_json::from_slice(body).context("parsing npm metadata")?;
 
+    if let Some(cached) = cached {
+        retain_versions(&mut metadata, |version| cached.contains(version));
+    }
+    if let Some((filter, name)) = policy {
+        let hidden = policy_hidden_versions(&metadata, name, filter);
+        if !hidden.is_empty() {
+            retain_versions(&mut metadata, |version| !hidden.contains(version));
+        }
+    }
+
     // Transform top-level dist.tarball (version-specific metadata)
     if let Some(dist) = metadata.get_mut("dist").and_then(|d| d.as_object_mut()) {
         rewrite_dist_tarball(dist, our_base);
@@ -398,6 +558,7 @@ pub(crate) mod test_override {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::IndexCacheConfig;
     use rama::http::body::util::BodyExt;
     use rama::tls::rustls::dep::rustls;
     use std::sync::{Arc, Once};
@@ -456,7 +617,8 @@ mod tests {
             }
         }"#;
 
-        let result = transform_metadata(metadata.as_bytes(), "http://localhost:8346").unwrap();
+        let result =
+            transform_metadata(metadata.as_bytes(), "http://localhost:8346", None, None).unwrap();
 
         let transformed: JsonValue = serde_json::from_slice(&result).unwrap();
         let tarball = transformed["versions"]["4.17.21"]["dist"]["tarball"]
@@ -475,13 +637,49 @@ mod tests {
             }
         }"#;
 
-        let result = transform_metadata(metadata.as_bytes(), "http://localhost:8346").unwrap();
+        let result =
+            transform_metadata(metadata.as_bytes(), "http://localhost:8346", None, None).unwrap();
 
         let transformed: JsonValue = serde_json::from_slice(&result).unwrap();
         let tarball = transformed["dist"]["tarball"].as_str().unwrap();
         assert_eq!(tarball, "http://localhost:8346/lodash/-/lodash-4.17.21.tgz");
     }
 
+    #[test]
+    fn test_transform_metadata_keeps_cached_versions() {
+        let metadata = r#"{
+            "name": "lodash",
+            "dist-tags": { "latest": "4.17.21", "legacy": "3.10.1" },
+            "time": { "created": "2012", "4.17.20": "2020", "4.17.21": "2021" },
+            "versions": {
+                "4.17.20": { "dist": { "tarball": "https://registry.npmjs.org/lodash/-/lodash-4.17.20.tgz" } },
+                "4.17.21": { "dist": { "tarball": "https://registry.npmjs.org/lodash/-/lodash-4.17.21.tgz" } },
+                "3.10.1": { "dist": { "tarball": "https://registry.npmjs.org/lodash/-/lodash-3.10.1.tgz" } }
+            }
+        }"#;
+        let cached: HashSet<String> = ["4.17.20", "3.10.1"]
+            .into_iter()
+            .map(str::to_string)
+            .collect();
+
+        let result = transform_metadata(
+            metadata.as_bytes(),
+            "http://localhost:8346",
+            Some(&cached),
+            None,
+        )
+        .unwrap();
+
+        let transformed: JsonValue = serde_json::from_slice(&result).unwrap();
+        let versions = transformed["versions"].as_object().unwrap();
+        assert_eq!(versions.len(), 2);
+        assert!(!versions.contains_key("4.17.21"));
+        assert_eq!(transformed["dist-tags"]["latest"], "4.17.20");
+        assert_eq!(transformed["dist-tags"]["legacy"], "3.10.1");
+        assert!(transformed["time"].get("4.17.21").is_none());
+        assert_eq!(transformed["time"]["created"], "2012");
+    }
+
     #[cfg(feature = "sqlite")]
     #[tokio::test]
     async fn test_handle_npm_metadata_caches_and_revalidates() {
@@ -526,6 +724,12 @@ mod tests {
             "http://localhost:8346",
             storage.clone(),
             index.clone(),
+            IndexOptions {
+                offline: &OfflineConfig::default(),
+                index_cache: &revalidate_always(),
+                policy: None,
+                client: None,
+            },
             &registry_base,
         )
         .await
@@ -547,6 +751,12 @@ mod tests {
             "http://localhost:8346",
             storage.clone(),
             index.clone(),
+            IndexOptions {
+                offline: &OfflineConfig::default(),
+                index_cache: &revalidate_always(),
+                policy: None,
+                client: None,
+            },
             &registry_base,
         )
         .await
@@ -602,6 +812,12 @@ mod tests {
             "http://localhost:8346",
             storage.clone(),
             index.clone(),
+            IndexOptions {
+                offline: &OfflineConfig::default(),
+                index_cache: &revalidate_always(),
+                policy: None,
+                client: None,
+            },
             &registry_base,
         )
         .await
@@ -623,6 +839,12 @@ mod tests {
             "http://localhost:8346",
             storage.clone(),
             index.clone(),
+            IndexOptions {
+                offline: &OfflineConfig::default(),
+                index_cache: &revalidate_always(),
+                policy: None,
+                client: None,
+            },
             &registry_base,
         )
         .await
@@ -633,6 +855,15 @@ mod tests {
         assert!(storage.resolve("npm/lodash/lodash-4.17.21.tgz").exists());
     }
 
+    #[cfg(feature = "sqlite")]
+    fn revalidate_always() -> IndexCacheConfig {
+        IndexCacheConfig {
+            npm_metadata_ttl_secs: 0,
+            stale_while_revalidate_secs: 0,
+            ..IndexCacheConfig::default()
+        }
+    }
+
     #[cfg(feature = "sqlite")]
     fn install_rustls_provider() {
         static INIT: Once = Once::new();
@@ -0,0 +1,45 @@
+//! Manifest metadata and SBOMs for non-gem packages.
+//!
+//! Gems keep their richer record in [`crate::gem_metadata`]; everything else
+//! lands in the shared `package_metadata` table.
+
+use anyhow::Result;
+use std::path::Path;
+use tokio::task;
+use vein_adapter::{Ecosystem, PackageMetadata};
+
+mod crates;
+mod npm;
+mod sbom;
+
+#[cfg(test)]
+mod tests;
+
+pub use crates::parse_crate_metadata;
+pub use npm::parse_npm_metadata;
+pub use sbom::generate_cyclonedx_sbom;
+
+/// Extract structured metadata from a cached package archive.
+///
+/// Returns `Ok(None)` for ecosystems without an extractor and for archives
+/// that carry no manifest we understand.
+pub async fn extract_package_metadata(
+    ecosystem: Ecosystem,
+    path: &Path,
+    name: &str,
+    version: &str,
+    size_bytes: u64,
+    sha256: &str,
+) -> Result<Option<PackageMetadata>> {
+    let path = path.to_owned();
+    let name = name.to_owned();
+    let version = version.to_owned();
+    let sha256 = sha256.to_owned();
+
+    task::spawn_blocking(move || match ecosystem {
+        Ecosystem::CratesIo => parse_crate_metadata(&path, &name, &version, size_bytes, &sha256),
+        Ecosystem::Npm => parse_npm_metadata(&path, &name, &version, size_bytes, &sha256),
+        Ecosystem::RubyGems => Ok(None),
+    })
+    .await?
+}
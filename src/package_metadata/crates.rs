@@ -0,0 +1,242 @@
+//! `Cargo.toml` extraction from `.crate` archives.
+
+use std::{collections::BTreeMap, fs::File, io::Read, path::Path};
+
+use anyhow::{Context, Result};
+use flate2::read::GzDecoder;
+use rama::telemetry::tracing::warn;
+use serde::Deserialize;
+use serde_json::json;
+use tar::Archive;
+use vein_adapter::{Ecosystem, PackageDependency, PackageMetadata};
+
+use super::sbom::generate_cyclonedx_sbom;
+use crate::sbom::SBOM_GENERATOR;
+
+/// Normalized manifest written by `cargo package`.
+const NORMALIZED_MANIFEST: &str = "Cargo.toml";
+/// Manifest as the author wrote it; may inherit fields from a workspace.
+const ORIGINAL_MANIFEST: &str = "Cargo.toml.orig";
+
+pub fn parse_crate_metadata(
+    path: &Path,
+    name: &str,
+    version: &str,
+    size_bytes: u64,
+    sha256: &str,
+) -> Result<Option<PackageMetadata>> {
+    let file = File::open(path).with_context(|| format!("opening crate at {}", path.display()))?;
+    let mut archive = Archive::new(GzDecoder::new(file));
+
+    let mut normalized: Option<String> = None;
+    let mut original: Option<String> = None;
+    let mut has_build_rs = false;
+
+    for entry in archive.entries().context("reading crate archive entries")? {
+        let mut entry = entry.context("accessing crate archive entry")?;
+        let entry_path = entry.path().context("reading entry path")?.into_owned();
+        // Everything lives under `<name>-<version>/`; only top-level files matter.
+        if entry_path.components().count() != 2 {
+            continue;
+        }
+        let file_name = entry_path
+            .file_name()
+            .map(|f| f.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let slot = match file_name.as_str() {
+            NORMALIZED_MANIFEST => &mut normalized,
+            ORIGINAL_MANIFEST => &mut original,
+            "build.rs" => {
+                has_build_rs = true;
+                continue;
+            }
+            _ => continue,
+        };
+        let mut buffer = String::new();
+        entry
+            .read_to_string(&mut buffer)
+            .with_context(|| format!("reading {file_name} from crate archive"))?;
+        *slot = Some(buffer);
+    }
+
+    let (manifest, source) = match parse_manifest(normalized.as_deref(), NORMALIZED_MANIFEST) {
+        Some(manifest) => (manifest, NORMALIZED_MANIFEST),
+        None => match parse_manifest(original.as_deref(), ORIGINAL_MANIFEST) {
+            Some(manifest) => (manifest, ORIGINAL_MANIFEST),
+            None => return Ok(None),
+        },
+    };
+    let Some(package) = manifest.package.as_ref() else {
+        return Ok(None);
+    };
+
+    // `build = false` opts out of an auto-detected build.rs.
+    let has_build_script = match &package.build {
+        Some(toml::Value::Boolean(enabled)) => *enabled && has_build_rs,
+        Some(toml::Value::String(_)) => true,
+        _ => has_build_rs,
+    };
+    let proc_macro = manifest
+        .lib
+        .as_ref()
+        .is_some_and(|lib| lib.proc_macro || lib.proc_macro_legacy);
+    let links = inherited(&package.links);
+
+    let mut metadata = PackageMetadata {
+        ecosystem: Ecosystem::CratesIo,
+        name: name.to_string(),
+        version: version.to_string(),
+        description: inherited(&package.description),
+        license: inherited(&package.license),
+        authors: inherited(&package.authors).unwrap_or_default(),
+        homepage: inherited(&package.homepage),
+        repository: inherited(&package.repository),
+        dependencies: manifest.dependencies(),
+        features: manifest.features.clone(),
+        attributes: json!({
+            "build_script": has_build_script,
+            "links": links,
+            "proc_macro": proc_macro,
+            "edition": inherited(&package.edition),
+            "rust_version": inherited(&package.rust_version),
+            "license_file": inherited(&package.license_file),
+            "manifest": source,
+        }),
+        size_bytes,
+        sha256: sha256.to_string(),
+        sbom: None,
+        sbom_generator: None,
+    };
+    metadata.sbom = generate_cyclonedx_sbom(&metadata)?;
+    metadata.sbom_generator = metadata.sbom.as_ref().map(|_| SBOM_GENERATOR.to_string());
+
+    Ok(Some(metadata))
+}
+
+fn parse_manifest(raw: Option<&str>, source: &str) -> Option<Manifest> {
+    match toml::from_str::<Manifest>(raw?) {
+        Ok(manifest) => Some(manifest),
+        Err(err) => {
+            warn!(error = %err, manifest = source, "failed to parse crate manifest");
+            None
+        }
+    }
+}
+
+/// A field that `Cargo.toml.orig` may take from the workspace with
+/// `{ workspace = true }`; those resolve to nothing here.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Inheritable<T> {
+    Value(T),
+    Workspace {},
+}
+
+fn inherited<T: Clone>(field: &Option<Inheritable<T>>) -> Option<T> {
+    match field {
+        Some(Inheritable::Value(value)) => Some(value.clone()),
+        Some(Inheritable::Workspace {}) | None => None,
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Manifest {
+    package: Option<Package>,
+    lib: Option<Lib>,
+    #[serde(default)]
+    features: BTreeMap<String, Vec<String>>,
+    #[serde(flatten)]
+    tables: DependencyTables,
+    #[serde(default)]
+    target: BTreeMap<String, DependencyTables>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct Package {
+    description: Option<Inheritable<String>>,
+    license: Option<Inheritable<String>>,
+    license_file: Option<Inheritable<String>>,
+    authors: Option<Inheritable<Vec<String>>>,
+    homepage: Option<Inheritable<String>>,
+    repository: Option<Inheritable<String>>,
+    edition: Option<Inheritable<String>>,
+    rust_version: Option<Inheritable<String>>,
+    links: Option<Inheritable<String>>,
+    build: Option<toml::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Lib {
+    #[serde(default, rename = "proc-macro")]
+    proc_macro: bool,
+    #[serde(default, rename = "proc_macro")]
+    proc_macro_legacy: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct DependencyTables {
+    #[serde(default)]
+    dependencies: BTreeMap<String, DependencySpec>,
+    #[serde(default, alias = "dev_dependencies")]
+    dev_dependencies: BTreeMap<String, DependencySpec>,
+    #[serde(default, alias = "build_dependencies")]
+    build_dependencies: BTreeMap<String, DependencySpec>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum DependencySpec {
+    Version(String),
+    Detailed(DetailedDependency),
+}
+
+#[derive(Debug, Deserialize)]
+struct DetailedDependency {
+    version: Option<String>,
+    /// Real crate name when the dependency is renamed
+    package: Option<String>,
+    #[serde(default)]
+    optional: bool,
+}
+
+impl Manifest {
+    fn dependencies(&self) -> Vec<PackageDependency> {
+        let mut dependencies = Vec::new();
+        self.tables.collect(None, &mut dependencies);
+        for (target, tables) in &self.target {
+            tables.collect(Some(target), &mut dependencies);
+        }
+        dependencies
+    }
+}
+
+impl DependencyTables {
+    fn collect(&self, target: Option<&String>, out: &mut Vec<PackageDependency>) {
+        let tables = [
+            ("normal", &self.dependencies),
+            ("dev", &self.dev_dependencies),
+            ("build", &self.build_dependencies),
+        ];
+        for (kind, table) in tables {
+            for (key, spec) in table {
+                let (name, requirement, optional) = match spec {
+                    DependencySpec::Version(requirement) => (key, requirement.as_str(), false),
+                    DependencySpec::Detailed(detail) => (
+                        detail.package.as_ref().unwrap_or(key),
+                        detail.version.as_deref().unwrap_or("*"),
+                        detail.optional,
+                    ),
+                };
+                out.push(PackageDependency {
+                    name: name.clone(),
+                    requirement: requirement.to_string(),
+                    kind: kind.to_string(),
+                    optional,
+                    target: target.cloned(),
+                });
+            }
+        }
+    }
+}
@@ -0,0 +1,223 @@
+//! `package.json` extraction from npm `.tgz` archives.
+
+use std::{fs::File, io::Read, path::Path};
+
+use anyhow::{Context, Result};
+use flate2::read::GzDecoder;
+use rama::telemetry::tracing::warn;
+use serde_json::{Map, Value as JsonValue, json};
+use tar::Archive;
+use vein_adapter::{Ecosystem, PackageDependency, PackageMetadata};
+
+use super::sbom::generate_cyclonedx_sbom;
+use crate::sbom::SBOM_GENERATOR;
+
+/// Scripts npm runs on `npm install` unless `--ignore-scripts` is given.
+const LIFECYCLE_SCRIPTS: [&str; 3] = ["preinstall", "install", "postinstall"];
+
+pub fn parse_npm_metadata(
+    path: &Path,
+    name: &str,
+    version: &str,
+    size_bytes: u64,
+    sha256: &str,
+) -> Result<Option<PackageMetadata>> {
+    let file =
+        File::open(path).with_context(|| format!("opening npm tarball at {}", path.display()))?;
+    let mut archive = Archive::new(GzDecoder::new(file));
+
+    let mut manifest: Option<String> = None;
+    let mut has_binding_gyp = false;
+    let mut has_node_binaries = false;
+
+    for entry in archive.entries().context("reading npm tarball entries")? {
+        let mut entry = entry.context("accessing npm tarball entry")?;
+        let entry_path = entry.path().context("reading entry path")?.into_owned();
+        // The top-level directory is usually `package/`, but not always.
+        let top_level = entry_path.components().count() == 2;
+        let file_name = entry_path
+            .file_name()
+            .map(|f| f.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        if file_name.ends_with(".node") {
+            has_node_binaries = true;
+        }
+        if !top_level {
+            continue;
+        }
+        match file_name.as_str() {
+            "binding.gyp" => has_binding_gyp = true,
+            "package.json" if manifest.is_none() => {
+                let mut buffer = String::new();
+                entry
+                    .read_to_string(&mut buffer)
+                    .context("reading package.json from npm tarball")?;
+                manifest = Some(buffer);
+            }
+            _ => {}
+        }
+    }
+
+    let Some(manifest) = manifest else {
+        return Ok(None);
+    };
+    let package: JsonValue = match serde_json::from_str(&manifest) {
+        Ok(package @ JsonValue::Object(_)) => package,
+        Ok(_) => return Ok(None),
+        Err(err) => {
+            warn!(error = %err, package = name, "failed to parse package.json");
+            return Ok(None);
+        }
+    };
+
+    let authors: Vec<String> = person(&package["author"]).into_iter().collect();
+    let maintainers: Vec<String> = people(&package["maintainers"]);
+
+    let lifecycle_scripts: Map<String, JsonValue> = LIFECYCLE_SCRIPTS
+        .iter()
+        .filter_map(|script| {
+            package["scripts"][script]
+                .as_str()
+                .map(|command| (script.to_string(), JsonValue::from(command)))
+        })
+        .collect();
+    let gypfile = package["gypfile"].as_bool().unwrap_or(false) || has_binding_gyp;
+    // npm runs `node-gyp rebuild` as the implicit install script for a binding.gyp.
+    let runs_install_scripts = !lifecycle_scripts.is_empty() || gypfile;
+
+    let mut metadata = PackageMetadata {
+        ecosystem: Ecosystem::Npm,
+        name: name.to_string(),
+        version: version.to_string(),
+        description: string(&package["description"]),
+        license: license(&package),
+        authors,
+        homepage: string(&package["homepage"]),
+        repository: string(&package["repository"])
+            .or_else(|| string(&package["repository"]["url"])),
+        dependencies: dependencies(&package),
+        features: Default::default(),
+        attributes: json!({
+            "maintainers": maintainers,
+            "bin": bin_names(&package, name),
+            "lifecycle_scripts": lifecycle_scripts,
+            "install_scripts": runs_install_scripts,
+            "gypfile": gypfile,
+            "native_addon": gypfile || has_node_binaries,
+            "bundled_dependencies": bundled_dependencies(&package),
+        }),
+        size_bytes,
+        sha256: sha256.to_string(),
+        sbom: None,
+        sbom_generator: None,
+    };
+    metadata.sbom = generate_cyclonedx_sbom(&metadata)?;
+    metadata.sbom_generator = metadata.sbom.as_ref().map(|_| SBOM_GENERATOR.to_string());
+
+    Ok(Some(metadata))
+}
+
+fn string(value: &JsonValue) -> Option<String> {
+    value
+        .as_str()
+        .map(str::trim)
+        .filter(|text| !text.is_empty())
+        .map(str::to_string)
+}
+
+/// A person is either `"Name <email> (url)"` or `{ "name", "email", "url" }`.
+fn person(value: &JsonValue) -> Option<String> {
+    if let Some(text) = string(value) {
+        return Some(text);
+    }
+    let name = string(&value["name"])?;
+    Some(match string(&value["email"]) {
+        Some(email) => format!("{name} <{email}>"),
+        None => name,
+    })
+}
+
+fn people(value: &JsonValue) -> Vec<String> {
+    value
+        .as_array()
+        .map(|list| list.iter().filter_map(person).collect())
+        .unwrap_or_default()
+}
+
+/// `license` is an SPDX expression; older packages use `{ "type": .. }` or a
+/// `licenses` array, which become an `OR` expression.
+fn license(package: &JsonValue) -> Option<String> {
+    string(&package["license"])
+        .or_else(|| string(&package["license"]["type"]))
+        .or_else(|| {
+            let legacy: Vec<String> = package["licenses"]
+                .as_array()?
+                .iter()
+                .filter_map(|entry| string(entry).or_else(|| string(&entry["type"])))
+                .collect();
+            (!legacy.is_empty()).then(|| legacy.join(" OR "))
+        })
+}
+
+fn dependencies(package: &JsonValue) -> Vec<PackageDependency> {
+    let tables = [
+        ("dependencies", "runtime"),
+        ("devDependencies", "dev"),
+        ("peerDependencies", "peer"),
+        ("optionalDependencies", "optional"),
+    ];
+    let mut dependencies = Vec::new();
+    for (field, kind) in tables {
+        let Some(table) = package[field].as_object() else {
+            continue;
+        };
+        for (name, requirement) in table {
+            let optional = match kind {
+                "optional" => true,
+                "peer" => package["peerDependenciesMeta"][name]["optional"]
+                    .as_bool()
+                    .unwrap_or(false),
+                _ => false,
+            };
+            dependencies.push(PackageDependency {
+                name: name.clone(),
+                requirement: requirement.as_str().unwrap_or("*").to_string(),
+                kind: kind.to_string(),
+                optional,
+                target: None,
+            });
+        }
+    }
+    dependencies
+}
+
+/// Command names installed into `node_modules/.bin`.
+fn bin_names(package: &JsonValue, name: &str) -> Vec<String> {
+    match &package["bin"] {
+        // A single path installs a command named after the unscoped package.
+        JsonValue::String(_) => vec![name.rsplit('/').next().unwrap_or(name).to_string()],
+        JsonValue::Object(commands) => commands.keys().cloned().collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// `bundleDependencies` (or `bundledDependencies`) is a name list, or `true`
+/// for every runtime dependency.
+fn bundled_dependencies(package: &JsonValue) -> Vec<String> {
+    let declared = match &package["bundleDependencies"] {
+        JsonValue::Null => &package["bundledDependencies"],
+        other => other,
+    };
+    match declared {
+        JsonValue::Array(names) => names
+            .iter()
+            .filter_map(|name| name.as_str().map(str::to_string))
+            .collect(),
+        JsonValue::Bool(true) => package["dependencies"]
+            .as_object()
+            .map(|deps| deps.keys().cloned().collect())
+            .unwrap_or_default(),
+        _ => Vec::new(),
+    }
+}
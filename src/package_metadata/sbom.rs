@@ -0,0 +1,195 @@
+//! CycloneDX SBOM generation for crates and npm packages.
+
+use anyhow::{Context, Result};
+use cyclonedx_bom::{
+    models::{
+        component::{Classification, Component},
+        hash::{Hash, HashAlgorithm, HashValue, Hashes},
+        license::{LicenseChoice, Licenses},
+        metadata::Metadata as BomMetadata,
+        property::{Properties, Property},
+        tool::{Tool, Tools},
+    },
+    prelude::{Bom, NormalizedString, Purl, SpdxExpression, SpecVersion, Validate},
+};
+use rama::telemetry::tracing::{info, warn};
+use serde_json::Value as JsonValue;
+use vein_adapter::{Ecosystem, PackageMetadata};
+
+/// Package URL type for an ecosystem.
+fn purl_type(ecosystem: Ecosystem) -> &'static str {
+    match ecosystem {
+        Ecosystem::RubyGems => "gem",
+        Ecosystem::CratesIo => "cargo",
+        Ecosystem::Npm => "npm",
+    }
+}
+
+/// Renders an attribute value; `None` for null and empty lists.
+fn property_value(value: &JsonValue) -> Option<String> {
+    match value {
+        JsonValue::Null => None,
+        JsonValue::String(text) => Some(text.clone()),
+        JsonValue::Array(items) if items.is_empty() => None,
+        JsonValue::Array(items) => Some(
+            items
+                .iter()
+                .map(|item| {
+                    item.as_str()
+                        .map_or_else(|| item.to_string(), str::to_string)
+                })
+                .collect::<Vec<_>>()
+                .join(", "),
+        ),
+        other => Some(other.to_string()),
+    }
+}
+
+/// Generate a CycloneDX SBOM for a crate or npm package.
+pub fn generate_cyclonedx_sbom(metadata: &PackageMetadata) -> Result<Option<JsonValue>> {
+    let purl_type = purl_type(metadata.ecosystem);
+    let root_ref = format!("pkg:{purl_type}/{}@{}", metadata.name, metadata.version);
+
+    let mut component = Component::new(
+        Classification::Library,
+        &metadata.name,
+        &metadata.version,
+        Some(root_ref),
+    );
+
+    if let Some(desc) = metadata.description.as_deref() {
+        component.description = Some(NormalizedString::new(desc));
+    }
+
+    let author_list: Vec<_> = metadata
+        .authors
+        .iter()
+        .map(|author| author.trim())
+        .filter(|author| !author.is_empty())
+        .collect();
+    if !author_list.is_empty() {
+        component.author = Some(NormalizedString::new(&author_list.join(", ")));
+    }
+
+    if let Some(license) = metadata
+        .license
+        .as_deref()
+        .map(str::trim)
+        .filter(|license| !license.is_empty())
+    {
+        // Both ecosystems declare SPDX expressions; older crates still use
+        // "MIT/Apache-2.0", which lax parsing rewrites. Anything else is kept by name.
+        let choice = SpdxExpression::parse_lax(license.to_string())
+            .map(LicenseChoice::Expression)
+            .unwrap_or_else(|_| LicenseChoice::license(license));
+        component.licenses = Some(Licenses(vec![choice]));
+    }
+
+    component.hashes = Some(Hashes(vec![Hash {
+        alg: HashAlgorithm::SHA_256,
+        content: HashValue(metadata.sha256.clone()),
+    }]));
+
+    if let Ok(purl) = Purl::new(purl_type, &metadata.name, &metadata.version) {
+        component.purl = Some(purl);
+    }
+
+    let mut properties = vec![Property::new(
+        "vein:size-bytes",
+        &metadata.size_bytes.to_string(),
+    )];
+
+    if let Some(homepage) = metadata.homepage.as_deref() {
+        properties.push(Property::new("vein:homepage", homepage));
+    }
+    if let Some(repository) = metadata.repository.as_deref() {
+        properties.push(Property::new("vein:source-url", repository));
+    }
+
+    // Ecosystem-specific flags, e.g. `build_script` becomes `vein:build-script`
+    // and each entry of an object such as `lifecycle_scripts` its own property.
+    if let Some(attributes) = metadata.attributes.as_object() {
+        for (key, value) in attributes {
+            let name = format!("vein:{}", key.replace('_', "-"));
+            match value {
+                JsonValue::Object(entries) => {
+                    for (entry, value) in entries {
+                        if let Some(value) = property_value(value) {
+                            properties.push(Property::new(format!("{name}:{entry}"), &value));
+                        }
+                    }
+                }
+                other => {
+                    if let Some(value) = property_value(other) {
+                        properties.push(Property::new(name, &value));
+                    }
+                }
+            }
+        }
+    }
+
+    if !metadata.features.is_empty() {
+        let features: Vec<&str> = metadata.features.keys().map(String::as_str).collect();
+        properties.push(Property::new("vein:features", &features.join(", ")));
+    }
+
+    for dependency in &metadata.dependencies {
+        let mut value = format!("{} {}", dependency.name, dependency.requirement);
+        if dependency.optional {
+            value.push_str(" (optional)");
+        }
+        if let Some(target) = dependency.target.as_deref() {
+            value.push_str(&format!(" [{target}]"));
+        }
+        properties.push(Property::new(
+            format!("vein:dependency:{}", dependency.kind),
+            &value,
+        ));
+    }
+
+    component.properties = Some(Properties(properties));
+
+    let mut bom_metadata = BomMetadata::new().unwrap_or_default();
+    bom_metadata.component = Some(component.clone());
+    bom_metadata.tools = Some(Tools::List(vec![Tool::new(
+        "Ore Ecosystem",
+        "Vein",
+        env!("CARGO_PKG_VERSION"),
+    )]));
+
+    if let Some(component_licenses) = component.licenses.clone() {
+        bom_metadata.licenses = Some(component_licenses);
+    }
+
+    let bom = Bom {
+        spec_version: SpecVersion::V1_5,
+        metadata: Some(bom_metadata),
+        ..Bom::default()
+    };
+
+    let validation = bom.validate_version(SpecVersion::V1_5);
+    if !validation.passed() {
+        warn!(
+            ?validation,
+            ecosystem = %metadata.ecosystem,
+            package = %metadata.name,
+            "generated CycloneDX SBOM failed validation"
+        );
+        return Ok(None);
+    }
+
+    let mut output = Vec::new();
+    bom.output_as_json_v1_5(&mut output)
+        .context("serializing CycloneDX SBOM")?;
+
+    info!(
+        event = "sbom.compute",
+        ecosystem = %metadata.ecosystem,
+        package = %metadata.name,
+        version = %metadata.version,
+        "generated CycloneDX SBOM"
+    );
+
+    let sbom = serde_json::from_slice(&output).context("parsing CycloneDX SBOM json")?;
+    Ok(Some(sbom))
+}
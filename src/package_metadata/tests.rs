@@ -0,0 +1,274 @@
+use super::*;
+use flate2::{Compression, write::GzEncoder};
+use std::io::Cursor;
+use tar::{Builder, Header};
+use tempfile::NamedTempFile;
+
+const MANIFEST: &str = r#"
+[package]
+edition = "2021"
+name = "zlib-wrap"
+version = "0.3.1"
+authors = ["Jane Doe <jane@example.com>"]
+links = "z"
+description = "Bindings to zlib"
+homepage = "https://example.com/zlib-wrap"
+license = "MIT/Apache-2.0"
+repository = "https://github.com/example/zlib-wrap"
+
+[features]
+default = ["static"]
+static = []
+serde = ["dep:serde"]
+
+[dependencies.serde]
+version = "1.0"
+optional = true
+
+[dependencies.libz]
+version = "1.1"
+package = "libz-sys"
+
+[dev-dependencies.tempfile]
+version = "3"
+
+[build-dependencies.cc]
+version = "1.0.83"
+
+[target."cfg(windows)".dependencies.winapi]
+version = "0.3"
+"#;
+
+fn build_test_archive(entries: &[(&str, &str)]) -> NamedTempFile {
+    let file = NamedTempFile::new().expect("create temp archive");
+    {
+        let handle = file.reopen().expect("reopen temp archive");
+        let mut builder = Builder::new(GzEncoder::new(handle, Compression::default()));
+        for (path, contents) in entries {
+            let mut header = Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o644);
+            builder
+                .append_data(&mut header, path, Cursor::new(contents.as_bytes()))
+                .expect("append archive entry");
+        }
+        builder
+            .into_inner()
+            .expect("finish tar")
+            .finish()
+            .expect("finish gzip");
+    }
+    file
+}
+
+#[test]
+fn crate_manifest_is_parsed_into_metadata() {
+    let archive = build_test_archive(&[
+        ("zlib-wrap-0.3.1/Cargo.toml", MANIFEST),
+        ("zlib-wrap-0.3.1/Cargo.toml.orig", "not [valid"),
+        ("zlib-wrap-0.3.1/build.rs", "fn main() {}"),
+        ("zlib-wrap-0.3.1/src/lib.rs", ""),
+    ]);
+
+    let metadata =
+        parse_crate_metadata(archive.path(), "zlib-wrap", "0.3.1", 1234, &"ab".repeat(32))
+            .expect("parse crate")
+            .expect("metadata present");
+
+    assert_eq!(metadata.ecosystem, Ecosystem::CratesIo);
+    assert_eq!(metadata.description.as_deref(), Some("Bindings to zlib"));
+    assert_eq!(metadata.license.as_deref(), Some("MIT/Apache-2.0"));
+    assert_eq!(metadata.authors, ["Jane Doe <jane@example.com>"]);
+    assert_eq!(
+        metadata.repository.as_deref(),
+        Some("https://github.com/example/zlib-wrap")
+    );
+    assert_eq!(metadata.features["default"], ["static"]);
+    assert_eq!(metadata.features["serde"], ["dep:serde"]);
+    assert_eq!(metadata.attributes["build_script"], true);
+    assert_eq!(metadata.attributes["links"], "z");
+    assert_eq!(metadata.attributes["proc_macro"], false);
+    assert_eq!(metadata.attributes["manifest"], "Cargo.toml");
+
+    let deps: Vec<_> = metadata
+        .dependencies
+        .iter()
+        .map(|dep| {
+            (
+                dep.name.as_str(),
+                dep.kind.as_str(),
+                dep.optional,
+                dep.target.as_deref(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        deps,
+        [
+            ("libz-sys", "normal", false, None),
+            ("serde", "normal", true, None),
+            ("tempfile", "dev", false, None),
+            ("cc", "build", false, None),
+            ("winapi", "normal", false, Some("cfg(windows)")),
+        ]
+    );
+
+    let sbom = metadata.sbom.expect("sbom generated");
+    let component = &sbom["metadata"]["component"];
+    assert_eq!(component["purl"], "pkg:cargo/zlib-wrap@0.3.1");
+    assert_eq!(component["licenses"][0]["expression"], "MIT OR Apache-2.0");
+}
+
+#[test]
+fn original_manifest_is_used_when_normalized_is_missing() {
+    let original = r#"
+[package]
+name = "derive-thing"
+version = "1.0.0"
+license.workspace = true
+description = "A derive macro"
+build = false
+
+[lib]
+proc-macro = true
+
+[dependencies]
+syn = { workspace = true }
+quote = "1"
+"#;
+    let archive = build_test_archive(&[
+        ("derive-thing-1.0.0/Cargo.toml.orig", original),
+        ("derive-thing-1.0.0/build.rs", "fn main() {}"),
+    ]);
+
+    let metadata = parse_crate_metadata(archive.path(), "derive-thing", "1.0.0", 10, "def456")
+        .expect("parse crate")
+        .expect("metadata present");
+
+    assert_eq!(metadata.license, None);
+    assert_eq!(metadata.description.as_deref(), Some("A derive macro"));
+    assert_eq!(metadata.attributes["build_script"], false);
+    assert_eq!(metadata.attributes["proc_macro"], true);
+    assert_eq!(metadata.attributes["manifest"], "Cargo.toml.orig");
+    let requirements: Vec<_> = metadata
+        .dependencies
+        .iter()
+        .map(|dep| (dep.name.as_str(), dep.requirement.as_str()))
+        .collect();
+    assert_eq!(requirements, [("quote", "1"), ("syn", "*")]);
+}
+
+#[test]
+fn crate_without_manifest_yields_none() {
+    let archive = build_test_archive(&[("empty-0.1.0/src/lib.rs", "")]);
+    let metadata =
+        parse_crate_metadata(archive.path(), "empty", "0.1.0", 0, "0").expect("parse crate");
+    assert!(metadata.is_none());
+}
+
+const PACKAGE_JSON: &str = r#"{
+  "name": "@acme/native-hash",
+  "version": "2.1.0",
+  "description": "Fast hashing",
+  "license": "MIT",
+  "author": { "name": "Acme", "email": "dev@acme.test" },
+  "maintainers": ["ops <ops@acme.test>"],
+  "repository": { "type": "git", "url": "git+https://github.com/acme/native-hash.git" },
+  "bin": "./cli.js",
+  "gypfile": true,
+  "scripts": {
+    "test": "jest",
+    "install": "node-gyp rebuild",
+    "postinstall": "node scripts/telemetry.js"
+  },
+  "dependencies": { "nan": "^2.17.0", "bindings": "^1.5.0" },
+  "devDependencies": { "jest": "^29.0.0" },
+  "peerDependencies": { "react": ">=18" },
+  "peerDependenciesMeta": { "react": { "optional": true } },
+  "optionalDependencies": { "fsevents": "^2.3.0" },
+  "bundleDependencies": ["bindings"]
+}"#;
+
+#[test]
+fn npm_package_json_is_parsed_into_metadata() {
+    let archive = build_test_archive(&[
+        ("package/package.json", PACKAGE_JSON),
+        ("package/binding.gyp", "{}"),
+        ("package/node_modules/bindings/package.json", "{}"),
+    ]);
+
+    let metadata = parse_npm_metadata(
+        archive.path(),
+        "@acme/native-hash",
+        "2.1.0",
+        2048,
+        &"cd".repeat(32),
+    )
+    .expect("parse tarball")
+    .expect("metadata present");
+
+    assert_eq!(metadata.ecosystem, Ecosystem::Npm);
+    assert_eq!(metadata.license.as_deref(), Some("MIT"));
+    assert_eq!(metadata.authors, ["Acme <dev@acme.test>"]);
+    assert_eq!(
+        metadata.repository.as_deref(),
+        Some("git+https://github.com/acme/native-hash.git")
+    );
+    assert_eq!(metadata.attributes["maintainers"][0], "ops <ops@acme.test>");
+    assert_eq!(metadata.attributes["bin"][0], "native-hash");
+    assert_eq!(
+        metadata.attributes["lifecycle_scripts"]["postinstall"],
+        "node scripts/telemetry.js"
+    );
+    assert!(metadata.attributes["lifecycle_scripts"]["test"].is_null());
+    assert_eq!(metadata.attributes["install_scripts"], true);
+    assert_eq!(metadata.attributes["native_addon"], true);
+    assert_eq!(metadata.attributes["bundled_dependencies"][0], "bindings");
+
+    let deps: Vec<_> = metadata
+        .dependencies
+        .iter()
+        .map(|dep| (dep.name.as_str(), dep.kind.as_str(), dep.optional))
+        .collect();
+    assert_eq!(
+        deps,
+        [
+            ("bindings", "runtime", false),
+            ("nan", "runtime", false),
+            ("jest", "dev", false),
+            ("react", "peer", true),
+            ("fsevents", "optional", true),
+        ]
+    );
+
+    let sbom = metadata.sbom.expect("sbom generated");
+    let properties = sbom["metadata"]["component"]["properties"]
+        .as_array()
+        .expect("properties");
+    assert!(properties.iter().any(|p| {
+        p["name"] == "vein:lifecycle-scripts:postinstall"
+            && p["value"] == "node scripts/telemetry.js"
+    }));
+    assert!(
+        properties
+            .iter()
+            .any(|p| p["name"] == "vein:native-addon" && p["value"] == "true")
+    );
+}
+
+#[test]
+fn npm_package_without_scripts_is_not_flagged() {
+    let archive = build_test_archive(&[(
+        "package/package.json",
+        r#"{ "name": "left-pad", "version": "1.3.0", "licenses": [{ "type": "WTFPL" }] }"#,
+    )]);
+
+    let metadata = parse_npm_metadata(archive.path(), "left-pad", "1.3.0", 1, &"ef".repeat(32))
+        .expect("parse tarball")
+        .expect("metadata present");
+
+    assert_eq!(metadata.license.as_deref(), Some("WTFPL"));
+    assert_eq!(metadata.attributes["install_scripts"], false);
+    assert_eq!(metadata.attributes["native_addon"], false);
+    assert!(metadata.dependencies.is_empty());
+}
@@ -0,0 +1,645 @@
+//! Package allowlist/denylist rules.
+//!
+//! Rules from `[policy]` come first, followed by the ones created in the
+//! admin UI; the first rule matching a package version decides what happens
+//! to it. Downloads are checked before they reach the upstream, and versions
+//! a rule refuses are hidden from the compact index, the crates sparse index
+//! and npm packuments.
+
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result, anyhow, bail};
+use parking_lot::RwLock;
+use rama::telemetry::tracing::{info, warn};
+use serde::Deserialize;
+use serde_json::{Value as JsonValue, json};
+use vein_adapter::{CacheBackend, CacheBackendTrait, Ecosystem, GemMetadata, StoredPolicyRule};
+
+use crate::config::{PolicyAction, PolicyConfig, PolicyRule};
+use crate::gem_requirement::Requirement;
+use crate::util::glob_match;
+
+/// Platform of gems built for any platform.
+const RUBY_PLATFORM: &str = "ruby";
+
+/// A version requirement in one ecosystem's syntax.
+#[derive(Debug, Clone)]
+enum VersionRange {
+    Gem(Requirement),
+    /// Alternatives separated by `||`, as npm writes them.
+    Semver(Vec<semver::VersionReq>),
+}
+
+impl VersionRange {
+    fn parse(ecosystem: Ecosystem, input: &str) -> Option<Self> {
+        match ecosystem {
+            Ecosystem::RubyGems => Requirement::parse(input).map(Self::Gem),
+            Ecosystem::CratesIo | Ecosystem::Npm => input
+                .split("||")
+                .map(|alternative| semver::VersionReq::parse(alternative.trim()).ok())
+                .collect::<Option<Vec<_>>>()
+                .map(Self::Semver),
+        }
+    }
+
+    fn matches(&self, version: &str) -> bool {
+        match self {
+            Self::Gem(requirement) => requirement.matches(version),
+            Self::Semver(alternatives) => semver::Version::parse(version)
+                .is_ok_and(|version| alternatives.iter().any(|req| req.matches(&version))),
+        }
+    }
+}
+
+/// The package version a rule is evaluated against.
+#[derive(Debug, Clone, Copy)]
+pub struct Subject<'a> {
+    pub ecosystem: Ecosystem,
+    pub name: &'a str,
+    pub version: &'a str,
+    pub platform: Option<&'a str>,
+    /// Extracted metadata attributes; `None` when not cached yet.
+    pub attributes: Option<&'a JsonValue>,
+}
+
+fn applies_to(rule: &PolicyRule, ecosystem: Ecosystem, name: &str) -> bool {
+    (rule.ecosystems.is_empty() || rule.ecosystems.contains(&ecosystem))
+        && (rule.packages.is_empty()
+            || rule
+                .packages
+                .iter()
+                .any(|pattern| glob_match(pattern, name)))
+}
+
+fn matches_version(rule: &PolicyRule, ecosystem: Ecosystem, version: &str) -> bool {
+    match &rule.versions {
+        None => true,
+        Some(range) => VersionRange::parse(ecosystem, range).is_some_and(|r| r.matches(version)),
+    }
+}
+
+fn matches_platform(rule: &PolicyRule, ecosystem: Ecosystem, platform: Option<&str>) -> bool {
+    if rule.platforms.is_empty() {
+        return true;
+    }
+    let platform = match (ecosystem, platform) {
+        (_, Some(platform)) => platform,
+        (Ecosystem::RubyGems, None) => RUBY_PLATFORM,
+        _ => return false,
+    };
+    rule.platforms
+        .iter()
+        .any(|pattern| glob_match(pattern, platform))
+}
+
+fn matches_attributes(rule: &PolicyRule, attributes: Option<&JsonValue>) -> bool {
+    if rule.attributes.is_empty() {
+        return true;
+    }
+    let Some(attributes) = attributes else {
+        return false;
+    };
+    rule.attributes
+        .iter()
+        .all(|(key, expected)| attributes.get(key) == Some(expected))
+}
+
+/// Whether `rule` matches every property of `subject`.
+pub fn matches(rule: &PolicyRule, subject: &Subject<'_>) -> bool {
+    applies_to(rule, subject.ecosystem, subject.name)
+        && matches_version(rule, subject.ecosystem, subject.version)
+        && matches_platform(rule, subject.ecosystem, subject.platform)
+        && matches_attributes(rule, subject.attributes)
+}
+
+/// Checks rule ids are unique and version requirements parse in the syntax of
+/// every ecosystem the rule applies to.
+pub fn validate_rules<'a>(rules: impl IntoIterator<Item = &'a PolicyRule>) -> Result<()> {
+    let mut ids = HashSet::new();
+    for rule in rules {
+        validate_rule(rule)?;
+        if !ids.insert(rule.id.as_str()) {
+            bail!("duplicate policy rule id '{}'", rule.id);
+        }
+    }
+    Ok(())
+}
+
+/// Checks one rule has an id and a usable version requirement.
+pub fn validate_rule(rule: &PolicyRule) -> Result<()> {
+    if rule.id.trim().is_empty() {
+        bail!("policy rules need an id");
+    }
+    let Some(range) = &rule.versions else {
+        return Ok(());
+    };
+    let ecosystems = if rule.ecosystems.is_empty() {
+        &[Ecosystem::RubyGems, Ecosystem::CratesIo, Ecosystem::Npm][..]
+    } else {
+        &rule.ecosystems[..]
+    };
+    let parsed: Vec<bool> = ecosystems
+        .iter()
+        .map(|ecosystem| VersionRange::parse(*ecosystem, range).is_some())
+        .collect();
+    // Rules for every ecosystem only need one syntax to make sense
+    let valid = if rule.ecosystems.is_empty() {
+        parsed.iter().any(|ok| *ok)
+    } else {
+        parsed.iter().all(|ok| *ok)
+    };
+    if !valid {
+        bail!(
+            "policy rule '{}' has an invalid version requirement '{range}'",
+            rule.id
+        );
+    }
+    Ok(())
+}
+
+/// Converts a rule created in the admin UI.
+pub fn rule_from_stored(stored: &StoredPolicyRule) -> Result<PolicyRule> {
+    let action = stored
+        .action
+        .parse::<PolicyAction>()
+        .map_err(|err| anyhow!(err))?;
+    let attributes = match &stored.attributes {
+        JsonValue::Object(map) => map.clone().into_iter().collect(),
+        JsonValue::Null => BTreeMap::new(),
+        other => bail!("policy rule attributes must be an object, got {other}"),
+    };
+    let rule = PolicyRule {
+        id: stored.id.clone(),
+        ecosystems: stored.ecosystem.into_iter().collect(),
+        packages: vec![stored.package.clone()],
+        versions: stored.versions.clone(),
+        platforms: stored.platform.iter().cloned().collect(),
+        attributes,
+        action,
+        reason: stored.reason.clone(),
+    };
+    validate_rule(&rule)?;
+    Ok(rule)
+}
+
+/// Configured rules followed by the stored ones.
+#[derive(Debug, Clone)]
+pub struct RuleSet<'a> {
+    configured: &'a [PolicyRule],
+    stored: Arc<Vec<PolicyRule>>,
+}
+
+impl<'a> RuleSet<'a> {
+    pub fn new(configured: &'a [PolicyRule], stored: Arc<Vec<PolicyRule>>) -> Self {
+        Self { configured, stored }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &PolicyRule> {
+        self.configured.iter().chain(self.stored.iter())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.configured.is_empty() && self.stored.is_empty()
+    }
+
+    /// Whether any rule needs extracted metadata to match.
+    pub fn has_attribute_rules(&self) -> bool {
+        self.iter().any(|rule| !rule.attributes.is_empty())
+    }
+
+    /// The first rule matching `subject`.
+    pub fn decide(&self, subject: &Subject<'_>) -> Option<&PolicyRule> {
+        self.iter().find(|rule| matches(rule, subject))
+    }
+}
+
+/// Caches the rules stored in the database between refreshes.
+#[derive(Default)]
+pub struct PolicyEngine {
+    stored: RwLock<Option<(Instant, Arc<Vec<PolicyRule>>)>>,
+}
+
+impl PolicyEngine {
+    /// Rules to evaluate; stored rules are reloaded every `refresh_seconds`.
+    pub async fn rules<'a>(
+        &self,
+        config: &'a PolicyConfig,
+        index: &CacheBackend,
+    ) -> Result<RuleSet<'a>> {
+        let max_age = Duration::from_secs(config.refresh_seconds);
+        if let Some((loaded_at, stored)) = self.stored.read().as_ref()
+            && loaded_at.elapsed() < max_age
+        {
+            return Ok(RuleSet::new(&config.rules, stored.clone()));
+        }
+
+        let stored = Arc::new(load_stored_rules(config, index).await?);
+        *self.stored.write() = Some((Instant::now(), stored.clone()));
+        Ok(RuleSet::new(&config.rules, stored))
+    }
+}
+
+/// Stored rules that are valid and do not reuse a configured rule's id.
+async fn load_stored_rules(config: &PolicyConfig, index: &CacheBackend) -> Result<Vec<PolicyRule>> {
+    let configured: HashSet<&str> = config.rules.iter().map(|rule| rule.id.as_str()).collect();
+    let stored = index
+        .policy_rules()
+        .await
+        .context("loading stored policy rules")?;
+
+    let mut rules = Vec::with_capacity(stored.len());
+    for stored in &stored {
+        if configured.contains(stored.id.as_str()) {
+            warn!(rule = %stored.id, "stored policy rule shadowed by a configured rule with the same id");
+            continue;
+        }
+        match rule_from_stored(stored) {
+            Ok(rule) => rules.push(rule),
+            Err(err) => {
+                warn!(rule = %stored.id, error = %err, "skipping invalid stored policy rule");
+            }
+        }
+    }
+    Ok(rules)
+}
+
+/// Attributes rules can match on for a gem.
+pub fn gem_attributes(metadata: &GemMetadata) -> JsonValue {
+    json!({
+        "has_native_extensions": metadata.has_native_extensions,
+        "has_embedded_binaries": metadata.has_embedded_binaries,
+        "has_executables": !metadata.executables.is_empty(),
+        "native_languages": metadata.native_languages,
+    })
+}
+
+/// Metadata attributes of a cached package version, or `None` when its
+/// metadata has not been extracted.
+pub async fn package_attributes(
+    index: &CacheBackend,
+    ecosystem: Ecosystem,
+    name: &str,
+    version: &str,
+    platform: Option<&str>,
+) -> Result<Option<JsonValue>> {
+    if ecosystem == Ecosystem::RubyGems {
+        let metadata = index
+            .gem_metadata(name, version, Some(platform.unwrap_or(RUBY_PLATFORM)))
+            .await
+            .context("loading gem metadata for policy rules")?;
+        Ok(metadata.as_ref().map(gem_attributes))
+    } else {
+        let metadata = index
+            .package_metadata(ecosystem, name, version)
+            .await
+            .context("loading package metadata for policy rules")?;
+        Ok(metadata.map(|metadata| metadata.attributes))
+    }
+}
+
+/// Logs the decision `rule` made about `subject`.
+pub fn log_decision(rule: &PolicyRule, subject: &Subject<'_>) {
+    info!(
+        rule = %rule.id,
+        action = rule.action.as_str(),
+        ecosystem = %subject.ecosystem,
+        package = %subject.name,
+        version = %subject.version,
+        platform = subject.platform.unwrap_or_default(),
+        "policy decision"
+    );
+}
+
+/// Hides versions refused by rules from index responses of one ecosystem.
+///
+/// Attribute rules are skipped: metadata of versions that were never
+/// downloaded is unknown.
+#[derive(Debug, Clone)]
+pub struct IndexFilter {
+    ecosystem: Ecosystem,
+    rules: Vec<PolicyRule>,
+    /// Whether quarantine actions hold versions instead of denying them.
+    holdable: bool,
+}
+
+#[derive(Deserialize)]
+struct SparseIndexLine {
+    vers: String,
+}
+
+impl IndexFilter {
+    /// `None` when no rule can hide a version of `ecosystem`.
+    pub fn new(rules: &RuleSet<'_>, ecosystem: Ecosystem, holdable: bool) -> Option<Self> {
+        let rules: Vec<PolicyRule> = rules
+            .iter()
+            .filter(|rule| rule.attributes.is_empty())
+            .filter(|rule| rule.ecosystems.is_empty() || rule.ecosystems.contains(&ecosystem))
+            .cloned()
+            .collect();
+        let filter = Self {
+            ecosystem,
+            rules,
+            holdable,
+        };
+        filter
+            .rules
+            .iter()
+            .any(|rule| filter.hides(rule.action))
+            .then_some(filter)
+    }
+
+    fn hides(&self, action: PolicyAction) -> bool {
+        match action {
+            PolicyAction::Allow => false,
+            PolicyAction::Deny => true,
+            // Held versions are hidden by the quarantine filter; versions
+            // never requested stay listed so a download can create the hold.
+            PolicyAction::Quarantine | PolicyAction::RequireApproval => !self.holdable,
+        }
+    }
+
+    /// The id of the rule hiding `version`, if any.
+    pub fn hidden_by(&self, name: &str, version: &str, platform: Option<&str>) -> Option<&str> {
+        let subject = Subject {
+            ecosystem: self.ecosystem,
+            name,
+            version,
+            platform,
+            attributes: None,
+        };
+        self.rules
+            .iter()
+            .find(|rule| matches(rule, &subject))
+            .filter(|rule| self.hides(rule.action))
+            .map(|rule| rule.id.as_str())
+    }
+
+    /// Versions among `versions` that are hidden, with the hiding rule.
+    pub fn hidden_versions<'v>(
+        &self,
+        name: &str,
+        versions: impl IntoIterator<Item = &'v str>,
+    ) -> Vec<(&'v str, &str)> {
+        versions
+            .into_iter()
+            .filter_map(|version| {
+                self.hidden_by(name, version, None)
+                    .map(|rule| (version, rule))
+            })
+            .collect()
+    }
+
+    /// Drops hidden versions from a compact index `/info/{gem}` body.
+    pub fn filter_compact_info(&self, name: &str, body: &[u8]) -> Vec<u8> {
+        let Ok(text) = std::str::from_utf8(body) else {
+            return body.to_vec();
+        };
+        let mut hidden = Vec::new();
+        let mut output = String::with_capacity(text.len());
+        for line in text.lines() {
+            let key = line.split(' ').next().unwrap_or_default();
+            if line != "---" && !key.is_empty() {
+                let (version, platform) = match key.split_once('-') {
+                    Some((version, platform)) => (version, Some(platform)),
+                    None => (key, None),
+                };
+                if let Some(rule) = self.hidden_by(name, version, platform) {
+                    hidden.push((key, rule));
+                    continue;
+                }
+            }
+            output.push_str(line);
+            output.push('\n');
+        }
+        self.log_hidden(name, &hidden);
+        output.into_bytes()
+    }
+
+    /// Drops hidden versions from a crates sparse index file.
+    pub fn filter_sparse_index(&self, name: &str, body: &[u8]) -> Vec<u8> {
+        let mut hidden = Vec::new();
+        let mut output = Vec::with_capacity(body.len());
+        for line in body.split(|byte| *byte == b'\n') {
+            if line.is_empty() {
+                continue;
+            }
+            if let Ok(entry) = serde_json::from_slice::<SparseIndexLine>(line)
+                && let Some(rule) = self.hidden_by(name, &entry.vers, None)
+            {
+                hidden.push((entry.vers, rule));
+                continue;
+            }
+            output.extend_from_slice(line);
+            output.push(b'\n');
+        }
+        let hidden: Vec<(&str, &str)> = hidden
+            .iter()
+            .map(|(version, rule)| (version.as_str(), *rule))
+            .collect();
+        self.log_hidden(name, &hidden);
+        output
+    }
+
+    /// Logs one line per rule that hid versions of `name`.
+    pub fn log_hidden(&self, name: &str, hidden: &[(&str, &str)]) {
+        let mut by_rule: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for (version, rule) in hidden {
+            by_rule.entry(rule).or_default().push(version);
+        }
+        for (rule, versions) in by_rule {
+            info!(
+                rule = %rule,
+                ecosystem = %self.ecosystem,
+                package = %name,
+                versions = %versions.join(", "),
+                "policy hid versions from index"
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(id: &str, action: PolicyAction) -> PolicyRule {
+        PolicyRule {
+            id: id.to_string(),
+            ecosystems: Vec::new(),
+            packages: Vec::new(),
+            versions: None,
+            platforms: Vec::new(),
+            attributes: BTreeMap::new(),
+            action,
+            reason: None,
+        }
+    }
+
+    fn gem<'a>(name: &'a str, version: &'a str, platform: Option<&'a str>) -> Subject<'a> {
+        Subject {
+            ecosystem: Ecosystem::RubyGems,
+            name,
+            version,
+            platform,
+            attributes: None,
+        }
+    }
+
+    #[test]
+    fn matches_versions_in_each_ecosystem_syntax() {
+        let old_rails = PolicyRule {
+            packages: vec!["rails".to_string()],
+            versions: Some("~> 6.0".to_string()),
+            ..rule("old-rails", PolicyAction::Deny)
+        };
+        assert!(matches(&old_rails, &gem("rails", "6.1.7", None)));
+        assert!(!matches(&old_rails, &gem("rails", "7.0.0", None)));
+        assert!(!matches(&old_rails, &gem("railties", "6.1.7", None)));
+
+        let lodash = PolicyRule {
+            ecosystems: vec![Ecosystem::Npm],
+            packages: vec!["lodash".to_string()],
+            versions: Some("<4.17.21 || =5.0.0".to_string()),
+            ..rule("lodash", PolicyAction::Deny)
+        };
+        let npm = |version| Subject {
+            ecosystem: Ecosystem::Npm,
+            name: "lodash",
+            version,
+            platform: None,
+            attributes: None,
+        };
+        assert!(matches(&lodash, &npm("4.17.20")));
+        assert!(matches(&lodash, &npm("5.0.0")));
+        assert!(!matches(&lodash, &npm("4.17.21")));
+        assert!(!matches(&lodash, &gem("lodash", "4.17.20", None)));
+    }
+
+    #[test]
+    fn matches_platforms_and_attributes() {
+        let native = PolicyRule {
+            platforms: vec!["x86_64-*".to_string()],
+            attributes: BTreeMap::from([("has_native_extensions".to_string(), json!(true))]),
+            ..rule("native", PolicyAction::RequireApproval)
+        };
+        let with_attributes = |platform, attributes| Subject {
+            attributes: Some(attributes),
+            ..gem("nokogiri", "1.16.0", platform)
+        };
+
+        let native_attributes = json!({ "has_native_extensions": true });
+        let pure_attributes = json!({ "has_native_extensions": false });
+        assert!(matches(
+            &native,
+            &with_attributes(Some("x86_64-linux"), &native_attributes)
+        ));
+        assert!(!matches(
+            &native,
+            &with_attributes(Some("x86_64-linux"), &pure_attributes)
+        ));
+        assert!(!matches(
+            &native,
+            &with_attributes(None, &native_attributes)
+        ));
+        // Unknown metadata never matches an attribute rule
+        assert!(!matches(
+            &native,
+            &gem("nokogiri", "1.16.0", Some("x86_64-linux"))
+        ));
+
+        let ruby_only = PolicyRule {
+            platforms: vec!["ruby".to_string()],
+            ..rule("ruby-only", PolicyAction::Allow)
+        };
+        assert!(matches(&ruby_only, &gem("rack", "3.0.0", None)));
+    }
+
+    #[test]
+    fn first_matching_rule_decides() {
+        let configured = vec![PolicyRule {
+            packages: vec!["acme-*".to_string()],
+            ..rule("internal", PolicyAction::Allow)
+        }];
+        let stored = Arc::new(vec![rule("deny-all", PolicyAction::Deny)]);
+        let rules = RuleSet::new(&configured, stored);
+
+        let decided = |name| {
+            rules
+                .decide(&gem(name, "1.0.0", None))
+                .map(|r| r.id.as_str())
+        };
+        assert_eq!(decided("acme-billing"), Some("internal"));
+        assert_eq!(decided("rack"), Some("deny-all"));
+    }
+
+    #[test]
+    fn validates_ids_and_version_requirements() {
+        let gem_only = PolicyRule {
+            ecosystems: vec![Ecosystem::RubyGems],
+            versions: Some("~> 1.0".to_string()),
+            ..rule("gem-only", PolicyAction::Deny)
+        };
+        assert!(validate_rules([&gem_only]).is_ok());
+
+        let semver_for_gems = PolicyRule {
+            ecosystems: vec![Ecosystem::RubyGems, Ecosystem::Npm],
+            versions: Some("^1.0".to_string()),
+            ..gem_only.clone()
+        };
+        assert!(validate_rules([&semver_for_gems]).is_err());
+
+        assert!(validate_rules([&gem_only, &gem_only]).is_err());
+        assert!(validate_rules([&rule(" ", PolicyAction::Deny)]).is_err());
+    }
+
+    #[test]
+    fn index_filter_hides_refused_versions() {
+        let configured = vec![
+            PolicyRule {
+                packages: vec!["rack".to_string()],
+                versions: Some("= 3.0.1".to_string()),
+                ..rule("rack-ok", PolicyAction::Allow)
+            },
+            PolicyRule {
+                packages: vec!["rack".to_string()],
+                versions: Some("< 3.1".to_string()),
+                ..rule("old-rack", PolicyAction::Deny)
+            },
+            PolicyRule {
+                packages: vec!["rack".to_string()],
+                platforms: vec!["java".to_string()],
+                ..rule("no-java", PolicyAction::RequireApproval)
+            },
+        ];
+        let rules = RuleSet::new(&configured, Arc::new(Vec::new()));
+        let body = b"---\n3.0.0 |checksum:a\n3.0.1 |checksum:b\n3.1.0 |checksum:c\n3.1.0-java |checksum:d\n";
+
+        let filter = IndexFilter::new(&rules, Ecosystem::RubyGems, true).unwrap();
+        assert_eq!(
+            String::from_utf8(filter.filter_compact_info("rack", body)).unwrap(),
+            "---\n3.0.1 |checksum:b\n3.1.0 |checksum:c\n3.1.0-java |checksum:d\n"
+        );
+
+        // Without quarantine, approval rules deny
+        let filter = IndexFilter::new(&rules, Ecosystem::RubyGems, false).unwrap();
+        assert_eq!(
+            String::from_utf8(filter.filter_compact_info("rack", body)).unwrap(),
+            "---\n3.0.1 |checksum:b\n3.1.0 |checksum:c\n"
+        );
+
+        let sparse =
+            b"{\"name\":\"rack\",\"vers\":\"3.0.0\"}\n{\"name\":\"rack\",\"vers\":\"3.1.0\"}\n";
+        let crates = RuleSet::new(&configured, Arc::new(Vec::new()));
+        let filter = IndexFilter::new(&crates, Ecosystem::CratesIo, false).unwrap();
+        assert_eq!(
+            filter.filter_sparse_index("rack", sparse),
+            b"{\"name\":\"rack\",\"vers\":\"3.1.0\"}\n"
+        );
+
+        let allow_only = vec![rule("allow", PolicyAction::Allow)];
+        let rules = RuleSet::new(&allow_only, Arc::new(Vec::new()));
+        assert!(IndexFilter::new(&rules, Ecosystem::Npm, false).is_none());
+    }
+}
@@ -3,7 +3,10 @@ mod compact;
 mod dispatch;
 mod fetch;
 mod handlers;
+mod policy;
+pub mod prefetch;
 mod quarantine;
+mod reload;
 mod response;
 #[cfg(test)]
 mod tests;
@@ -13,21 +16,34 @@ mod utils;
 use std::sync::Arc;
 
 use anyhow::{Context, Result};
+use parking_lot::RwLock;
 use rama::telemetry::tracing::info;
 
-use crate::{config::Config, upstream::UpstreamClient};
+use crate::config::Config;
+use crate::policy::PolicyEngine;
+use crate::typosquat::TyposquatGuard;
 use vein_adapter::{CacheBackend, FilesystemStorage};
 
 pub use types::{CacheStatus, RequestContext, UpstreamTarget};
 
 /// Main proxy service.
+///
+/// `config` and `upstreams` are the snapshot a request runs against; `live`
+/// holds the current one, replaced on reload.
 #[derive(Clone)]
 pub struct VeinProxy {
     config: Arc<Config>,
     storage: Arc<FilesystemStorage>,
     index: Arc<CacheBackend>,
-    upstreams: Vec<UpstreamTarget>,
-    upstream_client: Option<UpstreamClient>,
+    upstreams: Arc<Vec<UpstreamTarget>>,
+    typosquat: Arc<TyposquatGuard>,
+    policy: Arc<PolicyEngine>,
+    live: Arc<RwLock<LiveState>>,
+}
+
+struct LiveState {
+    config: Arc<Config>,
+    upstreams: Arc<Vec<UpstreamTarget>>,
 }
 
 impl VeinProxy {
@@ -36,26 +52,49 @@ impl VeinProxy {
         storage: Arc<FilesystemStorage>,
         index: Arc<CacheBackend>,
     ) -> Result<Self> {
-        let (upstreams, upstream_client) = if let Some(ref upstream_config) = config.upstream {
-            let mut upstreams = Vec::new();
-            upstreams.push(UpstreamTarget::from_url(&upstream_config.url)?);
-            for url in &upstream_config.fallback_urls {
-                upstreams.push(UpstreamTarget::from_url(url)?);
-            }
-            let client =
-                UpstreamClient::new(upstream_config).context("building upstream client")?;
-            (upstreams, Some(client))
-        } else {
+        let upstreams = Arc::new(build_upstreams(&config)?);
+        if upstreams.is_empty() {
             info!("No upstream configured - running in cache-only mode");
-            (Vec::new(), None)
-        };
+        }
 
         Ok(Self {
+            live: Arc::new(RwLock::new(LiveState {
+                config: config.clone(),
+                upstreams: upstreams.clone(),
+            })),
             config,
             storage,
             index,
             upstreams,
-            upstream_client,
+            typosquat: Arc::new(TyposquatGuard::default()),
+            policy: Arc::new(PolicyEngine::default()),
         })
     }
+
+    /// The proxy as of the latest reload.
+    fn current(&self) -> Self {
+        let live = self.live.read();
+        Self {
+            config: live.config.clone(),
+            upstreams: live.upstreams.clone(),
+            storage: self.storage.clone(),
+            index: self.index.clone(),
+            typosquat: self.typosquat.clone(),
+            policy: self.policy.clone(),
+            live: self.live.clone(),
+        }
+    }
+}
+
+fn build_upstreams(config: &Config) -> Result<Vec<UpstreamTarget>> {
+    let Some(upstream_config) = &config.upstream else {
+        return Ok(Vec::new());
+    };
+    std::iter::once(&upstream_config.url)
+        .chain(&upstream_config.fallback_urls)
+        .map(|url| {
+            UpstreamTarget::new(upstream_config, url)
+                .with_context(|| format!("building upstream client for {url}"))
+        })
+        .collect()
 }
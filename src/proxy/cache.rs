@@ -1,27 +1,390 @@
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock, Mutex, PoisonError};
+use std::time::SystemTime;
 
-use anyhow::{Context, Result, anyhow};
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::Utc;
 use rama::http::{Body, Response, StatusCode, body::util::BodyExt, header};
 use rama::telemetry::tracing::{debug, warn};
 use sha2::{Digest, Sha256};
 use tokio::io::AsyncWriteExt;
 use vein_adapter::{
-    AssetKind, CacheBackend, CacheBackendTrait, CachedAsset, FilesystemStorage, TempFile,
+    AssetKind, CacheBackend, CacheBackendTrait, CachedAsset, Ecosystem, FilesystemStorage,
+    SuspiciousName, TempFile,
 };
 
-use super::types::CacheableRequest;
+use super::{quarantine, response, types::CacheableRequest};
+use crate::advisories;
+use crate::config::{
+    AdvisoriesConfig, Config, LicenseAction, NotificationKind, TyposquatAction, YankSyncConfig,
+};
+use crate::license::{self, LicenseVerdict};
+use crate::notifications::{self, Notification};
+use crate::sbom::SBOM_GENERATOR;
+use crate::typosquat::TyposquatGuard;
+
+/// Header carrying the reason a served package breaks the license policy.
+const LICENSE_WARNING_HEADER: &str = "x-vein-license-warning";
+/// Verified files remembered before the record starts over.
+const MAX_VERIFIED: usize = 50_000;
+
+/// Size and modification time of a cached file when it was last verified.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileStamp {
+    len: u64,
+    modified: SystemTime,
+}
+
+/// Cached files whose checksum already matched, keyed by file path, so
+/// hits on unchanged files skip re-hashing.
+static VERIFIED: LazyLock<Mutex<HashMap<PathBuf, (String, FileStamp)>>> =
+    LazyLock::new(Default::default);
+
+fn remember_verified(path: PathBuf, sha256: String, stamp: FileStamp) {
+    let mut verified = VERIFIED.lock().unwrap_or_else(PoisonError::into_inner);
+    if verified.len() >= MAX_VERIFIED && !verified.contains_key(&path) {
+        verified.clear();
+    }
+    verified.insert(path, (sha256, stamp));
+}
+
+async fn file_stamp(path: &Path) -> Option<FileStamp> {
+    let metadata = tokio::fs::metadata(path).await.ok()?;
+    Some(FileStamp {
+        len: metadata.len(),
+        modified: metadata.modified().ok()?,
+    })
+}
+
+fn already_verified(path: &Path, sha256: &str, stamp: Option<FileStamp>) -> bool {
+    let Some(stamp) = stamp else {
+        return false;
+    };
+    VERIFIED
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get(path)
+        .is_some_and(|(verified_sha256, verified)| verified_sha256 == sha256 && *verified == stamp)
+}
+
+/// Returns `410 Gone` for artifacts upstream has yanked when
+/// `yank_sync.block_downloads` is enabled.
+pub(crate) async fn yanked_response(
+    config: &YankSyncConfig,
+    index: &CacheBackend,
+    cacheable: &CacheableRequest,
+) -> Result<Option<Response<Body>>> {
+    if !config.block_downloads {
+        return Ok(None);
+    }
+    let Some(yank) = index.get_upstream_yank(&cacheable.asset_key()).await? else {
+        return Ok(None);
+    };
+
+    warn!(
+        ecosystem = %yank.ecosystem,
+        package = %cacheable.name,
+        version = %cacheable.version,
+        reason = %yank.reason,
+        "blocked download of upstream-yanked version"
+    );
+    notifications::emit(Notification::new(
+        NotificationKind::PolicyFailed,
+        yank.ecosystem,
+        &cacheable.name,
+        &cacheable.version,
+        cacheable.platform.as_deref(),
+        Some(&format!("upstream yank: {}", yank.reason)),
+    ));
+    let body = format!(
+        "{} {} is no longer offered upstream ({})\n",
+        cacheable.name, cacheable.version, yank.reason
+    );
+    let response = Response::builder()
+        .status(StatusCode::GONE)
+        .header(
+            header::CONTENT_TYPE,
+            header::HeaderValue::from_static("text/plain; charset=utf-8"),
+        )
+        .body(Body::from(body))?;
+    Ok(Some(response))
+}
+
+/// Returns `403 Forbidden` for versions with an advisory at or above
+/// `advisories.block_severity`.
+pub(crate) async fn advisory_response(
+    config: &AdvisoriesConfig,
+    index: &CacheBackend,
+    cacheable: &CacheableRequest,
+) -> Result<Option<Response<Body>>> {
+    let ecosystem = cacheable.kind.ecosystem();
+    let Some(advisory) = advisories::blocking_advisory(
+        config,
+        index,
+        ecosystem,
+        &cacheable.name,
+        &cacheable.version,
+    )
+    .await?
+    else {
+        return Ok(None);
+    };
+
+    warn!(
+        %ecosystem,
+        package = %cacheable.name,
+        version = %cacheable.version,
+        advisory = %advisory.id,
+        severity = %advisory.severity,
+        "blocked download of version with security advisory"
+    );
+    notifications::emit(Notification::new(
+        NotificationKind::PolicyFailed,
+        ecosystem,
+        &cacheable.name,
+        &cacheable.version,
+        cacheable.platform.as_deref(),
+        Some(&format!("{} advisory {}", advisory.severity, advisory.id)),
+    ));
+    let body = format!(
+        "{} {} is blocked by {} advisory {}{}\n",
+        cacheable.name,
+        cacheable.version,
+        advisory.severity,
+        advisory.id,
+        advisory
+            .summary
+            .as_deref()
+            .map(|summary| format!(": {summary}"))
+            .unwrap_or_default()
+    );
+    let response = Response::builder()
+        .status(StatusCode::FORBIDDEN)
+        .header(
+            header::CONTENT_TYPE,
+            header::HeaderValue::from_static("text/plain; charset=utf-8"),
+        )
+        .body(Body::from(body))?;
+    Ok(Some(response))
+}
+
+/// Records requests for never-before-seen names that look like typosquats
+/// or dependency confusion, and applies `typosquat.action` to them.
+///
+/// `quarantine` holds new gem versions for manual approval; crates, npm
+/// packages and gems without an enabled `[delay_policy]` are blocked instead.
+pub(crate) async fn typosquat_response(
+    config: &Config,
+    guard: &TyposquatGuard,
+    index: &CacheBackend,
+    cacheable: &CacheableRequest,
+) -> Result<Option<Response<Body>>> {
+    let Some(found) = guard
+        .check(&config.typosquat, index, cacheable.kind, &cacheable.name)
+        .await?
+    else {
+        return Ok(None);
+    };
+    let ecosystem = cacheable.kind.ecosystem();
+    let action = config.typosquat.action;
+    let reason = found.describe();
+
+    warn!(
+        %ecosystem,
+        package = %cacheable.name,
+        version = %cacheable.version,
+        technique = found.technique.as_str(),
+        similar_to = %found.similar_to,
+        action = action.as_str(),
+        "suspicious package name requested"
+    );
+    let now = Utc::now();
+    let record = SuspiciousName {
+        ecosystem,
+        name: cacheable.name.clone(),
+        technique: found.technique.as_str().to_string(),
+        similar_to: Some(found.similar_to.clone()),
+        action: action.as_str().to_string(),
+        hits: 1,
+        first_seen: now,
+        last_seen: now,
+    };
+    if let Err(err) = index.record_suspicious_name(&record).await {
+        warn!(error = %err, package = %cacheable.name, "failed to record suspicious name");
+    }
+
+    match action {
+        TyposquatAction::Warn => Ok(None),
+        TyposquatAction::Quarantine
+            if ecosystem == Ecosystem::RubyGems && config.delay_policy.enabled =>
+        {
+            quarantine::hold_for_approval(
+                &config.delay_policy,
+                index,
+                &cacheable.name,
+                &cacheable.version,
+                cacheable.platform.as_deref(),
+                &format!("suspicious name: {reason}"),
+            )
+            .await?;
+            Ok(None)
+        }
+        TyposquatAction::Block | TyposquatAction::Quarantine => {
+            notifications::emit(Notification::new(
+                NotificationKind::PolicyFailed,
+                ecosystem,
+                &cacheable.name,
+                &cacheable.version,
+                cacheable.platform.as_deref(),
+                Some(&format!("suspicious name: {reason}")),
+            ));
+            let body = format!("{} is blocked: {reason}\n", cacheable.name);
+            let response = Response::builder()
+                .status(StatusCode::FORBIDDEN)
+                .header(
+                    header::CONTENT_TYPE,
+                    header::HeaderValue::from_static("text/plain; charset=utf-8"),
+                )
+                .body(Body::from(body))?;
+            Ok(Some(response))
+        }
+    }
+}
+
+/// Applies `[license_policy]` to a successful download, using the license
+/// extracted when the artifact was cached.
+///
+/// `newly_cached` downloads of gems can be held for approval; later hits of a
+/// held version are only flagged, so approving it sticks.
+pub(crate) async fn license_response(
+    config: &Config,
+    index: &CacheBackend,
+    cacheable: &CacheableRequest,
+    response: Response<Body>,
+    newly_cached: bool,
+) -> Result<Response<Body>> {
+    let policy = &config.license_policy;
+    if !policy.enabled || !response.status().is_success() {
+        return Ok(response);
+    }
+    let ecosystem = cacheable.kind.ecosystem();
+    let Some(package) = license::cached_license(
+        index,
+        ecosystem,
+        &cacheable.name,
+        &cacheable.version,
+        cacheable.platform.as_deref(),
+    )
+    .await?
+    else {
+        return Ok(response);
+    };
+    let LicenseVerdict::Violation { reason, action } = license::evaluate(
+        policy,
+        ecosystem,
+        &cacheable.name,
+        package.license.as_deref(),
+    ) else {
+        return Ok(response);
+    };
+
+    warn!(
+        %ecosystem,
+        package = %cacheable.name,
+        version = %cacheable.version,
+        license = package.license.as_deref().unwrap_or(license::NO_LICENSE),
+        reason = %reason,
+        action = action.as_str(),
+        "license policy violation"
+    );
+    let holdable = ecosystem == Ecosystem::RubyGems && config.delay_policy.enabled;
+    match action {
+        LicenseAction::Block => {}
+        LicenseAction::Quarantine if !holdable => {}
+        LicenseAction::Quarantine => {
+            if newly_cached {
+                quarantine::hold_for_approval(
+                    &config.delay_policy,
+                    index,
+                    &cacheable.name,
+                    &cacheable.version,
+                    cacheable.platform.as_deref(),
+                    &format!("license: {reason}"),
+                )
+                .await?;
+            }
+            return Ok(with_license_warning(response, &reason));
+        }
+        LicenseAction::Warn => return Ok(with_license_warning(response, &reason)),
+    }
+
+    notifications::emit(Notification::new(
+        NotificationKind::PolicyFailed,
+        ecosystem,
+        &cacheable.name,
+        &cacheable.version,
+        cacheable.platform.as_deref(),
+        Some(&format!("license: {reason}")),
+    ));
+    response::respond_text(
+        StatusCode::FORBIDDEN,
+        &format!(
+            "{} {} is blocked by license policy: {reason}\n",
+            cacheable.name, cacheable.version
+        ),
+    )
+}
+
+fn with_license_warning(mut response: Response<Body>, reason: &str) -> Response<Body> {
+    // License names are free text; keep the header valid whatever they contain
+    let value = header::HeaderValue::from_str(reason)
+        .unwrap_or_else(|_| header::HeaderValue::from_static("license policy violation"));
+    response.headers_mut().insert(LICENSE_WARNING_HEADER, value);
+    response
+}
 
 /// Serves a cached file to the client
+///
+/// The checksum is verified on the first hit and again whenever the file's
+/// size or modification time changes.
 pub async fn serve_cached(
     cacheable: &CacheableRequest,
     entry: CachedAsset,
     storage: &FilesystemStorage,
 ) -> Result<Response<Body>> {
     let path = storage.resolve(&entry.path);
+    // Stamp before reading, so a concurrent rewrite is verified on the next hit
+    let stamp = file_stamp(&path).await;
     let data = tokio::fs::read(&path)
         .await
         .with_context(|| format!("reading cached file {}", path.display()))?;
 
+    if !entry.sha256.is_empty() && !already_verified(&path, &entry.sha256, stamp) {
+        let actual = hex::encode(Sha256::digest(&data));
+        if actual != entry.sha256 {
+            VERIFIED
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .remove(&path);
+            notifications::emit(Notification::new(
+                NotificationKind::ChecksumFailed,
+                cacheable.kind.ecosystem(),
+                &cacheable.name,
+                &cacheable.version,
+                cacheable.platform.as_deref(),
+                Some(&format!("expected {}, found {actual}", entry.sha256)),
+            ));
+            bail!(
+                "cached file {} failed checksum verification",
+                path.display()
+            );
+        }
+        if let Some(stamp) = stamp {
+            remember_verified(path.clone(), actual, stamp);
+        }
+    }
+
     build_cached_response(cacheable, entry.sha256, data)
 }
 
@@ -117,8 +480,10 @@ pub async fn run_cache_miss_flow(
             )
             .await
         {
-            Ok(Some(meta)) => meta.sbom,
-            Ok(None) => None,
+            // Only reuse what the running generator wrote; `vein sbom backfill`
+            // refreshes the rest.
+            Ok(Some(meta)) if meta.sbom_generator.as_deref() == Some(SBOM_GENERATOR) => meta.sbom,
+            Ok(_) => None,
             Err(err) => {
                 warn!(
                     error = %err,
@@ -127,7 +492,7 @@ pub async fn run_cache_miss_flow(
                 None
             }
         };
-        match crate::gem_metadata::extract_gem_metadata(
+        match crate::gem_metadata::extract_gem_archive(
             &absolute_path,
             &cacheable.name,
             &cacheable.version,
@@ -138,8 +503,21 @@ pub async fn run_cache_miss_flow(
         )
         .await
         {
-            Ok(Some(metadata)) => {
-                if let Err(err) = index.upsert_metadata(&metadata).await {
+            Ok(Some(archive)) => {
+                if !archive.findings.is_empty() {
+                    warn!(
+                        gem = %cacheable.name,
+                        version = %cacheable.version,
+                        findings = archive.findings.len(),
+                        rules = ?archive
+                            .findings
+                            .iter()
+                            .map(|finding| finding.rule.as_str())
+                            .collect::<std::collections::BTreeSet<_>>(),
+                        "suspicious content in gem sources"
+                    );
+                }
+                if let Err(err) = crate::gem_metadata::store_gem_archive(&index, &archive).await {
                     warn!(
                         error = %err,
                         path = %absolute_path.display(),
@@ -160,6 +538,40 @@ pub async fn run_cache_miss_flow(
         }
     }
 
+    if matches!(cacheable.kind, AssetKind::Crate | AssetKind::NpmPackage) {
+        let absolute_path = storage.resolve(&cacheable.relative_path);
+        match crate::package_metadata::extract_package_metadata(
+            cacheable.kind.ecosystem(),
+            &absolute_path,
+            &cacheable.name,
+            &cacheable.version,
+            body_bytes.len() as u64,
+            &sha_hex,
+        )
+        .await
+        {
+            Ok(Some(metadata)) => {
+                if let Err(err) = index.upsert_package_metadata(&metadata).await {
+                    warn!(
+                        error = %err,
+                        path = %absolute_path.display(),
+                        "failed to persist package metadata"
+                    );
+                }
+            }
+            Ok(None) => {
+                debug!(path = %absolute_path.display(), "package metadata unavailable");
+            }
+            Err(err) => {
+                warn!(
+                    error = %err,
+                    path = %absolute_path.display(),
+                    "failed to analyze package metadata"
+                );
+            }
+        }
+    }
+
     // Build client response using upstream headers with our cache headers
     let mut builder = Response::builder().status(status);
     {
@@ -1,13 +1,18 @@
-use anyhow::Result;
+use std::collections::BTreeSet;
+
+use anyhow::{Context, Result};
 use percent_encoding::percent_decode_str;
 use rama::{
-    http::{Body, Request, Response},
+    extensions::ExtensionsRef as _,
+    http::{Body, Method, Request, Response},
     telemetry::tracing::warn,
 };
+use vein_adapter::Ecosystem;
 
 use crate::http_cache::{CacheOutcome, CachedTextOptions, MetaStoreMode, fetch_cached_text};
+use crate::rate_limit::{self, ClientBudget};
 
-use super::{CacheStatus, RequestContext, VeinProxy, quarantine, utils};
+use super::{CacheStatus, RequestContext, VeinProxy, policy, quarantine, utils};
 
 #[derive(Debug, Clone)]
 pub(super) enum CompactRequest {
@@ -59,6 +64,27 @@ impl CompactRequest {
 }
 
 impl VeinProxy {
+    /// Whether the cached `/info/{name}` lists no version other than `version`.
+    ///
+    /// Returns `false` when the info file has not been cached yet.
+    pub(super) async fn is_first_version(&self, name: &str, version: &str) -> bool {
+        let path = CompactRequest::Info {
+            name: name.to_string(),
+        }
+        .storage_path();
+        let Ok(body) = tokio::fs::read_to_string(self.storage.resolve(&path)).await else {
+            return false;
+        };
+
+        let versions: BTreeSet<&str> = body
+            .lines()
+            .filter(|line| *line != "---" && !line.is_empty())
+            .filter_map(|line| line.split([' ', '|']).next())
+            .map(|token| token.split('-').next().unwrap_or(token))
+            .collect();
+        !versions.is_empty() && versions.iter().all(|listed| *listed == version)
+    }
+
     pub(super) async fn handle_compact_request(
         &self,
         req: &Request<Body>,
@@ -75,10 +101,23 @@ impl VeinProxy {
 
         let delay_policy = &self.config.delay_policy;
         let index = self.index.as_ref();
+        let rule_filter = match &info_name {
+            Some(_) => {
+                policy::index_filter(&self.config, &self.policy, index, Ecosystem::RubyGems).await
+            }
+            None => None,
+        };
+        let rule_filter = rule_filter.as_ref();
+
+        // The refresh may outlive this request, so it gets its own handles.
+        let proxy = self.clone();
+        let client = ClientBudget::of(req.extensions()).cloned();
+        let upstream_req = upstream_request(req)?;
+        let index_cache = &self.config.index_cache;
 
         let result = fetch_cached_text(
             &self.storage,
-            index,
+            &self.index,
             CachedTextOptions {
                 storage_path: &storage_path,
                 meta_key: &meta_key,
@@ -87,24 +126,39 @@ impl VeinProxy {
                 include_content_length: true,
                 meta_mode: MetaStoreMode::Strict,
                 strip_transfer_encoding: true,
+                offline: self.config.offline.enabled,
+                stale_if_error: self.config.offline.stale_if_error,
+                fresh_for: index_cache.compact_index_ttl(),
+                stale_while_revalidate: index_cache.stale_while_revalidate(),
+            },
+            move |headers| {
+                rate_limit::with_upstream_slot(client, async move {
+                    proxy
+                        .fetch_with_fallback(&upstream_req, Some(&headers))
+                        .await
+                })
             },
-            |headers| async move { self.fetch_with_fallback(req, Some(&headers)).await },
             move |body| async move {
-                if let Some(name) = info_name {
-                    match quarantine::filter_compact_info(delay_policy, index, &name, &body).await {
-                        Ok(filtered) => Ok(filtered),
-                        Err(err) => {
-                            warn!(
-                                error = %err,
-                                gem = %name,
-                                "Failed to filter quarantined versions"
-                            );
-                            Ok(body)
-                        }
+                let Some(name) = info_name else {
+                    return Ok(body);
+                };
+                let body = match quarantine::filter_compact_info(delay_policy, index, &name, &body)
+                    .await
+                {
+                    Ok(filtered) => filtered,
+                    Err(err) => {
+                        warn!(
+                            error = %err,
+                            gem = %name,
+                            "Failed to filter quarantined versions"
+                        );
+                        body
                     }
-                } else {
-                    Ok(body)
-                }
+                };
+                Ok(match rule_filter {
+                    Some(filter) => filter.filter_compact_info(&name, &body),
+                    None => body,
+                })
             },
         )
         .await?;
@@ -113,9 +167,19 @@ impl VeinProxy {
             CacheOutcome::Hit => CacheStatus::Hit,
             CacheOutcome::Miss => CacheStatus::Miss,
             CacheOutcome::Revalidated => CacheStatus::Revalidated,
+            CacheOutcome::Stale => CacheStatus::Stale,
             CacheOutcome::Pass => CacheStatus::Pass,
         };
 
         Ok(Some((result.response, cache_status)))
     }
 }
+
+/// Bodyless copy of `req` that can be moved into a background refresh.
+fn upstream_request(req: &Request<Body>) -> Result<Request<Body>> {
+    Request::builder()
+        .method(Method::GET)
+        .uri(req.uri().clone())
+        .body(Body::empty())
+        .context("building upstream index request")
+}
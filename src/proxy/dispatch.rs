@@ -2,40 +2,114 @@ use anyhow::Result;
 use rama::{
     Service,
     error::BoxError,
+    extensions::ExtensionsRef as _,
     http::{Body, Method, Request, Response, StatusCode},
     telemetry::tracing::{error, info},
 };
+use vein_adapter::Ecosystem;
 
-use crate::http_cache::CacheOutcome;
+use crate::http_cache::{CacheOutcome, IndexOptions};
+use crate::rate_limit::ClientBudget;
 
-use super::{CacheStatus, RequestContext, VeinProxy, compact::CompactRequest, handlers, response};
+use super::{
+    CacheStatus, RequestContext, VeinProxy, compact::CompactRequest, handlers, policy, response,
+};
 
 use crate::{crates as crates_registry, npm as npm_registry};
 
 impl VeinProxy {
     async fn handle(&self, req: Request<Body>, ctx: &mut RequestContext) -> Result<Response<Body>> {
+        if req.method() == Method::POST && req.uri().path_or_root() == "/.well-known/vein/prefetch"
+        {
+            let (resp, status) = self.handle_prefetch_request(req).await?;
+            ctx.cache = status;
+            return Ok(resp);
+        }
+        self.route(req, ctx).await
+    }
+
+    /// Routes every request except prefetch, which fans out back into here.
+    pub(super) async fn route(
+        &self,
+        req: Request<Body>,
+        ctx: &mut RequestContext,
+    ) -> Result<Response<Body>> {
         let method = req.method().clone();
         let path = req.uri().path_or_root().into_owned();
 
         if npm_registry::is_npm_request(&req) {
+            let tarball = npm_registry::tarball_request(&path);
+            if let Some(cacheable) = &tarball
+                && let Some(resp) = self.policy_response(cacheable).await?
+            {
+                ctx.cache = CacheStatus::Pass;
+                return Ok(resp);
+            }
             let our_base = format!(
                 "http://{}:{}",
                 self.config.server.host, self.config.server.port
             );
+            let filter =
+                policy::index_filter(&self.config, &self.policy, &self.index, Ecosystem::Npm).await;
+            let client = ClientBudget::of(req.extensions()).cloned();
             let result = npm_registry::handle_npm_request(
                 req,
                 &our_base,
                 self.storage.clone(),
                 self.index.clone(),
+                IndexOptions {
+                    offline: &self.config.offline,
+                    index_cache: &self.config.index_cache,
+                    policy: filter.as_ref(),
+                    client: client.as_ref(),
+                },
             )
             .await;
+            let result = match (result, &tarball) {
+                (Ok((resp, outcome)), Some(cacheable)) => self
+                    .npm_tarball_checks(cacheable, resp, outcome)
+                    .await
+                    .map(|resp| (resp, outcome)),
+                (result, _) => result,
+            };
             return finish_registry_result(ctx, result, "npm request failed", "npm upstream error");
         }
 
+        if method == Method::POST && path == "/.well-known/vein/audit" {
+            let (resp, status) =
+                handlers::handle_audit_request(req, self.index.as_ref(), self.storage.as_ref())
+                    .await?;
+            ctx.cache = status;
+            return Ok(resp);
+        }
+
+        if method == Method::POST && path == "/.well-known/vein/sbom" {
+            let (resp, status) =
+                handlers::handle_lockfile_sbom_request(req, self.index.as_ref()).await?;
+            ctx.cache = status;
+            return Ok(resp);
+        }
+
         if method == Method::GET {
             match path.as_str() {
-                "/up" => {
-                    let (resp, status) = handlers::handle_health(self.index.as_ref()).await?;
+                "/livez" | "/up" => {
+                    let (resp, status) = handlers::handle_livez()?;
+                    ctx.cache = status;
+                    return Ok(resp);
+                }
+                "/readyz" => {
+                    let (resp, status) = handlers::handle_readyz(
+                        &self.config,
+                        self.index.as_ref(),
+                        &self.mirror_health(),
+                    )
+                    .await?;
+                    ctx.cache = status;
+                    return Ok(resp);
+                }
+                "/.well-known/vein/advisories" => {
+                    let (resp, status) =
+                        handlers::handle_advisories_request(&req, self.index.as_ref()).await?;
                     ctx.cache = status;
                     return Ok(resp);
                 }
@@ -45,6 +119,12 @@ impl VeinProxy {
                     ctx.cache = status;
                     return Ok(resp);
                 }
+                "/.well-known/vein/symbols" => {
+                    let (resp, status) =
+                        handlers::handle_symbols_request(&req, self.index.as_ref()).await?;
+                    ctx.cache = status;
+                    return Ok(resp);
+                }
                 "/" => {
                     ctx.cache = CacheStatus::Pass;
                     return response::respond_homepage(&self.config);
@@ -54,11 +134,24 @@ impl VeinProxy {
                         "http://{}:{}",
                         self.config.server.host, self.config.server.port
                     );
+                    let filter = policy::index_filter(
+                        &self.config,
+                        &self.policy,
+                        &self.index,
+                        Ecosystem::CratesIo,
+                    )
+                    .await;
                     let result = crates_registry::handle_sparse_index(
                         p,
                         &our_base,
                         self.storage.clone(),
                         self.index.clone(),
+                        IndexOptions {
+                            offline: &self.config.offline,
+                            index_cache: &self.config.index_cache,
+                            policy: filter.as_ref(),
+                            client: ClientBudget::of(req.extensions()),
+                        },
                     )
                     .await;
                     return finish_registry_result(
@@ -78,7 +171,7 @@ impl VeinProxy {
                 Ok(resp)
             }
             None => {
-                if self.upstreams.is_empty() {
+                if self.upstreams.is_empty() && !self.config.offline.enabled {
                     ctx.cache = CacheStatus::Miss;
                     return response::respond_text(StatusCode::NOT_FOUND, "not found in cache");
                 }
@@ -102,6 +195,14 @@ impl VeinProxy {
                         }
                     }
 
+                    if self.config.offline.enabled {
+                        ctx.cache = CacheStatus::Miss;
+                        return response::respond_text(
+                            StatusCode::GATEWAY_TIMEOUT,
+                            "not cached (offline mode)",
+                        );
+                    }
+
                     match self.proxy_generic_get(&req).await {
                         Ok(resp) => {
                             ctx.cache = CacheStatus::Pass;
@@ -143,7 +244,9 @@ impl Service<Request<Body>> for VeinProxy {
         let mut ctx = RequestContext::from_request(&req);
         ctx.start = Instant::now();
 
-        let result = self.handle(req, &mut ctx).await;
+        // Pin the request to the config it started with, across reloads.
+        let proxy = self.current();
+        let result = proxy.handle(req, &mut ctx).await;
 
         match &result {
             Ok(resp) => {
@@ -199,6 +302,7 @@ fn cache_status_from_outcome(outcome: CacheOutcome) -> CacheStatus {
         CacheOutcome::Hit => CacheStatus::Hit,
         CacheOutcome::Miss => CacheStatus::Miss,
         CacheOutcome::Revalidated => CacheStatus::Revalidated,
+        CacheOutcome::Stale => CacheStatus::Stale,
         CacheOutcome::Pass => CacheStatus::Pass,
     }
 }
@@ -1,11 +1,22 @@
+use std::time::Duration;
+
 use anyhow::{Context, Result, anyhow};
 use rama::{
     Service,
-    http::{Body, HeaderMap, HeaderValue, Method, Request, Response, body::util::BodyExt, header},
+    extensions::ExtensionsRef as _,
+    http::{
+        Body, HeaderMap, HeaderValue, Method, Request, Response, StatusCode, body::util::BodyExt,
+        header,
+    },
+    telemetry::tracing::debug,
 };
 use vein_adapter::CacheBackendTrait;
 
-use super::{CacheStatus, RequestContext, VeinProxy, cache, quarantine, types};
+use crate::http_cache::CacheOutcome;
+use crate::rate_limit::{Budget, ClientBudget};
+use crate::upstream::MirrorSnapshot;
+
+use super::{CacheStatus, RequestContext, VeinProxy, cache, policy, quarantine, response, types};
 
 impl VeinProxy {
     pub(super) async fn try_handle_cached_request(
@@ -26,26 +37,129 @@ impl VeinProxy {
             return Ok(None);
         };
 
-        match self.index.get(&cacheable.asset_key()).await? {
+        if let Some(resp) = self.policy_response(&cacheable).await? {
+            return Ok(Some((CacheStatus::Pass, resp)));
+        }
+
+        let cached = self.index.get(&cacheable.asset_key()).await?;
+        let client = ClientBudget::of(req.extensions());
+        if cached.is_some()
+            && let Some(client) = client
+            && let Err(limited) = client.charge(Budget::Hits)
+        {
+            return Ok(Some((CacheStatus::Pass, limited.into_response()?)));
+        }
+        if self.config.offline.enabled {
+            if let Some(entry) = cached
+                && let Ok(resp) = cache::serve_cached(&cacheable, entry, &self.storage).await
+            {
+                let resp = self.license_response(&cacheable, resp, false).await?;
+                return Ok(Some((CacheStatus::Hit, resp)));
+            }
+            let resp =
+                response::respond_text(StatusCode::GATEWAY_TIMEOUT, "not cached (offline mode)")?;
+            return Ok(Some((CacheStatus::Miss, resp)));
+        }
+
+        match cached {
             Some(entry) => match cache::serve_cached(&cacheable, entry, &self.storage).await {
-                Ok(resp) => Ok(Some((CacheStatus::Hit, resp))),
+                Ok(resp) => {
+                    let resp = self.license_response(&cacheable, resp, false).await?;
+                    Ok(Some((CacheStatus::Hit, resp)))
+                }
                 Err(err) => {
                     rama::telemetry::tracing::warn!(
                         error = %err,
                         "failed to serve cached asset, falling back to upstream"
                     );
-                    self.fetch_and_stream(req, &cacheable, true)
-                        .await
-                        .map(|resp| Some((CacheStatus::Revalidated, resp)))
+                    self.fetch_admitted(req, &cacheable, true).await.map(Some)
                 }
             },
-            None => self
-                .fetch_and_stream(req, &cacheable, false)
-                .await
-                .map(|resp| Some((CacheStatus::Miss, resp))),
+            None => self.fetch_admitted(req, &cacheable, false).await.map(Some),
         }
     }
 
+    /// Fetches a miss once the client's miss budget and a global upstream
+    /// slot allow it; the slot is held until the artifact is cached.
+    async fn fetch_admitted(
+        &self,
+        req: &Request<Body>,
+        cacheable: &types::CacheableRequest,
+        treating_as_revalidation: bool,
+    ) -> Result<(CacheStatus, Response<Body>)> {
+        let _permit = match ClientBudget::of(req.extensions()) {
+            Some(client) => match client.admit_upstream().await {
+                Ok(permit) => permit,
+                Err(limited) => return Ok((CacheStatus::Pass, limited.into_response()?)),
+            },
+            None => None,
+        };
+        let status = if treating_as_revalidation {
+            CacheStatus::Revalidated
+        } else {
+            CacheStatus::Miss
+        };
+        self.fetch_and_stream(req, cacheable, treating_as_revalidation)
+            .await
+            .map(|resp| (status, resp))
+    }
+
+    /// Refuses downloads blocked by policy rules, upstream yanks, security
+    /// advisories or suspicious names.
+    pub(super) async fn policy_response(
+        &self,
+        cacheable: &types::CacheableRequest,
+    ) -> Result<Option<Response<Body>>> {
+        if let Some(resp) =
+            policy::rule_response(&self.config, &self.policy, &self.index, cacheable, false).await?
+        {
+            return Ok(Some(resp));
+        }
+        if let Some(resp) =
+            cache::yanked_response(&self.config.yank_sync, &self.index, cacheable).await?
+        {
+            return Ok(Some(resp));
+        }
+        if let Some(resp) =
+            cache::advisory_response(&self.config.advisories, &self.index, cacheable).await?
+        {
+            return Ok(Some(resp));
+        }
+        cache::typosquat_response(&self.config, &self.typosquat, &self.index, cacheable).await
+    }
+
+    /// Flags, blocks or holds downloads whose license breaks the policy.
+    pub(super) async fn license_response(
+        &self,
+        cacheable: &types::CacheableRequest,
+        response: Response<Body>,
+        newly_cached: bool,
+    ) -> Result<Response<Body>> {
+        cache::license_response(&self.config, &self.index, cacheable, response, newly_cached).await
+    }
+
+    /// License and attribute-rule checks for an npm tarball the registry
+    /// handler served; both only apply once a miss has extracted metadata.
+    pub(super) async fn npm_tarball_checks(
+        &self,
+        cacheable: &types::CacheableRequest,
+        response: Response<Body>,
+        outcome: CacheOutcome,
+    ) -> Result<Response<Body>> {
+        let response = self
+            .license_response(cacheable, response, outcome == CacheOutcome::Miss)
+            .await?;
+        let newly_cached = matches!(outcome, CacheOutcome::Miss | CacheOutcome::Revalidated);
+        if !newly_cached || !response.status().is_success() {
+            return Ok(response);
+        }
+        Ok(
+            policy::rule_response(&self.config, &self.policy, &self.index, cacheable, true)
+                .await?
+                .unwrap_or(response),
+        )
+    }
+
     async fn fetch_and_stream(
         &self,
         req: &Request<Body>,
@@ -85,6 +199,20 @@ impl VeinProxy {
             treating_as_revalidation,
         )
         .await;
+        // Before the version is tracked, so a license hold is not overwritten
+        let result = match result {
+            Ok(resp) => self.license_response(cacheable, resp, true).await,
+            Err(err) => Err(err),
+        };
+        // Attribute rules can only match once the metadata is extracted
+        let result = match result {
+            Ok(resp) if resp.status().is_success() => {
+                policy::rule_response(&self.config, &self.policy, &self.index, cacheable, true)
+                    .await
+                    .map(|refused| refused.unwrap_or(resp))
+            }
+            result => result,
+        };
 
         if result.is_ok()
             && cacheable.kind == vein_adapter::AssetKind::Gem
@@ -95,6 +223,10 @@ impl VeinProxy {
                 &cacheable.version,
                 cacheable.platform.as_deref(),
                 "",
+                self.config.delay_policy.risk.first_version.is_some()
+                    && self
+                        .is_first_version(&cacheable.name, &cacheable.version)
+                        .await,
             )
             .await
         {
@@ -193,42 +325,136 @@ impl VeinProxy {
         self.forward_response(response).await
     }
 
+    /// Tries upstream mirrors from healthiest to least healthy. When hedging
+    /// is configured, a slow answer from the best mirror is raced against the
+    /// next one.
     pub(super) async fn fetch_with_fallback(
         &self,
         req: &Request<Body>,
         headers: Option<&HeaderMap>,
     ) -> Result<Response<Body>> {
-        let client = self
-            .upstream_client
-            .as_ref()
-            .ok_or_else(|| anyhow!("upstream client not configured"))?;
-
         if self.upstreams.is_empty() {
             return Err(anyhow!("no upstream configured"));
         }
 
+        let empty = HeaderMap::new();
+        let headers = headers.unwrap_or(&empty);
+        let ranked = self.ranked_upstreams();
+        let mut remaining = ranked.as_slice();
         let mut last_err: Option<anyhow::Error> = None;
 
-        for upstream in &self.upstreams {
-            let upstream_url = upstream
-                .join(req)
-                .with_context(|| format!("constructing upstream url for {}", upstream.base))?;
-
-            let response = client
-                .get_with_headers(upstream_url.clone(), headers.unwrap_or(&HeaderMap::new()))
-                .await;
+        if let Some(delay) = self.hedge_delay()
+            && let [first, second, rest @ ..] = remaining
+        {
+            match self.fetch_hedged(first, second, req, headers, delay).await {
+                Ok(response) => return Ok(response),
+                Err(err) => last_err = Some(err),
+            }
+            remaining = rest;
+        }
 
-            match response {
-                Ok(r) if r.status().is_server_error() => {
-                    last_err = Some(anyhow!("{} returned {}", upstream_url, r.status()));
-                }
-                Ok(r) => return Ok(r),
-                Err(err) => {
-                    last_err = Some(anyhow!("{} failed: {err}", upstream_url));
-                }
+        for upstream in remaining {
+            match self.fetch_from(upstream, req, headers).await {
+                Ok(response) => return Ok(response),
+                Err(err) => last_err = Some(err),
             }
         }
 
         Err(last_err.unwrap_or_else(|| anyhow!("all upstreams failed")))
     }
+
+    /// Upstreams ordered by health score; ties keep config order.
+    fn ranked_upstreams(&self) -> Vec<&types::UpstreamTarget> {
+        let mut ranked: Vec<_> = self
+            .upstreams
+            .iter()
+            .map(|upstream| (upstream.health().score(), upstream))
+            .collect();
+        ranked.sort_by(|a, b| a.0.total_cmp(&b.0));
+        ranked.into_iter().map(|(_, upstream)| upstream).collect()
+    }
+
+    fn hedge_delay(&self) -> Option<Duration> {
+        self.config
+            .upstream
+            .as_ref()
+            .and_then(|upstream| upstream.hedge_after_ms)
+            .map(Duration::from_millis)
+    }
+
+    /// Per-mirror health, in config order.
+    pub fn mirror_health(&self) -> Vec<MirrorSnapshot> {
+        self.upstreams
+            .iter()
+            .map(|upstream| upstream.health().snapshot())
+            .collect()
+    }
+
+    /// Fetches from a single mirror, treating 5xx as a failure.
+    async fn fetch_from(
+        &self,
+        upstream: &types::UpstreamTarget,
+        req: &Request<Body>,
+        headers: &HeaderMap,
+    ) -> Result<Response<Body>> {
+        let upstream_url = upstream
+            .join(req)
+            .with_context(|| format!("constructing upstream url for {}", upstream.base))?;
+
+        match upstream
+            .client
+            .get_with_headers(upstream_url.clone(), headers)
+            .await
+        {
+            Ok(r) if r.status().is_server_error() => {
+                Err(anyhow!("{} returned {}", upstream_url, r.status()))
+            }
+            Ok(r) => Ok(r),
+            Err(err) => Err(anyhow!("{} failed: {err}", upstream_url)),
+        }
+    }
+
+    /// Races `first` against `second` once `first` has been pending for
+    /// `delay`, returning whichever succeeds first.
+    async fn fetch_hedged(
+        &self,
+        first: &types::UpstreamTarget,
+        second: &types::UpstreamTarget,
+        req: &Request<Body>,
+        headers: &HeaderMap,
+        delay: Duration,
+    ) -> Result<Response<Body>> {
+        let primary = self.fetch_from(first, req, headers);
+        tokio::pin!(primary);
+
+        tokio::select! {
+            result = &mut primary => {
+                return match result {
+                    Ok(response) => Ok(response),
+                    Err(_) => self.fetch_from(second, req, headers).await,
+                };
+            }
+            () = tokio::time::sleep(delay) => {}
+        }
+
+        debug!(
+            slow = %first.base,
+            hedge = %second.base,
+            "upstream slow, hedging request"
+        );
+        second.health().record_hedge();
+        let secondary = self.fetch_from(second, req, headers);
+        tokio::pin!(secondary);
+
+        tokio::select! {
+            result = &mut primary => match result {
+                Ok(response) => Ok(response),
+                Err(_) => secondary.await,
+            },
+            result = &mut secondary => match result {
+                Ok(response) => Ok(response),
+                Err(_) => primary.await,
+            },
+        }
+    }
 }
@@ -1,65 +1,241 @@
 use std::borrow::Cow;
 
 use anyhow::{Context, Result};
+use rama::http::body::util::{BodyExt, CollectOptions};
 use rama::http::service::web::extract::Query;
-use rama::http::{Request, StatusCode};
+use rama::http::{Request, StatusCode, header};
 use serde::Deserialize;
 use serde_json::{json, to_string_pretty};
-use vein_adapter::{CacheBackend, CacheBackendTrait};
+use vein_adapter::{
+    AdvisorySeverity, CacheBackend, CacheBackendTrait, Ecosystem, FilesystemStorage,
+};
 
-use super::response::{respond_json, respond_json_download, respond_text};
+use crate::advisories::{self, AdvisoryMatch};
+use crate::config::Config;
+use crate::health;
+use crate::lockfile::{Lockfile, audit};
+use crate::sbom::{self, SbomFormat};
+use crate::upstream::MirrorSnapshot;
+
+use super::response::{respond_download, respond_json, respond_text};
 use super::types::CacheStatus;
 use super::utils::sanitize_filename;
 
-/// Handles health check requests
-pub async fn handle_health(
+/// Liveness probe: answers as long as the process is serving requests.
+pub fn handle_livez() -> Result<(rama::http::Response<rama::http::Body>, CacheStatus)> {
+    let resp = respond_json(StatusCode::OK, &json!({ "status": "ok" }).to_string())?;
+    Ok((resp, CacheStatus::Pass))
+}
+
+/// Readiness probe. Answers 503 only when a `critical` component check fails;
+/// failing `warn` checks report `degraded` with a 200.
+pub async fn handle_readyz(
+    config: &Config,
     index: &CacheBackend,
+    mirrors: &[MirrorSnapshot],
 ) -> Result<(rama::http::Response<rama::http::Body>, CacheStatus)> {
-    let mut ok = true;
-    let mut checks = Vec::new();
-
-    match index.stats().await {
-        Ok(stats) => {
-            checks.push(json!({
-                "component": "cache_index",
-                "status": "ok",
-                "total_assets": stats.total_assets,
-                "unique_packages": stats.unique_packages,
-                "rubygems_assets": stats.rubygems_assets,
-                "crates_assets": stats.crate_assets,
-                "npm_assets": stats.npm_assets,
-            }));
-        }
-        Err(err) => {
-            ok = false;
-            checks.push(json!({
-                "component": "cache_index",
-                "status": "error",
-                "error": err.to_string()
-            }));
-        }
+    let report = health::check_readiness(config, index, mirrors).await;
+    let (status, cache) = if report.is_ready() {
+        (StatusCode::OK, CacheStatus::Pass)
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, CacheStatus::Error)
+    };
+    let body = serde_json::to_string(&report).context("serializing readiness report")?;
+    Ok((respond_json(status, &body)?, cache))
+}
+
+/// Handles vulnerability advisory lookups
+/// (`?ecosystem=rubygems&name=rack&version=3.0.0`). Without `version`, every
+/// active advisory for the package is returned.
+pub async fn handle_advisories_request(
+    req: &Request<rama::http::Body>,
+    index: &CacheBackend,
+) -> Result<(rama::http::Response<rama::http::Body>, CacheStatus)> {
+    let query = req.uri().query_or_empty();
+
+    #[derive(Deserialize, Default)]
+    struct Parameters<'a> {
+        ecosystem: Option<Cow<'a, str>>,
+        name: Option<Cow<'a, str>>,
+        version: Option<Cow<'a, str>>,
     }
 
-    let status = if ok {
-        StatusCode::OK
-    } else {
-        StatusCode::SERVICE_UNAVAILABLE
+    let Parameters {
+        ecosystem,
+        name,
+        version,
+    } = Query::parse_query_str(query.as_ref())
+        .map(|q| q.0)
+        .unwrap_or_default();
+
+    let Ok(ecosystem) = ecosystem
+        .as_deref()
+        .unwrap_or("rubygems")
+        .parse::<Ecosystem>()
+    else {
+        let resp = respond_text(
+            StatusCode::BAD_REQUEST,
+            "query parameter 'ecosystem' must be rubygems, crates or npm\n",
+        )?;
+        return Ok((resp, CacheStatus::Pass));
     };
 
+    let Some(name) = name else {
+        let resp = respond_text(
+            StatusCode::BAD_REQUEST,
+            "query parameter 'name' is required\n",
+        )?;
+        return Ok((resp, CacheStatus::Pass));
+    };
+
+    let matches: Vec<AdvisoryMatch> =
+        advisories::advisories_for(index, ecosystem, &name, version.as_deref())
+            .await
+            .context("loading advisories")?
+            .iter()
+            .map(Into::into)
+            .collect();
+
     let body = json!({
-        "status": if ok { "ok" } else { "degraded" },
-        "checks": checks,
+        "ecosystem": ecosystem,
+        "name": name,
+        "version": version,
+        "advisories": matches,
     });
+    let resp = respond_json(StatusCode::OK, &body.to_string())?;
+    Ok((resp, CacheStatus::Hit))
+}
+
+/// Symbols returned when the caller does not ask for a limit.
+const DEFAULT_SYMBOL_LIMIT: u32 = 100;
+/// Upper bound on symbols returned by one search.
+const MAX_SYMBOL_LIMIT: u32 = 1000;
+
+/// Searches the Ruby symbols of cached gems
+/// (`?q=ActiveSupport::Concern` or `?q=*%23blank%3F&type=method`). `*` in `q`
+/// is a wildcard; `type` is `class`, `module`, `method`, `singleton_method`
+/// or `constant`.
+pub async fn handle_symbols_request(
+    req: &Request<rama::http::Body>,
+    index: &CacheBackend,
+) -> Result<(rama::http::Response<rama::http::Body>, CacheStatus)> {
+    let query = req.uri().query_or_empty();
 
-    let resp = respond_json(status, &body.to_string())?;
-    Ok((
-        resp,
-        if ok {
-            CacheStatus::Pass
-        } else {
-            CacheStatus::Error
-        },
-    ))
+    #[derive(Deserialize, Default)]
+    struct Parameters<'a> {
+        q: Option<Cow<'a, str>>,
+        r#type: Option<Cow<'a, str>>,
+        limit: Option<u32>,
+    }
+
+    let Parameters { q, r#type, limit } = Query::parse_query_str(query.as_ref())
+        .map(|q| q.0)
+        .unwrap_or_default();
+
+    let Some(q) = q.filter(|q| !q.trim().is_empty()) else {
+        let resp = respond_text(StatusCode::BAD_REQUEST, "query parameter 'q' is required\n")?;
+        return Ok((resp, CacheStatus::Pass));
+    };
+    let limit = limit
+        .unwrap_or(DEFAULT_SYMBOL_LIMIT)
+        .clamp(1, MAX_SYMBOL_LIMIT);
+
+    let symbols = index
+        .search_symbols(q.trim(), r#type.as_deref(), limit)
+        .await
+        .context("searching gem symbols")?;
+
+    let body = json!({
+        "query": q,
+        "type": r#type,
+        "symbols": symbols,
+    });
+    let resp = respond_json(StatusCode::OK, &body.to_string())?;
+    Ok((resp, CacheStatus::Hit))
+}
+
+/// Largest lockfile accepted by the audit and prefetch endpoints.
+const MAX_LOCKFILE_BYTES: usize = 16 * 1024 * 1024;
+
+/// Audits a posted lockfile (`POST /.well-known/vein/audit`). The format is
+/// taken from `?lockfile=Cargo.lock` or detected from the body; advisories
+/// below `?min_severity=` are listed without failing the audit.
+pub async fn handle_audit_request(
+    req: Request<rama::http::Body>,
+    index: &CacheBackend,
+    storage: &FilesystemStorage,
+) -> Result<(rama::http::Response<rama::http::Body>, CacheStatus)> {
+    #[derive(Deserialize, Default)]
+    struct Parameters {
+        lockfile: Option<String>,
+        min_severity: Option<String>,
+    }
+
+    let Parameters {
+        lockfile,
+        min_severity,
+    } = Query::parse_query_str(req.uri().query_or_empty().as_ref())
+        .map(|q| q.0)
+        .unwrap_or_default();
+
+    let Ok(min_severity) = min_severity
+        .as_deref()
+        .unwrap_or("unknown")
+        .parse::<AdvisorySeverity>()
+    else {
+        let resp = respond_text(
+            StatusCode::BAD_REQUEST,
+            "query parameter 'min_severity' must be low, moderate, high or critical\n",
+        )?;
+        return Ok((resp, CacheStatus::Pass));
+    };
+
+    let lockfile = match read_posted_lockfile(req, lockfile.as_deref()).await? {
+        Ok(lockfile) => lockfile,
+        Err(resp) => return Ok((resp, CacheStatus::Pass)),
+    };
+
+    let report = audit::audit_lockfile(index, storage, &lockfile, min_severity)
+        .await
+        .context("auditing lockfile")?;
+    let body = to_string_pretty(&report).context("serializing audit report")?;
+    let resp = respond_json(StatusCode::OK, &body)?;
+    Ok((resp, CacheStatus::Pass))
+}
+
+/// Reads and parses a lockfile from a request body, returning the error
+/// response to send back when it is too large or cannot be parsed.
+pub(super) async fn read_posted_lockfile(
+    req: Request<rama::http::Body>,
+    file_name: Option<&str>,
+) -> Result<std::result::Result<Lockfile, rama::http::Response<rama::http::Body>>> {
+    let declared_length = req
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<usize>().ok());
+    let too_large = || respond_text(StatusCode::PAYLOAD_TOO_LARGE, "lockfile too large\n");
+    if declared_length.is_some_and(|length| length > MAX_LOCKFILE_BYTES) {
+        return Ok(Err(too_large()?));
+    }
+    // Chunked uploads declare no length; stop reading once past the cap
+    let limit = CollectOptions::new().with_max_size(MAX_LOCKFILE_BYTES);
+    let body = match req.into_body().collect_with(limit).await {
+        Ok(collected) => collected.to_bytes(),
+        Err(err) if err.is_cap_reached() => return Ok(Err(too_large()?)),
+        Err(err) => return Err(anyhow::Error::new(err).context("reading lockfile body")),
+    };
+
+    let parsed = std::str::from_utf8(&body)
+        .map_err(anyhow::Error::from)
+        .and_then(|content| Lockfile::parse(file_name, content));
+    match parsed {
+        Ok(lockfile) => Ok(Ok(lockfile)),
+        Err(err) => Ok(Err(respond_text(
+            StatusCode::BAD_REQUEST,
+            &format!("could not parse lockfile: {err:#}\n"),
+        )?)),
+    }
 }
 
 /// Handles SBOM (Software Bill of Materials) requests
@@ -71,16 +247,20 @@ pub async fn handle_sbom_request(
 
     #[derive(Deserialize, Default)]
     struct Parameters<'a> {
+        ecosystem: Option<Cow<'a, str>>,
         name: Option<Cow<'a, str>>,
         version: Option<Cow<'a, str>>,
         platform: Option<Cow<'a, str>>,
+        format: Option<Cow<'a, str>>,
     }
 
     // or instead of default return error, which IMHO is probably better?
     let Parameters {
+        ecosystem,
         name,
         version,
         platform,
+        format,
     } = Query::parse_query_str(query.as_ref())
         .map(|q| q.0)
         .unwrap_or_default();
@@ -101,36 +281,141 @@ pub async fn handle_sbom_request(
         return Ok((resp, CacheStatus::Pass));
     };
 
-    let mut metadata = index
-        .gem_metadata(&name, &version, platform.as_deref())
+    let Ok(format) = format
+        .as_deref()
+        .map_or(Ok(SbomFormat::default()), str::parse::<SbomFormat>)
+    else {
+        let resp = respond_text(
+            StatusCode::BAD_REQUEST,
+            "query parameter 'format' must be one of cyclonedx, spdx, spdx-tag-value, spdx3\n",
+        )?;
+        return Ok((resp, CacheStatus::Pass));
+    };
+
+    match ecosystem.as_deref().map(str::parse::<Ecosystem>) {
+        None | Some(Ok(Ecosystem::RubyGems)) => {}
+        Some(Ok(other)) => {
+            return handle_package_sbom(index, other, &name, &version, format).await;
+        }
+        Some(Err(())) => {
+            let resp = respond_text(
+                StatusCode::BAD_REQUEST,
+                "query parameter 'ecosystem' must be one of rubygems, crates, npm\n",
+            )?;
+            return Ok((resp, CacheStatus::Pass));
+        }
+    }
+
+    let mut metadata = sbom::current_gem_metadata(index, &name, &version, platform.as_deref())
         .await
         .context("loading cached gem metadata for SBOM request")?;
 
     if metadata.is_none() && platform.is_none() {
-        metadata = index
-            .gem_metadata(&name, &version, Some("ruby"))
+        metadata = sbom::current_gem_metadata(index, &name, &version, Some("ruby"))
             .await
             .context("retrying SBOM lookup for ruby platform")?;
     }
 
-    let Some(meta) = metadata.filter(|m| m.sbom.is_some()) else {
+    let rendered = match &metadata {
+        Some(meta) => sbom::render_gem(format, meta)?,
+        None => None,
+    };
+    let (Some(meta), Some(body)) = (metadata, rendered) else {
         let resp = respond_text(
             StatusCode::NOT_FOUND,
             "SBOM not available for requested gem\n",
         )?;
         return Ok((resp, CacheStatus::Pass));
     };
-    let sbom = meta.sbom.as_ref().expect("sbom present after filter");
-
-    let body = to_string_pretty(sbom).context("serializing SBOM JSON payload")?;
 
     let filename = format!(
-        "{}-{}-{}.sbom.json",
+        "{}-{}-{}{}",
         sanitize_filename(&meta.name),
         sanitize_filename(&meta.version),
         sanitize_filename(&meta.platform),
+        format.file_suffix(),
+    );
+
+    let resp = respond_download(&body, &filename, format.content_type())?;
+    Ok((resp, CacheStatus::Hit))
+}
+
+/// Handles `POST /.well-known/vein/sbom`: one SBOM for a whole lockfile.
+pub async fn handle_lockfile_sbom_request(
+    req: Request<rama::http::Body>,
+    index: &CacheBackend,
+) -> Result<(rama::http::Response<rama::http::Body>, CacheStatus)> {
+    #[derive(Deserialize, Default)]
+    struct Parameters {
+        lockfile: Option<String>,
+        name: Option<String>,
+        format: Option<String>,
+    }
+
+    let Parameters {
+        lockfile,
+        name,
+        format,
+    } = Query::parse_query_str(req.uri().query_or_empty().as_ref())
+        .map(|q| q.0)
+        .unwrap_or_default();
+
+    let Ok(format) = format
+        .as_deref()
+        .map_or(Ok(SbomFormat::default()), str::parse::<SbomFormat>)
+    else {
+        let resp = respond_text(
+            StatusCode::BAD_REQUEST,
+            "query parameter 'format' must be one of cyclonedx, spdx, spdx-tag-value, spdx3\n",
+        )?;
+        return Ok((resp, CacheStatus::Pass));
+    };
+
+    let parsed = match read_posted_lockfile(req, lockfile.as_deref()).await? {
+        Ok(parsed) => parsed,
+        Err(resp) => return Ok((resp, CacheStatus::Pass)),
+    };
+
+    let name = name.or(lockfile).unwrap_or_else(|| "lockfile".to_string());
+    let body = sbom::lockfile_sbom(index, &parsed, &name, format)
+        .await
+        .context("building lockfile SBOM")?;
+    let filename = format!("{}{}", sanitize_filename(&name), format.file_suffix());
+    let resp = respond_download(&body, &filename, format.content_type())?;
+    Ok((resp, CacheStatus::Pass))
+}
+
+/// SBOM for a crate or npm package from the `package_metadata` table.
+async fn handle_package_sbom(
+    index: &CacheBackend,
+    ecosystem: Ecosystem,
+    name: &str,
+    version: &str,
+    format: SbomFormat,
+) -> Result<(rama::http::Response<rama::http::Body>, CacheStatus)> {
+    let metadata = sbom::current_package_metadata(index, ecosystem, name, version)
+        .await
+        .context("loading cached package metadata for SBOM request")?;
+
+    let rendered = match &metadata {
+        Some(meta) => sbom::render_package(format, meta)?,
+        None => None,
+    };
+    let Some(body) = rendered else {
+        let resp = respond_text(
+            StatusCode::NOT_FOUND,
+            &format!("SBOM not available for requested {ecosystem} package\n"),
+        )?;
+        return Ok((resp, CacheStatus::Pass));
+    };
+
+    let filename = format!(
+        "{}-{}{}",
+        sanitize_filename(name),
+        sanitize_filename(version),
+        format.file_suffix(),
     );
 
-    let resp = respond_json_download(&body, &filename)?;
+    let resp = respond_download(&body, &filename, format.content_type())?;
     Ok((resp, CacheStatus::Hit))
 }
@@ -0,0 +1,169 @@
+//! Policy rule enforcement for downloads and index responses.
+//!
+//! Downloads are decided before the cache lookup, so a refused version never
+//! reaches the upstream. Quarantine actions reuse the gem quarantine: the
+//! version is held on first request and served once released or approved.
+
+use anyhow::Result;
+use chrono::Utc;
+use rama::http::{Body, Response, StatusCode, header::HeaderValue};
+use rama::telemetry::tracing::warn;
+use vein_adapter::{CacheBackend, CacheBackendTrait, Ecosystem, is_version_available};
+
+use super::{quarantine, response, types::CacheableRequest};
+use crate::config::{Config, NotificationKind, PolicyAction, PolicyRule};
+use crate::notifications::{self, Notification};
+use crate::policy::{self, IndexFilter, PolicyEngine, Subject};
+use crate::quarantine::risk;
+
+/// Header naming the rule that refused or held a download.
+const POLICY_RULE_HEADER: &str = "x-vein-policy-rule";
+
+/// Whether quarantine actions can hold versions of `ecosystem` instead of
+/// denying them.
+fn holdable(config: &Config, ecosystem: Ecosystem) -> bool {
+    ecosystem == Ecosystem::RubyGems && config.delay_policy.enabled
+}
+
+/// Refuses or holds a download matched by a policy rule.
+///
+/// With `just_cached`, only runs when attribute rules may now match the
+/// freshly extracted metadata.
+pub(crate) async fn rule_response(
+    config: &Config,
+    engine: &PolicyEngine,
+    index: &CacheBackend,
+    cacheable: &CacheableRequest,
+    just_cached: bool,
+) -> Result<Option<Response<Body>>> {
+    if !config.policy.enabled {
+        return Ok(None);
+    }
+    let rules = engine.rules(&config.policy, index).await?;
+    if rules.is_empty() || (just_cached && !rules.has_attribute_rules()) {
+        return Ok(None);
+    }
+
+    let ecosystem = cacheable.kind.ecosystem();
+    let attributes = if rules.has_attribute_rules() {
+        policy::package_attributes(
+            index,
+            ecosystem,
+            &cacheable.name,
+            &cacheable.version,
+            cacheable.platform.as_deref(),
+        )
+        .await?
+    } else {
+        None
+    };
+    let subject = Subject {
+        ecosystem,
+        name: &cacheable.name,
+        version: &cacheable.version,
+        platform: cacheable.platform.as_deref(),
+        attributes: attributes.as_ref(),
+    };
+    let Some(rule) = rules.decide(&subject) else {
+        return Ok(None);
+    };
+    policy::log_decision(rule, &subject);
+
+    match rule.action {
+        PolicyAction::Allow => Ok(None),
+        PolicyAction::Quarantine | PolicyAction::RequireApproval if holdable(config, ecosystem) => {
+            held_response(config, index, cacheable, rule).await
+        }
+        PolicyAction::Deny | PolicyAction::Quarantine | PolicyAction::RequireApproval => {
+            let note = rule_note(rule);
+            notifications::emit(Notification::new(
+                NotificationKind::PolicyFailed,
+                ecosystem,
+                &cacheable.name,
+                &cacheable.version,
+                cacheable.platform.as_deref(),
+                Some(&note),
+            ));
+            refused(
+                rule,
+                &format!(
+                    "{} {} is denied by {note}\n",
+                    cacheable.name, cacheable.version
+                ),
+            )
+            .map(Some)
+        }
+    }
+}
+
+/// Holds a gem version the first time it is requested, then refuses it
+/// until the quarantine releases it.
+async fn held_response(
+    config: &Config,
+    index: &CacheBackend,
+    cacheable: &CacheableRequest,
+    rule: &PolicyRule,
+) -> Result<Option<Response<Body>>> {
+    let note = rule_note(rule);
+    let (name, version, platform) = (
+        cacheable.name.as_str(),
+        cacheable.version.as_str(),
+        cacheable.platform.as_deref(),
+    );
+    if rule.action == PolicyAction::RequireApproval {
+        quarantine::hold_for_approval(&config.delay_policy, index, name, version, platform, &note)
+            .await?;
+    } else {
+        quarantine::hold_for_delay(&config.delay_policy, index, name, version, platform, &note)
+            .await?;
+    }
+
+    // Pinned versions are never held; released and approved ones are served
+    let Some(tracked) = index.get_gem_version(name, version, platform).await? else {
+        return Ok(None);
+    };
+    if is_version_available(&tracked, Utc::now()) {
+        return Ok(None);
+    }
+    let release = if risk::awaits_manual_approval(&tracked) {
+        "awaiting approval".to_string()
+    } else {
+        format!("held until {}", tracked.available_after.to_rfc3339())
+    };
+    refused(rule, &format!("{name} {version} is {release} ({note})\n")).map(Some)
+}
+
+fn rule_note(rule: &PolicyRule) -> String {
+    match &rule.reason {
+        Some(reason) => format!("policy rule {}: {reason}", rule.id),
+        None => format!("policy rule {}", rule.id),
+    }
+}
+
+fn refused(rule: &PolicyRule, body: &str) -> Result<Response<Body>> {
+    let mut response = response::respond_text(StatusCode::FORBIDDEN, body)?;
+    if let Ok(value) = HeaderValue::from_str(&rule.id) {
+        response.headers_mut().insert(POLICY_RULE_HEADER, value);
+    }
+    Ok(response)
+}
+
+/// Filter hiding refused versions from `ecosystem` index responses, or
+/// `None` when no rule hides anything.
+pub(crate) async fn index_filter(
+    config: &Config,
+    engine: &PolicyEngine,
+    index: &CacheBackend,
+    ecosystem: Ecosystem,
+) -> Option<IndexFilter> {
+    if !config.policy.enabled {
+        return None;
+    }
+    match engine.rules(&config.policy, index).await {
+        Ok(rules) => IndexFilter::new(&rules, ecosystem, holdable(config, ecosystem)),
+        Err(err) => {
+            warn!(error = %err, "failed to load policy rules, index left unfiltered");
+            None
+        }
+    }
+}
@@ -0,0 +1,299 @@
+//! Lockfile-driven cache warmup.
+//!
+//! Every artifact pinned by a lockfile is requested through the regular
+//! request routing, so misses follow the normal fetch, cache and quarantine flow.
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use rama::{
+    extensions::ExtensionsRef as _,
+    http::{
+        Body, Method, Request, Response, StatusCode, body::util::BodyExt,
+        service::web::extract::Query,
+    },
+    telemetry::tracing::{info, warn},
+};
+use serde::{Deserialize, Serialize};
+use tokio::{sync::Semaphore, task::JoinSet};
+use vein_adapter::{CacheBackendTrait, Ecosystem, VersionStatus};
+
+use crate::lockfile::{LockedPackage, Lockfile, LockfileKind};
+use crate::rate_limit::ClientBudget;
+
+use super::{CacheStatus, RequestContext, VeinProxy, handlers, response::respond_json};
+
+/// Parallel fetches used when the caller does not ask for a limit.
+pub const DEFAULT_PREFETCH_CONCURRENCY: usize = 8;
+/// Upper bound on parallel fetches for a single prefetch run.
+pub const MAX_PREFETCH_CONCURRENCY: usize = 64;
+
+/// What happened to a single pinned artifact.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PrefetchOutcome {
+    /// Already cached.
+    Hit,
+    /// Fetched from upstream and cached by this run.
+    Cached,
+    /// Refused by policy (advisory, license) or yanked upstream.
+    Blocked,
+    /// Upstream or storage failure.
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrefetchItem {
+    pub ecosystem: Ecosystem,
+    pub name: String,
+    pub version: String,
+    pub platform: Option<String>,
+    pub outcome: PrefetchOutcome,
+    /// Held back by the quarantine delay policy (gems only).
+    pub quarantined: bool,
+    pub status: u16,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PrefetchSummary {
+    pub total: usize,
+    pub hits: usize,
+    pub cached: usize,
+    pub blocked: usize,
+    pub failed: usize,
+    pub quarantined: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrefetchReport {
+    pub lockfile: LockfileKind,
+    pub summary: PrefetchSummary,
+    pub items: Vec<PrefetchItem>,
+}
+
+/// Request path that makes the proxy serve `package`.
+fn artifact_path(package: &LockedPackage) -> String {
+    match package.ecosystem {
+        Ecosystem::RubyGems => match &package.platform {
+            Some(platform) => format!(
+                "/gems/{}-{}-{}.gem",
+                package.name, package.version, platform
+            ),
+            None => format!("/gems/{}-{}.gem", package.name, package.version),
+        },
+        Ecosystem::CratesIo => format!(
+            "/api/v1/crates/{}/{}/download",
+            package.name, package.version
+        ),
+        Ecosystem::Npm => {
+            let base = package
+                .name
+                .rsplit_once('/')
+                .map_or(package.name.as_str(), |(_, base)| base);
+            format!("/{}/-/{}-{}.tgz", package.name, base, package.version)
+        }
+    }
+}
+
+impl VeinProxy {
+    /// Pulls every artifact of `lockfile` into the cache, running at most
+    /// `concurrency` fetches at a time.
+    pub async fn prefetch(&self, lockfile: &Lockfile, concurrency: usize) -> PrefetchReport {
+        self.prefetch_for(lockfile, concurrency, None).await
+    }
+
+    /// Like [`prefetch`](Self::prefetch), charging each upstream fetch to
+    /// `client`'s rate limit budget.
+    async fn prefetch_for(
+        &self,
+        lockfile: &Lockfile,
+        concurrency: usize,
+        client: Option<ClientBudget>,
+    ) -> PrefetchReport {
+        let permits = Arc::new(Semaphore::new(
+            concurrency.clamp(1, MAX_PREFETCH_CONCURRENCY),
+        ));
+        let mut tasks = JoinSet::new();
+
+        for (position, package) in lockfile.packages.iter().cloned().enumerate() {
+            let proxy = self.clone();
+            let permits = permits.clone();
+            let client = client.clone();
+            tasks.spawn(async move {
+                let _permit = permits.acquire_owned().await;
+                (position, proxy.prefetch_one(package, client).await)
+            });
+        }
+
+        let mut items = Vec::with_capacity(lockfile.packages.len());
+        while let Some(joined) = tasks.join_next().await {
+            match joined {
+                Ok(item) => items.push(item),
+                Err(err) => warn!(error = %err, "prefetch task panicked"),
+            }
+        }
+        items.sort_by_key(|(position, _)| *position);
+        let items: Vec<PrefetchItem> = items.into_iter().map(|(_, item)| item).collect();
+
+        let mut summary = PrefetchSummary {
+            total: lockfile.packages.len(),
+            ..PrefetchSummary::default()
+        };
+        for item in &items {
+            match item.outcome {
+                PrefetchOutcome::Hit => summary.hits += 1,
+                PrefetchOutcome::Cached => summary.cached += 1,
+                PrefetchOutcome::Blocked => summary.blocked += 1,
+                PrefetchOutcome::Failed => summary.failed += 1,
+            }
+            if item.quarantined {
+                summary.quarantined += 1;
+            }
+        }
+        info!(
+            total = summary.total,
+            hits = summary.hits,
+            cached = summary.cached,
+            blocked = summary.blocked,
+            failed = summary.failed,
+            quarantined = summary.quarantined,
+            "lockfile prefetch finished"
+        );
+
+        PrefetchReport {
+            lockfile: lockfile.kind,
+            summary,
+            items,
+        }
+    }
+
+    async fn prefetch_one(
+        &self,
+        package: LockedPackage,
+        client: Option<ClientBudget>,
+    ) -> PrefetchItem {
+        let (outcome, status, error) = self.fetch_artifact(&package, client).await;
+
+        let quarantined = package.ecosystem == Ecosystem::RubyGems
+            && matches!(
+                self.index
+                    .get_gem_version(&package.name, &package.version, package.platform.as_deref())
+                    .await,
+                Ok(Some(row)) if row.status == VersionStatus::Quarantine
+            );
+
+        PrefetchItem {
+            ecosystem: package.ecosystem,
+            name: package.name,
+            version: package.version,
+            platform: package.platform,
+            outcome,
+            quarantined,
+            status,
+            error,
+        }
+    }
+
+    async fn fetch_artifact(
+        &self,
+        package: &LockedPackage,
+        client: Option<ClientBudget>,
+    ) -> (PrefetchOutcome, u16, Option<String>) {
+        let mut builder = Request::builder()
+            .method(Method::GET)
+            .uri(artifact_path(package));
+        if package.ecosystem == Ecosystem::Npm {
+            builder = builder.header("npm-command", "install");
+        }
+        let request = match builder.body(Body::empty()) {
+            Ok(request) => request,
+            Err(err) => {
+                return (
+                    PrefetchOutcome::Failed,
+                    StatusCode::BAD_REQUEST.as_u16(),
+                    Some(err.to_string()),
+                );
+            }
+        };
+
+        if let Some(client) = client {
+            request.extensions().insert(client);
+        }
+
+        let mut ctx = RequestContext::from_request(&request);
+        let resp = match self.route(request, &mut ctx).await {
+            Ok(resp) => resp,
+            Err(err) => {
+                return (
+                    PrefetchOutcome::Failed,
+                    StatusCode::BAD_GATEWAY.as_u16(),
+                    Some(format!("{err:#}")),
+                );
+            }
+        };
+
+        let status = resp.status();
+        if status.is_success() {
+            let outcome = if ctx.cache == CacheStatus::Hit {
+                PrefetchOutcome::Hit
+            } else {
+                PrefetchOutcome::Cached
+            };
+            return (outcome, status.as_u16(), None);
+        }
+
+        let outcome = if status == StatusCode::FORBIDDEN || status == StatusCode::GONE {
+            PrefetchOutcome::Blocked
+        } else {
+            PrefetchOutcome::Failed
+        };
+        let reason = match resp.into_body().collect().await {
+            Ok(body) => String::from_utf8_lossy(&body.to_bytes()).trim().to_string(),
+            Err(_) => String::new(),
+        };
+        let error = if reason.is_empty() {
+            status.to_string()
+        } else {
+            reason
+        };
+        (outcome, status.as_u16(), Some(error))
+    }
+
+    /// Warms the cache from a posted lockfile (`POST /.well-known/vein/prefetch`).
+    /// The format is taken from `?lockfile=` or detected from the body, and
+    /// `?concurrency=` bounds the number of parallel upstream fetches.
+    pub(super) async fn handle_prefetch_request(
+        &self,
+        req: Request<Body>,
+    ) -> Result<(Response<Body>, CacheStatus)> {
+        #[derive(Deserialize, Default)]
+        struct Parameters {
+            lockfile: Option<String>,
+            concurrency: Option<usize>,
+        }
+
+        let Parameters {
+            lockfile,
+            concurrency,
+        } = Query::parse_query_str(req.uri().query_or_empty().as_ref())
+            .map(|q| q.0)
+            .unwrap_or_default();
+
+        let client = ClientBudget::of(req.extensions()).cloned();
+        let lockfile = match handlers::read_posted_lockfile(req, lockfile.as_deref()).await? {
+            Ok(lockfile) => lockfile,
+            Err(resp) => return Ok((resp, CacheStatus::Pass)),
+        };
+
+        let report = self
+            .prefetch_for(
+                &lockfile,
+                concurrency.unwrap_or(DEFAULT_PREFETCH_CONCURRENCY),
+                client,
+            )
+            .await;
+        let body = serde_json::to_string_pretty(&report)?;
+        Ok((respond_json(StatusCode::OK, &body)?, CacheStatus::Pass))
+    }
+}
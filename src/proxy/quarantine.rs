@@ -3,19 +3,30 @@
 //! Filters quarantined versions from compact index responses to prevent
 //! `bundle update` and `bundle outdated` from seeing versions still in quarantine.
 
+use std::cmp::Ordering;
+
 use anyhow::Result;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use rama::telemetry::tracing::{debug, warn};
 use vein_adapter::{
-    CacheBackend, CacheBackendTrait, GemVersion, VersionStatus, calculate_availability,
-    is_version_available,
+    CacheBackend, CacheBackendTrait, Ecosystem, GemMetadata, GemVersion, VersionStatus,
+    calculate_availability, is_version_available,
 };
 
-use crate::config::DelayPolicyConfig;
+use crate::config::{DelayPolicyConfig, NotificationKind};
+use crate::gem_requirement::compare_versions;
+use crate::notifications::{self, Notification};
+use crate::quarantine::dependencies::{DependencyResolver, parse_compact_dependencies};
+use crate::quarantine::risk::{self, RiskAssessment};
+
+/// Earlier versions checked for metadata when evaluating risk rules.
+const PREVIOUS_VERSION_CANDIDATES: usize = 3;
 
 /// Records a new gem version in the quarantine system.
 ///
-/// Called when a gem is fetched from upstream for the first time.
+/// Called when a gem is fetched from upstream for the first time. Configured
+/// risk rules can lengthen the delay or hold the version for approval;
+/// `first_version` is whether upstream lists no other versions of the gem.
 pub async fn record_new_version(
     config: &DelayPolicyConfig,
     index: &CacheBackend,
@@ -23,6 +34,7 @@ pub async fn record_new_version(
     version: &str,
     platform: Option<&str>,
     sha256: &str,
+    first_version: bool,
 ) -> Result<()> {
     if !config.enabled {
         return Ok(());
@@ -36,7 +48,6 @@ pub async fn record_new_version(
 
     let delay_days = config.delay_for_gem(name);
     let now = Utc::now();
-    let available_after = calculate_availability(now, &config.to_adapter_policy());
 
     let status = if delay_days == 0 || config.is_pinned(name, version) {
         VersionStatus::Pinned
@@ -44,13 +55,27 @@ pub async fn record_new_version(
         VersionStatus::Quarantine
     };
 
+    // Zero-delay and pinned gems are trusted; risk rules only lengthen real delays
+    let risk = if status == VersionStatus::Quarantine && config.risk.is_enabled() {
+        assess_risk(config, index, name, version, platform, first_version).await
+    } else {
+        RiskAssessment::default()
+    };
+
+    let available_after = if risk.requires_approval() {
+        risk::manual_approval_hold()
+    } else {
+        let delay_days = delay_days.saturating_add(risk.extra_days());
+        calculate_availability(now, &config.adapter_policy_with_delay(delay_days))
+    };
+
     let status_reason = if status == VersionStatus::Pinned {
         config
             .pin_reason(name, version)
             .map(|r| format!("pinned: {}", r))
             .or_else(|| Some("zero-delay gem".to_string()))
     } else {
-        Some("auto".to_string())
+        Some(risk.reason().unwrap_or_else(|| "auto".to_string()))
     };
 
     let gem_version = GemVersion {
@@ -62,7 +87,7 @@ pub async fn record_new_version(
         published_at: now,
         available_after,
         status,
-        status_reason,
+        status_reason: status_reason.clone(),
         upstream_yanked: false,
         created_at: now,
         updated_at: now,
@@ -70,26 +95,224 @@ pub async fn record_new_version(
 
     index.upsert_gem_version(&gem_version).await?;
 
+    if status == VersionStatus::Quarantine {
+        let release = if risk.requires_approval() {
+            "awaiting manual approval".to_string()
+        } else {
+            format!("available after {}", available_after.to_rfc3339())
+        };
+        let reason = match risk.reason() {
+            Some(risk_reason) => format!("{release} ({risk_reason})"),
+            None => release,
+        };
+        notifications::emit(Notification::new(
+            NotificationKind::Quarantined,
+            Ecosystem::RubyGems,
+            name,
+            version,
+            platform,
+            Some(&reason),
+        ));
+    }
+
     debug!(
         gem = %name,
         version = %version,
         status = %status,
         available_after = %available_after,
+        reason = ?status_reason,
         "Recorded new gem version in quarantine system"
     );
 
     Ok(())
 }
 
+/// Holds a gem version for manual approval before it is tracked, e.g. when a
+/// policy flags it on first download. Pinned and already tracked versions are
+/// left alone; returns whether the version was held.
+pub async fn hold_for_approval(
+    config: &DelayPolicyConfig,
+    index: &CacheBackend,
+    name: &str,
+    version: &str,
+    platform: Option<&str>,
+    reason: &str,
+) -> Result<bool> {
+    hold_version(
+        config,
+        index,
+        name,
+        version,
+        platform,
+        risk::manual_approval_hold(),
+        reason,
+    )
+    .await
+}
+
+/// Like [`hold_for_approval`], but releases the version after the gem's
+/// regular quarantine delay.
+pub async fn hold_for_delay(
+    config: &DelayPolicyConfig,
+    index: &CacheBackend,
+    name: &str,
+    version: &str,
+    platform: Option<&str>,
+    reason: &str,
+) -> Result<bool> {
+    let available_after = calculate_availability(
+        Utc::now(),
+        &config.adapter_policy_with_delay(config.delay_for_gem(name)),
+    );
+    hold_version(
+        config,
+        index,
+        name,
+        version,
+        platform,
+        available_after,
+        reason,
+    )
+    .await
+}
+
+async fn hold_version(
+    config: &DelayPolicyConfig,
+    index: &CacheBackend,
+    name: &str,
+    version: &str,
+    platform: Option<&str>,
+    available_after: DateTime<Utc>,
+    reason: &str,
+) -> Result<bool> {
+    if !config.enabled
+        || config.is_pinned(name, version)
+        || index
+            .get_gem_version(name, version, platform)
+            .await?
+            .is_some()
+    {
+        return Ok(false);
+    }
+
+    let now = Utc::now();
+    index
+        .upsert_gem_version(&GemVersion {
+            id: 0,
+            name: name.to_string(),
+            version: version.to_string(),
+            platform: platform.map(String::from),
+            sha256: None,
+            published_at: now,
+            available_after,
+            status: VersionStatus::Quarantine,
+            status_reason: Some(reason.to_string()),
+            upstream_yanked: false,
+            created_at: now,
+            updated_at: now,
+        })
+        .await?;
+    let release = if available_after >= risk::manual_approval_hold() {
+        "awaiting manual approval".to_string()
+    } else {
+        format!("available after {}", available_after.to_rfc3339())
+    };
+    notifications::emit(Notification::new(
+        NotificationKind::Quarantined,
+        Ecosystem::RubyGems,
+        name,
+        version,
+        platform,
+        Some(&format!("{release} ({reason})")),
+    ));
+    debug!(
+        gem = %name,
+        version = %version,
+        available_after = %available_after,
+        reason = %reason,
+        "Held gem version"
+    );
+
+    Ok(true)
+}
+
+/// Evaluates risk rules against the new version and the closest earlier one.
+async fn assess_risk(
+    config: &DelayPolicyConfig,
+    index: &CacheBackend,
+    name: &str,
+    version: &str,
+    platform: Option<&str>,
+    first_version: bool,
+) -> RiskAssessment {
+    let platform = platform.unwrap_or("ruby");
+    let current = match index.gem_metadata(name, version, Some(platform)).await {
+        Ok(current) => current,
+        Err(err) => {
+            warn!(error = %err, gem = %name, version = %version, "Failed to load metadata for risk rules");
+            None
+        }
+    };
+    let previous = previous_metadata(index, name, version, platform).await;
+    let findings = match index.gem_findings(name, version, Some(platform)).await {
+        Ok(findings) => findings,
+        Err(err) => {
+            warn!(error = %err, gem = %name, version = %version, "Failed to load content findings for risk rules");
+            Vec::new()
+        }
+    };
+
+    risk::assess(
+        &config.risk,
+        current.as_ref(),
+        previous.as_ref(),
+        &findings,
+        first_version,
+    )
+}
+
+/// Metadata of the highest tracked version below `version` on the same platform.
+async fn previous_metadata(
+    index: &CacheBackend,
+    name: &str,
+    version: &str,
+    platform: &str,
+) -> Option<GemMetadata> {
+    let mut earlier: Vec<String> = match index.get_gem_versions_for_index(name).await {
+        Ok(versions) => versions
+            .into_iter()
+            .filter(|v| v.platform.as_deref().unwrap_or("ruby") == platform)
+            .map(|v| v.version)
+            .filter(|v| compare_versions(v, version) == Ordering::Less)
+            .collect(),
+        Err(err) => {
+            warn!(error = %err, gem = %name, "Failed to list earlier versions for risk rules");
+            return None;
+        }
+    };
+    earlier.sort_by(|a, b| compare_versions(b, a));
+
+    for candidate in earlier.iter().take(PREVIOUS_VERSION_CANDIDATES) {
+        if let Ok(Some(metadata)) = index.gem_metadata(name, candidate, Some(platform)).await {
+            return Some(metadata);
+        }
+    }
+    None
+}
+
 /// Filters quarantined versions from a compact index `/info/{gem}` response.
 ///
 /// The compact index format is:
 /// ```text
 /// ---
-/// 1.0.0 |checksum|dep1,dep2
-/// 1.1.0 platform|checksum|dep1
+/// 1.0.0 |checksum:abc123
+/// 1.1.0 dep1:>= 1.0,dep2:~> 2.0&< 2.5|checksum:def456,ruby:>= 2.7
+/// 1.1.0-x86_64-linux dep1:>= 1.0|checksum:789abc
 /// ```
 ///
+/// With `hold_dependents`, versions whose dependencies can only be satisfied
+/// by quarantined versions are hidden as well.
+///
 /// Returns the filtered response body.
 pub async fn filter_compact_info(
     config: &DelayPolicyConfig,
@@ -121,11 +344,6 @@ pub async fn filter_compact_info(
         }
     };
 
-    // If no versions tracked yet, pass through (quarantine system not populated for this gem)
-    if versions.is_empty() {
-        return Ok(body.to_vec());
-    }
-
     // Build a set of quarantined versions
     let quarantined: std::collections::HashSet<String> = versions
         .iter()
@@ -133,10 +351,13 @@ pub async fn filter_compact_info(
         .map(|v| format_version_key(&v.version, v.platform.as_deref()))
         .collect();
 
-    if quarantined.is_empty() {
-        return Ok(body.to_vec()); // Nothing to filter
+    // Nothing tracked or quarantined for this gem, and dependents are not held
+    if quarantined.is_empty() && !config.hold_dependents {
+        return Ok(body.to_vec());
     }
 
+    let mut resolver = DependencyResolver::new(index);
+
     // Filter the compact index lines
     let mut output_lines = Vec::new();
     for line in body_str.lines() {
@@ -146,10 +367,12 @@ pub async fn filter_compact_info(
         }
 
         // Parse the line to extract version and platform
-        // Format: "version platform|checksum|deps" or "version |checksum|deps"
-        if let Some((version_key, _rest)) = parse_compact_line(line)
-            && quarantined.contains(&version_key)
-        {
+        let Some((version_key, _rest)) = parse_compact_line(line) else {
+            output_lines.push(line);
+            continue;
+        };
+
+        if quarantined.contains(&version_key) {
             debug!(
                 gem = %gem_name,
                 version_key = %version_key,
@@ -158,6 +381,30 @@ pub async fn filter_compact_info(
             continue; // Skip this line
         }
 
+        if config.hold_dependents {
+            let dependencies = parse_compact_dependencies(compact_dependency_column(line));
+            match resolver.blockers(&dependencies, 0).await {
+                Ok(blocked) if !blocked.is_empty() => {
+                    debug!(
+                        gem = %gem_name,
+                        version_key = %version_key,
+                        blocked_by = %blocked[0].chain.join(" -> "),
+                        "Holding version until its dependencies leave quarantine"
+                    );
+                    continue;
+                }
+                Ok(_) => {}
+                Err(err) => {
+                    warn!(
+                        error = %err,
+                        gem = %gem_name,
+                        version_key = %version_key,
+                        "Failed to resolve dependency quarantine, keeping version"
+                    );
+                }
+            }
+        }
+
         output_lines.push(line);
     }
 
@@ -173,15 +420,28 @@ fn parse_compact_line(line: &str) -> Option<(String, &str)> {
     let version_part = line[..pipe_pos].trim();
     let rest = &line[pipe_pos..];
 
-    // version_part is either "1.0.0" or "1.0.0 x86_64-linux"
+    // version_part is "1.0.0", "1.0.0-x86_64-linux" or "1.0.0 x86_64-linux",
+    // optionally followed by the dependency column
     let mut parts = version_part.split_whitespace();
-    let version = parts.next()?;
-    let platform = parts.next();
+    let token = parts.next()?;
+    let (version, platform) = match token.split_once('-') {
+        Some((version, platform)) => (version, Some(platform)),
+        None => (token, parts.next().filter(|part| !part.contains(':'))),
+    };
 
     let key = format_version_key(version, platform);
     Some((key, rest))
 }
 
+/// Returns the dependency column of a compact index line (`dep1:>= 1.0,dep2:~> 2.0`).
+fn compact_dependency_column(line: &str) -> &str {
+    let version_part = line.split('|').next().unwrap_or_default().trim();
+    match version_part.split_once(' ') {
+        Some((_, dependencies)) if dependencies.contains(':') => dependencies.trim(),
+        _ => "",
+    }
+}
+
 /// Formats a version key for lookup.
 fn format_version_key(version: &str, platform: Option<&str>) -> String {
     match platform {
@@ -214,6 +474,19 @@ mod tests {
         assert_eq!(key, "1.0.0"); // ruby platform treated as no platform
     }
 
+    #[test]
+    fn test_parse_compact_line_with_dependencies() {
+        let line = "2.0.0 bar:>= 3.1&< 4,baz:~> 1.0|checksum:abc,ruby:>= 2.7";
+        let (key, rest) = parse_compact_line(line).unwrap();
+        assert_eq!(key, "2.0.0");
+        assert_eq!(rest, "|checksum:abc,ruby:>= 2.7");
+        assert_eq!(compact_dependency_column(line), "bar:>= 3.1&< 4,baz:~> 1.0");
+
+        let (key, _) = parse_compact_line("2.0.0-java bar:= 1|checksum:abc").unwrap();
+        assert_eq!(key, "2.0.0:java");
+        assert_eq!(compact_dependency_column("1.0.0 |checksum:abc"), "");
+    }
+
     #[test]
     fn test_format_version_key() {
         assert_eq!(format_version_key("1.0.0", None), "1.0.0");
@@ -223,4 +496,93 @@ mod tests {
             "1.0.0:x86_64-linux"
         );
     }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn hold_dependents_hides_versions_waiting_on_quarantined_deps() {
+        let index = CacheBackend::connect_memory().await.unwrap();
+        let now = Utc::now();
+        for (version, status) in [
+            ("3.0.0", VersionStatus::Available),
+            ("3.1.0", VersionStatus::Quarantine),
+        ] {
+            index
+                .upsert_gem_version(&GemVersion {
+                    id: 0,
+                    name: "bar".to_string(),
+                    version: version.to_string(),
+                    platform: None,
+                    sha256: None,
+                    published_at: now,
+                    available_after: now + chrono::Duration::days(2),
+                    status,
+                    status_reason: None,
+                    upstream_yanked: false,
+                    created_at: now,
+                    updated_at: now,
+                })
+                .await
+                .unwrap();
+        }
+
+        let body = "---\n1.0.0 bar:>= 3.0|checksum:a\n2.0.0 bar:>= 3.1&< 4|checksum:b";
+        let mut config = DelayPolicyConfig {
+            enabled: true,
+            ..Default::default()
+        };
+
+        let unfiltered = filter_compact_info(&config, &index, "foo", body.as_bytes())
+            .await
+            .unwrap();
+        assert_eq!(String::from_utf8(unfiltered).unwrap(), body);
+
+        config.hold_dependents = true;
+        let filtered = filter_compact_info(&config, &index, "foo", body.as_bytes())
+            .await
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(filtered).unwrap(),
+            "---\n1.0.0 bar:>= 3.0|checksum:a"
+        );
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn risk_rules_extend_delay_and_record_reason() {
+        let index = CacheBackend::connect_memory().await.unwrap();
+        let mut config = DelayPolicyConfig {
+            enabled: true,
+            ..Default::default()
+        };
+        config.risk.first_version = Some(crate::config::RiskRule {
+            add_days: 0,
+            require_approval: true,
+        });
+
+        record_new_version(&config, &index, "brand-new", "0.1.0", None, "", true)
+            .await
+            .unwrap();
+        record_new_version(&config, &index, "rack", "3.0.0", None, "", false)
+            .await
+            .unwrap();
+
+        let held = index
+            .get_gem_version("brand-new", "0.1.0", None)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(risk::awaits_manual_approval(&held));
+        assert_eq!(
+            held.status_reason.as_deref(),
+            Some("risk: first_version approval")
+        );
+
+        let regular = index
+            .get_gem_version("rack", "3.0.0", None)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(!risk::awaits_manual_approval(&regular));
+        assert_eq!(regular.status_reason.as_deref(), Some("auto"));
+    }
 }
@@ -0,0 +1,43 @@
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+
+use crate::config::Config;
+
+use super::{LiveState, VeinProxy, build_upstreams};
+
+impl VeinProxy {
+    /// Swaps in `next` for requests that start after this call; requests
+    /// already in flight finish on the config they started with.
+    ///
+    /// Settings that need a restart keep their running values. Their names
+    /// are returned so the caller can report them. Mirrors whose URL is
+    /// unchanged keep their breaker and health statistics.
+    pub fn reload(&self, mut next: Config) -> Result<Vec<&'static str>> {
+        next.validate().context("validating configuration")?;
+
+        let mut live = self.live.write();
+        let kept = next.keep_restart_settings(&live.config);
+        let mut upstreams = build_upstreams(&next)?;
+        for upstream in &mut upstreams {
+            if let Some(previous) = live
+                .upstreams
+                .iter()
+                .find(|previous| previous.base == upstream.base)
+            {
+                upstream.client.health = previous.client.health.clone();
+            }
+        }
+
+        *live = LiveState {
+            config: Arc::new(next),
+            upstreams: Arc::new(upstreams),
+        };
+        Ok(kept)
+    }
+
+    /// The configuration new requests run against.
+    pub fn config(&self) -> Arc<Config> {
+        self.live.read().config.clone()
+    }
+}
@@ -3,10 +3,11 @@ use rama::http::{Body, HeaderMap, Response, StatusCode, header};
 
 use crate::config::Config;
 
-/// Builds a `no-store` JSON response, letting the caller add request-specific
+/// Builds a `no-store` response, letting the caller add request-specific
 /// headers (content-length, content-disposition, ...) before the body is set.
-fn json_response(
+fn no_store_response(
     status: StatusCode,
+    content_type: &'static str,
     body: &str,
     extra: impl FnOnce(&mut HeaderMap) -> Result<()>,
 ) -> Result<Response<Body>> {
@@ -17,7 +18,7 @@ fn json_response(
             .expect("headers available while building response");
         headers.insert(
             header::CONTENT_TYPE,
-            header::HeaderValue::from_static("application/json; charset=utf-8"),
+            header::HeaderValue::from_static(content_type),
         );
         headers.insert(
             header::CACHE_CONTROL,
@@ -32,7 +33,7 @@ fn json_response(
 
 /// Responds with JSON content
 pub fn respond_json(status: StatusCode, body: &str) -> Result<Response<Body>> {
-    json_response(status, body, |headers| {
+    no_store_response(status, "application/json; charset=utf-8", body, |headers| {
         headers.insert(
             header::CONTENT_LENGTH,
             header::HeaderValue::from_str(&body.len().to_string())?,
@@ -41,9 +42,13 @@ pub fn respond_json(status: StatusCode, body: &str) -> Result<Response<Body>> {
     })
 }
 
-/// Responds with JSON content as a downloadable attachment
-pub fn respond_json_download(body: &str, filename: &str) -> Result<Response<Body>> {
-    json_response(StatusCode::OK, body, |headers| {
+/// Responds with a document (JSON, SPDX tag-value, ...) as a downloadable attachment
+pub fn respond_download(
+    body: &str,
+    filename: &str,
+    content_type: &'static str,
+) -> Result<Response<Body>> {
+    no_store_response(StatusCode::OK, content_type, body, |headers| {
         headers.insert(
             header::CONTENT_DISPOSITION,
             header::HeaderValue::from_str(&format!("attachment; filename=\"{filename}\""))?,
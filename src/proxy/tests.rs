@@ -1,8 +1,8 @@
 use super::*;
 use crate::config::Config;
-use rama::http::{Body, Method, Request};
+use rama::http::{Body, Method, Request, Response};
 use rama::net::{Protocol, uri::Uri};
-use rama::{Service, http::body::util::BodyExt, tls::rustls::dep::rustls};
+use rama::{Layer, Service, http::body::util::BodyExt, tls::rustls::dep::rustls};
 use std::{
     path::Path,
     sync::{Arc, Once},
@@ -346,7 +346,7 @@ async fn proxy_routes_npm_requests_by_header() {
     install_rustls_provider();
 
     let temp_dir = tempdir().unwrap();
-    let proxy = build_test_proxy(temp_dir.path()).await;
+    let proxy = build_test_proxy_with(temp_dir.path(), revalidating_config()).await;
 
     let metadata = br#"{
         "name": "lodash",
@@ -422,7 +422,7 @@ async fn proxy_routes_crates_index_requests_by_path() {
     install_rustls_provider();
 
     let temp_dir = tempdir().unwrap();
-    let proxy = build_test_proxy(temp_dir.path()).await;
+    let proxy = build_test_proxy_with(temp_dir.path(), revalidating_config()).await;
 
     let body = b"{\"name\":\"serde\"}\n";
     let (index_base, server) = spawn_sequence_server(vec![
@@ -454,9 +454,710 @@ async fn proxy_routes_crates_index_requests_by_path() {
     assert!(requests[1].contains("if-none-match: \"serde-v1\""));
 }
 
+#[cfg(feature = "sqlite")]
+#[tokio::test]
+async fn proxy_blocks_cached_versions_yanked_upstream() {
+    use sha2::{Digest, Sha256};
+    use vein_adapter::{AssetKey, CacheBackendTrait, Ecosystem, UpstreamYank};
+
+    let temp_dir = tempdir().unwrap();
+    let mut config = Config::default();
+    config.yank_sync.block_downloads = true;
+    let proxy = build_test_proxy_with(temp_dir.path(), config).await;
+
+    let relative_path = "gems/rack/rack-3.0.0.gem";
+    let file = proxy.storage.resolve(relative_path);
+    std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+    std::fs::write(&file, b"gem-bytes").unwrap();
+    let key = AssetKey {
+        kind: AssetKind::Gem,
+        name: "rack",
+        version: "3.0.0",
+        platform: None,
+    };
+    proxy
+        .index
+        .insert_or_replace(
+            &key,
+            relative_path,
+            &hex::encode(Sha256::digest(b"gem-bytes")),
+            9,
+        )
+        .await
+        .unwrap();
+
+    let response = proxy.serve(req("/gems/rack-3.0.0.gem")).await.unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+
+    proxy
+        .index
+        .record_upstream_yank(&UpstreamYank {
+            ecosystem: Ecosystem::RubyGems,
+            name: "rack".to_string(),
+            version: "3.0.0".to_string(),
+            platform: None,
+            reason: "yanked".to_string(),
+            detected_at: chrono::Utc::now(),
+        })
+        .await
+        .unwrap();
+
+    let response = proxy.serve(req("/gems/rack-3.0.0.gem")).await.unwrap();
+    assert_eq!(response.status().as_u16(), 410);
+}
+
+#[cfg(feature = "sqlite")]
+#[tokio::test]
+async fn proxy_blocks_suspicious_new_names() {
+    use sha2::{Digest, Sha256};
+    use vein_adapter::{AssetKey, CacheBackendTrait};
+
+    let temp_dir = tempdir().unwrap();
+    let mut config = Config::default();
+    config.typosquat.enabled = true;
+    config.typosquat.action = crate::config::TyposquatAction::Block;
+    config.typosquat.internal_prefixes = vec!["acme-".to_string()];
+    let proxy = build_test_proxy_with(temp_dir.path(), config).await;
+
+    let relative_path = "gems/rails/rails-7.0.0.gem";
+    let file = proxy.storage.resolve(relative_path);
+    std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+    std::fs::write(&file, b"gem-bytes").unwrap();
+    proxy
+        .index
+        .insert_or_replace(
+            &AssetKey {
+                kind: AssetKind::Gem,
+                name: "rails",
+                version: "7.0.0",
+                platform: None,
+            },
+            relative_path,
+            &hex::encode(Sha256::digest(b"gem-bytes")),
+            9,
+        )
+        .await
+        .unwrap();
+
+    let response = proxy.serve(req("/gems/rials-7.0.0.gem")).await.unwrap();
+    assert_eq!(response.status().as_u16(), 403);
+    let body = String::from_utf8(body_bytes(response).await).unwrap();
+    assert!(body.contains("looks like rails (edit_distance)"));
+
+    let response = proxy
+        .serve(req("/gems/acme-billing-1.0.0.gem"))
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 403);
+
+    let response = proxy.serve(req("/gems/rails-7.0.0.gem")).await.unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+
+    let names = proxy.index.suspicious_names(10).await.unwrap();
+    let mut flagged: Vec<(&str, &str)> = names
+        .iter()
+        .map(|name| (name.name.as_str(), name.technique.as_str()))
+        .collect();
+    flagged.sort_unstable();
+    assert_eq!(
+        flagged,
+        [
+            ("acme-billing", "internal_prefix"),
+            ("rials", "edit_distance")
+        ]
+    );
+}
+
+#[cfg(feature = "sqlite")]
+#[tokio::test]
+async fn proxy_applies_attribute_rules_to_new_npm_tarballs() {
+    use crate::config::{PolicyAction, PolicyRule};
+    use flate2::{Compression, write::GzEncoder};
+    use vein_adapter::Ecosystem;
+
+    install_rustls_provider();
+    let package_json =
+        br#"{"name":"evil","version":"1.0.0","scripts":{"postinstall":"node x.js"}}"#;
+    let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+    let mut header = tar::Header::new_gnu();
+    header.set_size(package_json.len() as u64);
+    header.set_mode(0o644);
+    builder
+        .append_data(&mut header, "package/package.json", &package_json[..])
+        .unwrap();
+    let tarball = builder.into_inner().unwrap().finish().unwrap();
+    let (registry_base, server) = spawn_sequence_server(vec![raw_response(
+        "200 OK",
+        &[("Content-Type", "application/octet-stream")],
+        &tarball,
+    )])
+    .await;
+    let _guard = crate::npm::override_npm_registry_base(registry_base);
+
+    let temp_dir = tempdir().unwrap();
+    let mut config = Config::default();
+    config.policy.enabled = true;
+    config.policy.rules = vec![PolicyRule {
+        id: "no-install-scripts".to_string(),
+        ecosystems: vec![Ecosystem::Npm],
+        packages: Vec::new(),
+        versions: None,
+        platforms: Vec::new(),
+        attributes: [("install_scripts".to_string(), serde_json::json!(true))].into(),
+        action: PolicyAction::Deny,
+        reason: None,
+    }];
+    let proxy = build_test_proxy_with(temp_dir.path(), config).await;
+
+    let response = proxy
+        .serve(req_with_headers(
+            "/evil/-/evil-1.0.0.tgz",
+            &[("npm-command", "install")],
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 403);
+    assert_eq!(
+        response.headers()["x-vein-policy-rule"],
+        "no-install-scripts"
+    );
+    assert_eq!(server.await.unwrap().len(), 1);
+}
+
+#[cfg(feature = "sqlite")]
+#[tokio::test]
+async fn proxy_reverifies_cached_files_that_change() {
+    use sha2::{Digest, Sha256};
+    use vein_adapter::{AssetKey, CacheBackendTrait};
+
+    let temp_dir = tempdir().unwrap();
+    let mut config = Config::default();
+    config.offline.enabled = true;
+    let proxy = build_test_proxy_with(temp_dir.path(), config).await;
+
+    let relative_path = "gems/rack/rack-3.0.0.gem";
+    let file = proxy.storage.resolve(relative_path);
+    std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+    std::fs::write(&file, b"gem-bytes").unwrap();
+    proxy
+        .index
+        .insert_or_replace(
+            &AssetKey {
+                kind: AssetKind::Gem,
+                name: "rack",
+                version: "3.0.0",
+                platform: None,
+            },
+            relative_path,
+            &hex::encode(Sha256::digest(b"gem-bytes")),
+            9,
+        )
+        .await
+        .unwrap();
+
+    for _ in 0..2 {
+        let response = proxy.serve(req("/gems/rack-3.0.0.gem")).await.unwrap();
+        assert_eq!(response.status().as_u16(), 200);
+    }
+
+    // Same size, new contents and a later modification time
+    std::fs::write(&file, b"bad-bytes").unwrap();
+    let later = std::time::SystemTime::now() + std::time::Duration::from_secs(60);
+    std::fs::File::options()
+        .write(true)
+        .open(&file)
+        .unwrap()
+        .set_modified(later)
+        .unwrap();
+
+    let response = proxy.serve(req("/gems/rack-3.0.0.gem")).await.unwrap();
+    assert_eq!(response.status().as_u16(), 504);
+}
+
+#[cfg(feature = "sqlite")]
+#[tokio::test]
+async fn proxy_enforces_license_policy_on_cached_gems() {
+    use sha2::{Digest, Sha256};
+    use vein_adapter::{AssetKey, CacheBackendTrait, GemMetadata};
+
+    let temp_dir = tempdir().unwrap();
+    let mut config = Config::default();
+    config.license_policy.enabled = true;
+    config.license_policy.action = crate::config::LicenseAction::Block;
+    config.license_policy.deny = vec!["GPL-*".to_string()];
+    config.license_policy.rules = vec![crate::config::LicenseRule {
+        ecosystems: Vec::new(),
+        packages: vec!["legacy-*".to_string()],
+        allow: Vec::new(),
+        deny: vec!["GPL-*".to_string()],
+        action: Some(crate::config::LicenseAction::Warn),
+    }];
+    let proxy = build_test_proxy_with(temp_dir.path(), config).await;
+
+    for (name, license) in [
+        ("copyleft", "GPL-3.0-only"),
+        ("legacy-copyleft", "GPL-2.0-or-later"),
+        ("permissive", "MIT"),
+    ] {
+        let relative_path = format!("gems/{name}/{name}-1.0.0.gem");
+        let file = proxy.storage.resolve(&relative_path);
+        std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+        std::fs::write(&file, b"gem-bytes").unwrap();
+        proxy
+            .index
+            .insert_or_replace(
+                &AssetKey {
+                    kind: AssetKind::Gem,
+                    name,
+                    version: "1.0.0",
+                    platform: None,
+                },
+                &relative_path,
+                &hex::encode(Sha256::digest(b"gem-bytes")),
+                9,
+            )
+            .await
+            .unwrap();
+        proxy
+            .index
+            .upsert_metadata(&GemMetadata {
+                name: name.to_string(),
+                version: "1.0.0".to_string(),
+                platform: "ruby".to_string(),
+                licenses: vec![license.to_string()],
+                size_bytes: 9,
+                sha256: hex::encode(Sha256::digest(b"gem-bytes")),
+                ..GemMetadata::default()
+            })
+            .await
+            .unwrap();
+    }
+
+    let response = proxy.serve(req("/gems/copyleft-1.0.0.gem")).await.unwrap();
+    assert_eq!(response.status().as_u16(), 403);
+    let body = String::from_utf8(body_bytes(response).await).unwrap();
+    assert!(body.contains("blocked by license policy"));
+    assert!(body.contains("GPL-3.0-only"));
+
+    let response = proxy
+        .serve(req("/gems/legacy-copyleft-1.0.0.gem"))
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+    assert!(response.headers().contains_key("x-vein-license-warning"));
+
+    let response = proxy
+        .serve(req("/gems/permissive-1.0.0.gem"))
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+    assert!(!response.headers().contains_key("x-vein-license-warning"));
+}
+
+#[cfg(feature = "sqlite")]
+#[tokio::test]
+async fn proxy_applies_policy_rules_to_downloads_and_index() {
+    use crate::config::{PolicyAction, PolicyRule, UpstreamConfig};
+    use sha2::{Digest, Sha256};
+    use vein_adapter::{AssetKey, CacheBackendTrait, Ecosystem, StoredPolicyRule};
+
+    install_rustls_provider();
+    let info = b"---\n2.2.0 |checksum:aa\n3.0.0 |checksum:bb\n";
+    let (upstream, server) = spawn_sequence_server(vec![raw_response(
+        "200 OK",
+        &[("Content-Type", "text/plain")],
+        info,
+    )])
+    .await;
+
+    let temp_dir = tempdir().unwrap();
+    let mut config = Config {
+        upstream: Some(UpstreamConfig {
+            url: Uri::try_from(format!("{upstream}/")).unwrap(),
+            ..UpstreamConfig::default()
+        }),
+        ..Config::default()
+    };
+    config.delay_policy.enabled = true;
+    config.policy.enabled = true;
+    config.policy.rules = vec![PolicyRule {
+        id: "old-rack".to_string(),
+        ecosystems: vec![Ecosystem::RubyGems],
+        packages: vec!["rack".to_string()],
+        versions: Some("< 3".to_string()),
+        platforms: Vec::new(),
+        attributes: Default::default(),
+        action: PolicyAction::Deny,
+        reason: Some("end of life".to_string()),
+    }];
+    let proxy = build_test_proxy_with(temp_dir.path(), config).await;
+    proxy
+        .index
+        .save_policy_rule(&StoredPolicyRule {
+            id: "review-nokogiri".to_string(),
+            ecosystem: Some(Ecosystem::RubyGems),
+            package: "nokogiri".to_string(),
+            versions: None,
+            platform: None,
+            attributes: serde_json::json!({}),
+            action: "require_approval".to_string(),
+            reason: None,
+            created_at: chrono::Utc::now(),
+        })
+        .await
+        .unwrap();
+
+    for (name, version) in [("rack", "2.2.0"), ("rack", "3.0.0"), ("nokogiri", "1.0.0")] {
+        let relative_path = format!("gems/{name}/{name}-{version}.gem");
+        let file = proxy.storage.resolve(&relative_path);
+        std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+        std::fs::write(&file, b"gem-bytes").unwrap();
+        proxy
+            .index
+            .insert_or_replace(
+                &AssetKey {
+                    kind: AssetKind::Gem,
+                    name,
+                    version,
+                    platform: None,
+                },
+                &relative_path,
+                &hex::encode(Sha256::digest(b"gem-bytes")),
+                9,
+            )
+            .await
+            .unwrap();
+    }
+
+    let response = proxy.serve(req("/gems/rack-2.2.0.gem")).await.unwrap();
+    assert_eq!(response.status().as_u16(), 403);
+    assert_eq!(response.headers()["x-vein-policy-rule"], "old-rack");
+    let body = String::from_utf8(body_bytes(response).await).unwrap();
+    assert!(body.contains("denied by policy rule old-rack: end of life"));
+
+    let response = proxy.serve(req("/gems/rack-3.0.0.gem")).await.unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = proxy.serve(req("/gems/nokogiri-1.0.0.gem")).await.unwrap();
+    assert_eq!(response.status().as_u16(), 403);
+    assert_eq!(response.headers()["x-vein-policy-rule"], "review-nokogiri");
+    let held = proxy
+        .index
+        .get_gem_version("nokogiri", "1.0.0", None)
+        .await
+        .unwrap()
+        .expect("held version is tracked");
+    assert!(crate::quarantine::risk::awaits_manual_approval(&held));
+
+    let response = proxy.serve(req("/info/rack")).await.unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+    let body = String::from_utf8(body_bytes(response).await).unwrap();
+    assert!(!body.contains("2.2.0"));
+    assert!(body.contains("3.0.0 |checksum:bb"));
+    server.await.unwrap();
+}
+
+#[cfg(feature = "sqlite")]
+#[tokio::test]
+async fn proxy_reports_advisories_and_blocks_critical_versions() {
+    use vein_adapter::{
+        Advisory, AdvisorySeverity, AffectedPackage, AffectedRange, CacheBackendTrait, Ecosystem,
+        RangeEvent,
+    };
+
+    let temp_dir = tempdir().unwrap();
+    let mut config = Config::default();
+    config.advisories.block_severity = Some(AdvisorySeverity::Critical);
+    let proxy = build_test_proxy_with(temp_dir.path(), config).await;
+
+    proxy
+        .index
+        .upsert_advisory(&Advisory {
+            id: "GHSA-rack-0001".to_string(),
+            summary: Some("Remote code execution".to_string()),
+            details: None,
+            severity: AdvisorySeverity::Critical,
+            aliases: vec!["CVE-2024-0001".to_string()],
+            url: None,
+            published: None,
+            modified: chrono::Utc::now(),
+            withdrawn: None,
+            affected: vec![AffectedPackage {
+                ecosystem: Ecosystem::RubyGems,
+                name: "rack".to_string(),
+                ranges: vec![AffectedRange {
+                    events: vec![
+                        RangeEvent::Introduced("3.0.0".to_string()),
+                        RangeEvent::Fixed("3.0.1".to_string()),
+                    ],
+                }],
+                versions: Vec::new(),
+            }],
+        })
+        .await
+        .unwrap();
+
+    let response = proxy
+        .serve(req(
+            "/.well-known/vein/advisories?ecosystem=rubygems&name=rack&version=3.0.0",
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+    let body: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(body["advisories"][0]["id"], "GHSA-rack-0001");
+    assert_eq!(body["advisories"][0]["severity"], "critical");
+    assert_eq!(body["advisories"][0]["patched_versions"][0], "3.0.1");
+
+    let response = proxy
+        .serve(req("/.well-known/vein/advisories?name=rack&version=3.0.1"))
+        .await
+        .unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(body["advisories"].as_array().unwrap().len(), 0);
+
+    let response = proxy
+        .serve(req("/.well-known/vein/advisories?ecosystem=pypi&name=rack"))
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 400);
+
+    let response = proxy.serve(req("/gems/rack-3.0.0.gem")).await.unwrap();
+    assert_eq!(response.status().as_u16(), 403);
+    let body = String::from_utf8(body_bytes(response).await).unwrap();
+    assert!(body.contains("GHSA-rack-0001"));
+}
+
+#[cfg(feature = "sqlite")]
+#[tokio::test]
+async fn proxy_audits_posted_lockfiles() {
+    use vein_adapter::{Advisory, AdvisorySeverity, AffectedPackage, CacheBackendTrait, Ecosystem};
+
+    let temp_dir = tempdir().unwrap();
+    let proxy = build_test_proxy(temp_dir.path()).await;
+    proxy
+        .index
+        .upsert_advisory(&Advisory {
+            id: "GHSA-rack-0002".to_string(),
+            summary: None,
+            details: None,
+            severity: AdvisorySeverity::High,
+            aliases: Vec::new(),
+            url: None,
+            published: None,
+            modified: chrono::Utc::now(),
+            withdrawn: None,
+            affected: vec![AffectedPackage {
+                ecosystem: Ecosystem::RubyGems,
+                name: "rack".to_string(),
+                ranges: Vec::new(),
+                versions: vec!["3.0.8".to_string()],
+            }],
+        })
+        .await
+        .unwrap();
+
+    let lockfile =
+        "GEM\n  remote: https://rubygems.org/\n  specs:\n    rack (3.0.8)\n    racc (1.7.3)\n";
+    let post = |uri: &str, body: &'static str| {
+        Request::builder()
+            .method(Method::POST)
+            .uri(uri)
+            .body(Body::from(body))
+            .unwrap()
+    };
+
+    let response = proxy
+        .serve(post(
+            "/.well-known/vein/audit?lockfile=Gemfile.lock",
+            lockfile,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+    let body: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(body["lockfile"], "gemfile_lock");
+    assert_eq!(body["passed"], false);
+    assert_eq!(body["summary"]["total"], 2);
+    assert_eq!(body["summary"]["cached"], 0);
+    assert_eq!(body["summary"]["vulnerable"], 1);
+
+    let response = proxy
+        .serve(post(
+            "/.well-known/vein/audit?lockfile=Gemfile.lock&min_severity=critical",
+            lockfile,
+        ))
+        .await
+        .unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(body["passed"], true);
+
+    let response = proxy
+        .serve(post("/.well-known/vein/audit", "not a lockfile"))
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 400);
+
+    // No content-length, as with a chunked upload
+    let oversized = Request::builder()
+        .method(Method::POST)
+        .uri("/.well-known/vein/audit")
+        .body(Body::from(vec![b'#'; 16 * 1024 * 1024 + 1]))
+        .unwrap();
+    assert!(!oversized.headers().contains_key("content-length"));
+    let response = proxy.serve(oversized).await.unwrap();
+    assert_eq!(response.status().as_u16(), 413);
+}
+
+#[cfg(feature = "sqlite")]
+#[tokio::test]
+async fn proxy_prefetches_lockfile_artifacts() {
+    use crate::config::UpstreamConfig;
+    use crate::lockfile::Lockfile;
+    use prefetch::PrefetchOutcome;
+    use vein_adapter::{AssetKey, CacheBackendTrait};
+
+    install_rustls_provider();
+
+    let gem = b"not really a gem".to_vec();
+    let mut ok = format!(
+        "HTTP/1.1 200 OK\r\ncontent-type: application/octet-stream\r\ncontent-length: {}\r\nconnection: close\r\n\r\n",
+        gem.len()
+    )
+    .into_bytes();
+    ok.extend_from_slice(&gem);
+    let not_found =
+        b"HTTP/1.1 404 Not Found\r\ncontent-length: 9\r\nconnection: close\r\n\r\nnot found"
+            .to_vec();
+    let (upstream, server) = spawn_sequence_server(vec![ok, not_found]).await;
+
+    let temp_dir = tempdir().unwrap();
+    let config = Config {
+        upstream: Some(UpstreamConfig {
+            url: Uri::try_from(format!("{upstream}/")).unwrap(),
+            ..UpstreamConfig::default()
+        }),
+        ..Config::default()
+    };
+    let proxy = build_test_proxy_with(temp_dir.path(), config).await;
+
+    let cached_path = "gems/rack/rack-3.0.8.gem";
+    tokio::fs::create_dir_all(proxy.storage.resolve("gems/rack"))
+        .await
+        .unwrap();
+    tokio::fs::write(proxy.storage.resolve(cached_path), b"rack")
+        .await
+        .unwrap();
+    proxy
+        .index
+        .insert_or_replace(
+            &AssetKey {
+                kind: AssetKind::Gem,
+                name: "rack",
+                version: "3.0.8",
+                platform: None,
+            },
+            cached_path,
+            &hex::encode(<sha2::Sha256 as sha2::Digest>::digest(b"rack")),
+            4,
+        )
+        .await
+        .unwrap();
+
+    let lockfile =
+        "GEM\n  remote: https://rubygems.org/\n  specs:\n    racc (1.7.3)\n    rack (3.0.8)\n";
+    let response = proxy
+        .serve(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/.well-known/vein/prefetch?lockfile=Gemfile.lock&concurrency=2")
+                .body(Body::from(lockfile))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+    let body: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(body["summary"]["total"], 2);
+    assert_eq!(body["summary"]["hits"], 1);
+    assert_eq!(body["summary"]["cached"], 1);
+    assert_eq!(body["items"][0]["name"], "racc");
+    assert_eq!(body["items"][0]["outcome"], "cached");
+    assert_eq!(body["items"][1]["outcome"], "hit");
+    assert!(
+        proxy
+            .index
+            .peek(&AssetKey {
+                kind: AssetKind::Gem,
+                name: "racc",
+                version: "1.7.3",
+                platform: None,
+            })
+            .await
+            .unwrap()
+            .is_some()
+    );
+
+    let missing = Lockfile::parse(None, "GEM\n  specs:\n    ghost (1.0.0)\n").unwrap();
+    let report = proxy.prefetch(&missing, 4).await;
+    assert_eq!(report.summary.failed, 1);
+    assert_eq!(report.items[0].outcome, PrefetchOutcome::Failed);
+    assert_eq!(report.items[0].status, 404);
+
+    let requests = server.await.unwrap();
+    assert!(requests[0].starts_with("get /gems/racc-1.7.3.gem"));
+    assert!(requests[1].starts_with("get /gems/ghost-1.0.0.gem"));
+}
+
+#[cfg(feature = "sqlite")]
+#[tokio::test]
+async fn proxy_prefetch_reports_upstream_yanks_as_blocked() {
+    use crate::lockfile::Lockfile;
+    use prefetch::PrefetchOutcome;
+    use vein_adapter::{CacheBackendTrait, Ecosystem, UpstreamYank};
+
+    let temp_dir = tempdir().unwrap();
+    let mut config = Config::default();
+    config.yank_sync.block_downloads = true;
+    let proxy = build_test_proxy_with(temp_dir.path(), config).await;
+    proxy
+        .index
+        .record_upstream_yank(&UpstreamYank {
+            ecosystem: Ecosystem::RubyGems,
+            name: "rack".to_string(),
+            version: "3.0.0".to_string(),
+            platform: None,
+            reason: "yanked".to_string(),
+            detected_at: chrono::Utc::now(),
+        })
+        .await
+        .unwrap();
+
+    let lockfile = Lockfile::parse(None, "GEM\n  specs:\n    rack (3.0.0)\n").unwrap();
+    let report = proxy.prefetch(&lockfile, 1).await;
+    assert_eq!(report.summary.blocked, 1);
+    assert_eq!(report.items[0].outcome, PrefetchOutcome::Blocked);
+    assert_eq!(report.items[0].status, 410);
+}
+
 #[cfg(feature = "sqlite")]
 async fn build_test_proxy(root: &Path) -> VeinProxy {
+    build_test_proxy_with(root, Config::default()).await
+}
+
+/// Treats every cached index entry as expired so each request revalidates.
+#[cfg(feature = "sqlite")]
+fn revalidating_config() -> Config {
     let mut config = Config::default();
+    config.index_cache.compact_index_ttl_secs = 0;
+    config.index_cache.sparse_index_ttl_secs = 0;
+    config.index_cache.npm_metadata_ttl_secs = 0;
+    config.index_cache.stale_while_revalidate_secs = 0;
+    config
+}
+
+#[cfg(feature = "sqlite")]
+async fn build_test_proxy_with(root: &Path, mut config: Config) -> VeinProxy {
     config.server.host = "127.0.0.1".to_string();
     config.server.port = 8346;
     config.storage.path = root.join("cache");
@@ -478,6 +1179,239 @@ fn req_with_headers(path: &str, headers: &[(&str, &str)]) -> Request<Body> {
     builder.body(Body::empty()).unwrap()
 }
 
+#[cfg(feature = "sqlite")]
+fn mirrored_config(primary: &str, fallback: &str, hedge_after_ms: Option<u64>) -> Config {
+    let mut upstream = crate::config::UpstreamConfig {
+        url: Uri::parse(primary).unwrap(),
+        fallback_urls: vec![Uri::parse(fallback).unwrap()],
+        hedge_after_ms,
+        ..Default::default()
+    };
+    upstream.reliability.retry.max_attempts = 1;
+    Config {
+        upstream: Some(upstream),
+        ..Config::default()
+    }
+}
+
+#[cfg(feature = "sqlite")]
+#[tokio::test]
+async fn proxy_prefers_healthy_mirror_after_failures() {
+    let temp_dir = tempdir().unwrap();
+    let (primary, primary_server) =
+        spawn_sequence_server(vec![raw_response("503 Service Unavailable", &[], b"")]).await;
+    let (fallback, fallback_server) = spawn_sequence_server(vec![
+        raw_response("200 OK", &[], b"one"),
+        raw_response("200 OK", &[], b"two"),
+    ])
+    .await;
+    let proxy =
+        build_test_proxy_with(temp_dir.path(), mirrored_config(&primary, &fallback, None)).await;
+
+    for expected in [b"one", b"two"] {
+        let response = proxy
+            .fetch_with_fallback(&req("/versions"), None)
+            .await
+            .unwrap();
+        assert_eq!(body_bytes(response).await, expected);
+    }
+
+    // The failed primary was skipped on the second request.
+    assert_eq!(primary_server.await.unwrap().len(), 1);
+    assert_eq!(fallback_server.await.unwrap().len(), 2);
+
+    let mirrors = proxy.mirror_health();
+    assert_eq!(mirrors[0].failures, 1);
+    assert!(mirrors[0].error_rate > mirrors[1].error_rate);
+    assert_eq!(mirrors[1].requests, 2);
+
+    let response = proxy.serve(req("/readyz")).await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(body["mirrors"].as_array().unwrap().len(), 2);
+    assert_eq!(body["mirrors"][0]["failures"], 1);
+}
+
+#[cfg(feature = "sqlite")]
+#[tokio::test]
+async fn proxy_hedges_slow_mirror() {
+    let temp_dir = tempdir().unwrap();
+    let slow = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let primary = format!("http://{}", slow.local_addr().unwrap());
+    tokio::spawn(async move {
+        let (mut socket, _) = slow.accept().await.unwrap();
+        read_http_request(&mut socket).await;
+        tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+        let _ = socket
+            .write_all(&raw_response("200 OK", &[], b"slow"))
+            .await;
+    });
+    let (fallback, fallback_server) =
+        spawn_sequence_server(vec![raw_response("200 OK", &[], b"fast")]).await;
+    let proxy = build_test_proxy_with(
+        temp_dir.path(),
+        mirrored_config(&primary, &fallback, Some(50)),
+    )
+    .await;
+
+    let response = proxy
+        .fetch_with_fallback(&req("/versions"), None)
+        .await
+        .unwrap();
+    assert_eq!(body_bytes(response).await, b"fast");
+    assert_eq!(fallback_server.await.unwrap().len(), 1);
+    assert_eq!(proxy.mirror_health()[1].hedged, 1);
+}
+
+#[cfg(feature = "sqlite")]
+#[tokio::test]
+async fn proxy_serves_liveness_and_readiness() {
+    let temp_dir = tempdir().unwrap();
+    let mut config = Config::default();
+    config.health.min_free_bytes = 0;
+    let proxy = build_test_proxy_with(temp_dir.path(), config).await;
+
+    for path in ["/livez", "/up"] {
+        let response = proxy.serve(req(path)).await.unwrap();
+        assert_eq!(response.status(), 200);
+        let body: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+        assert_eq!(body, serde_json::json!({ "status": "ok" }));
+    }
+
+    let response = proxy.serve(req("/readyz")).await.unwrap();
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    // The catalog has never been synced, a `warn` failure.
+    assert_eq!(body["status"], "degraded");
+    let components: Vec<_> = body["checks"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|check| check["component"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        components,
+        [
+            "database",
+            "storage",
+            "upstream:rubygems",
+            "upstream:crates",
+            "upstream:npm",
+            "scheduler",
+            "catalog"
+        ]
+    );
+}
+
+#[cfg(feature = "sqlite")]
+#[tokio::test]
+async fn proxy_reload_swaps_config_and_keeps_mirror_health() {
+    let temp_dir = tempdir().unwrap();
+    let mut config = Config::default();
+    config.upstream = Some(crate::config::UpstreamConfig {
+        url: Uri::parse("https://primary.example").unwrap(),
+        ..Default::default()
+    });
+    let proxy = build_test_proxy_with(temp_dir.path(), config).await;
+    let before = proxy.current();
+    before.upstreams[0].health().record_hedge();
+
+    let mut next = (*proxy.config()).clone();
+    next.storage.path = temp_dir.path().join("elsewhere");
+    next.delay_policy
+        .pinned
+        .push(crate::config::delay_policy::PinnedVersion {
+            name: "rack".to_string(),
+            version: "3.1.0".to_string(),
+            reason: "hotfix".to_string(),
+        });
+    if let Some(upstream) = next.upstream.as_mut() {
+        upstream
+            .fallback_urls
+            .push(Uri::parse("https://fallback.example").unwrap());
+    }
+    let kept = proxy.reload(next).unwrap();
+    assert_eq!(kept, ["storage.path"]);
+
+    let after = proxy.current();
+    assert!(after.config.delay_policy.is_pinned("rack", "3.1.0"));
+    assert_eq!(after.config.storage.path, temp_dir.path().join("cache"));
+    let mirrors = after.mirror_health();
+    assert_eq!(mirrors.len(), 2);
+    assert_eq!(mirrors[0].hedged, 1);
+
+    // A request that started before the reload keeps its snapshot.
+    assert!(!before.config.delay_policy.is_pinned("rack", "3.1.0"));
+    assert_eq!(before.upstreams.len(), 1);
+}
+
+#[cfg(feature = "sqlite")]
+#[tokio::test]
+async fn rate_limited_client_gets_429_before_upstream() {
+    use crate::rate_limit::RateLimitLayer;
+    use sha2::{Digest, Sha256};
+    use vein_adapter::{AssetKey, CacheBackendTrait};
+
+    let temp_dir = tempdir().unwrap();
+    let (upstream, server) =
+        spawn_sequence_server(vec![raw_response("200 OK", &[], b"gem-bytes")]).await;
+    let mut config = Config::default();
+    let mut upstream_config = crate::config::UpstreamConfig {
+        url: Uri::parse(upstream.as_str()).unwrap(),
+        ..Default::default()
+    };
+    upstream_config.reliability.retry.max_attempts = 1;
+    config.upstream = Some(upstream_config);
+    config.rate_limit.enabled = true;
+    config.rate_limit.hits_per_minute = 1;
+    config.rate_limit.misses_per_minute = 1;
+    let limits = config.rate_limit.clone();
+    let proxy = build_test_proxy_with(temp_dir.path(), config).await;
+
+    let relative_path = "gems/rack/rack-3.0.0.gem";
+    let file = proxy.storage.resolve(relative_path);
+    std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+    std::fs::write(&file, b"gem-bytes").unwrap();
+    let key = AssetKey {
+        kind: AssetKind::Gem,
+        name: "rack",
+        version: "3.0.0",
+        platform: None,
+    };
+    proxy
+        .index
+        .insert_or_replace(
+            &key,
+            relative_path,
+            &hex::encode(Sha256::digest(b"gem-bytes")),
+            9,
+        )
+        .await
+        .unwrap();
+
+    let service = RateLimitLayer::new(&limits).into_layer(proxy);
+    let status = |response: Response<Body>| response.status().as_u16();
+
+    assert_eq!(
+        status(service.serve(req("/gems/rack-3.0.0.gem")).await.unwrap()),
+        200
+    );
+    assert_eq!(
+        status(service.serve(req("/gems/rack-3.0.0.gem")).await.unwrap()),
+        429
+    );
+
+    assert_eq!(
+        status(service.serve(req("/gems/rails-7.1.0.gem")).await.unwrap()),
+        200
+    );
+    let limited = service.serve(req("/gems/sinatra-4.0.0.gem")).await.unwrap();
+    assert_eq!(limited.status().as_u16(), 429);
+    assert!(limited.headers().contains_key("retry-after"));
+
+    // Only the first miss reached upstream.
+    assert_eq!(server.await.unwrap().len(), 1);
+}
+
 #[cfg(feature = "sqlite")]
 fn install_rustls_provider() {
     static INIT: Once = Once::new();
@@ -553,3 +1487,268 @@ async fn body_bytes(response: rama::http::Response<Body>) -> Vec<u8> {
         .to_bytes()
         .to_vec()
 }
+
+#[cfg(feature = "sqlite")]
+#[tokio::test]
+async fn sbom_endpoint_serves_crate_sboms() {
+    use vein_adapter::{CacheBackendTrait, Ecosystem, PackageMetadata};
+
+    let temp_dir = tempdir().unwrap();
+    let proxy = build_test_proxy(temp_dir.path()).await;
+    let mut metadata = PackageMetadata {
+        ecosystem: Ecosystem::CratesIo,
+        name: "itoa".to_string(),
+        version: "1.0.11".to_string(),
+        description: Some("Fast integer primitive to string conversion".to_string()),
+        license: Some("MIT OR Apache-2.0".to_string()),
+        authors: vec!["David Tolnay".to_string()],
+        homepage: None,
+        repository: None,
+        dependencies: Vec::new(),
+        features: Default::default(),
+        attributes: serde_json::json!({ "build_script": false }),
+        size_bytes: 10_000,
+        sha256: "00".repeat(32),
+        sbom: None,
+        sbom_generator: None,
+    };
+    metadata.sbom = crate::package_metadata::generate_cyclonedx_sbom(&metadata).unwrap();
+    proxy
+        .index
+        .upsert_package_metadata(&metadata)
+        .await
+        .unwrap();
+
+    let response = proxy
+        .serve(req(
+            "/.well-known/vein/sbom?ecosystem=crates&name=itoa&version=1.0.11",
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(
+        body["metadata"]["component"]["purl"],
+        "pkg:cargo/itoa@1.0.11"
+    );
+
+    let response = proxy
+        .serve(req(
+            "/.well-known/vein/sbom?ecosystem=crates&name=itoa&version=9.9.9",
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+
+    let response = proxy
+        .serve(req(
+            "/.well-known/vein/sbom?ecosystem=pypi&name=x&version=1",
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+}
+
+#[cfg(feature = "sqlite")]
+#[tokio::test]
+async fn sbom_endpoint_renders_spdx_and_refreshes_outdated_cyclonedx() {
+    use vein_adapter::{CacheBackendTrait, Ecosystem, PackageMetadata};
+
+    let temp_dir = tempdir().unwrap();
+    let proxy = build_test_proxy(temp_dir.path()).await;
+    let metadata = PackageMetadata {
+        ecosystem: Ecosystem::Npm,
+        name: "left-pad".to_string(),
+        version: "1.3.0".to_string(),
+        description: None,
+        license: Some("WTFPL".to_string()),
+        authors: Vec::new(),
+        homepage: None,
+        repository: None,
+        dependencies: Vec::new(),
+        features: Default::default(),
+        attributes: serde_json::json!({}),
+        size_bytes: 1,
+        sha256: "11".repeat(32),
+        sbom: Some(serde_json::json!({ "bomFormat": "CycloneDX", "specVersion": "1.4" })),
+        sbom_generator: Some("vein/0.1.0".to_string()),
+    };
+    proxy
+        .index
+        .upsert_package_metadata(&metadata)
+        .await
+        .unwrap();
+
+    let response = proxy
+        .serve(req(
+            "/.well-known/vein/sbom?ecosystem=npm&name=left-pad&version=1.3.0&format=spdx",
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(body["spdxVersion"], "SPDX-2.3");
+    assert_eq!(body["packages"][0]["licenseDeclared"], "WTFPL");
+
+    let response = proxy
+        .serve(req(
+            "/.well-known/vein/sbom?ecosystem=npm&name=left-pad&version=1.3.0&format=spdx-tag-value",
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    assert!(
+        response.headers()["content-disposition"]
+            .to_str()
+            .unwrap()
+            .contains("left-pad-1.3.0.spdx\"")
+    );
+
+    let response = proxy
+        .serve(req(
+            "/.well-known/vein/sbom?ecosystem=npm&name=left-pad&version=1.3.0",
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(body["specVersion"], crate::sbom::CYCLONEDX_SPEC_VERSION);
+    let stored = proxy
+        .index
+        .package_metadata(Ecosystem::Npm, "left-pad", "1.3.0")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        stored.sbom.unwrap()["specVersion"],
+        crate::sbom::CYCLONEDX_SPEC_VERSION
+    );
+
+    let response = proxy
+        .serve(req(
+            "/.well-known/vein/sbom?ecosystem=npm&name=left-pad&version=1.3.0&format=swid",
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+}
+
+#[cfg(feature = "sqlite")]
+#[tokio::test]
+async fn sbom_endpoint_describes_posted_lockfiles() {
+    use vein_adapter::{AssetKey, AssetKind, CacheBackendTrait, Ecosystem, PackageMetadata};
+
+    let temp_dir = tempdir().unwrap();
+    let proxy = build_test_proxy(temp_dir.path()).await;
+    let mut metadata = PackageMetadata {
+        ecosystem: Ecosystem::CratesIo,
+        name: "serde".to_string(),
+        version: "1.0.200".to_string(),
+        description: Some("A serialization framework".to_string()),
+        license: Some("MIT OR Apache-2.0".to_string()),
+        authors: Vec::new(),
+        homepage: None,
+        repository: None,
+        dependencies: Vec::new(),
+        features: Default::default(),
+        attributes: serde_json::json!({}),
+        size_bytes: 10,
+        sha256: "22".repeat(32),
+        sbom: None,
+        sbom_generator: None,
+    };
+    metadata.sbom = crate::package_metadata::generate_cyclonedx_sbom(&metadata).unwrap();
+    proxy
+        .index
+        .upsert_package_metadata(&metadata)
+        .await
+        .unwrap();
+    proxy
+        .index
+        .insert_or_replace(
+            &AssetKey {
+                kind: AssetKind::Crate,
+                name: "serde",
+                version: "1.0.200",
+                platform: None,
+            },
+            "crates/serde/serde-1.0.200.crate",
+            &"33".repeat(32),
+            10,
+        )
+        .await
+        .unwrap();
+
+    let lockfile = r#"
+version = 3
+
+[[package]]
+name = "app"
+version = "0.1.0"
+dependencies = ["serde"]
+
+[[package]]
+name = "serde"
+version = "1.0.200"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+dependencies = ["serde_derive"]
+
+[[package]]
+name = "serde_derive"
+version = "1.0.200"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+"#;
+    let post = |uri: &str| {
+        Request::builder()
+            .method(Method::POST)
+            .uri(uri)
+            .body(Body::from(lockfile))
+            .unwrap()
+    };
+
+    let response = proxy
+        .serve(post("/.well-known/vein/sbom?lockfile=Cargo.lock&name=app"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(body["metadata"]["component"]["name"], "app");
+    let components = body["components"].as_array().unwrap();
+    assert_eq!(components.len(), 2);
+    assert_eq!(components[0]["bom-ref"], "pkg:cargo/serde@1.0.200");
+    assert_eq!(components[0]["description"], "A serialization framework");
+    assert_eq!(components[0]["hashes"][0]["content"], "33".repeat(32));
+    assert!(components[1]["hashes"].is_null());
+    let dependencies = body["dependencies"].as_array().unwrap();
+    assert_eq!(dependencies[0]["dependsOn"][0], "pkg:cargo/serde@1.0.200");
+    assert_eq!(dependencies[1]["ref"], "pkg:cargo/serde@1.0.200");
+    assert_eq!(
+        dependencies[1]["dependsOn"][0],
+        "pkg:cargo/serde_derive@1.0.200"
+    );
+
+    let response = proxy
+        .serve(post(
+            "/.well-known/vein/sbom?lockfile=Cargo.lock&name=app&format=spdx",
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(
+        body["documentDescribes"],
+        serde_json::json!(["SPDXRef-Package-1"])
+    );
+    assert_eq!(body["packages"][0]["licenseDeclared"], "MIT OR Apache-2.0");
+    assert!(body["relationships"].as_array().unwrap().iter().any(|rel| {
+        rel["spdxElementId"] == "SPDXRef-Package-1"
+            && rel["relationshipType"] == "DEPENDS_ON"
+            && rel["relatedSpdxElement"] == "SPDXRef-Package-2"
+    }));
+
+    let response = proxy
+        .serve(post("/.well-known/vein/sbom?format=swid"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+}
@@ -8,6 +8,9 @@ use rama::{
 };
 use vein_adapter::{AssetKey, AssetKind};
 
+use crate::config::UpstreamConfig;
+use crate::upstream::{MirrorHealth, UpstreamClient};
+
 /// Cache status for request tracking
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CacheStatus {
@@ -15,6 +18,7 @@ pub enum CacheStatus {
     Hit,
     Miss,
     Revalidated,
+    Stale,
     Error,
 }
 
@@ -25,6 +29,7 @@ impl std::fmt::Display for CacheStatus {
             CacheStatus::Hit => write!(f, "hit"),
             CacheStatus::Miss => write!(f, "miss"),
             CacheStatus::Revalidated => write!(f, "revalidated"),
+            CacheStatus::Stale => write!(f, "stale"),
             CacheStatus::Error => write!(f, "error"),
         }
     }
@@ -189,11 +194,24 @@ impl CacheableRequest {
 #[derive(Clone)]
 pub struct UpstreamTarget {
     pub base: Uri,
+    /// Client carrying this mirror's breaker and health statistics.
+    pub client: UpstreamClient,
 }
 
 impl UpstreamTarget {
+    pub fn new(config: &UpstreamConfig, url: &Uri) -> Result<Self> {
+        Ok(Self {
+            base: url.clone(),
+            client: UpstreamClient::for_mirror(config, url)?,
+        })
+    }
+
     pub fn from_url(url: &Uri) -> Result<Self> {
-        Ok(Self { base: url.clone() })
+        Self::new(&UpstreamConfig::default(), url)
+    }
+
+    pub fn health(&self) -> &MirrorHealth {
+        &self.client.health
     }
 
     pub fn join(&self, req: &Request<rama::http::Body>) -> Result<Uri> {
@@ -3,20 +3,71 @@
 //! Provides scheduled jobs for managing gem version quarantine:
 //! - Automatic promotion of versions when quarantine expires
 //! - Database migration for quarantine tables
+//!
+//! Also hosts the approve/block workflow shared by the CLI and admin UI, which
+//! records every decision in the append-only `quarantine_events` trail.
+
+pub mod dependencies;
+pub mod risk;
 
 use std::sync::Arc;
 
-use anyhow::{Context, Result};
-use chrono::Utc;
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Utc};
 use rama::telemetry::tracing;
 use tokio_cron_scheduler::{Job, JobScheduler};
-use vein_adapter::{CacheBackend, CacheBackendTrait};
+use vein_adapter::{
+    CacheBackend, CacheBackendTrait, Ecosystem, GemVersion, QuarantineAction, QuarantineEvent,
+    VersionStatus,
+};
 
-use crate::config::DelayPolicyConfig;
+use crate::config::{DelayPolicyConfig, NotificationKind};
+use crate::notifications::{self, Notification};
 
 /// Default cron schedule for quarantine promotion (every hour at minute 5)
 pub const DEFAULT_PROMOTION_SCHEDULE: &str = "0 5 * * * *";
 
+/// `catalog_meta` key holding the RFC 3339 time the promotion scheduler last
+/// ran (or started), read by the readiness probe.
+pub const META_LAST_RUN: &str = "quarantine_scheduler_last_run";
+
+/// Page size used when listing the versions a promotion run releases.
+const PROMOTION_PAGE_SIZE: u32 = 1000;
+
+/// How far back the approval count looks in a version's audit trail.
+const APPROVAL_HISTORY_LIMIT: u32 = 500;
+
+/// Result of recording an early-release approval.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ApprovalOutcome {
+    /// Enough distinct approvers signed off; the version is now pinned.
+    Released { approvers: Vec<String> },
+    /// Approval recorded, waiting on more distinct approvers.
+    Pending {
+        approvers: Vec<String>,
+        required: u32,
+    },
+}
+
+/// Who signs off on an early release.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Approver<'a> {
+    /// Identity vouched for by the server, such as the user an authenticating
+    /// proxy put in `approver_header`.
+    Verified(&'a str),
+    /// A name the caller supplied; fine for the audit trail, but it cannot
+    /// count towards several required approvers.
+    Claimed(&'a str),
+}
+
+impl<'a> Approver<'a> {
+    pub fn name(self) -> &'a str {
+        match self {
+            Self::Verified(name) | Self::Claimed(name) => name,
+        }
+    }
+}
+
 /// Spawns the quarantine promotion scheduler.
 ///
 /// This job runs periodically to promote versions whose quarantine period has expired.
@@ -31,6 +82,7 @@ pub fn spawn_promotion_scheduler(
     }
 
     let schedule = schedule.unwrap_or(DEFAULT_PROMOTION_SCHEDULE).to_string();
+    let started_index = index.clone();
 
     std::thread::spawn(move || {
         let rt = tokio::runtime::Builder::new_current_thread()
@@ -46,10 +98,10 @@ pub fn spawn_promotion_scheduler(
             let job = Job::new_async(schedule.as_str(), move |_uuid, _l| {
                 let index = index.clone();
                 Box::pin(async move {
-                    let now = Utc::now();
                     tracing::debug!("Running quarantine promotion check");
+                    record_run(index.as_ref()).await;
 
-                    match index.promote_expired_quarantines(now).await {
+                    match promote_now(index.as_ref()).await {
                         Ok(count) if count > 0 => {
                             tracing::info!(
                                 promoted = count,
@@ -77,6 +129,7 @@ pub fn spawn_promotion_scheduler(
                 .await
                 .expect("Failed to start quarantine scheduler");
 
+            record_run(started_index.as_ref()).await;
             tracing::info!(
                 schedule = %schedule,
                 "Quarantine promotion scheduler started"
@@ -90,14 +143,404 @@ pub fn spawn_promotion_scheduler(
     });
 }
 
-/// Manually triggers promotion of expired quarantines.
+async fn record_run(index: &CacheBackend) {
+    if let Err(err) = index
+        .catalog_meta_set(META_LAST_RUN, &Utc::now().to_rfc3339())
+        .await
+    {
+        tracing::warn!(error = %err, "Failed to record quarantine scheduler run");
+    }
+}
+
+/// Promotes expired quarantines and notifies about each promoted version.
 ///
-/// Useful for CLI commands.
+/// Used by the scheduler and CLI commands.
 pub async fn promote_now(index: &CacheBackend) -> Result<u64> {
     let now = Utc::now();
+    let due = due_for_promotion(index, now).await?;
+
     let count = index
         .promote_expired_quarantines(now)
         .await
         .context("promoting expired quarantines")?;
+
+    for gem_version in due {
+        notifications::emit(Notification::new(
+            NotificationKind::Promoted,
+            Ecosystem::RubyGems,
+            &gem_version.name,
+            &gem_version.version,
+            gem_version.platform.as_deref(),
+            Some("quarantine period expired"),
+        ));
+    }
     Ok(count)
 }
+
+/// Lists every quarantined version whose delay has expired by `now`.
+///
+/// Quarantines are listed oldest `available_after` first, so paging stops at
+/// the first version that is still delayed.
+async fn due_for_promotion(index: &CacheBackend, now: DateTime<Utc>) -> Result<Vec<GemVersion>> {
+    let mut due = Vec::new();
+    let mut offset = 0;
+    loop {
+        let page = index
+            .get_all_quarantined(PROMOTION_PAGE_SIZE, offset)
+            .await
+            .context("listing quarantined versions")?;
+        let full_page = page.len() == PROMOTION_PAGE_SIZE as usize;
+        let before = due.len();
+        due.extend(
+            page.into_iter()
+                .filter(|gem_version| gem_version.available_after <= now),
+        );
+        if !full_page || due.len() - before < PROMOTION_PAGE_SIZE as usize {
+            return Ok(due);
+        }
+        offset += PROMOTION_PAGE_SIZE;
+    }
+}
+
+/// Records an approval and pins the version once `required_approvers`
+/// distinct people have approved it since the last release or block.
+///
+/// Operator-blocked versions can be approved again; versions yanked upstream
+/// cannot. With more than one required approver every approver must be
+/// [`Approver::Verified`].
+pub async fn approve_version(
+    index: &CacheBackend,
+    config: &DelayPolicyConfig,
+    name: &str,
+    version: &str,
+    platform: Option<&str>,
+    approver: Approver<'_>,
+    reason: &str,
+) -> Result<ApprovalOutcome> {
+    let required = config.required_approvers.max(1);
+    if required > 1 && matches!(approver, Approver::Claimed(_)) {
+        bail!(
+            "early release needs {required} approvers with verified identities; \
+             approve in vein-admin behind a proxy that sets `delay_policy.approver_header`"
+        );
+    }
+    let tracked = index
+        .get_gem_version(name, version, platform)
+        .await
+        .context("loading gem version")?;
+    if tracked
+        .as_ref()
+        .is_some_and(|gem_version| gem_version.upstream_yanked)
+    {
+        bail!("{name} {version} was yanked upstream and cannot be approved");
+    }
+    let previous = tracked.map(|gem_version| gem_version.status);
+    let actor = approver.name();
+    record_event(
+        index,
+        new_event(
+            name,
+            version,
+            platform,
+            QuarantineAction::Approve,
+            actor,
+            reason,
+            previous,
+        ),
+    )
+    .await?;
+
+    let history = index
+        .quarantine_events(name, Some(version), APPROVAL_HISTORY_LIMIT)
+        .await
+        .context("loading quarantine history")?;
+    let approvers = pending_approvers(&history, platform);
+
+    if approvers.len() < required as usize {
+        tracing::info!(
+            gem = %name,
+            version = %version,
+            approvals = approvers.len(),
+            required,
+            "Approval recorded, waiting for more approvers"
+        );
+        return Ok(ApprovalOutcome::Pending {
+            approvers,
+            required,
+        });
+    }
+
+    index
+        .update_version_status(
+            name,
+            version,
+            platform,
+            VersionStatus::Pinned,
+            Some(format!("approved: {reason}")),
+        )
+        .await
+        .context("approving version")?;
+    record_event(
+        index,
+        new_event(
+            name,
+            version,
+            platform,
+            QuarantineAction::Release,
+            actor,
+            &format!("approved by {}", approvers.join(", ")),
+            previous,
+        ),
+    )
+    .await?;
+
+    notifications::emit(Notification::new(
+        NotificationKind::Released,
+        Ecosystem::RubyGems,
+        name,
+        version,
+        platform,
+        Some(&format!("approved by {}: {reason}", approvers.join(", "))),
+    ));
+
+    Ok(ApprovalOutcome::Released { approvers })
+}
+
+/// Blocks a version and records the decision in the audit trail.
+pub async fn block_version(
+    index: &CacheBackend,
+    name: &str,
+    version: &str,
+    platform: Option<&str>,
+    actor: &str,
+    reason: &str,
+) -> Result<()> {
+    let previous = current_status(index, name, version, platform).await?;
+    index
+        .update_version_status(
+            name,
+            version,
+            platform,
+            VersionStatus::Yanked,
+            Some(format!("blocked: {reason}")),
+        )
+        .await
+        .context("blocking version")?;
+    record_event(
+        index,
+        new_event(
+            name,
+            version,
+            platform,
+            QuarantineAction::Block,
+            actor,
+            reason,
+            previous,
+        ),
+    )
+    .await?;
+
+    notifications::emit(Notification::new(
+        NotificationKind::Blocked,
+        Ecosystem::RubyGems,
+        name,
+        version,
+        platform,
+        Some(&format!("{actor}: {reason}")),
+    ));
+    Ok(())
+}
+
+/// Distinct approvers (oldest first) since the most recent release or block.
+///
+/// `history` must be ordered newest first, as returned by the backend.
+fn pending_approvers(history: &[QuarantineEvent], platform: Option<&str>) -> Vec<String> {
+    let approvals: Vec<&QuarantineEvent> = history
+        .iter()
+        .filter(|event| event.platform.as_deref() == platform)
+        .take_while(|event| event.action == QuarantineAction::Approve)
+        .collect();
+
+    let mut approvers: Vec<String> = Vec::new();
+    for event in approvals.into_iter().rev() {
+        if !approvers.contains(&event.actor) {
+            approvers.push(event.actor.clone());
+        }
+    }
+    approvers
+}
+
+async fn current_status(
+    index: &CacheBackend,
+    name: &str,
+    version: &str,
+    platform: Option<&str>,
+) -> Result<Option<VersionStatus>> {
+    Ok(index
+        .get_gem_version(name, version, platform)
+        .await
+        .context("loading gem version")?
+        .map(|gem_version| gem_version.status))
+}
+
+async fn record_event(index: &CacheBackend, event: QuarantineEvent) -> Result<()> {
+    index
+        .record_quarantine_event(&event)
+        .await
+        .context("recording quarantine event")
+}
+
+fn new_event(
+    name: &str,
+    version: &str,
+    platform: Option<&str>,
+    action: QuarantineAction,
+    actor: &str,
+    reason: &str,
+    previous_status: Option<VersionStatus>,
+) -> QuarantineEvent {
+    QuarantineEvent {
+        id: 0,
+        name: name.to_string(),
+        version: version.to_string(),
+        platform: platform.map(str::to_string),
+        action,
+        actor: actor.to_string(),
+        reason: Some(reason.to_string()),
+        previous_status,
+        created_at: Utc::now(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(actor: &str, action: QuarantineAction) -> QuarantineEvent {
+        new_event("rack", "3.0.0", None, action, actor, "ok", None)
+    }
+
+    #[test]
+    fn pending_approvers_are_distinct_and_reset_by_decisions() {
+        // Newest first, as the backend returns them.
+        let history = vec![
+            event("bob", QuarantineAction::Approve),
+            event("alice", QuarantineAction::Approve),
+            event("bob", QuarantineAction::Approve),
+            event("carol", QuarantineAction::Block),
+            event("dave", QuarantineAction::Approve),
+        ];
+
+        assert_eq!(pending_approvers(&history, None), ["bob", "alice"]);
+        assert!(pending_approvers(&history, Some("java")).is_empty());
+        assert!(pending_approvers(&history[3..], None).is_empty());
+    }
+
+    #[cfg(feature = "sqlite")]
+    async fn quarantine(index: &CacheBackend, version: &str, available_after: DateTime<Utc>) {
+        let now = Utc::now();
+        index
+            .upsert_gem_version(&GemVersion {
+                id: 0,
+                name: "rack".to_string(),
+                version: version.to_string(),
+                platform: None,
+                sha256: None,
+                published_at: now,
+                available_after,
+                status: VersionStatus::Quarantine,
+                status_reason: None,
+                upstream_yanked: false,
+                created_at: now,
+                updated_at: now,
+            })
+            .await
+            .unwrap();
+    }
+
+    #[cfg(feature = "sqlite")]
+    async fn quarantined_index() -> CacheBackend {
+        let index = CacheBackend::connect_memory().await.unwrap();
+        quarantine(&index, "3.0.0", Utc::now() + chrono::Duration::days(3)).await;
+        index
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn promotion_lists_every_expired_version() {
+        let index = CacheBackend::connect_memory().await.unwrap();
+        let now = Utc::now();
+        let expired = PROMOTION_PAGE_SIZE as usize + 5;
+        for patch in 0..expired {
+            quarantine(
+                &index,
+                &format!("1.0.{patch}"),
+                now - chrono::Duration::hours(1),
+            )
+            .await;
+        }
+        quarantine(&index, "2.0.0", now + chrono::Duration::days(3)).await;
+
+        let due = due_for_promotion(&index, now).await.unwrap();
+        assert_eq!(due.len(), expired);
+        assert!(due.iter().all(|gem_version| gem_version.version != "2.0.0"));
+        assert_eq!(promote_now(&index).await.unwrap(), expired as u64);
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn blocked_versions_can_be_approved_unless_yanked_upstream() {
+        let index = quarantined_index().await;
+        let config = DelayPolicyConfig::default();
+        let approve = || {
+            approve_version(
+                &index,
+                &config,
+                "rack",
+                "3.0.0",
+                None,
+                Approver::Verified("bob"),
+                "blocked by mistake",
+            )
+        };
+
+        block_version(&index, "rack", "3.0.0", None, "alice", "malware")
+            .await
+            .unwrap();
+        assert!(matches!(
+            approve().await.unwrap(),
+            ApprovalOutcome::Released { .. }
+        ));
+        let status = current_status(&index, "rack", "3.0.0", None).await.unwrap();
+        assert_eq!(status, Some(VersionStatus::Pinned));
+
+        index.mark_yanked("rack", "3.0.0").await.unwrap();
+        assert!(approve().await.is_err());
+        let status = current_status(&index, "rack", "3.0.0", None).await.unwrap();
+        assert_eq!(status, Some(VersionStatus::Yanked));
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn several_approvers_must_be_verified() {
+        let index = quarantined_index().await;
+        let config = DelayPolicyConfig {
+            required_approvers: 2,
+            ..DelayPolicyConfig::default()
+        };
+        let approve =
+            |approver| approve_version(&index, &config, "rack", "3.0.0", None, approver, "ok");
+
+        assert!(approve(Approver::Claimed("alice")).await.is_err());
+        assert!(matches!(
+            approve(Approver::Verified("alice")).await.unwrap(),
+            ApprovalOutcome::Pending { .. }
+        ));
+        assert_eq!(
+            approve(Approver::Verified("bob")).await.unwrap(),
+            ApprovalOutcome::Released {
+                approvers: vec!["alice".to_string(), "bob".to_string()]
+            }
+        );
+    }
+}
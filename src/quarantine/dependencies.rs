@@ -0,0 +1,339 @@
+//! Dependency-aware quarantine.
+//!
+//! A version whose runtime dependencies can only be satisfied by versions
+//! that are still quarantined is not installable yet, even once its own delay
+//! has passed. The resolver walks those dependencies (from stored
+//! `GemMetadata` or compact index dependency columns) to work out when the
+//! version becomes installable and which chain of versions it is waiting on.
+//!
+//! Only versions Vein has tracked are considered: a dependency with no
+//! tracked version matching the requirement is assumed to be installable.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use vein_adapter::{
+    CacheBackend, CacheBackendTrait, DependencyKind, GemVersion, VersionStatus,
+    is_version_available,
+};
+
+use crate::gem_requirement::{Requirement, is_prerelease_version};
+
+/// How many levels of transitive dependencies are followed.
+const MAX_DEPTH: usize = 5;
+
+/// A dependency that is holding a version back.
+#[derive(Debug, Clone, Serialize)]
+pub struct BlockedBy {
+    /// Dependency name as declared by the dependent.
+    pub name: String,
+    pub requirement: String,
+    /// Earliest quarantined version that would satisfy the requirement.
+    pub version: String,
+    pub platform: Option<String>,
+    /// When `version` and its own dependencies are all installable.
+    pub installable_at: DateTime<Utc>,
+    /// `name version` hops from this dependency down to the last blocker.
+    pub chain: Vec<String>,
+}
+
+/// When a version can actually be installed.
+#[derive(Debug, Clone, Serialize)]
+pub struct Installability {
+    /// When the version itself leaves quarantine.
+    pub available_after: DateTime<Utc>,
+    /// When the version and all of its runtime dependencies are available.
+    pub installable_at: DateTime<Utc>,
+    pub blocked_by: Vec<BlockedBy>,
+}
+
+impl Installability {
+    pub fn is_blocked(&self) -> bool {
+        !self.blocked_by.is_empty()
+    }
+}
+
+type BlockerFuture<'f> = Pin<Box<dyn Future<Output = Result<Option<BlockedBy>>> + Send + 'f>>;
+
+/// Resolves dependency blockers, caching per-gem version lookups.
+pub struct DependencyResolver<'a> {
+    index: &'a CacheBackend,
+    now: DateTime<Utc>,
+    versions: HashMap<String, Vec<GemVersion>>,
+}
+
+impl<'a> DependencyResolver<'a> {
+    pub fn new(index: &'a CacheBackend) -> Self {
+        Self {
+            index,
+            now: Utc::now(),
+            versions: HashMap::new(),
+        }
+    }
+
+    /// Computes when `version` becomes installable using its stored metadata.
+    pub async fn installability(&mut self, version: &GemVersion) -> Result<Installability> {
+        let available_after = if is_version_available(version, self.now) {
+            self.now
+        } else {
+            version.available_after
+        };
+        let dependencies = self
+            .runtime_dependencies(&version.name, &version.version, version.platform.as_deref())
+            .await?;
+        let blocked_by = self.blockers(&dependencies, 0).await?;
+        let installable_at = blocked_by
+            .iter()
+            .map(|blocker| blocker.installable_at)
+            .fold(available_after, DateTime::max);
+
+        Ok(Installability {
+            available_after,
+            installable_at,
+            blocked_by,
+        })
+    }
+
+    /// Returns the dependencies in `dependencies` that are still waiting on
+    /// quarantined versions.
+    pub async fn blockers(
+        &mut self,
+        dependencies: &[(String, String)],
+        depth: usize,
+    ) -> Result<Vec<BlockedBy>> {
+        let mut blocked = Vec::new();
+        for (name, requirement) in dependencies {
+            if let Some(blocker) = self.blocker(name, requirement, depth).await? {
+                blocked.push(blocker);
+            }
+        }
+        Ok(blocked)
+    }
+
+    fn blocker<'f>(
+        &'f mut self,
+        name: &'f str,
+        requirement: &'f str,
+        depth: usize,
+    ) -> BlockerFuture<'f> {
+        Box::pin(async move {
+            let Some(parsed) = Requirement::parse(requirement) else {
+                return Ok(None);
+            };
+            let now = self.now;
+            let candidate = {
+                let versions = self.versions_of(name).await?;
+                let satisfying: Vec<&GemVersion> = versions
+                    .iter()
+                    .filter(|v| v.status != VersionStatus::Yanked)
+                    .filter(|v| parsed.allows_prerelease() || !is_prerelease_version(&v.version))
+                    .filter(|v| parsed.matches(&v.version))
+                    .collect();
+                if satisfying.is_empty() || satisfying.iter().any(|v| is_version_available(v, now))
+                {
+                    return Ok(None);
+                }
+                satisfying
+                    .into_iter()
+                    .min_by_key(|v| v.available_after)
+                    .cloned()
+            };
+            let Some(candidate) = candidate else {
+                return Ok(None);
+            };
+
+            let mut installable_at = candidate.available_after;
+            let mut chain = vec![format!("{} {}", candidate.name, candidate.version)];
+            if depth + 1 < MAX_DEPTH {
+                let nested = self
+                    .runtime_dependencies(
+                        &candidate.name,
+                        &candidate.version,
+                        candidate.platform.as_deref(),
+                    )
+                    .await?;
+                let latest = self
+                    .blockers(&nested, depth + 1)
+                    .await?
+                    .into_iter()
+                    .max_by_key(|blocker| blocker.installable_at);
+                if let Some(latest) = latest {
+                    installable_at = installable_at.max(latest.installable_at);
+                    chain.extend(latest.chain);
+                }
+            }
+
+            Ok(Some(BlockedBy {
+                name: name.to_string(),
+                requirement: requirement.to_string(),
+                version: candidate.version,
+                platform: candidate.platform,
+                installable_at,
+                chain,
+            }))
+        })
+    }
+
+    async fn versions_of(&mut self, name: &str) -> Result<&Vec<GemVersion>> {
+        if !self.versions.contains_key(name) {
+            let versions = self.index.get_gem_versions_for_index(name).await?;
+            self.versions.insert(name.to_string(), versions);
+        }
+        Ok(&self.versions[name])
+    }
+
+    async fn runtime_dependencies(
+        &self,
+        name: &str,
+        version: &str,
+        platform: Option<&str>,
+    ) -> Result<Vec<(String, String)>> {
+        let platform = platform.unwrap_or("ruby");
+        let Some(metadata) = self
+            .index
+            .gem_metadata(name, version, Some(platform))
+            .await?
+        else {
+            return Ok(Vec::new());
+        };
+        Ok(metadata
+            .dependencies
+            .into_iter()
+            .filter(|dep| dep.kind == DependencyKind::Runtime)
+            .map(|dep| (dep.name, dep.requirement))
+            .collect())
+    }
+}
+
+/// Parses a compact index dependency column (`bar:>= 3.1&< 4,baz:~> 1.0`).
+pub fn parse_compact_dependencies(column: &str) -> Vec<(String, String)> {
+    column
+        .split(',')
+        .filter_map(|entry| {
+            let (name, requirement) = entry.trim().split_once(':')?;
+            let name = name.trim();
+            (!name.is_empty()).then(|| (name.to_string(), requirement.trim().to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use vein_adapter::{GemDependency, GemMetadata};
+
+    fn tracked(name: &str, version: &str, status: VersionStatus, days: i64) -> GemVersion {
+        let now = Utc::now();
+        GemVersion {
+            id: 0,
+            name: name.to_string(),
+            version: version.to_string(),
+            platform: None,
+            sha256: None,
+            published_at: now,
+            available_after: now + Duration::days(days),
+            status,
+            status_reason: None,
+            upstream_yanked: false,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    fn metadata(name: &str, version: &str, deps: &[(&str, &str)]) -> GemMetadata {
+        GemMetadata {
+            name: name.to_string(),
+            version: version.to_string(),
+            platform: "ruby".to_string(),
+            dependencies: deps
+                .iter()
+                .map(|(name, requirement)| GemDependency {
+                    name: name.to_string(),
+                    requirement: requirement.to_string(),
+                    kind: DependencyKind::Runtime,
+                })
+                .collect(),
+            ..GemMetadata::default()
+        }
+    }
+
+    #[test]
+    fn parses_compact_dependency_column() {
+        assert_eq!(
+            parse_compact_dependencies("bar:>= 3.1&< 4,baz:~> 1.0"),
+            vec![
+                ("bar".to_string(), ">= 3.1&< 4".to_string()),
+                ("baz".to_string(), "~> 1.0".to_string()),
+            ]
+        );
+        assert!(parse_compact_dependencies("").is_empty());
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn reports_transitive_blockers_and_installable_time() {
+        let index = CacheBackend::connect_memory().await.unwrap();
+        index
+            .upsert_gem_version(&tracked("foo", "2.0.0", VersionStatus::Quarantine, 1))
+            .await
+            .unwrap();
+        index
+            .upsert_gem_version(&tracked("bar", "3.0.0", VersionStatus::Available, -10))
+            .await
+            .unwrap();
+        index
+            .upsert_gem_version(&tracked("bar", "3.1.0", VersionStatus::Quarantine, 2))
+            .await
+            .unwrap();
+        index
+            .upsert_gem_version(&tracked("baz", "1.0.0", VersionStatus::Quarantine, 4))
+            .await
+            .unwrap();
+        index
+            .upsert_metadata(&metadata(
+                "foo",
+                "2.0.0",
+                &[("bar", ">= 3.1"), ("rack", ">= 2")],
+            ))
+            .await
+            .unwrap();
+        index
+            .upsert_metadata(&metadata("bar", "3.1.0", &[("baz", "~> 1.0")]))
+            .await
+            .unwrap();
+
+        let foo = index
+            .get_gem_version("foo", "2.0.0", None)
+            .await
+            .unwrap()
+            .unwrap();
+        let baz = index
+            .get_gem_version("baz", "1.0.0", None)
+            .await
+            .unwrap()
+            .unwrap();
+
+        let mut resolver = DependencyResolver::new(&index);
+        let result = resolver.installability(&foo).await.unwrap();
+
+        assert!(result.is_blocked());
+        assert_eq!(result.blocked_by.len(), 1);
+        let blocker = &result.blocked_by[0];
+        assert_eq!(blocker.name, "bar");
+        assert_eq!(blocker.version, "3.1.0");
+        assert_eq!(blocker.chain, vec!["bar 3.1.0", "baz 1.0.0"]);
+        assert_eq!(result.installable_at, baz.available_after);
+        assert!(result.installable_at > result.available_after);
+
+        let relaxed = resolver
+            .blockers(&parse_compact_dependencies("bar:>= 3.0"), 0)
+            .await
+            .unwrap();
+        assert!(relaxed.is_empty());
+    }
+}
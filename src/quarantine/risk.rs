@@ -0,0 +1,322 @@
+//! Risk-based quarantine delays.
+//!
+//! Compares a new version's metadata with the closest earlier version, looks
+//! at the content scan findings and applies the configured
+//! `[delay_policy.risk]` rules: each signal that fires adds days or holds the
+//! version for manual approval. The applied rules end up in the version's
+//! status reason.
+
+use std::collections::BTreeSet;
+
+use chrono::{DateTime, TimeZone, Utc};
+use vein_adapter::{GemFinding, GemMetadata, GemVersion};
+
+use crate::config::{RiskPolicyConfig, RiskRule};
+
+/// `available_after` for versions held until someone approves them.
+pub fn manual_approval_hold() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(9999, 12, 31, 0, 0, 0)
+        .single()
+        .expect("valid far-future timestamp")
+}
+
+/// Whether a quarantined version only leaves quarantine by approval.
+pub fn awaits_manual_approval(version: &GemVersion) -> bool {
+    version.available_after >= manual_approval_hold()
+}
+
+/// A risk signal derived from package metadata.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RiskSignal {
+    FirstVersion,
+    AuthorChange,
+    NewNativeCode,
+    NewExecutables,
+    SizeJump,
+    SuspiciousContent,
+}
+
+impl RiskSignal {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::FirstVersion => "first_version",
+            Self::AuthorChange => "author_change",
+            Self::NewNativeCode => "new_native_code",
+            Self::NewExecutables => "new_executables",
+            Self::SizeJump => "size_jump",
+            Self::SuspiciousContent => "suspicious_content",
+        }
+    }
+}
+
+/// A signal that fired together with the rule it triggered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppliedRule {
+    pub signal: RiskSignal,
+    pub detail: Option<String>,
+    pub rule: RiskRule,
+}
+
+/// Outcome of evaluating every configured risk rule.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RiskAssessment {
+    pub applied: Vec<AppliedRule>,
+}
+
+impl RiskAssessment {
+    /// Days to add on top of the gem's base delay.
+    pub fn extra_days(&self) -> u32 {
+        self.applied
+            .iter()
+            .map(|applied| applied.rule.add_days)
+            .fold(0, u32::saturating_add)
+    }
+
+    pub fn requires_approval(&self) -> bool {
+        self.applied
+            .iter()
+            .any(|applied| applied.rule.require_approval)
+    }
+
+    /// Status reason fragment, e.g.
+    /// `risk: first_version +7d; author_change (eve@example.com) approval`.
+    pub fn reason(&self) -> Option<String> {
+        if self.applied.is_empty() {
+            return None;
+        }
+        let rules: Vec<String> = self
+            .applied
+            .iter()
+            .map(|applied| {
+                let mut text = applied.signal.as_str().to_string();
+                if let Some(detail) = &applied.detail {
+                    text.push_str(&format!(" ({detail})"));
+                }
+                if applied.rule.add_days > 0 {
+                    text.push_str(&format!(" +{}d", applied.rule.add_days));
+                }
+                if applied.rule.require_approval {
+                    text.push_str(" approval");
+                }
+                text
+            })
+            .collect();
+        Some(format!("risk: {}", rules.join("; ")))
+    }
+}
+
+/// Evaluates the risk rules for a new version.
+///
+/// `first_version` is whether upstream lists no other versions. `previous`
+/// is the metadata of the closest earlier cached version, if any, and
+/// `findings` are the content scan results for the new version.
+pub fn assess(
+    config: &RiskPolicyConfig,
+    current: Option<&GemMetadata>,
+    previous: Option<&GemMetadata>,
+    findings: &[GemFinding],
+    first_version: bool,
+) -> RiskAssessment {
+    let mut assessment = RiskAssessment::default();
+    let mut apply = |rule: Option<RiskRule>, signal, detail: Option<String>| {
+        if let Some(rule) = rule {
+            assessment.applied.push(AppliedRule {
+                signal,
+                detail,
+                rule,
+            });
+        }
+    };
+
+    if first_version {
+        apply(config.first_version, RiskSignal::FirstVersion, None);
+    }
+
+    if !findings.is_empty() {
+        let mut rules: Vec<&str> = Vec::new();
+        for finding in findings {
+            if !rules.contains(&finding.rule.as_str()) {
+                rules.push(&finding.rule);
+            }
+        }
+        apply(
+            config.suspicious_content,
+            RiskSignal::SuspiciousContent,
+            Some(rules.join(", ")),
+        );
+    }
+
+    let (Some(current), Some(previous)) = (current, previous) else {
+        return assessment;
+    };
+
+    let added_people: Vec<String> = added(&current.authors, &previous.authors)
+        .into_iter()
+        .chain(added(&current.emails, &previous.emails))
+        .collect();
+    if !added_people.is_empty() {
+        apply(
+            config.author_change,
+            RiskSignal::AuthorChange,
+            Some(added_people.join(", ")),
+        );
+    }
+
+    let mut native = Vec::new();
+    if current.has_native_extensions && !previous.has_native_extensions {
+        native.push("native extensions");
+    }
+    if current.has_embedded_binaries && !previous.has_embedded_binaries {
+        native.push("embedded binaries");
+    }
+    if !native.is_empty() {
+        apply(
+            config.new_native_code,
+            RiskSignal::NewNativeCode,
+            Some(native.join(", ")),
+        );
+    }
+
+    let executables = added(&current.executables, &previous.executables);
+    if !executables.is_empty() {
+        apply(
+            config.new_executables,
+            RiskSignal::NewExecutables,
+            Some(executables.join(", ")),
+        );
+    }
+
+    if previous.size_bytes > 0
+        && current.size_bytes as f64 >= previous.size_bytes as f64 * config.size_jump_ratio
+    {
+        apply(
+            config.size_jump,
+            RiskSignal::SizeJump,
+            Some(format!(
+                "{} -> {} bytes",
+                previous.size_bytes, current.size_bytes
+            )),
+        );
+    }
+
+    assessment
+}
+
+/// Entries of `current` missing from `previous`, compared case-insensitively.
+fn added(current: &[String], previous: &[String]) -> Vec<String> {
+    let known: BTreeSet<String> = previous
+        .iter()
+        .map(|entry| entry.trim().to_lowercase())
+        .collect();
+    current
+        .iter()
+        .filter(|entry| !entry.trim().is_empty() && !known.contains(&entry.trim().to_lowercase()))
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata(authors: &[&str], executables: &[&str], native: bool, size: u64) -> GemMetadata {
+        GemMetadata {
+            name: "rest-client".to_string(),
+            version: "1.6.13".to_string(),
+            platform: "ruby".to_string(),
+            authors: authors.iter().map(|a| a.to_string()).collect(),
+            executables: executables.iter().map(|e| e.to_string()).collect(),
+            has_native_extensions: native,
+            size_bytes: size,
+            ..GemMetadata::default()
+        }
+    }
+
+    fn rule(add_days: u32, require_approval: bool) -> Option<RiskRule> {
+        Some(RiskRule {
+            add_days,
+            require_approval,
+        })
+    }
+
+    #[test]
+    fn applies_configured_rules_for_fired_signals() {
+        let config = RiskPolicyConfig {
+            author_change: rule(0, true),
+            new_native_code: rule(3, false),
+            new_executables: rule(2, false),
+            size_jump: rule(1, false),
+            ..Default::default()
+        };
+        let previous = metadata(&["Matthew"], &[], false, 1_000);
+        let current = metadata(&["matthew", "Eve"], &["rc"], true, 5_000);
+
+        let assessment = assess(&config, Some(&current), Some(&previous), &[], false);
+
+        let signals: Vec<_> = assessment.applied.iter().map(|a| a.signal).collect();
+        assert_eq!(
+            signals,
+            [
+                RiskSignal::AuthorChange,
+                RiskSignal::NewNativeCode,
+                RiskSignal::NewExecutables,
+                RiskSignal::SizeJump
+            ]
+        );
+        assert_eq!(assessment.extra_days(), 6);
+        assert!(assessment.requires_approval());
+        assert_eq!(
+            assessment.reason().unwrap(),
+            "risk: author_change (Eve) approval; new_native_code (native extensions) +3d; \
+             new_executables (rc) +2d; size_jump (1000 -> 5000 bytes) +1d"
+        );
+    }
+
+    #[test]
+    fn suspicious_content_fires_without_previous_version() {
+        let config = RiskPolicyConfig {
+            suspicious_content: rule(0, true),
+            ..Default::default()
+        };
+        let finding = |rule: &str| GemFinding {
+            gem_name: "rest-client".to_string(),
+            gem_version: "1.6.13".to_string(),
+            gem_platform: Some("ruby".to_string()),
+            rule: rule.to_string(),
+            file_path: "lib/restclient.rb".to_string(),
+            line_number: Some(1),
+            excerpt: String::new(),
+        };
+        let findings = [
+            finding("obfuscated_eval"),
+            finding("credential_access"),
+            finding("obfuscated_eval"),
+        ];
+
+        let assessment = assess(&config, None, None, &findings, false);
+
+        assert!(assessment.requires_approval());
+        assert_eq!(
+            assessment.reason().unwrap(),
+            "risk: suspicious_content (obfuscated_eval, credential_access) approval"
+        );
+        assert!(assess(&config, None, None, &[], false).applied.is_empty());
+    }
+
+    #[test]
+    fn unconfigured_or_unchanged_signals_do_nothing() {
+        let config = RiskPolicyConfig {
+            first_version: rule(7, false),
+            ..Default::default()
+        };
+        let same = metadata(&["Matthew"], &["rc"], true, 1_000);
+
+        let assessment = assess(&config, Some(&same), Some(&same), &[], false);
+        assert!(assessment.applied.is_empty());
+        assert_eq!(assessment.reason(), None);
+
+        let first = assess(&config, Some(&same), None, &[], true);
+        assert_eq!(first.extra_days(), 7);
+        assert_eq!(first.reason().unwrap(), "risk: first_version +7d");
+    }
+}
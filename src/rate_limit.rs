@@ -0,0 +1,482 @@
+//! Per-client rate limiting and the global cap on upstream fetches.
+//!
+//! [`RateLimitLayer`] sits in the server stack. It keys each request by client
+//! IP or token and charges metadata requests up front. Artifact requests
+//! carry a [`ClientBudget`] extension instead; the proxy charges the hit
+//! budget when serving from cache and calls
+//! [`ClientBudget::admit_upstream`] before fetching a miss. Index and
+//! metadata refreshes that reach upstream wait for the same global slots
+//! through [`with_upstream_slot`]. Over-budget requests get
+//! `429 Too Many Requests` with `Retry-After`.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::{Result, anyhow};
+use parking_lot::Mutex;
+use rama::{
+    Layer, Service,
+    error::BoxError,
+    extensions::{Extension, Extensions, ExtensionsRef as _},
+    http::{Body, HeaderValue, Request, Response, StatusCode, header},
+    net::stream::SocketInfo,
+    telemetry::tracing::warn,
+};
+use sha2::{Digest, Sha256};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::config::{RateLimitConfig, RateLimitKey};
+use crate::npm;
+use crate::proxy::types::CacheableRequest;
+
+/// Idle buckets are dropped once this many clients are tracked.
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+/// A bucket idle this long has refilled and can be dropped.
+const IDLE_BUCKET: Duration = Duration::from_secs(60);
+
+/// Budgets a client draws from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Budget {
+    Hits,
+    Misses,
+    Metadata,
+}
+
+impl Budget {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Budget::Hits => "hits",
+            Budget::Misses => "misses",
+            Budget::Metadata => "metadata",
+        }
+    }
+}
+
+/// A request refused because its client ran out of budget or upstream slots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limited {
+    pub budget: Budget,
+    pub retry_after: Duration,
+}
+
+impl Limited {
+    pub fn into_response(self) -> Result<Response<Body>> {
+        let retry_after = self.retry_after.as_secs_f64().ceil().max(1.0) as u64;
+        Response::builder()
+            .status(StatusCode::TOO_MANY_REQUESTS)
+            .header(header::CONTENT_TYPE, HeaderValue::from_static("text/plain"))
+            .header(header::RETRY_AFTER, retry_after)
+            .body(Body::from(format!(
+                "rate limit exceeded ({} budget), retry in {retry_after}s\n",
+                self.budget.as_str()
+            )))
+            .map_err(|e| anyhow!("building rate limit response: {e}"))
+    }
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Shared budgets for every client plus the upstream fetch slots.
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    buckets: Mutex<HashMap<(String, Budget), Bucket>>,
+    upstream: Option<Arc<Semaphore>>,
+}
+
+impl fmt::Debug for RateLimiter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RateLimiter")
+            .field("config", &self.config)
+            .finish_non_exhaustive()
+    }
+}
+
+impl RateLimiter {
+    pub fn new(config: &RateLimitConfig) -> Self {
+        Self {
+            config: config.clone(),
+            buckets: Mutex::new(HashMap::new()),
+            upstream: (config.max_concurrent_upstream > 0)
+                .then(|| Arc::new(Semaphore::new(config.max_concurrent_upstream))),
+        }
+    }
+
+    fn per_minute(&self, budget: Budget) -> u32 {
+        match budget {
+            Budget::Hits => self.config.hits_per_minute,
+            Budget::Misses => self.config.misses_per_minute,
+            Budget::Metadata => self.config.metadata_per_minute,
+        }
+    }
+
+    /// Takes one token from `client`'s `budget`.
+    pub fn charge(&self, client: &str, budget: Budget) -> Result<(), Limited> {
+        let per_minute = self.per_minute(budget);
+        if per_minute == 0 {
+            return Ok(());
+        }
+        let capacity = f64::from(per_minute);
+        let per_sec = capacity / 60.0;
+        let now = Instant::now();
+
+        let mut buckets = self.buckets.lock();
+        if buckets.len() >= MAX_TRACKED_CLIENTS {
+            buckets.retain(|_, bucket| now.duration_since(bucket.updated) < IDLE_BUCKET);
+        }
+        let bucket = buckets
+            .entry((client.to_string(), budget))
+            .or_insert(Bucket {
+                tokens: capacity,
+                updated: now,
+            });
+        let refilled = now.duration_since(bucket.updated).as_secs_f64() * per_sec;
+        bucket.tokens = (bucket.tokens + refilled).min(capacity);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Limited {
+                budget,
+                retry_after: Duration::from_secs_f64((1.0 - bucket.tokens) / per_sec),
+            })
+        }
+    }
+
+    /// Charges `client`'s miss budget and waits for a global upstream slot.
+    /// Hold the permit until the fetch completes.
+    pub async fn admit_upstream(
+        &self,
+        client: &str,
+    ) -> Result<Option<OwnedSemaphorePermit>, Limited> {
+        self.charge(client, Budget::Misses)?;
+        self.upstream_slot(client, Budget::Misses).await
+    }
+
+    /// Waits for a global upstream slot without charging a budget; `budget`
+    /// labels the refusal when none frees up in time.
+    pub async fn upstream_slot(
+        &self,
+        client: &str,
+        budget: Budget,
+    ) -> Result<Option<OwnedSemaphorePermit>, Limited> {
+        let Some(upstream) = &self.upstream else {
+            return Ok(None);
+        };
+        match tokio::time::timeout(
+            self.config.upstream_queue_timeout(),
+            upstream.clone().acquire_owned(),
+        )
+        .await
+        {
+            Ok(Ok(permit)) => Ok(Some(permit)),
+            _ => {
+                warn!(client, "upstream fetch slots exhausted");
+                Err(Limited {
+                    budget,
+                    retry_after: Duration::from_secs(1),
+                })
+            }
+        }
+    }
+}
+
+/// The rate-limited client a request belongs to, attached by
+/// [`RateLimitLayer`].
+#[derive(Debug, Clone)]
+pub struct ClientBudget {
+    limiter: Arc<RateLimiter>,
+    client: String,
+}
+
+impl Extension for ClientBudget {}
+
+impl ClientBudget {
+    pub fn of(extensions: &Extensions) -> Option<&ClientBudget> {
+        extensions.get_ref::<ClientBudget>()
+    }
+
+    pub fn client(&self) -> &str {
+        &self.client
+    }
+
+    pub fn charge(&self, budget: Budget) -> Result<(), Limited> {
+        self.limiter.charge(&self.client, budget)
+    }
+
+    pub async fn admit_upstream(&self) -> Result<Option<OwnedSemaphorePermit>, Limited> {
+        self.limiter.admit_upstream(&self.client).await
+    }
+}
+
+/// Runs an upstream index or metadata fetch once a global upstream slot is
+/// free. The request was already charged to the metadata budget by
+/// [`RateLimitLayer`]; running out of slots answers `429`.
+pub async fn with_upstream_slot(
+    client: Option<ClientBudget>,
+    fetch: impl Future<Output = Result<Response<Body>>>,
+) -> Result<Response<Body>> {
+    let _permit = match &client {
+        Some(client) => match client
+            .limiter
+            .upstream_slot(&client.client, Budget::Metadata)
+            .await
+        {
+            Ok(permit) => permit,
+            Err(limited) => return limited.into_response(),
+        },
+        None => None,
+    };
+    fetch.await
+}
+
+/// Layer applying [`RateLimitConfig`]; a pass-through when disabled.
+#[derive(Debug, Clone)]
+pub struct RateLimitLayer {
+    limiter: Option<Arc<RateLimiter>>,
+}
+
+impl RateLimitLayer {
+    pub fn new(config: &RateLimitConfig) -> Self {
+        Self {
+            limiter: config.enabled.then(|| Arc::new(RateLimiter::new(config))),
+        }
+    }
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimitService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimitService {
+            inner,
+            limiter: self.limiter.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RateLimitService<S> {
+    inner: S,
+    limiter: Option<Arc<RateLimiter>>,
+}
+
+impl<S> Service<Request<Body>> for RateLimitService<S>
+where
+    S: Service<Request<Body>, Output = Response<Body>, Error: Into<BoxError>>,
+{
+    type Output = Response<Body>;
+    type Error = BoxError;
+
+    async fn serve(&self, req: Request<Body>) -> Result<Self::Output, Self::Error> {
+        let Some(limiter) = &self.limiter else {
+            return self.inner.serve(req).await.map_err(Into::into);
+        };
+
+        let client = client_key(&req, &limiter.config);
+        match classify(&req) {
+            RequestClass::Exempt => {}
+            RequestClass::Metadata => {
+                if let Err(limited) = limiter.charge(&client, Budget::Metadata) {
+                    warn!(client, budget = "metadata", "rate limit exceeded");
+                    return Ok(limited.into_response()?);
+                }
+            }
+            RequestClass::Artifact => {}
+        }
+
+        req.extensions().insert(ClientBudget {
+            limiter: limiter.clone(),
+            client,
+        });
+        self.inner.serve(req).await.map_err(Into::into)
+    }
+}
+
+enum RequestClass {
+    /// Health probes and the homepage.
+    Exempt,
+    /// Gems, crates and npm tarballs; charged by the proxy as hits or misses.
+    Artifact,
+    /// Everything else: indexes, packuments, APIs.
+    Metadata,
+}
+
+fn classify(req: &Request<Body>) -> RequestClass {
+    let path = req.uri().path_or_root();
+    if matches!(path.as_ref(), "/" | "/livez" | "/readyz" | "/up") {
+        return RequestClass::Exempt;
+    }
+    let npm_tarball = npm::is_npm_request(req) && npm::tarball_request(&path).is_some();
+    if npm_tarball || CacheableRequest::from_request(req).is_some() {
+        RequestClass::Artifact
+    } else {
+        RequestClass::Metadata
+    }
+}
+
+/// `token:<hash>` for a client sending `Authorization` when keying by token,
+/// otherwise `ip:<address>`.
+fn client_key(req: &Request<Body>, config: &RateLimitConfig) -> String {
+    if config.key == RateLimitKey::Token
+        && let Some(token) = req.headers().get(header::AUTHORIZATION)
+    {
+        let digest = Sha256::digest(token.as_bytes());
+        return format!("token:{}", &hex::encode(digest)[..16]);
+    }
+
+    let forwarded = config
+        .trust_forwarded_for
+        .then(|| req.headers().get("x-forwarded-for"))
+        .flatten()
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(',').next())
+        .map(|ip| ip.trim().to_string())
+        .filter(|ip| !ip.is_empty());
+    let ip = forwarded.or_else(|| {
+        req.extensions()
+            .get_ref::<SocketInfo>()
+            .map(|socket| socket.peer_addr().ip_addr.to_string())
+    });
+    format!("ip:{}", ip.as_deref().unwrap_or("unknown"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rama::service::service_fn;
+
+    fn limited_config() -> RateLimitConfig {
+        RateLimitConfig {
+            enabled: true,
+            hits_per_minute: 2,
+            misses_per_minute: 1,
+            metadata_per_minute: 1,
+            max_concurrent_upstream: 1,
+            upstream_queue_timeout_ms: 20,
+            ..RateLimitConfig::default()
+        }
+    }
+
+    fn request(path: &str, headers: &[(&str, &str)]) -> Request<Body> {
+        let mut builder = Request::builder().uri(path);
+        for (name, value) in headers {
+            builder = builder.header(*name, *value);
+        }
+        builder.body(Body::empty()).unwrap()
+    }
+
+    #[test]
+    fn budgets_are_separate_per_client() {
+        let limiter = RateLimiter::new(&limited_config());
+        assert!(limiter.charge("ip:10.0.0.1", Budget::Hits).is_ok());
+        assert!(limiter.charge("ip:10.0.0.1", Budget::Hits).is_ok());
+        let limited = limiter.charge("ip:10.0.0.1", Budget::Hits).unwrap_err();
+        assert_eq!(limited.budget, Budget::Hits);
+        assert!(limited.retry_after > Duration::from_secs(20));
+
+        assert!(limiter.charge("ip:10.0.0.1", Budget::Metadata).is_ok());
+        assert!(limiter.charge("ip:10.0.0.2", Budget::Hits).is_ok());
+    }
+
+    #[test]
+    fn zero_budget_is_unlimited() {
+        let config = RateLimitConfig {
+            metadata_per_minute: 0,
+            ..limited_config()
+        };
+        let limiter = RateLimiter::new(&config);
+        for _ in 0..100 {
+            assert!(limiter.charge("ip:10.0.0.1", Budget::Metadata).is_ok());
+        }
+    }
+
+    #[tokio::test]
+    async fn upstream_slots_are_global() {
+        let config = RateLimitConfig {
+            misses_per_minute: 10,
+            ..limited_config()
+        };
+        let limiter = RateLimiter::new(&config);
+        let permit = limiter.admit_upstream("ip:10.0.0.1").await.unwrap();
+        assert!(permit.is_some());
+        let limited = limiter.admit_upstream("ip:10.0.0.2").await.unwrap_err();
+        assert_eq!(limited.budget, Budget::Misses);
+        drop(permit);
+        assert!(limiter.admit_upstream("ip:10.0.0.2").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn metadata_fetches_share_upstream_slots() {
+        let limiter = Arc::new(RateLimiter::new(&limited_config()));
+        let client = ClientBudget {
+            limiter: limiter.clone(),
+            client: "ip:10.0.0.2".to_string(),
+        };
+        let fetch = || async { Ok(Response::new(Body::from("index"))) };
+
+        let permit = limiter.admit_upstream("ip:10.0.0.1").await.unwrap();
+        let response = with_upstream_slot(Some(client.clone()), fetch())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+
+        drop(permit);
+        let response = with_upstream_slot(Some(client), fetch()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        // Taking a slot does not charge the misses budget
+        assert!(limiter.admit_upstream("ip:10.0.0.2").await.is_ok());
+    }
+
+    #[test]
+    fn keys_by_token_or_forwarded_ip() {
+        let config = RateLimitConfig {
+            key: RateLimitKey::Token,
+            trust_forwarded_for: true,
+            ..limited_config()
+        };
+        let token = client_key(
+            &request("/versions", &[("authorization", "Bearer ci")]),
+            &config,
+        );
+        assert!(token.starts_with("token:"));
+        assert!(!token.contains("ci"));
+
+        let forwarded = request("/versions", &[("x-forwarded-for", "203.0.113.7, 10.0.0.1")]);
+        assert_eq!(client_key(&forwarded, &config), "ip:203.0.113.7");
+
+        let config = RateLimitConfig {
+            trust_forwarded_for: false,
+            ..config
+        };
+        assert_eq!(client_key(&forwarded, &config), "ip:unknown");
+    }
+
+    #[tokio::test]
+    async fn layer_limits_metadata_and_tags_artifacts() {
+        let service = RateLimitLayer::new(&limited_config()).into_layer(service_fn(
+            async |req: Request<Body>| {
+                let tagged = ClientBudget::of(req.extensions()).is_some();
+                Ok::<_, BoxError>(Response::new(Body::from(tagged.to_string())))
+            },
+        ));
+
+        let first = service.serve(request("/versions", &[])).await.unwrap();
+        assert_eq!(first.status(), StatusCode::OK);
+        let second = service.serve(request("/versions", &[])).await.unwrap();
+        assert_eq!(second.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert!(second.headers().contains_key(header::RETRY_AFTER));
+
+        // Artifacts and health probes are not charged as metadata.
+        for path in ["/gems/rack-3.0.0.gem", "/up"] {
+            let response = service.serve(request(path, &[])).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+    }
+}
@@ -0,0 +1,176 @@
+//! SBOM output formats shared by the proxy endpoint and the admin download.
+//!
+//! CycloneDX documents are generated once when a package is cached and stored
+//! with its metadata. SPDX documents are rendered from the same metadata on
+//! request, so they always match the running generator.
+
+use std::str::FromStr;
+
+use anyhow::{Context, Result};
+use rama::telemetry::tracing::{info, warn};
+use serde_json::Value as JsonValue;
+use vein_adapter::{CacheBackend, CacheBackendTrait, Ecosystem, GemMetadata, PackageMetadata};
+
+mod aggregate;
+pub mod backfill;
+pub mod spdx;
+
+#[cfg(test)]
+mod tests;
+
+/// CycloneDX spec version written by the generators. Stored SBOMs written for
+/// another version are regenerated.
+pub const CYCLONEDX_SPEC_VERSION: &str = "1.5";
+
+/// Recorded next to every stored SBOM as `sbom_generator`.
+pub const SBOM_GENERATOR: &str = concat!("vein/", env!("CARGO_PKG_VERSION"));
+
+pub use aggregate::lockfile_sbom;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SbomFormat {
+    /// CycloneDX 1.5 JSON
+    #[default]
+    CycloneDx,
+    /// SPDX 2.3 JSON
+    SpdxJson,
+    /// SPDX 2.3 tag-value
+    SpdxTagValue,
+    /// SPDX 3.0 JSON-LD
+    Spdx3Json,
+}
+
+impl FromStr for SbomFormat {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "cyclonedx" | "cdx" => Ok(Self::CycloneDx),
+            "spdx" | "spdx-json" | "spdx2" => Ok(Self::SpdxJson),
+            "spdx-tv" | "spdx-tag-value" | "tag-value" => Ok(Self::SpdxTagValue),
+            "spdx3" | "spdx-3" => Ok(Self::Spdx3Json),
+            _ => Err(()),
+        }
+    }
+}
+
+impl SbomFormat {
+    pub fn content_type(self) -> &'static str {
+        match self {
+            SbomFormat::CycloneDx | SbomFormat::SpdxJson => "application/json; charset=utf-8",
+            SbomFormat::Spdx3Json => "application/ld+json; charset=utf-8",
+            SbomFormat::SpdxTagValue => "text/plain; charset=utf-8",
+        }
+    }
+
+    /// Download filename suffix, appended to `<name>-<version>`.
+    pub fn file_suffix(self) -> &'static str {
+        match self {
+            SbomFormat::CycloneDx => ".sbom.json",
+            SbomFormat::SpdxJson => ".spdx.json",
+            SbomFormat::SpdxTagValue => ".spdx",
+            SbomFormat::Spdx3Json => ".spdx3.json",
+        }
+    }
+}
+
+/// Whether a stored CycloneDX SBOM was written for [`CYCLONEDX_SPEC_VERSION`].
+pub fn is_current_cyclonedx(sbom: &JsonValue) -> bool {
+    sbom["specVersion"] == CYCLONEDX_SPEC_VERSION
+}
+
+/// Renders a gem SBOM. `None` when the gem has no stored CycloneDX document.
+pub fn render_gem(format: SbomFormat, metadata: &GemMetadata) -> Result<Option<String>> {
+    match format {
+        SbomFormat::CycloneDx => metadata
+            .sbom
+            .as_ref()
+            .map(|sbom| serde_json::to_string_pretty(sbom).context("serializing SBOM JSON"))
+            .transpose(),
+        _ => spdx::render(format, &spdx::SpdxSubject::from(metadata)).map(Some),
+    }
+}
+
+/// Renders a crate or npm SBOM. `None` when no CycloneDX document is stored.
+pub fn render_package(format: SbomFormat, metadata: &PackageMetadata) -> Result<Option<String>> {
+    match format {
+        SbomFormat::CycloneDx => metadata
+            .sbom
+            .as_ref()
+            .map(|sbom| serde_json::to_string_pretty(sbom).context("serializing SBOM JSON"))
+            .transpose(),
+        _ => spdx::render(format, &spdx::SpdxSubject::from(metadata)).map(Some),
+    }
+}
+
+/// Loads gem metadata, regenerating and storing a CycloneDX SBOM that was
+/// written for an older spec version.
+pub async fn current_gem_metadata(
+    index: &CacheBackend,
+    name: &str,
+    version: &str,
+    platform: Option<&str>,
+) -> Result<Option<GemMetadata>> {
+    let Some(mut metadata) = index
+        .gem_metadata(name, version, platform)
+        .await
+        .context("loading cached gem metadata")?
+    else {
+        return Ok(None);
+    };
+    if metadata
+        .sbom
+        .as_ref()
+        .is_some_and(|sbom| !is_current_cyclonedx(sbom))
+    {
+        metadata.sbom = crate::gem_metadata::generate_cyclonedx_sbom(&metadata, None)?;
+        metadata.sbom_generator = Some(SBOM_GENERATOR.to_string());
+        info!(
+            event = "sbom.regenerate",
+            gem = %metadata.name,
+            version = %metadata.version,
+            platform = %metadata.platform,
+            "regenerated outdated CycloneDX SBOM"
+        );
+        if let Err(err) = index.upsert_metadata(&metadata).await {
+            warn!(error = %err, gem = %metadata.name, "failed to store regenerated SBOM");
+        }
+    }
+    Ok(Some(metadata))
+}
+
+/// Loads crate or npm metadata, regenerating and storing a CycloneDX SBOM that
+/// was written for an older spec version.
+pub async fn current_package_metadata(
+    index: &CacheBackend,
+    ecosystem: Ecosystem,
+    name: &str,
+    version: &str,
+) -> Result<Option<PackageMetadata>> {
+    let Some(mut metadata) = index
+        .package_metadata(ecosystem, name, version)
+        .await
+        .context("loading cached package metadata")?
+    else {
+        return Ok(None);
+    };
+    if metadata
+        .sbom
+        .as_ref()
+        .is_some_and(|sbom| !is_current_cyclonedx(sbom))
+    {
+        metadata.sbom = crate::package_metadata::generate_cyclonedx_sbom(&metadata)?;
+        metadata.sbom_generator = Some(SBOM_GENERATOR.to_string());
+        info!(
+            event = "sbom.regenerate",
+            ecosystem = %ecosystem,
+            package = %metadata.name,
+            version = %metadata.version,
+            "regenerated outdated CycloneDX SBOM"
+        );
+        if let Err(err) = index.upsert_package_metadata(&metadata).await {
+            warn!(error = %err, package = %metadata.name, "failed to store regenerated SBOM");
+        }
+    }
+    Ok(Some(metadata))
+}
@@ -0,0 +1,266 @@
+//! One SBOM for every registry package a lockfile pins.
+//!
+//! Components come from the per-package SBOMs Vein stores, hashes from the
+//! cached artifacts and dependency edges from the lockfile itself. Packages
+//! Vein has not cached are still listed, with identity only.
+
+use std::collections::HashMap;
+
+use anyhow::{Context, Result, bail};
+use cyclonedx_bom::{
+    models::{
+        component::Classification,
+        dependency::{Dependencies, Dependency},
+        hash::{Hash, HashAlgorithm, HashValue, Hashes},
+        metadata::Metadata as BomMetadata,
+        tool::{Tool, Tools},
+    },
+    prelude::{Bom, Component, Components, Purl, SpecVersion, Validate},
+};
+use rama::telemetry::tracing::warn;
+use serde_json::Value as JsonValue;
+use sha2::{Digest, Sha256};
+use vein_adapter::{CacheBackend, CacheBackendTrait, Ecosystem};
+
+use super::spdx::{self, SpdxGraph, SpdxSubject};
+use super::{SbomFormat, current_gem_metadata, current_package_metadata};
+use crate::lockfile::{LockedPackage, Lockfile};
+
+/// A locked package with whatever Vein knows about it.
+struct LockedComponent {
+    subject: SpdxSubject,
+    /// `metadata.component` of the stored CycloneDX SBOM
+    stored: Option<JsonValue>,
+}
+
+/// Renders one document describing every package of `lockfile`. `name`
+/// names the application the lockfile belongs to.
+pub async fn lockfile_sbom(
+    index: &CacheBackend,
+    lockfile: &Lockfile,
+    name: &str,
+    format: SbomFormat,
+) -> Result<String> {
+    let mut components = Vec::with_capacity(lockfile.packages.len());
+    for package in &lockfile.packages {
+        components.push(load_component(index, package).await?);
+    }
+    let edges = resolve_edges(&lockfile.packages);
+
+    match format {
+        SbomFormat::CycloneDx => cyclonedx(name, &components, &edges),
+        _ => {
+            let mut hasher = Sha256::new();
+            for component in &components {
+                hasher.update(component.subject.purl().as_bytes());
+                hasher.update(b"\n");
+            }
+            let graph = SpdxGraph {
+                name: name.to_string(),
+                namespace_id: hex::encode(hasher.finalize()),
+                packages: components
+                    .into_iter()
+                    .map(|component| component.subject)
+                    .collect(),
+                edges,
+            };
+            spdx::render_graph(format, &graph)
+        }
+    }
+}
+
+async fn load_component(index: &CacheBackend, package: &LockedPackage) -> Result<LockedComponent> {
+    let cached = index
+        .peek(&package.asset_key())
+        .await
+        .with_context(|| format!("looking up cached artifact for {}", package.name))?;
+
+    let (mut subject, stored_sbom) = match package.ecosystem {
+        Ecosystem::RubyGems => {
+            let platform = package.platform.as_deref().unwrap_or("ruby");
+            match current_gem_metadata(index, &package.name, &package.version, Some(platform))
+                .await?
+            {
+                Some(meta) => (SpdxSubject::from(&meta), meta.sbom),
+                None => (SpdxSubject::from(package), None),
+            }
+        }
+        Ecosystem::CratesIo | Ecosystem::Npm => {
+            match current_package_metadata(
+                index,
+                package.ecosystem,
+                &package.name,
+                &package.version,
+            )
+            .await?
+            {
+                Some(meta) => (SpdxSubject::from(&meta), meta.sbom),
+                None => (SpdxSubject::from(package), None),
+            }
+        }
+    };
+
+    // Edges come from the lockfile, not the declared requirements.
+    subject.dependencies.clear();
+    if let Some(sha256) = cached
+        .map(|asset| asset.sha256)
+        .filter(|sha256| !sha256.is_empty())
+    {
+        subject.sha256 = sha256;
+    }
+
+    Ok(LockedComponent {
+        subject,
+        stored: stored_sbom.map(|mut sbom| sbom["metadata"]["component"].take()),
+    })
+}
+
+/// Dependencies of each package as indexes into `packages`. An edge with a
+/// version links that version; without one (Bundler, npm v1) it links every
+/// locked version of the name, e.g. each platform build of a gem.
+fn resolve_edges(packages: &[LockedPackage]) -> Vec<Vec<usize>> {
+    let mut by_name: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (index, package) in packages.iter().enumerate() {
+        by_name.entry(&package.name).or_default().push(index);
+    }
+
+    packages
+        .iter()
+        .enumerate()
+        .map(|(from, package)| {
+            let mut targets: Vec<usize> = package
+                .dependencies
+                .iter()
+                .flat_map(|dep| {
+                    let candidates = by_name
+                        .get(dep.name.as_str())
+                        .map_or(&[][..], Vec::as_slice);
+                    let exact: Vec<usize> = candidates
+                        .iter()
+                        .copied()
+                        .filter(|&to| Some(&packages[to].version) == dep.version.as_ref())
+                        .collect();
+                    if exact.is_empty() {
+                        candidates.to_vec()
+                    } else {
+                        exact
+                    }
+                })
+                .filter(|&to| to != from)
+                .collect();
+            targets.sort_unstable();
+            targets.dedup();
+            targets
+        })
+        .collect()
+}
+
+fn cyclonedx(name: &str, components: &[LockedComponent], edges: &[Vec<usize>]) -> Result<String> {
+    let refs: Vec<String> = components
+        .iter()
+        .map(|component| component.subject.purl())
+        .collect();
+
+    let bom_components = components
+        .iter()
+        .zip(&refs)
+        .map(|(component, bom_ref)| cyclonedx_component(component, bom_ref))
+        .collect();
+
+    let root_ref = format!("vein:lockfile:{name}");
+    let mut root = Component::new(
+        Classification::Application,
+        name,
+        "",
+        Some(root_ref.clone()),
+    );
+    root.version = None;
+
+    let mut depended_on = vec![false; components.len()];
+    for &to in edges.iter().flatten() {
+        depended_on[to] = true;
+    }
+    let mut dependencies = vec![Dependency {
+        dependency_ref: root_ref,
+        dependencies: (0..components.len())
+            .filter(|&index| !depended_on[index])
+            .map(|index| refs[index].clone())
+            .collect(),
+    }];
+    dependencies.extend(edges.iter().enumerate().map(|(from, targets)| Dependency {
+        dependency_ref: refs[from].clone(),
+        dependencies: targets.iter().map(|&to| refs[to].clone()).collect(),
+    }));
+
+    let mut bom_metadata = BomMetadata::new().unwrap_or_default();
+    bom_metadata.component = Some(root);
+    bom_metadata.tools = Some(Tools::List(vec![Tool::new(
+        "Ore Ecosystem",
+        "Vein",
+        env!("CARGO_PKG_VERSION"),
+    )]));
+
+    let bom = Bom {
+        spec_version: SpecVersion::V1_5,
+        metadata: Some(bom_metadata),
+        components: Some(Components(bom_components)),
+        dependencies: Some(Dependencies(dependencies)),
+        ..Bom::default()
+    };
+
+    let validation = bom.validate_version(SpecVersion::V1_5);
+    if !validation.passed() {
+        warn!(?validation, "aggregate CycloneDX SBOM failed validation");
+        bail!("generated CycloneDX SBOM failed validation");
+    }
+
+    let mut output = Vec::new();
+    bom.output_as_json_v1_5(&mut output)
+        .context("serializing CycloneDX SBOM")?;
+    let sbom: JsonValue = serde_json::from_slice(&output).context("parsing CycloneDX SBOM json")?;
+    serde_json::to_string_pretty(&sbom).context("serializing SBOM JSON")
+}
+
+/// The stored component when there is one, otherwise a bare library entry.
+/// Either way the `bom-ref` is the purl and the hash is the cached artifact's.
+fn cyclonedx_component(component: &LockedComponent, bom_ref: &str) -> Component {
+    let subject = &component.subject;
+    let stored = component.stored.as_ref().and_then(|stored| {
+        let document = serde_json::json!({
+            "bomFormat": "CycloneDX",
+            "specVersion": super::CYCLONEDX_SPEC_VERSION,
+            "version": 1,
+            "metadata": { "component": stored },
+        });
+        Bom::parse_from_json_v1_5(document.to_string().as_bytes())
+            .ok()
+            .and_then(|bom| bom.metadata?.component)
+    });
+
+    let mut bom_component = match stored {
+        Some(component) => component,
+        None => {
+            let mut bare = Component::new(
+                Classification::Library,
+                &subject.name,
+                &subject.version,
+                None,
+            );
+            let purl_type = match subject.ecosystem {
+                Ecosystem::RubyGems => "gem",
+                Ecosystem::CratesIo => "cargo",
+                Ecosystem::Npm => "npm",
+            };
+            bare.purl = Purl::new(purl_type, &subject.name, &subject.version).ok();
+            bare
+        }
+    };
+    bom_component.bom_ref = Some(bom_ref.to_string());
+    if !subject.sha256.is_empty() {
+        bom_component.hashes = Some(Hashes(vec![Hash {
+            alg: HashAlgorithm::SHA_256,
+            content: HashValue(subject.sha256.clone()),
+        }]));
+    }
+    bom_component
+}
@@ -0,0 +1,267 @@
+//! Regenerates metadata and SBOMs for artifacts that are already cached.
+//!
+//! SBOMs are written when an artifact is first cached, so anything cached
+//! before SBOM support or by an older Vein has none or a stale one. The
+//! backfill re-parses those artifacts from storage and records which
+//! generator wrote each SBOM.
+
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use rama::telemetry::tracing::{info, warn};
+use serde::Serialize;
+use tokio::{sync::Semaphore, task::JoinSet};
+use vein_adapter::{
+    AssetKey, AssetKind, CacheBackend, CacheBackendTrait, Ecosystem, FilesystemStorage,
+};
+
+use super::SBOM_GENERATOR;
+
+/// Parallel re-parses used when the caller does not ask for a limit.
+pub const DEFAULT_BACKFILL_CONCURRENCY: usize = 4;
+/// Upper bound on parallel re-parses.
+pub const MAX_BACKFILL_CONCURRENCY: usize = 32;
+
+#[derive(Debug, Clone, Default)]
+pub struct BackfillOptions {
+    /// Regenerate SBOMs the running generator already wrote.
+    pub force: bool,
+    /// Ecosystems to walk; empty walks all of them.
+    pub ecosystems: Vec<Ecosystem>,
+    pub concurrency: usize,
+}
+
+/// What happened to a single cached artifact.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackfillOutcome {
+    /// Metadata and SBOM were rewritten.
+    Regenerated,
+    /// The stored SBOM came from the running generator.
+    UpToDate,
+    /// The artifact is gone from storage.
+    Missing,
+    /// The archive holds no manifest Vein understands.
+    NoMetadata,
+    /// Parsing or storing failed.
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BackfillItem {
+    pub ecosystem: Ecosystem,
+    pub name: String,
+    pub version: String,
+    pub platform: Option<String>,
+    pub outcome: BackfillOutcome,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct BackfillSummary {
+    pub total: usize,
+    pub regenerated: usize,
+    pub up_to_date: usize,
+    pub missing: usize,
+    pub no_metadata: usize,
+    pub failed: usize,
+}
+
+/// A cached artifact waiting to be re-parsed.
+struct BackfillJob {
+    kind: AssetKind,
+    name: String,
+    version: String,
+    platform: Option<String>,
+}
+
+/// Walks the cached gems, crates and npm packages and regenerates their
+/// metadata and SBOMs. `progress` is called after each artifact with the
+/// number finished so far and the total.
+pub async fn backfill_sboms(
+    index: Arc<CacheBackend>,
+    storage: FilesystemStorage,
+    options: &BackfillOptions,
+    mut progress: impl FnMut(usize, usize, &BackfillItem) + Send,
+) -> Result<BackfillSummary> {
+    let mut jobs = Vec::new();
+    for kind in [AssetKind::Gem, AssetKind::Crate, AssetKind::NpmPackage] {
+        if !options.ecosystems.is_empty() && !options.ecosystems.contains(&kind.ecosystem()) {
+            continue;
+        }
+        let versions = index
+            .cached_versions(kind)
+            .await
+            .with_context(|| format!("listing cached {} artifacts", kind.as_str()))?;
+        jobs.extend(
+            versions
+                .into_iter()
+                .map(|(name, version, platform)| BackfillJob {
+                    kind,
+                    name,
+                    version,
+                    platform,
+                }),
+        );
+    }
+
+    let permits = Arc::new(Semaphore::new(
+        options.concurrency.clamp(1, MAX_BACKFILL_CONCURRENCY),
+    ));
+    let mut tasks = JoinSet::new();
+    let mut summary = BackfillSummary {
+        total: jobs.len(),
+        ..BackfillSummary::default()
+    };
+
+    for job in jobs {
+        let index = index.clone();
+        let storage = storage.clone();
+        let permits = permits.clone();
+        let force = options.force;
+        tasks.spawn(async move {
+            let _permit = permits.acquire_owned().await;
+            backfill_one(&index, &storage, job, force).await
+        });
+    }
+
+    let mut done = 0;
+    while let Some(joined) = tasks.join_next().await {
+        let item = match joined {
+            Ok(item) => item,
+            Err(err) => {
+                warn!(error = %err, "SBOM backfill task panicked");
+                summary.failed += 1;
+                continue;
+            }
+        };
+        match item.outcome {
+            BackfillOutcome::Regenerated => summary.regenerated += 1,
+            BackfillOutcome::UpToDate => summary.up_to_date += 1,
+            BackfillOutcome::Missing => summary.missing += 1,
+            BackfillOutcome::NoMetadata => summary.no_metadata += 1,
+            BackfillOutcome::Failed => summary.failed += 1,
+        }
+        done += 1;
+        progress(done, summary.total, &item);
+    }
+
+    info!(
+        event = "sbom.backfill",
+        generator = SBOM_GENERATOR,
+        total = summary.total,
+        regenerated = summary.regenerated,
+        up_to_date = summary.up_to_date,
+        missing = summary.missing,
+        no_metadata = summary.no_metadata,
+        failed = summary.failed,
+        "SBOM backfill finished"
+    );
+    Ok(summary)
+}
+
+async fn backfill_one(
+    index: &CacheBackend,
+    storage: &FilesystemStorage,
+    job: BackfillJob,
+    force: bool,
+) -> BackfillItem {
+    let outcome = regenerate(index, storage, &job, force).await;
+    if let Err(err) = &outcome {
+        warn!(
+            error = %err,
+            kind = job.kind.as_str(),
+            name = %job.name,
+            version = %job.version,
+            "SBOM backfill failed"
+        );
+    }
+    let (outcome, error) = match outcome {
+        Ok(outcome) => (outcome, None),
+        Err(err) => (BackfillOutcome::Failed, Some(format!("{err:#}"))),
+    };
+    BackfillItem {
+        ecosystem: job.kind.ecosystem(),
+        name: job.name,
+        version: job.version,
+        platform: job.platform,
+        outcome,
+        error,
+    }
+}
+
+async fn regenerate(
+    index: &CacheBackend,
+    storage: &FilesystemStorage,
+    job: &BackfillJob,
+    force: bool,
+) -> Result<BackfillOutcome> {
+    let key = AssetKey {
+        kind: job.kind,
+        name: &job.name,
+        version: &job.version,
+        platform: job.platform.as_deref(),
+    };
+    let Some(asset) = index.peek(&key).await? else {
+        return Ok(BackfillOutcome::Missing);
+    };
+    let path = storage.resolve(&asset.path);
+    if !tokio::fs::try_exists(&path).await.unwrap_or(false) {
+        return Ok(BackfillOutcome::Missing);
+    }
+
+    if job.kind == AssetKind::Gem {
+        let platform = job.platform.as_deref().unwrap_or("ruby");
+        if !force
+            && let Some(existing) = index
+                .gem_metadata(&job.name, &job.version, Some(platform))
+                .await?
+            && existing.sbom.is_some()
+            && existing.sbom_generator.as_deref() == Some(SBOM_GENERATOR)
+        {
+            return Ok(BackfillOutcome::UpToDate);
+        }
+        let Some(archive) = crate::gem_metadata::extract_gem_archive(
+            &path,
+            &job.name,
+            &job.version,
+            job.platform.as_deref(),
+            asset.size_bytes,
+            &asset.sha256,
+            None,
+        )
+        .await?
+        else {
+            return Ok(BackfillOutcome::NoMetadata);
+        };
+        crate::gem_metadata::store_gem_archive(index, &archive).await?;
+    } else {
+        let ecosystem = job.kind.ecosystem();
+        if !force
+            && let Some(existing) = index
+                .package_metadata(ecosystem, &job.name, &job.version)
+                .await?
+            && existing.sbom.is_some()
+            && existing.sbom_generator.as_deref() == Some(SBOM_GENERATOR)
+        {
+            return Ok(BackfillOutcome::UpToDate);
+        }
+        let Some(metadata) = crate::package_metadata::extract_package_metadata(
+            ecosystem,
+            &path,
+            &job.name,
+            &job.version,
+            asset.size_bytes,
+            &asset.sha256,
+        )
+        .await?
+        else {
+            return Ok(BackfillOutcome::NoMetadata);
+        };
+        index
+            .upsert_package_metadata(&metadata)
+            .await
+            .context("storing package metadata")?;
+    }
+    Ok(BackfillOutcome::Regenerated)
+}
@@ -0,0 +1,768 @@
+//! SPDX 2.3 (JSON and tag-value) and SPDX 3.0 (JSON-LD) documents.
+
+use std::fmt::Write as _;
+
+use anyhow::{Context, Result};
+use chrono::{SecondsFormat, Utc};
+use cyclonedx_bom::prelude::SpdxExpression;
+use serde_json::{Value as JsonValue, json};
+use vein_adapter::{DependencyKind, Ecosystem, GemMetadata, PackageMetadata};
+
+use super::SbomFormat;
+use crate::lockfile::LockedPackage;
+
+const NOASSERTION: &str = "NOASSERTION";
+const ROOT_ID: &str = "SPDXRef-Package";
+
+/// When in its lifecycle the described package needs a dependency.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DependencyScope {
+    Runtime,
+    Development,
+    Build,
+}
+
+#[derive(Debug, Clone)]
+pub struct SpdxDependency {
+    pub name: String,
+    pub requirement: String,
+    pub scope: DependencyScope,
+    pub optional: bool,
+}
+
+/// The fields SPDX documents are built from, common to every ecosystem.
+#[derive(Debug, Clone)]
+pub struct SpdxSubject {
+    pub ecosystem: Ecosystem,
+    pub name: String,
+    pub version: String,
+    /// Non-default platform, e.g. `x86_64-linux` for a native gem
+    pub platform: Option<String>,
+    pub summary: Option<String>,
+    pub description: Option<String>,
+    /// Declared licenses; several entries all apply
+    pub licenses: Vec<String>,
+    pub authors: Vec<String>,
+    pub homepage: Option<String>,
+    pub source_url: Option<String>,
+    /// Artifact digest; empty when Vein has not cached the artifact
+    pub sha256: String,
+    pub dependencies: Vec<SpdxDependency>,
+}
+
+impl From<&GemMetadata> for SpdxSubject {
+    fn from(meta: &GemMetadata) -> Self {
+        SpdxSubject {
+            ecosystem: Ecosystem::RubyGems,
+            name: meta.name.clone(),
+            version: meta.version.clone(),
+            platform: (meta.platform != "ruby").then(|| meta.platform.clone()),
+            summary: meta.summary.clone(),
+            description: meta.description.clone(),
+            licenses: meta.licenses.clone(),
+            authors: meta.authors.clone(),
+            homepage: meta.homepage.clone(),
+            source_url: meta.source_code_url.clone(),
+            sha256: meta.sha256.clone(),
+            dependencies: meta
+                .dependencies
+                .iter()
+                .map(|dep| SpdxDependency {
+                    name: dep.name.clone(),
+                    requirement: dep.requirement.clone(),
+                    scope: match dep.kind {
+                        DependencyKind::Development => DependencyScope::Development,
+                        _ => DependencyScope::Runtime,
+                    },
+                    optional: dep.kind == DependencyKind::Optional,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<&PackageMetadata> for SpdxSubject {
+    fn from(meta: &PackageMetadata) -> Self {
+        SpdxSubject {
+            ecosystem: meta.ecosystem,
+            name: meta.name.clone(),
+            version: meta.version.clone(),
+            platform: None,
+            summary: None,
+            description: meta.description.clone(),
+            licenses: meta.license.iter().cloned().collect(),
+            authors: meta.authors.clone(),
+            homepage: meta.homepage.clone(),
+            source_url: meta.repository.clone(),
+            sha256: meta.sha256.clone(),
+            dependencies: meta
+                .dependencies
+                .iter()
+                .map(|dep| SpdxDependency {
+                    name: dep.name.clone(),
+                    requirement: dep.requirement.clone(),
+                    scope: match dep.kind.as_str() {
+                        "dev" => DependencyScope::Development,
+                        "build" => DependencyScope::Build,
+                        _ => DependencyScope::Runtime,
+                    },
+                    optional: dep.optional,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<&LockedPackage> for SpdxSubject {
+    /// A package Vein holds no metadata for: identity only.
+    fn from(package: &LockedPackage) -> Self {
+        SpdxSubject {
+            ecosystem: package.ecosystem,
+            name: package.name.clone(),
+            version: package.version.clone(),
+            platform: package
+                .platform
+                .clone()
+                .filter(|platform| platform != "ruby"),
+            summary: None,
+            description: None,
+            licenses: Vec::new(),
+            authors: Vec::new(),
+            homepage: None,
+            source_url: None,
+            sha256: String::new(),
+            dependencies: Vec::new(),
+        }
+    }
+}
+
+impl SpdxSubject {
+    pub(super) fn purl(&self) -> String {
+        let purl_type = match self.ecosystem {
+            Ecosystem::RubyGems => "gem",
+            Ecosystem::CratesIo => "cargo",
+            Ecosystem::Npm => "npm",
+        };
+        // Scoped npm names keep their `/`, but the `@` is percent-encoded.
+        let name = match self.name.strip_prefix('@') {
+            Some(scoped) => format!("%40{scoped}"),
+            None => self.name.clone(),
+        };
+        let mut purl = format!("pkg:{purl_type}/{name}@{}", self.version);
+        if let Some(platform) = &self.platform {
+            purl.push_str("?platform=");
+            purl.push_str(platform);
+        }
+        purl
+    }
+
+    fn document_name(&self) -> String {
+        match &self.platform {
+            Some(platform) => format!("{}-{}-{platform}", self.name, self.version),
+            None => format!("{}-{}", self.name, self.version),
+        }
+    }
+
+    /// Stable per artifact: the same bytes always get the same namespace.
+    fn namespace(&self) -> String {
+        format!(
+            "{}/spdxdocs/{}/{}-{}",
+            env!("CARGO_PKG_REPOSITORY"),
+            self.ecosystem,
+            self.document_name().replace('/', "-"),
+            self.sha256
+        )
+    }
+
+    /// Declared licenses as one SPDX expression; `None` when any entry is not
+    /// valid SPDX, so the raw list goes into a comment instead.
+    fn license_expression(&self) -> Option<String> {
+        let parsed: Vec<String> = self
+            .licenses
+            .iter()
+            .map(|license| license.trim())
+            .filter(|license| !license.is_empty())
+            .map(|license| {
+                SpdxExpression::parse_lax(license.to_string())
+                    .ok()
+                    .map(|expr| expr.to_string())
+            })
+            .collect::<Option<_>>()?;
+        match parsed.len() {
+            0 => None,
+            1 => parsed.into_iter().next(),
+            _ => Some(
+                parsed
+                    .iter()
+                    .map(|expr| format!("({expr})"))
+                    .collect::<Vec<_>>()
+                    .join(" AND "),
+            ),
+        }
+    }
+
+    fn license_comment(&self) -> Option<String> {
+        (self.license_expression().is_none() && !self.licenses.is_empty())
+            .then(|| format!("Declared licenses: {}", self.licenses.join(", ")))
+    }
+
+    fn originator(&self) -> Option<String> {
+        let authors: Vec<&str> = self
+            .authors
+            .iter()
+            .map(|author| author.trim())
+            .filter(|author| !author.is_empty())
+            .collect();
+        (!authors.is_empty()).then(|| format!("Person: {}", authors.join(", ")))
+    }
+}
+
+/// Several packages and the dependency edges between them, e.g. everything a
+/// lockfile pins.
+#[derive(Debug, Clone)]
+pub struct SpdxGraph {
+    pub name: String,
+    /// Distinguishes documents with the same name, e.g. a digest of the input
+    pub namespace_id: String,
+    pub packages: Vec<SpdxSubject>,
+    /// Dependencies of each package, as indexes into `packages`
+    pub edges: Vec<Vec<usize>>,
+}
+
+impl SpdxGraph {
+    fn namespace(&self) -> String {
+        format!(
+            "{}/spdxdocs/lockfile/{}-{}",
+            env!("CARGO_PKG_REPOSITORY"),
+            self.name.replace('/', "-"),
+            self.namespace_id
+        )
+    }
+
+    /// Packages nothing else depends on; every package when cycles leave none.
+    fn roots(&self) -> Vec<usize> {
+        let mut depended_on = vec![false; self.packages.len()];
+        for &to in self.edges.iter().flatten() {
+            depended_on[to] = true;
+        }
+        let roots: Vec<usize> = (0..self.packages.len())
+            .filter(|&index| !depended_on[index])
+            .collect();
+        if roots.is_empty() {
+            (0..self.packages.len()).collect()
+        } else {
+            roots
+        }
+    }
+}
+
+/// Renders `subject` in one of the SPDX formats.
+pub fn render(format: SbomFormat, subject: &SpdxSubject) -> Result<String> {
+    let created = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true);
+    match format {
+        SbomFormat::SpdxJson => serde_json::to_string_pretty(&spdx2_json(subject, &created))
+            .context("serializing SPDX JSON"),
+        SbomFormat::SpdxTagValue => Ok(spdx2_tag_value(subject, &created)),
+        SbomFormat::Spdx3Json => serde_json::to_string_pretty(&spdx3_json(subject, &created))
+            .context("serializing SPDX 3 JSON-LD"),
+        SbomFormat::CycloneDx => anyhow::bail!("CycloneDX is not an SPDX format"),
+    }
+}
+
+/// Renders a multi-package `graph` in one of the SPDX formats.
+pub fn render_graph(format: SbomFormat, graph: &SpdxGraph) -> Result<String> {
+    let created = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true);
+    match format {
+        SbomFormat::SpdxJson => serde_json::to_string_pretty(&graph_spdx2_json(graph, &created))
+            .context("serializing SPDX JSON"),
+        SbomFormat::SpdxTagValue => Ok(graph_spdx2_tag_value(graph, &created)),
+        SbomFormat::Spdx3Json => serde_json::to_string_pretty(&graph_spdx3_json(graph, &created))
+            .context("serializing SPDX 3 JSON-LD"),
+        SbomFormat::CycloneDx => anyhow::bail!("CycloneDX is not an SPDX format"),
+    }
+}
+
+fn creator() -> String {
+    format!("Tool: vein-{}", env!("CARGO_PKG_VERSION"))
+}
+
+fn dependency_id(index: usize) -> String {
+    format!("SPDXRef-Dependency-{}", index + 1)
+}
+
+fn graph_package_id(index: usize) -> String {
+    format!("SPDXRef-Package-{}", index + 1)
+}
+
+/// SPDX 2.3 relationship for a dependency edge, as `(from, type, to)`.
+fn spdx2_relationship(index: usize, dep: &SpdxDependency) -> (String, &'static str, String) {
+    let dep_id = dependency_id(index);
+    let kind = match (dep.scope, dep.optional) {
+        (DependencyScope::Development, _) => "DEV_DEPENDENCY_OF",
+        (DependencyScope::Build, _) => "BUILD_DEPENDENCY_OF",
+        (DependencyScope::Runtime, true) => "OPTIONAL_DEPENDENCY_OF",
+        (DependencyScope::Runtime, false) => {
+            return (ROOT_ID.to_string(), "DEPENDS_ON", dep_id);
+        }
+    };
+    (dep_id, kind, ROOT_ID.to_string())
+}
+
+fn spdx2_relationship_json(from: &str, kind: &str, to: &str) -> JsonValue {
+    json!({
+        "spdxElementId": from,
+        "relationshipType": kind,
+        "relatedSpdxElement": to,
+    })
+}
+
+/// The SPDX 2.3 package entry for `subject`.
+fn spdx2_package(subject: &SpdxSubject, id: &str) -> JsonValue {
+    let mut package = json!({
+        "SPDXID": id,
+        "name": subject.name,
+        "versionInfo": subject.version,
+        "downloadLocation": NOASSERTION,
+        "filesAnalyzed": false,
+        "licenseConcluded": NOASSERTION,
+        "licenseDeclared": subject.license_expression().unwrap_or_else(|| NOASSERTION.to_string()),
+        "copyrightText": NOASSERTION,
+        "primaryPackagePurpose": "LIBRARY",
+        "externalRefs": [{
+            "referenceCategory": "PACKAGE-MANAGER",
+            "referenceType": "purl",
+            "referenceLocator": subject.purl(),
+        }],
+    });
+    if !subject.sha256.is_empty() {
+        package["checksums"] = json!([{ "algorithm": "SHA256", "checksumValue": subject.sha256 }]);
+    }
+    let optional = [
+        ("summary", subject.summary.clone()),
+        ("description", subject.description.clone()),
+        ("homepage", subject.homepage.clone()),
+        (
+            "sourceInfo",
+            subject
+                .source_url
+                .as_ref()
+                .map(|url| format!("Source repository: {url}")),
+        ),
+        ("originator", subject.originator()),
+        ("licenseComments", subject.license_comment()),
+    ];
+    for (key, value) in optional {
+        if let Some(value) = value {
+            package[key] = JsonValue::String(value);
+        }
+    }
+    package
+}
+
+fn spdx2_document(
+    name: &str,
+    namespace: &str,
+    created: &str,
+    describes: Vec<String>,
+    packages: Vec<JsonValue>,
+    relationships: Vec<JsonValue>,
+) -> JsonValue {
+    json!({
+        "spdxVersion": "SPDX-2.3",
+        "dataLicense": "CC0-1.0",
+        "SPDXID": "SPDXRef-DOCUMENT",
+        "name": name,
+        "documentNamespace": namespace,
+        "creationInfo": {
+            "created": created,
+            "creators": [creator()],
+        },
+        "documentDescribes": describes,
+        "packages": packages,
+        "relationships": relationships,
+    })
+}
+
+fn spdx2_json(subject: &SpdxSubject, created: &str) -> JsonValue {
+    let mut packages = vec![spdx2_package(subject, ROOT_ID)];
+    let mut relationships = vec![spdx2_relationship_json(
+        "SPDXRef-DOCUMENT",
+        "DESCRIBES",
+        ROOT_ID,
+    )];
+    for (index, dep) in subject.dependencies.iter().enumerate() {
+        packages.push(json!({
+            "SPDXID": dependency_id(index),
+            "name": dep.name,
+            "downloadLocation": NOASSERTION,
+            "filesAnalyzed": false,
+            "licenseConcluded": NOASSERTION,
+            "licenseDeclared": NOASSERTION,
+            "copyrightText": NOASSERTION,
+            "comment": format!("Version requirement: {}", dep.requirement),
+        }));
+        let (from, kind, to) = spdx2_relationship(index, dep);
+        relationships.push(spdx2_relationship_json(&from, kind, &to));
+    }
+
+    spdx2_document(
+        &subject.document_name(),
+        &subject.namespace(),
+        created,
+        vec![ROOT_ID.to_string()],
+        packages,
+        relationships,
+    )
+}
+
+fn graph_spdx2_json(graph: &SpdxGraph, created: &str) -> JsonValue {
+    let packages = graph
+        .packages
+        .iter()
+        .enumerate()
+        .map(|(index, subject)| spdx2_package(subject, &graph_package_id(index)))
+        .collect();
+    let roots: Vec<String> = graph.roots().into_iter().map(graph_package_id).collect();
+    let mut relationships: Vec<JsonValue> = roots
+        .iter()
+        .map(|root| spdx2_relationship_json("SPDXRef-DOCUMENT", "DESCRIBES", root))
+        .collect();
+    for (from, targets) in graph.edges.iter().enumerate() {
+        for &to in targets {
+            relationships.push(spdx2_relationship_json(
+                &graph_package_id(from),
+                "DEPENDS_ON",
+                &graph_package_id(to),
+            ));
+        }
+    }
+
+    spdx2_document(
+        &graph.name,
+        &graph.namespace(),
+        created,
+        roots,
+        packages,
+        relationships,
+    )
+}
+
+/// Wraps values that may span lines, as tag-value requires.
+fn text(value: &str) -> String {
+    if value.contains('\n') {
+        format!("<text>{value}</text>")
+    } else {
+        value.to_string()
+    }
+}
+
+fn tag(out: &mut String, tag: &str, value: &str) {
+    let _ = writeln!(out, "{tag}: {value}");
+}
+
+fn spdx2_document_tags(out: &mut String, name: &str, namespace: &str, created: &str) {
+    tag(out, "SPDXVersion", "SPDX-2.3");
+    tag(out, "DataLicense", "CC0-1.0");
+    tag(out, "SPDXID", "SPDXRef-DOCUMENT");
+    tag(out, "DocumentName", name);
+    tag(out, "DocumentNamespace", namespace);
+    tag(out, "Creator", &creator());
+    tag(out, "Created", created);
+}
+
+/// The SPDX 2.3 tag-value package section for `subject`.
+fn spdx2_package_tags(out: &mut String, subject: &SpdxSubject, id: &str) {
+    out.push('\n');
+    tag(out, "PackageName", &subject.name);
+    tag(out, "SPDXID", id);
+    tag(out, "PackageVersion", &subject.version);
+    tag(out, "PrimaryPackagePurpose", "LIBRARY");
+    tag(out, "PackageDownloadLocation", NOASSERTION);
+    tag(out, "FilesAnalyzed", "false");
+    if !subject.sha256.is_empty() {
+        tag(
+            out,
+            "PackageChecksum",
+            &format!("SHA256: {}", subject.sha256),
+        );
+    }
+    if let Some(homepage) = &subject.homepage {
+        tag(out, "PackageHomePage", homepage);
+    }
+    if let Some(url) = &subject.source_url {
+        tag(
+            out,
+            "PackageSourceInfo",
+            &text(&format!("Source repository: {url}")),
+        );
+    }
+    if let Some(originator) = subject.originator() {
+        tag(out, "PackageOriginator", &originator);
+    }
+    tag(out, "PackageLicenseConcluded", NOASSERTION);
+    tag(
+        out,
+        "PackageLicenseDeclared",
+        subject
+            .license_expression()
+            .as_deref()
+            .unwrap_or(NOASSERTION),
+    );
+    if let Some(comment) = subject.license_comment() {
+        tag(out, "PackageLicenseComments", &text(&comment));
+    }
+    tag(out, "PackageCopyrightText", NOASSERTION);
+    if let Some(summary) = &subject.summary {
+        tag(out, "PackageSummary", &format!("<text>{summary}</text>"));
+    }
+    if let Some(description) = &subject.description {
+        tag(
+            out,
+            "PackageDescription",
+            &format!("<text>{description}</text>"),
+        );
+    }
+    tag(
+        out,
+        "ExternalRef",
+        &format!("PACKAGE-MANAGER purl {}", subject.purl()),
+    );
+}
+
+fn spdx2_tag_value(subject: &SpdxSubject, created: &str) -> String {
+    let mut out = String::new();
+    spdx2_document_tags(
+        &mut out,
+        &subject.document_name(),
+        &subject.namespace(),
+        created,
+    );
+    tag(
+        &mut out,
+        "Relationship",
+        &format!("SPDXRef-DOCUMENT DESCRIBES {ROOT_ID}"),
+    );
+    spdx2_package_tags(&mut out, subject, ROOT_ID);
+
+    for (index, dep) in subject.dependencies.iter().enumerate() {
+        out.push('\n');
+        tag(&mut out, "PackageName", &dep.name);
+        tag(&mut out, "SPDXID", &dependency_id(index));
+        tag(&mut out, "PackageDownloadLocation", NOASSERTION);
+        tag(&mut out, "FilesAnalyzed", "false");
+        tag(&mut out, "PackageLicenseConcluded", NOASSERTION);
+        tag(&mut out, "PackageLicenseDeclared", NOASSERTION);
+        tag(&mut out, "PackageCopyrightText", NOASSERTION);
+        tag(
+            &mut out,
+            "PackageComment",
+            &format!("<text>Version requirement: {}</text>", dep.requirement),
+        );
+        let (from, kind, to) = spdx2_relationship(index, dep);
+        tag(&mut out, "Relationship", &format!("{from} {kind} {to}"));
+    }
+    out
+}
+
+fn graph_spdx2_tag_value(graph: &SpdxGraph, created: &str) -> String {
+    let mut out = String::new();
+    spdx2_document_tags(&mut out, &graph.name, &graph.namespace(), created);
+    for root in graph.roots() {
+        tag(
+            &mut out,
+            "Relationship",
+            &format!("SPDXRef-DOCUMENT DESCRIBES {}", graph_package_id(root)),
+        );
+    }
+    for (index, subject) in graph.packages.iter().enumerate() {
+        spdx2_package_tags(&mut out, subject, &graph_package_id(index));
+        for &to in &graph.edges[index] {
+            tag(
+                &mut out,
+                "Relationship",
+                &format!(
+                    "{} DEPENDS_ON {}",
+                    graph_package_id(index),
+                    graph_package_id(to)
+                ),
+            );
+        }
+    }
+    out
+}
+
+const CREATION_INFO: &str = "_:creationinfo";
+
+/// `CreationInfo` plus the agent and tool it references.
+fn spdx3_preamble(namespace: &str, created: &str) -> Vec<JsonValue> {
+    let tool_id = format!("{namespace}#SPDXRef-Tool");
+    let agent_id = format!("{namespace}#SPDXRef-Agent");
+    vec![
+        json!({
+            "type": "CreationInfo",
+            "@id": CREATION_INFO,
+            "specVersion": "3.0.1",
+            "created": created,
+            "createdBy": [agent_id],
+            "createdUsing": [tool_id],
+        }),
+        json!({
+            "type": "Organization",
+            "spdxId": agent_id,
+            "creationInfo": CREATION_INFO,
+            "name": "Vein",
+        }),
+        json!({
+            "type": "Tool",
+            "spdxId": tool_id,
+            "creationInfo": CREATION_INFO,
+            "name": format!("vein-{}", env!("CARGO_PKG_VERSION")),
+        }),
+    ]
+}
+
+/// The SPDX 3 package element for `subject` and, when its license is valid
+/// SPDX, the declared-license expression and relationship.
+fn spdx3_package(subject: &SpdxSubject, namespace: &str, local_id: &str) -> Vec<JsonValue> {
+    let package_id = format!("{namespace}#{local_id}");
+    let mut package = json!({
+        "type": "software_Package",
+        "spdxId": package_id,
+        "creationInfo": CREATION_INFO,
+        "name": subject.name,
+        "software_packageVersion": subject.version,
+        "software_packageUrl": subject.purl(),
+        "software_primaryPurpose": "library",
+    });
+    if !subject.sha256.is_empty() {
+        package["verifiedUsing"] = json!([{
+            "type": "Hash",
+            "algorithm": "sha256",
+            "hashValue": subject.sha256,
+        }]);
+    }
+    let optional = [
+        ("summary", subject.summary.clone()),
+        ("description", subject.description.clone()),
+        ("software_homePage", subject.homepage.clone()),
+        ("software_sourceInfo", subject.source_url.clone()),
+        ("comment", subject.license_comment()),
+    ];
+    for (key, value) in optional {
+        if let Some(value) = value {
+            package[key] = JsonValue::String(value);
+        }
+    }
+
+    let mut elements = vec![package];
+    if let Some(expression) = subject.license_expression() {
+        let license_id = format!("{namespace}#{local_id}-License");
+        elements.push(json!({
+            "type": "simplelicensing_LicenseExpression",
+            "spdxId": license_id,
+            "creationInfo": CREATION_INFO,
+            "simplelicensing_licenseExpression": expression,
+        }));
+        elements.push(json!({
+            "type": "Relationship",
+            "spdxId": format!("{namespace}#{local_id}-HasDeclaredLicense"),
+            "creationInfo": CREATION_INFO,
+            "from": package_id,
+            "relationshipType": "hasDeclaredLicense",
+            "to": [license_id],
+        }));
+    }
+    elements
+}
+
+/// Closes `graph` with the `SpdxDocument` listing every element.
+fn spdx3_document(
+    mut graph: Vec<JsonValue>,
+    namespace: &str,
+    name: &str,
+    roots: Vec<String>,
+) -> JsonValue {
+    let elements: Vec<JsonValue> = graph
+        .iter()
+        .filter_map(|element| element.get("spdxId").cloned())
+        .collect();
+    graph.push(json!({
+        "type": "SpdxDocument",
+        "spdxId": format!("{namespace}#SPDXRef-DOCUMENT"),
+        "creationInfo": CREATION_INFO,
+        "name": name,
+        "dataLicense": "https://spdx.org/licenses/CC0-1.0",
+        "profileConformance": ["core", "software", "simpleLicensing"],
+        "rootElement": roots,
+        "element": elements,
+    }));
+
+    json!({
+        "@context": "https://spdx.org/rdf/3.0.1/spdx-context.jsonld",
+        "@graph": graph,
+    })
+}
+
+fn spdx3_json(subject: &SpdxSubject, created: &str) -> JsonValue {
+    let namespace = subject.namespace();
+    let root_id = format!("{namespace}#{ROOT_ID}");
+
+    let mut graph = spdx3_preamble(&namespace, created);
+    graph.extend(spdx3_package(subject, &namespace, ROOT_ID));
+
+    for (index, dep) in subject.dependencies.iter().enumerate() {
+        let dep_id = format!("{namespace}#{}", dependency_id(index));
+        let mut package = json!({
+            "type": "software_Package",
+            "spdxId": dep_id,
+            "creationInfo": CREATION_INFO,
+            "name": dep.name,
+            "comment": format!("Version requirement: {}", dep.requirement),
+        });
+        if dep.optional {
+            package["summary"] = JsonValue::String("optional dependency".to_string());
+        }
+        graph.push(package);
+        graph.push(json!({
+            "type": "LifecycleScopedRelationship",
+            "spdxId": format!("{namespace}#SPDXRef-Relationship-Dependency-{}", index + 1),
+            "creationInfo": CREATION_INFO,
+            "from": root_id,
+            "relationshipType": "dependsOn",
+            "to": [dep_id],
+            "scope": match dep.scope {
+                DependencyScope::Runtime => "runtime",
+                DependencyScope::Development => "development",
+                DependencyScope::Build => "build",
+            },
+        }));
+    }
+
+    spdx3_document(graph, &namespace, &subject.document_name(), vec![root_id])
+}
+
+fn graph_spdx3_json(graph: &SpdxGraph, created: &str) -> JsonValue {
+    let namespace = graph.namespace();
+    let id = |index: usize| format!("{namespace}#{}", graph_package_id(index));
+
+    let mut elements = spdx3_preamble(&namespace, created);
+    for (index, subject) in graph.packages.iter().enumerate() {
+        elements.extend(spdx3_package(subject, &namespace, &graph_package_id(index)));
+    }
+    for (from, targets) in graph.edges.iter().enumerate() {
+        if targets.is_empty() {
+            continue;
+        }
+        elements.push(json!({
+            "type": "Relationship",
+            "spdxId": format!("{}-DependsOn", id(from)),
+            "creationInfo": CREATION_INFO,
+            "from": id(from),
+            "relationshipType": "dependsOn",
+            "to": targets.iter().map(|&to| id(to)).collect::<Vec<_>>(),
+        }));
+    }
+
+    let roots = graph.roots().into_iter().map(id).collect();
+    spdx3_document(elements, &namespace, &graph.name, roots)
+}
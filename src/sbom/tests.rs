@@ -0,0 +1,287 @@
+use super::*;
+use serde_json::json;
+use vein_adapter::{DependencyKind, GemDependency, PackageDependency};
+
+fn gem() -> GemMetadata {
+    GemMetadata {
+        name: "nokogiri".to_string(),
+        version: "1.16.0".to_string(),
+        platform: "x86_64-linux".to_string(),
+        summary: Some("XML and HTML parser".to_string()),
+        description: Some("Nokogiri makes it easy\nto work with XML.".to_string()),
+        licenses: vec!["MIT".to_string()],
+        authors: vec!["Mike Dalessio".to_string()],
+        homepage: Some("https://nokogiri.org".to_string()),
+        source_code_url: Some("https://github.com/sparklemotion/nokogiri".to_string()),
+        dependencies: vec![
+            GemDependency {
+                name: "racc".to_string(),
+                requirement: "~> 1.4".to_string(),
+                kind: DependencyKind::Runtime,
+            },
+            GemDependency {
+                name: "rake".to_string(),
+                requirement: ">= 13".to_string(),
+                kind: DependencyKind::Development,
+            },
+        ],
+        has_embedded_binaries: true,
+        size_bytes: 4096,
+        sha256: "ab".repeat(32),
+        ..GemMetadata::default()
+    }
+}
+
+fn npm_package() -> PackageMetadata {
+    PackageMetadata {
+        ecosystem: Ecosystem::Npm,
+        name: "@acme/hash".to_string(),
+        version: "2.1.0".to_string(),
+        description: None,
+        license: Some("MIT/Apache-2.0".to_string()),
+        authors: Vec::new(),
+        homepage: None,
+        repository: None,
+        dependencies: vec![PackageDependency {
+            name: "fsevents".to_string(),
+            requirement: "^2.3.0".to_string(),
+            kind: "optional".to_string(),
+            optional: true,
+            target: None,
+        }],
+        features: Default::default(),
+        attributes: json!({}),
+        size_bytes: 1,
+        sha256: "cd".repeat(32),
+        sbom: None,
+        sbom_generator: None,
+    }
+}
+
+#[test]
+fn format_names_are_parsed() {
+    assert_eq!("cyclonedx".parse(), Ok(SbomFormat::CycloneDx));
+    assert_eq!("SPDX".parse(), Ok(SbomFormat::SpdxJson));
+    assert_eq!("spdx-tag-value".parse(), Ok(SbomFormat::SpdxTagValue));
+    assert_eq!("spdx3".parse(), Ok(SbomFormat::Spdx3Json));
+    assert_eq!("swid".parse::<SbomFormat>(), Err(()));
+}
+
+#[test]
+fn spdx2_json_describes_gem_and_dependencies() {
+    let body = render_gem(SbomFormat::SpdxJson, &gem())
+        .expect("render")
+        .expect("spdx is always available");
+    let doc: JsonValue = serde_json::from_str(&body).expect("valid json");
+
+    assert_eq!(doc["spdxVersion"], "SPDX-2.3");
+    assert_eq!(doc["name"], "nokogiri-1.16.0-x86_64-linux");
+    let root = &doc["packages"][0];
+    assert_eq!(root["licenseDeclared"], "MIT");
+    assert_eq!(
+        root["externalRefs"][0]["referenceLocator"],
+        "pkg:gem/nokogiri@1.16.0?platform=x86_64-linux"
+    );
+    assert_eq!(root["checksums"][0]["checksumValue"], "ab".repeat(32));
+
+    let relationships: Vec<_> = doc["relationships"]
+        .as_array()
+        .expect("relationships")
+        .iter()
+        .map(|rel| {
+            (
+                rel["spdxElementId"].as_str().unwrap(),
+                rel["relationshipType"].as_str().unwrap(),
+                rel["relatedSpdxElement"].as_str().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        relationships,
+        [
+            ("SPDXRef-DOCUMENT", "DESCRIBES", "SPDXRef-Package"),
+            ("SPDXRef-Package", "DEPENDS_ON", "SPDXRef-Dependency-1"),
+            (
+                "SPDXRef-Dependency-2",
+                "DEV_DEPENDENCY_OF",
+                "SPDXRef-Package"
+            ),
+        ]
+    );
+}
+
+#[test]
+fn spdx_tag_value_wraps_multiline_text() {
+    let body = render_gem(SbomFormat::SpdxTagValue, &gem())
+        .expect("render")
+        .expect("spdx is always available");
+
+    assert!(body.starts_with("SPDXVersion: SPDX-2.3\n"));
+    assert!(body.contains("PackageLicenseDeclared: MIT\n"));
+    assert!(
+        body.contains(
+            "PackageDescription: <text>Nokogiri makes it easy\nto work with XML.</text>\n"
+        )
+    );
+    assert!(body.contains("Relationship: SPDXRef-Package DEPENDS_ON SPDXRef-Dependency-1\n"));
+}
+
+#[test]
+fn spdx3_graph_holds_package_license_and_scoped_dependencies() {
+    let body = render_package(SbomFormat::Spdx3Json, &npm_package())
+        .expect("render")
+        .expect("spdx is always available");
+    let doc: JsonValue = serde_json::from_str(&body).expect("valid json");
+    let graph = doc["@graph"].as_array().expect("graph");
+
+    let root = graph
+        .iter()
+        .find(|el| el["software_packageUrl"].is_string())
+        .expect("root package");
+    assert_eq!(root["type"], "software_Package");
+    assert_eq!(root["software_packageUrl"], "pkg:npm/%40acme/hash@2.1.0");
+
+    let license = graph
+        .iter()
+        .find(|el| el["type"] == "simplelicensing_LicenseExpression")
+        .expect("license expression");
+    assert_eq!(
+        license["simplelicensing_licenseExpression"],
+        "MIT OR Apache-2.0"
+    );
+
+    let dependency = graph
+        .iter()
+        .find(|el| el["type"] == "LifecycleScopedRelationship")
+        .expect("dependency relationship");
+    assert_eq!(dependency["scope"], "runtime");
+
+    let document = graph.last().expect("document");
+    assert_eq!(document["type"], "SpdxDocument");
+    assert_eq!(document["rootElement"][0], root["spdxId"]);
+}
+
+#[test]
+fn cyclonedx_needs_a_stored_document() {
+    let mut meta = npm_package();
+    assert_eq!(
+        render_package(SbomFormat::CycloneDx, &meta).expect("render"),
+        None
+    );
+
+    meta.sbom = crate::package_metadata::generate_cyclonedx_sbom(&meta).expect("generate");
+    let sbom = meta.sbom.as_ref().expect("sbom");
+    assert!(is_current_cyclonedx(sbom));
+    assert!(
+        render_package(SbomFormat::CycloneDx, &meta)
+            .expect("render")
+            .is_some()
+    );
+}
+
+#[test]
+fn outdated_cyclonedx_is_not_reused() {
+    let stale = json!({ "bomFormat": "CycloneDX", "specVersion": "1.4" });
+    let sbom = crate::gem_metadata::generate_cyclonedx_sbom(&gem(), Some(stale))
+        .expect("generate")
+        .expect("sbom");
+    assert_eq!(sbom["specVersion"], CYCLONEDX_SPEC_VERSION);
+    assert!(sbom["metadata"]["component"].is_object());
+}
+
+#[cfg(feature = "sqlite")]
+#[tokio::test]
+async fn backfill_regenerates_missing_and_outdated_sboms() {
+    use std::sync::Arc;
+
+    use backfill::{BackfillOptions, backfill_sboms};
+    use flate2::{Compression, write::GzEncoder};
+    use vein_adapter::{AssetKey, AssetKind, FilesystemStorage};
+
+    let dir = tempfile::tempdir().unwrap();
+    let storage = FilesystemStorage::new(dir.path().to_path_buf());
+    let index = Arc::new(CacheBackend::connect_memory().await.unwrap());
+
+    let manifest = r#"{ "name": "left-pad", "version": "1.3.0", "license": "WTFPL" }"#;
+    let path = "npm/left-pad/left-pad-1.3.0.tgz";
+    let target = storage.resolve(path);
+    std::fs::create_dir_all(target.parent().unwrap()).unwrap();
+    let mut archive = tar::Builder::new(GzEncoder::new(
+        std::fs::File::create(&target).unwrap(),
+        Compression::default(),
+    ));
+    let mut header = tar::Header::new_gnu();
+    header.set_size(manifest.len() as u64);
+    header.set_mode(0o644);
+    archive
+        .append_data(&mut header, "package/package.json", manifest.as_bytes())
+        .unwrap();
+    archive.into_inner().unwrap().finish().unwrap();
+
+    for (name, version, path) in [
+        ("left-pad", "1.3.0", path),
+        ("gone", "1.0.0", "npm/gone/gone-1.0.0.tgz"),
+    ] {
+        let key = AssetKey {
+            kind: AssetKind::NpmPackage,
+            name,
+            version,
+            platform: None,
+        };
+        index
+            .insert_or_replace(&key, path, &"ef".repeat(32), 1)
+            .await
+            .unwrap();
+    }
+    // Metadata stored before generators were recorded.
+    let mut stale = npm_package();
+    stale.name = "left-pad".to_string();
+    stale.version = "1.3.0".to_string();
+    stale.sbom = Some(json!({ "bomFormat": "CycloneDX", "specVersion": "1.5" }));
+    index.upsert_package_metadata(&stale).await.unwrap();
+
+    let options = BackfillOptions {
+        concurrency: 2,
+        ..BackfillOptions::default()
+    };
+    let mut seen = Vec::new();
+    let summary = backfill_sboms(
+        index.clone(),
+        storage.clone(),
+        &options,
+        |done, total, _| {
+            seen.push((done, total));
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        (summary.total, summary.regenerated, summary.missing),
+        (2, 1, 1)
+    );
+    assert_eq!(seen, [(1, 2), (2, 2)]);
+
+    let stored = index
+        .package_metadata(Ecosystem::Npm, "left-pad", "1.3.0")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(stored.license.as_deref(), Some("WTFPL"));
+    assert_eq!(stored.sbom_generator.as_deref(), Some(SBOM_GENERATOR));
+    assert!(is_current_cyclonedx(stored.sbom.as_ref().unwrap()));
+
+    let summary = backfill_sboms(index.clone(), storage.clone(), &options, |_, _, _| {})
+        .await
+        .unwrap();
+    assert_eq!(summary.up_to_date, 1);
+
+    let forced = BackfillOptions {
+        force: true,
+        ecosystems: vec![Ecosystem::Npm],
+        ..options
+    };
+    let summary = backfill_sboms(index.clone(), storage, &forced, |_, _, _| {})
+        .await
+        .unwrap();
+    assert_eq!(summary.regenerated, 1);
+}
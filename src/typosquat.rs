@@ -0,0 +1,428 @@
+//! Typosquatting and dependency confusion detection.
+//!
+//! A package name requested for the first time is compared against the
+//! RubyGems catalog and the most downloaded cached packages of its ecosystem.
+//! Names one typo, a swapped separator or a look-alike character away from an
+//! established package are suspicious, as are names carrying a configured
+//! internal prefix, which should never be resolved from a public registry.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use parking_lot::RwLock;
+use vein_adapter::{AssetKind, CacheBackend, CacheBackendTrait, Ecosystem};
+
+use crate::config::TyposquatConfig;
+
+/// Names shorter than this are too dense to compare by edit distance.
+const MIN_EDIT_DISTANCE_LEN: usize = 4;
+/// Names up to this length may differ by one edit, longer ones by two.
+const SHORT_NAME_LEN: usize = 8;
+/// Verdicts kept before the cache starts over.
+const MAX_VERDICTS: usize = 50_000;
+
+/// Characters and sequences commonly swapped for look-alikes, with the
+/// character they imitate.
+const CONFUSABLES: &[(&str, char)] = &[
+    ("rn", 'm'),
+    ("vv", 'w'),
+    ("0", 'o'),
+    ("1", 'l'),
+    ("3", 'e'),
+    ("4", 'a'),
+    ("5", 's'),
+    ("7", 't'),
+    ("а", 'a'),
+    ("с", 'c'),
+    ("е", 'e'),
+    ("һ", 'h'),
+    ("і", 'i'),
+    ("ј", 'j'),
+    ("о", 'o'),
+    ("р", 'p'),
+    ("ѕ", 's'),
+    ("х", 'x'),
+    ("у", 'y'),
+];
+
+/// How a suspicious name imitates an established one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Technique {
+    /// Carries a configured internal prefix but was requested upstream.
+    InternalPrefix,
+    /// Look-alike characters, e.g. `rai1s` or `rnail`.
+    Homoglyph,
+    /// Same name with different `-`, `_` or `.` separators.
+    Separator,
+    /// One or two typos away from a popular package.
+    EditDistance,
+}
+
+impl Technique {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Technique::InternalPrefix => "internal_prefix",
+            Technique::Homoglyph => "homoglyph",
+            Technique::Separator => "separator",
+            Technique::EditDistance => "edit_distance",
+        }
+    }
+}
+
+/// Why a name is suspicious.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NameMatch {
+    pub technique: Technique,
+    /// The imitated package, or the matched internal prefix.
+    pub similar_to: String,
+}
+
+impl NameMatch {
+    /// Human-readable reason used in logs, responses and notifications.
+    pub fn describe(&self) -> String {
+        match self.technique {
+            Technique::InternalPrefix => {
+                format!("uses internal prefix {}", self.similar_to)
+            }
+            technique => format!("looks like {} ({})", self.similar_to, technique.as_str()),
+        }
+    }
+}
+
+/// Established names of one ecosystem.
+#[derive(Debug, Default)]
+pub struct KnownNames {
+    /// Most downloaded first.
+    popular: Vec<String>,
+    catalog: HashSet<String>,
+    /// Separator-free key to the name it came from and whether it is popular.
+    separator_keys: HashMap<String, (String, bool)>,
+    /// Homoglyph skeleton to the name it came from and whether it is popular.
+    skeletons: HashMap<String, (String, bool)>,
+}
+
+impl KnownNames {
+    /// Indexes `popular` (most downloaded first) and `catalog` names.
+    pub fn new(popular: Vec<String>, catalog: Vec<String>) -> Self {
+        let popular: Vec<String> = popular.iter().map(|name| name.to_lowercase()).collect();
+        let catalog: HashSet<String> = catalog.iter().map(|name| name.to_lowercase()).collect();
+
+        let mut separator_keys = HashMap::new();
+        let mut skeletons = HashMap::new();
+        // Popular names go first so they win shared keys
+        let sources = popular
+            .iter()
+            .map(|name| (name, true))
+            .chain(catalog.iter().map(|name| (name, false)));
+        for (name, is_popular) in sources {
+            separator_keys
+                .entry(separator_key(name))
+                .or_insert_with(|| (name.clone(), is_popular));
+            skeletons
+                .entry(skeleton(name).0)
+                .or_insert_with(|| (name.clone(), is_popular));
+        }
+
+        Self {
+            popular,
+            catalog,
+            separator_keys,
+            skeletons,
+        }
+    }
+
+    /// Compares a new `name` against the established names.
+    ///
+    /// Catalog names only count when `name` is not in the catalog itself:
+    /// two long-standing gems that happen to be similar are not an attack.
+    pub fn assess(&self, name: &str) -> Option<NameMatch> {
+        let name = name.to_lowercase();
+        if self.popular.contains(&name) {
+            return None;
+        }
+        let catalog_targets = !self.catalog.contains(&name);
+        let eligible = |target: &(String, bool)| target.0 != name && (target.1 || catalog_targets);
+
+        if let Some((target, _)) = self
+            .separator_keys
+            .get(&separator_key(&name))
+            .filter(|target| eligible(target))
+        {
+            return Some(NameMatch {
+                technique: Technique::Separator,
+                similar_to: target.clone(),
+            });
+        }
+
+        let (name_skeleton, confusables) = skeleton(&name);
+        if confusables > 0
+            && let Some((target, _)) = self
+                .skeletons
+                .get(&name_skeleton)
+                .filter(|target| eligible(target))
+            && skeleton(target).1 < confusables
+        {
+            return Some(NameMatch {
+                technique: Technique::Homoglyph,
+                similar_to: target.clone(),
+            });
+        }
+
+        let len = name.chars().count();
+        if len < MIN_EDIT_DISTANCE_LEN {
+            return None;
+        }
+        let threshold = if len <= SHORT_NAME_LEN { 1 } else { 2 };
+        self.popular
+            .iter()
+            .filter(|target| target.chars().count() >= MIN_EDIT_DISTANCE_LEN)
+            .filter(|target| target.chars().count().abs_diff(len) <= threshold)
+            .find(|target| edit_distance(&name, target) <= threshold)
+            .map(|target| NameMatch {
+                technique: Technique::EditDistance,
+                similar_to: target.clone(),
+            })
+    }
+}
+
+/// Checks requested names, caching the known-name snapshots and verdicts.
+#[derive(Default)]
+pub struct TyposquatGuard {
+    known: RwLock<HashMap<Ecosystem, (Instant, Arc<KnownNames>)>>,
+    verdicts: RwLock<HashMap<(Ecosystem, String), Option<NameMatch>>>,
+}
+
+impl TyposquatGuard {
+    /// Returns why `name` is suspicious, or `None` for allowed, already cached
+    /// and unremarkable names.
+    pub async fn check(
+        &self,
+        config: &TyposquatConfig,
+        index: &CacheBackend,
+        kind: AssetKind,
+        name: &str,
+    ) -> Result<Option<NameMatch>> {
+        if !config.enabled || config.is_allowed(name) {
+            return Ok(None);
+        }
+        let ecosystem = kind.ecosystem();
+        let key = (ecosystem, name.to_string());
+        if let Some(verdict) = self.verdicts.read().get(&key) {
+            return Ok(verdict.clone());
+        }
+
+        let kind = package_kind(kind);
+        let seen = !index
+            .cached_package_versions(kind, name)
+            .await
+            .context("checking whether package was cached before")?
+            .is_empty();
+        let verdict = if seen {
+            None
+        } else if let Some(prefix) = config.internal_prefix(name) {
+            Some(NameMatch {
+                technique: Technique::InternalPrefix,
+                similar_to: prefix.to_string(),
+            })
+        } else {
+            self.known_names(config, index, kind).await?.assess(name)
+        };
+
+        let mut verdicts = self.verdicts.write();
+        if verdicts.len() >= MAX_VERDICTS {
+            verdicts.clear();
+        }
+        verdicts.insert(key, verdict.clone());
+        Ok(verdict)
+    }
+
+    async fn known_names(
+        &self,
+        config: &TyposquatConfig,
+        index: &CacheBackend,
+        kind: AssetKind,
+    ) -> Result<Arc<KnownNames>> {
+        let ecosystem = kind.ecosystem();
+        let max_age = Duration::from_secs(config.refresh_minutes.saturating_mul(60));
+        if let Some((loaded_at, known)) = self.known.read().get(&ecosystem)
+            && loaded_at.elapsed() < max_age
+        {
+            return Ok(known.clone());
+        }
+
+        let popular = index
+            .popular_packages(kind, config.popular_limit)
+            .await
+            .context("loading popular packages")?;
+        let catalog = if ecosystem == Ecosystem::RubyGems {
+            let total = index.catalog_total().await.context("counting catalog")?;
+            index
+                .catalog_page(0, i64::try_from(total).unwrap_or(i64::MAX))
+                .await
+                .context("loading catalog names")?
+        } else {
+            Vec::new()
+        };
+        let known = Arc::new(KnownNames::new(popular, catalog));
+
+        self.known
+            .write()
+            .insert(ecosystem, (Instant::now(), known.clone()));
+        // Names cached since the last snapshot are no longer new
+        self.verdicts
+            .write()
+            .retain(|(eco, _), _| *eco != ecosystem);
+        Ok(known)
+    }
+}
+
+/// Gemspec requests are checked against cached `.gem` files.
+fn package_kind(kind: AssetKind) -> AssetKind {
+    match kind {
+        AssetKind::Spec => AssetKind::Gem,
+        kind => kind,
+    }
+}
+
+fn separator_key(name: &str) -> String {
+    name.chars()
+        .filter(|ch| !matches!(ch, '-' | '_' | '.'))
+        .collect()
+}
+
+/// Replaces look-alikes with the characters they imitate and counts them.
+fn skeleton(name: &str) -> (String, usize) {
+    let mut skeleton = String::with_capacity(name.len());
+    let mut replaced = 0;
+    let mut rest = name;
+    'outer: while let Some(ch) = rest.chars().next() {
+        for (confusable, plain) in CONFUSABLES {
+            if let Some(after) = rest.strip_prefix(confusable) {
+                skeleton.push(*plain);
+                replaced += 1;
+                rest = after;
+                continue 'outer;
+            }
+        }
+        skeleton.push(ch);
+        rest = &rest[ch.len_utf8()..];
+    }
+    (skeleton, replaced)
+}
+
+/// Optimal string alignment distance: insertions, deletions, substitutions
+/// and adjacent transpositions.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut rows = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in rows[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut best = (rows[i - 1][j] + 1)
+                .min(rows[i][j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                best = best.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = best;
+        }
+    }
+    rows[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn known() -> KnownNames {
+        KnownNames::new(
+            vec![
+                "rails".into(),
+                "nokogiri".into(),
+                "activesupport".into(),
+                "mail".into(),
+            ],
+            vec!["rack-test".into(), "rails".into(), "rest-client".into()],
+        )
+    }
+
+    fn technique(known: &KnownNames, name: &str) -> Option<(Technique, String)> {
+        known
+            .assess(name)
+            .map(|found| (found.technique, found.similar_to))
+    }
+
+    #[test]
+    fn flags_typos_of_popular_names() {
+        let known = known();
+        assert_eq!(
+            technique(&known, "nokogiru"),
+            Some((Technique::EditDistance, "nokogiri".into()))
+        );
+        assert_eq!(
+            technique(&known, "noko-giri"),
+            Some((Technique::Separator, "nokogiri".into()))
+        );
+        assert_eq!(
+            technique(&known, "activesupprot"),
+            Some((Technique::EditDistance, "activesupport".into()))
+        );
+        assert_eq!(
+            technique(&known, "rai1s"),
+            Some((Technique::Homoglyph, "rails".into()))
+        );
+        assert_eq!(
+            technique(&known, "rnail"),
+            Some((Technique::Homoglyph, "mail".into()))
+        );
+        assert_eq!(
+            technique(&known, "rаils"),
+            Some((Technique::Homoglyph, "rails".into()))
+        );
+        assert_eq!(technique(&known, "Rails"), None);
+        assert_eq!(technique(&known, "sidekiq"), None);
+    }
+
+    #[test]
+    fn catalog_names_only_guard_new_names() {
+        let known = known();
+        // Not in the catalog, so it imitates `rack-test`
+        assert_eq!(
+            technique(&known, "rack_test"),
+            Some((Technique::Separator, "rack-test".into()))
+        );
+        // Catalog entries are not compared against each other
+        let both = KnownNames::new(Vec::new(), vec!["rest-client".into(), "rest_client".into()]);
+        assert_eq!(technique(&both, "rest_client"), None);
+    }
+
+    #[test]
+    fn measures_transpositions_as_one_edit() {
+        assert_eq!(edit_distance("rails", "rails"), 0);
+        assert_eq!(edit_distance("rials", "rails"), 1);
+        assert_eq!(edit_distance("rail", "rails"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn matches_internal_prefixes_case_insensitively() {
+        let config = TyposquatConfig {
+            internal_prefixes: vec!["acme-".into(), "@acme/".into()],
+            allow: vec!["acme-public".into()],
+            ..TyposquatConfig::default()
+        };
+        assert_eq!(config.internal_prefix("ACME-billing"), Some("acme-"));
+        assert_eq!(config.internal_prefix("@acme/ui"), Some("@acme/"));
+        assert_eq!(config.internal_prefix("acmecorp"), None);
+        assert!(config.is_allowed("Acme-Public"));
+    }
+}
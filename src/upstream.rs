@@ -1,9 +1,9 @@
+pub mod health;
+
 use anyhow::{Result, anyhow};
-use breaker_machines::CircuitBreaker;
 use chrono_machines::{
     BackoffPolicy, BackoffStrategy, ConstantBackoff, ExponentialBackoff, FibonacciBackoff,
 };
-use parking_lot::Mutex;
 use rama::{
     Service,
     http::{
@@ -15,19 +15,21 @@ use rama::{
     },
     layer::Layer,
     net::uri::Uri,
-    telemetry::tracing,
 };
 use rand::SeedableRng;
 use rand::rngs::SmallRng;
 use std::sync::Arc;
-use tracing::{info, warn};
+use std::time::Instant;
 
 use crate::config::{BackoffStrategy as ConfigBackoffStrategy, UpstreamConfig};
 
+pub use health::{MirrorHealth, MirrorSnapshot};
+
 pub const UA: &str = concat!("vein/", env!("CARGO_PKG_VERSION"));
 
 /// Performs a one-shot upstream `GET` (no retry/circuit-breaker), setting the
-/// vein user-agent and forwarding `extra_headers`. When `accept` is provided it
+/// vein user-agent and forwarding `extra_headers`. The outcome is recorded
+/// against the origin's passive health for readiness probes. When `accept` is provided it
 /// is applied only if `extra_headers` did not already set an `Accept` header.
 pub async fn simple_get(
     url: &str,
@@ -53,40 +55,40 @@ pub async fn simple_get(
     let request = builder
         .body(Body::empty())
         .map_err(|e| anyhow!("building upstream request: {e}"))?;
-    client
-        .serve(request)
-        .await
-        .map_err(|e| anyhow!("upstream request failed: {e}"))
+    let health = health::observed(&health::origin_of(url));
+    let started = Instant::now();
+    match client.serve(request).await {
+        Ok(response) if response.status().is_server_error() => {
+            health.record_failure(started.elapsed(), response.status().to_string());
+            Ok(response)
+        }
+        Ok(response) => {
+            health.record_success(started.elapsed());
+            Ok(response)
+        }
+        Err(e) => {
+            health.record_failure(started.elapsed(), e.to_string());
+            Err(anyhow!("upstream request failed: {e}"))
+        }
+    }
 }
 
-/// Rama-based upstream HTTP client with retry, circuit breaker, and tracing.
+/// Rama-based upstream HTTP client for a single mirror, with retry, circuit
+/// breaker, and tracing.
 #[derive(Clone)]
 pub struct UpstreamClient {
     pub backoff: BackoffPolicy,
-    pub breaker: Arc<Mutex<CircuitBreaker>>,
+    pub health: Arc<MirrorHealth>,
 }
 
 impl UpstreamClient {
+    /// Client for the primary upstream URL.
     pub fn new(config: &UpstreamConfig) -> Result<Self> {
-        // Configure circuit breaker for upstream resilience
-        // Open circuit after 5 server errors in 60s window, reset after 30s
-        let breaker = CircuitBreaker::builder("rubygems_upstream")
-            .failure_threshold(5)
-            .failure_window_secs(60.0)
-            .half_open_timeout_secs(30.0)
-            .success_threshold(2)
-            .jitter_factor(0.1) // 10% jitter on reset timeout
-            .on_open(|name| {
-                warn!(circuit = %name, "Circuit breaker opened - upstream is failing");
-            })
-            .on_close(|name| {
-                info!(circuit = %name, "Circuit breaker closed - upstream recovered");
-            })
-            .on_half_open(|name| {
-                info!(circuit = %name, "Circuit breaker half-open - testing upstream");
-            })
-            .build();
+        Self::for_mirror(config, &config.url)
+    }
 
+    /// Client whose breaker and health statistics belong to `url`.
+    pub fn for_mirror(config: &UpstreamConfig, url: &Uri) -> Result<Self> {
         let retry = &config.reliability.retry;
         let max_delay_ms = retry.max_backoff_secs * 1000;
         let jitter = retry.jitter_factor;
@@ -114,7 +116,7 @@ impl UpstreamClient {
 
         Ok(Self {
             backoff,
-            breaker: Arc::new(Mutex::new(breaker)),
+            health: Arc::new(MirrorHealth::new(url.to_string())),
         })
     }
 
@@ -127,7 +129,7 @@ impl UpstreamClient {
 
     pub async fn get_with_headers(&self, url: Uri, headers: &HeaderMap) -> Result<Response<Body>> {
         // Check if circuit is open before attempting request
-        if self.breaker.lock().is_open() {
+        if !self.health.allows_request() {
             return Err(anyhow!(
                 "Circuit breaker is open - upstream is currently unavailable"
             ));
@@ -168,17 +170,16 @@ impl UpstreamClient {
                 }
                 Ok(response) => {
                     let status = response.status();
-                    let duration = start_time.elapsed().as_secs_f64();
+                    let duration = start_time.elapsed();
 
                     // Record success or failure based on status code
                     if status.is_server_error() {
                         // This is the final attempt and it failed with 5xx
-                        let mut breaker = self.breaker.lock();
-                        breaker.record_failure(duration);
-                        breaker.check_and_trip();
+                        self.health
+                            .record_failure(duration, format!("returned {status}"));
                     } else {
                         // Success or client error (4xx) - don't trip on client errors
-                        self.breaker.lock().record_success(duration);
+                        self.health.record_success(duration);
                     }
 
                     let resp_headers = response.headers().clone();
@@ -199,13 +200,11 @@ impl UpstreamClient {
                         .map_err(|e| anyhow!("rebuilding upstream response: {e}"));
                 }
                 Err(err) => {
-                    let duration = start_time.elapsed().as_secs_f64();
+                    let duration = start_time.elapsed();
 
                     if attempt >= max_attempts {
                         // All retries exhausted - record failure and try to trip
-                        let mut breaker = self.breaker.lock();
-                        breaker.record_failure(duration);
-                        breaker.check_and_trip();
+                        self.health.record_failure(duration, err.to_string());
                         return Err(anyhow!("upstream request failed: {err}"));
                     }
 
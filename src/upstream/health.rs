@@ -0,0 +1,246 @@
+//! Per-mirror health: a circuit breaker plus latency and error-rate EWMAs
+//! used to rank upstream targets.
+
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock};
+use std::time::{Duration, Instant};
+
+use breaker_machines::CircuitBreaker;
+use parking_lot::Mutex;
+use rama::net::uri::Uri;
+use rama::telemetry::tracing::{info, warn};
+use serde::{Deserialize, Serialize};
+
+/// Weight of the newest sample in the latency and error-rate averages.
+const EWMA_ALPHA: f64 = 0.3;
+/// How long an open breaker rejects requests before letting one through.
+const OPEN_COOLDOWN: Duration = Duration::from_secs(30);
+/// Score penalty per unit of error rate, in milliseconds.
+const ERROR_PENALTY_MS: f64 = 5_000.0;
+
+/// Passive health of origins reached through one-shot requests (crates.io,
+/// npm), keyed by `scheme://authority`.
+static OBSERVED: LazyLock<Mutex<HashMap<String, Arc<MirrorHealth>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Shared health for `origin`, created on first use.
+pub fn observed(origin: &str) -> Arc<MirrorHealth> {
+    OBSERVED
+        .lock()
+        .entry(origin.to_string())
+        .or_insert_with(|| Arc::new(MirrorHealth::new(origin)))
+        .clone()
+}
+
+/// `scheme://authority` of `url`, or the url itself when it does not parse.
+pub fn origin_of(url: &str) -> String {
+    match url.parse::<Uri>() {
+        Ok(uri) => match (uri.scheme_str(), uri.authority()) {
+            (Some(scheme), Some(authority)) => format!("{scheme}://{authority}"),
+            _ => url.to_string(),
+        },
+        Err(_) => url.to_string(),
+    }
+}
+
+/// Snapshot of `origin`, if any request to it has been recorded.
+pub fn observed_snapshot(origin: &str) -> Option<MirrorSnapshot> {
+    OBSERVED.lock().get(origin).map(|health| health.snapshot())
+}
+
+/// Health of a single upstream mirror, shared by every clone of its target.
+pub struct MirrorHealth {
+    url: String,
+    breaker: Mutex<CircuitBreaker>,
+    stats: Mutex<MirrorStats>,
+}
+
+#[derive(Default)]
+struct MirrorStats {
+    latency_ms: Option<f64>,
+    error_rate: f64,
+    requests: u64,
+    failures: u64,
+    hedged: u64,
+    opened_at: Option<Instant>,
+    last_error: Option<String>,
+}
+
+/// Point-in-time view of a mirror, served by `/readyz`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MirrorSnapshot {
+    pub url: String,
+    /// Circuit breaker state (`closed`, `open`, `half_open`).
+    pub state: String,
+    pub healthy: bool,
+    pub latency_ms: Option<f64>,
+    pub error_rate: f64,
+    pub requests: u64,
+    pub failures: u64,
+    /// Requests raced against a slower mirror.
+    pub hedged: u64,
+    pub last_error: Option<String>,
+}
+
+impl MirrorHealth {
+    pub fn new(url: impl Into<String>) -> Self {
+        let url = url.into();
+        // Open after 5 server errors in 60s, reset after 30s
+        let breaker = CircuitBreaker::builder(url.clone())
+            .failure_threshold(5)
+            .failure_window_secs(60.0)
+            .half_open_timeout_secs(OPEN_COOLDOWN.as_secs_f64())
+            .success_threshold(2)
+            .jitter_factor(0.1)
+            .on_open(|name| {
+                warn!(circuit = %name, "Circuit breaker opened - upstream is failing");
+            })
+            .on_close(|name| {
+                info!(circuit = %name, "Circuit breaker closed - upstream recovered");
+            })
+            .build();
+
+        Self {
+            url,
+            breaker: Mutex::new(breaker),
+            stats: Mutex::new(MirrorStats::default()),
+        }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Whether a request may be sent. An open breaker lets a probe through
+    /// once its cooldown has passed.
+    pub fn allows_request(&self) -> bool {
+        let mut breaker = self.breaker.lock();
+        if !breaker.is_open() {
+            return true;
+        }
+        let mut stats = self.stats.lock();
+        match stats.opened_at {
+            Some(opened_at) if opened_at.elapsed() < OPEN_COOLDOWN => false,
+            _ => {
+                info!(circuit = %self.url, "Circuit breaker cooldown elapsed - probing upstream");
+                breaker.reset();
+                stats.opened_at = None;
+                true
+            }
+        }
+    }
+
+    pub fn record_success(&self, elapsed: Duration) {
+        self.breaker.lock().record_success(elapsed.as_secs_f64());
+        let mut stats = self.stats.lock();
+        stats.record(elapsed, false);
+    }
+
+    pub fn record_failure(&self, elapsed: Duration, error: impl Into<String>) {
+        let tripped = {
+            let mut breaker = self.breaker.lock();
+            breaker.record_failure(elapsed.as_secs_f64());
+            breaker.check_and_trip()
+        };
+        let mut stats = self.stats.lock();
+        stats.record(elapsed, true);
+        stats.last_error = Some(error.into());
+        if tripped {
+            stats.opened_at = Some(Instant::now());
+        }
+    }
+
+    pub fn record_hedge(&self) {
+        self.stats.lock().hedged += 1;
+    }
+
+    /// Lower is better. Open mirrors rank last; unmeasured mirrors rank first
+    /// so they get sampled.
+    pub fn score(&self) -> f64 {
+        if self.breaker.lock().is_open() {
+            return f64::INFINITY;
+        }
+        let stats = self.stats.lock();
+        stats.latency_ms.unwrap_or(0.0) + stats.error_rate * ERROR_PENALTY_MS
+    }
+
+    pub fn snapshot(&self) -> MirrorSnapshot {
+        let (state, healthy) = {
+            let breaker = self.breaker.lock();
+            (breaker.state_name().to_string(), !breaker.is_open())
+        };
+        let stats = self.stats.lock();
+        MirrorSnapshot {
+            url: self.url.clone(),
+            state,
+            healthy,
+            latency_ms: stats.latency_ms.map(|ms| (ms * 10.0).round() / 10.0),
+            error_rate: (stats.error_rate * 1000.0).round() / 1000.0,
+            requests: stats.requests,
+            failures: stats.failures,
+            hedged: stats.hedged,
+            last_error: stats.last_error.clone(),
+        }
+    }
+}
+
+impl MirrorStats {
+    fn record(&mut self, elapsed: Duration, failed: bool) {
+        let sample = elapsed.as_secs_f64() * 1000.0;
+        self.latency_ms = Some(match self.latency_ms {
+            Some(current) => current + EWMA_ALPHA * (sample - current),
+            None => sample,
+        });
+        let outcome = if failed { 1.0 } else { 0.0 };
+        self.error_rate += EWMA_ALPHA * (outcome - self.error_rate);
+        self.requests += 1;
+        if failed {
+            self.failures += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failures_raise_score_and_trip_breaker() {
+        let fast = MirrorHealth::new("https://fast.example/");
+        let flaky = MirrorHealth::new("https://flaky.example/");
+        fast.record_success(Duration::from_millis(40));
+        flaky.record_success(Duration::from_millis(20));
+        flaky.record_failure(Duration::from_millis(20), "503");
+        assert!(fast.score() < flaky.score());
+
+        for _ in 0..5 {
+            flaky.record_failure(Duration::from_millis(20), "503");
+        }
+        let snapshot = flaky.snapshot();
+        assert!(!snapshot.healthy);
+        assert_eq!(snapshot.failures, 6);
+        assert_eq!(flaky.score(), f64::INFINITY);
+        assert!(!flaky.allows_request());
+    }
+
+    #[test]
+    fn observed_origins_are_shared() {
+        let origin = origin_of("https://observed.example/some/path?q=1");
+        assert_eq!(origin, "https://observed.example");
+        let origin = origin.as_str();
+        assert!(observed_snapshot(origin).is_none());
+        observed(origin).record_failure(Duration::from_millis(10), "timeout");
+        let snapshot = observed_snapshot(origin).unwrap();
+        assert_eq!(snapshot.failures, 1);
+        assert_eq!(snapshot.last_error.as_deref(), Some("timeout"));
+    }
+
+    #[test]
+    fn latency_is_smoothed() {
+        let health = MirrorHealth::new("https://mirror.example/");
+        health.record_success(Duration::from_millis(100));
+        health.record_success(Duration::from_millis(200));
+        let latency = health.snapshot().latency_ms.unwrap();
+        assert!((latency - 130.0).abs() < 0.5, "{latency}");
+    }
+}
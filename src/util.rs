@@ -1,4 +1,4 @@
-//! Small shared formatting/sanitization helpers used across the crate.
+//! Small shared formatting/sanitization/matching helpers used across the crate.
 
 /// Formats a byte count as a human-readable size (e.g. `1.50 MB`).
 pub fn format_bytes(bytes: u64) -> String {
@@ -49,3 +49,55 @@ pub fn sanitize_filename(input: &str) -> String {
 pub fn sanitize_npm_segment(input: &str) -> String {
     sanitize_chars(input, &['-', '_', '.', '@', '+'])
 }
+
+/// Simple glob matching for patterns like "*-internal" or "rails-*".
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    if pattern == "*" {
+        return true;
+    }
+
+    if let Some(suffix) = pattern.strip_prefix('*') {
+        return name.ends_with(suffix);
+    }
+
+    if let Some(prefix) = pattern.strip_suffix('*') {
+        return name.starts_with(prefix);
+    }
+
+    // If pattern contains * in the middle, do a simple split match
+    if let Some(pos) = pattern.find('*') {
+        let prefix = &pattern[..pos];
+        let suffix = &pattern[pos + 1..];
+        return name.starts_with(prefix) && name.ends_with(suffix);
+    }
+
+    // No wildcards, exact match
+    pattern == name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_match() {
+        // Suffix match
+        assert!(glob_match("*-internal", "my-gem-internal"));
+        assert!(!glob_match("*-internal", "internal-gem"));
+
+        // Prefix match
+        assert!(glob_match("rails-*", "rails-api"));
+        assert!(!glob_match("rails-*", "my-rails"));
+
+        // Middle wildcard
+        assert!(glob_match("my-*-gem", "my-awesome-gem"));
+        assert!(!glob_match("my-*-gem", "your-awesome-gem"));
+
+        // Exact match
+        assert!(glob_match("rails", "rails"));
+        assert!(!glob_match("rails", "rack"));
+
+        // Wildcard all
+        assert!(glob_match("*", "anything"));
+    }
+}
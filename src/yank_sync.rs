@@ -0,0 +1,533 @@
+//! Upstream yank synchronisation.
+//!
+//! Periodically diffs cached versions against upstream signals and records
+//! the ones that were pulled:
+//! - RubyGems: the compact index `/versions` file (`-version` entries)
+//! - crates.io: `yanked` flags in the sparse index
+//! - npm: unpublished versions (and optionally deprecations)
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Utc};
+use rama::http::{HeaderMap, StatusCode, body::util::BodyExt};
+use rama::telemetry::tracing;
+use serde_json::Value as JsonValue;
+use tokio_cron_scheduler::{Job, JobScheduler};
+use vein_adapter::{AssetKind, CacheBackend, CacheBackendTrait, Ecosystem, UpstreamYank};
+
+use crate::config::Config;
+use crate::upstream::simple_get;
+
+/// Outcome of a single synchronisation pass.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct YankSyncReport {
+    /// Cached packages compared against upstream.
+    pub packages_checked: usize,
+    /// Yanks that were not known before this pass.
+    pub newly_yanked: usize,
+    /// Packages whose upstream state could not be fetched.
+    pub failures: usize,
+}
+
+/// Upstream endpoints consulted during a pass.
+#[derive(Debug, Clone)]
+pub struct YankSources {
+    pub rubygems: Option<String>,
+    pub crates_index: String,
+    pub npm_registry: String,
+}
+
+impl YankSources {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            rubygems: config
+                .upstream
+                .as_ref()
+                .map(|upstream| upstream.url.to_string()),
+            crates_index: crate::crates::crates_index_base().into_owned(),
+            npm_registry: crate::npm::npm_registry_base().into_owned(),
+        }
+    }
+}
+
+type CachedVersions = BTreeMap<String, Vec<(String, Option<String>)>>;
+
+/// Runs one synchronisation pass across all ecosystems.
+pub async fn sync_once(config: &Config, index: &CacheBackend) -> Result<YankSyncReport> {
+    sync_once_from(config, index, &YankSources::from_config(config)).await
+}
+
+pub async fn sync_once_from(
+    config: &Config,
+    index: &CacheBackend,
+    sources: &YankSources,
+) -> Result<YankSyncReport> {
+    let mut report = YankSyncReport::default();
+    let detected_at = Utc::now();
+
+    if let Some(base) = sources.rubygems.as_deref() {
+        let cached = group_cached(index.cached_versions(AssetKind::Gem).await?);
+        report.packages_checked += cached.len();
+        match fetch_text(&format!("{}/versions", base.trim_end_matches('/'))).await {
+            Ok(Some(body)) => {
+                let yanks = rubygems_yanks(&cached, &body, detected_at);
+                report.newly_yanked += record_yanks(index, &yanks).await?;
+            }
+            Ok(None) => report.failures += 1,
+            Err(err) => {
+                tracing::warn!(error = %err, "failed to fetch rubygems versions file");
+                report.failures += 1;
+            }
+        }
+    }
+
+    let crates = group_cached(index.cached_versions(AssetKind::Crate).await?);
+    for (name, versions) in &crates {
+        report.packages_checked += 1;
+        let Some(path) = crate::crates::index_path(name) else {
+            continue;
+        };
+        let url = format!("{}/{}", sources.crates_index.trim_end_matches('/'), path);
+        match fetch_text(&url).await {
+            Ok(Some(body)) => {
+                let yanks = crate_yanks(name, versions, &body, detected_at);
+                report.newly_yanked += record_yanks(index, &yanks).await?;
+            }
+            Ok(None) => {}
+            Err(err) => {
+                tracing::warn!(error = %err, crate_name = %name, "failed to fetch crate index");
+                report.failures += 1;
+            }
+        }
+    }
+
+    let packages = group_cached(index.cached_versions(AssetKind::NpmPackage).await?);
+    for (name, versions) in &packages {
+        report.packages_checked += 1;
+        let url = format!(
+            "{}/{}",
+            sources.npm_registry.trim_end_matches('/'),
+            name.replace('/', "%2f")
+        );
+        let packument = match fetch_text(&url).await {
+            Ok(Some(body)) => match serde_json::from_str::<JsonValue>(&body) {
+                Ok(json) => Some(json),
+                Err(err) => {
+                    tracing::warn!(error = %err, package = %name, "invalid npm packument");
+                    report.failures += 1;
+                    continue;
+                }
+            },
+            // Packages the registry never heard of are private; leave them alone.
+            Ok(None) => None,
+            Err(err) => {
+                tracing::warn!(error = %err, package = %name, "failed to fetch npm packument");
+                report.failures += 1;
+                continue;
+            }
+        };
+        let yanks = npm_yanks(
+            name,
+            versions,
+            packument.as_ref(),
+            config.yank_sync.npm_deprecations,
+            detected_at,
+        );
+        report.newly_yanked += record_yanks(index, &yanks).await?;
+    }
+
+    Ok(report)
+}
+
+/// Spawns the scheduled yank synchronisation job.
+pub fn spawn_yank_sync_scheduler(config: Arc<Config>, index: Arc<CacheBackend>) {
+    if !config.yank_sync.enabled {
+        tracing::info!("Yank sync not enabled, skipping scheduler");
+        return;
+    }
+
+    let schedule = config.yank_sync.schedule.clone();
+
+    std::thread::spawn(move || {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("Failed to create yank sync runtime");
+
+        rt.block_on(async {
+            let sched = JobScheduler::new()
+                .await
+                .expect("Failed to create yank sync scheduler");
+
+            let job = Job::new_async(schedule.as_str(), move |_uuid, _l| {
+                let config = config.clone();
+                let index = index.clone();
+                Box::pin(async move {
+                    tracing::debug!("Running upstream yank sync");
+
+                    match sync_once(&config, &index).await {
+                        Ok(report) if report.newly_yanked > 0 => {
+                            tracing::warn!(
+                                newly_yanked = report.newly_yanked,
+                                checked = report.packages_checked,
+                                failures = report.failures,
+                                "Cached versions were yanked upstream"
+                            );
+                        }
+                        Ok(report) => {
+                            tracing::debug!(
+                                checked = report.packages_checked,
+                                failures = report.failures,
+                                "No new upstream yanks"
+                            );
+                        }
+                        Err(err) => {
+                            tracing::error!(error = %err, "Upstream yank sync failed");
+                        }
+                    }
+                })
+            })
+            .expect("Failed to create yank sync job");
+
+            sched
+                .add(job)
+                .await
+                .expect("Failed to add yank sync job to scheduler");
+
+            sched
+                .start()
+                .await
+                .expect("Failed to start yank sync scheduler");
+
+            tracing::info!(schedule = %schedule, "Upstream yank sync scheduler started");
+
+            // Keep the scheduler runtime alive forever
+            loop {
+                tokio::time::sleep(tokio::time::Duration::from_secs(3600)).await;
+            }
+        });
+    });
+}
+
+/// Records yanks and mirrors RubyGems ones into the quarantine table.
+async fn record_yanks(index: &CacheBackend, yanks: &[UpstreamYank]) -> Result<usize> {
+    let mut recorded = 0;
+    for yank in yanks {
+        if !index.record_upstream_yank(yank).await? {
+            continue;
+        }
+        recorded += 1;
+        tracing::warn!(
+            ecosystem = %yank.ecosystem,
+            package = %yank.name,
+            version = %yank.version,
+            reason = %yank.reason,
+            "Cached version no longer offered upstream"
+        );
+        if yank.ecosystem == Ecosystem::RubyGems {
+            index
+                .mark_yanked(&yank.name, &yank.version)
+                .await
+                .context("marking gem version yanked")?;
+        }
+    }
+    Ok(recorded)
+}
+
+/// Fetches a text body, returning `None` on 404/410.
+async fn fetch_text(url: &str) -> Result<Option<String>> {
+    let response = simple_get(url, &HeaderMap::new(), None).await?;
+    let status = response.status();
+    if status == StatusCode::NOT_FOUND || status == StatusCode::GONE {
+        return Ok(None);
+    }
+    if !status.is_success() {
+        bail!("{url} returned {status}");
+    }
+    let bytes = response
+        .into_body()
+        .collect()
+        .await
+        .with_context(|| format!("reading {url}"))?
+        .to_bytes();
+    Ok(Some(String::from_utf8_lossy(&bytes).into_owned()))
+}
+
+fn group_cached(rows: Vec<(String, String, Option<String>)>) -> CachedVersions {
+    let mut grouped = CachedVersions::new();
+    for (name, version, platform) in rows {
+        grouped
+            .entry(name)
+            .or_default()
+            .push((version, platform.filter(|p| p != "ruby")));
+    }
+    grouped
+}
+
+/// Replays the compact index `/versions` file into the live version set per gem.
+///
+/// The file is append-only: later lines add versions, `-version` entries
+/// remove them. Keys are `version` or `version-platform`.
+fn parse_versions_file(body: &str) -> HashMap<&str, HashSet<&str>> {
+    let mut live: HashMap<&str, HashSet<&str>> = HashMap::new();
+    let entries = body.split_once("\n---\n").map_or(body, |(_, rest)| rest);
+
+    for line in entries.lines() {
+        let mut parts = line.split_whitespace();
+        let (Some(name), Some(versions)) = (parts.next(), parts.next()) else {
+            continue;
+        };
+        let set = live.entry(name).or_default();
+        for version in versions.split(',') {
+            match version.strip_prefix('-') {
+                Some(yanked) => {
+                    set.remove(yanked);
+                }
+                None => {
+                    set.insert(version);
+                }
+            }
+        }
+    }
+
+    live
+}
+
+fn rubygems_yanks(
+    cached: &CachedVersions,
+    versions_file: &str,
+    detected_at: DateTime<Utc>,
+) -> Vec<UpstreamYank> {
+    let live = parse_versions_file(versions_file);
+    let mut yanks = Vec::new();
+
+    for (name, versions) in cached {
+        // Gems upstream never heard of are private; leave them alone.
+        let Some(upstream) = live.get(name.as_str()) else {
+            continue;
+        };
+        for (version, platform) in versions {
+            let key = match platform {
+                Some(platform) => format!("{version}-{platform}"),
+                None => version.clone(),
+            };
+            if !upstream.contains(key.as_str()) {
+                yanks.push(UpstreamYank {
+                    ecosystem: Ecosystem::RubyGems,
+                    name: name.clone(),
+                    version: version.clone(),
+                    platform: platform.clone(),
+                    reason: "yanked".to_string(),
+                    detected_at,
+                });
+            }
+        }
+    }
+
+    yanks
+}
+
+fn crate_yanks(
+    name: &str,
+    cached: &[(String, Option<String>)],
+    index_body: &str,
+    detected_at: DateTime<Utc>,
+) -> Vec<UpstreamYank> {
+    let mut yanked = HashSet::new();
+    for line in index_body.lines().filter(|line| !line.trim().is_empty()) {
+        let Ok(entry) = serde_json::from_str::<JsonValue>(line) else {
+            continue;
+        };
+        if entry["yanked"].as_bool() == Some(true)
+            && let Some(version) = entry["vers"].as_str()
+        {
+            yanked.insert(version.to_string());
+        }
+    }
+
+    cached
+        .iter()
+        .filter(|(version, _)| yanked.contains(version))
+        .map(|(version, _)| UpstreamYank {
+            ecosystem: Ecosystem::CratesIo,
+            name: name.to_string(),
+            version: version.clone(),
+            platform: None,
+            reason: "yanked".to_string(),
+            detected_at,
+        })
+        .collect()
+}
+
+fn npm_yanks(
+    name: &str,
+    cached: &[(String, Option<String>)],
+    packument: Option<&JsonValue>,
+    include_deprecations: bool,
+    detected_at: DateTime<Utc>,
+) -> Vec<UpstreamYank> {
+    let Some(packument) = packument else {
+        return Vec::new();
+    };
+    // Unpublished packages leave a tombstone without versions behind.
+    let tombstone = packument["time"]["unpublished"].is_object();
+    let versions = packument["versions"].as_object();
+    if versions.is_none() && !tombstone {
+        return Vec::new();
+    }
+
+    cached
+        .iter()
+        .filter_map(|(version, _)| {
+            let reason = match versions.and_then(|all| all.get(version)) {
+                None => "unpublished".to_string(),
+                Some(manifest) if include_deprecations => {
+                    let message = manifest["deprecated"].as_str()?;
+                    format!("deprecated: {message}")
+                }
+                Some(_) => return None,
+            };
+            Some(UpstreamYank {
+                ecosystem: Ecosystem::Npm,
+                name: name.to_string(),
+                version: version.clone(),
+                platform: None,
+                reason,
+                detected_at,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn cached(entries: &[(&str, &str, Option<&str>)]) -> CachedVersions {
+        group_cached(
+            entries
+                .iter()
+                .map(|(name, version, platform)| {
+                    (
+                        name.to_string(),
+                        version.to_string(),
+                        platform.map(str::to_string),
+                    )
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn versions_file_replay_drops_yanked_entries() {
+        let body = "created_at: 2025-01-01T00:00:00Z\n---\n\
+                    rack 3.0.0,3.0.1 abc\n\
+                    rack -3.0.1 def\n\
+                    nokogiri 1.16.0,1.16.0-x86_64-linux 123\n";
+        let live = parse_versions_file(body);
+
+        assert!(live["rack"].contains("3.0.0"));
+        assert!(!live["rack"].contains("3.0.1"));
+        assert!(live["nokogiri"].contains("1.16.0-x86_64-linux"));
+    }
+
+    #[test]
+    fn rubygems_yanks_respect_platform_and_skip_unknown_gems() {
+        let body = "---\n\
+                    rack 3.0.0,3.0.1 abc\n\
+                    rack -3.0.1 def\n\
+                    nokogiri 1.16.0,1.16.0-x86_64-linux 123\n\
+                    nokogiri -1.16.0-x86_64-linux 456\n";
+        let cached = cached(&[
+            ("rack", "3.0.0", None),
+            ("rack", "3.0.1", None),
+            ("nokogiri", "1.16.0", Some("ruby")),
+            ("nokogiri", "1.16.0", Some("x86_64-linux")),
+            ("internal-gem", "0.1.0", None),
+        ]);
+
+        let yanks = rubygems_yanks(&cached, body, Utc::now());
+        let keys: Vec<_> = yanks
+            .iter()
+            .map(|y| (y.name.as_str(), y.version.as_str(), y.platform.as_deref()))
+            .collect();
+
+        assert_eq!(
+            keys,
+            vec![
+                ("nokogiri", "1.16.0", Some("x86_64-linux")),
+                ("rack", "3.0.1", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn crate_yanks_follow_index_flags() {
+        let body = concat!(
+            "{\"name\":\"serde\",\"vers\":\"1.0.0\",\"yanked\":false}\n",
+            "{\"name\":\"serde\",\"vers\":\"1.0.1\",\"yanked\":true}\n",
+        );
+        let versions = vec![("1.0.0".to_string(), None), ("1.0.1".to_string(), None)];
+
+        let yanks = crate_yanks("serde", &versions, body, Utc::now());
+        assert_eq!(yanks.len(), 1);
+        assert_eq!(yanks[0].version, "1.0.1");
+        assert_eq!(yanks[0].ecosystem, Ecosystem::CratesIo);
+    }
+
+    #[test]
+    fn npm_yanks_cover_unpublish_and_optional_deprecation() {
+        let packument = json!({
+            "versions": {
+                "1.0.0": {},
+                "1.1.0": { "deprecated": "use 2.x" }
+            }
+        });
+        let versions = vec![
+            ("1.0.0".to_string(), None),
+            ("1.1.0".to_string(), None),
+            ("0.9.0".to_string(), None),
+        ];
+
+        let strict = npm_yanks("left-pad", &versions, Some(&packument), false, Utc::now());
+        assert_eq!(strict.len(), 1);
+        assert_eq!(strict[0].version, "0.9.0");
+        assert_eq!(strict[0].reason, "unpublished");
+
+        let with_deprecations =
+            npm_yanks("left-pad", &versions, Some(&packument), true, Utc::now());
+        assert_eq!(with_deprecations.len(), 2);
+        assert_eq!(with_deprecations[0].reason, "deprecated: use 2.x");
+
+        let tombstone = json!({
+            "name": "left-pad",
+            "time": { "unpublished": { "time": "2016-03-22T00:00:00Z" } }
+        });
+        let gone = npm_yanks("left-pad", &versions, Some(&tombstone), false, Utc::now());
+        assert_eq!(
+            gone.len(),
+            3,
+            "an unpublished package unpublishes everything"
+        );
+    }
+
+    #[test]
+    fn npm_yanks_skip_packages_unknown_upstream() {
+        let versions = vec![("1.0.0".to_string(), None)];
+
+        let private = npm_yanks("@acme/internal", &versions, None, true, Utc::now());
+        assert!(private.is_empty(), "a 404 leaves private packages alone");
+
+        let odd = npm_yanks(
+            "@acme/internal",
+            &versions,
+            Some(&json!({})),
+            true,
+            Utc::now(),
+        );
+        assert!(odd.is_empty());
+    }
+}